@@ -0,0 +1,15 @@
+// Runs the fixed determinism probe and prints the state hash every few
+// hundred ticks, ending with the final one. The wasm build prints the same
+// lines from `tests/determinism_probe.js`, and any line that differs
+// between the two is where the targets stopped stepping the world alike.
+//
+//   cargo run --example determinism_probe
+
+use sdl2_1::world::{determinism_probe, PROBE_SEED, PROBE_TICKS};
+
+fn main() {
+	println!("seed {:x}, {} ticks", PROBE_SEED, PROBE_TICKS);
+	for (tick, hash) in determinism_probe() {
+		println!("tick {}: {:016x}", tick, hash);
+	}
+}
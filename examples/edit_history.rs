@@ -0,0 +1,81 @@
+// Paints over the generated cube as the editor would, in strokes and
+// rectangles, then goes right back through the edit history to before the
+// first and forward again to the last, checking the world's tiles are just
+// as they were at either end. Then goes partway back and edits again, which
+// has to drop the edits that had been undone. Exits with an error if
+// anything differs.
+//
+//   cargo run --example edit_history
+
+use sdl2_1::world::{EditHistory, Rng, Tile, World, FRAME_WIDTH};
+
+const EDITS: usize = 200;
+// Tiles painted by each stroke, at most.
+const STROKE_LENGTH: usize = 12;
+
+fn fail(message: String) -> ! {
+	eprintln!("{}", message);
+	std::process::exit(1);
+}
+
+fn point(rng: &mut Rng) -> (isize, isize) {
+	let w = FRAME_WIDTH as isize;
+	(rng.range_i(0, w), rng.range_i(0, w))
+}
+
+fn edit(world: &mut World, history: &mut EditHistory, rng: &mut Rng) {
+	let frame_ids = world.frame_ids();
+	let frame_id = *rng.pick(&frame_ids).unwrap();
+	let tiles = [Tile::Empty, Tile::Solid, Tile::Sand, Tile::Sign];
+	let tile = *rng.pick(&tiles).unwrap();
+	if rng.range_i(0, 4) == 0 {
+		let (from, to) = (point(rng), point(rng));
+		history.fill_rect(world, frame_id, from, to, tile);
+	} else {
+		for _ in 0..rng.range_i(1, STROKE_LENGTH as isize) {
+			let (x, y) = point(rng);
+			// Tiles an entity is in can't be filled, which is fine to leave.
+			history.set_tile(world, frame_id, x, y, tile).ok();
+		}
+	}
+	history.finish();
+}
+
+fn main() {
+	let mut world = World::new();
+	let mut history = EditHistory::new();
+	let mut rng = Rng::new(1);
+	let start = world.state_hash();
+	for _ in 0..EDITS {
+		edit(&mut world, &mut history, &mut rng);
+	}
+	let end = world.state_hash();
+	let len = history.len();
+
+	history.seek(&mut world, 0);
+	if world.state_hash() != start {
+		fail(format!("back at the start, {} edits undone differ", len));
+	}
+	history.seek(&mut world, len);
+	if world.state_hash() != end {
+		fail(format!("back at the end, {} edits redone differ", len));
+	}
+
+	let back = len / 2;
+	history.seek(&mut world, back);
+	while history.len() == len {
+		edit(&mut world, &mut history, &mut rng);
+	}
+	let dropped = len - back;
+	if history.len() != back + 1 || history.cursor() != back + 1 {
+		fail(format!(
+			"editing from {} of {} left {} of {}, not all {} undone dropped",
+			back,
+			len,
+			history.cursor(),
+			history.len(),
+			dropped
+		));
+	}
+	println!("{} edits undone and redone, then {} dropped", len, dropped);
+}
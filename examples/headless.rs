@@ -0,0 +1,36 @@
+// Runs the simulation with no window: builds the generated cube, ticks it
+// for a while with no input and prints where the player ended up.
+//
+//   cargo run --example headless [--dump-state]
+//
+// With `--dump-state` the whole world is printed instead, as
+// `World::dump_state` writes it, for diffing one run against another.
+
+use sdl2_1::world::{Actions, World};
+
+const TICKS: usize = 100;
+
+fn main() {
+	let dump_state = std::env::args().any(|arg| arg == "--dump-state");
+
+	let mut world = World::new();
+	for _ in 0..TICKS {
+		world.tick(&Actions::none());
+	}
+
+	if dump_state {
+		print!("{}", world.dump_state());
+		return;
+	}
+	let player = world.focus_entity.and_then(|id| world.get_entity(id));
+	match player {
+		Some(player) => {
+			let position = player.position;
+			println!(
+				"After {} ticks the player is on frame {} at ({:.3}, {:.3})",
+				TICKS, position.frame_id, position.x, position.y
+			);
+		}
+		None => println!("The world has no player"),
+	}
+}
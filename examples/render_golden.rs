@@ -0,0 +1,32 @@
+// Draws each of the golden scenes without a window and compares a hash of
+// the lines each drew with the one checked in for it, to catch changes to
+// what the renderer draws that weren't meant, as the tests do when built
+// headless. Exits with an error if any differ, leaving the lines checked
+// in and those drawn side by side under `target/render_golden`, and
+// saying where they first differ:
+//
+//   cargo run --example render_golden --no-default-features --features headless
+//
+// When a change to what is drawn is meant, check the scenes in again with
+//
+//   cargo run --example render_golden --no-default-features --features headless -- --update
+
+use sdl2_1::window::golden::{check_golden_scenes, update_golden_scenes};
+
+fn main() {
+	let update = std::env::args().any(|arg| arg == "--update");
+	let result = if update {
+		update_golden_scenes().map(|count| {
+			format!("{} scenes checked in to tests/render_golden.txt", count)
+		})
+	} else {
+		check_golden_scenes().map(|drawn| drawn.trim_end().to_string())
+	};
+	match result {
+		Ok(report) => println!("{}", report),
+		Err(report) => {
+			eprintln!("{}", report);
+			std::process::exit(1);
+		}
+	}
+}
@@ -0,0 +1,52 @@
+// Times world ticks with a crowd of walkers and no window, the way the
+// `--stress` option does in the game, and prints the percentiles:
+//
+//   cargo run --release --example stress [walkers]
+//
+// Walkers default to a thousand, spread over every frame of the generated
+// cube.
+
+use std::time::Instant;
+
+use sdl2_1::world::{Actions, EdgeBehavior, World};
+
+const WALKERS: usize = 1000;
+// Left out of the timings, for the walkers to get moving.
+const WARM_UP_TICKS: usize = 100;
+const TICKS: usize = 2000;
+
+fn main() {
+	let count = std::env::args()
+		.nth(1)
+		.map(|count| count.parse().expect("walkers should be a number"))
+		.unwrap_or(WALKERS);
+
+	let mut world = World::new();
+	let spawned = world.spawn_walkers(count, EdgeBehavior::default());
+	for _ in 0..WARM_UP_TICKS {
+		world.tick(&Actions::none());
+	}
+
+	let mut samples: Vec<f64> = (0..TICKS)
+		.map(|_| {
+			let start = Instant::now();
+			world.tick(&Actions::none());
+			start.elapsed().as_secs_f64()
+		})
+		.collect();
+	samples.sort_by(|a, b| a.partial_cmp(b).unwrap());
+	let percentile = |p: f64| {
+		let index = ((samples.len() - 1) as f64 * p).round();
+		samples[index as usize] * 1000.0
+	};
+	println!(
+		"{} walkers, {} ticks: p50 {:.3} ms, p90 {:.3} ms, p99 {:.3} ms, \
+		max {:.3} ms",
+		spawned,
+		samples.len(),
+		percentile(0.5),
+		percentile(0.9),
+		percentile(0.99),
+		percentile(1.0)
+	);
+}
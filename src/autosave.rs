@@ -0,0 +1,163 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::backend::{storage, time};
+use crate::prelude::*;
+use crate::world::{from_hex, to_hex, SaveError, SaveHeader, World};
+
+// Where every save is kept, and where the save browser looks for them.
+const SAVE_DIRECTORY: &str = "saves";
+// Written to in turn, so that a crash partway through writing one always
+// leaves the other intact.
+const AUTOSAVE_SLOTS: [&str; 2] =
+	["saves/autosave_a.sav", "saves/autosave_b.sav"];
+// Where the `save` console command writes to.
+pub const MANUAL_SAVE: &str = "saves/save.sav";
+
+// Counts up with every autosave; the slot written is this modulo two.
+static NEXT_SLOT: AtomicUsize = AtomicUsize::new(0);
+
+// Saves the world every so often, but only if it has changed since.
+pub struct Autosave {
+	// Seconds between autosaves, or zero for none.
+	interval: u32,
+	// From `backend::time::now`.
+	last_check_time: f64,
+	// The world's change count as of the last save. Taken from the first
+	// world seen rather than saving it, so that starting up can never
+	// overwrite an autosave still waiting to be restored.
+	saved_changes: Option<u64>,
+}
+
+impl Autosave {
+	pub fn new(interval: u32) -> Self {
+		// Overwrite the older slot first.
+		let newest = newest_slot().unwrap_or(1);
+		NEXT_SLOT.store(newest + 1, Ordering::SeqCst);
+
+		Self {
+			interval,
+			last_check_time: time::now(),
+			saved_changes: None,
+		}
+	}
+
+	pub fn set_interval(&mut self, interval: u32) {
+		self.interval = interval;
+	}
+
+	// Called once a frame. Whether an autosave was started.
+	pub fn update(&mut self, world: &World) -> bool {
+		let changes = world.change_count();
+		let saved_changes = *self.saved_changes.get_or_insert(changes);
+
+		let now = time::now();
+		if self.interval == 0
+			|| now - self.last_check_time < self.interval as f64
+		{
+			return false;
+		}
+		self.last_check_time = now;
+		if changes == saved_changes {
+			return false;
+		}
+
+		self.saved_changes = Some(changes);
+		let slot = NEXT_SLOT.fetch_add(1, Ordering::SeqCst) % 2;
+		storage::write_in_background(AUTOSAVE_SLOTS[slot], save_text(world));
+		true
+	}
+
+	// Treats `world` as saved, after it was saved by hand or restored.
+	pub fn mark_saved(&mut self, world: &World) {
+		self.saved_changes = Some(world.change_count());
+	}
+}
+
+// Saves are in the world's binary save format, stored as hex since storage
+// only holds text. A save cut short fails to read its header.
+fn save_text(world: &World) -> String {
+	to_hex(&world.to_save_bytes(time::unix_time()))
+}
+
+pub fn save(world: &World, name: &str) -> Result<(), String> {
+	storage::write(name, &save_text(world))
+}
+
+// A complete save's bytes and the Unix time it was made.
+pub fn read_save(name: &str) -> Option<(Vec<u8>, f64)> {
+	let bytes = from_hex(&storage::read(name)?)?;
+	let header = SaveHeader::read(&bytes).ok()?;
+	Some((bytes, header.saved_at))
+}
+
+pub fn load(bytes: &[u8]) -> Result<World, SaveError> {
+	World::from_save_bytes(bytes)
+}
+
+// A save found in storage, with its header or why it couldn't be read.
+#[derive(Clone, Debug)]
+pub struct SaveEntry {
+	pub name: String,
+	pub header: Result<SaveHeader, String>,
+}
+
+// Everything in the save directory, newest first, followed by anything there
+// that couldn't be read. Only headers are read, not whole worlds.
+pub fn list_saves() -> Vec<SaveEntry> {
+	let mut saves: Vec<SaveEntry> = storage::list(SAVE_DIRECTORY)
+		.into_iter()
+		.map(|name| {
+			let header = storage::read(&name)
+				.and_then(|text| from_hex(&text))
+				.ok_or(SaveError::NotASave)
+				.and_then(|bytes| SaveHeader::read(&bytes))
+				.map_err(|error| error.to_string());
+			SaveEntry { name, header }
+		})
+		.collect();
+	saves.sort_by(|a, b| match (&a.header, &b.header) {
+		(Ok(a), Ok(b)) => b.saved_at.total_cmp(&a.saved_at),
+		(a, b) => a.is_err().cmp(&b.is_err()),
+	});
+	saves
+}
+
+pub fn delete_save(name: &str) -> Result<(), String> {
+	storage::remove(name)
+}
+
+// The slot holding the newest complete autosave.
+fn newest_slot() -> Option<usize> {
+	(0..AUTOSAVE_SLOTS.len())
+		.filter_map(|slot| {
+			read_save(AUTOSAVE_SLOTS[slot])
+				.map(|(_, saved_at)| (slot, saved_at))
+		})
+		.max_by(|(_, a), (_, b)| a.total_cmp(b))
+		.map(|(slot, _)| slot)
+}
+
+pub fn newest_autosave() -> Option<(Vec<u8>, f64)> {
+	newest_slot().and_then(|slot| read_save(AUTOSAVE_SLOTS[slot]))
+}
+
+// The newest autosave, if it is newer than the manual save and so holds
+// work that would otherwise be lost.
+pub fn recoverable_autosave() -> Option<(Vec<u8>, f64)> {
+	let manual_time = read_save(MANUAL_SAVE).map(|(_, saved_at)| saved_at);
+	newest_autosave()
+		.filter(|&(_, saved_at)| manual_time.is_none_or(|t| saved_at > t))
+}
+
+// Saves the world to the next autosave slot after a panic, once whatever
+// panicked has let go of it. It may have been left halfway through a tick,
+// which is still worth saving over losing everything since the last
+// autosave.
+pub fn emergency_save(world: &World) {
+	let slot = NEXT_SLOT.fetch_add(1, Ordering::SeqCst) % 2;
+	let name = AUTOSAVE_SLOTS[slot];
+	match storage::write(name, &save_text(world)) {
+		Ok(()) => elog(format!("Emergency save written to {}", name)),
+		Err(error) => elog(format!("Emergency save failed: {}", error)),
+	}
+}
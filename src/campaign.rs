@@ -0,0 +1,346 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::autosave::{self, SaveEntry};
+use crate::window::MenuWidget;
+use crate::world::{Actions, LevelInfo, Replay, World, WorldStats};
+use crate::world::{TICK_RATE, TILE_SIZE};
+
+// Levels built into the game, by their path in the repository. A path
+// naming one of these loads the built-in copy, so the campaign works
+// wherever the game is run from.
+const LEVELS: &[(&str, &str)] = &[
+	(
+		"levels/campaign/1_first_steps.lvl",
+		include_str!("../levels/campaign/1_first_steps.lvl"),
+	),
+	(
+		"levels/campaign/2_walls.lvl",
+		include_str!("../levels/campaign/2_walls.lvl"),
+	),
+	(
+		"levels/campaign/3_upside_down.lvl",
+		include_str!("../levels/campaign/3_upside_down.lvl"),
+	),
+];
+
+// The state hash the demo ends on in each precision, which tests/demo.rs
+// prints when it doesn't.
+#[cfg(not(feature = "f64"))]
+const DEMO_HASH: u64 = 0xd5ad_f238_03c6_2ac1;
+#[cfg(feature = "f64")]
+const DEMO_HASH: u64 = 0x805c_d7ae_5eab_67d4;
+
+// Played by the attract mode on the menu when the player has left it alone
+// for a while: once around the first level, jumping now and then, picking
+// up each coin.
+pub fn demo_replay() -> Replay {
+	let walk = Actions {
+		move_x: 1.0,
+		..Actions::none()
+	};
+	let sprint = Actions {
+		sprint: true,
+		..walk
+	};
+	// A jump, then holding it while it carries on.
+	let jump = |actions: Actions| {
+		let held = Actions {
+			jump_held: true,
+			..actions
+		};
+		[(1, Actions { jump: true, ..held }), (24, held)]
+	};
+	let mut runs = vec![(60, Actions::none()), (100, walk)];
+	runs.extend_from_slice(&jump(walk));
+	runs.push((160, walk));
+	runs.extend_from_slice(&jump(walk));
+	runs.push((150, sprint));
+	runs.extend_from_slice(&jump(sprint));
+	runs.extend_from_slice(&[(60, walk), (120, Actions::none())]);
+	Replay {
+		level: "levels/campaign/1_first_steps.lvl".to_string(),
+		hash: Some(DEMO_HASH),
+		runs,
+	}
+}
+
+// The source of a built-in level, or else of the level file at `path`.
+pub fn read_level(path: &str) -> Result<String, String> {
+	match LEVELS.iter().find(|&&(level_path, _)| level_path == path) {
+		Some(&(_, source)) => Ok(source.to_string()),
+		None => {
+			std::fs::read_to_string(path).map_err(|error| error.to_string())
+		}
+	}
+}
+
+pub fn format_ticks(ticks: u64) -> String {
+	format!("{:.2}s", ticks as f64 / TICK_RATE)
+}
+
+// A Unix time as a UTC date and time, to the minute.
+fn format_unix_time(time: f64) -> String {
+	let minutes = (time / 60.0).floor() as i64;
+	let days = minutes.div_euclid(24 * 60);
+	let minute_of_day = minutes.rem_euclid(24 * 60);
+
+	// Howard Hinnant's `civil_from_days`, counting in 400 year eras from
+	// 0000-03-01 so that leap days fall at the end of each year.
+	let days = days + 719_468;
+	let era = days.div_euclid(146_097);
+	let day_of_era = days.rem_euclid(146_097);
+	let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524
+		- day_of_era / 146_096)
+		/ 365;
+	let day_of_year =
+		day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+	let month_index = (5 * day_of_year + 2) / 153;
+	let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+	let month = if month_index < 10 {
+		month_index + 3
+	} else {
+		month_index - 9
+	};
+	let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+	format!(
+		"{:04}-{:02}-{:02} {:02}:{:02}",
+		year,
+		month,
+		day,
+		minute_of_day / 60,
+		minute_of_day % 60
+	)
+}
+
+// What each item in the level menu leads to.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MenuChoice {
+	// Loaded from this path.
+	Level(&'static str),
+	RandomCube,
+	LoadGame,
+}
+
+// Offered at startup when no level is given: each built-in level in order,
+// then a randomly generated cube, then the saves to carry on from.
+pub struct Menu {
+	// Names, and what choosing each does.
+	entries: Vec<(String, MenuChoice)>,
+	pub widget: MenuWidget,
+	// Shown in place of the levels once Load game is chosen.
+	pub saves: Option<SaveBrowser>,
+}
+
+impl Menu {
+	pub fn new() -> Self {
+		let mut entries: Vec<_> = LEVELS
+			.iter()
+			.map(|&(path, source)| {
+				let name = World::from_level_str(source)
+					.ok()
+					.and_then(|world| world.level.name);
+				let name = name.unwrap_or_else(|| path.to_string());
+				(name, MenuChoice::Level(path))
+			})
+			.collect();
+		entries.push(("Random cube".to_string(), MenuChoice::RandomCube));
+		entries.push(("Load game".to_string(), MenuChoice::LoadGame));
+		let items = entries.iter().map(|(name, _)| name.clone()).collect();
+
+		Self {
+			entries,
+			widget: MenuWidget::new(items),
+			saves: None,
+		}
+	}
+
+	pub fn choice(&self, index: usize) -> MenuChoice {
+		self.entries[index].1
+	}
+
+	pub fn header(&self) -> &'static str {
+		"Choose a level with W and S, the mouse or a gamepad, then press \
+		Return to play."
+	}
+
+	// Labels each level with its best time, which can change while the menu
+	// is open.
+	pub fn update_items(&mut self, best_times: &BTreeMap<String, u64>) {
+		for (item, (name, choice)) in
+			self.widget.items.iter_mut().zip(self.entries.iter())
+		{
+			item.clear();
+			item.push_str(name);
+			let path = match choice {
+				MenuChoice::Level(path) => path,
+				_ => continue,
+			};
+			if let Some(&best) = best_times.get(*path) {
+				write!(item, ", best {}", format_ticks(best)).unwrap();
+			}
+		}
+	}
+}
+
+// The saves in storage, each labeled from its header alone. Ones that can't
+// be read are still listed, grayed out with the reason, so they can be
+// deleted.
+pub struct SaveBrowser {
+	saves: Vec<SaveEntry>,
+	pub widget: MenuWidget,
+}
+
+impl SaveBrowser {
+	pub fn new() -> Self {
+		let mut browser = Self {
+			saves: Vec::new(),
+			widget: MenuWidget::new(Vec::new()),
+		};
+		browser.refresh();
+		browser
+	}
+
+	// Lists the saves again, keeping the selection where it was as far as
+	// the list allows.
+	pub fn refresh(&mut self) {
+		self.saves = autosave::list_saves();
+		self.widget.items = self.saves.iter().map(save_label).collect();
+		self.widget.disabled =
+			self.saves.iter().map(|save| save.header.is_err()).collect();
+		let last = self.saves.len().saturating_sub(1);
+		self.widget.selected = self.widget.selected.min(last);
+	}
+
+	// The save at `index`, if it can be loaded.
+	pub fn save(&self, index: usize) -> Option<&str> {
+		self.saves
+			.get(index)
+			.filter(|save| save.header.is_ok())
+			.map(|save| save.name.as_str())
+	}
+
+	pub fn delete(&mut self, index: usize) -> Result<(), String> {
+		if let Some(save) = self.saves.get(index) {
+			autosave::delete_save(&save.name)?;
+		}
+		self.refresh();
+		Ok(())
+	}
+
+	pub fn header(&self) -> &'static str {
+		if self.saves.is_empty() {
+			return "There are no saves yet. Press Escape to go back.";
+		}
+		"Choose a save to carry on from with W and S, the mouse or a \
+		gamepad, then press Return. Delete removes the selected save, and \
+		Escape goes back."
+	}
+}
+
+fn save_label(save: &SaveEntry) -> String {
+	let file = save.name.rsplit('/').next().unwrap_or(&save.name);
+	match &save.header {
+		Ok(header) => format!(
+			"{}, {} played, saved {} ({})",
+			header.level_name.as_deref().unwrap_or("Random cube"),
+			format_ticks(header.play_ticks),
+			format_unix_time(header.saved_at),
+			file
+		),
+		Err(error) => format!("{}: {}", file, error),
+	}
+}
+
+// What can be done once a level is complete.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CompletionChoice {
+	NextLevel,
+	Replay,
+	Menu,
+}
+
+impl CompletionChoice {
+	fn label(&self) -> &'static str {
+		match self {
+			CompletionChoice::NextLevel => "Next level",
+			CompletionChoice::Replay => "Play again",
+			CompletionChoice::Menu => "Back to the menu",
+		}
+	}
+}
+
+// How a level went, shown once its last coin is collected.
+#[derive(Clone, Debug)]
+pub struct Completion {
+	pub ticks: u64,
+	pub stats: WorldStats,
+	// Whether `ticks` is the fewest the level has been finished in.
+	pub new_best: bool,
+	// What each of the widget's items does.
+	choices: Vec<CompletionChoice>,
+	pub widget: MenuWidget,
+}
+
+impl Completion {
+	pub fn new(ticks: u64, stats: WorldStats, level: &LevelInfo) -> Self {
+		let mut choices = Vec::new();
+		if level.next_level.is_some() {
+			choices.push(CompletionChoice::NextLevel);
+		}
+		choices.push(CompletionChoice::Replay);
+		choices.push(CompletionChoice::Menu);
+		let items = choices
+			.iter()
+			.map(|choice| choice.label().to_string())
+			.collect();
+
+		Self {
+			ticks,
+			stats,
+			new_best: false,
+			choices,
+			widget: MenuWidget::new(items),
+		}
+	}
+
+	pub fn choice(&self, index: usize) -> CompletionChoice {
+		self.choices[index]
+	}
+
+	pub fn text(&self, level: &LevelInfo) -> String {
+		let mut out = match &level.name {
+			Some(name) => format!("{} complete!", name),
+			None => String::from("Level complete!"),
+		};
+		if let Some(author) = &level.author {
+			write!(out, "\nBy {}", author).unwrap();
+		}
+
+		write!(out, "\n\nTime {}", format_ticks(self.ticks)).unwrap();
+		if let Some(par_ticks) = level.par_ticks {
+			write!(out, ", par {}", format_ticks(par_ticks)).unwrap();
+		}
+		if self.new_best {
+			write!(out, ", a new best").unwrap();
+		}
+		let stats = &self.stats;
+		write!(out, "\nCoins {}", stats.coins_collected).unwrap();
+		write!(
+			out,
+			"\n\n{:.1} tiles traveled, {} frame crossings, {} jumps",
+			stats.distance / TILE_SIZE,
+			stats.frame_crossings,
+			stats.jumps
+		)
+		.unwrap();
+		write!(
+			out,
+			"\n{} tiles placed, {} removed, {:.1} damage taken",
+			stats.tiles_placed, stats.tiles_removed, stats.damage_taken
+		)
+		.unwrap();
+		out
+	}
+}
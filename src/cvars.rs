@@ -0,0 +1,339 @@
+use crate::geometry::Scalar;
+use crate::settings::{Settings, MAX_RENDER_SCALE};
+use crate::world::PhysicsConfig;
+
+// Console variables: the tunables of every system under one naming scheme,
+// for the console to read and change and the settings file to keep. Each
+// value still lives in its own system's struct, which reads it as a plain
+// field, so nothing is looked up by name outside the console and settings.
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CvarValue {
+	Bool(bool),
+	Int(i64),
+	Scalar(Scalar),
+}
+
+impl CvarValue {
+	pub fn as_bool(self) -> bool {
+		match self {
+			CvarValue::Bool(value) => value,
+			CvarValue::Int(value) => value != 0,
+			CvarValue::Scalar(value) => value != 0.0,
+		}
+	}
+
+	pub fn as_int(self) -> i64 {
+		match self {
+			CvarValue::Bool(value) => value as i64,
+			CvarValue::Int(value) => value,
+			CvarValue::Scalar(value) => value as i64,
+		}
+	}
+
+	pub fn as_scalar(self) -> Scalar {
+		match self {
+			CvarValue::Bool(value) => value as i64 as Scalar,
+			CvarValue::Int(value) => value as Scalar,
+			CvarValue::Scalar(value) => value,
+		}
+	}
+}
+
+impl std::fmt::Display for CvarValue {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			CvarValue::Bool(value) => {
+				write!(f, "{}", if *value { "on" } else { "off" })
+			}
+			CvarValue::Int(value) => write!(f, "{}", value),
+			CvarValue::Scalar(value) => write!(f, "{}", value),
+		}
+	}
+}
+
+// The values a cvar takes, with numbers kept within the bounds given, both
+// included.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CvarRange {
+	Bool,
+	Int(i64, i64),
+	Scalar(Scalar, Scalar),
+}
+
+// Reads and writes the field a cvar stands for, in the struct of the
+// system it tunes.
+#[derive(Copy, Clone)]
+pub enum CvarField {
+	// Of the current world, so lasting until another is loaded.
+	Physics(
+		fn(&PhysicsConfig) -> CvarValue,
+		fn(&mut PhysicsConfig, CvarValue),
+	),
+	Settings(fn(&Settings) -> CvarValue, fn(&mut Settings, CvarValue)),
+}
+
+pub struct Cvar {
+	// A group and a name within it, joined by a dot.
+	pub name: &'static str,
+	// What the settings file called it before there were cvars, still read
+	// so that older files keep their values.
+	pub old_name: Option<&'static str>,
+	pub range: CvarRange,
+	// Written to the settings file, and read back from it at startup.
+	// Always a settings field.
+	pub persistent: bool,
+	pub field: CvarField,
+}
+
+impl Cvar {
+	// `on`, `off`, `true` or `false` for a switch, otherwise a number in
+	// range.
+	pub fn parse(&self, text: &str) -> Result<CvarValue, String> {
+		let bad = || format!("Bad value {:?} for {}", text, self.name);
+		match self.range {
+			CvarRange::Bool => match text {
+				"on" | "true" => Ok(CvarValue::Bool(true)),
+				"off" | "false" => Ok(CvarValue::Bool(false)),
+				_ => Err(bad()),
+			},
+			CvarRange::Int(min, max) => match text.parse() {
+				Ok(value) if (min..=max).contains(&value) => {
+					Ok(CvarValue::Int(value))
+				}
+				_ => Err(bad()),
+			},
+			CvarRange::Scalar(min, max) => match text.parse() {
+				Ok(value) if (min..=max).contains(&value) => {
+					Ok(CvarValue::Scalar(value))
+				}
+				_ => Err(bad()),
+			},
+		}
+	}
+
+	// What the cvar takes, for the console's usage lines.
+	pub fn range_name(&self) -> String {
+		match self.range {
+			CvarRange::Bool => "on|off".to_string(),
+			CvarRange::Int(min, max) => format!("{}-{}", min, max),
+			CvarRange::Scalar(min, max) => format!("{}-{}", min, max),
+		}
+	}
+
+	// As in a fresh world, or before the settings file is read.
+	pub fn default_value(&self) -> CvarValue {
+		match self.field {
+			CvarField::Physics(get, _) => get(&PhysicsConfig::default()),
+			CvarField::Settings(get, _) => get(&Settings::default()),
+		}
+	}
+}
+
+pub fn cvars() -> &'static [Cvar] {
+	CVARS
+}
+
+pub fn find_cvar(name: &str) -> Option<&'static Cvar> {
+	CVARS.iter().find(|cvar| cvar.name == name)
+}
+
+// Names of the cvars starting with `prefix`, in the order they are listed.
+pub fn complete_cvar(prefix: &str) -> Vec<&'static str> {
+	CVARS
+		.iter()
+		.map(|cvar| cvar.name)
+		.filter(|name| name.starts_with(prefix))
+		.collect()
+}
+
+// A physics field as a cvar named after it, either a switch or a number
+// within bounds.
+macro_rules! physics_cvar {
+	($field:ident) => {
+		Cvar {
+			name: concat!("phys.", stringify!($field)),
+			old_name: None,
+			range: CvarRange::Bool,
+			persistent: false,
+			field: CvarField::Physics(
+				|p| CvarValue::Bool(p.$field),
+				|p, value| p.$field = value.as_bool(),
+			),
+		}
+	};
+	($field:ident, $min:expr, $max:expr) => {
+		Cvar {
+			name: concat!("phys.", stringify!($field)),
+			old_name: None,
+			range: CvarRange::Scalar($min, $max),
+			persistent: false,
+			field: CvarField::Physics(
+				|p| CvarValue::Scalar(p.$field),
+				|p, value| p.$field = value.as_scalar(),
+			),
+		}
+	};
+}
+
+const CVARS: &[Cvar] = &[
+	Cvar {
+		name: "render.three_d_tiles",
+		old_name: Some("three_d_tiles"),
+		range: CvarRange::Bool,
+		persistent: true,
+		field: CvarField::Settings(
+			|s| CvarValue::Bool(s.three_d_tiles),
+			|s, value| s.three_d_tiles = value.as_bool(),
+		),
+	},
+	Cvar {
+		name: "render.globe",
+		old_name: None,
+		range: CvarRange::Scalar(0.0, 1.0),
+		persistent: true,
+		field: CvarField::Settings(
+			|s| CvarValue::Scalar(s.globe),
+			|s, value| s.globe = value.as_scalar(),
+		),
+	},
+	Cvar {
+		name: "render.vsync",
+		old_name: Some("vsync"),
+		range: CvarRange::Bool,
+		persistent: true,
+		field: CvarField::Settings(
+			|s| CvarValue::Bool(s.vsync),
+			|s, value| s.vsync = value.as_bool(),
+		),
+	},
+	Cvar {
+		name: "render.scale",
+		old_name: Some("render_scale"),
+		range: CvarRange::Int(1, MAX_RENDER_SCALE as i64),
+		persistent: true,
+		field: CvarField::Settings(
+			|s| CvarValue::Int(s.render_scale as i64),
+			|s, value| s.render_scale = value.as_int() as u32,
+		),
+	},
+	physics_cvar!(walk_acceleration, 0.0, 0.1),
+	physics_cvar!(jump_speed, 0.0, 0.1),
+	// Short of one, where nothing would ever slow down.
+	physics_cvar!(velocity_retention, 0.0, 0.99),
+	physics_cvar!(conveyor_acceleration, 0.0, 0.1),
+	physics_cvar!(push_out_of_placed_tiles),
+	physics_cvar!(fall_damage),
+	physics_cvar!(fall_damage_speed, 0.0, 1.0),
+	physics_cvar!(fall_damage_per_speed, 0.0, 10000.0),
+	physics_cvar!(spring_speed, 0.0, 0.2),
+	physics_cvar!(spring_jump_bonus, 0.0, 0.1),
+	physics_cvar!(max_stamina, 0.0, 10.0),
+	physics_cvar!(stamina_regen, 0.0, 1.0),
+	physics_cvar!(sprint_multiplier, 0.0, 10.0),
+	physics_cvar!(sprint_stamina_cost, 0.0, 1.0),
+	physics_cvar!(dash_speed, 0.0, 0.2),
+	physics_cvar!(dash_stamina_cost, 0.0, 10.0),
+];
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn cvar(name: &str) -> &'static Cvar {
+		find_cvar(name).unwrap()
+	}
+
+	#[test]
+	fn values_are_read_as_the_cvar_takes_them() {
+		let vsync = cvar("render.vsync");
+		assert_eq!(vsync.parse("on"), Ok(CvarValue::Bool(true)));
+		assert_eq!(vsync.parse("false"), Ok(CvarValue::Bool(false)));
+		assert!(vsync.parse("1").is_err());
+		let scale = cvar("render.scale");
+		assert_eq!(scale.parse("2"), Ok(CvarValue::Int(2)));
+		assert!(scale.parse("1.5").is_err());
+		let jump = cvar("phys.jump_speed");
+		assert_eq!(jump.parse("0.05"), Ok(CvarValue::Scalar(0.05)));
+		assert!(jump.parse("fast").is_err());
+	}
+
+	// Both bounds are in range and nothing past them is.
+	#[test]
+	fn values_out_of_range_are_refused() {
+		let scale = cvar("render.scale");
+		let max = MAX_RENDER_SCALE as i64;
+		assert_eq!(scale.parse("1"), Ok(CvarValue::Int(1)));
+		assert_eq!(scale.parse(&max.to_string()), Ok(CvarValue::Int(max)));
+		assert!(scale.parse("0").is_err());
+		assert!(scale.parse(&(max + 1).to_string()).is_err());
+		let retention = cvar("phys.velocity_retention");
+		assert_eq!(retention.parse("0"), Ok(CvarValue::Scalar(0.0)));
+		assert_eq!(retention.parse("0.99"), Ok(CvarValue::Scalar(0.99)));
+		assert!(retention.parse("1").is_err());
+		assert!(retention.parse("-0.1").is_err());
+		assert_eq!(
+			retention.parse("1").unwrap_err(),
+			"Bad value \"1\" for phys.velocity_retention"
+		);
+	}
+
+	#[test]
+	fn setting_a_cvar_changes_its_field() {
+		let mut physics = PhysicsConfig::default();
+		match cvar("phys.jump_speed").field {
+			CvarField::Physics(get, set) => {
+				set(&mut physics, CvarValue::Scalar(0.07));
+				assert_eq!(physics.jump_speed, 0.07);
+				assert_eq!(get(&physics), CvarValue::Scalar(0.07));
+			}
+			CvarField::Settings(..) => panic!("not a physics cvar"),
+		}
+		let mut settings = Settings::default();
+		match cvar("render.scale").field {
+			CvarField::Settings(get, set) => {
+				set(&mut settings, CvarValue::Int(3));
+				assert_eq!(settings.render_scale, 3);
+				assert_eq!(get(&settings), CvarValue::Int(3));
+			}
+			CvarField::Physics(..) => panic!("not a settings cvar"),
+		}
+	}
+
+	// By either name, and only the cvars that are kept.
+	#[test]
+	fn the_settings_file_sets_persistent_cvars() {
+		let mut settings = Settings::default();
+		assert_eq!(settings.set("render_scale", "2"), Ok(()));
+		assert_eq!(settings.set("render.vsync", "off"), Ok(()));
+		assert_eq!(settings.render_scale, 2);
+		assert!(settings.vsync == false);
+		assert!(settings.set("render.scale", "99").is_err());
+		assert_eq!(settings.render_scale, 2);
+		let jump = settings.set("phys.jump_speed", "0.05");
+		assert_eq!(jump, Err("Unknown setting \"phys.jump_speed\"".into()));
+	}
+
+	#[test]
+	fn every_default_is_in_range() {
+		for cvar in cvars() {
+			let value = cvar.default_value();
+			assert_eq!(
+				cvar.parse(&value.to_string()),
+				Ok(value),
+				"{}",
+				cvar.name
+			);
+		}
+	}
+
+	#[test]
+	fn names_complete_from_their_start() {
+		let render = complete_cvar("render.");
+		assert!(render.len() >= 4);
+		assert!(render.iter().all(|name| name.starts_with("render.")));
+		assert_eq!(complete_cvar("phys.jump"), vec!["phys.jump_speed"]);
+		assert!(complete_cvar("nothing").is_empty());
+		assert!(find_cvar("render").is_none());
+	}
+}
@@ -0,0 +1,103 @@
+use std::fmt::Write;
+
+use crate::backend::storage;
+use crate::prelude::*;
+use crate::world::{Angle, EntityId, ForeverChange, FrameId, Tile, World};
+
+// Where each level's lasting changes are kept, under a name made from the
+// level's name.
+const FOREVER_DIRECTORY: &str = "forever";
+
+// Levels without a name have nothing to keep their changes under, so
+// never keep any.
+fn forever_path(world: &World) -> Option<String> {
+	let name: String = world
+		.level
+		.name
+		.as_ref()?
+		.chars()
+		.map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+		.collect();
+	Some(format!("{}/{}.txt", FOREVER_DIRECTORY, name))
+}
+
+// One change a line, as `removed <id>`, `tile <frame> <x> <y> <tile>` or
+// `rotation <frame> <x> <y> <quarter turns clockwise>`.
+fn serialize(changes: &[ForeverChange]) -> String {
+	let mut out = String::new();
+	for change in changes {
+		match *change {
+			ForeverChange::Removed(id) => writeln!(out, "removed {}", id.0),
+			ForeverChange::Tile((frame, x, y), tile) => {
+				writeln!(out, "tile {} {} {} {}", frame.0, x, y, tile.name())
+			}
+			ForeverChange::Rotation((frame, x, y), rotation) => {
+				let turns = rotation.quarter_turns();
+				writeln!(out, "rotation {} {} {} {}", frame.0, x, y, turns)
+			}
+		}
+		.unwrap();
+	}
+	out
+}
+
+// Lines that don't parse are skipped, so one bad line loses only its own
+// change.
+fn parse(source: &str) -> Vec<ForeverChange> {
+	source
+		.lines()
+		.filter_map(|line| {
+			let fields: Vec<&str> = line.split_whitespace().collect();
+			match fields.as_slice() {
+				["removed", id] => {
+					Some(ForeverChange::Removed(EntityId(id.parse().ok()?)))
+				}
+				["tile", frame, x, y, tile] => {
+					let location = (
+						FrameId(frame.parse().ok()?),
+						x.parse().ok()?,
+						y.parse().ok()?,
+					);
+					Some(ForeverChange::Tile(location, Tile::from_name(tile)?))
+				}
+				["rotation", frame, x, y, turns] => {
+					let location = (
+						FrameId(frame.parse().ok()?),
+						x.parse().ok()?,
+						y.parse().ok()?,
+					);
+					let turns = turns.parse().ok().filter(|&t| t < 4)?;
+					let rotation = Angle::from_quarter_turns(turns);
+					Some(ForeverChange::Rotation(location, rotation))
+				}
+				_ => None,
+			}
+		})
+		.collect()
+}
+
+// Makes the changes kept from every earlier time the world's level was
+// played, whether it was loaded from its level file or a save.
+pub fn apply(world: &mut World) {
+	let changes = forever_path(world)
+		.and_then(|path| storage::read(&path))
+		.map(|source| parse(&source));
+	if let Some(changes) = changes {
+		world.apply_forever_changes(&changes);
+	}
+}
+
+// Keeps the world's lasting changes since this was last called, after
+// those kept before.
+pub fn record(world: &mut World) {
+	let changes = world.take_forever_changes();
+	let path = match forever_path(world) {
+		Some(path) if changes.is_empty() == false => path,
+		_ => return,
+	};
+	let mut source = storage::read(&path).unwrap_or_default();
+	source.push_str(&serialize(&changes));
+	if let Err(error) = storage::write(&path, &source) {
+		elog(format!("Could not save lasting changes: {}", error));
+	}
+}
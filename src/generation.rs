@@ -0,0 +1,99 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+
+use crate::backend;
+use crate::world::{World, WorldGenerator};
+
+// A generated cube on its way, so that the window keeps drawing while it
+// waits. Either way it is made, it comes out the same for the same seed.
+pub struct Generation {
+	total: usize,
+	work: Work,
+}
+
+enum Work {
+	// A frame for each call to `update`, or `None` once handed over.
+	Stepped(Option<WorldGenerator>),
+	Threaded(Worker),
+}
+
+// Generates on a thread of its own, telling how far it has got.
+struct Worker {
+	generated: Arc<AtomicUsize>,
+	cancelled: Arc<AtomicBool>,
+	world: Receiver<World>,
+}
+
+impl Generation {
+	// Only made on a thread if asked to and the backend has them.
+	pub fn new(seed: u64, threaded: bool) -> Self {
+		let generator = WorldGenerator::new(seed);
+		let total = generator.total();
+		let work = if threaded && backend::threads::AVAILABLE {
+			Work::Threaded(Worker::spawn(generator))
+		} else {
+			Work::Stepped(Some(generator))
+		};
+		Self { total, work }
+	}
+
+	// Frames generated so far, and how many there will be.
+	pub fn progress(&self) -> (usize, usize) {
+		let generated = match &self.work {
+			Work::Stepped(Some(generator)) => generator.generated(),
+			Work::Stepped(None) => self.total,
+			Work::Threaded(worker) => worker.generated.load(Ordering::Relaxed),
+		};
+		(generated, self.total)
+	}
+
+	// Called once a frame. The world once it is finished, which is only
+	// given the once.
+	pub fn update(&mut self) -> Option<World> {
+		match &mut self.work {
+			Work::Stepped(slot) => {
+				let generator = slot.as_mut()?;
+				generator.step();
+				if generator.is_done() == false {
+					return None;
+				}
+				slot.take().map(WorldGenerator::finish)
+			}
+			Work::Threaded(worker) => worker.world.try_recv().ok(),
+		}
+	}
+}
+
+impl Worker {
+	fn spawn(mut generator: WorldGenerator) -> Self {
+		let generated = Arc::new(AtomicUsize::new(0));
+		let cancelled = Arc::new(AtomicBool::new(false));
+		let (sender, world) = mpsc::channel();
+		let (progress, cancel) = (generated.clone(), cancelled.clone());
+		std::thread::spawn(move || {
+			while generator.is_done() == false {
+				if cancel.load(Ordering::Relaxed) {
+					return;
+				}
+				generator.step();
+				progress.store(generator.generated(), Ordering::Relaxed);
+			}
+			// Nothing is waiting for it any more if cancelled since.
+			let _ = sender.send(generator.finish());
+		});
+		Self {
+			generated,
+			cancelled,
+			world,
+		}
+	}
+}
+
+// Dropping the generation cancels it, stopping the thread at its next
+// frame.
+impl Drop for Worker {
+	fn drop(&mut self) {
+		self.cancelled.store(true, Ordering::Relaxed);
+	}
+}
@@ -1,9 +1,38 @@
-use std::ops::{
-	Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign,
-};
-
+// Conventions used throughout:
+//
+// * Matrices are stored row-major, `at(row, column)`.
+// * Vectors are column vectors. `vector * matrix` is written in that order
+//   for convenience but computes `matrix · vector`, followed by the
+//   homogeneous divide. `p * a * b` therefore applies `a` first, then `b`.
+// * `a * b` for matrices is the ordinary product, so `a.translated_by_vec3(t)`
+//   (which is `a * translation`) translates a point first and then applies
+//   `a`.
+// * The coordinate system is right-handed. `normal(a, b, c)` points towards
+//   the side from which `a → b → c` appears counter-clockwise under that
+//   handedness.
+
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub};
+
+// Built with the `f64` feature, the simulation and rendering math run in
+// double precision. The backend still takes `f32` screen coordinates.
+#[cfg(not(feature = "f64"))]
 pub type Scalar = f32;
-pub const PI: f32 = std::f32::consts::PI;
+#[cfg(feature = "f64")]
+pub type Scalar = f64;
+pub const PI: Scalar = std::f64::consts::PI as Scalar;
+// Default tolerance for `approx_eq` comparisons.
+pub const EPSILON: Scalar = 1e-5;
+
+pub fn approx_eq(a: Scalar, b: Scalar, epsilon: Scalar) -> bool {
+	(a - b).abs() <= epsilon
+}
+
+// The length of `(x, y)`. Unlike `hypot`, which comes from each target's
+// own math library, `sqrt` is exactly rounded everywhere, so this is safe to
+// use in the simulation.
+pub fn length_2d(x: Scalar, y: Scalar) -> Scalar {
+	(x * x + y * y).sqrt()
+}
 
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub struct Vector3 {
@@ -22,7 +51,7 @@ impl Vector3 {
 	}
 
 	pub fn len(&self) -> Scalar {
-		(self.x.powi(2) + self.y.powi(2) + self.z.powi(2)).sqrt()
+		(self.x * self.x + self.y * self.y + self.z * self.z).sqrt()
 	}
 
 	pub fn normalized(&self) -> Self {
@@ -59,12 +88,46 @@ impl Vector3 {
 	pub fn dot(&self, other: Vector3) -> Scalar {
 		self.x * other.x + self.y * other.y + self.z * other.z
 	}
+
+	pub fn approx_eq(&self, other: Vector3, epsilon: Scalar) -> bool {
+		approx_eq(self.x, other.x, epsilon)
+			&& approx_eq(self.y, other.y, epsilon)
+			&& approx_eq(self.z, other.z, epsilon)
+	}
+
+	pub fn cross(&self, other: Vector3) -> Self {
+		Self::new(
+			(self.y * other.z) - (self.z * other.y),
+			(self.z * other.x) - (self.x * other.z),
+			(self.x * other.y) - (self.y * other.x),
+		)
+	}
 }
 
 pub fn vec3(x: Scalar, y: Scalar, z: Scalar) -> Vector3 {
 	Vector3::new(x, y, z)
 }
 
+#[derive(Copy, Clone, Debug)]
+pub struct Ray {
+	pub origin: Vector3,
+	// Normalized.
+	pub direction: Vector3,
+}
+
+impl Ray {
+	pub fn new(origin: Vector3, direction: Vector3) -> Self {
+		Self {
+			origin,
+			direction: direction.normalized(),
+		}
+	}
+
+	pub fn at(&self, distance: Scalar) -> Vector3 {
+		self.origin + self.direction * distance
+	}
+}
+
 impl Add for Vector3 {
 	type Output = Self;
 
@@ -187,7 +250,15 @@ impl Matrix4x4 {
 		&mut self.values[i * 4 + j]
 	}
 
-	// Euler rotations
+	pub fn approx_eq(&self, other: &Matrix4x4, epsilon: Scalar) -> bool {
+		self.values
+			.iter()
+			.zip(other.values.iter())
+			.all(|(&a, &b)| approx_eq(a, b, epsilon))
+	}
+
+	// Euler rotations. Applied to a point, the z rotation happens first,
+	// then y, then x.
 	pub fn rotated(&self, x: Scalar, y: Scalar, z: Scalar) -> Self {
 		#[rustfmt::skip]
 		let x_rot_matrix = Matrix4x4::from_values([
@@ -216,6 +287,7 @@ impl Matrix4x4 {
 
 	// See https://en.wikipedia.org/wiki/Rotation_matrix
 	// #Rotation_matrix_from_axis_and_angle
+	// `axis` must be normalized.
 	pub fn rotated_about_axis(&self, axis: Vector3, angle: Scalar) -> Self {
 		let Vector3 { x, y, z } = axis;
 		let a = angle;
@@ -256,7 +328,53 @@ impl Matrix4x4 {
 		output
 	}
 
+	// Gauss-Jordan elimination with partial pivoting. Returns `None` for
+	// singular matrices.
+	pub fn inverse(&self) -> Option<Self> {
+		let mut left = *self;
+		let mut right = Matrix4x4::identity();
+
+		for column in 0..4 {
+			let pivot_row = (column..4)
+				.max_by(|&a, &b| {
+					let a = left.at(a, column).abs();
+					let b = left.at(b, column).abs();
+					a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal)
+				})
+				.unwrap();
+			if left.at(pivot_row, column).abs() < 1e-12 {
+				return None;
+			}
+
+			for j in 0..4 {
+				left.values.swap(column * 4 + j, pivot_row * 4 + j);
+				right.values.swap(column * 4 + j, pivot_row * 4 + j);
+			}
+
+			let pivot = *left.at(column, column);
+			for j in 0..4 {
+				*left.at_mut(column, j) /= pivot;
+				*right.at_mut(column, j) /= pivot;
+			}
+
+			for i in 0..4 {
+				if i == column {
+					continue;
+				}
+				let factor = *left.at(i, column);
+				for j in 0..4 {
+					*left.at_mut(i, j) -= factor * *left.at(column, j);
+					*right.at_mut(i, j) -= factor * *right.at(column, j);
+				}
+			}
+		}
+
+		Some(right)
+	}
+
 	pub fn translated_by_vec3(&self, vector: Vector3) -> Self {
+		// The translation lives in the last column since vectors are column
+		// vectors.
 		#[rustfmt::skip]
 		let translation_matrix = Matrix4x4::from_values([
 			1.0, 0.0, 0.0, vector.x,
@@ -264,12 +382,6 @@ impl Matrix4x4 {
 			0.0, 0.0, 1.0, vector.z,
 			0.0, 0.0, 0.0, 1.0,
 		]);
-		// let translation_matrix = Matrix4x4::from_values([
-		// 	1.0, 0.0, 0.0, 0.0,
-		// 	0.0, 1.0, 0.0, 0.0,
-		// 	0.0, 0.0, 1.0, 0.0,
-		// 	vector.x, vector.y, vector.z, 1.0,
-		// ]);
 
 		*self * translation_matrix
 	}
@@ -318,12 +430,143 @@ pub fn normal(a: Vector3, b: Vector3, c: Vector3) -> Vector3 {
 	let v = b - a;
 	let w = c - a;
 
-	let normal_x = (v.y * w.z) - (v.z * w.y);
-	let normal_y = (v.z * w.x) - (v.x * w.z);
-	let normal_z = (v.x * w.y) - (v.y * w.x);
-
-	Vector3::new(normal_x, normal_y, normal_z).normalized()
+	v.cross(w).normalized()
 }
 
 // pub fn clockwise(a: Vector3, b: Vector3, c: Vector3) -> bool {
 // }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const QUARTER: Scalar = PI / 2.0;
+
+	fn translation(x: Scalar, y: Scalar, z: Scalar) -> Matrix4x4 {
+		Matrix4x4::identity().translated_by_vec3(vec3(x, y, z))
+	}
+
+	fn assert_vec_eq(actual: Vector3, expected: Vector3) {
+		assert!(
+			actual.approx_eq(expected, EPSILON),
+			"{} is not {}",
+			actual,
+			expected
+		);
+	}
+
+	fn assert_matrix_eq(actual: Matrix4x4, expected: Matrix4x4) {
+		assert!(
+			actual.approx_eq(&expected, EPSILON),
+			"{:?} is not {:?}",
+			actual,
+			expected
+		);
+	}
+
+	#[test]
+	fn identity_changes_nothing() {
+		let m =
+			Matrix4x4::rotation(0.3, -1.2, 2.0) * translation(1.0, 2.0, 3.0);
+		assert_matrix_eq(m * Matrix4x4::identity(), m);
+		assert_matrix_eq(Matrix4x4::identity() * m, m);
+		let v = vec3(0.5, -0.25, 4.0);
+		assert_vec_eq(v * Matrix4x4::identity(), v);
+	}
+
+	#[test]
+	fn multiplication_is_associative() {
+		let a = Matrix4x4::rotation(0.4, 0.0, 1.1);
+		let b = translation(-2.0, 0.5, 7.0);
+		let c = Matrix4x4::identity()
+			.rotated_about_axis(vec3(1.0, 1.0, 0.0).normalized(), 0.7);
+		assert_matrix_eq((a * b) * c, a * (b * c));
+
+		// Applying a product to a point is applying each in turn, the
+		// rightmost first.
+		let v = vec3(1.0, -3.0, 0.5);
+		assert_vec_eq(v * (a * b * c), ((v * c) * b) * a);
+	}
+
+	#[test]
+	fn rotations_turn_towards_the_next_axis() {
+		let (x, y, z) = (
+			vec3(1.0, 0.0, 0.0),
+			vec3(0.0, 1.0, 0.0),
+			vec3(0.0, 0.0, 1.0),
+		);
+		assert_vec_eq(y * Matrix4x4::rotation(QUARTER, 0.0, 0.0), z);
+		assert_vec_eq(z * Matrix4x4::rotation(0.0, QUARTER, 0.0), x);
+		assert_vec_eq(x * Matrix4x4::rotation(0.0, 0.0, QUARTER), y);
+		assert_vec_eq(x * Matrix4x4::rotation(0.0, QUARTER, 0.0), -z);
+	}
+
+	#[test]
+	fn euler_rotations_apply_z_then_y_then_x() {
+		let x = vec3(1.0, 0.0, 0.0);
+		// z takes x to y, which x then takes to z. The other way around, x
+		// would leave x alone and z take it to y.
+		let turned = x * Matrix4x4::rotation(QUARTER, 0.0, QUARTER);
+		assert_vec_eq(turned, vec3(0.0, 0.0, 1.0));
+	}
+
+	#[test]
+	fn rotation_about_an_axis_matches_euler_rotation() {
+		let angle = 0.9;
+		let about_z = Matrix4x4::identity()
+			.rotated_about_axis(vec3(0.0, 0.0, 1.0), angle);
+		assert_matrix_eq(about_z, Matrix4x4::rotation(0.0, 0.0, angle));
+		let about_x = Matrix4x4::identity()
+			.rotated_about_axis(vec3(1.0, 0.0, 0.0), angle);
+		assert_matrix_eq(about_x, Matrix4x4::rotation(angle, 0.0, 0.0));
+	}
+
+	#[test]
+	fn translation_and_rotation_apply_innermost_first() {
+		let origin = Vector3::zero();
+		let turn = Matrix4x4::rotation(0.0, 0.0, QUARTER);
+
+		// Moved along x, then turned onto y.
+		let moved_then_turned = turn.translated_by_vec3(vec3(1.0, 0.0, 0.0));
+		assert_vec_eq(origin * moved_then_turned, vec3(0.0, 1.0, 0.0));
+
+		// Turned, which leaves the origin where it is, then moved.
+		let turned_then_moved = translation(1.0, 0.0, 0.0) * turn;
+		assert_vec_eq(origin * turned_then_moved, vec3(1.0, 0.0, 0.0));
+	}
+
+	#[test]
+	fn vector_times_matrix_divides_by_w() {
+		#[rustfmt::skip]
+		let m = Matrix4x4::from_values([
+			1.0, 2.0, 3.0, 4.0,
+			5.0, 6.0, 7.0, 8.0,
+			9.0, 1.0, 2.0, 3.0,
+			0.0, 0.0, 0.0, 2.0,
+		]);
+		// (1 + 2 + 3 + 4, 5 + 6 + 7 + 8, 9 + 1 + 2 + 3) / 2
+		assert_vec_eq(vec3(1.0, 1.0, 1.0) * m, vec3(5.0, 13.0, 7.5));
+	}
+
+	#[test]
+	fn inverse_undoes_a_transform() {
+		let m =
+			translation(3.0, -1.0, 2.0) * Matrix4x4::rotation(0.2, 1.3, -0.6);
+		let inverse = m.inverse().unwrap();
+		assert_matrix_eq(m * inverse, Matrix4x4::identity());
+		let v = vec3(0.5, 2.0, -1.5);
+		assert_vec_eq(v * m * inverse, v);
+
+		assert!(Matrix4x4::zero().inverse().is_none());
+	}
+
+	#[test]
+	fn normal_follows_the_right_hand_rule() {
+		let a = Vector3::zero();
+		let (b, c) = (vec3(2.0, 0.0, 0.0), vec3(0.0, 3.0, 0.0));
+		// Counterclockwise seen from +z points out towards +z, and the
+		// other way round away from it.
+		assert_vec_eq(normal(a, b, c), vec3(0.0, 0.0, 1.0));
+		assert_vec_eq(normal(a, c, b), vec3(0.0, 0.0, -1.0));
+	}
+}
@@ -0,0 +1,278 @@
+use crate::backend::storage;
+use crate::geometry::Scalar;
+use crate::prelude::*;
+use crate::world::{from_hex, to_hex, Reader, SaveError, Writer};
+use crate::world::{Direction, FrameId, World, WorldEvent};
+
+// Where each level's ghost is kept, under a name made from its path.
+const GHOST_DIRECTORY: &str = "ghosts";
+// Ticks between keyframes. Drawing interpolates between them.
+const KEYFRAME_TICKS: u64 = 2;
+// Ten minutes of keyframes. Longer runs stop being recorded, and so never
+// become the ghost.
+const MAX_KEYFRAMES: usize = 18_000;
+// Ghosts of any other version are thrown away, as those of another version
+// of the level are.
+const GHOST_MAGIC: &[u8; 4] = b"GHST";
+const GHOST_VERSION: u32 = 1;
+
+// Where the player was on one tick of a run.
+#[derive(Copy, Clone, Debug)]
+pub struct Keyframe {
+	pub tick: u64,
+	pub frame: FrameId,
+	pub x: Scalar,
+	pub y: Scalar,
+	pub gravity: Direction,
+}
+
+// A completed run through a level, for racing against.
+pub struct Ghost {
+	// Of the level source the run was made on. Ghosts of any other version
+	// of the level are thrown away.
+	level_hash: u64,
+	// How long the run took.
+	pub ticks: u64,
+	keyframes: Vec<Keyframe>,
+	// The tick each coin was collected on, in order.
+	splits: Vec<u64>,
+}
+
+impl Ghost {
+	fn new(level_hash: u64) -> Self {
+		Self {
+			level_hash,
+			ticks: 0,
+			keyframes: Vec::new(),
+			splits: Vec::new(),
+		}
+	}
+
+	// Where the ghost was on `tick`, between the keyframes either side, or
+	// nothing before it started or after it finished. Keyframes on
+	// different frames are not blended, since their coordinates don't line
+	// up.
+	pub fn at(&self, tick: u64) -> Option<Keyframe> {
+		if tick > self.ticks {
+			return None;
+		}
+		let next = self.keyframes.iter().position(|k| k.tick >= tick)?;
+		let after = self.keyframes[next];
+		if after.tick == tick || next == 0 {
+			return Some(after);
+		}
+		let before = self.keyframes[next - 1];
+		if before.frame != after.frame {
+			return Some(before);
+		}
+		let t = (tick - before.tick) as Scalar
+			/ (after.tick - before.tick) as Scalar;
+		Some(Keyframe {
+			tick,
+			x: before.x + (after.x - before.x) * t,
+			y: before.y + (after.y - before.y) * t,
+			..before
+		})
+	}
+
+	// `GHOST_MAGIC` and `GHOST_VERSION`, the level hash, how long the run
+	// took, the splits, then the keyframes, as the save format writes
+	// values.
+	fn to_bytes(&self) -> Vec<u8> {
+		let mut out = Writer::new();
+		out.bytes.extend_from_slice(GHOST_MAGIC);
+		out.u32(GHOST_VERSION);
+		out.u64(self.level_hash);
+		out.u64(self.ticks);
+		out.u32(self.splits.len() as u32);
+		for &split in &self.splits {
+			out.u64(split);
+		}
+		out.u32(self.keyframes.len() as u32);
+		for k in &self.keyframes {
+			out.u64(k.tick);
+			out.id(k.frame.0);
+			out.scalar(k.x);
+			out.scalar(k.y);
+			out.direction(k.gravity);
+		}
+		out.bytes
+	}
+
+	fn from_bytes(bytes: &[u8]) -> Result<Self, SaveError> {
+		let mut input = Reader::new(bytes);
+		match input.take(GHOST_MAGIC.len()) {
+			Ok(magic) if magic == GHOST_MAGIC => {}
+			_ => return Err(SaveError::NotASave),
+		}
+		let version = input.u32()?;
+		if version != GHOST_VERSION {
+			return Err(SaveError::UnsupportedVersion(version));
+		}
+		let mut ghost = Self::new(input.u64()?);
+		ghost.ticks = input.u64()?;
+		for _ in 0..input.count(8)? {
+			ghost.splits.push(input.u64()?);
+		}
+		for _ in 0..input.count(8 + 8 + 8 + 8 + 1)? {
+			ghost.keyframes.push(Keyframe {
+				tick: input.u64()?,
+				frame: FrameId(input.id()?),
+				x: input.finite_scalar()?,
+				y: input.finite_scalar()?,
+				gravity: input.direction()?,
+			});
+		}
+		if input.remaining() > 0 {
+			return Err(SaveError::Invalid("bytes left over".to_string()));
+		}
+		Ok(ghost)
+	}
+}
+
+// FNV-1a, rather than the standard library's hasher, whose output may
+// change between Rust versions and would then throw every ghost away.
+pub fn level_hash(source: &str) -> u64 {
+	source.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+		(hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+	})
+}
+
+fn ghost_path(level_path: &str) -> String {
+	let name: String = level_path
+		.chars()
+		.map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+		.collect();
+	format!("{}/{}.ghost", GHOST_DIRECTORY, name)
+}
+
+// A level being played against the clock, recording the run as it goes and
+// racing the best run before it.
+pub struct TimeTrial {
+	level_path: String,
+	// The best run so far, if it was made on this version of the level.
+	pub ghost: Option<Ghost>,
+	run: Ghost,
+}
+
+impl TimeTrial {
+	pub fn new(level_path: &str, level_source: &str) -> Self {
+		let level_hash = level_hash(level_source);
+		let ghost = storage::read(&ghost_path(level_path))
+			.and_then(|text| from_hex(&text))
+			.and_then(|bytes| Ghost::from_bytes(&bytes).ok())
+			.filter(|ghost| ghost.level_hash == level_hash);
+		Self {
+			level_path: level_path.to_string(),
+			ghost,
+			run: Ghost::new(level_hash),
+		}
+	}
+
+	// Called after every tick of the run.
+	pub fn update(&mut self, world: &World) {
+		let focus = match world.focus_entity {
+			Some(id) => id,
+			None => return,
+		};
+		for event in world.events() {
+			if let WorldEvent::CoinCollected { collector, .. } = *event {
+				if collector == focus {
+					self.run.splits.push(world.tick_count);
+				}
+			}
+		}
+
+		let run = &mut self.run;
+		if world.tick_count.is_multiple_of(KEYFRAME_TICKS) == false
+			|| run.keyframes.len() >= MAX_KEYFRAMES
+		{
+			return;
+		}
+		if let Some(entity) = world.get_entity(focus) {
+			run.keyframes.push(Keyframe {
+				tick: world.tick_count,
+				frame: entity.position.frame_id,
+				x: entity.position.x,
+				y: entity.position.y,
+				gravity: entity.gravity_dir,
+			});
+		}
+	}
+
+	// Keeps the run just finished as the ghost to race from now on, unless
+	// it ran too long to have been recorded to the end.
+	pub fn keep_run(&mut self, ticks: u64) {
+		let level_hash = self.run.level_hash;
+		let mut run = std::mem::replace(&mut self.run, Ghost::new(level_hash));
+		if run.keyframes.len() >= MAX_KEYFRAMES {
+			return;
+		}
+		run.ticks = ticks;
+		let path = ghost_path(&self.level_path);
+		if let Err(error) = storage::write(&path, &to_hex(&run.to_bytes())) {
+			elog(format!("Could not save ghost: {}", error));
+		}
+		self.ghost = Some(run);
+	}
+
+	// How many ticks this run is behind the ghost, or ahead of it if
+	// negative. Taken at the last coin both have collected, or if the ghost
+	// has since collected the next and this run hasn't, from then.
+	pub fn delta(&self, tick: u64) -> Option<i64> {
+		let ghost = self.ghost.as_ref()?;
+		let collected = self.run.splits.len();
+		if let Some(&next) = ghost.splits.get(collected) {
+			if tick > next {
+				return Some((tick - next) as i64);
+			}
+		}
+		let last = collected.checked_sub(1)?;
+		let ghost_split = *ghost.splits.get(last)?;
+		Some(self.run.splits[last] as i64 - ghost_split as i64)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn run() -> Ghost {
+		let mut ghost = Ghost::new(level_hash("frame 0"));
+		ghost.ticks = 90;
+		ghost.splits = vec![12, 40];
+		for i in 0..4 {
+			ghost.keyframes.push(Keyframe {
+				tick: i * KEYFRAME_TICKS,
+				frame: FrameId(i as usize % 2),
+				x: 0.25 * i as Scalar - 0.5,
+				y: 0.625,
+				gravity: Direction::Down,
+			});
+		}
+		ghost
+	}
+
+	#[test]
+	fn a_kept_ghost_is_read_back_as_it_was() {
+		let ghost = run();
+		let read = Ghost::from_bytes(&ghost.to_bytes()).unwrap();
+		assert_eq!(read.level_hash, ghost.level_hash);
+		assert_eq!(read.ticks, ghost.ticks);
+		assert_eq!(read.splits, ghost.splits);
+		assert_eq!(read.keyframes.len(), ghost.keyframes.len());
+		for (read, k) in read.keyframes.iter().zip(&ghost.keyframes) {
+			assert_eq!((read.tick, read.frame), (k.tick, k.frame));
+			assert_eq!((read.x, read.y), (k.x, k.y));
+			assert_eq!(read.gravity, k.gravity);
+		}
+	}
+
+	#[test]
+	fn a_cut_short_ghost_is_never_read() {
+		let bytes = run().to_bytes();
+		for length in 0..bytes.len() {
+			assert!(Ghost::from_bytes(&bytes[..length]).is_err());
+		}
+	}
+}
@@ -0,0 +1,441 @@
+// The game as a library. `world` simulates the cube and needs nothing else,
+// so tools can load, tick and save worlds without opening a window; `run`
+// is the whole game, as started by the binary.
+
+mod autosave;
+mod campaign;
+mod cvars;
+mod forever;
+mod generation;
+pub mod geometry;
+mod ghost;
+mod logging;
+pub mod multiplayer;
+mod settings;
+pub mod window;
+pub mod world;
+
+use std::panic::{self, AssertUnwindSafe};
+
+use campaign::{Completion, Menu};
+use generation::Generation;
+use ghost::TimeTrial;
+use multiplayer::{Client, Host, Session};
+use window::{BackendError, Window};
+use world::{
+	Actions, EdgeBehavior, EditHistory, Playback, TileData, World, WorldEvent,
+};
+
+pub(crate) use window::backend;
+#[cfg(target_arch = "wasm32")]
+pub use window::external_exports::*;
+
+#[macro_use]
+extern crate lazy_static;
+
+// Opens the window and plays until it is closed, taking options from the
+// command line such as `--level <path>`. Fails if the window can't be
+// opened.
+pub fn run() -> Result<(), BackendError> {
+	// Checks a level and exits without opening a window.
+	if let Some(path) = argument("--validate") {
+		let valid = validate_level(&path);
+		std::process::exit(if valid { 0 } else { 1 });
+	}
+	// Ticks a world and exits without opening a window either.
+	let dump_state = std::env::args().any(|arg| arg == "--dump-state");
+	let headless_ticks = argument("--headless-ticks");
+	if dump_state || headless_ticks.is_some() {
+		let ticks = headless_ticks.unwrap_or_else(|| String::from("0"));
+		let ok = run_headless(argument("--level"), &ticks, dump_state);
+		std::process::exit(if ok { 0 } else { 1 });
+	}
+
+	let demo = std::env::args().any(|arg| arg == "--demo");
+	let join = argument("--join");
+	let mut game_state = match argument("--level") {
+		Some(path) => GameState::from_level_file(&path),
+		// Whatever world there is until the host's comes.
+		None if join.is_some() => GameState::from_world_as_is(World::new()),
+		None if demo => GameState::demo().unwrap_or_else(|error| {
+			prelude::elog(error);
+			GameState::with_menu()
+		}),
+		None => GameState::with_menu(),
+	};
+	if let Some(address) = join {
+		match Client::connect(&address) {
+			Ok(client) => game_state.session = Some(Session::Client(client)),
+			Err(error) => {
+				prelude::elog(format!("Could not join {}: {}", address, error))
+			}
+		}
+	} else if let Some(address) = argument("--host") {
+		match Host::bind(&address, &game_state.world) {
+			Ok(host) => game_state.session = Some(Session::Host(host)),
+			Err(error) => prelude::elog(format!(
+				"Could not host on {}: {}",
+				address, error
+			)),
+		}
+	}
+
+	let mut window = Window::new()?;
+	match argument("--stress").map(|count| count.parse()) {
+		Some(Ok(count)) => {
+			window.start_stress(&mut game_state, count, EdgeBehavior::default())
+		}
+		Some(Err(_)) => prelude::elog("--stress expects a number of walkers"),
+		None => {}
+	}
+	// After the window, which loads the settings.
+	let log_path = argument("--log-file")
+		.or_else(|| window.log_file_path().map(String::from));
+	if let Some(path) = log_path {
+		logging::start_file(&path);
+	}
+	// After the window, which may install its own hook to wrap.
+	logging::install_panic_hook();
+
+	window::begin_loop(
+		window,
+		game_state,
+		move |window: &mut Window, game_state: &mut GameState| {
+			// A panic during the frame is caught just long enough to save
+			// the world, once nothing is left borrowing it. Where panics
+			// abort instead, as on the web, nothing is saved.
+			let frame = panic::catch_unwind(AssertUnwindSafe(|| {
+				window.tick(game_state);
+				window.render(game_state);
+			}));
+			if let Err(panic) = frame {
+				autosave::emergency_save(&game_state.world);
+				logging::flush();
+				panic::resume_unwind(panic);
+			}
+			logging::set_tick(game_state.world.tick_count);
+			logging::update();
+		},
+	);
+	Ok(())
+}
+
+// Prints what stops the level at `path` from being played through, and
+// whether there was anything.
+fn validate_level(path: &str) -> bool {
+	let world = match load_level(path) {
+		Ok(world) => world,
+		Err(error) => {
+			println!("{}: {}", path, error);
+			return false;
+		}
+	};
+
+	let issues = world.validate_level();
+	if issues.is_empty() {
+		println!("{}: ok", path);
+		return true;
+	}
+	for issue in &issues {
+		println!("{}: {}", path, issue);
+	}
+	println!(
+		"{}: {} issue{}",
+		path,
+		issues.len(),
+		if issues.len() == 1 { "" } else { "s" }
+	);
+	false
+}
+
+// Ticks the level at `path`, or the generated cube, `ticks` times with no
+// input, then prints its tick and state hash, or all of it as
+// `World::dump_state` writes it. Gives whether there was a world to tick.
+fn run_headless(path: Option<String>, ticks: &str, dump_state: bool) -> bool {
+	let ticks: u64 = match ticks.parse() {
+		Ok(ticks) => ticks,
+		Err(_) => {
+			println!("--headless-ticks expects a number of ticks");
+			return false;
+		}
+	};
+	let mut world = match path {
+		Some(path) => match load_level(&path) {
+			Ok(world) => world,
+			Err(error) => {
+				println!("{}: {}", path, error);
+				return false;
+			}
+		},
+		None => World::new(),
+	};
+	for _ in 0..ticks {
+		world.tick(&Actions::none());
+	}
+	match dump_state {
+		true => print!("{}", world.dump_state()),
+		false => println!(
+			"tick {} hash {:016x}",
+			world.tick_count,
+			world.state_hash()
+		),
+	}
+	true
+}
+
+fn load_level(path: &str) -> Result<World, String> {
+	campaign::read_level(path)
+		.map_err(|error| format!("could not read level: {}", error))
+		.and_then(|source| {
+			World::from_level_str(&source)
+				.map_err(|error| format!("could not load level: {}", error))
+		})
+}
+
+// The value given after `name` on the command line, if any, such as the
+// path in `--level <path>`.
+fn argument(name: &str) -> Option<String> {
+	let mut args = std::env::args().skip(1);
+	while let Some(arg) = args.next() {
+		if arg == name {
+			return args.next();
+		}
+	}
+	None
+}
+
+mod prelude {
+	use super::logging::{self, Level};
+
+	pub fn elog<T: std::borrow::Borrow<str> + std::fmt::Display>(msg: T) {
+		super::backend::print(msg.borrow());
+		logging::record(Level::Error, msg.borrow());
+	}
+
+	pub fn log<T: std::borrow::Borrow<str> + std::fmt::Display>(msg: T) {
+		super::backend::print(msg.borrow());
+		logging::record(Level::Info, msg.borrow());
+	}
+}
+
+pub struct GameState {
+	pub world: World,
+	// Text being shown to the player, such as a sign they are reading.
+	pub text_box: Option<String>,
+	// Where the level being played was loaded from, which its best time is
+	// kept under.
+	pub level_path: Option<String>,
+	// Shown over the world until a level is chosen.
+	pub menu: Option<Menu>,
+	// Set once the level is complete, after which the player can't act.
+	pub completion: Option<Completion>,
+	// Races the best run so far on levels that can be completed.
+	pub time_trial: Option<TimeTrial>,
+	// A new cube being made, shown over the world until it replaces it.
+	pub generating: Option<Generation>,
+	// Input played back in place of the player's, as the attract mode on
+	// the menu does.
+	pub demo: Option<Playback>,
+	// Playing over the network, as its host or watching someone else's
+	// world.
+	pub session: Option<Session>,
+	// What has been done to the world in the editor, gone with the world.
+	pub edit_history: EditHistory,
+}
+
+impl GameState {
+	// A generated world, different every run.
+	pub fn new() -> Self {
+		let seed = backend::random::entropy_seed();
+		Self::from_world(World::with_seed(seed))
+	}
+
+	// A generated world behind the level menu.
+	pub fn with_menu() -> Self {
+		Self {
+			menu: Some(Menu::new()),
+			..Self::new()
+		}
+	}
+
+	pub fn from_world(mut world: World) -> Self {
+		forever::apply(&mut world);
+		Self::from_world_as_is(world)
+	}
+
+	// Without the lasting changes kept for the world's level, which would
+	// make it differ from one player to the next.
+	fn from_world_as_is(world: World) -> Self {
+		Self {
+			world,
+			text_box: None,
+			level_path: None,
+			menu: None,
+			completion: None,
+			time_trial: None,
+			generating: None,
+			demo: None,
+			session: None,
+			edit_history: EditHistory::new(),
+		}
+	}
+
+	// Starts on a new cube to replace the world with, made a frame at a
+	// time so that there is something to draw in the meantime.
+	pub fn start_generating(&mut self, threaded: bool) {
+		let seed = backend::random::entropy_seed();
+		self.generating = Some(Generation::new(seed, threaded));
+		self.text_box = None;
+		self.menu = None;
+		self.completion = None;
+	}
+
+	// Whether the cube being made was finished, and the world replaced by
+	// it.
+	pub fn update_generation(&mut self) -> bool {
+		let generation = self.generating.as_mut();
+		match generation.and_then(|generation| generation.update()) {
+			Some(world) => {
+				*self = Self::from_world(world);
+				true
+			}
+			None => false,
+		}
+	}
+
+	// Back to the level menu, leaving the world as it was.
+	pub fn cancel_generation(&mut self) {
+		self.generating = None;
+		self.menu = Some(Menu::new());
+	}
+
+	// `path` may name a built-in level as well as a file.
+	pub fn load_level(path: &str) -> Result<Self, String> {
+		let source = campaign::read_level(path).map_err(|error| {
+			format!("Could not read level {}: {}", path, error)
+		})?;
+		let world = World::from_level_str(&source).map_err(|error| {
+			format!("Could not load level {}: {}", path, error)
+		})?;
+		let time_trial = if world.coins_remaining() > 0 {
+			Some(TimeTrial::new(path, &source))
+		} else {
+			None
+		};
+		Ok(Self {
+			level_path: Some(path.to_string()),
+			time_trial,
+			..Self::from_world(world)
+		})
+	}
+
+	// A level that didn't come from a file, so has no best time kept.
+	pub fn from_level_source(source: &str) -> Result<Self, String> {
+		let world = World::from_level_str(source)
+			.map_err(|error| format!("Could not load level: {}", error))?;
+		Ok(Self::from_world(world))
+	}
+
+	// The built-in demo, playing itself from the start of its level and
+	// keeping nothing of what happens.
+	pub fn demo() -> Result<Self, String> {
+		let replay = campaign::demo_replay();
+		let source = campaign::read_level(&replay.level).map_err(|error| {
+			format!("Could not read level {}: {}", replay.level, error)
+		})?;
+		let world = World::from_level_str(&source).map_err(|error| {
+			format!("Could not load level {}: {}", replay.level, error)
+		})?;
+		Ok(Self {
+			demo: Some(Playback::new(replay)),
+			..Self::from_world_as_is(world)
+		})
+	}
+
+	// Whether the demo being played has run out of input.
+	pub fn demo_finished(&self) -> bool {
+		self.demo.as_ref().is_some_and(Playback::is_finished)
+	}
+
+	// Falls back to a generated world if the level can't be loaded.
+	pub fn from_level_file(path: &str) -> Self {
+		Self::load_level(path).unwrap_or_else(|error| {
+			prelude::elog(error);
+			Self::new()
+		})
+	}
+
+	pub fn tick(&mut self, actions: &Actions) {
+		// A demo plays its own input, whatever the player does.
+		let played = self
+			.demo
+			.as_mut()
+			.map(|playback| playback.next_actions().unwrap_or_default());
+		let actions = played.as_ref().unwrap_or(actions);
+		let waiting = self.generating.is_some();
+		if self.menu.is_some() || self.completion.is_some() || waiting {
+			self.tick_world(&Actions::none());
+			return;
+		}
+
+		// Movement is suppressed while a text box is open, and the interact
+		// action that opened it closes it again.
+		if self.text_box.is_some() {
+			if actions.interact {
+				self.text_box = None;
+			}
+			self.tick_world(&Actions::none());
+			self.update_time_trial();
+			return;
+		}
+
+		self.tick_world(actions);
+		self.update_time_trial();
+
+		for event in self.world.events() {
+			match *event {
+				WorldEvent::SignActivated { frame, x, y, .. } => {
+					let data = self.world.get_frame(frame).and_then(|frame| {
+						frame.tile_data(x as isize, y as isize)
+					});
+					if let Some(TileData::Text(text)) = data {
+						self.text_box = Some(text.clone());
+					}
+				}
+				WorldEvent::LevelCompleted { ticks, .. } => {
+					self.completion = Some(Completion::new(
+						ticks,
+						self.world.stats(),
+						&self.world.level,
+					));
+				}
+				_ => {}
+			}
+		}
+	}
+
+	// A client has its world brought up to date by the host instead.
+	fn tick_world(&mut self, actions: &Actions) {
+		if let Some(Session::Client(client)) = self.session.as_mut() {
+			if let Err(error) = client.update(&mut self.world) {
+				prelude::elog(format!("Left the game: {}", error));
+				self.session = None;
+			}
+			return;
+		}
+		self.world.tick(actions);
+		if self.demo.is_none() {
+			forever::record(&mut self.world);
+		}
+		if let Some(Session::Host(host)) = self.session.as_mut() {
+			host.update(&self.world);
+		}
+	}
+
+	// The clock keeps running while a sign is being read.
+	fn update_time_trial(&mut self) {
+		if let Some(time_trial) = self.time_trial.as_mut() {
+			time_trial.update(&self.world);
+		}
+	}
+}
@@ -0,0 +1,94 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use crate::backend::{self, log_file};
+
+pub const DEFAULT_LOG_PATH: &str = "sdl_cube.log";
+// How many of the latest lines are kept for `tail`.
+const TAIL_LINES: usize = 200;
+
+lazy_static! {
+	static ref TAIL: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+}
+// The world tick lines are stamped with, set once per frame.
+static TICK: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Level {
+	Info,
+	Error,
+}
+
+impl Level {
+	fn name(&self) -> &'static str {
+		match self {
+			Level::Info => "info",
+			Level::Error => "error",
+		}
+	}
+}
+
+// Keeps a line from the prelude logger for `tail` and the log file, stamped
+// with the time and tick it was logged at.
+pub fn record(level: Level, message: &str) {
+	let line = format!(
+		"[{:.3}s tick {}] {}: {}",
+		backend::time::now(),
+		TICK.load(Ordering::Relaxed),
+		level.name(),
+		message
+	);
+	log_file::write(&line);
+	let mut tail = TAIL.lock().unwrap();
+	tail.push_back(line);
+	while tail.len() > TAIL_LINES {
+		tail.pop_front();
+	}
+}
+
+// Mirrors the log to the file at `path` from now on, starting with
+// whatever was logged before it was opened. Carries on with the console
+// alone if it can't be opened.
+pub fn start_file(path: &str) {
+	if let Err(error) = log_file::open(path) {
+		backend::print(&format!("Could not open log file {}: {}", path, error));
+		return;
+	}
+	for line in TAIL.lock().unwrap().iter() {
+		log_file::write(line);
+	}
+}
+
+pub fn set_tick(tick: u64) {
+	TICK.store(tick, Ordering::Relaxed);
+}
+
+// Run once per frame, so the file is written out even when nothing new is
+// being logged.
+pub fn update() {
+	log_file::flush_if_due();
+}
+
+// The last `count` lines, oldest first.
+pub fn tail(count: usize) -> Vec<String> {
+	let tail = TAIL.lock().unwrap();
+	let skip = tail.len().saturating_sub(count);
+	tail.iter().skip(skip).cloned().collect()
+}
+
+// Writes out the log file now rather than when next due.
+pub fn flush() {
+	log_file::flush();
+}
+
+// Has panics write out the log file once the previously installed hook,
+// which may still log, has reported them.
+pub fn install_panic_hook() {
+	let previous = std::panic::take_hook();
+	std::panic::set_hook(Box::new(move |info| {
+		previous(info);
+		record(Level::Error, &format!("Panic: {}", info));
+		log_file::flush();
+	}));
+}
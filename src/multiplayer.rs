@@ -0,0 +1,256 @@
+use std::io::{ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+
+use crate::prelude::*;
+use crate::world::{SyncClient, SyncHost, SyncMessage, World};
+
+// Playing over TCP, with one player hosting the world and any number
+// joining to watch it. Only the host ticks the world; see `SyncHost` for
+// what the others are sent of it.
+
+// The longest message either side takes, far more than a whole world.
+const MAX_MESSAGE_LENGTH: usize = 64 << 20;
+// Read from the stream at most this much at a time.
+const READ_CHUNK: usize = 64 << 10;
+
+// Sync messages each after their length, as a little-endian u32. Never
+// waits on the stream: whatever it won't take yet is kept for the next
+// `flush`.
+struct Connection {
+	stream: TcpStream,
+	incoming: Vec<u8>,
+	outgoing: Vec<u8>,
+}
+
+impl Connection {
+	fn new(stream: TcpStream) -> Result<Self, String> {
+		stream
+			.set_nonblocking(true)
+			.map_err(|error| error.to_string())?;
+		stream
+			.set_nodelay(true)
+			.map_err(|error| error.to_string())?;
+		Ok(Self {
+			stream,
+			incoming: Vec::new(),
+			outgoing: Vec::new(),
+		})
+	}
+
+	fn send(&mut self, message: &SyncMessage) {
+		let bytes = message.to_bytes();
+		self.outgoing
+			.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+		self.outgoing.extend_from_slice(&bytes);
+	}
+
+	fn flush(&mut self) -> Result<(), String> {
+		while self.outgoing.is_empty() == false {
+			match self.stream.write(&self.outgoing) {
+				Ok(0) => return Err("connection closed".to_string()),
+				Ok(written) => {
+					self.outgoing.drain(..written);
+				}
+				Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+				Err(error) if error.kind() == ErrorKind::Interrupted => {}
+				Err(error) => return Err(error.to_string()),
+			}
+		}
+		Ok(())
+	}
+
+	// Every message that has come in full since the last call. Fails once
+	// the other side has gone, or sent something that isn't a message.
+	fn receive(&mut self) -> Result<Vec<SyncMessage>, String> {
+		let mut chunk = vec![0; READ_CHUNK];
+		loop {
+			match self.stream.read(&mut chunk) {
+				Ok(0) => return Err("connection closed".to_string()),
+				Ok(read) => self.incoming.extend_from_slice(&chunk[..read]),
+				Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+				Err(error) if error.kind() == ErrorKind::Interrupted => {}
+				Err(error) => return Err(error.to_string()),
+			}
+		}
+
+		let mut messages = Vec::new();
+		let mut start = 0;
+		while self.incoming.len() - start >= 4 {
+			let mut length = [0; 4];
+			length.copy_from_slice(&self.incoming[start..start + 4]);
+			let length = u32::from_le_bytes(length) as usize;
+			if length > MAX_MESSAGE_LENGTH {
+				return Err(format!("message of {} bytes is too long", length));
+			}
+			let end = start + 4 + length;
+			if self.incoming.len() < end {
+				break;
+			}
+			let message =
+				SyncMessage::from_bytes(&self.incoming[start + 4..end])
+					.map_err(|error| error.to_string())?;
+			messages.push(message);
+			start = end;
+		}
+		self.incoming.drain(..start);
+		Ok(messages)
+	}
+}
+
+struct Peer {
+	connection: Connection,
+	address: SocketAddr,
+	// Whether it has asked for the world, and so is sent what changes.
+	joined: bool,
+}
+
+pub struct Host {
+	listener: TcpListener,
+	sync: SyncHost,
+	peers: Vec<Peer>,
+}
+
+impl Host {
+	// Listens at `address`, such as `0.0.0.0:7777`, for clients to join
+	// `world`.
+	pub fn bind(address: &str, world: &World) -> Result<Self, String> {
+		let listener =
+			TcpListener::bind(address).map_err(|error| error.to_string())?;
+		listener
+			.set_nonblocking(true)
+			.map_err(|error| error.to_string())?;
+		Ok(Self {
+			listener,
+			sync: SyncHost::new(world),
+			peers: Vec::new(),
+		})
+	}
+
+	pub fn local_address(&self) -> Option<SocketAddr> {
+		self.listener.local_addr().ok()
+	}
+
+	pub fn client_count(&self) -> usize {
+		self.peers.iter().filter(|peer| peer.joined).count()
+	}
+
+	// After every tick of the world, even with nobody there, so that the
+	// edits a client joining later is sent are only those since it joined.
+	// Clients that have gone or fail are let go of.
+	pub fn update(&mut self, world: &World) {
+		loop {
+			match self.listener.accept() {
+				Ok((stream, address)) => match Connection::new(stream) {
+					Ok(connection) => self.peers.push(Peer {
+						connection,
+						address,
+						joined: false,
+					}),
+					Err(error) => elog(format!("{}: {}", address, error)),
+				},
+				Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+				Err(error) => {
+					elog(format!("Could not accept a client: {}", error));
+					break;
+				}
+			}
+		}
+
+		let updates = self.sync.update(world);
+		let sync = &self.sync;
+		self.peers.retain_mut(|peer| {
+			if peer.joined {
+				for message in updates.iter() {
+					peer.connection.send(message);
+				}
+			}
+			let result = peer.connection.receive().and_then(|messages| {
+				for message in messages {
+					match message {
+						SyncMessage::Join => {
+							peer.connection.send(&sync.snapshot(world));
+							peer.joined = true;
+						}
+						SyncMessage::Resync(frame_id) => {
+							if let Some(frame) = sync.frame(world, frame_id) {
+								peer.connection.send(&frame);
+							}
+						}
+						_ => {}
+					}
+				}
+				peer.connection.flush()
+			});
+			match result {
+				Ok(()) => true,
+				Err(error) => {
+					log(format!("{} left: {}", peer.address, error));
+					false
+				}
+			}
+		});
+	}
+}
+
+// Someone else's world, as sent by its host.
+pub struct Client {
+	connection: Connection,
+	sync: SyncClient,
+}
+
+impl Client {
+	// Connects to a host at `address` and asks for its world, which
+	// replaces whatever world `update` is given once it comes.
+	pub fn connect(address: &str) -> Result<Self, String> {
+		let stream =
+			TcpStream::connect(address).map_err(|error| error.to_string())?;
+		let mut connection = Connection::new(stream)?;
+		connection.send(&SyncMessage::Join);
+		connection.flush()?;
+		Ok(Self {
+			connection,
+			sync: SyncClient::new(),
+		})
+	}
+
+	// Whether the host's world has come, with every frame up to date.
+	pub fn is_synced(&self) -> bool {
+		self.sync.is_synced()
+	}
+
+	// What has come from the host since the last call.
+	pub fn messages(&mut self) -> Result<Vec<SyncMessage>, String> {
+		self.connection.receive()
+	}
+
+	// Applies a message from the host, asking it for anything found to be
+	// missing.
+	pub fn apply(
+		&mut self,
+		world: &mut World,
+		message: SyncMessage,
+	) -> Result<(), String> {
+		let replies = self
+			.sync
+			.receive(world, message)
+			.map_err(|error| error.to_string())?;
+		for reply in &replies {
+			self.connection.send(reply);
+		}
+		self.connection.flush()
+	}
+
+	// In place of ticking the world. Fails once the host has gone.
+	pub fn update(&mut self, world: &mut World) -> Result<(), String> {
+		for message in self.messages()? {
+			self.apply(world, message)?;
+		}
+		self.connection.flush()
+	}
+}
+
+// Which side of a game over the network this is.
+pub enum Session {
+	Host(Host),
+	Client(Client),
+}
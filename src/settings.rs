@@ -0,0 +1,224 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::cvars::{cvars, CvarField};
+use crate::geometry::Scalar;
+use crate::prelude::*;
+use crate::window::PaletteKind;
+
+const SETTINGS_PATH: &str = "settings.cfg";
+// Best times are stored as `best_time.<level path> = <ticks>`.
+const BEST_TIME_PREFIX: &str = "best_time.";
+pub const MAX_RENDER_SCALE: u32 = 8;
+pub const MIN_UI_SCALE: Scalar = 0.5;
+pub const MAX_UI_SCALE: Scalar = 4.0;
+
+// User settings, stored as `key = value` lines. Unknown keys and bad values
+// are logged and otherwise ignored so an old settings file never prevents
+// the game from starting.
+pub struct Settings {
+	pub palette: PaletteKind,
+	// Window pixels per rendered pixel. Above one gives a blocky low
+	// resolution look and costs less to draw.
+	pub render_scale: u32,
+	// How much bigger the HUD, menus and text are drawn than at 700 pixels
+	// tall, or `None` to scale them with the viewport. Written as `auto`.
+	pub ui_scale: Option<Scalar>,
+	pub vsync: bool,
+	// Frames per second the frame limiter holds rendering to, or zero for
+	// no limit.
+	pub max_fps: u32,
+	// Seconds between autosaves, or zero to turn them off.
+	pub autosave_interval: u32,
+	// Tiles are drawn as raised blocks instead of flat outlines. Slower to
+	// draw.
+	pub three_d_tiles: bool,
+	// How far the cube is drawn blown up into a sphere, from nothing to all
+	// the way.
+	pub globe: Scalar,
+	// Events are polled again right after each frame is shown.
+	pub late_input: bool,
+	// The log is also written to a file, on the native backend. Read at
+	// startup only.
+	pub log_file: bool,
+	// Generated cubes are made on a thread of their own, on the native
+	// backend, rather than a frame at a time between drawing.
+	pub threaded_generation: bool,
+	// Fewest ticks each completed level has been finished in, by path.
+	pub best_times: BTreeMap<String, u64>,
+}
+
+impl Settings {
+	pub fn default() -> Self {
+		Self {
+			palette: PaletteKind::Default,
+			render_scale: 1,
+			ui_scale: None,
+			vsync: true,
+			max_fps: 0,
+			autosave_interval: 60,
+			three_d_tiles: false,
+			globe: 0.0,
+			late_input: false,
+			log_file: true,
+			threaded_generation: true,
+			best_times: BTreeMap::new(),
+		}
+	}
+
+	pub fn load() -> Self {
+		match std::fs::read_to_string(SETTINGS_PATH) {
+			Ok(source) => Self::parse(&source),
+			Err(_) => Self::default(),
+		}
+	}
+
+	pub fn parse(source: &str) -> Self {
+		let mut settings = Self::default();
+
+		for line in source.lines() {
+			let line = line.trim();
+			if line.is_empty() || line.starts_with('#') {
+				continue;
+			}
+
+			let mut parts = line.splitn(2, '=');
+			let key = parts.next().unwrap().trim();
+			let value = match parts.next() {
+				Some(value) => value.trim(),
+				None => {
+					elog(format!("Malformed settings line: {:?}", line));
+					continue;
+				}
+			};
+
+			if let Err(message) = settings.set(key, value) {
+				elog(message);
+			}
+		}
+
+		settings
+	}
+
+	pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+		let cvar = cvars().iter().find(|cvar| {
+			cvar.persistent && (cvar.name == key || cvar.old_name == Some(key))
+		});
+		if let Some(cvar) = cvar {
+			if let CvarField::Settings(_, set) = cvar.field {
+				set(self, cvar.parse(value)?);
+			}
+			return Ok(());
+		}
+
+		match key {
+			"palette" => {
+				self.palette = PaletteKind::from_name(value)
+					.ok_or_else(|| format!("Unknown palette {:?}", value))?;
+			}
+			"ui_scale" => {
+				self.ui_scale = parse_ui_scale(value)
+					.ok_or_else(|| format!("Bad UI scale {:?}", value))?;
+			}
+			"max_fps" => {
+				self.max_fps = value
+					.parse()
+					.map_err(|_| format!("Bad max fps {:?}", value))?;
+			}
+			"autosave_interval" => {
+				self.autosave_interval = value.parse().map_err(|_| {
+					format!("Bad autosave interval {:?}", value)
+				})?;
+			}
+			"late_input" => {
+				self.late_input = value
+					.parse()
+					.map_err(|_| format!("Bad late input value {:?}", value))?;
+			}
+			"log_file" => {
+				self.log_file = value
+					.parse()
+					.map_err(|_| format!("Bad log file value {:?}", value))?;
+			}
+			"threaded_generation" => {
+				self.threaded_generation = value.parse().map_err(|_| {
+					format!("Bad threaded generation value {:?}", value)
+				})?;
+			}
+			_ if key.starts_with(BEST_TIME_PREFIX) => {
+				let ticks = value
+					.parse()
+					.map_err(|_| format!("Bad best time {:?}", value))?;
+				let path = &key[BEST_TIME_PREFIX.len()..];
+				self.best_times.insert(path.to_string(), ticks);
+			}
+			_ => return Err(format!("Unknown setting {:?}", key)),
+		}
+		Ok(())
+	}
+
+	// Whether `ticks` beat the previous best for the level, which it then
+	// replaces.
+	pub fn record_time(&mut self, path: &str, ticks: u64) -> bool {
+		let best = self.best_times.entry(path.to_string()).or_insert(ticks);
+		if ticks > *best {
+			return false;
+		}
+		*best = ticks;
+		true
+	}
+
+	pub fn serialize(&self) -> String {
+		let mut out = format!(
+			"palette = {}\nui_scale = {}\nmax_fps = {}\n\
+			autosave_interval = {}\nlate_input = {}\nlog_file = {}\n\
+			threaded_generation = {}\n",
+			self.palette.name(),
+			ui_scale_name(self.ui_scale),
+			self.max_fps,
+			self.autosave_interval,
+			self.late_input,
+			self.log_file,
+			self.threaded_generation
+		);
+		for cvar in cvars().iter().filter(|cvar| cvar.persistent) {
+			if let CvarField::Settings(get, _) = cvar.field {
+				writeln!(out, "{} = {}", cvar.name, get(self)).unwrap();
+			}
+		}
+		for (path, ticks) in self.best_times.iter() {
+			writeln!(out, "{}{} = {}", BEST_TIME_PREFIX, path, ticks).unwrap();
+		}
+		out
+	}
+
+	pub fn save(&self) {
+		// Headless windows start from the default settings, and whatever
+		// is changed in them is no business of the player's.
+		if cfg!(feature = "headless") {
+			return;
+		}
+		if let Err(error) = std::fs::write(SETTINGS_PATH, self.serialize()) {
+			elog(format!("Could not save settings: {}", error));
+		}
+	}
+}
+
+// `auto`, or a scale within the allowed range. `None` if it is neither.
+pub fn parse_ui_scale(value: &str) -> Option<Option<Scalar>> {
+	if value == "auto" {
+		return Some(None);
+	}
+	value
+		.parse()
+		.ok()
+		.filter(|&scale| (MIN_UI_SCALE..=MAX_UI_SCALE).contains(&scale))
+		.map(Some)
+}
+
+pub fn ui_scale_name(scale: Option<Scalar>) -> String {
+	match scale {
+		Some(scale) => scale.to_string(),
+		None => String::from("auto"),
+	}
+}
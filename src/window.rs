@@ -1,48 +1,293 @@
-pub mod backend;
+pub(crate) mod backend;
+mod background;
+mod bindings;
+mod blocks;
+mod camera_script;
+mod cliffs;
+mod clip;
+mod console;
+mod demo;
+mod font;
+mod generating;
+mod gif;
+mod globe;
+#[cfg(feature = "headless")]
+pub mod golden;
+mod grid;
+mod hud;
+mod interpolation;
+mod menu;
+mod net_view;
+mod notifications;
+mod painting;
+mod palette;
 mod projection;
-pub mod types;
-
-use std::collections::HashSet;
-
+mod prompt;
+mod recorder;
+mod seam;
+mod shadows;
+mod sprites;
+mod stress;
+mod thumbnails;
+mod tick_rate;
+mod tile_geometry;
+mod time_trial;
+mod timeline;
+mod types;
+mod ui_transform;
+mod watchdog;
+mod world_map;
+
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+pub(crate) use backend::begin_loop;
+pub use backend::external_exports;
 use backend::Backend;
-pub use backend::{begin_loop, external_exports};
-
-use super::world::{Direction, Entity, Frame, Tile, World, FRAME_WIDTH};
+#[cfg(feature = "headless")]
+pub use backend::{DrawnLine, PlayedSound};
+use background::BackgroundPatterns;
+use bindings::Bindings;
+use camera_script::CameraScripts;
+
+use super::world::{
+	compose_rotation, Angle, Direction, EdgeBehavior, Entity, EntityId,
+	EntityKind, Frame, FrameId, Interaction, OpenEdges, StatusKind, Tile,
+	TopologyIssue, World, WorldEvent, WorldEventKind, WorldPosition,
+	FRAME_WIDTH, TILE_SIZE,
+};
 use super::GameState;
-use crate::geometry::{self, vec3, Matrix4x4, Vector3, PI};
-
-pub use types::*;
-
+use crate::autosave::{self, Autosave};
+use crate::campaign::{CompletionChoice, MenuChoice, SaveBrowser};
+use crate::geometry::{vec3, Matrix4x4, Scalar, Vector3, PI};
+use crate::prelude::*;
+use crate::settings::{ui_scale_name, Settings};
+
+pub use palette::{Palette, PaletteKind, Role};
+pub use types::BackendError;
+pub(crate) use types::*;
+
+use cliffs::{cliff_edges, fall_off_shapes};
+use clip::{backend_point, ClipRect, CLIP_MARGIN};
+use console::Console;
+use globe::Globe;
+use grid::{GridLines, GridMode};
+use hud::Hud;
+use interpolation::Interpolation;
+pub(crate) use menu::{MenuInput, MenuWidget};
+use notifications::{Notifications, Severity, NOTIFY_TICKS};
+use painting::Stroke;
 use projection::{Camera, CameraProjector};
+use recorder::Recorder;
+use stress::TickTimer;
+use thumbnails::FrameStrip;
+use tick_rate::TickRate;
+use tile_geometry::TileGeometry;
+use watchdog::{Phase, Watchdog, SLOW_FRAME_SECONDS};
+use world_map::WorldMap;
+
+// Cube-space coordinates are magnified by this before projection. Should
+// eventually be removed in favour of moving the camera closer.
+const VIEW_SCALE: Scalar = 100.0;
+// Faces whose transformed area is below this are treated as degenerate.
+const MIN_FACE_AREA: Scalar = 1e-6;
+// Screen pixels per font pixel for UI text.
+const TEXT_SCALE: usize = 2;
+// Gap in screen pixels between the text box and the viewport edges, and
+// between the box outline and its text.
+const TEXT_BOX_MARGIN: Scalar = 12.0;
+const TEXT_BOX_PADDING: Scalar = 10.0;
+// After a long stall, such as the window being dragged, the world catches up
+// by at most this many steps and the rest of the time is dropped.
+const MAX_TICKS_PER_FRAME: usize = 8;
+// Fraction of the remaining difference the displayed frame rate moves by
+// each frame.
+const FRAME_RATE_SMOOTHING: Scalar = 0.05;
+// Length of each dash, and of each gap, along an edge with no link.
+const BORDER_DASH_LENGTH: Scalar = TILE_SIZE / 2.0;
+// Length of each stroke of the mark on a linked edge.
+const LINK_MARK_LENGTH: Scalar = TILE_SIZE / 2.0;
+// How far towards white the focus frame's border is drawn.
+const FOCUS_BORDER_BRIGHTEN: Scalar = 0.5;
+// How long a tile stays highlighted after an edit to it is made or refused.
+const EDIT_FLASH_SECONDS: f64 = 0.3;
+// World ticks invulnerable entities spend shown, then hidden, as they blink.
+const INVULNERABLE_BLINK_TICKS: u64 = 4;
+// How fast the cube turns while there is nothing to follow, in radians a
+// second, and how far it tips over at most as it does.
+const ORBIT_SPEED: Scalar = 0.3;
+const ORBIT_TILT: Scalar = PI / 3.0;
+
+// The rotation the cube is drawn with, leaning the view frame away from
+// the camera the further from its middle the camera follows, as x, y on
+// it. Without anywhere to follow the cube slowly turns to show every face.
+fn view_rotation(surface_position: Option<(Scalar, Scalar)>) -> Matrix4x4 {
+	let (view_x, view_y) = match surface_position {
+		Some(position) => position,
+		None => {
+			let angle = backend::time::now() as Scalar * ORBIT_SPEED;
+			let tilt = angle.sin() * ORBIT_TILT;
+			return Matrix4x4::rotation(tilt, angle, 0.0);
+		}
+	};
+
+	let focus_x = view_x.abs().powf(1.5).copysign(view_x);
+	let focus_y = view_y.abs().powf(1.5).copysign(view_y);
+
+	// let r = vec3(
+	// 	focus_y * (PI / 4.0),
+	// 	focus_x * -(PI / 4.0),
+	// 	0.0,
+	// );
+
+	// let view_rotate_y = focus_x * (-PI / 4.0);
+	// let view_rotate_x = vec3(focus_x, focus_y, 1.0).normalized().y;
+	// let view_rotate_x = view_rotate_x * (PI / 3.0);
+
+	// Convert Cartesian coordinates on the cube into spherical
+	// coordinates.
+	let view_rotate_y = focus_x.atan();
+	let view_rotate_x =
+		(PI / 4.0 * 2.0) - ((focus_x.powi(2) + 1.0).sqrt()).atan2(focus_y);
+	// let view_rotate_x = 0.0;
+
+	let r = vec3(view_rotate_x, -view_rotate_y, 0.0);
+	//let focus_vec = vec3(focus_x, focus_y, 1.0).normalized();
+	//let spin = 0.0;
+	//println!("{}", );
+	//let r = r.rotated(0.0, 0.0, 0.5);
+	//let r =
+
+	let view_rotation = Matrix4x4::rotation(r.x, r.y, r.z);
+
+	let twist = 0.0;
+
+	//let twist = (self.tick as Scalar) / 300.0;
+	let p = vec3(focus_x, focus_y, 1.0).normalized();
+	view_rotation.rotated_about_axis(p, twist)
+}
+
+// The normal of a rect wound so that it points out of its visible side,
+// and the rect's center.
+fn rect_facing(
+	top_left: Vector3,
+	top_right: Vector3,
+	bottom_right: Vector3,
+	bottom_left: Vector3,
+) -> (Vector3, Vector3) {
+	let (p1, p2, p3, p4) = (top_left, top_right, bottom_right, bottom_left);
+	let normal = (p2 - p1).cross(p3 - p1);
+	(normal, (p1 + p2 + p3 + p4) / 4.0)
+}
 
-const DEBUG_0: usize = 60;
-const THREE_D_TILES: bool = false;
+// Whether the camera sees the side of a face that its `normal` points out
+// of. The test is done in world space rather than on projected points,
+// since the perspective divide flips the winding of geometry behind the
+// camera.
+fn is_rect_visible(
+	normal: Vector3,
+	center: Vector3,
+	camera_position: Vector3,
+) -> bool {
+	if normal.len() < MIN_FACE_AREA {
+		return false;
+	}
+	normal.dot(center - camera_position) < 0.0
+}
 
 pub struct Window {
 	backend: Backend,
 	input_state: InputState,
-	pub should_exit: bool,
+	bindings: Bindings,
+	settings: Settings,
+	palette: Palette,
+	hud: Hud,
+	// What the player had in reach to interact with at the last tick, which
+	// is prompted for above it.
+	prompt: Option<Interaction>,
+	notifications: Notifications,
+	console: Console,
+	recorder: Recorder,
+	autosave: Autosave,
+	// Present while stress testing.
+	tick_timer: Option<TickTimer>,
+	watchdog: Watchdog,
+	grid_mode: GridMode,
+	grid_lines: Rc<GridLines>,
+	background_patterns: Rc<BackgroundPatterns>,
+	pick_view: Option<PickView>,
+	// Where the focus entity was last drawn, for the camera to stay put
+	// while there isn't one.
+	last_view: Option<WorldPosition>,
+	frame_strip: FrameStrip,
+	world_map: WorldMap,
+	// Each frame's tile outlines, kept until its tiles change.
+	tile_geometry: HashMap<FrameId, Rc<TileGeometry>>,
+	hovered_tile: Option<(FrameId, usize, usize)>,
+	selected_tile: Tile,
+	// Placed by left clicks instead of the selected tile while there is
+	// one.
+	selected_entity: Option<EntityKind>,
+	// Present while a mouse button is held down to edit tiles.
+	stroke: Option<Stroke>,
+	// Whether the edit timeline is being dragged along.
+	timeline_drag: bool,
+	// The tile of the last edit, whether it was made or refused, and the
+	// time its highlight ends, from `backend::time::now`.
+	edited_tile: Option<((FrameId, usize, usize), Role, f64)>,
+	pub(crate) should_exit: bool,
 	tick: usize,
-	debug: (isize, isize),
+	// Time of the previous frame's tick, from `backend::time::now`.
+	last_frame_time: Option<f64>,
+	// Elapsed time not yet covered by world ticks, in seconds.
+	tick_accumulator: f64,
+	tick_rate: TickRate,
+	// Where entities were before the last tick, to draw them in between.
+	interpolation: Interpolation,
+	// Camera moves that pause the world while they play.
+	camera_scripts: CameraScripts,
+	// Smoothed frames per second, for the debug overlay.
+	frame_rate: Scalar,
+	// Toggled with F3. Shows frame ids and link labels on each face.
+	debug_mode: bool,
+}
+
+// What was on screen in the last rendered frame, for mapping pointer
+// positions back onto the cube.
+#[derive(Copy, Clone)]
+struct PickView {
+	projector: CameraProjector,
+	view_rotation: Matrix4x4,
+	focus: FrameId,
 }
 
-pub struct InputState {
+pub(crate) struct InputState {
 	// Keyboard keys that started being pressed this frame
 	pub keys_pressed: HashSet<Keycode>,
 	// Keyboard keys that have not yet been released, regardless of when
 	// they started being pressed.
 	pub keys_held: HashSet<Keycode>,
+	pub mouse_buttons_pressed: HashSet<MouseButton>,
+	pub mouse_buttons_held: HashSet<MouseButton>,
+	// Last known pointer position in viewport pixels.
+	pub mouse_position: Option<(Scalar, Scalar)>,
+	// Ticks since the player last did anything at all.
+	pub idle_ticks: u64,
 }
 
 impl InputState {
-	pub fn new() -> Self {
+	pub(crate) fn new() -> Self {
 		Self {
 			keys_pressed: HashSet::new(),
 			keys_held: HashSet::new(),
+			mouse_buttons_pressed: HashSet::new(),
+			mouse_buttons_held: HashSet::new(),
+			mouse_position: None,
+			idle_ticks: 0,
 		}
 	}
 
-	pub fn key_down_event(&mut self, keycode: Keycode) {
+	pub(crate) fn key_down_event(&mut self, keycode: Keycode) {
 		// SDL triggers this event on a key long-press, so handle that case.
 		if self.keys_held.contains(&keycode) == false {
 			self.keys_held.insert(keycode);
@@ -50,347 +295,1770 @@ impl InputState {
 		}
 	}
 
-	pub fn key_up_event(&mut self, keycode: Keycode) {
+	pub(crate) fn key_up_event(&mut self, keycode: Keycode) {
 		self.keys_held.remove(&keycode);
 	}
 
+	// Brings `keys_held` in line with `held`. A key that was missed being
+	// pressed counts as held from now on, without counting as pressed, as
+	// whatever it should have done is long past.
+	pub(crate) fn sync_keys(&mut self, held: &[Keycode]) {
+		self.keys_held.retain(|keycode| held.contains(keycode));
+		self.keys_held.extend(held.iter().copied());
+	}
+
+	pub(crate) fn mouse_down_event(&mut self, button: MouseButton) {
+		if self.mouse_buttons_held.contains(&button) == false {
+			self.mouse_buttons_held.insert(button);
+			self.mouse_buttons_pressed.insert(button);
+		}
+	}
+
+	pub(crate) fn mouse_up_event(&mut self, button: MouseButton) {
+		self.mouse_buttons_held.remove(&button);
+	}
+
 	// Run at the end of every frame to ensure keys in `keys_pressed`
 	// no longer count as pressed in the next frame.
-	pub fn clear_frame(&mut self) {
+	// Forgets every held key, for when input stops going to the game.
+	pub(crate) fn release_keys(&mut self) {
+		self.keys_pressed.clear();
+		self.keys_held.clear();
+	}
+
+	pub(crate) fn clear_frame(&mut self) {
 		self.keys_pressed.clear();
+		self.mouse_buttons_pressed.clear();
+		self.idle_ticks += 1;
 	}
 }
 
 impl Window {
-	pub fn new() -> Self {
-		Self {
-			backend: Backend::new(),
+	pub fn new() -> Result<Self, BackendError> {
+		let mut window = Self::with_settings(Settings::load())?;
+		if autosave::recoverable_autosave().is_some() {
+			window.console.open = true;
+			window
+				.console
+				.print("Found an autosave newer than the last save.");
+			window.console.print("Type restore to load it.");
+		}
+		Ok(window)
+	}
+
+	// With the default settings whatever has been saved, and no console
+	// open, so that the same world is always drawn the same.
+	#[cfg(feature = "headless")]
+	pub fn headless() -> Result<Self, BackendError> {
+		Self::with_settings(Settings::default())
+	}
+
+	// Every line drawn since the canvas was last cleared.
+	#[cfg(feature = "headless")]
+	pub fn drawn_lines(&self) -> &[DrawnLine] {
+		self.backend.lines()
+	}
+
+	// Every sound played since the window was opened.
+	#[cfg(feature = "headless")]
+	pub fn played_sounds(&self) -> &[PlayedSound] {
+		self.backend.sounds()
+	}
+
+	fn with_settings(settings: Settings) -> Result<Self, BackendError> {
+		let palette = Palette::new(settings.palette);
+		let config = WindowConfig {
+			vsync: settings.vsync,
+			..WindowConfig::default()
+		};
+		let mut backend = Backend::new(&config)?;
+		backend.set_render_scale(settings.render_scale);
+		let console = Console::new();
+		let autosave = Autosave::new(settings.autosave_interval);
+
+		Ok(Self {
+			backend,
 			input_state: InputState::new(),
+			bindings: Bindings::default(),
+			settings,
+			palette,
+			hud: Hud::new(),
+			prompt: None,
+			notifications: Notifications::new(),
+			console,
+			recorder: Recorder::new(),
+			autosave,
+			tick_timer: None,
+			watchdog: Watchdog::new(SLOW_FRAME_SECONDS),
+			grid_mode: GridMode::Off,
+			grid_lines: Rc::new(GridLines::new()),
+			background_patterns: Rc::new(BackgroundPatterns::new()),
+			pick_view: None,
+			last_view: None,
+			frame_strip: FrameStrip::new(),
+			tile_geometry: HashMap::new(),
+			world_map: WorldMap::new(),
+			hovered_tile: None,
+			selected_tile: Tile::Solid,
+			selected_entity: None,
+			stroke: None,
+			timeline_drag: false,
+			edited_tile: None,
 			should_exit: false,
 			tick: 0,
-			debug: (0, 0),
-		}
+			last_frame_time: None,
+			tick_accumulator: 0.0,
+			tick_rate: TickRate::new(),
+			interpolation: Interpolation::new(),
+			camera_scripts: CameraScripts::new(),
+			frame_rate: 0.0,
+			debug_mode: false,
+		})
 	}
 
 	pub fn tick(&mut self, game_state: &mut GameState) {
+		self.watchdog.begin_frame();
+		self.poll_events(game_state);
+		if game_state.update_generation() {
+			self.input_state.release_keys();
+			self.autosave.mark_saved(&game_state.world);
+		}
+		self.update_demo(game_state);
+		// The pointer is the world map's alone while it is open.
+		if self.world_map.open == false
+			&& self.pick_thumbnail(&game_state.world) == false
+			&& self.scrub_timeline(game_state) == false
+		{
+			self.edit_picked_tile(game_state);
+		}
+		self.watchdog.end_phase(Phase::Events);
+
+		// The world runs at a fixed rate however often frames are drawn.
+		let now = backend::time::now();
+		let elapsed = self.last_frame_time.map_or(0.0, |last| now - last);
+		self.last_frame_time = Some(now);
+		if elapsed > 0.0 {
+			let rate = 1.0 / elapsed as Scalar;
+			self.frame_rate += (rate - self.frame_rate) * FRAME_RATE_SMOOTHING;
+		}
+		// No time passes in the world while the map is open, which pans
+		// with it instead.
+		let elapsed = if self.world_map.open {
+			self.pan_world_map(elapsed);
+			0.0
+		} else {
+			elapsed
+		};
+
+		// Falls back to a lower rate when steps can't keep up.
+		self.tick_rate.apply(&mut game_state.world);
+		let step_duration = self.tick_rate.step_duration();
+		let max_accumulated = step_duration * MAX_TICKS_PER_FRAME as f64;
+		let pending = self.tick_accumulator + elapsed;
+		let steps_due = (pending / step_duration) as usize;
+		self.tick_rate.record_frame(steps_due);
+		self.tick_accumulator = pending.min(max_accumulated);
+		while self.tick_accumulator >= step_duration {
+			self.tick_accumulator -= step_duration;
+			// As late as possible, so that anything that came in while the
+			// last step ran still makes this one.
+			self.poll_events(game_state);
+			self.interpolation.record(&game_state.world);
+			// The world waits while a camera script has the camera.
+			if self.camera_scripts.step(&game_state.world) {
+				self.input_state.clear_frame();
+				continue;
+			}
+			let tick_start = backend::time::now();
+			game_state.tick(&self.bindings.actions(&self.input_state));
+			if let Some(timer) = self.tick_timer.as_mut() {
+				timer.record(backend::time::now() - tick_start);
+			}
+			self.watchdog.record_tick(&game_state.world);
+			self.interpolation.update(&game_state.world);
+			self.record_completion(game_state);
+			self.hud.update(&game_state.world);
+			self.update_prompt(&game_state.world);
+			self.script_shown_targets(&game_state.world);
+			self.notify_world_events(&game_state.world);
+			self.play_world_sounds(&game_state.world);
+			self.notifications.update();
+			// Presses are kept until a tick has seen them, which may be
+			// several frames later when drawing faster than the tick rate.
+			self.input_state.clear_frame();
+		}
+		self.watchdog.end_phase(Phase::Ticks);
+		let playing_demo = game_state.demo.is_some();
+		if playing_demo == false && self.autosave.update(&game_state.world) {
+			self.notifications.notify(
+				"Autosaved",
+				NOTIFY_TICKS,
+				Severity::Info,
+			);
+		}
+		self.watchdog.end_phase(Phase::Autosave);
+		self.tick += 1;
+	}
+
+	// Handles every event that has come in since the last call. Key presses
+	// are kept for the next tick to see, so this can be called as often as
+	// is useful, such as again straight after presenting a frame to have
+	// them in hand before the next one starts.
+	pub(crate) fn poll_events(&mut self, game_state: &mut GameState) {
 		while let Some(event) = self.backend.poll_event() {
 			use WindowEvent::*;
+			// Dropping a level loads it whatever else is going on.
+			match event {
+				KeysHeld(keys) => {
+					self.input_state.sync_keys(&keys);
+					continue;
+				}
+				FileDropped(path) => {
+					let path = path.to_string_lossy().into_owned();
+					let loaded = GameState::load_level(&path);
+					self.load_dropped_level(game_state, loaded);
+					continue;
+				}
+				FileContentsDropped(bytes) => {
+					let loaded = String::from_utf8(bytes)
+						.map_err(|_| String::from("Dropped file is not text"))
+						.and_then(|source| {
+							GameState::from_level_source(&source)
+						});
+					self.load_dropped_level(game_state, loaded);
+					continue;
+				}
+				_ => {}
+			}
+			if self.demo_event(game_state, &event) {
+				continue;
+			}
+			if self.console.open {
+				self.console_event(game_state, event);
+				continue;
+			}
+			if self.camera_script_event(&event) {
+				continue;
+			}
+			if self.world_map_event(game_state, &event) {
+				continue;
+			}
+			if self.menu_event(game_state, &event) {
+				continue;
+			}
 			match event {
-				Quit { .. } => self.should_exit = true,
-				KeyDown(Keycode::Escape) => self.should_exit = true,
+				Quit | KeyDown(Keycode::Escape) => {
+					self.quit(&game_state.world)
+				}
+				KeyDown(Keycode::Backquote) => {
+					self.console.toggle();
+					self.input_state.release_keys();
+				}
+				// Shift+G shows the grid on every visible face rather than
+				// just the focus face.
+				KeyDown(Keycode::G) => {
+					let held = &self.input_state.keys_held;
+					let mode = if held.contains(&Keycode::Shift) {
+						GridMode::All
+					} else {
+						GridMode::Focus
+					};
+					self.grid_mode = if self.grid_mode == mode {
+						GridMode::Off
+					} else {
+						mode
+					};
+				}
+				KeyDown(Keycode::P) => {
+					self.set_palette(self.palette.kind.next());
+				}
+				KeyDown(Keycode::H) => self.hud.toggle(),
+				KeyDown(Keycode::F) => self.frame_strip.toggle(),
+				KeyDown(Keycode::M) => self.toggle_world_map(game_state),
+				KeyDown(Keycode::T) => {
+					self.set_three_d_tiles(!self.settings.three_d_tiles)
+				}
+				KeyDown(Keycode::F3) => self.debug_mode = !self.debug_mode,
+				KeyDown(Keycode::F9) => self.toggle_recording(),
+				KeyDown(Keycode::PageUp) => {
+					self.step_timeline(game_state, true)
+				}
+				KeyDown(Keycode::PageDown) => {
+					self.step_timeline(game_state, false)
+				}
 				KeyDown(keycode) => self.input_state.key_down_event(keycode),
 				KeyUp(keycode) => self.input_state.key_up_event(keycode),
+				MouseMove(x, y) => {
+					self.input_state.mouse_position =
+						Some((x as Scalar, y as Scalar))
+				}
+				MouseDown(button) => self.input_state.mouse_down_event(button),
+				MouseUp(button) => self.input_state.mouse_up_event(button),
 				_ => {}
 			}
 		}
+	}
 
-		game_state.tick(&self.input_state);
-		self.input_state.clear_frame();
-		self.tick += 1;
+	// Ends the session with a summary of how it went.
+	fn quit(&mut self, world: &World) {
+		log("Session summary:");
+		for line in world.stats().lines() {
+			log(format!("  {}", line));
+		}
+		self.should_exit = true;
+	}
+
+	// Gives the event to the level menu, completion screen or cube being
+	// generated, if any is showing. Whether it was used up by it.
+	fn menu_event(
+		&mut self,
+		game_state: &mut GameState,
+		event: &WindowEvent,
+	) -> bool {
+		if game_state.generating.is_some() {
+			use WindowEvent::*;
+			return match *event {
+				KeyDown(Keycode::Escape)
+				| GamepadDown(GamepadButton::Cancel) => {
+					game_state.cancel_generation();
+					true
+				}
+				_ => false,
+			};
+		}
+		// Menus are hit tested where they were laid out, in UI coordinates.
+		let pointer = match *event {
+			WindowEvent::MouseMove(x, y) => Some((x as Scalar, y as Scalar)),
+			_ => self.input_state.mouse_position,
+		};
+		let pointer = pointer.map(|point| self.ui().to_reference(point));
+		if let Some(menu) = game_state.menu.as_mut() {
+			if let Some(saves) = menu.saves.as_mut() {
+				use WindowEvent::*;
+				match *event {
+					KeyDown(Keycode::Escape)
+					| KeyDown(Keycode::Backspace)
+					| GamepadDown(GamepadButton::Cancel) => {
+						menu.saves = None;
+						return true;
+					}
+					KeyDown(Keycode::Delete) => {
+						let selected = saves.widget.selected;
+						if let Err(error) = saves.delete(selected) {
+							let message =
+								format!("Could not delete save: {}", error);
+							self.notifications.notify(
+								message,
+								NOTIFY_TICKS,
+								Severity::Error,
+							);
+						}
+						return true;
+					}
+					_ => {}
+				}
+				return match saves.widget.event(event, pointer) {
+					MenuInput::Ignored => false,
+					MenuInput::Used => true,
+					MenuInput::Activated(index) => {
+						if let Some(name) = saves.save(index) {
+							let name = name.to_string();
+							self.load_save(game_state, &name);
+						}
+						true
+					}
+				};
+			}
+			return match menu.widget.event(event, pointer) {
+				MenuInput::Ignored => false,
+				MenuInput::Used => true,
+				MenuInput::Activated(index) => {
+					match menu.choice(index) {
+						MenuChoice::Level(path) => {
+							self.start_level(game_state, Some(path))
+						}
+						MenuChoice::RandomCube => {
+							self.start_level(game_state, None)
+						}
+						MenuChoice::LoadGame => {
+							menu.saves = Some(SaveBrowser::new())
+						}
+					}
+					true
+				}
+			};
+		}
+		if let Some(completion) = game_state.completion.as_mut() {
+			return match completion.widget.event(event, pointer) {
+				MenuInput::Ignored => false,
+				MenuInput::Used => true,
+				MenuInput::Activated(index) => {
+					match completion.choice(index) {
+						CompletionChoice::NextLevel => {
+							self.advance_level(game_state)
+						}
+						CompletionChoice::Replay => {
+							let path = game_state.level_path.clone();
+							self.start_level(game_state, path.as_deref());
+						}
+						CompletionChoice::Menu => {
+							self.return_to_menu(game_state)
+						}
+					}
+					true
+				}
+			};
+		}
+		false
+	}
+
+	// Keeps the time of a level completed this tick if it is the best yet.
+	fn record_completion(&mut self, game_state: &mut GameState) {
+		let completed = game_state
+			.world
+			.events()
+			.iter()
+			.any(|event| event.kind() == WorldEventKind::LevelCompleted);
+		if completed == false {
+			return;
+		}
+		let (path, completion) =
+			match (&game_state.level_path, game_state.completion.as_mut()) {
+				(Some(path), Some(completion)) => (path, completion),
+				_ => return,
+			};
+		completion.new_best = self.settings.record_time(path, completion.ticks);
+		if completion.new_best {
+			self.settings.save();
+		}
+		// Also kept when there was no ghost, such as after the level was
+		// changed, so there is always something to race.
+		if let Some(time_trial) = game_state.time_trial.as_mut() {
+			if completion.new_best || time_trial.ghost.is_none() {
+				time_trial.keep_run(completion.ticks);
+			}
+		}
+	}
+
+	// Replaces the game with a level dropped onto the window, or keeps it if
+	// the level couldn't be loaded. Either way, a notification says which.
+	fn load_dropped_level(
+		&mut self,
+		game_state: &mut GameState,
+		loaded: Result<GameState, String>,
+	) {
+		match loaded {
+			Ok(state) => {
+				*game_state = state;
+				self.input_state.release_keys();
+				self.autosave.mark_saved(&game_state.world);
+				let name = game_state
+					.world
+					.level
+					.name
+					.clone()
+					.or_else(|| game_state.level_path.clone())
+					.unwrap_or_else(|| String::from("untitled"));
+				let message = format!("Loaded level: {}", name);
+				log(message.as_str());
+				self.notifications.notify(
+					message,
+					NOTIFY_TICKS,
+					Severity::Info,
+				);
+			}
+			Err(error) => {
+				elog(error.as_str());
+				self.notifications
+					.notify(error, NOTIFY_TICKS, Severity::Error);
+			}
+		}
+	}
+
+	fn play_world_sounds(&mut self, world: &World) {
+		for (sound, placement) in world.tick_sounds() {
+			// In `f32`, as the backend takes points.
+			let (gain, pan) = backend_point((placement.gain, placement.pan));
+			self.backend.play_sound(sound.id(), gain, pan);
+		}
+	}
+
+	// Tells the player about things that happened to them in the last tick
+	// that they might otherwise miss.
+	fn notify_world_events(&mut self, world: &World) {
+		for event in world.events() {
+			match *event {
+				WorldEvent::EntityOutOfBounds { entity, .. }
+					if Some(entity) == world.focus_entity =>
+				{
+					self.notifications.notify(
+						"Fell out of the world, back to the start",
+						NOTIFY_TICKS,
+						Severity::Warning,
+					)
+				}
+				WorldEvent::EntityDied { entity }
+					if Some(entity) == world.focus_entity =>
+				{
+					self.notifications.notify(
+						"Died, back to the last checkpoint",
+						NOTIFY_TICKS,
+						Severity::Warning,
+					)
+				}
+				WorldEvent::CheckpointReached { entity }
+					if Some(entity) == world.focus_entity =>
+				{
+					self.notifications.notify(
+						"Checkpoint reached",
+						NOTIFY_TICKS,
+						Severity::Info,
+					)
+				}
+				WorldEvent::TileEdited {
+					entity,
+					frame,
+					x,
+					y,
+					..
+				} if Some(entity) == world.focus_entity => {
+					self.flash_edited_tile((frame, x, y), Role::Accepted)
+				}
+				WorldEvent::TileEditFailed {
+					entity,
+					target: Some(target),
+					..
+				} if Some(entity) == world.focus_entity => {
+					self.flash_edited_tile(target, Role::Rejected)
+				}
+				_ => {}
+			}
+		}
+	}
+
+	// Briefly highlights a tile in `role`, over the pointer's highlight.
+	fn flash_edited_tile(&mut self, tile: (FrameId, usize, usize), role: Role) {
+		let until = backend::time::now() + EDIT_FLASH_SECONDS;
+		self.edited_tile = Some((tile, role, until));
+	}
+
+	// Moves on from a completed level to the one it names next, or back to
+	// the menu at the end of the campaign.
+	fn advance_level(&mut self, game_state: &mut GameState) {
+		let next = game_state.world.level.next_level.clone();
+		match next {
+			Some(path) => self.start_level(game_state, Some(&path)),
+			None => self.return_to_menu(game_state),
+		}
+	}
+
+	fn return_to_menu(&mut self, game_state: &mut GameState) {
+		*game_state = GameState::with_menu();
+		self.input_state.release_keys();
+		self.input_state.idle_ticks = 0;
+		self.autosave.mark_saved(&game_state.world);
+	}
+
+	// Replaces the game with the level at `path`, or with a generated world
+	// once it has been made. Returns to the menu if the level can't be
+	// loaded.
+	fn start_level(&mut self, game_state: &mut GameState, path: Option<&str>) {
+		let path = match path {
+			Some(path) => path,
+			None => {
+				game_state.start_generating(self.settings.threaded_generation);
+				return;
+			}
+		};
+		*game_state = match GameState::load_level(path) {
+			Ok(state) => state,
+			Err(error) => {
+				self.console.open = true;
+				self.console.print(error);
+				GameState::with_menu()
+			}
+		};
+		self.input_state.release_keys();
+		self.autosave.mark_saved(&game_state.world);
+	}
+
+	// Carries on from a save chosen in the menu, staying in the menu if it
+	// turns out not to load after all.
+	fn load_save(&mut self, game_state: &mut GameState, name: &str) {
+		let loaded = match autosave::read_save(name) {
+			Some((bytes, _)) => {
+				autosave::load(&bytes).map_err(|error| error.to_string())
+			}
+			None => Err(String::from("not a complete save")),
+		};
+		match loaded {
+			Ok(world) => {
+				*game_state = GameState::from_world(world);
+				self.input_state.release_keys();
+				self.autosave.mark_saved(&game_state.world);
+			}
+			Err(error) => {
+				let message = format!("Could not load {}: {}", name, error);
+				elog(message.as_str());
+				self.notifications.notify(
+					message,
+					NOTIFY_TICKS,
+					Severity::Error,
+				);
+			}
+		}
+	}
+
+	// The shortest time a frame may take in seconds, if rendering is being
+	// held below the display's pace.
+	pub(crate) fn frame_time_limit(&self) -> Option<f64> {
+		match self.settings.max_fps {
+			0 => None,
+			fps => Some(1.0 / fps as f64),
+		}
+	}
+
+	// Whether to poll events again straight after each frame is presented,
+	// which catches presses made while waiting on vsync before the next
+	// frame starts its steps. Only the native backend does this.
+	pub(crate) fn late_input(&self) -> bool {
+		self.settings.late_input
+	}
+
+	// Where to mirror the log to, if anywhere, going by the settings.
+	pub(crate) fn log_file_path(&self) -> Option<&'static str> {
+		if self.settings.log_file {
+			Some(crate::logging::DEFAULT_LOG_PATH)
+		} else {
+			None
+		}
+	}
+
+	// How frames are being paced, for the frame rate overlay.
+	fn frame_pacing_name(&self) -> String {
+		match (self.settings.vsync, self.settings.max_fps) {
+			(true, 0) => String::from("vsync"),
+			(true, fps) => format!("vsync, capped at {}", fps),
+			(false, 0) => String::from("uncapped"),
+			(false, fps) => format!("capped at {}", fps),
+		}
+	}
+
+	// While the console is open, typing goes to it instead of the game.
+	fn console_event(
+		&mut self,
+		game_state: &mut GameState,
+		event: WindowEvent,
+	) {
+		use WindowEvent::*;
+		match event {
+			Quit => self.quit(&game_state.world),
+			KeyDown(Keycode::Backquote) | KeyDown(Keycode::Escape) => {
+				self.console.toggle()
+			}
+			KeyDown(Keycode::Backspace) => self.console.backspace(),
+			KeyDown(Keycode::Tab) => self.console.complete(),
+			KeyDown(Keycode::Return) => {
+				if let Some(line) = self.console.submit() {
+					self.run_console_command(game_state, &line);
+				}
+			}
+			TextInput(text) => self.console.text_input(&text),
+			MouseMove(x, y) => {
+				self.input_state.mouse_position =
+					Some((x as Scalar, y as Scalar))
+			}
+			_ => {}
+		}
+	}
+
+	// The tile under a screen position in the last rendered frame, found by
+	// intersecting the pointer's ray with each face facing the camera.
+	pub(crate) fn pick_tile(
+		&self,
+		world: &World,
+		screen_position: (Scalar, Scalar),
+	) -> Option<(FrameId, usize, usize)> {
+		let view = self.pick_view?;
+		let (screen_x, screen_y) = screen_position;
+		let ray = view.projector.unproject(screen_x, screen_y)?;
+
+		let mut nearest: Option<(Scalar, FrameId, Scalar, Scalar)> = None;
+		for (frame_id, surface_transform) in
+			world.surface_transforms(view.focus)
+		{
+			let to_local =
+				match (view.view_rotation * surface_transform).inverse() {
+					Some(matrix) => matrix,
+					None => continue,
+				};
+			let origin = ray.origin * to_local;
+			let direction = ray.at(1.0) * to_local - origin;
+
+			let (distance, x, y) =
+				match view.projector.globe().hit_frame(origin, direction) {
+					Some(hit) => hit,
+					None => continue,
+				};
+			let inside = (-1.0..1.0).contains(&x) && (-1.0..1.0).contains(&y);
+			if distance < 0.0 || inside == false {
+				continue;
+			}
+
+			if nearest.is_none_or(|(nearest_distance, ..)| {
+				distance < nearest_distance
+			}) {
+				nearest = Some((distance, frame_id, x, y));
+			}
+		}
+
+		let (_, frame_id, x, y) = nearest?;
+		let f = FRAME_WIDTH as Scalar / 2.0;
+		let tile_x = (((x + 1.0) * f) as usize).min(FRAME_WIDTH - 1);
+		let tile_y = (((y + 1.0) * f) as usize).min(FRAME_WIDTH - 1);
+		Some((frame_id, tile_x, tile_y))
+	}
+
+	// Takes effect from the next rendered frame and is persisted to the
+	// settings file.
+	pub(crate) fn set_palette(&mut self, kind: PaletteKind) {
+		self.palette = Palette::new(kind);
+		self.settings.palette = kind;
+		self.settings.save();
+		log(format!("Palette: {}", kind.name()));
+	}
+
+	// `None` scales the UI with the viewport. Takes effect from the next
+	// rendered frame and is persisted to the settings file.
+	pub(crate) fn set_ui_scale(&mut self, scale: Option<Scalar>) {
+		self.settings.ui_scale = scale;
+		self.settings.save();
+		log(format!("UI scale: {}", ui_scale_name(scale)));
+	}
+
+	// Zero removes the limit. Persisted to the settings file.
+	pub(crate) fn set_max_fps(&mut self, fps: u32) {
+		self.settings.max_fps = fps;
+		self.settings.save();
+		log(format!("Max FPS: {}", fps));
+	}
+
+	// Persisted to the settings file.
+	pub(crate) fn set_late_input(&mut self, on: bool) {
+		self.settings.late_input = on;
+		self.settings.save();
+		log(format!("Late input: {}", on));
+	}
+
+	// Persisted to the settings file.
+	pub fn set_three_d_tiles(&mut self, on: bool) {
+		self.settings.three_d_tiles = on;
+		self.settings.save();
+		log(format!("3D tiles: {}", on));
+	}
+
+	pub(crate) fn set_autosave_interval(&mut self, seconds: u32) {
+		self.settings.autosave_interval = seconds;
+		self.settings.save();
+		self.autosave.set_interval(seconds);
+		log(format!("Autosave interval: {}", seconds));
+	}
+
+	pub(super) fn save_world(&mut self, game_state: &GameState) {
+		match autosave::save(&game_state.world, autosave::MANUAL_SAVE) {
+			Ok(()) => {
+				self.autosave.mark_saved(&game_state.world);
+				self.console
+					.print(format!("saved to {}", autosave::MANUAL_SAVE));
+			}
+			Err(error) => {
+				self.console.print(format!("could not save: {}", error))
+			}
+		}
+	}
+
+	// Replaces the world with a saved one, from the manual save or else the
+	// newest autosave.
+	pub(super) fn load_world(
+		&mut self,
+		game_state: &mut GameState,
+		from_autosave: bool,
+	) {
+		let save = if from_autosave {
+			autosave::newest_autosave()
+		} else {
+			autosave::read_save(autosave::MANUAL_SAVE)
+		};
+		let (bytes, _) = match save {
+			Some(save) => save,
+			None => {
+				self.console.print("nothing to load");
+				return;
+			}
+		};
+		match autosave::load(&bytes) {
+			Ok(world) => {
+				*game_state = GameState::from_world(world);
+				self.autosave.mark_saved(&game_state.world);
+				self.console.print("loaded");
+			}
+			Err(error) => {
+				self.console.print(format!("could not load: {}", error))
+			}
+		}
 	}
 
 	pub fn render(&mut self, game_state: &mut GameState) {
-		self.backend.clear_canvas();
+		let background = self.palette.get(Role::Background);
+		self.backend.clear_canvas(background);
 
 		let projector = {
 			let position = Vector3::new(0.0, 0.0, 240.0);
 			let rotation = Vector3::new(0.0, 0.0, 0.0);
-			let fov_degrees = 50.0;
+			let fov_degrees = 100.0;
 			let camera = Camera::new(position, rotation, fov_degrees);
 
-			let viewport_width = self.backend.viewport_width() as f32;
-			let viewport_height = self.backend.viewport_height() as f32;
+			let viewport_width = self.backend.viewport_width() as Scalar;
+			let viewport_height = self.backend.viewport_height() as Scalar;
+
+			camera.projector(viewport_width, viewport_height, VIEW_SCALE)
+		};
+
+		if self.world_map.open {
+			self.draw_world_map(&game_state.world);
+		} else {
+			self.render_cube(&projector, game_state);
+			if let Some(view) = self.pick_view {
+				self.draw_frame_strip(&game_state.world, view.focus);
+			}
+			self.draw_timeline(game_state);
+			self.draw_hud(&game_state.world);
+			self.draw_time_trial(game_state);
+			self.draw_demo_caption(game_state);
+		}
+		self.draw_notifications();
+		if self.debug_mode {
+			self.draw_frame_rate(&game_state.world);
+		}
+		self.draw_entity_count(game_state);
+		self.draw_console();
+
+		if let Some(text) = &game_state.text_box {
+			self.draw_text_box(text);
+		}
+		if let Some(menu) = game_state.menu.as_mut() {
+			match menu.saves.as_mut() {
+				Some(saves) => {
+					self.draw_menu_widget(saves.header(), &mut saves.widget)
+				}
+				None => {
+					menu.update_items(&self.settings.best_times);
+					self.draw_menu_widget(menu.header(), &mut menu.widget);
+				}
+			}
+		}
+		if let Some(completion) = game_state.completion.as_mut() {
+			let text = completion.text(&game_state.world.level);
+			self.draw_menu_widget(&text, &mut completion.widget);
+		}
+		if let Some(generation) = &game_state.generating {
+			self.draw_generation(generation);
+		}
+
+		self.capture_recording_frame();
+		self.backend.update_canvas();
+		self.draw_net_window(&game_state.world);
+		self.watchdog.end_phase(Phase::Render);
+		self.watchdog.end_frame(&game_state.world);
+	}
+
+	fn render_cube(
+		&mut self,
+		projector: &CameraProjector,
+		game_state: &mut GameState,
+	) {
+		// let red = Color::RED;
+		// let from = Vector3::new(-5.0, 20.0, 0.0);
+		// let to = Vector3::new(30.0, -10.0, 5.0);
+		// self.draw_line(&projector, from, to, red);
+
+		let world = &game_state.world;
+
+		// A camera script playing takes the camera from the focus entity.
+		let scripted = self.camera_scripts.view();
+		let focus = scripted.or_else(|| {
+			world
+				.focus_entity
+				.and_then(|id| self.drawn_position(world, id))
+		});
+		if let Some(position) = focus {
+			// Out of bounds until the next tick puts it back, so there is
+			// nothing to draw around.
+			if world.get_frame(position.frame_id).is_none() {
+				return;
+			}
+			self.last_view = Some(position);
+		}
+
+		// With no focus entity, such as just after it is removed, the camera
+		// stays where it last was, and circles the cube if it never had one.
+		let view = focus
+			.or(self.last_view)
+			.filter(|position| world.get_frame(position.frame_id).is_some());
+		let (view_frame, view_rotation) = match view {
+			Some(position) => {
+				// A frame picked from the frame strip is looked at straight
+				// on.
+				let view_frame = match scripted {
+					Some(_) => position.frame_id,
+					None => {
+						self.frame_strip.view_frame(world, position.frame_id)
+					}
+				};
+				let (view_x, view_y) = if view_frame == position.frame_id {
+					(position.x, position.y)
+				} else {
+					(0.0, 0.0)
+				};
+				(view_frame, view_rotation(Some((view_x, view_y))))
+			}
+			None => match world.frame_ids().first() {
+				Some(&frame_id) => (frame_id, view_rotation(None)),
+				None => return,
+			},
+		};
+		let globe = Globe::new(self.settings.globe, view_rotation);
+		let projector = &projector.with_globe(globe);
+		type DrawFrameFn =
+			fn(&mut Window, &CameraProjector, &Frame, Matrix4x4, Matrix4x4);
+
+		let surface_transforms = world.surface_transforms(view_frame);
 
-			camera.projector(viewport_width, viewport_height)
+		for &(frame_id, surface_transform) in surface_transforms.iter() {
+			let frame = world.get_frame(frame_id).unwrap();
+			self.draw_frame_border(
+				projector,
+				frame,
+				frame_id == view_frame,
+				surface_transform,
+				view_rotation,
+			);
+		}
+
+		let mut frames_do = |f: DrawFrameFn| {
+			for &(frame_id, surface_transform) in surface_transforms.iter() {
+				let frame = world.get_frame(frame_id).unwrap();
+				f(self, projector, frame, surface_transform, view_rotation);
+			}
+		};
+
+		frames_do(Self::draw_frame_background);
+		for &(frame_id, surface_transform) in surface_transforms.iter() {
+			let frame = world.get_frame(frame_id).unwrap();
+			self.draw_frame_wind(
+				projector,
+				world,
+				frame,
+				surface_transform,
+				view_rotation,
+			);
+		}
+		for &(frame_id, surface_transform) in surface_transforms.iter() {
+			let frame = world.get_frame(frame_id).unwrap();
+			self.draw_frame_interior(
+				projector,
+				world,
+				frame,
+				surface_transform,
+				view_rotation,
+			);
+		}
+
+		// Chevrons move along the belts at the speed they carry things.
+		let belt_travel = world.physics.conveyor_speed() / TILE_SIZE;
+		let conveyor_phase = (world.tick_count as Scalar * belt_travel).fract();
+		for &(frame_id, surface_transform) in surface_transforms.iter() {
+			let frame = world.get_frame(frame_id).unwrap();
+			self.draw_conveyors(
+				projector,
+				frame,
+				conveyor_phase,
+				surface_transform,
+				view_rotation,
+			);
+		}
+
+		let grid_frames = match self.grid_mode {
+			GridMode::Off => 0,
+			GridMode::Focus => 1,
+			GridMode::All => surface_transforms.len(),
+		};
+		for &(_, surface_transform) in
+			surface_transforms.iter().take(grid_frames)
+		{
+			self.draw_grid(projector, surface_transform, view_rotation);
+		}
+
+		self.pick_view = Some(PickView {
+			projector: *projector,
+			view_rotation,
+			focus: view_frame,
+		});
+		// Nothing is picked through the frame strip.
+		self.hovered_tile = match self.input_state.mouse_position {
+			Some(position) if self.thumbnail_at(world, position).is_none() => {
+				self.pick_tile(world, position)
+			}
+			_ => None,
 		};
+		let now = backend::time::now();
+		let edited_tile = self
+			.edited_tile
+			.filter(|&(_, _, until)| now < until)
+			.map(|(tile, role, _)| (tile, role));
+		let highlights = self
+			.hovered_tile
+			.filter(|&tile| edited_tile.map(|(edited, _)| edited) != Some(tile))
+			.map(|tile| (tile, Role::Highlight))
+			.into_iter()
+			.chain(edited_tile);
+		for ((frame_id, x, y), role) in highlights.collect::<Vec<_>>() {
+			let surface_transform = surface_transforms
+				.iter()
+				.find(|&&(id, _)| id == frame_id)
+				.map(|&(_, transform)| transform);
+			if let Some(surface_transform) = surface_transform {
+				self.draw_tile_highlight(
+					projector,
+					x,
+					y,
+					surface_transform,
+					view_rotation,
+					role,
+				);
+			}
+		}
+
+		// Under the entities, so as never to hide the player.
+		if let Some(time_trial) = &game_state.time_trial {
+			self.draw_ghost(
+				projector,
+				world,
+				time_trial,
+				&surface_transforms,
+				view_rotation,
+			);
+		}
+		// Drawn a layer at a time, and in order of creation within a layer,
+		// so the same entity always comes out on top wherever two overlap.
+		let mut entities: Vec<&Entity> = world
+			.entity_ids()
+			.into_iter()
+			.filter_map(|id| world.get_entity(id))
+			.collect();
+		entities.sort_by_key(|entity| (entity.drawn_layer(), entity.id.0));
+		self.draw_shadows(
+			projector,
+			world,
+			&entities,
+			&surface_transforms,
+			view_rotation,
+		);
+		for entity in entities {
+			self.draw_entity(
+				projector,
+				world,
+				entity,
+				&surface_transforms,
+				view_rotation,
+			);
+		}
+		// Nothing can be interacted with while a text box or menu is up.
+		let menu = game_state.menu.is_some() || game_state.completion.is_some();
+		if game_state.text_box.is_none() && menu == false {
+			self.draw_prompt(projector, &surface_transforms, view_rotation);
+		}
 
-		self.render_cube(&projector, game_state);
+		if self.debug_mode {
+			let issues = world.validate_topology(OpenEdges::Any);
+			for &(frame_id, surface_transform) in surface_transforms.iter() {
+				let frame = world.get_frame(frame_id).unwrap();
+				self.draw_frame_labels(
+					projector,
+					frame,
+					&issues,
+					surface_transform,
+					view_rotation,
+				);
+			}
+			self.draw_entity_tags(
+				projector,
+				world,
+				&surface_transforms,
+				view_rotation,
+			);
+		}
+	}
+
+	// Each tagged entity's group name just above it, on its own frame.
+	fn draw_entity_tags(
+		&mut self,
+		projector: &CameraProjector,
+		world: &World,
+		surface_transforms: &[(FrameId, Matrix4x4)],
+		view_rotation: Matrix4x4,
+	) {
+		let color = self.palette.get(Role::UiText);
+		for entity_id in world.entity_ids() {
+			let entity = world.get_entity(entity_id).unwrap();
+			let tag = match entity.tag {
+				Some(tag) => world.tag_name(tag),
+				None => continue,
+			};
+			let p = self.drawn_position(world, entity_id).unwrap();
+			let transform = surface_transforms
+				.iter()
+				.find(|&&(frame_id, _)| frame_id == p.frame_id);
+			if let Some(&(_, m)) = transform {
+				let center = (p.x, p.y - TILE_SIZE);
+				let r = view_rotation;
+				self.draw_surface_text(
+					projector, tag, center, 0.06, m, r, color,
+				);
+			}
+		}
+	}
+
+	// The frame's id at its center, and next to each linked edge that edge's
+	// initial and the id of the frame it links to. Labels are laid out in
+	// the frame's own coordinates, so for rotated links they sit on the edge
+	// that is physically shared rather than where the focus frame's edge of
+	// the same name would be. Links flagged by `validate_topology` are red.
+	fn draw_frame_labels(
+		&mut self,
+		projector: &CameraProjector,
+		frame: &Frame,
+		issues: &[TopologyIssue],
+		surface_transform: Matrix4x4,
+		view_rotation: Matrix4x4,
+	) {
+		let m = surface_transform;
+		let r = view_rotation;
+		let p1 = vec3(-1.0, -1.0, 1.0) * m * r;
+		let p2 = vec3(1.0, -1.0, 1.0) * m * r;
+		let p3 = vec3(1.0, 1.0, 1.0) * m * r;
+		let p4 = vec3(-1.0, 1.0, 1.0) * m * r;
+		let (normal, center) = rect_facing(p1, p2, p3, p4);
+		let camera = projector.camera_position();
+		if is_rect_visible(normal, center, camera) == false {
+			return;
+		}
+
+		let id_color = self.palette.get(Role::UiText);
+		self.draw_surface_text(
+			projector,
+			&frame.position.0.to_string(),
+			(0.0, 0.0),
+			0.4,
+			m,
+			r,
+			id_color,
+		);
+
+		for &edge in Direction::iter() {
+			let (label, center) = match edge {
+				Direction::Up => ("U", (0.0, -0.8)),
+				Direction::Down => ("D", (0.0, 0.8)),
+				Direction::Left => ("L", (-0.8, 0.0)),
+				Direction::Right => ("R", (0.8, 0.0)),
+				Direction::Neutral => continue,
+			};
+			let link = match frame.borders.at_direction(edge) {
+				Some(link) => link,
+				None => continue,
+			};
+
+			let flagged = issues.iter().any(|issue| {
+				issue.frame() == frame.position && issue.edge() == edge
+			});
+			let color = if flagged { Color::RED } else { Color::GREEN };
+			let text = format!("{}{}", label, link.frame.0);
+			self.draw_surface_text(projector, &text, center, 0.12, m, r, color);
+		}
+	}
+
+	// Text lying on a frame's surface, centered on `center` in frame
+	// coordinates with glyphs `height` frame units tall. Each row of lit font
+	// pixels becomes a line segment through the middle of that row.
+	fn draw_surface_text(
+		&mut self,
+		projector: &CameraProjector,
+		text: &str,
+		center: (Scalar, Scalar),
+		height: Scalar,
+		surface_transform: Matrix4x4,
+		view_rotation: Matrix4x4,
+		color: Color,
+	) {
+		let m = surface_transform;
+		let r = view_rotation;
+
+		let pixel = height / font::GLYPH_HEIGHT as Scalar;
+		let left = center.0 - font::text_width(text) as Scalar * pixel / 2.0;
+		let top = center.1 - height / 2.0;
+		for run in font::text_runs(text) {
+			let y = top + (run.row as Scalar + 0.5) * pixel;
+			let start = vec3(left + run.start as Scalar * pixel, y, 1.0);
+			let end = vec3(left + run.end as Scalar * pixel, y, 1.0);
+			self.draw_line(projector, start * m * r, end * m * r, color);
+		}
+	}
+
+	// Where an entity is drawn, as far from where it was at the last tick to
+	// where it is now as the time to the next tick has gone by.
+	fn drawn_position(
+		&self,
+		world: &World,
+		id: EntityId,
+	) -> Option<WorldPosition> {
+		let step_duration = self.tick_rate.step_duration();
+		let fraction = (self.tick_accumulator / step_duration).min(1.0);
+		self.interpolation.position(world, id, fraction as Scalar)
+	}
+
+	// Entities are drawn as polylines in their frame's coordinates, which are
+	// carried over onto neighboring faces where they overhang an edge.
+	fn draw_entity(
+		&mut self,
+		projector: &CameraProjector,
+		world: &World,
+		entity: &Entity,
+		surface_transforms: &[(FrameId, Matrix4x4)],
+		view_rotation: Matrix4x4,
+	) {
+		let p = self.drawn_position(world, entity.id).unwrap();
+
+		let (role, shapes): (Role, Vec<Vec<(Scalar, Scalar)>>) = match entity
+			.kind
+		{
+			EntityKind::Player => {
+				// Drawn feet down, so mirrored when gravity points up.
+				let down = if entity.gravity_dir == Direction::Up {
+					-1.0
+				} else {
+					1.0
+				};
+				let d = 0.01;
+				let point =
+					|x: Scalar, y: Scalar| (p.x + x * d, p.y + y * d * down);
+				let pose = sprites::PLAYER.pose(&entity.animation);
+				let mut shapes = sprites::pose_shapes(pose, point);
+				// A short tick from the middle of the body the way the
+				// player is pointing.
+				let (dx, dy) = entity.pointing().unit();
+				shapes.push(vec![
+					(p.x, p.y),
+					(p.x + dx * 0.008, p.y + dy * 0.008),
+				]);
+				(Role::Player, shapes)
+			}
+			EntityKind::Coin => {
+				let d = 0.03;
+				let point = |x: Scalar, y: Scalar| (p.x + x * d, p.y + y * d);
+				let pose = sprites::COIN.pose(&entity.animation);
+				(Role::Coin, sprites::pose_shapes(pose, point))
+			}
+			EntityKind::GravityOrb => {
+				let radius = 0.025;
+				let circle = (0..=8)
+					.map(|i| {
+						let t = i as Scalar / 8.0 * PI * 2.0;
+						(p.x + t.cos() * radius, p.y + t.sin() * radius)
+					})
+					.collect();
+				(Role::GravityOrb, vec![circle])
+			}
+			// Two chevrons pointing up.
+			EntityKind::SpeedBoost => {
+				let d = 0.02;
+				let chevrons = [-0.01, 0.01]
+					.iter()
+					.map(|&offset| {
+						let y = p.y + offset;
+						vec![
+							(p.x - d, y + d / 2.0),
+							(p.x, y - d / 2.0),
+							(p.x + d, y + d / 2.0),
+						]
+					})
+					.collect();
+				(Role::SpeedBoost, chevrons)
+			}
+			// A lightning bolt.
+			EntityKind::Energizer => {
+				let d = 0.01;
+				let point = |x: Scalar, y: Scalar| (p.x + x * d, p.y + y * d);
+				let bolt = vec![
+					point(0.5, -2.0),
+					point(-1.0, 0.3),
+					point(0.6, -0.3),
+					point(-0.5, 2.0),
+				];
+				(Role::Energizer, vec![bolt])
+			}
+			// A square just inside its tile, with a cross so it reads as a
+			// crate rather than a tile outline.
+			EntityKind::Block => {
+				let (ox, oy) = world.slide_offset(entity.id);
+				let (x, y) = (p.x + ox, p.y + oy);
+				let d = TILE_SIZE * 0.4;
+				let point = |sx: Scalar, sy: Scalar| (x + sx * d, y + sy * d);
+				let crate_lines = vec![
+					point(-1.0, -1.0),
+					point(1.0, -1.0),
+					point(1.0, 1.0),
+					point(-1.0, 1.0),
+					point(-1.0, -1.0),
+					point(1.0, 1.0),
+				];
+				let cross = vec![point(1.0, -1.0), point(-1.0, 1.0)];
+				(Role::Block, vec![crate_lines, cross])
+			}
+			// An arrowhead the way it walks. Walkers that turn at ledges
+			// have a bar across the tip, and ones that follow the surface
+			// a line out to the floor they cling to.
+			EntityKind::Walker => {
+				let (dx, dy) = entity.orientation.unit();
+				let (nx, ny) = (-dy, dx);
+				let d = 0.015;
+				let point = |along: Scalar, across: Scalar| {
+					(
+						p.x + (dx * along + nx * across) * d,
+						p.y + (dy * along + ny * across) * d,
+					)
+				};
+				let pose = sprites::WALKER.pose(&entity.animation);
+				let mut shapes = sprites::pose_shapes(pose, point);
+				match entity.edge_behavior {
+					EdgeBehavior::TurnAtLedge => {
+						shapes.push(vec![point(1.5, 1.0), point(1.5, -1.0)]);
+					}
+					EdgeBehavior::WalkOff => {}
+					EdgeBehavior::FollowSurface => {
+						let (gx, gy) = entity.gravity_dir.unit();
+						shapes.push(vec![
+							(p.x, p.y),
+							(p.x + gx * d, p.y + gy * d),
+						]);
+					}
+				}
+				(Role::Enemy, shapes)
+			}
+			// Rings, the head's with jaws the way it crawls and the tail's
+			// with a smaller one inside, as the only place it can be hurt.
+			EntityKind::Worm | EntityKind::WormSegment => {
+				let ring = |radius: Scalar| -> Vec<(Scalar, Scalar)> {
+					(0..=8)
+						.map(|i| {
+							let t = i as Scalar / 8.0 * PI * 2.0;
+							(p.x + t.cos() * radius, p.y + t.sin() * radius)
+						})
+						.collect()
+				};
+				let mut shapes = vec![ring(0.025)];
+				if entity.kind == EntityKind::Worm {
+					let (dx, dy) = entity.orientation.unit();
+					let (nx, ny) = (-dy, dx);
+					let jaw = |side: Scalar| {
+						vec![
+							(p.x + dx * 0.02, p.y + dy * 0.02),
+							(
+								p.x + dx * 0.035 + nx * side,
+								p.y + dy * 0.035 + ny * side,
+							),
+						]
+					};
+					shapes.push(jaw(0.01));
+					shapes.push(jaw(-0.01));
+				}
+				if world.worm_follower(entity.id).is_none() {
+					shapes.push(ring(0.012));
+				}
+				(Role::Enemy, shapes)
+			}
+		};
+
+		let shapes = match entity.falling_off {
+			Some(fall) => fall_off_shapes(shapes, (p.x, p.y), fall),
+			None => shapes,
+		};
+		// Invulnerable entities blink, hidden every other few ticks.
+		let blink = world.tick_count / INVULNERABLE_BLINK_TICKS % 2 == 1;
+		if blink && world.has_status(entity.id, StatusKind::Invulnerable) {
+			return;
+		}
+		let mut color = match entity.render_tint {
+			Some(([r, g, b], _)) => Color::rgb(r, g, b),
+			None => self.palette.get(role),
+		};
+		// So that recordings can't pass cheated play off as real.
+		if entity.cheats.any() {
+			color = color.mix(self.palette.get(Role::Cheat), 0.6);
+		}
+		let thick = self.palette.thickens(role);
+		for shape in shapes {
+			self.draw_surface_lines(
+				projector,
+				world,
+				surface_transforms,
+				p.frame_id,
+				&shape,
+				view_rotation,
+				color,
+				thick,
+			);
+		}
+	}
+
+	// Each edge is drawn solid when linked, as a cliff when open on purpose
+	// and dashed otherwise, so missing links stand out. Linked edges also get a mark near their midpoint
+	// that points inward for a straight link and is turned by the link's
+	// rotation otherwise.
+	fn draw_frame_border(
+		&mut self,
+		projector: &CameraProjector,
+		frame: &Frame,
+		is_focus: bool,
+		surface_transform: Matrix4x4,
+		view_rotation: Matrix4x4,
+	) {
+		let m = surface_transform;
+		let r = view_rotation;
+		let p1 = vec3(-1.0, -1.0, 1.0) * m * r;
+		let p2 = vec3(1.0, -1.0, 1.0) * m * r;
+		let p3 = vec3(1.0, 1.0, 1.0) * m * r;
+		let p4 = vec3(-1.0, 1.0, 1.0) * m * r;
+
+		let (normal, center) = rect_facing(p1, p2, p3, p4);
+		let camera = projector.camera_position();
+		if is_rect_visible(normal, center, camera) == false {
+			return;
+		}
 
-		self.backend.update_canvas();
+		let mut color = self.palette.get(Role::FrameBorder);
+		if is_focus {
+			color = color.mix(Color::WHITE, FOCUS_BORDER_BRIGHTEN);
+		}
+		let unlinked_color = self.palette.get(Role::UnlinkedEdge);
+		let mark_color = color.mix(self.palette.get(Role::Background), 0.5);
+		let cliffs = cliff_edges(frame);
+
+		let edges = [
+			(Direction::Up, p1, p2),
+			(Direction::Right, p2, p3),
+			(Direction::Down, p3, p4),
+			(Direction::Left, p4, p1),
+		];
+		for &(edge, start, end) in edges.iter() {
+			let link = match frame.borders.at_direction(edge) {
+				Some(link) => link,
+				None if cliffs.contains(&edge) => {
+					self.draw_cliff(projector, edge, m, r);
+					continue;
+				}
+				None => {
+					let dash = BORDER_DASH_LENGTH;
+					self.draw_dashed_line(
+						projector,
+						start,
+						end,
+						dash,
+						unlinked_color,
+					);
+					continue;
+				}
+			};
+			self.draw_line(projector, start, end, color);
+
+			let (edge_x, edge_y) = edge.unit();
+			let inward = edge.reverse();
+			let turned = inward.rotated(compose_rotation(edge, link));
+			let (in_x, in_y) = inward.unit();
+			let (turned_x, turned_y) = turned.unit();
+			let length = LINK_MARK_LENGTH;
+			let mark_x = edge_x + in_x * length;
+			let mark_y = edge_y + in_y * length;
+			let mark_start = vec3(mark_x, mark_y, 1.0) * m * r;
+			let mark_end = vec3(
+				mark_x + turned_x * length,
+				mark_y + turned_y * length,
+				1.0,
+			) * m * r;
+			self.draw_line(projector, mark_start, mark_end, mark_color);
+		}
 	}
 
-	fn render_cube(
+	fn draw_frame_interior(
 		&mut self,
 		projector: &CameraProjector,
-		game_state: &mut GameState,
+		world: &World,
+		frame: &Frame,
+		surface_transform: Matrix4x4,
+		view_rotation: Matrix4x4,
 	) {
-		// let red = Color::RED;
-		// let from = Vector3::new(-5.0, 20.0, 0.0);
-		// let to = Vector3::new(30.0, -10.0, 5.0);
-		// self.draw_line(&projector, from, to, red);
-
-		let world = &game_state.world;
-
-		let focus_entity_id = world.focus_entity.expect("No focus entity");
-		let focus_entity = world.get_entity(focus_entity_id).unwrap();
-		let focus_position = focus_entity.position;
-
-		// let debug_tile_pos = world.tile_index_at_entity(focus_entity.id);
-		// self.debug = debug_tile_pos;
-
-		let focus_x =
-			focus_position.x.abs().powf(1.5).copysign(focus_position.x);
-		let focus_y =
-			focus_position.y.abs().powf(1.5).copysign(focus_position.y);
-
-		// let r = vec3(
-		// 	focus_y * (PI / 4.0),
-		// 	focus_x * -(PI / 4.0),
-		// 	0.0,
-		// );
-
-		// let view_rotate_y = focus_x * (-PI / 4.0);
-		// let view_rotate_x = vec3(focus_x, focus_y, 1.0).normalized().y;
-		// let view_rotate_x = view_rotate_x * (PI / 3.0);
-
-		// Convert Cartesian coordinates on the cube into spherical
-		// coordinates.
-		let view_rotate_y = focus_x.atan();
-		let view_rotate_x =
-			(PI / 4.0 * 2.0) - ((focus_x.powi(2) + 1.0).sqrt()).atan2(focus_y);
-		// let view_rotate_x = 0.0;
-
-		let r = vec3(view_rotate_x, -view_rotate_y, 0.0);
-		//let focus_vec = vec3(focus_x, focus_y, 1.0).normalized();
-		//let spin = 0.0;
-		//println!("{}", );
-		//let r = r.rotated(0.0, 0.0, 0.5);
-		//let r =
-
-		let focus_frame = world.get_frame(focus_position.frame_id).unwrap();
-		let neighbors = focus_frame.borders;
+		let m = surface_transform;
+		let r = view_rotation;
 
-		let view_rotation = Matrix4x4::rotation(r.x, r.y, r.z);
+		let p1 = vec3(-1.0, -1.0, 1.0) * m * r;
+		let p2 = vec3(1.0, -1.0, 1.0) * m * r;
+		let p3 = vec3(1.0, 1.0, 1.0) * m * r;
+		let p4 = vec3(-1.0, 1.0, 1.0) * m * r;
 
-		let mut twist = 0.0;
+		// Tiles lie flat on the face, so they are only ever visible along
+		// with it.
+		let (normal, center) = rect_facing(p1, p2, p3, p4);
+		let camera = projector.camera_position();
+		if is_rect_visible(normal, center, camera) == false {
+			return;
+		}
 
-		if focus_y > 0.0 {
-			//twist = (PI * 2.0) / 6.0;
+		let pieces = projector.globe().tile_pieces();
+		let geometry =
+			TileGeometry::updated(&mut self.tile_geometry, frame, pieces);
+		let three_d = self.settings.three_d_tiles;
+		if three_d {
+			self.draw_tile_blocks(projector, world, frame, m, r);
+		} else {
+			for (role, segments) in geometry.outlines.iter() {
+				let color = self.palette.get(*role);
+				let thick = self.palette.thickens(*role);
+				self.draw_segments(projector, segments, m, r, color, thick);
+			}
 		}
 
-		//let twist = (self.tick as f32) / 300.0;
-		let view_rotation = {
-			let p = vec3(focus_x, focus_y, 1.0).normalized();
-			view_rotation.rotated_about_axis(p, twist)
-		};
-		type DrawFrameFn =
-			fn(&mut Window, &CameraProjector, &Frame, Direction, Matrix4x4);
+		let f = 1.0 / FRAME_WIDTH as Scalar;
+		for &(x, y) in geometry.special.iter() {
+			let o = vec3(x as Scalar * 2.0 * f, y as Scalar * 2.0 * f, 0.0)
+				- vec3(1.0, 1.0, 0.0);
 
-		let mut frames_do = |f: DrawFrameFn| {
-			for &direction in Direction::iter() {
-				let neighbor = neighbors.at_direction(direction);
-				if let Some(neighbor) = neighbor {
-					let frame = world.get_frame(neighbor.frame).unwrap();
-					f(self, projector, &frame, direction, view_rotation);
+			let (tx, ty) = (x as isize, y as isize);
+			match *frame.tile(tx, ty) {
+				Tile::Sign => self.draw_sign_tile(projector, o, m, r),
+				Tile::PressurePlate if three_d == false => {
+					self.draw_pressure_plate_tile(projector, o, m, r)
+				}
+				Tile::Spring => {
+					let rotation = frame.rotation(tx, ty);
+					let squash = frame.spring_squash(tx, ty);
+					self.draw_spring_tile(projector, rotation, squash, o, m, r)
+				}
+				tile @ Tile::Sand | tile @ Tile::Water => {
+					self.draw_fluid_tile(projector, tile, o, m, r)
+				}
+				tile @ Tile::SlopeUpRight | tile @ Tile::SlopeUpLeft => {
+					let rotation = frame.rotation(tx, ty);
+					self.draw_slope_tile(projector, tile, rotation, o, m, r)
 				}
+				_ => {}
 			}
-		};
-
-		frames_do(Self::draw_frame_border);
-		frames_do(Self::draw_frame_interior);
-
-		// self.draw_frame(projector, &frame, Direction::Neutral, r);
-		// self.draw_frame(projector, &frame, Direction::Up, r);
-		// self.draw_frame(projector, &frame, Direction::Down, r);
-		// self.draw_frame(projector, &frame, Direction::Left, r);
-		// self.draw_frame(projector, &frame, Direction::Right, r);
-
-		for entity_id in world.entity_ids() {
-			let entity = world.get_entity(entity_id).unwrap();
-			let frame = entity.position.frame_id;
-			self.draw_entity(
-				projector,
-				entity,
-				Direction::Neutral,
-				view_rotation,
-			);
 		}
 	}
 
-	fn draw_entity(
+	// A low slab along the bottom of the tile whose top left corner is
+	// offset `o` from the top left of the frame.
+	fn draw_pressure_plate_tile(
 		&mut self,
 		projector: &CameraProjector,
-		entity: &Entity,
-		direction: Direction,
+		o: Vector3,
+		surface_transform: Matrix4x4,
 		view_rotation: Matrix4x4,
 	) {
-		let (mut rotate_pitch, mut rotate_roll) = match direction {
-			Direction::Neutral => (0.0, 0.0),
-			Direction::Up => (PI / 2.0, 0.0),
-			Direction::Down => (-PI / 2.0, 0.0),
-			Direction::Right => (0.0, PI / 2.0),
-			Direction::Left => (0.0, -PI / 2.0),
-			_ => (0.0, 0.0),
-		};
-
-		//rotate_pitch += (self.tick as f32 / 100.0);
-
-		let mut direction_rotation =
-			Matrix4x4::rotation(rotate_pitch, rotate_roll, 0.0);
+		let color = self.palette.get(Role::TilePlate);
+		let thick = self.palette.thickens(Role::TilePlate);
+		let m = surface_transform;
+		let r = view_rotation;
 
-		let r = view_rotation * direction_rotation;
-		let p = entity.position;
-		self.draw_line(
+		let f = 2.0 / FRAME_WIDTH as Scalar;
+		let point =
+			|x: Scalar, y: Scalar| (vec3(x * f, y * f, 1.0) + o) * m * r;
+		self.draw_rect(
 			projector,
-			vec3(p.x, p.y - 0.01, 1.00) * r,
-			vec3(p.x, p.y + 0.01, 1.00) * r,
-			Color::CYAN,
+			point(0.1, 0.8),
+			point(0.9, 0.8),
+			point(0.9, 1.0),
+			point(0.1, 1.0),
+			color,
+			thick,
 		);
 	}
 
-	fn frame_rotation_matrix(
-		&self,
+	// A coil under a plate, facing up in the tile whose top left corner is
+	// offset `o` from the top left of the frame before it is turned by
+	// `rotation`. The plate is pressed down towards the base by `squash`,
+	// from zero to one.
+	fn draw_spring_tile(
+		&mut self,
 		projector: &CameraProjector,
-		direction: Direction,
+		rotation: Angle,
+		squash: Scalar,
+		o: Vector3,
+		surface_transform: Matrix4x4,
 		view_rotation: Matrix4x4,
-	) -> Matrix4x4 {
-		let (mut rotate_pitch, mut rotate_roll) = match direction {
-			Direction::Neutral => (0.0, 0.0),
-			Direction::Up => (PI / 2.0, 0.0),
-			Direction::Down => (-PI / 2.0, 0.0),
-			Direction::Right => (0.0, PI / 2.0),
-			Direction::Left => (0.0, -PI / 2.0),
-			_ => (0.0, 0.0),
-		};
-
-		let mut direction_rotation =
-			Matrix4x4::rotation(rotate_pitch, rotate_roll, 0.0);
+	) {
+		let color = self.palette.get(Role::TileSpring);
+		let m = surface_transform;
+		let r = view_rotation;
 
-		direction_rotation
+		let f = 2.0 / FRAME_WIDTH as Scalar;
+		// Turned clockwise about the middle of the tile, with +y down.
+		let point = |x: Scalar, y: Scalar| {
+			let (mut x, mut y) = (x - 0.5, y - 0.5);
+			for _ in 0..rotation.quarter_turns() {
+				let turned = (-y, x);
+				x = turned.0;
+				y = turned.1;
+			}
+			(vec3((x + 0.5) * f, (y + 0.5) * f, 1.0) + o) * m * r
+		};
+		let top = 0.1 + squash * 0.4;
+		let base = 0.9;
+		self.draw_line(projector, point(0.1, top), point(0.9, top), color);
+		self.draw_line(projector, point(0.1, base), point(0.9, base), color);
+		// Zigzagging from side to side between the plates.
+		let turns = 6;
+		let mut coil = vec![point(0.5, top)];
+		for i in 0..turns {
+			let x = if i % 2 == 0 { 0.25 } else { 0.75 };
+			let along = (i as Scalar + 0.5) / turns as Scalar;
+			coil.push(point(x, top + (base - top) * along));
+		}
+		coil.push(point(0.5, base));
+		self.draw_lines(projector, &coil, color);
 	}
 
-	fn draw_frame_border(
+	// Sand as scattered grains and water as ripples, in the tile whose top
+	// left corner is offset `o` from the top left of the frame.
+	fn draw_fluid_tile(
 		&mut self,
 		projector: &CameraProjector,
-		frame: &Frame,
-		direction: Direction,
+		tile: Tile,
+		o: Vector3,
+		surface_transform: Matrix4x4,
 		view_rotation: Matrix4x4,
 	) {
-		let color = Color::GRAY;
-
-		let direction_rotation =
-			self.frame_rotation_matrix(projector, direction, view_rotation);
-
-		let m = direction_rotation;
+		let m = surface_transform;
 		let r = view_rotation;
-		let p1 = vec3(-1.0, -1.0, 1.0) * m * r;
-		let p2 = vec3(1.0, -1.0, 1.0) * m * r;
-		let p3 = vec3(1.0, 1.0, 1.0) * m * r;
-		let p4 = vec3(-1.0, 1.0, 1.0) * m * r;
 
-		if self.is_rect_visible(projector, p1, p2, p3, p4) == false {
-			return;
+		let f = 2.0 / FRAME_WIDTH as Scalar;
+		let point =
+			|x: Scalar, y: Scalar| (vec3(x * f, y * f, 1.0) + o) * m * r;
+		if tile == Tile::Sand {
+			let color = self.palette.get(Role::TileSand);
+			let grains = [(0.2, 0.3), (0.6, 0.2), (0.4, 0.6), (0.75, 0.75)];
+			for &(x, y) in grains.iter() {
+				self.draw_line(
+					projector,
+					point(x, y),
+					point(x + 0.1, y),
+					color,
+				);
+			}
+		} else {
+			let color = self.palette.get(Role::TileWater);
+			for &y in &[0.35, 0.75] {
+				let ripple = [
+					point(0.1, y),
+					point(0.3, y - 0.1),
+					point(0.5, y),
+					point(0.7, y - 0.1),
+					point(0.9, y),
+				];
+				self.draw_lines(projector, &ripple, color);
+			}
 		}
-
-		self.draw_rect(projector, p1, p2, p3, p4, color);
 	}
 
-	fn draw_frame_interior(
+	// A board on a post, filling the upper part of the tile whose top left
+	// corner is offset `o` from the top left of the frame.
+	fn draw_sign_tile(
 		&mut self,
 		projector: &CameraProjector,
-		frame: &Frame,
-		direction: Direction,
+		o: Vector3,
+		surface_transform: Matrix4x4,
 		view_rotation: Matrix4x4,
 	) {
-		let color = Color::WHITE;
+		let color = self.palette.get(Role::TileSign);
+		let thick = self.palette.thickens(Role::TileSign);
+		let m = surface_transform;
+		let r = view_rotation;
 
-		let direction_rotation =
-			self.frame_rotation_matrix(projector, direction, view_rotation);
+		let f = 2.0 / FRAME_WIDTH as Scalar;
+		let point =
+			|x: Scalar, y: Scalar| (vec3(x * f, y * f, 1.0) + o) * m * r;
+		self.draw_rect(
+			projector,
+			point(0.1, 0.15),
+			point(0.9, 0.15),
+			point(0.9, 0.6),
+			point(0.1, 0.6),
+			color,
+			thick,
+		);
+		self.draw_line(projector, point(0.5, 0.6), point(0.5, 1.0), color);
+	}
 
-		let m = direction_rotation;
+	// Chevrons pointing along each conveyor on a face, offset by `phase`
+	// tiles in the direction the belt runs.
+	fn draw_conveyors(
+		&mut self,
+		projector: &CameraProjector,
+		frame: &Frame,
+		phase: Scalar,
+		surface_transform: Matrix4x4,
+		view_rotation: Matrix4x4,
+	) {
+		let m = surface_transform;
 		let r = view_rotation;
-
 		let p1 = vec3(-1.0, -1.0, 1.0) * m * r;
 		let p2 = vec3(1.0, -1.0, 1.0) * m * r;
 		let p3 = vec3(1.0, 1.0, 1.0) * m * r;
 		let p4 = vec3(-1.0, 1.0, 1.0) * m * r;
-
-		if self.is_rect_visible(projector, p1, p2, p3, p4) == false {
+		let (normal, center) = rect_facing(p1, p2, p3, p4);
+		let camera = projector.camera_position();
+		if is_rect_visible(normal, center, camera) == false {
 			return;
 		}
 
-		//self.draw_rect(projector, p1, p2, p3, p4, color);
+		let background = self.palette.get(Role::Background);
+		let color = background.mix(self.palette.get(Role::TileSolid), 0.6);
+		let f = 2.0 / FRAME_WIDTH as Scalar;
 
-		let f = 1.0 / FRAME_WIDTH as f32;
 		for x in 0..FRAME_WIDTH {
 			for y in 0..FRAME_WIDTH {
-				let mut o = vec3(
-					(x as f32 / FRAME_WIDTH as f32 * 2.0),
-					(y as f32 / FRAME_WIDTH as f32 * 2.0),
-					0.0,
-				);
-				o = o - vec3(1.0, 1.0, 0.0);
-				//let o = Vector3::zero();
-
-				let mut will_render = match *frame.tile(x as isize, y as isize)
-				{
-					Tile::Solid => true,
-					_ => false,
+				let (tx, ty) = (x as isize, y as isize);
+				let rotation = frame.rotation(tx, ty);
+				let (dx, dy) =
+					match frame.tile(tx, ty).conveyor_direction(rotation) {
+						Some(direction) => direction.unit(),
+						None => continue,
+					};
+				let o = vec3(x as Scalar * f - 1.0, y as Scalar * f - 1.0, 0.0);
+				// From the tile's center, along the way the belt runs and
+				// across it.
+				let point = |along: Scalar, across: Scalar| {
+					let x = 0.5 + along * dx - across * dy;
+					let y = 0.5 + along * dy + across * dx;
+					(vec3(x * f, y * f, 1.0) + o) * m * r
 				};
 
-				//println!("{:?}", self.debug);
-				// let color = if (x, y) == self.debug {
-				// 	will_render = true;
-				// 	Color::RED
-				// } else {
-				// 	color
-				// };
-
-				if will_render && THREE_D_TILES {
-					// depth
-					let d = 0.08;
-					// front
-					self.draw_rect(
-						projector,
-						(vec3(0.0 * f, 0.0 * f, 1.00 + d) + o) * m * r,
-						(vec3(2.0 * f, 0.0 * f, 1.00 + d) + o) * m * r,
-						(vec3(2.0 * f, 2.0 * f, 1.00 + d) + o) * m * r,
-						(vec3(0.0 * f, 2.0 * f, 1.00 + d) + o) * m * r,
-						color,
-					);
-					// top
-					self.draw_rect(
-						projector,
-						(vec3(0.0 * f, 0.0 * f, 1.00) + o) * m * r,
-						(vec3(2.0 * f, 0.0 * f, 1.00) + o) * m * r,
-						(vec3(2.0 * f, 0.0 * f, 1.00 + d) + o) * m * r,
-						(vec3(0.0 * f, 0.0 * f, 1.00 + d) + o) * m * r,
-						color,
-					);
-					// left
-					self.draw_rect(
-						projector,
-						(vec3(0.0 * f, 0.0 * f, 1.00) + o) * m * r,
-						(vec3(0.0 * f, 0.0 * f, 1.00 + d) + o) * m * r,
-						(vec3(0.0 * f, 2.0 * f, 1.00 + d) + o) * m * r,
-						(vec3(0.0 * f, 2.0 * f, 1.00) + o) * m * r,
-						color,
-					);
-					// bottom
-					self.draw_rect(
-						projector,
-						(vec3(0.0 * f, 2.0 * f, 1.00 + d) + o) * m * r,
-						(vec3(2.0 * f, 2.0 * f, 1.00 + d) + o) * m * r,
-						(vec3(2.0 * f, 2.0 * f, 1.00) + o) * m * r,
-						(vec3(0.0 * f, 2.0 * f, 1.00) + o) * m * r,
-						color,
-					);
-					// right
-					self.draw_rect(
-						projector,
-						(vec3(2.0 * f, 0.0 * f, 1.00 + d) + o) * m * r,
-						(vec3(2.0 * f, 0.0 * f, 1.00) + o) * m * r,
-						(vec3(2.0 * f, 2.0 * f, 1.00) + o) * m * r,
-						(vec3(2.0 * f, 2.0 * f, 1.00 + d) + o) * m * r,
-						color,
-					);
-				} else if will_render {
-					self.draw_rect(
+				// Two chevrons per tile, kept clear of the tile outline.
+				for chevron in 0..2 {
+					let t = (chevron as Scalar * 0.5 + phase).fract();
+					let center = t * 0.6 - 0.3;
+					let (back, tip) = (center - 0.12, center + 0.12);
+					self.draw_lines(
 						projector,
-						(vec3(0.0 * f, 0.0 * f, 1.00) + o) * m * r,
-						(vec3(2.0 * f, 0.0 * f, 1.00) + o) * m * r,
-						(vec3(2.0 * f, 2.0 * f, 1.00) + o) * m * r,
-						(vec3(0.0 * f, 2.0 * f, 1.00) + o) * m * r,
+						&[point(back, -0.2), point(tip, 0.0), point(back, 0.2)],
 						color,
 					);
 				}
@@ -398,6 +2066,154 @@ impl Window {
 		}
 	}
 
+	// The triangle on the solid side of a ramp's diagonal, in the tile whose
+	// top left corner is offset `o` from the top left of the frame.
+	fn draw_slope_tile(
+		&mut self,
+		projector: &CameraProjector,
+		tile: Tile,
+		rotation: Angle,
+		o: Vector3,
+		surface_transform: Matrix4x4,
+		view_rotation: Matrix4x4,
+	) {
+		let color = self.palette.get(Role::TileSolid);
+		let m = surface_transform;
+		let r = view_rotation;
+
+		let f = 2.0 / FRAME_WIDTH as Scalar;
+		let point =
+			|x: Scalar, y: Scalar| (vec3(x * f, y * f, 1.0) + o) * m * r;
+		let corners = match tile.ramp_corners(rotation) {
+			Some(corners) => corners,
+			None => return,
+		};
+		let mut outline: Vec<Vector3> = corners
+			.iter()
+			.map(|&(x, y)| point((x + 1.0) / 2.0, (y + 1.0) / 2.0))
+			.collect();
+		outline.push(outline[0]);
+		let thick = self.palette.thickens(Role::TileSolid);
+		self.draw_weighted_lines(projector, &outline, color, thick);
+	}
+
+	fn draw_tile_highlight(
+		&mut self,
+		projector: &CameraProjector,
+		x: usize,
+		y: usize,
+		surface_transform: Matrix4x4,
+		view_rotation: Matrix4x4,
+		role: Role,
+	) {
+		let color = self.palette.get(role);
+		let thick = self.palette.thickens(role);
+		let m = surface_transform;
+		let r = view_rotation;
+
+		let f = 2.0 / FRAME_WIDTH as Scalar;
+		let o = vec3(x as Scalar * f - 1.0, y as Scalar * f - 1.0, 1.0);
+		self.draw_rect(
+			projector,
+			(vec3(0.0, 0.0, 0.0) + o) * m * r,
+			(vec3(f, 0.0, 0.0) + o) * m * r,
+			(vec3(f, f, 0.0) + o) * m * r,
+			(vec3(0.0, f, 0.0) + o) * m * r,
+			color,
+			thick,
+		);
+	}
+
+	// A box along the bottom of the screen with `text` wrapped to fit inside
+	// it.
+	fn draw_text_box(&mut self, text: &str) {
+		let lines = font::wrap(text, self.text_box_columns());
+		self.draw_text_box_lines(&lines, &[]);
+	}
+
+	// How many characters fit across the text box.
+	fn text_box_columns(&self) -> usize {
+		let (viewport_width, _) = self.ui_size();
+		let inset = TEXT_BOX_MARGIN + TEXT_BOX_PADDING;
+		let advance = (font::GLYPH_ADVANCE * TEXT_SCALE) as Scalar;
+		((viewport_width - inset * 2.0) / advance) as usize
+	}
+
+	// The text box around lines already wrapped to fit it, graying out those
+	// true in `muted`. Returns where each line was drawn.
+	fn draw_text_box_lines(
+		&mut self,
+		lines: &[String],
+		muted: &[bool],
+	) -> Vec<ClipRect> {
+		let (viewport_width, viewport_height) = self.ui_size();
+		let inset = TEXT_BOX_MARGIN + TEXT_BOX_PADDING;
+
+		let line_height = (font::LINE_ADVANCE * TEXT_SCALE) as Scalar;
+		let text_height = lines.len() as Scalar * line_height;
+		let top = viewport_height
+			- TEXT_BOX_MARGIN
+			- TEXT_BOX_PADDING * 2.0
+			- text_height;
+
+		let color = self.palette.get(Role::UiText);
+		let left = TEXT_BOX_MARGIN;
+		let right = viewport_width - TEXT_BOX_MARGIN;
+		let bottom = viewport_height - TEXT_BOX_MARGIN;
+		self.draw_ui_lines(
+			&[
+				(left, top),
+				(right, top),
+				(right, bottom),
+				(left, bottom),
+				(left, top),
+			],
+			color,
+		);
+
+		let muted_color = color.mix(self.palette.get(Role::Background), 0.5);
+		let mut bounds = Vec::with_capacity(lines.len());
+		for (index, line) in lines.iter().enumerate() {
+			let y = top + TEXT_BOX_PADDING + index as Scalar * line_height;
+			let color = match muted.get(index) {
+				Some(true) => muted_color,
+				_ => color,
+			};
+			bounds.push(self.draw_text(line, inset, y, TEXT_SCALE, color));
+		}
+		bounds
+	}
+
+	// Draws a single line of text in screen space with its top left corner at
+	// `x`, `y`, in UI reference coordinates. Returns the box the glyphs fill
+	// in the same coordinates, for hit testing.
+	fn draw_text(
+		&mut self,
+		text: &str,
+		x: Scalar,
+		y: Scalar,
+		scale: usize,
+		color: Color,
+	) -> ClipRect {
+		let s = scale as Scalar;
+		for run in font::text_runs(text) {
+			let top = y + run.row as Scalar * s;
+			self.fill_ui_rect(
+				(x + run.start as Scalar * s, top),
+				(x + run.end as Scalar * s, top + s),
+				color,
+			);
+		}
+
+		let columns = text.chars().count() * font::GLYPH_ADVANCE;
+		ClipRect {
+			min_x: x,
+			min_y: y,
+			max_x: x + (columns * scale) as Scalar,
+			max_y: y + (font::GLYPH_HEIGHT * scale) as Scalar,
+		}
+	}
+
 	fn draw_rect(
 		&mut self,
 		projector: &CameraProjector,
@@ -406,60 +2222,142 @@ impl Window {
 		bottom_right: Vector3,
 		bottom_left: Vector3,
 		color: Color,
+		thick: bool,
 	) {
 		let p1 = top_left;
 		let p2 = top_right;
 		let p3 = bottom_right;
 		let p4 = bottom_left;
 
-		if self.is_rect_visible(projector, p1, p2, p3, p4) == false {
+		let (normal, center) = rect_facing(p1, p2, p3, p4);
+		let camera = projector.camera_position();
+		if is_rect_visible(normal, center, camera) == false {
 			return;
 		}
 
-		self.draw_lines(projector, &[top_left, top_right, bottom_right], color);
-		self.draw_lines(
+		let (p1, p2) = (top_left, bottom_right);
+		self.draw_weighted_lines(projector, &[p1, top_right, p2], color, thick);
+		self.draw_weighted_lines(
 			projector,
-			&[top_left, bottom_left, bottom_right],
+			&[p1, bottom_left, p2],
 			color,
+			thick,
 		);
 	}
 
-	fn is_rect_visible(
-		&self,
+	fn draw_lines(
+		&mut self,
 		projector: &CameraProjector,
-		top_left: Vector3,
-		top_right: Vector3,
-		bottom_right: Vector3,
-		bottom_left: Vector3,
-	) -> bool {
-		//return true;
-		let p1 = projector.apply_projection_matrix(top_left * 100.0);
-		let p2 = projector.apply_projection_matrix(top_right * 100.0);
-		let p3 = projector.apply_projection_matrix(bottom_right * 100.0);
-		let normal = geometry::normal(p1, p2, p3);
-		normal.z >= 0.0
+		points: &[Vector3],
+		color: Color,
+	) {
+		self.draw_weighted_lines(projector, points, color, false);
 	}
 
-	fn draw_lines(
+	// `draw_lines`, drawn thicker if `thick`.
+	fn draw_weighted_lines(
 		&mut self,
 		projector: &CameraProjector,
 		points: &[Vector3],
 		color: Color,
+		thick: bool,
 	) {
-		let projected_points: Vec<(f32, f32)> = points
+		let globe = projector.globe();
+		let curved;
+		let points = match globe.is_flat() {
+			true => points,
+			false => {
+				curved = globe.curve(points);
+				&curved
+			}
+		};
+		let projected_points: Vec<(Scalar, Scalar)> = points
 			.iter()
 			.map(|point| {
-				// Magnify for debugging. `* 100.0` should be removed eventually.
-				let (x, y, depth) = projector.project_point(*point * 100.0);
+				let (x, y, _) = projector.project_point(*point);
 				(x, y)
 			})
 			.collect();
 
-		self.backend.set_draw_color(color);
-		self.backend.draw_lines(projected_points.as_slice());
+		self.draw_screen_lines(projected_points.as_slice(), color, thick);
 		//self.backend.draw_line(end_point, start_point);
 	}
 
+	// Many separate segments of the same color, transformed by `m` then `r`,
+	// with the draw color only set once. Each is split up as the globe
+	// needs, and drawn thicker if `thick`.
+	fn draw_segments(
+		&mut self,
+		projector: &CameraProjector,
+		segments: &[(Vector3, Vector3)],
+		m: Matrix4x4,
+		r: Matrix4x4,
+		color: Color,
+		thick: bool,
+	) {
+		let clip_rect = ClipRect::viewport(
+			self.backend.viewport_width() as Scalar,
+			self.backend.viewport_height() as Scalar,
+			CLIP_MARGIN,
+		);
+
+		self.backend.set_draw_color(color);
+		let globe = *projector.globe();
+		for &(start, end) in segments {
+			let (start, end) = (start * m * r, end * m * r);
+			let pieces = globe.pieces(start, end);
+			let point = |piece: usize| {
+				let along = piece as Scalar / pieces as Scalar;
+				let (x, y, _) = projector.project_point(start.mix(end, along));
+				(x, y)
+			};
+			let mut from = point(0);
+			for piece in 1..=pieces {
+				let to = point(piece);
+				if let Some((a, b)) = clip_rect.clip_segment(from, to) {
+					self.backend.draw_line(backend_point(a), backend_point(b));
+					if thick {
+						self.backend.draw_line(
+							backend_point((a.0 + 1.0, a.1 + 1.0)),
+							backend_point((b.0 + 1.0, b.1 + 1.0)),
+						);
+					}
+				}
+				from = to;
+			}
+		}
+	}
+
+	// Draws a polyline already in screen coordinates, clipped to the
+	// viewport, and thicker if `thick`.
+	fn draw_screen_lines(
+		&mut self,
+		points: &[(Scalar, Scalar)],
+		color: Color,
+		thick: bool,
+	) {
+		let clip_rect = ClipRect::viewport(
+			self.backend.viewport_width() as Scalar,
+			self.backend.viewport_height() as Scalar,
+			CLIP_MARGIN,
+		);
+
+		self.backend.set_draw_color(color);
+		for strip in clip_rect.clip_polyline(points) {
+			let backend_strip: Vec<(f32, f32)> =
+				strip.iter().map(|&point| backend_point(point)).collect();
+			self.backend.draw_lines(backend_strip.as_slice());
+
+			if thick {
+				let offset_strip: Vec<(f32, f32)> = backend_strip
+					.iter()
+					.map(|&(x, y)| (x + 1.0, y + 1.0))
+					.collect();
+				self.backend.draw_lines(offset_strip.as_slice());
+			}
+		}
+	}
+
 	fn draw_line(
 		&mut self,
 		projector: &CameraProjector,
@@ -469,4 +2367,318 @@ impl Window {
 	) {
 		self.draw_lines(projector, &[start, end], color);
 	}
+
+	// A line broken into dashes `dash_length` long in world units, with
+	// gaps of the same length between them. The last dash is cut short at
+	// `end`.
+	fn draw_dashed_line(
+		&mut self,
+		projector: &CameraProjector,
+		start: Vector3,
+		end: Vector3,
+		dash_length: Scalar,
+		color: Color,
+	) {
+		let length = (end - start).len();
+		if length == 0.0 || dash_length <= 0.0 {
+			return;
+		}
+		let direction = (end - start) / length;
+
+		let mut segments = Vec::new();
+		let mut offset = 0.0;
+		while offset < length {
+			let dash_end = (offset + dash_length).min(length);
+			segments.push((
+				start + direction * offset,
+				start + direction * dash_end,
+			));
+			offset += dash_length * 2.0;
+		}
+		let identity = Matrix4x4::identity();
+		let (m, r) = (identity, identity);
+		self.draw_segments(projector, &segments, m, r, color, false);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn held(input: &InputState) -> Vec<Keycode> {
+		let mut keys: Vec<_> = input.keys_held.iter().copied().collect();
+		keys.sort_by_key(|keycode| format!("{:?}", keycode));
+		keys
+	}
+
+	// The square from -1 to 1 on the plane `z`, wound so its normal points
+	// towards +z.
+	fn square(z: Scalar) -> (Vector3, Vector3) {
+		rect_facing(
+			vec3(-1.0, -1.0, z),
+			vec3(1.0, -1.0, z),
+			vec3(1.0, 1.0, z),
+			vec3(-1.0, 1.0, z),
+		)
+	}
+
+	#[test]
+	fn a_rect_is_visible_only_from_the_side_it_faces() {
+		let (normal, center) = square(1.0);
+		assert!(is_rect_visible(normal, center, vec3(0.0, 0.0, 5.0)));
+		assert!(is_rect_visible(normal, center, vec3(3.0, -2.0, 1.5)));
+		assert!(is_rect_visible(normal, center, vec3(0.0, 0.0, -5.0)) == false);
+		assert!(is_rect_visible(normal, center, vec3(3.0, 2.0, 0.5)) == false);
+	}
+
+	#[test]
+	fn a_rect_seen_edge_on_is_not_visible() {
+		let (normal, center) = square(1.0);
+		assert!(is_rect_visible(normal, center, vec3(5.0, 0.0, 1.0)) == false);
+		assert!(is_rect_visible(normal, center, vec3(0.0, -9.0, 1.0)) == false);
+	}
+
+	// Behind the camera the perspective divide would flip the winding of
+	// projected points, which the test in world space isn't fooled by. The
+	// camera looks down -z, so +z is behind it.
+	#[test]
+	fn a_rect_behind_the_camera_faces_the_way_it_does_in_front() {
+		let camera = vec3(0.0, 0.0, 0.0);
+		let (normal, in_front) = square(-2.0);
+		assert!(is_rect_visible(normal, in_front, camera));
+		assert!(is_rect_visible(-normal, in_front, camera) == false);
+		let (normal, behind) = square(2.0);
+		assert!(is_rect_visible(normal, behind, camera) == false);
+		assert!(is_rect_visible(-normal, behind, camera));
+	}
+
+	#[test]
+	fn a_rect_with_no_area_is_never_visible() {
+		let corner = vec3(1.0, 1.0, 1.0);
+		let (normal, center) = rect_facing(corner, corner, corner, corner);
+		assert!(is_rect_visible(normal, center, vec3(0.0, 0.0, 5.0)) == false);
+	}
+
+	#[test]
+	fn a_key_down_repeated_is_pressed_once() {
+		let mut input = InputState::new();
+		input.key_down_event(Keycode::A);
+		input.clear_frame();
+		input.key_down_event(Keycode::A);
+		assert!(input.keys_pressed.is_empty());
+		assert_eq!(held(&input), vec![Keycode::A]);
+	}
+
+	#[test]
+	fn a_release_that_never_came_is_made_by_a_sync() {
+		let mut input = InputState::new();
+		input.key_down_event(Keycode::A);
+		input.key_down_event(Keycode::D);
+		input.clear_frame();
+		input.sync_keys(&[Keycode::D]);
+		assert_eq!(held(&input), vec![Keycode::D]);
+		assert!(input.keys_pressed.is_empty());
+	}
+
+	#[test]
+	fn a_press_that_never_came_is_held_without_being_pressed() {
+		let mut input = InputState::new();
+		input.sync_keys(&[Keycode::A]);
+		assert_eq!(held(&input), vec![Keycode::A]);
+		assert!(input.keys_pressed.is_empty());
+		input.key_down_event(Keycode::A);
+		assert!(input.keys_pressed.is_empty());
+	}
+
+	#[test]
+	fn keys_listed_twice_are_held_once() {
+		let mut input = InputState::new();
+		input.sync_keys(&[Keycode::A, Keycode::A, Keycode::D]);
+		assert_eq!(held(&input), vec![Keycode::A, Keycode::D]);
+		input.sync_keys(&[]);
+		assert!(input.keys_held.is_empty());
+	}
+
+	#[test]
+	fn a_sync_outweighs_the_events_before_it() {
+		let mut input = InputState::new();
+		input.key_down_event(Keycode::A);
+		input.key_up_event(Keycode::A);
+		input.sync_keys(&[Keycode::A]);
+		assert_eq!(held(&input), vec![Keycode::A]);
+
+		input.clear_frame();
+		input.key_down_event(Keycode::D);
+		input.sync_keys(&[]);
+		assert!(input.keys_held.is_empty());
+		// The press still happened, even if the key is already up again.
+		assert!(input.keys_pressed.contains(&Keycode::D));
+	}
+
+	// Frames drawn after a key is pressed before the player starts moving
+	// because of it, stepping the world exactly once a frame.
+	#[cfg(feature = "headless")]
+	fn frames_until_moving(press_after: usize) -> usize {
+		let mut level = String::from("frame 0\n");
+		for y in 0..FRAME_WIDTH {
+			let row = if y < 13 { '.' } else { '#' };
+			level.extend(std::iter::repeat_n(row, FRAME_WIDTH));
+			level += "\n";
+		}
+		level += "spawn 0 0.0 0.6\n";
+		let mut game_state = GameState::from_level_source(&level).unwrap();
+		let mut window = Window::headless().unwrap();
+		let step = |window: &mut Window, game_state: &mut GameState| {
+			window.last_frame_time = None;
+			window.tick_accumulator = window.tick_rate.step_duration();
+			window.tick(game_state);
+		};
+		for _ in 0..press_after {
+			step(&mut window, &mut game_state);
+		}
+		window.backend.push_event(WindowEvent::KeyDown(Keycode::D));
+		for frames in 0..10 {
+			step(&mut window, &mut game_state);
+			let world = &game_state.world;
+			let player = world.get_entity(world.focus_entity.unwrap());
+			if player.unwrap().velocity.x > 0.0 {
+				return frames;
+			}
+		}
+		panic!("the press never moved the player");
+	}
+
+	#[cfg(feature = "headless")]
+	#[test]
+	fn a_key_pressed_moves_the_player_on_the_very_next_step() {
+		assert_eq!(frames_until_moving(0), 0);
+		assert_eq!(frames_until_moving(5), 0);
+	}
+
+	// Where the middle of a tile was drawn in the last rendered frame, if
+	// its face was turned towards the camera.
+	#[cfg(feature = "headless")]
+	fn drawn_tile_center(
+		window: &Window,
+		world: &World,
+		(frame_id, x, y): (FrameId, usize, usize),
+	) -> Option<(Scalar, Scalar)> {
+		let view = window.pick_view.unwrap();
+		let transforms = world.surface_transforms(view.focus);
+		let &(_, m) = transforms.iter().find(|&&(id, _)| id == frame_id)?;
+		let r = view.view_rotation;
+		let (normal, center) = rect_facing(
+			vec3(-1.0, -1.0, 1.0) * m * r,
+			vec3(1.0, -1.0, 1.0) * m * r,
+			vec3(1.0, 1.0, 1.0) * m * r,
+			vec3(-1.0, 1.0, 1.0) * m * r,
+		);
+		let camera = view.projector.camera_position();
+		if is_rect_visible(normal, center, camera) == false {
+			return None;
+		}
+		let local = |index: usize| -1.0 + (index as Scalar + 0.5) * TILE_SIZE;
+		let point = vec3(local(x), local(y), 1.0) * m * r;
+		let (screen_x, screen_y, _) = view.projector.project_point(point);
+		Some((screen_x, screen_y))
+	}
+
+	#[cfg(feature = "headless")]
+	#[test]
+	fn the_tile_drawn_under_the_pointer_is_the_one_picked() {
+		let mut game_state = GameState::from_world(World::new());
+		// Near a corner, so that the view turns to show the faces there.
+		let id = game_state.world.focus_entity.unwrap();
+		let player = game_state.world.get_entity_mut(id).unwrap();
+		player.position.x = 0.9;
+		player.position.y = 0.9;
+		let mut window = Window::headless().unwrap();
+		window.tick(&mut game_state);
+		window.render(&mut game_state);
+		let world = &game_state.world;
+
+		let mut picked = 0;
+		for frame_id in world.frame_ids() {
+			for (x, y) in (0..FRAME_WIDTH)
+				.flat_map(|y| (0..FRAME_WIDTH).map(move |x| (x, y)))
+			{
+				let tile = (frame_id, x, y);
+				let center = match drawn_tile_center(&window, world, tile) {
+					Some(center) => center,
+					None => continue,
+				};
+				assert_eq!(window.pick_tile(world, center), Some(tile));
+				picked += 1;
+			}
+		}
+		// More than just the front face.
+		let face = FRAME_WIDTH * FRAME_WIDTH;
+		assert!(picked > face, "only {} tiles were facing", picked);
+	}
+
+	// Where lines of each role were first drawn in the last frame.
+	#[cfg(feature = "headless")]
+	fn first_drawn(window: &Window, role: Role) -> usize {
+		let color = window.palette.get(role);
+		let lines = window.drawn_lines();
+		lines.iter().position(|line| line.color == color).unwrap()
+	}
+
+	// A coin and a walker right on top of the player, drawn under them,
+	// unless the player is put on a layer of their own underneath.
+	#[cfg(feature = "headless")]
+	#[test]
+	fn entities_on_top_of_each_other_are_drawn_by_layer() {
+		use crate::world::EdgeBehavior;
+
+		let mut game_state = GameState::from_world(World::new());
+		let world = &mut game_state.world;
+		let id = world.focus_entity.unwrap();
+		let position = world.get_entity(id).unwrap().position;
+		let walker = Entity::new_walker(world, position, EdgeBehavior::WalkOff);
+		world.insert_entity(walker);
+		let coin = Entity::new_coin(world, position);
+		world.insert_entity(coin);
+		let mut window = Window::headless().unwrap();
+		window.render(&mut game_state);
+		let coin = first_drawn(&window, Role::Coin);
+		let walker = first_drawn(&window, Role::Enemy);
+		let player = first_drawn(&window, Role::Player);
+		assert!(
+			coin < walker && walker < player,
+			"{:?}",
+			(coin, walker, player)
+		);
+
+		let player = game_state.world.get_entity_mut(id).unwrap();
+		player.render_layer = Some(-1);
+		window.render(&mut game_state);
+		let coin = first_drawn(&window, Role::Coin);
+		let player = first_drawn(&window, Role::Player);
+		assert!(player < coin, "{} {}", player, coin);
+	}
+
+	// Frames that each come far too late run no more steps than a frame
+	// is allowed, and before long the world drops to a lower rate.
+	#[cfg(feature = "headless")]
+	#[test]
+	fn falling_behind_drops_the_tick_rate() {
+		let mut game_state = GameState::from_world(World::new());
+		let mut window = Window::headless().unwrap();
+		let mut frames = 0;
+		while game_state.world.ticks_per_step() == 1 {
+			assert!(frames < 100, "the rate never dropped");
+			let late = window.tick_rate.step_duration() * 20.0;
+			window.last_frame_time = Some(backend::time::now() - late);
+			window.tick_accumulator = 0.0;
+			let before = game_state.world.tick_count;
+			window.tick(&mut game_state);
+			let ran = game_state.world.tick_count - before;
+			let per_step = game_state.world.ticks_per_step() as u64;
+			assert!(ran <= MAX_TICKS_PER_FRAME as u64 * per_step, "{}", ran);
+			frames += 1;
+		}
+		assert!(frames > 1);
+		assert_eq!(window.tick_rate.rate(), crate::world::TICK_RATE / 2.0);
+	}
 }
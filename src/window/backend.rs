@@ -4,6 +4,16 @@ mod wasm;
 pub use wasm::*;
 
 #[cfg(not(target_arch = "wasm32"))]
-mod sdl;
+mod native;
 #[cfg(not(target_arch = "wasm32"))]
+pub use native::*;
+
+#[cfg(all(not(target_arch = "wasm32"), not(feature = "headless")))]
+mod sdl;
+#[cfg(all(not(target_arch = "wasm32"), not(feature = "headless")))]
 pub use sdl::*;
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "headless"))]
+mod headless;
+#[cfg(all(not(target_arch = "wasm32"), feature = "headless"))]
+pub use headless::*;
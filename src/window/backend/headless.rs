@@ -0,0 +1,190 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::VecDeque;
+use std::hash::Hasher;
+
+use super::super::{BackendError, Color, WindowConfig, WindowEvent};
+
+// Coordinates are compared to the nearest this many pixels, so that
+// rounding that differs from one build to the next isn't a difference.
+const COMPARED_PRECISION: f32 = 0.1;
+
+// A line as it was drawn, in viewport pixels.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DrawnLine {
+	pub color: Color,
+	pub start: (f32, f32),
+	pub end: (f32, f32),
+}
+
+// The lines of a frame as text, one per line, for comparing what two
+// builds draw. Within each run of lines of the same color the order they
+// were drawn in doesn't show, so drawing a batch in another order is no
+// difference, while drawing one color over another in another order is.
+pub fn normalized_lines(lines: &[DrawnLine]) -> Vec<String> {
+	let round = |value: f32| {
+		let rounded = (value / COMPARED_PRECISION).round() * COMPARED_PRECISION;
+		// Never "-0.0", which is the same place as "0.0".
+		format!("{:.1}", rounded + 0.0)
+	};
+	let mut normalized = Vec::with_capacity(lines.len());
+	let mut batch: Vec<String> = Vec::new();
+	for (i, line) in lines.iter().enumerate() {
+		let Color { r, g, b } = line.color;
+		batch.push(format!(
+			"{:02x}{:02x}{:02x} {} {} {} {}",
+			r,
+			g,
+			b,
+			round(line.start.0),
+			round(line.start.1),
+			round(line.end.0),
+			round(line.end.1)
+		));
+		let next = lines.get(i + 1).map(|next| next.color);
+		if next != Some(line.color) {
+			batch.sort();
+			normalized.append(&mut batch);
+		}
+	}
+	normalized
+}
+
+// A hash of `normalized_lines`, the same on every build.
+pub fn lines_hash(normalized: &[String]) -> u64 {
+	// `DefaultHasher::new` always starts from the same keys, unlike the
+	// `RandomState` a `HashMap` is given.
+	let mut hasher = DefaultHasher::new();
+	for line in normalized {
+		hasher.write(line.as_bytes());
+		hasher.write_u8(b'\n');
+	}
+	hasher.finish()
+}
+
+// A sound as it was played, with its gain from zero to one and its pan
+// from -1 on the left to 1 on the right.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PlayedSound {
+	pub id: u32,
+	pub gain: f32,
+	pub pan: f32,
+}
+
+// Draws nothing anywhere, keeping the lines of the frame being drawn in the
+// order they were drawn instead, and plays nothing, keeping every sound.
+// Events come in only when they are pushed, as tests do.
+pub struct Backend {
+	width: u32,
+	height: u32,
+	render_scale: u32,
+	color: Color,
+	// Since the canvas was last cleared.
+	lines: Vec<DrawnLine>,
+	sounds: Vec<PlayedSound>,
+	events: VecDeque<WindowEvent>,
+}
+
+impl Backend {
+	pub fn new(config: &WindowConfig) -> Result<Self, BackendError> {
+		Ok(Self {
+			width: config.width,
+			height: config.height,
+			render_scale: 1,
+			color: Color { r: 0, g: 0, b: 0 },
+			lines: Vec::new(),
+			sounds: Vec::new(),
+			events: VecDeque::new(),
+		})
+	}
+
+	pub fn lines(&self) -> &[DrawnLine] {
+		&self.lines
+	}
+
+	pub fn sounds(&self) -> &[PlayedSound] {
+		&self.sounds
+	}
+
+	pub fn play_sound(&mut self, id: u32, gain: f32, pan: f32) {
+		self.sounds.push(PlayedSound { id, gain, pan });
+	}
+
+	pub fn viewport_width(&self) -> u32 {
+		(self.width / self.render_scale).max(1)
+	}
+
+	pub fn viewport_height(&self) -> u32 {
+		(self.height / self.render_scale).max(1)
+	}
+
+	pub fn set_render_scale(&mut self, scale: u32) {
+		self.render_scale = scale.max(1);
+	}
+
+	pub fn set_vsync(&mut self, _vsync: bool) {}
+
+	pub fn clear_canvas(&mut self, color: Color) {
+		self.color = color;
+		self.lines.clear();
+	}
+
+	pub fn update_canvas(&mut self) {}
+
+	pub fn supports_read_pixels(&self) -> bool {
+		false
+	}
+
+	pub fn read_pixels(&self) -> Result<(u32, u32, Vec<u8>), String> {
+		Err("nothing is drawn without a window".to_string())
+	}
+
+	pub fn set_draw_color(&mut self, color: Color) {
+		self.color = color;
+	}
+
+	pub fn draw_line(&mut self, start: (f32, f32), end: (f32, f32)) {
+		self.lines.push(DrawnLine {
+			color: self.color,
+			start,
+			end,
+		});
+	}
+
+	pub fn draw_lines(&mut self, lines: &[(f32, f32)]) {
+		for pair in lines.windows(2) {
+			self.draw_line(pair[0], pair[1]);
+		}
+	}
+
+	pub fn poll_event(&mut self) -> Option<WindowEvent> {
+		self.events.pop_front()
+	}
+
+	// Comes in at the next poll, after any pushed before it.
+	#[cfg(test)]
+	pub fn push_event(&mut self, event: WindowEvent) {
+		self.events.push_back(event);
+	}
+
+	pub fn open_debug_window(
+		&mut self,
+		_title: &str,
+		_width: u32,
+		_height: u32,
+	) -> Result<(), String> {
+		Err("there are no windows to open".to_string())
+	}
+
+	pub fn close_debug_window(&mut self) {}
+
+	pub fn debug_window_size(&self) -> Option<(u32, u32)> {
+		None
+	}
+
+	pub fn draw_debug_window(
+		&mut self,
+		_background: Color,
+		_paths: &[(Color, Vec<(f32, f32)>)],
+	) {
+	}
+}
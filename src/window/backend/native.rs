@@ -0,0 +1,199 @@
+use std::time::{Duration, Instant};
+
+use super::super::super::GameState;
+use super::super::Window;
+
+// What every build off the web has, whatever it draws with: the loop, the
+// clock, files and the rest.
+
+// The frame limiter sleeps until this long before the deadline, then spins
+// the rest of the way, since sleeps can overshoot by a millisecond or more.
+const LIMITER_SPIN_MARGIN: Duration = Duration::from_millis(2);
+
+pub fn begin_loop(
+	mut window: Window,
+	mut game_state: GameState,
+	closure: impl Fn(&mut Window, &mut GameState),
+) {
+	loop {
+		let frame_start = Instant::now();
+		closure(&mut window, &mut game_state);
+		if window.late_input() {
+			window.poll_events(&mut game_state);
+		}
+		if window.should_exit {
+			break;
+		}
+
+		if let Some(frame_time) = window.frame_time_limit() {
+			let deadline = frame_start + Duration::from_secs_f64(frame_time);
+			wait_until(deadline);
+		}
+	}
+}
+
+fn wait_until(deadline: Instant) {
+	let now = Instant::now();
+	if deadline > now + LIMITER_SPIN_MARGIN {
+		std::thread::sleep(deadline - now - LIMITER_SPIN_MARGIN);
+	}
+	while Instant::now() < deadline {
+		std::hint::spin_loop();
+	}
+}
+
+pub mod external_exports {}
+
+pub mod time {
+	use std::time::Instant;
+
+	lazy_static! {
+		static ref START: Instant = Instant::now();
+	}
+
+	// Seconds since some fixed point early in the program's life.
+	pub fn now() -> f64 {
+		START.elapsed().as_secs_f64()
+	}
+
+	// Seconds since the Unix epoch, for comparing across runs.
+	pub fn unix_time() -> f64 {
+		std::time::SystemTime::now()
+			.duration_since(std::time::UNIX_EPOCH)
+			.map_or(0.0, |duration| duration.as_secs_f64())
+	}
+}
+
+// Named pieces of text that persist between runs, kept as files in the
+// working directory.
+pub mod storage {
+	use crate::prelude::*;
+
+	pub fn read(name: &str) -> Option<String> {
+		std::fs::read_to_string(name).ok()
+	}
+
+	// Makes any directories in `name` that don't exist yet.
+	pub fn write(name: &str, contents: &str) -> Result<(), String> {
+		if let Some(parent) = std::path::Path::new(name).parent() {
+			std::fs::create_dir_all(parent)
+				.map_err(|error| error.to_string())?;
+		}
+		std::fs::write(name, contents).map_err(|error| error.to_string())
+	}
+
+	pub fn remove(name: &str) -> Result<(), String> {
+		std::fs::remove_file(name).map_err(|error| error.to_string())
+	}
+
+	// The names of everything stored under `directory`, in order, ready to
+	// pass to `read`. Empty if there is no such directory.
+	pub fn list(directory: &str) -> Vec<String> {
+		let entries = match std::fs::read_dir(directory) {
+			Ok(entries) => entries,
+			Err(_) => return Vec::new(),
+		};
+		let mut names: Vec<String> = entries
+			.filter_map(|entry| entry.ok())
+			.filter(|entry| entry.path().is_file())
+			.filter_map(|entry| entry.file_name().into_string().ok())
+			.map(|file| format!("{}/{}", directory, file))
+			.collect();
+		names.sort();
+		names
+	}
+
+	// Returns straight away, logging any failure once the write finishes.
+	pub fn write_in_background(name: &str, contents: String) {
+		let name = name.to_string();
+		std::thread::spawn(move || {
+			if let Err(error) = write(&name, &contents) {
+				elog(format!("Could not write {}: {}", name, error));
+			}
+		});
+	}
+}
+
+// A file mirroring the log, for looking back over long sessions.
+pub mod log_file {
+	use std::fs::File;
+	use std::io::{BufWriter, Write};
+	use std::sync::Mutex;
+	use std::time::{Duration, Instant};
+
+	// Lines are buffered, and written out at least this often.
+	const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+	struct LogFile {
+		writer: BufWriter<File>,
+		last_flush: Instant,
+	}
+
+	lazy_static! {
+		static ref FILE: Mutex<Option<LogFile>> = Mutex::new(None);
+	}
+
+	// Starts the file afresh, replacing any log from an earlier run.
+	pub fn open(path: &str) -> Result<(), String> {
+		let file = File::create(path).map_err(|error| error.to_string())?;
+		*FILE.lock().unwrap() = Some(LogFile {
+			writer: BufWriter::new(file),
+			last_flush: Instant::now(),
+		});
+		Ok(())
+	}
+
+	// A failed write closes the file rather than failing again on every
+	// line after it.
+	pub fn write(line: &str) {
+		let mut file = FILE.lock().unwrap();
+		if let Some(log) = file.as_mut() {
+			if writeln!(log.writer, "{}", line).is_err() {
+				*file = None;
+				eprintln!("Could not write to the log file, closing it");
+				return;
+			}
+		}
+		drop(file);
+		flush_if_due();
+	}
+
+	pub fn flush_if_due() {
+		let due = FILE
+			.lock()
+			.unwrap()
+			.as_ref()
+			.is_some_and(|log| log.last_flush.elapsed() >= FLUSH_INTERVAL);
+		if due {
+			flush();
+		}
+	}
+
+	// Doesn't wait on the lock, since a panic can come from inside `write`
+	// while it is held.
+	pub fn flush() {
+		if let Ok(mut file) = FILE.try_lock() {
+			if let Some(log) = file.as_mut() {
+				log.writer.flush().ok();
+				log.last_flush = Instant::now();
+			}
+		}
+	}
+}
+
+pub mod threads {
+	// Whether work can be handed to a thread of its own.
+	pub const AVAILABLE: bool = true;
+}
+
+pub mod random {
+	// Different every run, from the operating system's entropy source. Only
+	// for seeding, since worlds draw everything else from their own `Rng`.
+	pub fn entropy_seed() -> u64 {
+		rand::random()
+	}
+}
+
+pub fn print(msg: &str) {
+	println!("{}", msg);
+}
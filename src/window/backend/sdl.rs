@@ -1,44 +1,33 @@
 use std::convert::From;
 
+use sdl2::controller::{Axis as SdlAxis, Button as SdlButton, GameController};
 use sdl2::keyboard::Keycode as SdlKeycode;
+use sdl2::mouse::{MouseButton as SdlMouseButton, MouseWheelDirection};
 use sdl2::pixels::Color as SdlColor;
 use sdl2::rect::Point as SdlPoint;
-use sdl2::render::Canvas;
-
-use super::super::{Color, Keycode, WindowEvent, Window};
-use super::super::super::GameState;
-
-pub fn begin_loop(
-	mut window: Window,
-	mut game_state: GameState,
-	closure: impl Fn(&mut Window, &mut GameState),
-) {
-	loop {
-		closure(&mut window, &mut game_state);
-		if window.should_exit {
-			break;
-		}
-	}
-}
-
-pub mod external_exports {}
-
-pub mod random {
-	use rand::Rng;
+use sdl2::render::{Canvas, Texture};
 
-	pub fn rangei(start: isize, end: isize) -> isize {
-		let mut rng = rand::thread_rng();
-		rng.gen_range(start, end)
-	}
-}
-
-pub fn print(msg: &str) {
-	println!("{}", msg);
-}
+use super::super::{
+	BackendError, Color, GamepadAxis, GamepadButton, Keycode, MouseButton,
+	WindowConfig, WindowEvent,
+};
+use super::native::print;
 
 pub struct Backend {
 	sdl: sdl2::Sdl,
 	canvas: Canvas<sdl2::video::Window>,
+	vsync: bool,
+	render_scale: u32,
+	// Everything is drawn into this when `render_scale` is above one, then
+	// stretched over the window in `update_canvas`.
+	render_target: Option<Texture>,
+	// Second window for debug views, opened on request.
+	debug_canvas: Option<Canvas<sdl2::video::Window>>,
+	// `None` if SDL couldn't start its game controller support.
+	controller_subsystem: Option<sdl2::GameControllerSubsystem>,
+	// Gamepads only send events while open, so each is opened as it is
+	// connected.
+	controllers: Vec<GameController>,
 }
 
 macro_rules! match_keycodes {
@@ -65,51 +54,261 @@ macro_rules! match_keycodes {
 impl From<SdlKeycode> for Keycode {
 	fn from(sdl_keycode: SdlKeycode) -> Keycode {
 		match_keycodes!(sdl_keycode {
-			...(W, S, A, D, Q, E, Escape),
+			...(W, S, A, D, Q, E, R, F, G, H, M, P, T, Escape, F3, F9),
+			...(C, Backquote, Backspace, Tab, Delete, Return, Space),
+			...(Up, Down, Left, Right, PageUp, PageDown),
+			SdlKeycode::LShift => Keycode::Shift,
+			SdlKeycode::RShift => Keycode::Shift,
 			_ => Keycode::Unknown,
 		})
 	}
 }
 
+impl From<SdlMouseButton> for MouseButton {
+	fn from(sdl_button: SdlMouseButton) -> MouseButton {
+		match sdl_button {
+			SdlMouseButton::Left => MouseButton::Left,
+			SdlMouseButton::Middle => MouseButton::Middle,
+			SdlMouseButton::Right => MouseButton::Right,
+			_ => MouseButton::Unknown,
+		}
+	}
+}
+
+impl From<SdlButton> for GamepadButton {
+	fn from(sdl_button: SdlButton) -> GamepadButton {
+		match sdl_button {
+			SdlButton::DPadUp => GamepadButton::Up,
+			SdlButton::DPadDown => GamepadButton::Down,
+			SdlButton::DPadLeft => GamepadButton::Left,
+			SdlButton::DPadRight => GamepadButton::Right,
+			SdlButton::A => GamepadButton::Confirm,
+			SdlButton::B => GamepadButton::Cancel,
+			SdlButton::Start => GamepadButton::Start,
+			_ => GamepadButton::Unknown,
+		}
+	}
+}
+
 impl From<Color> for SdlColor {
 	fn from(color: Color) -> SdlColor {
 		SdlColor::RGB(color.r, color.g, color.b)
 	}
 }
 
+// Lines are clipped to the viewport before they get here, but SDL does its
+// own arithmetic on point coordinates, so clamp well inside the i32 range
+// rather than relying on whatever the clipper let through.
+const SDL_COORDINATE_LIMIT: f32 = 1_000_000.0;
+
+fn sdl_coordinate(value: f32) -> i32 {
+	if value.is_nan() {
+		return 0;
+	}
+	value.clamp(-SDL_COORDINATE_LIMIT, SDL_COORDINATE_LIMIT) as i32
+}
+
+fn build_canvas(
+	window: sdl2::video::Window,
+	vsync: bool,
+) -> Result<Canvas<sdl2::video::Window>, String> {
+	let builder = window.into_canvas();
+	let builder = if vsync {
+		builder.present_vsync()
+	} else {
+		builder
+	};
+	builder.build().map_err(|error| error.to_string())
+}
+
 impl Backend {
-	pub fn new() -> Self {
-		let sdl = sdl2::init().unwrap();
-		let video_subsystem = sdl.video().unwrap();
+	pub fn new(config: &WindowConfig) -> Result<Self, BackendError> {
+		// Said alongside every error, since problems often come down to
+		// which SDL was found.
+		let with_version = |error: String| {
+			format!("{} (SDL {})", error, sdl2::version::version())
+		};
+		let sdl = sdl2::init()
+			.map_err(|error| BackendError::Init(with_version(error)))?;
+		let video_subsystem = sdl
+			.video()
+			.map_err(|error| BackendError::Video(with_version(error)))?;
 		let window = video_subsystem
-			.window("cube", 900, 700)
+			.window(&config.title, config.width, config.height)
 			.resizable()
 			.build()
-			.unwrap();
-		let mut canvas = window.into_canvas().present_vsync().build().unwrap();
+			.map_err(|error| {
+				BackendError::Window(with_version(error.to_string()))
+			})?;
+		let canvas = build_canvas(window, config.vsync)
+			.map_err(|error| BackendError::Renderer(with_version(error)))?;
+		// Nearest-neighbor scaling for the low resolution render target.
+		sdl2::hint::set("SDL_RENDER_SCALE_QUALITY", "0");
+		// Gamepads already connected are announced as if just added, so
+		// they are opened along with any connected later.
+		let controller_subsystem = sdl
+			.game_controller()
+			.map_err(|error| {
+				print(&format!("Could not start gamepad support: {}", error))
+			})
+			.ok();
+
+		Ok(Self {
+			sdl,
+			canvas,
+			vsync: config.vsync,
+			render_scale: 1,
+			render_target: None,
+			debug_canvas: None,
+			controller_subsystem,
+			controllers: Vec::new(),
+		})
+	}
 
-		Self { sdl, canvas }
+	fn open_controller(&mut self, index: u32) {
+		let subsystem = match self.controller_subsystem.as_ref() {
+			Some(subsystem) => subsystem,
+			None => return,
+		};
+		match subsystem.open(index) {
+			Ok(controller) => self.controllers.push(controller),
+			Err(error) => print(&format!("Could not open gamepad: {}", error)),
+		}
+	}
+
+	// Size of the drawing area, which is smaller than the window when
+	// rendering at a reduced scale.
+	fn internal_size(&self) -> (u32, u32) {
+		let (width, height) = self.canvas.window().drawable_size();
+		let scale = self.render_scale;
+		((width / scale).max(1), (height / scale).max(1))
 	}
 
 	pub fn viewport_width(&self) -> u32 {
-		self.canvas.viewport().width()
+		self.internal_size().0
 	}
 
 	pub fn viewport_height(&self) -> u32 {
-		self.canvas.viewport().height()
+		self.internal_size().1
+	}
+
+	// How many window pixels each drawn pixel covers. Takes effect from the
+	// next frame.
+	pub fn set_render_scale(&mut self, scale: u32) {
+		self.render_scale = scale.max(1);
+	}
+
+	// SDL can only change vsync by creating a new renderer, so the canvas is
+	// rebuilt around the same window.
+	pub fn set_vsync(&mut self, vsync: bool) {
+		if vsync == self.vsync {
+			return;
+		}
+		// Textures belong to the renderer being replaced.
+		self.release_render_target();
+		self.vsync = vsync;
+
+		// `into_window` consumes the canvas, so it is moved out and the new
+		// one written back in its place. Nothing in between may unwind, or
+		// the old canvas would be dropped twice.
+		unsafe {
+			let canvas = std::ptr::read(&self.canvas);
+			let rebuilt = match build_canvas(canvas.into_window(), vsync) {
+				Ok(canvas) => canvas,
+				Err(error) => {
+					print(&format!("Could not rebuild canvas: {}", error));
+					std::process::abort();
+				}
+			};
+			std::ptr::write(&mut self.canvas, rebuilt);
+		}
+	}
+
+	// Points subsequent drawing at the render target, creating or resizing
+	// it as needed.
+	fn bind_render_target(&mut self) {
+		if self.render_scale == 1 {
+			self.release_render_target();
+			return;
+		}
+
+		let (width, height) = self.internal_size();
+		let up_to_date = self.render_target.as_ref().is_some_and(|target| {
+			let query = target.query();
+			query.width == width && query.height == height
+		});
+		if up_to_date == false {
+			self.release_render_target();
+			match self.canvas.create_texture_target(None, width, height) {
+				Ok(target) => self.render_target = Some(target),
+				Err(error) => {
+					print(&format!(
+						"Could not create render target: {}",
+						error
+					));
+					self.render_scale = 1;
+					return;
+				}
+			}
+		}
+
+		let target = self.render_target.as_ref().unwrap();
+		unsafe {
+			sdl2::sys::SDL_SetRenderTarget(self.canvas.raw(), target.raw());
+		}
+	}
+
+	fn release_render_target(&mut self) {
+		if let Some(target) = self.render_target.take() {
+			unsafe {
+				sdl2::sys::SDL_SetRenderTarget(
+					self.canvas.raw(),
+					std::ptr::null_mut(),
+				);
+				target.destroy();
+			}
+		}
 	}
 
-	pub fn clear_canvas(&mut self) {
-		self.canvas.set_draw_color(SdlColor::BLACK);
+	pub fn clear_canvas(&mut self, color: Color) {
+		self.bind_render_target();
+		self.canvas.set_draw_color(color);
 		self.canvas.clear();
 	}
 
 	pub fn update_canvas(&mut self) {
+		if let Some(target) = self.render_target.as_ref() {
+			unsafe {
+				sdl2::sys::SDL_SetRenderTarget(
+					self.canvas.raw(),
+					std::ptr::null_mut(),
+				);
+			}
+			if let Err(error) = self.canvas.copy(target, None, None) {
+				print(&format!("Could not present render target: {}", error));
+			}
+		}
 		self.canvas.present();
 	}
 
+	pub fn supports_read_pixels(&self) -> bool {
+		true
+	}
+
+	// The frame drawn so far as RGB bytes, row by row, at the drawing
+	// resolution rather than the window's.
+	pub fn read_pixels(&self) -> Result<(u32, u32, Vec<u8>), String> {
+		let (width, height) = self.internal_size();
+		let format = sdl2::pixels::PixelFormatEnum::RGB24;
+		// The whole render target, which is smaller than the window's
+		// output when rendering at a reduced scale.
+		let area = sdl2::rect::Rect::new(0, 0, width, height);
+		let pixels = self.canvas.read_pixels(area, format)?;
+		Ok((width, height, pixels))
+	}
+
 	pub fn set_draw_color(&mut self, color: Color) {
-		let sdl_color = self.canvas.set_draw_color(color);
+		self.canvas.set_draw_color(color);
 	}
 
 	pub fn draw_line(&mut self, start: (f32, f32), end: (f32, f32)) {
@@ -119,31 +318,151 @@ impl Backend {
 	pub fn draw_lines(&mut self, lines: &[(f32, f32)]) {
 		let lines: Vec<SdlPoint> = lines
 			.iter()
-			.map(|&(x, y)| (x as i32, y as i32).into())
+			.map(|&(x, y)| (sdl_coordinate(x), sdl_coordinate(y)).into())
 			.collect();
 
-		self.canvas.draw_lines(lines.as_slice());
+		// A line SDL can't draw is left out rather than stopping the frame.
+		self.canvas.draw_lines(lines.as_slice()).ok();
 	}
 
-	pub fn poll_event(&mut self) -> Option<WindowEvent> {
-		let sdl_event = self.sdl.event_pump().unwrap().poll_event();
-		if (sdl_event.is_none()) {
-			return None;
-		}
+	// There is no mixer here yet, so only the web plays sounds.
+	pub fn play_sound(&mut self, _id: u32, _gain: f32, _pan: f32) {}
 
+	pub fn poll_event(&mut self) -> Option<WindowEvent> {
 		use sdl2::event::Event as S;
+		use sdl2::event::WindowEvent as SdlWindowEvent;
 		use WindowEvent as W;
-		Some(match sdl_event.unwrap() {
-			S::Quit { .. } => W::Quit,
-			S::KeyDown {
-				keycode: Some(keycode),
-				..
-			} => W::KeyDown(keycode.into()),
-			S::KeyUp {
-				keycode: Some(keycode),
-				..
-			} => W::KeyUp(keycode.into()),
-			_ => return None,
-		})
+
+		let mut event_pump = self.sdl.event_pump().unwrap();
+		loop {
+			let sdl_event = event_pump.poll_event()?;
+
+			// Nothing from the debug window reaches the game, and closing it
+			// only closes it.
+			let debug_id = self.debug_canvas.as_ref().map(|c| c.window().id());
+			if debug_id.is_some() && sdl_event.get_window_id() == debug_id {
+				if let S::Window {
+					win_event: SdlWindowEvent::Close,
+					..
+				} = sdl_event
+				{
+					self.close_debug_window();
+				}
+				continue;
+			}
+
+			return Some(match sdl_event {
+				S::Quit { .. } => W::Quit,
+				// SDL only sends `Quit` once the last window is closed.
+				S::Window {
+					win_event: SdlWindowEvent::Close,
+					..
+				} => W::Quit,
+				S::KeyDown {
+					keycode: Some(keycode),
+					..
+				} => W::KeyDown(keycode.into()),
+				S::KeyUp {
+					keycode: Some(keycode),
+					..
+				} => W::KeyUp(keycode.into()),
+				S::MouseMotion { x, y, .. } => {
+					let scale = self.render_scale as f32;
+					W::MouseMove(x as f32 / scale, y as f32 / scale)
+				}
+				S::MouseButtonDown { mouse_btn, .. } => {
+					W::MouseDown(mouse_btn.into())
+				}
+				S::MouseButtonUp { mouse_btn, .. } => {
+					W::MouseUp(mouse_btn.into())
+				}
+				S::MouseWheel { y, direction, .. } => match direction {
+					MouseWheelDirection::Flipped => W::MouseWheel(-y as f32),
+					_ => W::MouseWheel(y as f32),
+				},
+				S::TextInput { text, .. } => W::TextInput(text),
+				S::DropFile { filename, .. } => W::FileDropped(filename.into()),
+				S::ControllerDeviceAdded { which, .. } => {
+					self.open_controller(which);
+					continue;
+				}
+				S::ControllerDeviceRemoved { which, .. } => {
+					self.controllers
+						.retain(|controller| controller.instance_id() != which);
+					continue;
+				}
+				S::ControllerButtonDown { button, .. } => {
+					W::GamepadDown(button.into())
+				}
+				S::ControllerButtonUp { button, .. } => {
+					W::GamepadUp(button.into())
+				}
+				S::ControllerAxisMotion { axis, value, .. } => {
+					let axis = match axis {
+						SdlAxis::LeftX => GamepadAxis::LeftX,
+						SdlAxis::LeftY => GamepadAxis::LeftY,
+						_ => continue,
+					};
+					let value = (value as f32 / i16::MAX as f32).max(-1.0);
+					W::GamepadAxisMotion(axis, value)
+				}
+				_ => continue,
+			});
+		}
+	}
+
+	// Opens a second window alongside the main one, for debug views. Does
+	// nothing if it is already open.
+	pub fn open_debug_window(
+		&mut self,
+		title: &str,
+		width: u32,
+		height: u32,
+	) -> Result<(), String> {
+		if self.debug_canvas.is_some() {
+			return Ok(());
+		}
+		let window = self
+			.sdl
+			.video()?
+			.window(title, width, height)
+			.resizable()
+			.build()
+			.map_err(|error| error.to_string())?;
+		// The main canvas already waits for vsync, if enabled.
+		self.debug_canvas = Some(build_canvas(window, false)?);
+		Ok(())
+	}
+
+	pub fn close_debug_window(&mut self) {
+		self.debug_canvas = None;
+	}
+
+	pub fn debug_window_size(&self) -> Option<(u32, u32)> {
+		let canvas = self.debug_canvas.as_ref()?;
+		Some(canvas.window().drawable_size())
+	}
+
+	// Clears the debug window and draws each polyline in its color.
+	pub fn draw_debug_window(
+		&mut self,
+		background: Color,
+		paths: &[(Color, Vec<(f32, f32)>)],
+	) {
+		let canvas = match self.debug_canvas.as_mut() {
+			Some(canvas) => canvas,
+			None => return,
+		};
+		canvas.set_draw_color(background);
+		canvas.clear();
+		for (color, points) in paths {
+			canvas.set_draw_color(*color);
+			let points: Vec<SdlPoint> = points
+				.iter()
+				.map(|&(x, y)| (sdl_coordinate(x), sdl_coordinate(y)).into())
+				.collect();
+			canvas.draw_lines(points.as_slice()).ok();
+		}
+		canvas.present();
 	}
 }
@@ -1,10 +1,13 @@
 use crate::prelude::*;
 
 use super::super::super::GameState;
-use super::super::{Color, Keycode, Window, WindowEvent};
+use super::super::{
+	BackendError, Color, GamepadAxis, GamepadButton, Keycode, MouseButton,
+	Window, WindowConfig, WindowEvent,
+};
 
-use std::sync::Mutex;
 use std::collections::VecDeque;
+use std::sync::Mutex;
 
 extern "C" {
 	fn console_log(ptr: *const u8, len: u32);
@@ -14,13 +17,54 @@ extern "C" {
 	fn canvas_begin_path();
 	fn canvas_move_to(x: f64, y: f64);
 	fn canvas_line_to(x: f64, y: f64);
-	fn canvas_clear();
+	fn canvas_clear(r: u8, g: u8, b: u8);
 	fn canvas_width() -> u32;
 	fn canvas_height() -> u32;
+	fn canvas_display_width() -> u32;
+	fn canvas_display_height() -> u32;
+	// Draws into an offscreen canvas of this size from then on, or straight
+	// to the page again if either dimension is zero.
+	fn canvas_set_internal_size(width: u32, height: u32);
+	// Stretches the offscreen canvas, if any, over the page canvas.
+	fn canvas_present();
+
+	// Plays the sound numbered `sound_id` at `gain` from zero to one,
+	// panned from -1 on the left to 1 on the right.
+	fn audio_play_at(sound_id: u32, gain: f32, pan: f32);
 
 	fn random() -> f64;
+	// Milliseconds, from `performance.now()`.
+	fn performance_now() -> f64;
+	// Milliseconds since the Unix epoch, from `Date.now()`.
+	fn date_now() -> f64;
+
+	// `localStorage` access. The length is in UTF-8 bytes, or negative if
+	// the key is missing, and writing returns zero on failure.
+	fn storage_length(key_ptr: *const u8, key_len: u32) -> i32;
+	fn storage_read(key_ptr: *const u8, key_len: u32, ptr: *mut u8, len: u32);
+	fn storage_write(
+		key_ptr: *const u8,
+		key_len: u32,
+		ptr: *const u8,
+		len: u32,
+	) -> u32;
+	fn storage_remove(key_ptr: *const u8, key_len: u32);
+	// Every key, one to a line, measured and read like a value.
+	fn storage_keys_length() -> u32;
+	fn storage_keys(ptr: *mut u8, len: u32);
 }
 
+// Events kept waiting for the game at most, so that a page stuck sending
+// them can't use up memory. Far more than a frame's worth.
+const MAX_QUEUED_EVENTS: usize = 1024;
+// Bytes in the mask given to `sync_keys`, a bit for each key number the
+// page sends.
+const KEY_MASK_BYTES: usize = 16;
+
+// Animation frames arrive at slightly uneven intervals, so a frame counts as
+// due this much before the limit has fully elapsed.
+const FRAME_LIMIT_TOLERANCE: f64 = 0.002;
+
 fn js_log<T: std::borrow::Borrow<str>>(message: T) {
 	let slice = message.borrow().as_bytes();
 	unsafe {
@@ -35,7 +79,8 @@ lazy_static! {
 	static ref LOOPING_WINDOW: Mutex<Option<Window>> = Mutex::new(None);
 	static ref LOOPING_GAME_STATE: Mutex<Option<GameState>> = Mutex::new(None);
 	static ref LOOPING_CLOSURE: LoopClosure = Mutex::new(None);
-	static ref EVENTS: Mutex<VecDeque<WindowEvent>> = Mutex::new(VecDeque::new());
+	static ref EVENTS: Mutex<EventQueue> = Mutex::new(EventQueue::new());
+	static ref LAST_FRAME_TIME: Mutex<Option<f64>> = Mutex::new(None);
 }
 
 pub mod external_exports {
@@ -47,31 +92,342 @@ pub mod external_exports {
 		let mut game_state = super::LOOPING_GAME_STATE.lock().unwrap();
 		let mut closure = super::LOOPING_CLOSURE.lock().unwrap();
 
+		// The browser calls this once per display refresh. With a frame
+		// limit below the refresh rate, some of those calls are skipped.
+		let now = time::now();
+		let mut last_frame_time = super::LAST_FRAME_TIME.lock().unwrap();
+		let limit = window.as_ref().unwrap().frame_time_limit();
+		if let (Some(limit), Some(last)) = (limit, *last_frame_time) {
+			if now - last < limit - FRAME_LIMIT_TOLERANCE {
+				return;
+			}
+		}
+		*last_frame_time = Some(now);
+
 		closure.as_ref().unwrap()(
 			window.as_mut().unwrap(),
 			game_state.as_mut().unwrap(),
 		);
 	}
 
+	// Keys the game has no use for are dropped here rather than queued.
 	#[no_mangle]
 	pub fn key_down_event(keycode: i32) {
-		queue_event(WindowEvent::KeyDown(super::match_keycode_num(keycode)));
+		match super::match_keycode_num(keycode) {
+			Keycode::Unknown => {}
+			keycode => queue_event(WindowEvent::KeyDown(keycode)),
+		}
 	}
 
 	#[no_mangle]
 	pub fn key_up_event(keycode: i32) {
-		queue_event(WindowEvent::KeyUp(super::match_keycode_num(keycode)));
+		match super::match_keycode_num(keycode) {
+			Keycode::Unknown => {}
+			keycode => queue_event(WindowEvent::KeyUp(keycode)),
+		}
+	}
+
+	// The page calls this every so often with the keys it knows to be held,
+	// as a bit for each key number, since browsers don't always send a key's
+	// release, such as when it opened some browser window or the page lost
+	// focus while it was down. `ptr` must point to `len` readable bytes,
+	// such as from one call to `alloc_bytes` that is kept for every sync.
+	#[no_mangle]
+	pub unsafe fn sync_keys(ptr: *const u8, len: u32) {
+		let mask = std::slice::from_raw_parts(ptr, len as usize);
+		queue_event(WindowEvent::KeysHeld(super::keys_in_mask(mask)));
+	}
+
+	#[no_mangle]
+	pub fn text_input_event(code_point: u32) {
+		if let Some(c) = std::char::from_u32(code_point) {
+			queue_event(WindowEvent::TextInput(c.to_string()));
+		}
+	}
+
+	#[no_mangle]
+	pub fn mouse_move_event(x: f64, y: f64) {
+		queue_event(WindowEvent::MouseMove(x as f32, y as f32));
+	}
+
+	#[no_mangle]
+	pub fn mouse_down_event(button: i32) {
+		queue_event(WindowEvent::MouseDown(super::match_mouse_button(button)));
+	}
+
+	#[no_mangle]
+	pub fn mouse_up_event(button: i32) {
+		queue_event(WindowEvent::MouseUp(super::match_mouse_button(button)));
+	}
+
+	#[no_mangle]
+	pub fn mouse_wheel_event(notches: f64) {
+		queue_event(WindowEvent::MouseWheel(notches as f32));
+	}
+
+	#[no_mangle]
+	pub fn gamepad_down_event(button: i32) {
+		let button = super::match_gamepad_button(button);
+		queue_event(WindowEvent::GamepadDown(button));
+	}
+
+	#[no_mangle]
+	pub fn gamepad_up_event(button: i32) {
+		let button = super::match_gamepad_button(button);
+		queue_event(WindowEvent::GamepadUp(button));
+	}
+
+	// Runs the determinism probe, logging the lines the native
+	// `determinism_probe` example prints, and gives the final hash, for
+	// `tests/determinism_probe.js` to compare the two builds with.
+	#[no_mangle]
+	pub fn run_probe() -> u64 {
+		use crate::world::{determinism_probe, PROBE_SEED, PROBE_TICKS};
+
+		js_log(format!("seed {:x}, {} ticks", PROBE_SEED, PROBE_TICKS));
+		let hashes = determinism_probe();
+		for &(tick, hash) in hashes.iter() {
+			js_log(format!("tick {}: {:016x}", tick, hash));
+		}
+		hashes.last().map_or(0, |&(_, hash)| hash)
+	}
+
+	// Space for the page to copy a dropped file into, handed back with
+	// `file_dropped`.
+	#[no_mangle]
+	pub fn alloc_bytes(len: u32) -> *mut u8 {
+		let bytes = vec![0u8; len as usize].into_boxed_slice();
+		Box::into_raw(bytes) as *mut u8
+	}
+
+	// `ptr` and `len` must come from a call to `alloc_bytes`.
+	#[no_mangle]
+	pub unsafe fn file_dropped(ptr: *mut u8, len: u32) {
+		let bytes = std::slice::from_raw_parts_mut(ptr, len as usize);
+		let bytes = Box::from_raw(bytes as *mut [u8]);
+		queue_event(WindowEvent::FileContentsDropped(bytes.into_vec()));
+	}
+
+	// The page polls `navigator.getGamepads()` and reports axes that changed.
+	#[no_mangle]
+	pub fn gamepad_axis_event(axis: i32, value: f64) {
+		let axis = match axis {
+			0 => GamepadAxis::LeftX,
+			1 => GamepadAxis::LeftY,
+			_ => return,
+		};
+		queue_event(WindowEvent::GamepadAxisMotion(axis, value as f32));
 	}
 }
 
 fn queue_event(event: WindowEvent) {
-	EVENTS.lock().unwrap().push_back(event);
+	EVENTS.lock().unwrap().push(event);
+}
+
+// Events from the page, waiting for the game to poll them.
+struct EventQueue {
+	events: VecDeque<WindowEvent>,
+	// Events that came in while the queue was full, since it last was.
+	dropped: usize,
+}
+
+impl EventQueue {
+	fn new() -> Self {
+		Self {
+			events: VecDeque::new(),
+			dropped: 0,
+		}
+	}
+
+	// Once full, a key repeating while it is already waiting to go down is
+	// merged into that, and otherwise the oldest event that isn't a release
+	// makes way. Releases are always kept, so that nothing is left held.
+	fn push(&mut self, event: WindowEvent) {
+		if self.events.len() < MAX_QUEUED_EVENTS {
+			self.events.push_back(event);
+			return;
+		}
+		if self.dropped == 0 {
+			js_log("Too many events waiting, dropping old ones");
+		}
+		if let WindowEvent::KeyDown(keycode) = event {
+			if self.is_going_down(keycode) {
+				self.dropped += 1;
+				return;
+			}
+		}
+		match self.events.iter().position(|e| is_release(e) == false) {
+			Some(i) => {
+				self.events.remove(i);
+				self.dropped += 1;
+				self.events.push_back(event);
+			}
+			None if is_release(&event) => self.events.push_back(event),
+			None => self.dropped += 1,
+		}
+	}
+
+	// Whether the last queued event for the key presses it.
+	fn is_going_down(&self, keycode: Keycode) -> bool {
+		let last = self.events.iter().rev().find(|event| match event {
+			WindowEvent::KeyDown(k) | WindowEvent::KeyUp(k) => *k == keycode,
+			WindowEvent::KeysHeld(_) => true,
+			_ => false,
+		});
+		match last {
+			Some(WindowEvent::KeyDown(_)) => true,
+			_ => false,
+		}
+	}
+
+	// Says how many events were dropped once the game has caught up.
+	fn pop(&mut self) -> Option<WindowEvent> {
+		let event = self.events.pop_front();
+		if event.is_none() && self.dropped > 0 {
+			js_log(format!("Dropped {} events", self.dropped));
+			self.dropped = 0;
+		}
+		event
+	}
+}
+
+// Events that let go of something, or say what is still held, without
+// which a key or button could be left down.
+fn is_release(event: &WindowEvent) -> bool {
+	match event {
+		WindowEvent::KeyUp(_)
+		| WindowEvent::KeysHeld(_)
+		| WindowEvent::MouseUp(_)
+		| WindowEvent::GamepadUp(_) => true,
+		_ => false,
+	}
+}
+
+// The keys with their bits set in a `sync_keys` mask, least significant
+// bit of the first byte first.
+fn keys_in_mask(mask: &[u8]) -> Vec<Keycode> {
+	let mut keys = Vec::new();
+	for (i, &byte) in mask.iter().take(KEY_MASK_BYTES).enumerate() {
+		for bit in 0..8 {
+			if byte & (1 << bit) == 0 {
+				continue;
+			}
+			match match_keycode_num((i * 8 + bit) as i32) {
+				Keycode::Unknown => {}
+				keycode => keys.push(keycode),
+			}
+		}
+	}
+	keys
+}
+
+// There is no file system, so the log only goes to the browser console.
+pub mod log_file {
+	pub fn open(_path: &str) -> Result<(), String> {
+		Ok(())
+	}
+
+	pub fn write(_line: &str) {}
+
+	pub fn flush_if_due() {}
+
+	pub fn flush() {}
+}
+
+pub mod threads {
+	// Spawning one panics here, so everything stays on the main thread.
+	pub const AVAILABLE: bool = false;
 }
 
 pub mod random {
-	pub fn rangei(start: isize, end: isize) -> isize {
-		let r = unsafe { super::random() };
-		(r * (end as f64 - start as f64) + start as f64).floor() as isize
+	// Different every run, from `Math.random`. Only for seeding, since
+	// worlds draw everything else from their own `Rng`. Each call gives 32
+	// good bits at most, so it takes two.
+	pub fn entropy_seed() -> u64 {
+		let bits = || (unsafe { super::random() } * 4294967296.0) as u64;
+		bits() << 32 | bits()
+	}
+}
+
+pub mod time {
+	// Seconds since the page started loading.
+	pub fn now() -> f64 {
+		unsafe { super::performance_now() / 1000.0 }
+	}
+
+	// Seconds since the Unix epoch, for comparing across runs.
+	pub fn unix_time() -> f64 {
+		unsafe { super::date_now() / 1000.0 }
+	}
+}
+
+// Named pieces of text that persist between runs, kept in `localStorage`.
+pub mod storage {
+	pub fn read(name: &str) -> Option<String> {
+		let key = name.as_bytes();
+		let length =
+			unsafe { super::storage_length(key.as_ptr(), key.len() as u32) };
+		if length < 0 {
+			return None;
+		}
+		let mut bytes = vec![0; length as usize];
+		unsafe {
+			super::storage_read(
+				key.as_ptr(),
+				key.len() as u32,
+				bytes.as_mut_ptr(),
+				bytes.len() as u32,
+			)
+		};
+		String::from_utf8(bytes).ok()
+	}
+
+	pub fn write(name: &str, contents: &str) -> Result<(), String> {
+		let key = name.as_bytes();
+		let written = unsafe {
+			super::storage_write(
+				key.as_ptr(),
+				key.len() as u32,
+				contents.as_ptr(),
+				contents.len() as u32,
+			)
+		};
+		if written == 0 {
+			return Err(String::from("local storage is full or unavailable"));
+		}
+		Ok(())
+	}
+
+	pub fn remove(name: &str) -> Result<(), String> {
+		let key = name.as_bytes();
+		unsafe { super::storage_remove(key.as_ptr(), key.len() as u32) };
+		Ok(())
+	}
+
+	// Keys are flat, so a directory is taken as a prefix of them.
+	pub fn list(directory: &str) -> Vec<String> {
+		let length = unsafe { super::storage_keys_length() };
+		let mut bytes = vec![0; length as usize];
+		unsafe { super::storage_keys(bytes.as_mut_ptr(), bytes.len() as u32) };
+		let keys = String::from_utf8(bytes).unwrap_or_default();
+		let prefix = format!("{}/", directory);
+		let mut names: Vec<String> = keys
+			.lines()
+			.filter(|key| key.starts_with(&prefix))
+			.map(String::from)
+			.collect();
+		names.sort();
+		names
+	}
+
+	// There are no threads here, so this writes synchronously.
+	pub fn write_in_background(name: &str, contents: String) {
+		if let Err(error) = write(name, &contents) {
+			crate::prelude::elog(format!(
+				"Could not write {}: {}",
+				name, error
+			));
+		}
 	}
 }
 
@@ -112,13 +468,46 @@ pub fn begin_loop(
 	//self.backend.begin_loop(closure);
 }
 
-pub struct Backend {}
+pub struct Backend {
+	render_scale: u32,
+	internal_size: Option<(u32, u32)>,
+}
 
 impl Backend {
-	pub fn new() -> Self {
+	// Never fails, since the page has already made the canvas.
+	pub fn new(_config: &WindowConfig) -> Result<Self, BackendError> {
 		set_panic_hook();
 
-		Self {}
+		Ok(Self {
+			render_scale: 1,
+			internal_size: None,
+		})
+	}
+
+	// How many page canvas pixels each drawn pixel covers. Takes effect from
+	// the next frame.
+	pub fn set_render_scale(&mut self, scale: u32) {
+		self.render_scale = scale.max(1);
+	}
+
+	// Frames are always paced by `requestAnimationFrame`.
+	pub fn set_vsync(&mut self, _vsync: bool) {}
+
+	fn update_internal_size(&mut self) {
+		let size = if self.render_scale == 1 {
+			None
+		} else {
+			let scale = self.render_scale;
+			let width = unsafe { canvas_display_width() } / scale;
+			let height = unsafe { canvas_display_height() } / scale;
+			Some((width.max(1), height.max(1)))
+		};
+
+		if size != self.internal_size {
+			let (width, height) = size.unwrap_or((0, 0));
+			unsafe { canvas_set_internal_size(width, height) };
+			self.internal_size = size;
+		}
 	}
 	// TODO
 	pub fn viewport_width(&self) -> u32 {
@@ -129,16 +518,31 @@ impl Backend {
 		unsafe { canvas_height() }
 	}
 
-	pub fn clear_canvas(&mut self) {
-		unsafe { canvas_clear() }
+	pub fn clear_canvas(&mut self, color: Color) {
+		self.update_internal_size();
+		unsafe { canvas_clear(color.r, color.g, color.b) }
+	}
+
+	pub fn update_canvas(&mut self) {
+		unsafe { canvas_present() }
+	}
+
+	pub fn supports_read_pixels(&self) -> bool {
+		false
 	}
 
-	pub fn update_canvas(&mut self) {}
+	pub fn read_pixels(&self) -> Result<(u32, u32, Vec<u8>), String> {
+		Err(String::from("reading pixels is not supported on the web"))
+	}
 
 	pub fn set_draw_color(&mut self, color: Color) {
 		unsafe { canvas_set_stroke_color(color.r, color.g, color.b) }
 	}
 
+	pub fn play_sound(&mut self, id: u32, gain: f32, pan: f32) {
+		unsafe { audio_play_at(id, gain, pan) }
+	}
+
 	pub fn draw_line(&mut self, start: (f32, f32), end: (f32, f32)) {
 		self.draw_lines(&[start, end]);
 	}
@@ -163,19 +567,95 @@ impl Backend {
 	}
 
 	pub fn poll_event(&mut self) -> Option<WindowEvent> {
-		EVENTS.lock().unwrap().pop_front()
+		let event = EVENTS.lock().unwrap().pop()?;
+		Some(match event {
+			WindowEvent::MouseMove(x, y) => {
+				let scale = self.render_scale as f32;
+				WindowEvent::MouseMove(x / scale, y / scale)
+			}
+			event => event,
+		})
+	}
+
+	pub fn open_debug_window(
+		&mut self,
+		_title: &str,
+		_width: u32,
+		_height: u32,
+	) -> Result<(), String> {
+		Err(String::from("extra windows are not supported on the web"))
+	}
+
+	pub fn close_debug_window(&mut self) {}
+
+	pub fn debug_window_size(&self) -> Option<(u32, u32)> {
+		None
+	}
+
+	pub fn draw_debug_window(
+		&mut self,
+		_background: Color,
+		_paths: &[(Color, Vec<(f32, f32)>)],
+	) {
 	}
 }
 
 fn match_keycode_num(num: i32) -> Keycode {
 	match num {
 		0 => Keycode::A,
+		2 => Keycode::C,
 		3 => Keycode::D,
 		4 => Keycode::E,
+		5 => Keycode::F,
+		6 => Keycode::G,
+		7 => Keycode::H,
+		12 => Keycode::M,
+		15 => Keycode::P,
 		16 => Keycode::Q,
+		17 => Keycode::R,
 		18 => Keycode::S,
+		19 => Keycode::T,
 		22 => Keycode::W,
 		100 => Keycode::Escape,
+		101 => Keycode::Backquote,
+		102 => Keycode::Backspace,
+		103 => Keycode::F3,
+		104 => Keycode::Return,
+		105 => Keycode::Shift,
+		106 => Keycode::F9,
+		107 => Keycode::Delete,
+		108 => Keycode::Space,
+		109 => Keycode::Up,
+		110 => Keycode::Down,
+		111 => Keycode::Left,
+		112 => Keycode::Right,
+		113 => Keycode::Tab,
+		114 => Keycode::PageUp,
+		115 => Keycode::PageDown,
 		_ => Keycode::Unknown,
 	}
 }
+
+// Matches `MouseEvent.button` numbering.
+fn match_mouse_button(num: i32) -> MouseButton {
+	match num {
+		0 => MouseButton::Left,
+		1 => MouseButton::Middle,
+		2 => MouseButton::Right,
+		_ => MouseButton::Unknown,
+	}
+}
+
+// Matches the standard `Gamepad.buttons` layout.
+fn match_gamepad_button(num: i32) -> GamepadButton {
+	match num {
+		0 => GamepadButton::Confirm,
+		1 => GamepadButton::Cancel,
+		9 => GamepadButton::Start,
+		12 => GamepadButton::Up,
+		13 => GamepadButton::Down,
+		14 => GamepadButton::Left,
+		15 => GamepadButton::Right,
+		_ => GamepadButton::Unknown,
+	}
+}
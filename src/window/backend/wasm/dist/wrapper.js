@@ -1,11 +1,21 @@
 window.addEventListener('load', init);
 
 const WASM_FILE = 'sdl2_1.wasm';
+// Matches `KEY_MASK_BYTES` in `wasm.rs`.
+const KEY_MASK_BYTES = 16;
+// Milliseconds between telling the game which keys are held.
+const KEY_SYNC_INTERVAL = 500;
 
-let state = {};
+let state = {
+	// Key numbers, as from `convertKeycode`.
+	heldKeys: new Set(),
+};
 
 async function init() {
 	state.canvas = document.querySelector('#viewport');
+	// The canvas being drawn into: either `state.canvas` or, when rendering
+	// at a reduced scale, an offscreen canvas.
+	state.target = state.canvas;
 	state.context = state.canvas.getContext('2d');
 	state.canvas.width = 500;
 	state.canvas.height = 500;
@@ -15,11 +25,60 @@ async function init() {
 	state.mod.main();
 
 	window.addEventListener('keydown', event => {
-		state.mod.key_down_event(convertKeycode(event.code))
+		let keycode = convertKeycode(event.code);
+		if (keycode >= 0) {
+			state.mod.key_down_event(keycode);
+			state.heldKeys.add(keycode);
+		}
+		// Printable keys have a single character `key`.
+		if ([...event.key].length === 1) {
+			state.mod.text_input_event(event.key.codePointAt(0));
+		}
+		// Tab completes in the console rather than moving the focus.
+		if (event.code === 'Tab') {
+			event.preventDefault();
+		}
 	});
 
 	window.addEventListener('keyup', event => {
-		state.mod.key_up_event(convertKeycode(event.code))
+		let keycode = convertKeycode(event.code);
+		if (keycode >= 0) {
+			state.mod.key_up_event(keycode);
+			state.heldKeys.delete(keycode);
+		}
+	});
+
+	// No key releases come while the page is out of focus, so every key is
+	// taken to be released when it loses it.
+	window.addEventListener('blur', () => state.heldKeys.clear());
+	document.addEventListener('visibilitychange', () => {
+		if (document.hidden) {
+			state.heldKeys.clear();
+		}
+	});
+	state.keyMask = state.mod.alloc_bytes(KEY_MASK_BYTES);
+	setInterval(syncKeys, KEY_SYNC_INTERVAL);
+
+	state.canvas.addEventListener('mousemove', event => {
+		state.mod.mouse_move_event(event.offsetX, event.offsetY);
+	});
+
+	state.canvas.addEventListener('mousedown', event => {
+		state.mod.mouse_down_event(event.button);
+	});
+
+	state.canvas.addEventListener('mouseup', event => {
+		state.mod.mouse_up_event(event.button);
+	});
+
+	// Browsers give positive deltas for scrolling towards the user.
+	state.canvas.addEventListener('wheel', event => {
+		event.preventDefault();
+		state.mod.mouse_wheel_event(-Math.sign(event.deltaY));
+	});
+
+	state.canvas.addEventListener('contextmenu', event => {
+		event.preventDefault();
 	});
 
 	let resizeHandler = () => {
@@ -35,15 +94,50 @@ async function init() {
 function convertKeycode(code) {
 	return ({
 		"KeyA": 0,
+		"KeyC": 2,
 		"KeyD": 3,
 		"KeyE": 4,
+		"KeyF": 5,
+		"KeyG": 6,
+		"KeyH": 7,
+		"KeyM": 12,
+		"KeyP": 15,
 		"KeyQ": 16,
+		"KeyR": 17,
 		"KeyS": 18,
+		"KeyT": 19,
 		"KeyW": 22,
 		"Escape": 100,
+		"Backquote": 101,
+		"Backspace": 102,
+		"F3": 103,
+		"Enter": 104,
+		"ShiftLeft": 105,
+		"ShiftRight": 105,
+		"F9": 106,
+		"Delete": 107,
+		"Space": 108,
+		"ArrowUp": 109,
+		"ArrowDown": 110,
+		"ArrowLeft": 111,
+		"ArrowRight": 112,
+		"Tab": 113,
+		"PageUp": 114,
+		"PageDown": 115,
 	})[code] ?? -1;
 }
 
+// Tells the game which keys are held, in case it missed a release.
+function syncKeys() {
+	let mask = new Uint8Array(state.mod.memory.buffer, state.keyMask,
+		KEY_MASK_BYTES);
+	mask.fill(0);
+	for (let keycode of state.heldKeys) {
+		mask[keycode >> 3] |= 1 << (keycode & 7);
+	}
+	state.mod.sync_keys(state.keyMask, KEY_MASK_BYTES);
+}
+
 async function run() {
 	while (true) {
 		state.mod.tick();
@@ -67,20 +161,88 @@ function generateExports() {
 	obj.canvas_begin_path = () => state.context.beginPath();
 	obj.canvas_move_to = (x, y) => state.context.moveTo(x, y);
 	obj.canvas_line_to = (x, y) => state.context.lineTo(x, y);
-	obj.canvas_clear = () => {
-		let [w, h] = [state.canvas.width, state.canvas.height];
+	obj.canvas_clear = (r, g, b) => {
+		let [w, h] = [state.target.width, state.target.height];
 		state.context.clearRect(0, 0, w, h);
-		state.context.fillColor = '#000';
+		state.context.fillStyle = `rgb(${r},${g},${b})`;
 		state.context.fillRect(0, 0, w, h);
 	};
-	obj.canvas_width = () => state.canvas.width;
-	obj.canvas_height = () => state.canvas.height;
+	obj.canvas_width = () => state.target.width;
+	obj.canvas_height = () => state.target.height;
+	obj.canvas_display_width = () => state.canvas.width;
+	obj.canvas_display_height = () => state.canvas.height;
+	obj.canvas_set_internal_size = (width, height) => {
+		if (width === 0 || height === 0) {
+			state.target = state.canvas;
+		} else {
+			state.target = document.createElement('canvas');
+			state.target.width = width;
+			state.target.height = height;
+		}
+		state.context = state.target.getContext('2d');
+	};
+	obj.canvas_present = () => {
+		if (state.target === state.canvas) {
+			return;
+		}
+		let context = state.canvas.getContext('2d');
+		context.imageSmoothingEnabled = false;
+		context.drawImage(state.target, 0, 0,
+			state.canvas.width, state.canvas.height);
+	};
 
 	obj.sin = Math.sin;
 	obj.cos = Math.cos;
 	obj.fmod = (num, div) => num % div;
 
+	// Each sound is a short tone of its own pitch, as there are no samples
+	// to play yet. The context can only start once the page has had input.
+	obj.audio_play_at = (soundId, gain, pan) => {
+		if (state.audio === undefined) {
+			state.audio = new AudioContext();
+		}
+		let audio = state.audio;
+		let end = audio.currentTime + 0.12;
+		let oscillator = audio.createOscillator();
+		oscillator.frequency.value = 220 * Math.pow(2, soundId / 4);
+		let volume = audio.createGain();
+		volume.gain.setValueAtTime(gain * 0.2, audio.currentTime);
+		volume.gain.exponentialRampToValueAtTime(0.001, end);
+		let panner = audio.createStereoPanner();
+		panner.pan.value = pan;
+		oscillator.connect(volume).connect(panner).connect(audio.destination);
+		oscillator.start();
+		oscillator.stop(end);
+	};
+
 	obj.random = Math.random;
+	obj.performance_now = () => performance.now();
+	obj.date_now = () => Date.now();
+
+	let readString = (ptr, len) => (new TextDecoder("UTF-8")).decode(
+		new Uint8Array(state.mod.memory.buffer, ptr, len));
+	let storageBytes = (keyPtr, keyLen) => {
+		let value = localStorage.getItem(readString(keyPtr, keyLen));
+		return value === null ? null : (new TextEncoder()).encode(value);
+	};
+	obj.storage_length = (keyPtr, keyLen) => {
+		let bytes = storageBytes(keyPtr, keyLen);
+		return bytes === null ? -1 : bytes.length;
+	};
+	obj.storage_read = (keyPtr, keyLen, ptr, len) => {
+		let bytes = storageBytes(keyPtr, keyLen);
+		new Uint8Array(state.mod.memory.buffer, ptr, len)
+			.set(bytes.subarray(0, len));
+	};
+	obj.storage_write = (keyPtr, keyLen, ptr, len) => {
+		try {
+			localStorage.setItem(readString(keyPtr, keyLen),
+				readString(ptr, len));
+			return 1;
+		} catch (error) {
+			return 0;
+		}
+	};
 
 	return obj;
 }
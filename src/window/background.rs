@@ -0,0 +1,185 @@
+use std::rc::Rc;
+
+use super::{
+	is_rect_visible, rect_facing, CameraProjector, Color, Role, Window,
+};
+use crate::geometry::{vec3, Matrix4x4, Scalar, Vector3};
+use crate::world::{BackgroundPattern, BackgroundStyle, Frame, World};
+
+// How far from the background color towards its tint a pattern is drawn.
+// Well below the tiles, which are drawn at full strength.
+const PATTERN_STRENGTH: Scalar = 0.3;
+// Pattern elements per side of a frame.
+const DOT_COUNT: usize = 12;
+const DIAGONAL_COUNT: usize = 8;
+const CHECKER_COUNT: usize = 8;
+// Length of a dot, as a fraction of the spacing between dots.
+const DOT_LENGTH: Scalar = 0.15;
+// Hatching lines across each filled checker cell.
+const CHECKER_HATCHES: usize = 3;
+// Streaks drifting across a frame with wind, and how long each is in frame
+// units.
+const WIND_STREAKS: usize = 10;
+const WIND_STREAK_LENGTH: Scalar = 0.2;
+// Streaks move this many times faster than the wind would carry something
+// along, so that weak winds still show.
+const WIND_STREAK_SPEED: Scalar = 4.0;
+
+// Segments for each pattern in frame coordinates, built once and transformed
+// onto each face as it is drawn. None has more than a couple of hundred.
+pub struct BackgroundPatterns {
+	dots: Vec<(Vector3, Vector3)>,
+	diagonals: Vec<(Vector3, Vector3)>,
+	checker: Vec<(Vector3, Vector3)>,
+}
+
+impl BackgroundPatterns {
+	pub fn new() -> Self {
+		let point = |x: Scalar, y: Scalar| vec3(x, y, 1.0);
+
+		// Short horizontal dashes at the centers of a grid of cells.
+		let mut dots = Vec::new();
+		let spacing = 2.0 / DOT_COUNT as Scalar;
+		for i in 0..DOT_COUNT {
+			for j in 0..DOT_COUNT {
+				let x = -1.0 + (i as Scalar + 0.5) * spacing;
+				let y = -1.0 + (j as Scalar + 0.5) * spacing;
+				let half = spacing * DOT_LENGTH / 2.0;
+				dots.push((point(x - half, y), point(x + half, y)));
+			}
+		}
+
+		// Lines rising to the right, each running corner to corner of one
+		// cell along a diagonal of cells.
+		let mut diagonals = Vec::new();
+		let spacing = 2.0 / DIAGONAL_COUNT as Scalar;
+		for i in 0..DIAGONAL_COUNT {
+			for j in 0..DIAGONAL_COUNT {
+				let left = -1.0 + i as Scalar * spacing;
+				let top = -1.0 + j as Scalar * spacing;
+				diagonals.push((
+					point(left, top + spacing),
+					point(left + spacing, top),
+				));
+			}
+		}
+
+		// Every other cell hatched horizontally.
+		let mut checker = Vec::new();
+		let spacing = 2.0 / CHECKER_COUNT as Scalar;
+		for i in 0..CHECKER_COUNT {
+			for j in 0..CHECKER_COUNT {
+				if (i + j) % 2 == 1 {
+					continue;
+				}
+				let left = -1.0 + i as Scalar * spacing;
+				let top = -1.0 + j as Scalar * spacing;
+				for k in 0..CHECKER_HATCHES {
+					let t = (k as Scalar + 0.5) / CHECKER_HATCHES as Scalar;
+					let y = top + t * spacing;
+					checker.push((point(left, y), point(left + spacing, y)));
+				}
+			}
+		}
+
+		Self {
+			dots,
+			diagonals,
+			checker,
+		}
+	}
+
+	pub fn segments(
+		&self,
+		pattern: BackgroundPattern,
+	) -> &[(Vector3, Vector3)] {
+		match pattern {
+			BackgroundPattern::None => &[],
+			BackgroundPattern::Dots => &self.dots,
+			BackgroundPattern::Diagonals => &self.diagonals,
+			BackgroundPattern::Checker => &self.checker,
+		}
+	}
+}
+
+impl Window {
+	// What a frame's pattern is drawn in, in both the cube view and the net.
+	pub(super) fn background_color(&self, style: BackgroundStyle) -> Color {
+		let [r, g, b] = style.tint;
+		let background = self.palette.get(Role::Background);
+		background.mix(Color::rgb(r, g, b), PATTERN_STRENGTH)
+	}
+
+	pub(super) fn draw_frame_background(
+		&mut self,
+		projector: &CameraProjector,
+		frame: &Frame,
+		surface_transform: Matrix4x4,
+		view_rotation: Matrix4x4,
+	) {
+		let m = surface_transform;
+		let r = view_rotation;
+		let p1 = vec3(-1.0, -1.0, 1.0) * m * r;
+		let p2 = vec3(1.0, -1.0, 1.0) * m * r;
+		let p3 = vec3(1.0, 1.0, 1.0) * m * r;
+		let p4 = vec3(-1.0, 1.0, 1.0) * m * r;
+		let (normal, center) = rect_facing(p1, p2, p3, p4);
+		let camera = projector.camera_position();
+		if is_rect_visible(normal, center, camera) == false {
+			return;
+		}
+
+		let color = self.background_color(frame.background);
+		let patterns = Rc::clone(&self.background_patterns);
+		let segments = patterns.segments(frame.background.pattern);
+		self.draw_segments(projector, segments, m, r, color, false);
+	}
+
+	// Short lines in evenly spaced lanes along the frame's wind, each
+	// wrapping round to the upwind side once it blows off the face. Lanes
+	// start at staggered points so the streaks don't march in step.
+	pub(super) fn draw_frame_wind(
+		&mut self,
+		projector: &CameraProjector,
+		world: &World,
+		frame: &Frame,
+		surface_transform: Matrix4x4,
+		view_rotation: Matrix4x4,
+	) {
+		let wind = match frame.wind {
+			Some(wind) if wind.x != 0.0 || wind.y != 0.0 => wind,
+			_ => return,
+		};
+		let strength = wind.x.hypot(wind.y);
+		let (dx, dy) = (wind.x / strength, wind.y / strength);
+		let speed = world.physics.terminal_speed(strength) * WIND_STREAK_SPEED;
+		let travel = world.tick_count as Scalar * speed;
+
+		// Lanes run across the whole diagonal, so that for winds at an angle
+		// the frame's corners get streaks too. Those off the face are
+		// skipped.
+		let reach = std::f64::consts::SQRT_2 as Scalar;
+		let span = 2.0 * reach + WIND_STREAK_LENGTH;
+		let mut segments = Vec::with_capacity(WIND_STREAKS);
+		for lane in 0..WIND_STREAKS {
+			let across = -reach
+				+ (lane as Scalar + 0.5) * 2.0 * reach / WIND_STREAKS as Scalar;
+			let stagger = (lane as Scalar * 0.618).fract() * span;
+			let along = (travel + stagger) % span - reach - WIND_STREAK_LENGTH;
+			let (x, y) = (along * dx - across * dy, along * dy + across * dx);
+			let (end_x, end_y) =
+				(x + dx * WIND_STREAK_LENGTH, y + dy * WIND_STREAK_LENGTH);
+			let on_face =
+				|x: Scalar, y: Scalar| x.abs() <= 1.0 && y.abs() <= 1.0;
+			if on_face(x, y) && on_face(end_x, end_y) {
+				segments.push((vec3(x, y, 1.0), vec3(end_x, end_y, 1.0)));
+			}
+		}
+
+		// Stronger than the pattern, since it says something about play.
+		let background = self.palette.get(Role::Background);
+		let color = self.palette.get(Role::UiText).mix(background, 0.6);
+		let (m, r) = (surface_transform, view_rotation);
+		self.draw_segments(projector, &segments, m, r, color, false);
+	}
+}
@@ -0,0 +1,79 @@
+use super::types::Keycode;
+use super::InputState;
+use crate::world::{Actions, Direction};
+
+// Which keys produce each of the player's actions.
+pub struct Bindings {
+	pub left: Keycode,
+	pub right: Keycode,
+	pub up: Keycode,
+	pub down: Keycode,
+	pub jump: Keycode,
+	pub interact: Keycode,
+	pub remove: Keycode,
+	pub rotate: Keycode,
+	// Held to sprint while moving.
+	pub sprint: Keycode,
+	pub dash: Keycode,
+	// Held to make up and down aim instead of moving and jumping.
+	pub aim: Keycode,
+}
+
+impl Bindings {
+	pub fn default() -> Self {
+		Self {
+			left: Keycode::A,
+			right: Keycode::D,
+			up: Keycode::W,
+			down: Keycode::S,
+			jump: Keycode::W,
+			interact: Keycode::E,
+			remove: Keycode::Q,
+			rotate: Keycode::R,
+			sprint: Keycode::C,
+			dash: Keycode::Space,
+			aim: Keycode::Shift,
+		}
+	}
+
+	// Movement follows held keys, and everything else only the keys pressed
+	// since the last tick.
+	pub fn actions(&self, input_state: &InputState) -> Actions {
+		let held = |key| input_state.keys_held.contains(&key);
+		let pressed = |key| input_state.keys_pressed.contains(&key);
+		let axis = |negative, positive| match (held(negative), held(positive)) {
+			(true, false) => -1.0,
+			(false, true) => 1.0,
+			_ => 0.0,
+		};
+
+		if held(self.aim) {
+			let aim = match axis(self.up, self.down) {
+				y if y < 0.0 => Direction::Up,
+				y if y > 0.0 => Direction::Down,
+				_ => Direction::Neutral,
+			};
+			return Actions {
+				move_x: axis(self.left, self.right),
+				interact: pressed(self.interact),
+				remove: pressed(self.remove),
+				rotate: pressed(self.rotate),
+				aim,
+				..Actions::none()
+			};
+		}
+
+		Actions {
+			move_x: axis(self.left, self.right),
+			move_y: axis(self.up, self.down),
+			jump: pressed(self.jump),
+			jump_held: held(self.jump),
+			interact: pressed(self.interact),
+			remove: pressed(self.remove),
+			rotate: pressed(self.rotate),
+			sprint: held(self.sprint),
+			dash: pressed(self.dash),
+			aim: Direction::Neutral,
+		}
+	}
+}
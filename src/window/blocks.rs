@@ -0,0 +1,228 @@
+use super::{CameraProjector, Role, Window};
+use crate::geometry::{vec3, Matrix4x4, Scalar};
+use crate::world::{Frame, Tile, World, FRAME_WIDTH};
+
+// How far tiles stand out from the surface in 3D mode.
+const BLOCK_DEPTH: Scalar = 0.08;
+const PLATE_DEPTH: Scalar = 0.02;
+
+// How far a solid tile stands out from the surface in 3D mode, or nothing
+// for tiles that aren't drawn as blocks.
+fn block_depth(tile: Tile) -> Option<Scalar> {
+	match tile {
+		Tile::Solid | Tile::ConveyorLeft | Tile::ConveyorRight => {
+			Some(BLOCK_DEPTH)
+		}
+		_ => None,
+	}
+}
+
+// Which faces of a prism are drawn. Its back lies on the surface and never
+// is.
+#[derive(Copy, Clone, Debug)]
+pub(super) struct PrismFaces {
+	pub front: bool,
+	pub top: bool,
+	pub left: bool,
+	pub bottom: bool,
+	pub right: bool,
+}
+
+impl PrismFaces {
+	pub fn all() -> Self {
+		Self {
+			front: true,
+			top: true,
+			left: true,
+			bottom: true,
+			right: true,
+		}
+	}
+
+	pub fn none() -> Self {
+		Self {
+			front: false,
+			top: false,
+			left: false,
+			bottom: false,
+			right: false,
+		}
+	}
+}
+
+impl Window {
+	// Solid tiles as raised blocks, and pressure plates as thin slabs. Only
+	// sides facing a tile that doesn't hide them are drawn, looking past the
+	// frame's edges through its links, and the fronts of each row's runs of
+	// equally deep blocks are merged into one rectangle, so dense areas cost
+	// little more than their outlines.
+	pub(super) fn draw_tile_blocks(
+		&mut self,
+		projector: &CameraProjector,
+		world: &World,
+		frame: &Frame,
+		surface_transform: Matrix4x4,
+		view_rotation: Matrix4x4,
+	) {
+		let (m, r) = (surface_transform, view_rotation);
+
+		// The depth of the tile at `x`, `y`, which may be past the frame's
+		// edge. Unlinked edges hide nothing.
+		let depth = |x: isize, y: isize| {
+			let (frame_id, x, y) =
+				world.normalize_tile_index(frame.position, x, y)?;
+			let frame = world.get_frame(frame_id)?;
+			block_depth(*frame.tile(x, y))
+		};
+		let hides = |x: isize, y: isize, d: Scalar| {
+			depth(x, y).is_some_and(|depth| depth >= d)
+		};
+
+		let w = FRAME_WIDTH as isize;
+		for y in 0..w {
+			let mut x = 0;
+			while x < w {
+				if *frame.tile(x, y) == Tile::PressurePlate {
+					self.draw_tile_prism(
+						projector,
+						(x as Scalar + 0.1, y as Scalar + 0.8),
+						(0.8, 0.2),
+						PLATE_DEPTH,
+						PrismFaces::all(),
+						Role::TilePlate,
+						m,
+						r,
+					);
+				}
+				let d = match depth(x, y) {
+					Some(d) => d,
+					None => {
+						x += 1;
+						continue;
+					}
+				};
+				let start = x;
+				while x < w && depth(x, y) == Some(d) {
+					x += 1;
+				}
+				let end = x;
+
+				let faces = PrismFaces {
+					front: true,
+					left: hides(start - 1, y, d) == false,
+					right: hides(end, y, d) == false,
+					..PrismFaces::none()
+				};
+				self.draw_tile_prism(
+					projector,
+					(start as Scalar, y as Scalar),
+					((end - start) as Scalar, 1.0),
+					d,
+					faces,
+					Role::TileSolid,
+					m,
+					r,
+				);
+				for x in start..end {
+					let faces = PrismFaces {
+						top: hides(x, y - 1, d) == false,
+						bottom: hides(x, y + 1, d) == false,
+						..PrismFaces::none()
+					};
+					self.draw_tile_prism(
+						projector,
+						(x as Scalar, y as Scalar),
+						(1.0, 1.0),
+						d,
+						faces,
+						Role::TileSolid,
+						m,
+						r,
+					);
+				}
+			}
+		}
+	}
+
+	// A box standing `depth` out from the surface, with its top left corner
+	// at `origin` and `size` across, both in tiles from the frame's top
+	// left.
+	pub(super) fn draw_tile_prism(
+		&mut self,
+		projector: &CameraProjector,
+		origin: (Scalar, Scalar),
+		size: (Scalar, Scalar),
+		depth: Scalar,
+		faces: PrismFaces,
+		role: Role,
+		surface_transform: Matrix4x4,
+		view_rotation: Matrix4x4,
+	) {
+		let (m, r) = (surface_transform, view_rotation);
+		let color = self.palette.get(role);
+		let thick = self.palette.thickens(role);
+		let f = 2.0 / FRAME_WIDTH as Scalar;
+		let (left, top) = origin;
+		let (right, bottom) = (left + size.0, top + size.1);
+		let (back, front) = (1.0, 1.0 + depth);
+		let point = |x: Scalar, y: Scalar, z: Scalar| {
+			vec3(x * f - 1.0, y * f - 1.0, z) * m * r
+		};
+
+		if faces.front {
+			self.draw_rect(
+				projector,
+				point(left, top, front),
+				point(right, top, front),
+				point(right, bottom, front),
+				point(left, bottom, front),
+				color,
+				thick,
+			);
+		}
+		if faces.top {
+			self.draw_rect(
+				projector,
+				point(left, top, back),
+				point(right, top, back),
+				point(right, top, front),
+				point(left, top, front),
+				color,
+				thick,
+			);
+		}
+		if faces.left {
+			self.draw_rect(
+				projector,
+				point(left, top, back),
+				point(left, top, front),
+				point(left, bottom, front),
+				point(left, bottom, back),
+				color,
+				thick,
+			);
+		}
+		if faces.bottom {
+			self.draw_rect(
+				projector,
+				point(left, bottom, front),
+				point(right, bottom, front),
+				point(right, bottom, back),
+				point(left, bottom, back),
+				color,
+				thick,
+			);
+		}
+		if faces.right {
+			self.draw_rect(
+				projector,
+				point(right, top, front),
+				point(right, top, back),
+				point(right, bottom, back),
+				point(right, bottom, front),
+				color,
+				thick,
+			);
+		}
+	}
+}
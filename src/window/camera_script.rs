@@ -0,0 +1,342 @@
+use std::collections::VecDeque;
+
+use super::{GamepadButton, Keycode, Window, WindowEvent};
+use crate::geometry::Scalar;
+use crate::world::{World, WorldEvent, WorldPosition, TILE_SIZE};
+
+// How long the camera stays on a tile a hook asked to show, and how fast it
+// goes there and back, in frame units a tick.
+const SHOW_TARGET_HOLD_TICKS: u32 = 90;
+const SHOW_TARGET_SPEED: Scalar = 0.04;
+
+// A camera move played while the world waits: over the surface to a
+// position, holding there, then back to the focus entity.
+#[derive(Copy, Clone, Debug)]
+pub struct CameraScript {
+	target: WorldPosition,
+	hold_ticks: u32,
+	// Frame units the view moves across the surface each tick.
+	speed: Scalar,
+}
+
+impl CameraScript {
+	pub fn pan_to(
+		position: WorldPosition,
+		hold_ticks: u32,
+		speed: Scalar,
+	) -> Self {
+		Self {
+			target: position,
+			hold_ticks,
+			speed,
+		}
+	}
+}
+
+#[derive(Copy, Clone, Debug)]
+enum Phase {
+	Going,
+	// Ticks left to hold for.
+	Holding(u32),
+	Returning,
+	// Back on the focus entity, for a last tick before handing the camera
+	// back.
+	Returned,
+}
+
+struct Running {
+	script: CameraScript,
+	phase: Phase,
+	// Where the camera follows instead of the focus entity.
+	view: WorldPosition,
+}
+
+// The script playing, and those started while it was, which play after it
+// in turn.
+pub struct CameraScripts {
+	running: Option<Running>,
+	queue: VecDeque<CameraScript>,
+}
+
+impl CameraScripts {
+	pub fn new() -> Self {
+		Self {
+			running: None,
+			queue: VecDeque::new(),
+		}
+	}
+
+	pub fn push(&mut self, script: CameraScript) {
+		self.queue.push_back(script);
+	}
+
+	pub fn is_playing(&self) -> bool {
+		self.running.is_some() || self.queue.is_empty() == false
+	}
+
+	// Where the camera is, while a script has it.
+	pub fn view(&self) -> Option<WorldPosition> {
+		self.running.as_ref().map(|running| running.view)
+	}
+
+	// Stops the script playing and drops any waiting, handing the camera
+	// straight back.
+	pub fn skip(&mut self) {
+		self.running = None;
+		self.queue.clear();
+	}
+
+	// Moves the playing script on by a tick, starting the next one if none
+	// is. The camera comes back to exactly where the focus entity is, so
+	// that it is the same once given back as it was before. Scripts are
+	// dropped while there is no focus entity to come back to. Whether a
+	// script had the camera this tick.
+	pub fn step(&mut self, world: &World) -> bool {
+		let home = match world.focus_entity.and_then(|id| world.get_entity(id))
+		{
+			Some(entity) => entity.position,
+			None => {
+				self.skip();
+				return false;
+			}
+		};
+		if let Some(Running {
+			phase: Phase::Returned,
+			..
+		}) = self.running
+		{
+			self.running = None;
+		}
+		if self.running.is_none() {
+			let script = match self.queue.pop_front() {
+				Some(script) => script,
+				None => return false,
+			};
+			self.running = Some(Running {
+				script,
+				phase: Phase::Going,
+				view: home,
+			});
+		}
+
+		let running = self.running.as_mut().unwrap();
+		let (target, speed) = (running.script.target, running.script.speed);
+		match running.phase {
+			Phase::Going => {
+				if move_towards(world, &mut running.view, target, speed) {
+					running.phase = Phase::Holding(running.script.hold_ticks);
+				}
+			}
+			Phase::Holding(0) => running.phase = Phase::Returning,
+			Phase::Holding(ticks) => running.phase = Phase::Holding(ticks - 1),
+			Phase::Returned => {}
+			Phase::Returning => {
+				if move_towards(world, &mut running.view, home, speed) {
+					running.phase = Phase::Returned;
+				}
+			}
+		}
+		true
+	}
+}
+
+// Moves `view` along the surface by `speed` towards `target`, landing on it
+// exactly once within reach. Anywhere it can't find a way to is jumped to.
+// Whether it got there.
+fn move_towards(
+	world: &World,
+	view: &mut WorldPosition,
+	target: WorldPosition,
+	speed: Scalar,
+) -> bool {
+	let distance = world.surface_distance(*view, target);
+	if distance <= speed || distance.is_finite() == false {
+		*view = target;
+		return true;
+	}
+	let direction = world.surface_direction(*view, target);
+	let moved = WorldPosition {
+		x: view.x + direction.x * speed,
+		y: view.y + direction.y * speed,
+		..*view
+	};
+	match moved.normalize(world) {
+		Ok(moved) => {
+			*view = moved;
+			false
+		}
+		Err(_) => {
+			*view = target;
+			true
+		}
+	}
+}
+
+impl Window {
+	// Shows each tile a hook asked to this tick, after any already showing.
+	pub(super) fn script_shown_targets(&mut self, world: &World) {
+		for event in world.events() {
+			if let WorldEvent::TargetShown { frame, x, y } = *event {
+				let target = WorldPosition {
+					frame_id: frame,
+					x: -1.0 + (x as Scalar + 0.5) * TILE_SIZE,
+					y: -1.0 + (y as Scalar + 0.5) * TILE_SIZE,
+				};
+				self.camera_scripts.push(CameraScript::pan_to(
+					target,
+					SHOW_TARGET_HOLD_TICKS,
+					SHOW_TARGET_SPEED,
+				));
+			}
+		}
+	}
+
+	// Input is ignored while a script plays, apart from skipping it.
+	// Releases and pointer moves still go through, so that nothing is left
+	// held afterwards. Whether the event was used up.
+	pub(super) fn camera_script_event(&mut self, event: &WindowEvent) -> bool {
+		if self.camera_scripts.is_playing() == false {
+			return false;
+		}
+		use WindowEvent::*;
+		match *event {
+			KeyDown(Keycode::Escape)
+			| KeyDown(Keycode::Return)
+			| GamepadDown(GamepadButton::Confirm)
+			| GamepadDown(GamepadButton::Cancel) => self.camera_scripts.skip(),
+			KeyUp(_) | MouseUp(_) | GamepadUp(_) | MouseMove(..) | Quit => {
+				return false
+			}
+			_ => {}
+		}
+		true
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// Somewhere on the `nth` frame other than the focus entity's.
+	fn away(world: &World, nth: usize) -> WorldPosition {
+		let home = home(world).unwrap().frame_id;
+		let frames = world.frame_ids().into_iter();
+		WorldPosition {
+			frame_id: frames.filter(|&id| id != home).nth(nth).unwrap(),
+			x: 0.25,
+			y: -0.5,
+		}
+	}
+
+	fn home(world: &World) -> Option<WorldPosition> {
+		let id = world.focus_entity?;
+		world.get_entity(id).map(|entity| entity.position)
+	}
+
+	fn same(a: WorldPosition, b: WorldPosition) -> bool {
+		(a.frame_id, a.x, a.y) == (b.frame_id, b.x, b.y)
+	}
+
+	// Every view the scripts give until they hand the camera back.
+	fn play(scripts: &mut CameraScripts, world: &World) -> Vec<WorldPosition> {
+		let mut views = Vec::new();
+		while scripts.step(world) {
+			assert!(views.len() < 10_000, "never finished");
+			views.push(scripts.view().unwrap());
+		}
+		views
+	}
+
+	#[test]
+	fn a_script_goes_there_holds_and_comes_back_exactly() {
+		let world = World::new();
+		let target = away(&world, 0);
+		let mut scripts = CameraScripts::new();
+		scripts.push(CameraScript::pan_to(target, 5, 0.1));
+		assert!(scripts.is_playing());
+		let views = play(&mut scripts, &world);
+
+		let held = views.iter().filter(|&&view| same(view, target)).count();
+		// Arriving, then each tick of holding, then the last before leaving.
+		assert_eq!(held, 5 + 2);
+		assert!(same(*views.last().unwrap(), home(&world).unwrap()));
+		assert!(scripts.is_playing() == false);
+		assert!(scripts.view().is_none());
+	}
+
+	#[test]
+	fn scripts_started_while_one_plays_come_after_it_in_turn() {
+		let world = World::new();
+		let (first, second) = (away(&world, 0), away(&world, 1));
+		let mut scripts = CameraScripts::new();
+		scripts.push(CameraScript::pan_to(first, 0, 0.1));
+		scripts.step(&world);
+		scripts.push(CameraScript::pan_to(second, 0, 0.1));
+		let views = play(&mut scripts, &world);
+		let reached =
+			|target| views.iter().position(|&view| same(view, target)).unwrap();
+		let home = home(&world).unwrap();
+		let back = views.iter().position(|&view| same(view, home)).unwrap();
+		assert!(reached(first) < back && back < reached(second));
+		assert!(same(*views.last().unwrap(), home));
+	}
+
+	#[test]
+	fn skipping_drops_the_script_and_every_one_waiting() {
+		let world = World::new();
+		let mut scripts = CameraScripts::new();
+		for _ in 0..3 {
+			scripts.push(CameraScript::pan_to(away(&world, 0), 10, 0.01));
+		}
+		for _ in 0..5 {
+			assert!(scripts.step(&world));
+		}
+		scripts.skip();
+		assert!(scripts.is_playing() == false);
+		assert!(scripts.view().is_none());
+		assert!(scripts.step(&world) == false);
+	}
+
+	#[test]
+	fn without_a_focus_entity_scripts_are_dropped() {
+		let mut world = World::new();
+		let mut scripts = CameraScripts::new();
+		scripts.push(CameraScript::pan_to(away(&world, 0), 10, 0.01));
+		world.remove_entity(world.focus_entity.unwrap());
+		assert!(scripts.step(&world) == false);
+		assert!(scripts.is_playing() == false);
+	}
+
+	// Cancelling comes before the menu and quitting get to see the key.
+	#[cfg(feature = "headless")]
+	#[test]
+	fn escape_skips_a_script_rather_than_quitting() {
+		use crate::GameState;
+
+		let mut game_state = GameState::from_world(World::new());
+		let mut window = Window::headless().unwrap();
+		let target = away(&game_state.world, 0);
+		window
+			.camera_scripts
+			.push(CameraScript::pan_to(target, 10, 0.01));
+		window.camera_scripts.step(&game_state.world);
+
+		window.backend.push_event(WindowEvent::KeyDown(Keycode::D));
+		window.poll_events(&mut game_state);
+		assert!(window.input_state.keys_held.is_empty());
+		assert!(window.camera_scripts.is_playing());
+
+		window
+			.backend
+			.push_event(WindowEvent::KeyDown(Keycode::Escape));
+		window.poll_events(&mut game_state);
+		assert!(window.camera_scripts.is_playing() == false);
+		assert!(window.should_exit == false);
+
+		window
+			.backend
+			.push_event(WindowEvent::KeyDown(Keycode::Escape));
+		window.poll_events(&mut game_state);
+		assert!(window.should_exit);
+	}
+}
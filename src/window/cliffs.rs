@@ -0,0 +1,84 @@
+use super::{CameraProjector, Role, Window};
+use crate::geometry::{vec3, Matrix4x4, Scalar};
+use crate::world::{Direction, FallOff, Frame, TILE_SIZE};
+
+// Ticks hanging off an open edge, and how far out each reaches.
+const CLIFF_TICKS: usize = 16;
+const CLIFF_TICK_LENGTH: Scalar = TILE_SIZE * 0.6;
+// How far past the edge something falling off it has drifted once it is
+// gone.
+const FALL_DRIFT: Scalar = TILE_SIZE * 1.5;
+
+// The edges of the frame drawn as cliffs, which are those it is open on.
+pub(super) fn cliff_edges(frame: &Frame) -> Vec<Direction> {
+	use Direction::*;
+	[Up, Right, Down, Left]
+		.iter()
+		.copied()
+		.filter(|&edge| frame.is_open(edge))
+		.collect()
+}
+
+// An entity's shapes around `center`, shrunk to nothing and moved out over
+// the edge as its fall goes on.
+pub(super) fn fall_off_shapes(
+	shapes: Vec<Vec<(Scalar, Scalar)>>,
+	center: (Scalar, Scalar),
+	fall: FallOff,
+) -> Vec<Vec<(Scalar, Scalar)>> {
+	let progress = fall.progress();
+	let scale = 1.0 - progress;
+	let (dx, dy) = fall.edge.unit();
+	let (cx, cy) = center;
+	let (cx_to, cy_to) = (
+		cx + dx * FALL_DRIFT * progress,
+		cy + dy * FALL_DRIFT * progress,
+	);
+	shapes
+		.into_iter()
+		.map(|shape| {
+			shape
+				.into_iter()
+				.map(|(x, y)| {
+					(cx_to + (x - cx) * scale, cy_to + (y - cy) * scale)
+				})
+				.collect()
+		})
+		.collect()
+}
+
+impl Window {
+	// A solid line along the edge with ticks hanging off it outwards, like
+	// a cliff on a map, so that it reads as a drop rather than as a link
+	// that is missing.
+	pub(super) fn draw_cliff(
+		&mut self,
+		projector: &CameraProjector,
+		edge: Direction,
+		surface_transform: Matrix4x4,
+		view_rotation: Matrix4x4,
+	) {
+		let (m, r) = (surface_transform, view_rotation);
+		let color = self.palette.get(Role::Cliff);
+		let (out_x, out_y) = edge.unit();
+		let (along_x, along_y) = (-out_y, out_x);
+		let point = |along: Scalar, out: Scalar| {
+			let x = out_x + along_x * along + out_x * out;
+			let y = out_y + along_y * along + out_y * out;
+			vec3(x, y, 1.0) * m * r
+		};
+		self.draw_line(projector, point(-1.0, 0.0), point(1.0, 0.0), color);
+		for i in 0..CLIFF_TICKS {
+			let along =
+				-1.0 + (i as Scalar + 0.5) * 2.0 / CLIFF_TICKS as Scalar;
+			// Alternately long and short, as hachures are drawn.
+			let length = if i % 2 == 0 {
+				CLIFF_TICK_LENGTH
+			} else {
+				CLIFF_TICK_LENGTH / 2.0
+			};
+			let (start, end) = (point(along, 0.0), point(along, length));
+			self.draw_line(projector, start, end, color);
+		}
+	}
+}
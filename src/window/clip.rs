@@ -0,0 +1,325 @@
+// Cohen–Sutherland clipping of projected screen-space segments, so lines
+// that extend far off screen never reach the backend at huge coordinates.
+
+use crate::geometry::Scalar;
+
+pub type ScreenPoint = (Scalar, Scalar);
+
+// The backend draws in `f32` whatever precision the rest of the crate uses.
+#[cfg(not(feature = "f64"))]
+pub fn backend_point(point: ScreenPoint) -> (f32, f32) {
+	point
+}
+#[cfg(feature = "f64")]
+pub fn backend_point((x, y): ScreenPoint) -> (f32, f32) {
+	(x as f32, y as f32)
+}
+
+// Extra space around the viewport that segments are allowed to extend into.
+// Keeps clipping from being visible at the viewport edges when outlines are
+// thickened or offset.
+pub const CLIP_MARGIN: Scalar = 16.0;
+
+const INSIDE: u8 = 0b0000;
+const LEFT: u8 = 0b0001;
+const RIGHT: u8 = 0b0010;
+const TOP: u8 = 0b0100;
+const BOTTOM: u8 = 0b1000;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ClipRect {
+	pub min_x: Scalar,
+	pub min_y: Scalar,
+	pub max_x: Scalar,
+	pub max_y: Scalar,
+}
+
+impl ClipRect {
+	pub fn viewport(width: Scalar, height: Scalar, margin: Scalar) -> Self {
+		Self {
+			min_x: -margin,
+			min_y: -margin,
+			max_x: width + margin,
+			max_y: height + margin,
+		}
+	}
+
+	pub fn contains(&self, (x, y): ScreenPoint) -> bool {
+		self.outcode((x, y)) == INSIDE
+	}
+
+	fn outcode(&self, (x, y): ScreenPoint) -> u8 {
+		let mut code = INSIDE;
+		if x < self.min_x {
+			code |= LEFT;
+		} else if x > self.max_x {
+			code |= RIGHT;
+		}
+		if y < self.min_y {
+			code |= TOP;
+		} else if y > self.max_y {
+			code |= BOTTOM;
+		}
+		code
+	}
+
+	// Returns the part of the segment inside the rectangle, or `None` if the
+	// segment lies entirely outside it.
+	pub fn clip_segment(
+		&self,
+		start: ScreenPoint,
+		end: ScreenPoint,
+	) -> Option<(ScreenPoint, ScreenPoint)> {
+		let (mut a, mut b) = (start, end);
+		let finite = |(x, y): ScreenPoint| x.is_finite() && y.is_finite();
+		if finite(a) == false || finite(b) == false {
+			return None;
+		}
+
+		let mut code_a = self.outcode(a);
+		let mut code_b = self.outcode(b);
+
+		loop {
+			if code_a | code_b == INSIDE {
+				return Some((a, b));
+			}
+			if code_a & code_b != INSIDE {
+				return None;
+			}
+
+			// At least one endpoint is outside; move it onto the rectangle
+			// edge it lies beyond.
+			let code = if code_a != INSIDE { code_a } else { code_b };
+			let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+			let point = if code & BOTTOM != 0 {
+				(a.0 + dx * (self.max_y - a.1) / dy, self.max_y)
+			} else if code & TOP != 0 {
+				(a.0 + dx * (self.min_y - a.1) / dy, self.min_y)
+			} else if code & RIGHT != 0 {
+				(self.max_x, a.1 + dy * (self.max_x - a.0) / dx)
+			} else {
+				(self.min_x, a.1 + dy * (self.min_x - a.0) / dx)
+			};
+
+			if code == code_a {
+				a = point;
+				code_a = self.outcode(a);
+			} else {
+				b = point;
+				code_b = self.outcode(b);
+			}
+		}
+	}
+
+	// Clips a connected line strip, splitting it into several strips wherever
+	// it leaves the rectangle.
+	pub fn clip_polyline(
+		&self,
+		points: &[ScreenPoint],
+	) -> Vec<Vec<ScreenPoint>> {
+		let mut strips: Vec<Vec<ScreenPoint>> = Vec::new();
+		let mut current: Vec<ScreenPoint> = Vec::new();
+
+		for pair in points.windows(2) {
+			match self.clip_segment(pair[0], pair[1]) {
+				Some((a, b)) => {
+					if current.last() != Some(&a) {
+						if current.len() > 1 {
+							strips.push(current);
+						}
+						current = vec![a];
+					}
+					current.push(b);
+				}
+				None => {
+					if current.len() > 1 {
+						strips.push(current);
+					}
+					current = Vec::new();
+				}
+			}
+		}
+
+		if current.len() > 1 {
+			strips.push(current);
+		}
+
+		strips
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const RECT: ClipRect = ClipRect {
+		min_x: 0.0,
+		min_y: 0.0,
+		max_x: 800.0,
+		max_y: 600.0,
+	};
+	// As near a clipped endpoint has to be to the edge it was moved onto,
+	// which is loose enough for `f32` segments millions of pixels long.
+	const ON_EDGE: Scalar = 0.01;
+
+	fn on_edge((x, y): ScreenPoint) -> bool {
+		let loose = ClipRect {
+			min_x: RECT.min_x - ON_EDGE,
+			min_y: RECT.min_y - ON_EDGE,
+			max_x: RECT.max_x + ON_EDGE,
+			max_y: RECT.max_y + ON_EDGE,
+		};
+		let distance = (x - RECT.min_x)
+			.abs()
+			.min((x - RECT.max_x).abs())
+			.min((y - RECT.min_y).abs())
+			.min((y - RECT.max_y).abs());
+		loose.contains((x, y)) && distance <= ON_EDGE
+	}
+
+	// The clipped part of a segment, checking that each end either stayed
+	// where it was or was moved onto the edge of the rectangle.
+	fn clip(
+		start: ScreenPoint,
+		end: ScreenPoint,
+	) -> Option<(ScreenPoint, ScreenPoint)> {
+		let clipped = RECT.clip_segment(start, end);
+		if let Some((a, b)) = clipped {
+			for &(moved, from) in [(a, start), (b, end)].iter() {
+				assert!(
+					moved == from || on_edge(moved),
+					"{:?} to {:?} was clipped to {:?}",
+					start,
+					end,
+					(a, b)
+				);
+				assert!(RECT.contains(from) == false || moved == from);
+			}
+		}
+		clipped
+	}
+
+	fn close(a: ScreenPoint, b: ScreenPoint, epsilon: Scalar) -> bool {
+		(a.0 - b.0).abs() <= epsilon && (a.1 - b.1).abs() <= epsilon
+	}
+
+	#[test]
+	fn a_segment_inside_is_left_alone() {
+		let (a, b) = ((10.0, 20.0), (790.0, 580.0));
+		assert_eq!(clip(a, b), Some((a, b)));
+	}
+
+	#[test]
+	fn a_segment_entirely_outside_is_dropped() {
+		// To one side, and past a corner without touching it.
+		assert_eq!(clip((-50.0, 10.0), (-10.0, 590.0)), None);
+		assert_eq!(clip((900.0, 10.0), (900.0, 590.0)), None);
+		assert_eq!(clip((-100.0, 50.0), (50.0, -100.0)), None);
+		assert_eq!(clip((850.0, 700.0), (900.0, 550.0)), None);
+	}
+
+	#[test]
+	fn a_segment_far_off_screen_is_cut_to_the_viewport() {
+		let far = 1e7;
+		// Across the whole viewport each way, and diagonally from corner to
+		// corner through the middle.
+		let (a, b) = clip((-far, 300.0), (far, 300.0)).unwrap();
+		assert!(close(a, (0.0, 300.0), ON_EDGE), "{:?}", a);
+		assert!(close(b, (800.0, 300.0), ON_EDGE), "{:?}", b);
+		let (a, b) = clip((400.0, far), (400.0, -far)).unwrap();
+		assert!(close(a, (400.0, 600.0), ON_EDGE), "{:?}", a);
+		assert!(close(b, (400.0, 0.0), ON_EDGE), "{:?}", b);
+		let (a, b) =
+			clip((400.0 - far, 300.0 - far), (400.0 + far, 300.0 + far))
+				.unwrap();
+		assert!(close(a, (100.0, 0.0), 1.0), "{:?}", a);
+		assert!(close(b, (700.0, 600.0), 1.0), "{:?}", b);
+		// From inside out to far away.
+		let (a, b) = clip((400.0, 300.0), (far, -far)).unwrap();
+		assert_eq!(a, (400.0, 300.0));
+		assert!(close(b, (700.0, 0.0), 1.0), "{:?}", b);
+		assert_eq!(clip((-far, -far), (-far, far)), None);
+	}
+
+	#[test]
+	fn a_segment_crossing_a_corner_keeps_the_part_inside() {
+		// Into the top left corner from above and to the left, then out
+		// through the edges either side of the bottom right corner.
+		let (a, b) = clip((-100.0, 50.0), (100.0, -50.0)).unwrap();
+		assert!(close(a, (0.0, 0.0), ON_EDGE), "{:?}", a);
+		assert!(close(b, (0.0, 0.0), ON_EDGE), "{:?}", b);
+		let (a, b) = clip((700.0, 700.0), (900.0, 500.0)).unwrap();
+		assert!(close(a, (800.0, 600.0), ON_EDGE), "{:?}", a);
+		assert!(close(b, (800.0, 600.0), ON_EDGE), "{:?}", b);
+		let (a, b) = clip((750.0, 500.0), (850.0, 700.0)).unwrap();
+		assert_eq!(a, (750.0, 500.0));
+		assert!(close(b, (800.0, 600.0), ON_EDGE), "{:?}", b);
+	}
+
+	#[test]
+	fn zero_length_segments_are_kept_only_inside() {
+		let inside = (400.0, 300.0);
+		assert_eq!(clip(inside, inside), Some((inside, inside)));
+		let on_corner = (800.0, 600.0);
+		assert_eq!(clip(on_corner, on_corner), Some((on_corner, on_corner)));
+		assert_eq!(clip((-1.0, 300.0), (-1.0, 300.0)), None);
+		assert_eq!(clip((1e7, 1e7), (1e7, 1e7)), None);
+	}
+
+	#[test]
+	fn segments_that_arent_numbers_are_dropped() {
+		let nan = Scalar::NAN;
+		assert_eq!(clip((nan, 0.0), (100.0, 100.0)), None);
+		assert_eq!(clip((0.0, 0.0), (Scalar::INFINITY, 100.0)), None);
+	}
+
+	#[test]
+	fn a_polyline_is_split_where_it_leaves() {
+		let points = [
+			(100.0, 100.0),
+			(200.0, 100.0),
+			(1000.0, 100.0),
+			(1000.0, 200.0),
+			(200.0, 200.0),
+			(300.0, 200.0),
+		];
+		let strips = RECT.clip_polyline(&points);
+		assert_eq!(strips.len(), 2, "{:?}", strips);
+		assert_eq!(
+			strips[0],
+			vec![(100.0, 100.0), (200.0, 100.0), (800.0, 100.0)]
+		);
+		assert_eq!(
+			strips[1],
+			vec![(800.0, 200.0), (200.0, 200.0), (300.0, 200.0)]
+		);
+		for point in strips.iter().flatten() {
+			assert!(RECT.contains(*point), "{:?}", point);
+		}
+	}
+
+	#[test]
+	fn a_polyline_far_off_screen_stays_on_the_viewport() {
+		let far = 1e7;
+		let points = [
+			(-far, -far),
+			(far, far),
+			(far, 600.0 - far),
+			(-far, 600.0 + far),
+		];
+		let strips = RECT.clip_polyline(&points);
+		// Each diagonal crosses the viewport, but the edge between them
+		// is far off to the right.
+		assert_eq!(strips.len(), 2, "{:?}", strips);
+		for strip in strips.iter() {
+			assert_eq!(strip.len(), 2);
+			assert!(strip.iter().all(|&point| on_edge(point)), "{:?}", strip);
+		}
+	}
+
+	#[test]
+	fn a_polyline_of_one_point_or_none_draws_nothing() {
+		assert!(RECT.clip_polyline(&[]).is_empty());
+		assert!(RECT.clip_polyline(&[(400.0, 300.0)]).is_empty());
+	}
+}
@@ -0,0 +1,493 @@
+use std::collections::VecDeque;
+
+use super::{font, GridMode, PaletteKind, Role, Window, TEXT_SCALE};
+use crate::cvars::{complete_cvar, find_cvar, Cvar, CvarField, CvarValue};
+use crate::geometry::{vec3, Scalar, Vector3};
+use crate::logging;
+use crate::prelude::*;
+use crate::settings::{parse_ui_scale, ui_scale_name};
+use crate::world::{Cheats, EdgeBehavior, EntityKind, FrameId, Tile, World};
+use crate::GameState;
+
+// How many lines of output are kept and shown above the prompt.
+const CONSOLE_OUTPUT_LINES: usize = 8;
+const CONSOLE_PADDING: Scalar = 8.0;
+
+// Command names and usage, listed by `help`.
+const COMMANDS: &[(&str, &str)] = &[
+	("help", "help"),
+	("set", "set <cvar> <value>"),
+	("get", "get <cvar or start of one>"),
+	("reset", "reset <cvar>"),
+	("palette", "palette [default|high_contrast|deuteranopia]"),
+	("grid", "grid [off|focus|all]"),
+	("ui_scale", "ui_scale [auto|0.5-4]"),
+	("max_fps", "max_fps [fps, 0 for none]"),
+	("autosave", "autosave [seconds, 0 for none]"),
+	("late_input", "late_input [on|off]"),
+	("save", "save"),
+	("load", "load"),
+	("restore", "restore"),
+	("tile", "tile [name]"),
+	("entity", "entity [kind|off]"),
+	("gravity", "gravity"),
+	("wind", "wind [x y|off]"),
+	("cheat", "cheat [god|noclip|infinite_jumps] [on|off]"),
+	("stress", "stress [walkers] [edge behavior]"),
+	("stats", "stats"),
+	("log", "log tail [lines]"),
+	("state_hash", "state_hash"),
+	("dump", "dump"),
+	("tagged", "tagged [tag]"),
+	("hud", "hud"),
+	("net", "net"),
+	("debug", "debug"),
+];
+
+// A single line text prompt for commands, opened with the backquote key.
+pub struct Console {
+	pub open: bool,
+	input: String,
+	output: VecDeque<String>,
+}
+
+impl Console {
+	pub fn new() -> Self {
+		Self {
+			open: false,
+			input: String::new(),
+			output: VecDeque::new(),
+		}
+	}
+
+	pub fn toggle(&mut self) {
+		self.open = !self.open;
+	}
+
+	pub fn text_input(&mut self, text: &str) {
+		// The key that opens the console also produces text.
+		self.input
+			.extend(text.chars().filter(|&c| c != '`' && c != '\n'));
+	}
+
+	pub fn backspace(&mut self) {
+		self.input.pop();
+	}
+
+	// Takes the typed line, echoing it to the output. `None` if nothing was
+	// typed.
+	pub fn submit(&mut self) -> Option<String> {
+		let line = std::mem::take(&mut self.input);
+		let line = line.trim().to_string();
+		if line.is_empty() {
+			return None;
+		}
+		self.print(format!("> {}", line));
+		Some(line)
+	}
+
+	// Completes the cvar name being typed after `set`, `get` or `reset` as
+	// far as every cvar it could be agrees, listing them if there are more
+	// than one.
+	pub fn complete(&mut self) {
+		let mut words = self.input.splitn(2, ' ');
+		let command = words.next().unwrap_or("");
+		let prefix = match (command, words.next()) {
+			("set", Some(prefix))
+			| ("get", Some(prefix))
+			| ("reset", Some(prefix))
+				if prefix.contains(' ') == false =>
+			{
+				prefix.to_string()
+			}
+			_ => return,
+		};
+		let names = complete_cvar(&prefix);
+		let first = match names.first() {
+			Some(first) => first,
+			None => return,
+		};
+		let mut common = first.len();
+		for name in names.iter() {
+			common = first
+				.bytes()
+				.zip(name.bytes())
+				.take(common)
+				.take_while(|(a, b)| a == b)
+				.count();
+		}
+		self.input = format!("{} {}", command, &first[..common]);
+		if names.len() == 1 {
+			self.input.push(' ');
+		} else {
+			self.print(names.join(" "));
+		}
+	}
+
+	pub fn print<T: Into<String>>(&mut self, line: T) {
+		self.output.push_back(line.into());
+		while self.output.len() > CONSOLE_OUTPUT_LINES {
+			self.output.pop_front();
+		}
+	}
+}
+
+impl Window {
+	pub(super) fn run_console_command(
+		&mut self,
+		game_state: &mut GameState,
+		line: &str,
+	) {
+		let mut words = line.split_whitespace();
+		let command = words.next().unwrap_or("");
+		let args: Vec<&str> = words.collect();
+
+		match (command, args.as_slice()) {
+			("help", []) => {
+				for &(_, usage) in COMMANDS {
+					self.console.print(usage);
+				}
+			}
+			("set", [name, value]) => match find_cvar(name) {
+				Some(cvar) => match cvar.parse(value) {
+					Ok(value) => self.set_cvar(game_state, cvar, value),
+					Err(message) => self.console.print(message),
+				},
+				None => self.console.print(format!("unknown cvar {}", name)),
+			},
+			("get", [prefix]) => {
+				let cvars = cvars_starting_with(prefix);
+				if cvars.is_empty() {
+					self.console.print(format!("unknown cvar {}", prefix));
+				}
+				for cvar in cvars {
+					let value = self.cvar_value(game_state, cvar);
+					self.console.print(format!(
+						"{}: {} ({}, default {})",
+						cvar.name,
+						value,
+						cvar.range_name(),
+						cvar.default_value()
+					));
+				}
+			}
+			("reset", [name]) => match find_cvar(name) {
+				Some(cvar) => {
+					self.set_cvar(game_state, cvar, cvar.default_value())
+				}
+				None => self.console.print(format!("unknown cvar {}", name)),
+			},
+			("palette", []) => {
+				let name = self.palette.name();
+				self.console.print(format!("palette: {}", name));
+			}
+			("palette", [name]) => match PaletteKind::from_name(name) {
+				Some(kind) => self.set_palette(kind),
+				None => self.console.print(format!("unknown palette {}", name)),
+			},
+			("grid", []) => {
+				let mode = self.grid_mode.name();
+				self.console.print(format!("grid: {}", mode));
+			}
+			("grid", [name]) => match GridMode::from_name(name) {
+				Some(mode) => self.grid_mode = mode,
+				None => {
+					self.console.print(format!("unknown grid mode {}", name))
+				}
+			},
+			("ui_scale", []) => {
+				let scale = ui_scale_name(self.settings.ui_scale);
+				self.console.print(format!("ui_scale: {}", scale));
+			}
+			("ui_scale", [value]) => match parse_ui_scale(value) {
+				Some(scale) => self.set_ui_scale(scale),
+				None => self.console.print(format!("bad UI scale {}", value)),
+			},
+			("max_fps", []) => {
+				let fps = self.settings.max_fps;
+				self.console.print(format!("max_fps: {}", fps));
+			}
+			("max_fps", [value]) => match value.parse() {
+				Ok(fps) => self.set_max_fps(fps),
+				_ => self.console.print(format!("bad fps {}", value)),
+			},
+			("autosave", []) => {
+				let seconds = self.settings.autosave_interval;
+				self.console
+					.print(format!("autosave_interval: {}", seconds));
+			}
+			("autosave", [value]) => match value.parse() {
+				Ok(seconds) => self.set_autosave_interval(seconds),
+				_ => self.console.print(format!("bad interval {}", value)),
+			},
+			("late_input", []) => {
+				let on = self.settings.late_input;
+				let state = if on { "on" } else { "off" };
+				self.console.print(format!("late_input: {}", state));
+			}
+			("late_input", ["on"]) => self.set_late_input(true),
+			("late_input", ["off"]) => self.set_late_input(false),
+			("save", []) => self.save_world(game_state),
+			("load", []) => self.load_world(game_state, false),
+			("restore", []) => self.load_world(game_state, true),
+			("tile", []) => {
+				let name = self.selected_tile.name();
+				self.console.print(format!("tile: {}", name));
+				let names: Vec<&str> =
+					Tile::placeable().iter().map(|tile| tile.name()).collect();
+				self.console.print(names.join(" "));
+			}
+			("tile", [name]) => match Tile::from_name(name) {
+				Some(tile) => {
+					self.selected_tile = tile;
+					self.selected_entity = None;
+				}
+				None => self.console.print(format!("unknown tile {}", name)),
+			},
+			("entity", []) => {
+				let name =
+					self.selected_entity.map_or("off", |kind| kind.name());
+				self.console.print(format!("entity: {}", name));
+				let names: Vec<&str> = EntityKind::placeable()
+					.iter()
+					.map(|kind| kind.name())
+					.collect();
+				self.console.print(names.join(" "));
+			}
+			("entity", ["off"]) => self.selected_entity = None,
+			("entity", [name]) => match EntityKind::from_name(name) {
+				Some(kind) if kind != EntityKind::Player => {
+					self.selected_entity = Some(kind)
+				}
+				_ => self.console.print(format!("unknown entity {}", name)),
+			},
+			("gravity", []) => {
+				let world = &mut game_state.world;
+				if let Some(player) = world.focus_entity {
+					world.flip_gravity(player);
+				}
+			}
+			("wind", []) => {
+				let world = &game_state.world;
+				let wind = focus_frame(world)
+					.and_then(|frame_id| world.get_frame(frame_id))
+					.and_then(|frame| frame.wind);
+				match wind {
+					Some(wind) => self
+						.console
+						.print(format!("wind: {} {}", wind.x, wind.y)),
+					None => self.console.print("wind: off"),
+				}
+			}
+			("wind", ["off"]) => self.set_focus_wind(game_state, None),
+			("wind", [x, y]) => match (x.parse(), y.parse()) {
+				(Ok(x), Ok(y)) => {
+					self.set_focus_wind(game_state, Some(vec3(x, y, 0.0)))
+				}
+				_ => self.console.print(format!("bad wind {} {}", x, y)),
+			},
+			("cheat", []) => {
+				let world = &game_state.world;
+				let player =
+					world.focus_entity.and_then(|id| world.get_entity(id));
+				if let Some(player) = player {
+					for &name in Cheats::names() {
+						let on = player.cheats.get(name).unwrap();
+						let state = if on { "on" } else { "off" };
+						self.console.print(format!("{}: {}", name, state));
+					}
+				}
+			}
+			("cheat", [name])
+			| ("cheat", [name, "on"])
+			| ("cheat", [name, "off"]) => {
+				let world = &mut game_state.world;
+				let player =
+					world.focus_entity.and_then(|id| world.get_entity_mut(id));
+				if let Some(player) = player {
+					// A cheat named without a state is toggled.
+					let on = match args.get(1) {
+						Some(&state) => state == "on",
+						None => player.cheats.get(name) == Some(false),
+					};
+					if player.cheats.set(name, on) == false {
+						self.console.print(format!("unknown cheat {}", name));
+					}
+				}
+			}
+			("stress", [value]) => match value.parse() {
+				Ok(count) => {
+					let behavior = EdgeBehavior::default();
+					self.start_stress(game_state, count, behavior)
+				}
+				_ => self.console.print(format!("bad count {}", value)),
+			},
+			("stress", [value, name]) => {
+				match (value.parse(), EdgeBehavior::from_name(name)) {
+					(Ok(count), Some(behavior)) => {
+						self.start_stress(game_state, count, behavior)
+					}
+					(Err(_), _) => {
+						self.console.print(format!("bad count {}", value))
+					}
+					(_, None) => self
+						.console
+						.print(format!("unknown edge behavior {}", name)),
+				}
+			}
+			("stats", []) => {
+				for line in game_state.world.stats().lines() {
+					self.console.print(line);
+				}
+				#[cfg(feature = "profiling")]
+				self.show_worst_frames(game_state);
+			}
+			("log", ["tail"]) => self.show_log_tail(game_state, 20),
+			("log", ["tail", value]) => match value.parse() {
+				Ok(count) => self.show_log_tail(game_state, count),
+				_ => self.console.print(format!("bad line count {}", value)),
+			},
+			("state_hash", []) => {
+				let world = &game_state.world;
+				self.console.print(format!(
+					"tick {}: {:016x}",
+					world.tick_count,
+					world.state_hash()
+				));
+			}
+			("dump", []) => {
+				let dump = game_state.world.dump_state();
+				for line in dump.lines() {
+					log(line);
+				}
+				self.console.print(format!(
+					"dumped {} lines to the log",
+					dump.lines().count()
+				));
+			}
+			("tagged", [name]) => {
+				let ids = game_state.world.entities_with_tag(name);
+				let ids: Vec<String> =
+					ids.iter().map(|id| id.0.to_string()).collect();
+				self.console.print(format!("{}: {}", name, ids.join(" ")));
+			}
+			("hud", []) => self.hud.toggle(),
+			("net", []) => self.toggle_net_window(),
+			("debug", []) => self.debug_mode = !self.debug_mode,
+			_ => {
+				let usage = COMMANDS.iter().find(|&&(name, _)| name == command);
+				match usage {
+					Some(&(_, usage)) => {
+						self.console.print(format!("usage: {}", usage))
+					}
+					None => self
+						.console
+						.print(format!("unknown command {}", command)),
+				}
+			}
+		}
+	}
+
+	fn cvar_value(&self, game_state: &GameState, cvar: &Cvar) -> CvarValue {
+		match cvar.field {
+			CvarField::Physics(get, _) => get(&game_state.world.physics),
+			CvarField::Settings(get, _) => get(&self.settings),
+		}
+	}
+
+	// Settings take effect straight away and are saved along with the rest
+	// of the settings file.
+	fn set_cvar(
+		&mut self,
+		game_state: &mut GameState,
+		cvar: &Cvar,
+		value: CvarValue,
+	) {
+		match cvar.field {
+			CvarField::Physics(_, set) => {
+				set(&mut game_state.world.physics, value)
+			}
+			CvarField::Settings(_, set) => {
+				set(&mut self.settings, value);
+				self.backend.set_vsync(self.settings.vsync);
+				self.backend.set_render_scale(self.settings.render_scale);
+				self.settings.save();
+			}
+		}
+		log(format!("{}: {}", cvar.name, value));
+	}
+
+	// On the frame the player is in, which is where it is drawn.
+	fn set_focus_wind(
+		&mut self,
+		game_state: &mut GameState,
+		wind: Option<Vector3>,
+	) {
+		let world = &mut game_state.world;
+		match focus_frame(world) {
+			Some(frame_id) => {
+				world.set_wind(frame_id, wind);
+			}
+			None => self.console.print("no player to set the wind around"),
+		}
+	}
+
+	// More lines than the console keeps, so they go in a text box, which
+	// closes like a sign's.
+	fn show_log_tail(&mut self, game_state: &mut GameState, count: usize) {
+		let lines = logging::tail(count);
+		if lines.is_empty() {
+			self.console.print("nothing logged yet");
+			return;
+		}
+		game_state.text_box = Some(lines.join("\n"));
+	}
+
+	// Long lines, so in a text box like the log tail.
+	#[cfg(feature = "profiling")]
+	fn show_worst_frames(&mut self, game_state: &mut GameState) {
+		let frames = self.watchdog.worst_frames();
+		if frames.is_empty() {
+			self.console.print("no slow frames");
+			return;
+		}
+		let lines: Vec<String> = frames.iter().map(|f| f.summary()).collect();
+		game_state.text_box = Some(lines.join("\n"));
+	}
+
+	// Output lines followed by the prompt, in a box along the top of the
+	// screen.
+	pub(super) fn draw_console(&mut self) {
+		if self.console.open == false {
+			return;
+		}
+
+		let line_height = (font::LINE_ADVANCE * TEXT_SCALE) as Scalar;
+		let mut lines: Vec<String> =
+			self.console.output.iter().cloned().collect();
+		lines.push(format!("> {}_", self.console.input));
+
+		let (width, _) = self.ui_size();
+		let height =
+			lines.len() as Scalar * line_height + CONSOLE_PADDING * 2.0;
+		let color = self.palette.get(Role::UiText);
+		self.draw_ui_lines(&[(0.0, height), (width, height)], color);
+
+		for (index, line) in lines.iter().enumerate() {
+			let y = CONSOLE_PADDING + index as Scalar * line_height;
+			self.draw_text(line, CONSOLE_PADDING, y, TEXT_SCALE, color);
+		}
+	}
+}
+
+fn cvars_starting_with(prefix: &str) -> Vec<&'static Cvar> {
+	complete_cvar(prefix)
+		.into_iter()
+		.filter_map(find_cvar)
+		.collect()
+}
+
+fn focus_frame(world: &World) -> Option<FrameId> {
+	let player = world.get_entity(world.focus_entity?)?;
+	Some(player.position.frame_id)
+}
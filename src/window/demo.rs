@@ -0,0 +1,97 @@
+use super::{font, Role, Window, WindowEvent, TEXT_SCALE};
+use crate::geometry::Scalar;
+use crate::prelude::*;
+use crate::world::TICK_RATE;
+use crate::GameState;
+
+// How long the menu waits for the player before playing the demo.
+const DEMO_IDLE_TICKS: u64 = (60.0 * TICK_RATE) as u64;
+// Distance of the demo's caption from the top of the viewport.
+const CAPTION_INSET: Scalar = 8.0;
+// Ticks the caption stays on, then off, for.
+const CAPTION_BLINK_TICKS: u64 = 40;
+
+// Anything the player did, as opposed to what the platform tells of by
+// itself, which keeps the menu from going idle.
+fn is_activity(event: &WindowEvent) -> bool {
+	use WindowEvent::*;
+	match *event {
+		KeyDown(_) | MouseDown(_) | MouseMove(..) | MouseWheel(_) => true,
+		GamepadDown(_) | TextInput(_) => true,
+		_ => false,
+	}
+}
+
+// What ends the demo: a key or a button, but not just moving the mouse.
+fn is_press(event: &WindowEvent) -> bool {
+	use WindowEvent::*;
+	match *event {
+		KeyDown(_) | MouseDown(_) | GamepadDown(_) => true,
+		_ => false,
+	}
+}
+
+impl Window {
+	// Starts the demo once the menu has been left alone for long enough,
+	// and goes back to the menu once it has played out.
+	pub(super) fn update_demo(&mut self, game_state: &mut GameState) {
+		if game_state.demo_finished() {
+			self.return_to_menu(game_state);
+			return;
+		}
+		let idle = self.input_state.idle_ticks >= DEMO_IDLE_TICKS;
+		let on_menu = match &game_state.menu {
+			Some(menu) => menu.saves.is_none(),
+			None => false,
+		};
+		if idle && on_menu && self.console.open == false {
+			self.start_demo(game_state);
+		}
+	}
+
+	// The whole game is replaced, so nothing of the demo is left over once
+	// it is replaced in turn.
+	fn start_demo(&mut self, game_state: &mut GameState) {
+		self.input_state.idle_ticks = 0;
+		match GameState::demo() {
+			Ok(demo) => *game_state = demo,
+			Err(error) => {
+				elog(error);
+				return;
+			}
+		}
+		self.input_state.release_keys();
+		self.autosave.mark_saved(&game_state.world);
+	}
+
+	// Notes the player's activity, and ends the demo on any press, which is
+	// used up in doing so. Whether the event was.
+	pub(super) fn demo_event(
+		&mut self,
+		game_state: &mut GameState,
+		event: &WindowEvent,
+	) -> bool {
+		if is_activity(event) {
+			self.input_state.idle_ticks = 0;
+		}
+		if game_state.demo.is_none() || is_press(event) == false {
+			return false;
+		}
+		self.return_to_menu(game_state);
+		true
+	}
+
+	// Blinking along the top, where the timer would otherwise be.
+	pub(super) fn draw_demo_caption(&mut self, game_state: &GameState) {
+		let shown = (game_state.world.tick_count / CAPTION_BLINK_TICKS).is_multiple_of(2);
+		if game_state.demo.is_none() || shown == false {
+			return;
+		}
+		let text = "DEMO - press any key";
+		let pixel = TEXT_SCALE as Scalar;
+		let width = font::text_width(text) as Scalar * pixel;
+		let x = (self.ui_size().0 - width) / 2.0;
+		let color = self.palette.get(Role::UiText);
+		self.draw_text(text, x, CAPTION_INSET, TEXT_SCALE, color);
+	}
+}
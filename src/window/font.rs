@@ -0,0 +1,168 @@
+// A fixed 5x7 bitmap font. Neither backend can fill shapes, so glyphs are
+// turned into horizontal runs of lit pixels which are then drawn as lines.
+
+pub const GLYPH_WIDTH: usize = 5;
+pub const GLYPH_HEIGHT: usize = 7;
+// Horizontal distance between the start of one glyph and the next.
+pub const GLYPH_ADVANCE: usize = GLYPH_WIDTH + 1;
+// Vertical distance between the top of one line of text and the next.
+pub const LINE_ADVANCE: usize = GLYPH_HEIGHT + 3;
+
+type Glyph = [u8; GLYPH_HEIGHT];
+
+// Drawn for characters the font has no glyph for.
+#[rustfmt::skip]
+const MISSING_GLYPH: Glyph =
+	[0b11111, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b11111];
+
+// Rows of the glyph from top to bottom, with the most significant of the
+// low five bits as the leftmost pixel. Lowercase letters share the uppercase
+// glyphs.
+#[rustfmt::skip]
+pub fn glyph(c: char) -> Glyph {
+	match c.to_ascii_uppercase() {
+		' ' => [0; GLYPH_HEIGHT],
+		'A' => [0b01110, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001],
+		'B' => [0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110],
+		'C' => [0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110],
+		'D' => [0b11110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b11110],
+		'E' => [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111],
+		'F' => [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000],
+		'G' => [0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111],
+		'H' => [0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001],
+		'I' => [0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110],
+		'J' => [0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100],
+		'K' => [0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001],
+		'L' => [0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111],
+		'M' => [0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001],
+		'N' => [0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001],
+		'O' => [0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110],
+		'P' => [0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000],
+		'Q' => [0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101],
+		'R' => [0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001],
+		'S' => [0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110],
+		'T' => [0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100],
+		'U' => [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110],
+		'V' => [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100],
+		'W' => [0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010],
+		'X' => [0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001],
+		'Y' => [0b10001, 0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100],
+		'Z' => [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111],
+		'0' => [0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110],
+		'1' => [0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110],
+		'2' => [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111],
+		'3' => [0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110],
+		'4' => [0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010],
+		'5' => [0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110],
+		'6' => [0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110],
+		'7' => [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000],
+		'8' => [0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110],
+		'9' => [0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100],
+		'!' => [0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00000, 0b00100],
+		'"' => [0b01010, 0b01010, 0b01010, 0b00000, 0b00000, 0b00000, 0b00000],
+		'#' => [0b01010, 0b01010, 0b11111, 0b01010, 0b11111, 0b01010, 0b01010],
+		'%' => [0b11000, 0b11001, 0b00010, 0b00100, 0b01000, 0b10011, 0b00011],
+		'&' => [0b01100, 0b10010, 0b10100, 0b01000, 0b10101, 0b10010, 0b01101],
+		'\'' => [0b00100, 0b00100, 0b01000, 0b00000, 0b00000, 0b00000, 0b00000],
+		'(' => [0b00010, 0b00100, 0b01000, 0b01000, 0b01000, 0b00100, 0b00010],
+		')' => [0b01000, 0b00100, 0b00010, 0b00010, 0b00010, 0b00100, 0b01000],
+		'*' => [0b00000, 0b00100, 0b10101, 0b01110, 0b10101, 0b00100, 0b00000],
+		'+' => [0b00000, 0b00100, 0b00100, 0b11111, 0b00100, 0b00100, 0b00000],
+		',' => [0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b00100, 0b01000],
+		'-' => [0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000],
+		'.' => [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b01100],
+		'/' => [0b00000, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b00000],
+		':' => [0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b01100, 0b00000],
+		';' => [0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b00100, 0b01000],
+		'<' => [0b00010, 0b00100, 0b01000, 0b10000, 0b01000, 0b00100, 0b00010],
+		'=' => [0b00000, 0b00000, 0b11111, 0b00000, 0b11111, 0b00000, 0b00000],
+		'>' => [0b01000, 0b00100, 0b00010, 0b00001, 0b00010, 0b00100, 0b01000],
+		'?' => [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b00000, 0b00100],
+		'@' => [0b01110, 0b10001, 0b00001, 0b01101, 0b10101, 0b10101, 0b01110],
+		'[' => [0b01110, 0b01000, 0b01000, 0b01000, 0b01000, 0b01000, 0b01110],
+		']' => [0b01110, 0b00010, 0b00010, 0b00010, 0b00010, 0b00010, 0b01110],
+		'_' => [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b11111],
+		_ => MISSING_GLYPH,
+	}
+}
+
+// A horizontal run of lit pixels covering columns `start..end` of `row`, in
+// font pixel units relative to the top left of the text.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PixelRun {
+	pub start: usize,
+	pub end: usize,
+	pub row: usize,
+}
+
+// Runs for a single line of text. Newlines are not handled here; split the
+// text with `wrap` first.
+pub fn text_runs(text: &str) -> Vec<PixelRun> {
+	let mut runs = Vec::new();
+	for (index, c) in text.chars().enumerate() {
+		let left = index * GLYPH_ADVANCE;
+		for (row, bits) in glyph(c).iter().enumerate() {
+			let mut start = None;
+			for column in 0..=GLYPH_WIDTH {
+				let lit = column < GLYPH_WIDTH
+					&& bits & (1 << (GLYPH_WIDTH - 1 - column)) != 0;
+				match (lit, start) {
+					(true, None) => start = Some(column),
+					(false, Some(run_start)) => {
+						runs.push(PixelRun {
+							start: left + run_start,
+							end: left + column,
+							row,
+						});
+						start = None;
+					}
+					_ => {}
+				}
+			}
+		}
+	}
+	runs
+}
+
+// Width of a single line of text in font pixel units.
+pub fn text_width(text: &str) -> usize {
+	let count = text.chars().count();
+	if count == 0 {
+		0
+	} else {
+		count * GLYPH_ADVANCE - 1
+	}
+}
+
+// Splits text into lines of at most `max_columns` characters, breaking at
+// spaces where possible and always at newlines. Words longer than a whole
+// line are split mid-word.
+pub fn wrap(text: &str, max_columns: usize) -> Vec<String> {
+	let max_columns = max_columns.max(1);
+	let mut lines = Vec::new();
+
+	for paragraph in text.split('\n') {
+		let mut line = String::new();
+		for word in paragraph.split_whitespace() {
+			let mut word: Vec<char> = word.chars().collect();
+			let line_length = line.chars().count();
+			if line_length > 0 && line_length + 1 + word.len() > max_columns {
+				lines.push(std::mem::take(&mut line));
+			}
+
+			while word.len() > max_columns {
+				let rest = word.split_off(max_columns);
+				lines.push(word.into_iter().collect());
+				word = rest;
+			}
+
+			if line.is_empty() == false {
+				line.push(' ');
+			}
+			line.extend(word);
+		}
+		lines.push(line);
+	}
+
+	lines
+}
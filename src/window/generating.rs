@@ -0,0 +1,37 @@
+use super::{Role, Window, TEXT_BOX_MARGIN, TEXT_BOX_PADDING};
+use crate::generation::Generation;
+use crate::geometry::Scalar;
+
+impl Window {
+	// How many frames of the cube have been made, in a text box with a bar
+	// across it that fills as they are.
+	pub(super) fn draw_generation(&mut self, generation: &Generation) {
+		let (generated, total) = generation.progress();
+		let lines = [
+			format!("Generating a cube, {} of {} frames.", generated, total),
+			String::new(),
+			String::from("Press Escape to cancel."),
+		];
+		let bounds = self.draw_text_box_lines(&lines, &[]);
+
+		// Over the blank line, as wide as the text box.
+		let (viewport_width, _) = self.ui_size();
+		let inset = TEXT_BOX_MARGIN + TEXT_BOX_PADDING;
+		let (left, right) = (inset, viewport_width - inset);
+		let (top, bottom) = (bounds[1].min_y, bounds[1].max_y);
+		let fraction = generated as Scalar / total.max(1) as Scalar;
+		let filled = left + (right - left) * fraction;
+		let color = self.palette.get(Role::UiText);
+		self.fill_ui_rect((left, top), (filled, bottom), color);
+		self.draw_ui_lines(
+			&[
+				(left, top),
+				(right, top),
+				(right, bottom),
+				(left, bottom),
+				(left, top),
+			],
+			color,
+		);
+	}
+}
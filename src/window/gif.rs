@@ -0,0 +1,162 @@
+// A minimal animated GIF encoder for frames that are already indexed into a
+// single global palette.
+
+use std::collections::HashMap;
+
+// Codes are never wider than this.
+const MAX_CODE_SIZE: u32 = 12;
+const MAX_SUB_BLOCK: usize = 255;
+
+pub struct GifFrame {
+	// Palette indices, row by row.
+	pub pixels: Vec<u8>,
+	// How long the frame is shown for, in hundredths of a second.
+	pub delay: u16,
+}
+
+// The whole file for `frames`, each `width` by `height` pixels. The palette
+// length must be a power of two between 2 and 256, and no pixel may index
+// past it. The animation loops forever.
+pub fn encode(
+	width: u16,
+	height: u16,
+	palette: &[[u8; 3]],
+	frames: &[GifFrame],
+) -> Vec<u8> {
+	let depth = palette_depth(palette.len());
+	let mut out = Vec::new();
+
+	out.extend_from_slice(b"GIF89a");
+	out.extend_from_slice(&width.to_le_bytes());
+	out.extend_from_slice(&height.to_le_bytes());
+	// Global color table present, with `depth` bits per entry.
+	let table_size = (depth - 1) as u8;
+	out.push(0x80 | (table_size << 4) | table_size);
+	out.push(0); // Background color index
+	out.push(0); // Square pixels
+	for color in palette {
+		out.extend_from_slice(color);
+	}
+
+	// Netscape application extension, repeating forever.
+	out.extend_from_slice(&[0x21, 0xff, 0x0b]);
+	out.extend_from_slice(b"NETSCAPE2.0");
+	out.extend_from_slice(&[0x03, 0x01, 0x00, 0x00, 0x00]);
+
+	for frame in frames {
+		// Graphic control extension carrying the delay.
+		out.extend_from_slice(&[0x21, 0xf9, 0x04, 0x00]);
+		out.extend_from_slice(&frame.delay.to_le_bytes());
+		out.extend_from_slice(&[0x00, 0x00]);
+
+		// Image descriptor covering the whole screen, no local table.
+		out.push(0x2c);
+		out.extend_from_slice(&[0, 0, 0, 0]);
+		out.extend_from_slice(&width.to_le_bytes());
+		out.extend_from_slice(&height.to_le_bytes());
+		out.push(0);
+
+		// The minimum code size can't be below two, even for two colors.
+		let min_code_size = depth.max(2);
+		out.push(min_code_size as u8);
+		let data = lzw_compress(&frame.pixels, min_code_size);
+		for block in data.chunks(MAX_SUB_BLOCK) {
+			out.push(block.len() as u8);
+			out.extend_from_slice(block);
+		}
+		out.push(0);
+	}
+
+	out.push(0x3b);
+	out
+}
+
+fn palette_depth(length: usize) -> u32 {
+	let mut depth = 1;
+	while (1 << depth) < length {
+		depth += 1;
+	}
+	depth
+}
+
+// Writes codes least significant bit first, as GIF expects.
+struct BitWriter {
+	bytes: Vec<u8>,
+	buffer: u32,
+	bit_count: u32,
+}
+
+impl BitWriter {
+	fn write(&mut self, code: u16, size: u32) {
+		self.buffer |= (code as u32) << self.bit_count;
+		self.bit_count += size;
+		while self.bit_count >= 8 {
+			self.bytes.push(self.buffer as u8);
+			self.buffer >>= 8;
+			self.bit_count -= 8;
+		}
+	}
+
+	fn finish(mut self) -> Vec<u8> {
+		if self.bit_count > 0 {
+			self.bytes.push(self.buffer as u8);
+		}
+		self.bytes
+	}
+}
+
+fn lzw_compress(pixels: &[u8], min_code_size: u32) -> Vec<u8> {
+	let clear_code: u16 = 1 << min_code_size;
+	let end_code = clear_code + 1;
+	let first_free = clear_code + 2;
+
+	let mut writer = BitWriter {
+		bytes: Vec::new(),
+		buffer: 0,
+		bit_count: 0,
+	};
+	// Maps a prefix code and the next index to the code for both together.
+	let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+	let mut next_code = first_free;
+	let mut code_size = min_code_size + 1;
+
+	writer.write(clear_code, code_size);
+
+	let mut pixels = pixels.iter();
+	let mut prefix = match pixels.next() {
+		Some(&first) => first as u16,
+		None => {
+			writer.write(end_code, code_size);
+			return writer.finish();
+		}
+	};
+
+	for &pixel in pixels {
+		if let Some(&code) = table.get(&(prefix, pixel)) {
+			prefix = code;
+			continue;
+		}
+
+		writer.write(prefix, code_size);
+		if next_code as u32 == 1 << MAX_CODE_SIZE {
+			// The table is full, so start over.
+			writer.write(clear_code, code_size);
+			table.clear();
+			next_code = first_free;
+			code_size = min_code_size + 1;
+		} else {
+			table.insert((prefix, pixel), next_code);
+			// Decoders widen codes once the table outgrows the current
+			// size, one code after the encoder adds the entry.
+			if next_code as u32 >= 1 << code_size {
+				code_size += 1;
+			}
+			next_code += 1;
+		}
+		prefix = pixel as u16;
+	}
+
+	writer.write(prefix, code_size);
+	writer.write(end_code, code_size);
+	writer.finish()
+}
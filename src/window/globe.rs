@@ -0,0 +1,153 @@
+use crate::geometry::{Matrix4x4, Scalar, Vector3};
+use crate::world::TILE_SIZE;
+
+// Drawing the cube blown up towards a sphere. Only where things are drawn
+// changes: to the world, and to everything in it, it is still a cube.
+
+// Inflation from which the tiles themselves curve, each side drawn in two
+// as if the tile were a two by two grid of smaller ones, rather than just
+// the lines longer than a tile.
+const CURVED_TILES_INFLATION: Scalar = 0.3;
+// Straight steps a ray is followed in to find where it meets an inflated
+// frame, and halvings of the step it does so in.
+const HIT_STEPS: usize = 16;
+const HIT_REFINEMENTS: usize = 24;
+
+#[derive(Copy, Clone)]
+pub struct Globe {
+	// From nothing for the cube to one for a sphere.
+	inflation: Scalar,
+	// Back from the view to the cube's own axes, once the view has been
+	// turned, as the cube's shape is.
+	to_cube: Matrix4x4,
+}
+
+impl Globe {
+	pub fn flat() -> Self {
+		Self {
+			inflation: 0.0,
+			to_cube: Matrix4x4::identity(),
+		}
+	}
+
+	// For points in view space, turned by `view_rotation` from the cube's.
+	pub fn new(inflation: Scalar, view_rotation: Matrix4x4) -> Self {
+		Self {
+			inflation: inflation.clamp(0.0, 1.0),
+			to_cube: view_rotation
+				.inverse()
+				.unwrap_or_else(Matrix4x4::identity),
+		}
+	}
+
+	pub fn is_flat(&self) -> bool {
+		self.inflation == 0.0
+	}
+
+	// Where a point is drawn, moved straight out from the middle of the cube
+	// towards the sphere through the middle of each face, as far as the
+	// inflation says. Every point on the surface goes onto the sphere
+	// together, and anything above or below the surface stays as far from
+	// it as on the cube.
+	pub fn inflate(&self, point: Vector3) -> Vector3 {
+		let length = point.len();
+		if self.is_flat() || length == 0.0 {
+			return point;
+		}
+		let cube = point * self.to_cube;
+		let radius = cube.x.abs().max(cube.y.abs()).max(cube.z.abs());
+		point.mix(point * (radius / length), self.inflation)
+	}
+
+	// How many pieces a line is drawn in, for it to bend with the surface
+	// rather than cut straight across it. Pieces are a tile long, or half
+	// one once tiles curve too.
+	pub fn pieces(&self, start: Vector3, end: Vector3) -> usize {
+		if self.is_flat() {
+			return 1;
+		}
+		let piece = TILE_SIZE / self.tile_pieces() as Scalar;
+		((end - start).len() / piece).ceil().max(1.0) as usize
+	}
+
+	// How many pieces each side of a tile is drawn in.
+	pub fn tile_pieces(&self) -> usize {
+		match self.inflation >= CURVED_TILES_INFLATION {
+			true => 2,
+			false => 1,
+		}
+	}
+
+	// The points of a polyline, with more in between wherever that takes
+	// for it to curve.
+	pub fn curve(&self, points: &[Vector3]) -> Vec<Vector3> {
+		let mut curved = Vec::with_capacity(points.len());
+		for (i, &point) in points.iter().enumerate() {
+			if i > 0 {
+				let start = points[i - 1];
+				let pieces = self.pieces(start, point);
+				for piece in 1..pieces {
+					let along = piece as Scalar / pieces as Scalar;
+					curved.push(start.mix(point, along));
+				}
+			}
+			curved.push(point);
+		}
+		curved
+	}
+
+	// Where a ray in a frame's own space, where the frame lies on z = 1,
+	// first meets the frame as drawn, as how far along the ray and the
+	// point on the frame's plane that is drawn there. What is found may be
+	// off the frame.
+	pub fn hit_frame(
+		&self,
+		origin: Vector3,
+		direction: Vector3,
+	) -> Option<(Scalar, Scalar, Scalar)> {
+		// Only a ray travelling towards -z can meet the frame's outside.
+		if direction.z >= 0.0 {
+			return None;
+		}
+		let flat = (1.0 - origin.z) / direction.z;
+		if self.is_flat() {
+			let hit = origin + direction * flat;
+			return Some((flat, hit.x, hit.y));
+		}
+
+		// The difference between how far a point is from the middle and
+		// how far the drawn surface is in that direction, which is
+		// positive outside it. Frames are reached through quarter turns of
+		// the cube, so the frame's space has the cube's shape too.
+		let t = self.inflation;
+		let outside = |distance: Scalar| {
+			let point = origin + direction * distance;
+			let length = point.len();
+			length - (length / point.z * (1.0 - t) + t)
+		};
+		// Past where the ray crosses the plane through the middle there is
+		// nothing of the frame left to meet.
+		let middle = -origin.z / direction.z;
+		let step = (middle - flat) / HIT_STEPS as Scalar;
+		let (mut near, mut far) = (flat, None);
+		for i in 1..HIT_STEPS {
+			let distance = flat + step * i as Scalar;
+			if outside(distance) < 0.0 {
+				far = Some(distance);
+				break;
+			}
+			near = distance;
+		}
+		let mut far = far?;
+		for _ in 0..HIT_REFINEMENTS {
+			let half = (near + far) / 2.0;
+			if outside(half) < 0.0 {
+				far = half;
+			} else {
+				near = half;
+			}
+		}
+		let hit = origin + direction * far;
+		Some((far, hit.x / hit.z, hit.y / hit.z))
+	}
+}
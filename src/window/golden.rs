@@ -0,0 +1,256 @@
+use super::backend::lines_hash;
+use super::backend::normalized_lines;
+use super::notifications::{Severity, NOTIFY_TICKS};
+use super::{BackendError, Role, Window};
+use crate::geometry::Scalar;
+use crate::world::{
+	Actions, EntityKind, EntitySpawn, Tile, World, WorldPosition, FRAME_WIDTH,
+	TILE_SIZE,
+};
+use crate::GameState;
+
+// Scenes drawn exactly the same every time, for telling whether a change
+// to the renderer changed what is drawn. Each is the generated cube from a
+// fresh window with the default settings, drawn once.
+
+// Ticks the entities scene runs for before it is drawn, for things to have
+// moved and the HUD to have something to show.
+const ENTITY_SCENE_TICKS: usize = 30;
+// Along the generated cube's first frame, the row the player can go all
+// the way right along to the edge.
+const OPEN_ROW_Y: Scalar = -0.5625;
+
+// Each scene's hash as checked in, one a line after its name.
+const HASHES_PATH: &str = "tests/render_golden.txt";
+// Each scene's lines as checked in, and where they are left to compare
+// when they differ.
+const CHECKED_IN_DIRECTORY: &str = "tests/render_golden";
+const DIFFERENCE_DIRECTORY: &str = "target/render_golden";
+// Differences reported for each scene that differs, at most.
+const REPORTED_DIFFERENCES: usize = 5;
+
+// Each scene's name and the lines it drew, as `normalized_lines` gives
+// them.
+pub(crate) fn golden_scenes(
+) -> Result<Vec<(&'static str, Vec<String>)>, BackendError> {
+	type Setup = fn(&mut Window, &mut GameState);
+	let scenes: [(&'static str, Setup); 7] = [
+		("flat_tiles", |_, _| {}),
+		("three_d_tiles", |window, _| {
+			window.settings.three_d_tiles = true
+		}),
+		("entities", entities_scene),
+		("straddling_edges", straddling_scene),
+		("three_d_solid_block", solid_block_scene),
+		("crossing_an_edge", crossing_scene),
+		("edited_after_drawing", edited_scene),
+	];
+	let mut drawn = Vec::new();
+	for &(name, setup) in scenes.iter() {
+		let mut window = Window::headless()?;
+		let mut game_state = GameState::from_world_as_is(World::new());
+		setup(&mut window, &mut game_state);
+		window.render(&mut game_state);
+		drawn.push((name, normalized_lines(window.drawn_lines())));
+	}
+	Ok(drawn)
+}
+
+// Draws each scene and compares a hash of its lines with the one checked
+// in for it, saying how many lines each drew. Where any differ, the lines
+// checked in and those drawn are left side by side under
+// `target/render_golden`, and the error says where they first differ.
+pub fn check_golden_scenes() -> Result<String, String> {
+	let scenes = golden_scenes()
+		.map_err(|error| format!("could not draw: {:?}", error))?;
+	let checked_in = std::fs::read_to_string(HASHES_PATH).map_err(|error| {
+		format!("could not read {}: {}", HASHES_PATH, error)
+	})?;
+
+	let (mut drawn, mut differences) = (String::new(), String::new());
+	let mut differed = 0;
+	for (name, lines) in &scenes {
+		let hash = format!("{:016x}", lines_hash(lines));
+		let expected = checked_in
+			.lines()
+			.filter_map(|line| line.split_once(' '))
+			.find(|&(scene, _)| scene == *name)
+			.map(|(_, hash)| hash.trim());
+		if expected == Some(hash.as_str()) {
+			drawn += &format!("{}: {} lines, {}\n", name, lines.len(), hash);
+			continue;
+		}
+
+		differed += 1;
+		let path = format!("{}/{}.txt", CHECKED_IN_DIRECTORY, name);
+		let checked_in: Vec<String> = std::fs::read_to_string(path)
+			.map(|lines| lines.lines().map(String::from).collect())
+			.unwrap_or_default();
+		let directory = DIFFERENCE_DIRECTORY;
+		write_lines(directory, &format!("{}.expected.txt", name), &checked_in)?;
+		write_lines(directory, &format!("{}.actual.txt", name), lines)?;
+		differences += &format!(
+			"{}: drew {}, not {}; both sets of lines are in {}\n",
+			name,
+			hash,
+			expected.unwrap_or("anything checked in"),
+			directory
+		);
+		differences += &report_differences(&checked_in, lines);
+	}
+	if differed > 0 {
+		return Err(format!(
+			"{}{} of {} scenes differ",
+			differences,
+			differed,
+			scenes.len()
+		));
+	}
+	Ok(drawn)
+}
+
+// Draws each scene and checks in its lines and their hash, for when a
+// change to what is drawn is meant. How many scenes there are.
+pub fn update_golden_scenes() -> Result<usize, String> {
+	let scenes = golden_scenes()
+		.map_err(|error| format!("could not draw: {:?}", error))?;
+	let mut hashes = String::new();
+	for (name, lines) in &scenes {
+		hashes += &format!("{} {:016x}\n", name, lines_hash(lines));
+		write_lines(CHECKED_IN_DIRECTORY, &format!("{}.txt", name), lines)?;
+	}
+	std::fs::write(HASHES_PATH, hashes).map_err(|error| {
+		format!("could not write {}: {}", HASHES_PATH, error)
+	})?;
+	Ok(scenes.len())
+}
+
+fn write_lines(
+	directory: &str,
+	file: &str,
+	lines: &[String],
+) -> Result<(), String> {
+	let path = format!("{}/{}", directory, file);
+	std::fs::create_dir_all(directory)
+		.and_then(|_| std::fs::write(&path, lines.join("\n") + "\n"))
+		.map_err(|error| format!("could not write {}: {}", path, error))
+}
+
+// The first few lines where `expected` and `actual` aren't the same, by
+// their number from one.
+fn report_differences(expected: &[String], actual: &[String]) -> String {
+	let none = String::from("(nothing)");
+	let length = expected.len().max(actual.len());
+	let differing = (0..length)
+		.filter(|&i| expected.get(i) != actual.get(i))
+		.take(REPORTED_DIFFERENCES);
+	let mut report = String::new();
+	for i in differing {
+		report += &format!("  line {}:\n", i + 1);
+		report += &format!("    was {}\n", expected.get(i).unwrap_or(&none));
+		report += &format!("    now {}\n", actual.get(i).unwrap_or(&none));
+	}
+	report
+}
+
+// Walkers and coins around the player, a tile under the pointer, a tile
+// just edited, and the HUD with a notification over it all.
+fn entities_scene(window: &mut Window, game_state: &mut GameState) {
+	let world = &mut game_state.world;
+	let player = world.focus_entity.and_then(|id| world.get_entity(id));
+	let at = player.unwrap().position;
+	let kinds = [EntityKind::Walker, EntityKind::Coin, EntityKind::Coin];
+	for (i, &kind) in kinds.iter().enumerate() {
+		let offset = (i as Scalar + 2.0) * TILE_SIZE;
+		let position = WorldPosition {
+			x: (at.x + offset).min(1.0 - TILE_SIZE),
+			..at
+		};
+		// Somewhere solid is no place for one, which just leaves it out.
+		world.spawn_entity(&EntitySpawn::new(kind, position)).ok();
+	}
+	for _ in 0..ENTITY_SCENE_TICKS {
+		game_state.tick(&Actions::none());
+		window.hud.update(&game_state.world);
+	}
+
+	let (width, height) = (
+		window.backend.viewport_width() as Scalar,
+		window.backend.viewport_height() as Scalar,
+	);
+	window.input_state.mouse_position = Some((width * 0.6, height * 0.4));
+	let at = game_state.world.focus_entity.and_then(|id| {
+		let position = game_state.world.get_entity(id)?.position;
+		let x = ((position.x + 1.0) / TILE_SIZE) as usize;
+		let y = ((position.y + 1.0) / TILE_SIZE) as usize;
+		Some((position.frame_id, (x + 1).min(FRAME_WIDTH - 1), y))
+	});
+	if let Some(at) = at {
+		window.edited_tile = Some((at, Role::Rejected, f64::INFINITY));
+	}
+	window
+		.notifications
+		.notify("Autosaved", NOTIFY_TICKS, Severity::Info);
+}
+
+// The player hanging over the right edge of their frame and a walker over
+// the left, each drawn partly on the face beside it.
+fn straddling_scene(_: &mut Window, game_state: &mut GameState) {
+	let world = &mut game_state.world;
+	let id = world.focus_entity.unwrap();
+	let player = world.get_entity_mut(id).unwrap();
+	player.position.x = 1.0 - TILE_SIZE * 0.1;
+	player.position.y = OPEN_ROW_Y;
+	let position = WorldPosition {
+		x: -1.0 + TILE_SIZE * 0.1,
+		..player.position
+	};
+	let walker = EntitySpawn::new(EntityKind::Walker, position);
+	world.spawn_entity(&walker).unwrap();
+}
+
+// Solid tiles packed together in 3D, with the sides between them hidden.
+fn solid_block_scene(window: &mut Window, game_state: &mut GameState) {
+	window.settings.three_d_tiles = true;
+	let world = &mut game_state.world;
+	let frame_id = world.get_entity(world.focus_entity.unwrap()).unwrap();
+	let frame_id = frame_id.position.frame_id;
+	for y in 10..15 {
+		for x in 2..8 {
+			world.set_tile(frame_id, x, y, Tile::Solid).unwrap();
+		}
+	}
+}
+
+// The player drawn halfway through the tick they went over the right edge
+// of their frame in, between where they were and where they are.
+fn crossing_scene(window: &mut Window, game_state: &mut GameState) {
+	let id = game_state.world.focus_entity.unwrap();
+	let player = game_state.world.get_entity_mut(id).unwrap();
+	player.position.x = 1.0 - TILE_SIZE;
+	player.position.y = OPEN_ROW_Y;
+	player.velocity.x = TILE_SIZE * 0.4;
+	let start = player.position.frame_id;
+	let frame_of = |game_state: &GameState| {
+		game_state.world.get_entity(id).unwrap().position.frame_id
+	};
+	while frame_of(game_state) == start {
+		window.interpolation.record(&game_state.world);
+		game_state.tick(&Actions::none());
+		window.interpolation.update(&game_state.world);
+	}
+	window.tick_accumulator = window.tick_rate.step_duration() / 2.0;
+}
+
+// Tiles changed after the frame they are on was drawn once, for the
+// geometry kept from that drawing to be made again.
+fn edited_scene(window: &mut Window, game_state: &mut GameState) {
+	window.render(game_state);
+	let world = &mut game_state.world;
+	let frame_id = world.get_entity(world.focus_entity.unwrap()).unwrap();
+	let frame_id = frame_id.position.frame_id;
+	for x in 6..FRAME_WIDTH as isize {
+		world.set_tile(frame_id, x, 3, Tile::Solid).unwrap();
+	}
+	world.set_tile(frame_id, 12, 6, Tile::Empty).unwrap();
+}
@@ -0,0 +1,123 @@
+use std::rc::Rc;
+
+use super::{is_rect_visible, rect_facing, CameraProjector, Role, Window};
+use crate::geometry::{vec3, Matrix4x4, Scalar, Vector3};
+use crate::world::FRAME_WIDTH;
+
+// Every this many tiles the grid line is drawn brighter.
+const GRID_MAJOR_INTERVAL: usize = 4;
+// Height of ruler numbers in frame units, and their distance outside the
+// face's top and left edges.
+const RULER_TEXT_HEIGHT: Scalar = 0.05;
+const RULER_OFFSET: Scalar = 0.08;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum GridMode {
+	Off,
+	// Only on the focus frame.
+	Focus,
+	// On every visible frame.
+	All,
+}
+
+impl GridMode {
+	pub fn name(&self) -> &'static str {
+		match self {
+			GridMode::Off => "off",
+			GridMode::Focus => "focus",
+			GridMode::All => "all",
+		}
+	}
+
+	pub fn from_name(name: &str) -> Option<Self> {
+		[GridMode::Off, GridMode::Focus, GridMode::All]
+			.iter()
+			.find(|mode| mode.name() == name)
+			.copied()
+	}
+}
+
+// Segments along every tile boundary of a frame, in frame coordinates. Built
+// once and transformed onto each face as it is drawn.
+pub struct GridLines {
+	minor: Vec<(Vector3, Vector3)>,
+	major: Vec<(Vector3, Vector3)>,
+}
+
+impl GridLines {
+	pub fn new() -> Self {
+		let mut minor = Vec::new();
+		let mut major = Vec::new();
+
+		for i in 0..=FRAME_WIDTH {
+			let t = i as Scalar / FRAME_WIDTH as Scalar * 2.0 - 1.0;
+			let lines = if i % GRID_MAJOR_INTERVAL == 0 {
+				&mut major
+			} else {
+				&mut minor
+			};
+			lines.push((vec3(t, -1.0, 1.0), vec3(t, 1.0, 1.0)));
+			lines.push((vec3(-1.0, t, 1.0), vec3(1.0, t, 1.0)));
+		}
+
+		Self { minor, major }
+	}
+}
+
+impl Window {
+	// The grid and tile coordinate rulers along the top and left edges of a
+	// face.
+	pub(super) fn draw_grid(
+		&mut self,
+		projector: &CameraProjector,
+		surface_transform: Matrix4x4,
+		view_rotation: Matrix4x4,
+	) {
+		let m = surface_transform;
+		let r = view_rotation;
+		let p1 = vec3(-1.0, -1.0, 1.0) * m * r;
+		let p2 = vec3(1.0, -1.0, 1.0) * m * r;
+		let p3 = vec3(1.0, 1.0, 1.0) * m * r;
+		let p4 = vec3(-1.0, 1.0, 1.0) * m * r;
+		let (normal, center) = rect_facing(p1, p2, p3, p4);
+		let camera = projector.camera_position();
+		if is_rect_visible(normal, center, camera) == false {
+			return;
+		}
+
+		let background = self.palette.get(Role::Background);
+		let border = self.palette.get(Role::FrameBorder);
+		let minor_color = background.mix(border, 0.35);
+		let major_color = background.mix(border, 0.7);
+
+		let lines = Rc::clone(&self.grid_lines);
+		let (minor, major) = (&lines.minor, &lines.major);
+		self.draw_segments(projector, minor, m, r, minor_color, false);
+		self.draw_segments(projector, major, m, r, major_color, false);
+
+		let ruler_color = background.mix(self.palette.get(Role::UiText), 0.7);
+		let tile = 2.0 / FRAME_WIDTH as Scalar;
+		for i in 0..FRAME_WIDTH {
+			let center = -1.0 + (i as Scalar + 0.5) * tile;
+			let label = i.to_string();
+			self.draw_surface_text(
+				projector,
+				&label,
+				(center, -1.0 - RULER_OFFSET),
+				RULER_TEXT_HEIGHT,
+				m,
+				r,
+				ruler_color,
+			);
+			self.draw_surface_text(
+				projector,
+				&label,
+				(-1.0 - RULER_OFFSET, center),
+				RULER_TEXT_HEIGHT,
+				m,
+				r,
+				ruler_color,
+			);
+		}
+	}
+}
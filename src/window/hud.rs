@@ -0,0 +1,365 @@
+use super::{font, Color, Role, Window, TEXT_SCALE};
+use crate::geometry::{Scalar, PI};
+use crate::world::{
+	compose_rotation, Direction, EntityKind, FrameActivity, FrameLink,
+	StatusKind, World, WorldEvent, MAX_HEALTH,
+};
+
+// Fraction of the remaining turn the compass needle makes each tick.
+const COMPASS_SMOOTHING: Scalar = 0.15;
+// Fraction of the remaining fade the compass makes each tick.
+const COMPASS_FADE_RATE: Scalar = 0.1;
+const COMPASS_RADIUS: Scalar = 18.0;
+// Distance of the compass center from the top and right viewport edges.
+const COMPASS_INSET: Scalar = 36.0;
+const COMPASS_SEGMENTS: usize = 24;
+// Distance of the frame rate overlay from the top left corner.
+const FRAME_RATE_INSET: Scalar = 8.0;
+// The focus entity's health, in the bottom right corner.
+const HEALTH_BAR_WIDTH: Scalar = 80.0;
+const HEALTH_BAR_HEIGHT: Scalar = 6.0;
+const HEALTH_BAR_INSET: Scalar = 8.0;
+// Its stamina, in a thinner bar below the health bar.
+const STAMINA_BAR_HEIGHT: Scalar = 4.0;
+const STAMINA_BAR_GAP: Scalar = 3.0;
+// How long the stamina bar flashes for after a dash is refused, swapping
+// its outline's color every `STAMINA_FLASH_PERIOD` ticks.
+const STAMINA_FLASH_TICKS: u32 = 24;
+const STAMINA_FLASH_PERIOD: u32 = 4;
+// Status effect icons sit in a row above the health bar.
+const STATUS_ICON_SIZE: Scalar = 8.0;
+const STATUS_ICON_GAP: Scalar = 4.0;
+
+// Screen-space overlays drawn on top of the world.
+pub struct Hud {
+	pub visible: bool,
+	compass: Compass,
+	// Ticks left flashing the stamina bar.
+	stamina_flash: u32,
+}
+
+// Points towards the nearest coin along the surface.
+struct Compass {
+	// Needle angle in the focus frame's coordinates, measured from +x
+	// towards +y. `None` until there has been something to point at.
+	angle: Option<Scalar>,
+	opacity: Scalar,
+}
+
+impl Hud {
+	pub fn new() -> Self {
+		Self {
+			visible: true,
+			compass: Compass {
+				angle: None,
+				opacity: 0.0,
+			},
+			stamina_flash: 0,
+		}
+	}
+
+	pub fn toggle(&mut self) {
+		self.visible = !self.visible;
+	}
+
+	// Called once per world tick so that smoothing is independent of the
+	// frame rate.
+	pub fn update(&mut self, world: &World) {
+		self.stamina_flash = self.stamina_flash.saturating_sub(1);
+		let refused = world.events().iter().any(|event| match *event {
+			WorldEvent::DashRefused { entity } => {
+				Some(entity) == world.focus_entity
+			}
+			_ => false,
+		});
+		if refused {
+			self.stamina_flash = STAMINA_FLASH_TICKS;
+		}
+
+		let compass = &mut self.compass;
+
+		// The needle is kept in the focus frame's coordinates, which rotate
+		// relative to the old ones when the focus entity crosses a rotated
+		// link. Turn it by the same amount so it doesn't spin to catch up.
+		for event in world.events() {
+			if let WorldEvent::EntityCrossedFrame {
+				entity,
+				to,
+				exit_edge,
+				entry_edge,
+				..
+			} = *event
+			{
+				if Some(entity) != world.focus_entity {
+					continue;
+				}
+				let link = FrameLink {
+					frame: to,
+					entry_edge,
+				};
+				let rotation = compose_rotation(exit_edge, link).negative();
+				let turns = rotation.quarter_turns() as Scalar;
+				if let Some(angle) = compass.angle.as_mut() {
+					*angle -= turns * (PI / 2.0);
+				}
+			}
+		}
+
+		let (target_angle, target_opacity) = match compass_target(world) {
+			Some((angle, true)) => (Some(angle), 1.0),
+			Some((angle, false)) => (Some(angle), 0.0),
+			None => (None, 0.0),
+		};
+
+		if let Some(target_angle) = target_angle {
+			compass.angle = Some(match compass.angle {
+				Some(angle) => {
+					angle + wrap_angle(target_angle - angle) * COMPASS_SMOOTHING
+				}
+				None => target_angle,
+			});
+		}
+		compass.opacity +=
+			(target_opacity - compass.opacity) * COMPASS_FADE_RATE;
+	}
+}
+
+// The direction to the nearest coin, and whether it is on the focus frame or
+// one linked directly to it. Farther away, the in-frame direction depends on
+// which way around the cube is taken, so it isn't shown.
+fn compass_target(world: &World) -> Option<(Scalar, bool)> {
+	let focus = world.get_entity(world.focus_entity?)?;
+	let from = focus.position;
+	let target_id = world.nearest_entity_of_kind(from, EntityKind::Coin)?;
+	let to = world.get_entity(target_id)?.position;
+
+	let direction = world.surface_direction(from, to);
+	if direction.len() == 0.0 {
+		return None;
+	}
+
+	let frame = world.get_frame(from.frame_id)?;
+	let adjacent = to.frame_id == from.frame_id
+		|| Direction::iter().any(|&edge| {
+			let link = frame.borders.at_direction(edge);
+			link.is_some_and(|link| link.frame == to.frame_id)
+		});
+
+	Some((direction.y.atan2(direction.x), adjacent))
+}
+
+// The equivalent angle in (-PI, PI].
+fn wrap_angle(angle: Scalar) -> Scalar {
+	let wrapped = (angle + PI).rem_euclid(PI * 2.0) - PI;
+	if wrapped == -PI {
+		PI
+	} else {
+		wrapped
+	}
+}
+
+impl Window {
+	pub(super) fn draw_hud(&mut self, world: &World) {
+		if self.hud.visible == false {
+			return;
+		}
+
+		self.draw_compass();
+		self.draw_health(world);
+	}
+
+	fn draw_health(&mut self, world: &World) {
+		let focus = match world.focus_entity.and_then(|id| world.get_entity(id))
+		{
+			Some(focus) => focus,
+			None => return,
+		};
+
+		let (viewport_width, viewport_height) = self.ui_size();
+		let right = viewport_width - HEALTH_BAR_INSET;
+		let left = right - HEALTH_BAR_WIDTH;
+		let stamina_bottom = viewport_height - HEALTH_BAR_INSET;
+		let stamina_top = stamina_bottom - STAMINA_BAR_HEIGHT;
+		let bottom = stamina_top - STAMINA_BAR_GAP;
+		let top = bottom - HEALTH_BAR_HEIGHT;
+
+		let outline = self.palette.get(Role::UiText);
+		let health = focus.health / MAX_HEALTH;
+		self.draw_bar(
+			(left, top),
+			(right, bottom),
+			health,
+			Role::Health,
+			outline,
+		);
+		let max_stamina = world.physics.max_stamina;
+		let stamina = if max_stamina > 0.0 {
+			focus.stamina / max_stamina
+		} else {
+			0.0
+		};
+		let flash = self.hud.stamina_flash;
+		let outline = if flash > 0 && (flash / STAMINA_FLASH_PERIOD).is_multiple_of(2) {
+			self.palette.get(Role::UiWarning)
+		} else {
+			outline
+		};
+		self.draw_bar(
+			(left, stamina_top),
+			(right, stamina_bottom),
+			stamina,
+			Role::Stamina,
+			outline,
+		);
+
+		let icon_top = top - STATUS_ICON_GAP - STATUS_ICON_SIZE;
+		for (index, effect) in focus.effects.iter().enumerate() {
+			let icon_left =
+				left + index as Scalar * (STATUS_ICON_SIZE + STATUS_ICON_GAP);
+			self.draw_status_icon(effect.kind, icon_left, icon_top);
+		}
+	}
+
+	// Filled from the left by `fraction` of its width in `role`.
+	fn draw_bar(
+		&mut self,
+		(left, top): (Scalar, Scalar),
+		(right, bottom): (Scalar, Scalar),
+		fraction: Scalar,
+		role: Role,
+		outline: Color,
+	) {
+		let color = self.palette.get(role);
+		let filled = left + (right - left) * fraction.clamp(0.0, 1.0);
+		self.fill_ui_rect((left, top), (filled, bottom), color);
+		self.draw_ui_lines(
+			&[
+				(left, top),
+				(right, top),
+				(right, bottom),
+				(left, bottom),
+				(left, top),
+			],
+			outline,
+		);
+	}
+
+	// A small symbol for each kind of status effect, within a square of
+	// `STATUS_ICON_SIZE` from `left`, `top`.
+	fn draw_status_icon(
+		&mut self,
+		kind: StatusKind,
+		left: Scalar,
+		top: Scalar,
+	) {
+		let s = STATUS_ICON_SIZE;
+		let (right, bottom, middle) = (left + s, top + s, left + s / 2.0);
+		let color = self.palette.get(Role::UiText);
+		match kind {
+			StatusKind::SpeedBoost => self.draw_ui_lines(
+				&[(left, bottom), (middle, top), (right, bottom)],
+				color,
+			),
+			StatusKind::Slow => self.draw_ui_lines(
+				&[(left, top), (middle, bottom), (right, top)],
+				color,
+			),
+			StatusKind::Invulnerable => self.draw_ui_lines(
+				&[
+					(left, top),
+					(right, top),
+					(right, bottom),
+					(left, bottom),
+					(left, top),
+				],
+				color,
+			),
+			StatusKind::Poison => {
+				self.draw_ui_lines(&[(left, top), (right, bottom)], color);
+				self.draw_ui_lines(&[(right, top), (left, bottom)], color);
+			}
+			StatusKind::Energized => self.draw_ui_lines(
+				&[
+					(middle + s / 4.0, top),
+					(left, top + s / 2.0),
+					(right, top + s / 2.0),
+					(middle - s / 4.0, bottom),
+				],
+				color,
+			),
+		}
+	}
+
+	fn draw_compass(&mut self) {
+		let compass = &self.hud.compass;
+		let angle = match compass.angle {
+			Some(angle) if compass.opacity > 0.01 => angle,
+			_ => return,
+		};
+
+		// There's no alpha blending, so fading is done by mixing towards the
+		// background color.
+		let background = self.palette.get(Role::Background);
+		let color =
+			background.mix(self.palette.get(Role::UiText), compass.opacity);
+
+		let center_x = self.ui_size().0 - COMPASS_INSET;
+		let center_y = COMPASS_INSET;
+
+		let circle: Vec<(Scalar, Scalar)> = (0..=COMPASS_SEGMENTS)
+			.map(|i| {
+				let t = i as Scalar / COMPASS_SEGMENTS as Scalar * PI * 2.0;
+				(
+					center_x + t.cos() * COMPASS_RADIUS,
+					center_y + t.sin() * COMPASS_RADIUS,
+				)
+			})
+			.collect();
+		self.draw_ui_lines(circle.as_slice(), color);
+
+		// Frame coordinates and screen coordinates both have +y pointing
+		// down, and the focus frame is drawn facing the camera.
+		let needle = COMPASS_RADIUS - 4.0;
+		let tip = (
+			center_x + angle.cos() * needle,
+			center_y + angle.sin() * needle,
+		);
+		self.draw_ui_lines(&[(center_x, center_y), tip], color);
+	}
+
+	// Frames per second, how they are paced and the world's tick rate, in
+	// the top left corner, with how many frames are being simulated below.
+	pub(super) fn draw_frame_rate(&mut self, world: &World) {
+		let text = format!(
+			"{:.0} fps ({}), {:.0} Hz",
+			self.frame_rate,
+			self.frame_pacing_name(),
+			self.tick_rate.rate()
+		);
+		let color = self.palette.get(Role::UiText);
+		let inset = FRAME_RATE_INSET;
+		self.draw_text(&text, inset, inset, TEXT_SCALE, color);
+
+		let frame_ids = world.frame_ids();
+		let active = frame_ids
+			.iter()
+			.filter(|&&id| world.frame_activity(id) == FrameActivity::Active)
+			.count();
+		let text = format!(
+			"{} frames active, {} dormant",
+			active,
+			frame_ids.len() - active
+		);
+		let line_height = (font::LINE_ADVANCE * TEXT_SCALE) as Scalar;
+		self.draw_text(&text, inset, inset + line_height, TEXT_SCALE, color);
+
+		// The camera is then held still or circling rather than following
+		// anyone.
+		let focus = world.focus_entity.and_then(|id| world.get_entity(id));
+		if focus.is_none() {
+			let y = inset + line_height * 2.0;
+			let color = self.palette.get(Role::UiWarning);
+			self.draw_text("no focus", inset, y, TEXT_SCALE, color);
+		}
+	}
+}
@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+
+use crate::geometry::Scalar;
+use crate::world::{
+	transform_position_across, EntityId, FrameLink, World, WorldEvent,
+	WorldPosition,
+};
+
+// Further than this in frame units between two ticks is a jump, such as a
+// respawn, and is drawn as one.
+const MAX_INTERPOLATED_DISTANCE: Scalar = 0.5;
+
+// Where each entity was before the last tick, so that frames drawn between
+// ticks can show it part of the way to where it is now. Entities that
+// crossed a frame edge in that tick have where they were carried across
+// the edge with them, so that both ends are in the same frame's
+// coordinates.
+pub struct Interpolation {
+	previous: HashMap<EntityId, WorldPosition>,
+	// The world's tick count once `previous` was brought up to date, which
+	// it only applies to.
+	tick: Option<u64>,
+}
+
+impl Interpolation {
+	pub fn new() -> Self {
+		Self {
+			previous: HashMap::new(),
+			tick: None,
+		}
+	}
+
+	// Called just before each tick.
+	pub fn record(&mut self, world: &World) {
+		self.previous.clear();
+		for id in world.entity_ids() {
+			let position = world.get_entity(id).unwrap().position;
+			self.previous.insert(id, position);
+		}
+		self.tick = None;
+	}
+
+	// Called just after each tick. Anything that changed frames other than
+	// by walking over an edge, or was put back somewhere, isn't
+	// interpolated.
+	pub fn update(&mut self, world: &World) {
+		for event in world.events() {
+			match *event {
+				WorldEvent::EntityCrossedFrame {
+					entity,
+					to,
+					exit_edge,
+					entry_edge,
+					..
+				} => {
+					let previous = match self.previous.get_mut(&entity) {
+						Some(previous) => previous,
+						None => continue,
+					};
+					let link = FrameLink {
+						frame: to,
+						entry_edge,
+					};
+					let (x, y) = transform_position_across(
+						exit_edge, link, previous.x, previous.y,
+					);
+					*previous = WorldPosition { frame_id: to, x, y };
+				}
+				WorldEvent::EntityOutOfBounds { entity, .. }
+				| WorldEvent::EntityDied { entity } => {
+					self.previous.remove(&entity);
+				}
+				_ => {}
+			}
+		}
+		self.previous.retain(|&id, previous| {
+			let position = match world.get_entity(id) {
+				Some(entity) => entity.position,
+				None => return false,
+			};
+			let (dx, dy) = (position.x - previous.x, position.y - previous.y);
+			previous.frame_id == position.frame_id
+				&& dx.hypot(dy) <= MAX_INTERPOLATED_DISTANCE
+		});
+		self.tick = Some(world.tick_count);
+	}
+
+	// Where the entity is `fraction` of the way from its last tick to the
+	// next, in the frame it is on now.
+	pub fn position(
+		&self,
+		world: &World,
+		id: EntityId,
+		fraction: Scalar,
+	) -> Option<WorldPosition> {
+		let position = world.get_entity(id)?.position;
+		let previous = match self.previous.get(&id) {
+			Some(previous) if self.tick == Some(world.tick_count) => previous,
+			_ => return Some(position),
+		};
+		let blend = |from: Scalar, to: Scalar| from + (to - from) * fraction;
+		Some(WorldPosition {
+			frame_id: position.frame_id,
+			x: blend(previous.x, position.x),
+			y: blend(previous.y, position.y),
+		})
+	}
+}
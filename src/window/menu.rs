@@ -0,0 +1,151 @@
+use super::clip::ClipRect;
+use super::{
+	font, GamepadAxis, GamepadButton, Keycode, MouseButton, Window, WindowEvent,
+};
+use crate::geometry::Scalar;
+
+// How far the stick has to be pushed to move the selection, and how far back
+// it has to come before it moves it again.
+const STICK_PRESS: f32 = 0.5;
+const STICK_RELEASE: f32 = 0.25;
+
+// What a menu did with an event.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MenuInput {
+	// Left for the rest of the window to handle.
+	Ignored,
+	Used,
+	// The item at this index was chosen.
+	Activated(usize),
+}
+
+// A list of items chosen between with the keyboard, the mouse or a gamepad.
+// There is one selection whichever is used, so the one used last decides
+// what is highlighted: pointing at an item selects it, and keys or buttons
+// move on from wherever that left it.
+#[derive(Clone, Debug)]
+pub struct MenuWidget {
+	pub items: Vec<String>,
+	// Items that can't be chosen, true at their index, which are grayed out
+	// but can still be selected. Shorter than `items` if the rest can be.
+	pub disabled: Vec<bool>,
+	pub selected: usize,
+	// Where each item was last drawn, in viewport pixels.
+	rows: Vec<ClipRect>,
+	// The way the stick is held, -1 up or 1 down, once pushed past
+	// `STICK_PRESS`.
+	stick: isize,
+}
+
+impl MenuWidget {
+	pub fn new(items: Vec<String>) -> Self {
+		Self {
+			items,
+			disabled: Vec::new(),
+			selected: 0,
+			rows: Vec::new(),
+			stick: 0,
+		}
+	}
+
+	// Moves the selection by `offset` items, wrapping around at either end.
+	pub fn move_selection(&mut self, offset: isize) {
+		let count = self.items.len() as isize;
+		if count == 0 {
+			return;
+		}
+		self.selected =
+			(self.selected as isize + offset).rem_euclid(count) as usize;
+	}
+
+	// `pointer` is where the mouse is, in the same coordinates the widget
+	// was drawn in, since clicks don't carry it.
+	pub fn event(
+		&mut self,
+		event: &WindowEvent,
+		pointer: Option<(Scalar, Scalar)>,
+	) -> MenuInput {
+		use WindowEvent::*;
+		match *event {
+			KeyDown(Keycode::W) | GamepadDown(GamepadButton::Up) => {
+				self.move_selection(-1)
+			}
+			KeyDown(Keycode::S) | GamepadDown(GamepadButton::Down) => {
+				self.move_selection(1)
+			}
+			KeyDown(Keycode::Return) | GamepadDown(GamepadButton::Confirm) => {
+				if self.is_disabled(self.selected) == false {
+					return MenuInput::Activated(self.selected);
+				}
+			}
+			GamepadAxisMotion(GamepadAxis::LeftY, value) => {
+				let held = if value <= -STICK_PRESS {
+					-1
+				} else if value >= STICK_PRESS {
+					1
+				} else if value.abs() < STICK_RELEASE {
+					0
+				} else {
+					self.stick
+				};
+				if held != self.stick && held != 0 {
+					self.move_selection(held);
+				}
+				self.stick = held;
+			}
+			// The window still keeps track of the pointer itself.
+			MouseMove(..) => {
+				let index = pointer.and_then(|point| self.item_at(point));
+				if let Some(index) = index {
+					self.selected = index;
+				}
+				return MenuInput::Ignored;
+			}
+			// Clicks never reach the world behind a menu.
+			MouseDown(MouseButton::Left) => {
+				let index = pointer.and_then(|point| self.item_at(point));
+				if let Some(index) = index {
+					self.selected = index;
+					if self.is_disabled(index) == false {
+						return MenuInput::Activated(index);
+					}
+				}
+			}
+			MouseDown(_) => {}
+			_ => return MenuInput::Ignored,
+		}
+		MenuInput::Used
+	}
+
+	pub fn is_disabled(&self, index: usize) -> bool {
+		self.disabled.get(index).copied().unwrap_or(false)
+	}
+
+	fn item_at(&self, point: (Scalar, Scalar)) -> Option<usize> {
+		self.rows.iter().position(|row| row.contains(point))
+	}
+}
+
+impl Window {
+	// The header wrapped above the items, one to a line, in a text box. The
+	// selected item is marked, disabled ones are grayed out, and where each
+	// was drawn is kept for pointing at them.
+	pub(super) fn draw_menu_widget(
+		&mut self,
+		header: &str,
+		widget: &mut MenuWidget,
+	) {
+		let mut lines = font::wrap(header, self.text_box_columns());
+		lines.push(String::new());
+		let mut muted = vec![false; lines.len()];
+		for (index, item) in widget.items.iter().enumerate() {
+			let marker = if index == widget.selected { '>' } else { '-' };
+			lines.push(format!("{} {}", marker, item));
+			muted.push(widget.is_disabled(index));
+		}
+
+		let bounds = self.draw_text_box_lines(&lines, &muted);
+		let first_item = bounds.len() - widget.items.len();
+		widget.rows = bounds[first_item..].to_vec();
+	}
+}
@@ -0,0 +1,182 @@
+use super::clip::backend_point;
+use super::thumbnails::tile_role;
+use super::{Color, Role, Window};
+use crate::geometry::Scalar;
+use crate::world::{EntityKind, NetFace, World, FRAME_WIDTH, TILE_SIZE};
+
+const NET_WINDOW_WIDTH: u32 = 640;
+const NET_WINDOW_HEIGHT: u32 = 480;
+// Gap in pixels between the net and the window edges.
+const NET_MARGIN: Scalar = 16.0;
+// Half the size of entity markers, in frame units.
+const NET_MARKER_SIZE: Scalar = 0.04;
+
+impl Window {
+	// Opens or closes the debug window showing the whole world unfolded
+	// flat.
+	pub(super) fn toggle_net_window(&mut self) {
+		if self.backend.debug_window_size().is_some() {
+			self.backend.close_debug_window();
+			return;
+		}
+		let result = self.backend.open_debug_window(
+			"cube net",
+			NET_WINDOW_WIDTH,
+			NET_WINDOW_HEIGHT,
+		);
+		if let Err(message) = result {
+			self.console.print(format!("no net view: {}", message));
+		}
+	}
+
+	// Redraws the net window, if it is open.
+	pub(super) fn draw_net_window(&mut self, world: &World) {
+		let (width, height) = match self.backend.debug_window_size() {
+			Some(size) => size,
+			None => return,
+		};
+		// Rooted at the lowest frame id rather than the focus frame, so the
+		// layout doesn't jump around as the player moves.
+		let root = match world.frame_ids().first() {
+			Some(&root) => root,
+			None => return,
+		};
+		let faces = world.net_layout(root);
+		let paths = self.net_paths(world, &faces);
+
+		// Fit the bounding box of all the cells into the window.
+		let min_x = faces.iter().map(|face| face.cell.0).min().unwrap_or(0);
+		let max_x = faces.iter().map(|face| face.cell.0).max().unwrap_or(0);
+		let min_y = faces.iter().map(|face| face.cell.1).min().unwrap_or(0);
+		let max_y = faces.iter().map(|face| face.cell.1).max().unwrap_or(0);
+		let left = min_x as Scalar * 2.0 - 1.0;
+		let top = min_y as Scalar * 2.0 - 1.0;
+		let net_width = (max_x - min_x + 1) as Scalar * 2.0;
+		let net_height = (max_y - min_y + 1) as Scalar * 2.0;
+		let scale = ((width as Scalar - NET_MARGIN * 2.0) / net_width)
+			.min((height as Scalar - NET_MARGIN * 2.0) / net_height);
+
+		let paths: Vec<_> = paths
+			.into_iter()
+			.map(|(color, points)| {
+				let points = points
+					.into_iter()
+					.map(|(x, y)| {
+						backend_point((
+							NET_MARGIN + (x - left) * scale,
+							NET_MARGIN + (y - top) * scale,
+						))
+					})
+					.collect();
+				(color, points)
+			})
+			.collect();
+
+		let background = self.palette.get(Role::Background);
+		self.backend.draw_debug_window(background, paths.as_slice());
+	}
+
+	// Frame borders, background patterns, tiles and entities as polylines in
+	// net coordinates.
+	fn net_paths(
+		&self,
+		world: &World,
+		faces: &[NetFace],
+	) -> Vec<(Color, Vec<(Scalar, Scalar)>)> {
+		let mut paths = Vec::new();
+		let focus_frame = world
+			.focus_entity
+			.and_then(|id| world.get_entity(id))
+			.map(|entity| entity.position.frame_id);
+
+		let square = |face: &NetFace, x: Scalar, y: Scalar, half: Scalar| {
+			vec![
+				face.to_net(x - half, y - half),
+				face.to_net(x + half, y - half),
+				face.to_net(x + half, y + half),
+				face.to_net(x - half, y + half),
+				face.to_net(x - half, y - half),
+			]
+		};
+
+		for face in faces {
+			let frame = match world.get_frame(face.frame_id) {
+				Some(frame) => frame,
+				None => continue,
+			};
+
+			let color = self.background_color(frame.background);
+			let segments =
+				self.background_patterns.segments(frame.background.pattern);
+			for &(start, end) in segments {
+				let points = vec![
+					face.to_net(start.x, start.y),
+					face.to_net(end.x, end.y),
+				];
+				paths.push((color, points));
+			}
+
+			for tile_y in 0..FRAME_WIDTH {
+				for tile_x in 0..FRAME_WIDTH {
+					let (tx, ty) = (tile_x as isize, tile_y as isize);
+					let tile = *frame.tile(tx, ty);
+					let x = -1.0 + (tile_x as Scalar + 0.5) * TILE_SIZE;
+					let y = -1.0 + (tile_y as Scalar + 0.5) * TILE_SIZE;
+					let half = TILE_SIZE / 2.0;
+					let role = match tile_role(tile) {
+						Some(role) => role,
+						None => continue,
+					};
+					// Ramps as the triangle on the solid side of the
+					// diagonal.
+					let rotation = frame.rotation(tx, ty);
+					let outline = match tile.ramp_corners(rotation) {
+						Some(corners) => {
+							let mut outline: Vec<_> = corners
+								.iter()
+								.map(|&(cx, cy)| {
+									face.to_net(x + cx * half, y + cy * half)
+								})
+								.collect();
+							outline.push(outline[0]);
+							outline
+						}
+						None => square(face, x, y, half),
+					};
+					paths.push((self.palette.get(role), outline));
+				}
+			}
+
+			let border_role = if Some(face.frame_id) == focus_frame {
+				Role::Highlight
+			} else {
+				Role::FrameBorder
+			};
+			let border = square(face, 0.0, 0.0, 1.0);
+			paths.push((self.palette.get(border_role), border));
+
+			for &entity_id in world.entities_in_frame(face.frame_id) {
+				let entity = match world.get_entity(entity_id) {
+					Some(entity) => entity,
+					None => continue,
+				};
+				let role = match entity.kind {
+					EntityKind::Player => Role::Player,
+					EntityKind::Coin => Role::Coin,
+					EntityKind::GravityOrb => Role::GravityOrb,
+					EntityKind::SpeedBoost => Role::SpeedBoost,
+					EntityKind::Energizer => Role::Energizer,
+					EntityKind::Block => Role::Block,
+					EntityKind::Walker
+					| EntityKind::Worm
+					| EntityKind::WormSegment => Role::Enemy,
+				};
+				let p = entity.position;
+				let marker = square(face, p.x, p.y, NET_MARKER_SIZE);
+				paths.push((self.palette.get(role), marker));
+			}
+		}
+
+		paths
+	}
+}
@@ -0,0 +1,125 @@
+use std::collections::VecDeque;
+
+use super::{font, Role, Window, TEXT_SCALE};
+use crate::geometry::Scalar;
+
+// Notifications shown at once, stacked in the bottom left corner. Any more
+// wait their turn, up to `MAX_QUEUED`, past which the oldest waiting are
+// dropped.
+const MAX_SHOWN: usize = 3;
+const MAX_QUEUED: usize = 8;
+// World ticks over which a notification fades out at the end of its time.
+const FADE_TICKS: u32 = 30;
+// Distance of the stack from the bottom left corner.
+const NOTIFICATION_INSET: Scalar = 8.0;
+// A reasonable time to leave a one line message up, in world ticks.
+pub const NOTIFY_TICKS: u32 = 180;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Severity {
+	Info,
+	Warning,
+	Error,
+}
+
+impl Severity {
+	fn role(&self) -> Role {
+		match self {
+			Severity::Info => Role::UiText,
+			Severity::Warning => Role::UiWarning,
+			Severity::Error => Role::UiError,
+		}
+	}
+}
+
+struct Notification {
+	text: String,
+	ticks_left: u32,
+	severity: Severity,
+}
+
+// Brief messages telling the player something happened, such as an
+// autosave, without stopping the game.
+pub struct Notifications {
+	// Oldest first.
+	shown: VecDeque<Notification>,
+	queued: VecDeque<Notification>,
+}
+
+impl Notifications {
+	pub fn new() -> Self {
+		Self {
+			shown: VecDeque::with_capacity(MAX_SHOWN),
+			queued: VecDeque::with_capacity(MAX_QUEUED),
+		}
+	}
+
+	pub fn notify<T: Into<String>>(
+		&mut self,
+		text: T,
+		duration_ticks: u32,
+		severity: Severity,
+	) {
+		let notification = Notification {
+			text: text.into(),
+			ticks_left: duration_ticks,
+			severity,
+		};
+		if self.shown.len() < MAX_SHOWN {
+			self.shown.push_back(notification);
+			return;
+		}
+		if self.queued.len() == MAX_QUEUED {
+			self.queued.pop_front();
+		}
+		self.queued.push_back(notification);
+	}
+
+	// Called once per world tick, like the HUD.
+	pub fn update(&mut self) {
+		for notification in self.shown.iter_mut() {
+			notification.ticks_left = notification.ticks_left.saturating_sub(1);
+		}
+		self.shown
+			.retain(|notification| notification.ticks_left > 0);
+		while self.shown.len() < MAX_SHOWN {
+			match self.queued.pop_front() {
+				Some(notification) => self.shown.push_back(notification),
+				None => break,
+			}
+		}
+	}
+}
+
+impl Window {
+	// Newest at the bottom. Drawn even with the HUD hidden, since they
+	// often answer something the player just did.
+	pub(super) fn draw_notifications(&mut self) {
+		let line_height = (font::LINE_ADVANCE * TEXT_SCALE) as Scalar;
+		let mut bottom = self.ui_size().1 - NOTIFICATION_INSET - line_height;
+		// Kept clear of the stress test's entity count.
+		if self.tick_timer.is_some() {
+			bottom -= line_height;
+		}
+		let background = self.palette.get(Role::Background);
+
+		let count = self.notifications.shown.len();
+		for index in 0..count {
+			let notification = &self.notifications.shown[index];
+			let fade = 1.0
+				- (notification.ticks_left.min(FADE_TICKS) as Scalar
+					/ FADE_TICKS as Scalar);
+			let color = self
+				.palette
+				.get(notification.severity.role())
+				.mix(background, fade);
+			// Moved out for the duration of the draw, which needs all of
+			// `self`, rather than copied.
+			let text =
+				std::mem::take(&mut self.notifications.shown[index].text);
+			let y = bottom - (count - 1 - index) as Scalar * line_height;
+			self.draw_text(&text, NOTIFICATION_INSET, y, TEXT_SCALE, color);
+			self.notifications.shown[index].text = text;
+		}
+	}
+}
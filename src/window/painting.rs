@@ -0,0 +1,214 @@
+use super::types::{Keycode, MouseButton};
+use super::{Role, Window};
+use crate::geometry::Scalar;
+use crate::prelude::*;
+use crate::world::{
+	EntityKind, EntitySpawn, FrameId, SetTileError, Tile, WorldPosition,
+	TILE_SIZE,
+};
+use crate::GameState;
+
+type PickedTile = (FrameId, usize, usize);
+
+// Tiles being painted from the press of a mouse button until its release.
+pub struct Stroke {
+	tile: Tile,
+	button: MouseButton,
+	// The last tile painted, joined to the next by a line so that a fast
+	// drag leaves no gaps.
+	last: Option<PickedTile>,
+	// Where a rectangle was started, if Shift was held when the button was
+	// pressed. Nothing is painted until the button is released.
+	anchor: Option<PickedTile>,
+}
+
+// The tiles on a line from `from` to `to`, both included, stepping one
+// tile at a time along the longer axis.
+fn tile_line(from: (isize, isize), to: (isize, isize)) -> Vec<(isize, isize)> {
+	let (mut x, mut y) = from;
+	let (dx, dy) = ((to.0 - x).abs(), -(to.1 - y).abs());
+	let (sx, sy) = ((to.0 - x).signum(), (to.1 - y).signum());
+	let mut error = dx + dy;
+	let mut tiles = vec![(x, y)];
+	while (x, y) != to {
+		let doubled = error * 2;
+		if doubled >= dy {
+			error += dy;
+			x += sx;
+		}
+		if doubled <= dx {
+			error += dx;
+			y += sy;
+		}
+		tiles.push((x, y));
+	}
+	tiles
+}
+
+impl Window {
+	// Left click places the selected tile kind at the tile under the
+	// pointer, or the selected entity kind in the middle of it, and right
+	// click clears it. Holding either paints wherever the pointer goes,
+	// and with Shift held as it is pressed, fills the rectangle dragged out
+	// once it is released.
+	pub(super) fn edit_picked_tile(&mut self, game_state: &mut GameState) {
+		let picked = match self.input_state.mouse_position {
+			Some(position) => self.pick_tile(&game_state.world, position),
+			None => None,
+		};
+
+		if self.stroke.is_none() {
+			let pressed = &self.input_state.mouse_buttons_pressed;
+			let (tile, button) = if pressed.contains(&MouseButton::Left) {
+				if let Some(kind) = self.selected_entity {
+					if let Some(picked) = picked {
+						self.place_picked_entity(game_state, kind, picked);
+					}
+					return;
+				}
+				(self.selected_tile, MouseButton::Left)
+			} else if pressed.contains(&MouseButton::Right) {
+				(Tile::Empty, MouseButton::Right)
+			} else {
+				return;
+			};
+			let rectangle =
+				self.input_state.keys_held.contains(&Keycode::Shift);
+			// A rectangle started off the cube has no corner to go from.
+			if rectangle && picked.is_none() {
+				return;
+			}
+			self.stroke = Some(Stroke {
+				tile,
+				button,
+				last: None,
+				anchor: if rectangle { picked } else { None },
+			});
+		}
+
+		let (tile, button, anchor) = match &self.stroke {
+			Some(stroke) => (stroke.tile, stroke.button, stroke.anchor),
+			None => return,
+		};
+		let held = self.input_state.mouse_buttons_held.contains(&button);
+		if let Some(anchor) = anchor {
+			if held == false {
+				self.stroke = None;
+				if let Some(corner) = picked {
+					self.paint_rect(game_state, anchor, corner, tile);
+				}
+				self.finish_edit(game_state);
+			}
+			return;
+		}
+
+		if let Some(picked) = picked {
+			self.paint_to(game_state, picked, tile);
+		}
+		if held == false {
+			self.stroke = None;
+			self.finish_edit(game_state);
+		}
+	}
+
+	// Paints the line from the last tile painted to `to`. A line only runs
+	// within one face, so one that would cross an edge starts again from
+	// `to` on the face beyond it.
+	fn paint_to(
+		&mut self,
+		game_state: &mut GameState,
+		to: PickedTile,
+		tile: Tile,
+	) {
+		let last = self
+			.stroke
+			.as_mut()
+			.and_then(|stroke| stroke.last.replace(to));
+		let (frame_id, x, y) = to;
+		let from = match last {
+			Some((last_frame, last_x, last_y)) if last_frame == frame_id => {
+				(last_x as isize, last_y as isize)
+			}
+			_ => (x as isize, y as isize),
+		};
+		for (x, y) in tile_line(from, (x as isize, y as isize)) {
+			let at = (frame_id, x as usize, y as usize);
+			self.paint_tile(game_state, at, tile);
+		}
+	}
+
+	// Fills from one corner to the other, inclusive, on the first corner's
+	// face. A rectangle dragged onto another face isn't painted at all, as
+	// there is no telling which tiles it would cover.
+	fn paint_rect(
+		&mut self,
+		game_state: &mut GameState,
+		from: PickedTile,
+		to: PickedTile,
+		tile: Tile,
+	) {
+		if from.0 != to.0 {
+			self.flash_edited_tile(to, Role::Rejected);
+			return;
+		}
+		let (frame_id, x0, y0) = from;
+		let (_, x1, y1) = to;
+		let refused = game_state.edit_history.fill_rect(
+			&mut game_state.world,
+			frame_id,
+			(x0 as isize, y0 as isize),
+			(x1 as isize, y1 as isize),
+			tile,
+		);
+		for ((x, y), error) in refused {
+			let at = (frame_id, x as usize, y as usize);
+			self.report_refused_edit(at, error);
+		}
+	}
+
+	fn paint_tile(
+		&mut self,
+		game_state: &mut GameState,
+		at: PickedTile,
+		tile: Tile,
+	) {
+		let (frame_id, x, y) = (at.0, at.1 as isize, at.2 as isize);
+		let world = &mut game_state.world;
+		// Held still, the pointer stays over a tile that is already done.
+		let current = world.get_frame(frame_id).map(|frame| *frame.tile(x, y));
+		if current == Some(tile) {
+			return;
+		}
+		let history = &mut game_state.edit_history;
+		if let Err(error) = history.set_tile(world, frame_id, x, y, tile) {
+			self.report_refused_edit(at, error);
+		}
+	}
+
+	fn report_refused_edit(&mut self, at: PickedTile, error: SetTileError) {
+		match error {
+			SetTileError::Occupied(_) => {
+				self.flash_edited_tile(at, Role::Rejected)
+			}
+			error => elog(format!("Could not edit picked tile: {:?}", error)),
+		}
+	}
+
+	fn place_picked_entity(
+		&mut self,
+		game_state: &mut GameState,
+		kind: EntityKind,
+		at: PickedTile,
+	) {
+		let (frame_id, x, y) = at;
+		let position = WorldPosition {
+			frame_id,
+			x: -1.0 + (x as Scalar + 0.5) * TILE_SIZE,
+			y: -1.0 + (y as Scalar + 0.5) * TILE_SIZE,
+		};
+		let spawn = EntitySpawn::new(kind, position);
+		if game_state.world.spawn_entity(&spawn).is_err() {
+			self.flash_edited_tile(at, Role::Rejected);
+		}
+	}
+}
@@ -0,0 +1,211 @@
+use super::Color;
+
+// Semantic color roles. Gameplay rendering asks the active palette for a
+// role instead of hard-coding a `Color`, so that tile kinds stay
+// distinguishable for colorblind players.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+pub enum Role {
+	Background,
+	FrameBorder,
+	// A frame edge with no link.
+	UnlinkedEdge,
+	// An edge left open on purpose, to fall off the world from.
+	Cliff,
+	TileSolid,
+	TileIce,
+	TileSpike,
+	TileSign,
+	TilePlate,
+	TileSpring,
+	TileSand,
+	TileWater,
+	Player,
+	Enemy,
+	Coin,
+	GravityOrb,
+	SpeedBoost,
+	Energizer,
+	Block,
+	Health,
+	Stamina,
+	Highlight,
+	// Frames on the world map the player hasn't been on yet.
+	MapFog,
+	// A tile edit that was made, or refused.
+	Accepted,
+	Rejected,
+	// Mixed into the player while any cheat is on.
+	Cheat,
+	UiText,
+	// Notifications about something going wrong, or that went wrong.
+	UiWarning,
+	UiError,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+pub enum PaletteKind {
+	Default,
+	HighContrast,
+	Deuteranopia,
+}
+
+impl PaletteKind {
+	pub fn name(&self) -> &'static str {
+		use PaletteKind::*;
+		match self {
+			Default => "default",
+			HighContrast => "high_contrast",
+			Deuteranopia => "deuteranopia",
+		}
+	}
+
+	pub fn from_name(name: &str) -> Option<Self> {
+		Self::iter().find(|kind| kind.name() == name).copied()
+	}
+
+	pub fn iter<'a>() -> impl std::iter::Iterator<Item = &'a Self> {
+		use PaletteKind::*;
+		[Default, HighContrast, Deuteranopia].iter()
+	}
+
+	pub fn next(&self) -> Self {
+		use PaletteKind::*;
+		match self {
+			Default => HighContrast,
+			HighContrast => Deuteranopia,
+			Deuteranopia => Default,
+		}
+	}
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Palette {
+	pub kind: PaletteKind,
+}
+
+impl Palette {
+	pub fn new(kind: PaletteKind) -> Self {
+		Self { kind }
+	}
+
+	pub fn name(&self) -> &'static str {
+		self.kind.name()
+	}
+
+	pub fn get(&self, role: Role) -> Color {
+		use PaletteKind::*;
+		use Role::*;
+		match (self.kind, role) {
+			(Default, Background) => Color::BLACK,
+			(Default, FrameBorder) => Color::GRAY,
+			(Default, UnlinkedEdge) => Color::RED,
+			(Default, Cliff) => Color::rgb(255, 150, 60),
+			(Default, TileSolid) => Color::WHITE,
+			(Default, TileIce) => Color::rgb(120, 200, 255),
+			(Default, TileSpike) => Color::RED,
+			(Default, TileSign) => Color::rgb(200, 150, 80),
+			(Default, TilePlate) => Color::rgb(160, 160, 200),
+			(Default, TileSpring) => Color::rgb(255, 120, 200),
+			(Default, TileSand) => Color::rgb(230, 200, 120),
+			(Default, TileWater) => Color::rgb(60, 120, 255),
+			(Default, Player) => Color::CYAN,
+			(Default, Enemy) => Color::rgb(255, 128, 0),
+			(Default, Coin) => Color::rgb(255, 215, 0),
+			(Default, GravityOrb) => Color::rgb(170, 120, 255),
+			(Default, SpeedBoost) => Color::rgb(120, 255, 120),
+			(Default, Energizer) => Color::rgb(255, 255, 120),
+			(Default, Block) => Color::rgb(190, 140, 90),
+			(Default, Health) => Color::rgb(220, 60, 60),
+			(Default, Stamina) => Color::rgb(80, 220, 120),
+			(Default, Highlight) => Color::YELLOW,
+			(Default, MapFog) => Color::rgb(40, 40, 48),
+			(Default, Accepted) => Color::GREEN,
+			(Default, Rejected) => Color::RED,
+			(Default, Cheat) => Color::rgb(255, 0, 255),
+			(Default, UiText) => Color::WHITE,
+			(Default, UiWarning) => Color::rgb(255, 200, 0),
+			(Default, UiError) => Color::rgb(255, 80, 80),
+
+			(HighContrast, Background) => Color::BLACK,
+			(HighContrast, FrameBorder) => Color::WHITE,
+			(HighContrast, UnlinkedEdge) => Color::rgb(255, 0, 255),
+			(HighContrast, Cliff) => Color::rgb(255, 165, 0),
+			(HighContrast, TileSolid) => Color::WHITE,
+			(HighContrast, TileIce) => Color::CYAN,
+			(HighContrast, TileSpike) => Color::rgb(255, 0, 255),
+			(HighContrast, TileSign) => Color::rgb(255, 165, 0),
+			(HighContrast, TilePlate) => Color::rgb(0, 255, 128),
+			(HighContrast, TileSpring) => Color::rgb(255, 0, 128),
+			(HighContrast, TileSand) => Color::YELLOW,
+			(HighContrast, TileWater) => Color::rgb(0, 128, 255),
+			(HighContrast, Player) => Color::YELLOW,
+			(HighContrast, Enemy) => Color::rgb(255, 0, 255),
+			(HighContrast, Coin) => Color::rgb(255, 215, 0),
+			(HighContrast, GravityOrb) => Color::rgb(0, 160, 255),
+			(HighContrast, SpeedBoost) => Color::GREEN,
+			(HighContrast, Energizer) => Color::rgb(128, 255, 0),
+			(HighContrast, Block) => Color::rgb(255, 165, 0),
+			(HighContrast, Health) => Color::WHITE,
+			(HighContrast, Stamina) => Color::CYAN,
+			(HighContrast, Highlight) => Color::GREEN,
+			(HighContrast, MapFog) => Color::rgb(64, 64, 64),
+			(HighContrast, Accepted) => Color::CYAN,
+			(HighContrast, Rejected) => Color::rgb(255, 0, 255),
+			(HighContrast, Cheat) => Color::RED,
+			(HighContrast, UiText) => Color::YELLOW,
+			(HighContrast, UiWarning) => Color::rgb(255, 165, 0),
+			(HighContrast, UiError) => Color::rgb(255, 0, 255),
+
+			// Avoids distinguishing anything by red versus green alone; the
+			// hazard colors sit on the blue/orange axis instead.
+			(Deuteranopia, Background) => Color::BLACK,
+			(Deuteranopia, FrameBorder) => Color::GRAY,
+			(Deuteranopia, UnlinkedEdge) => Color::rgb(213, 94, 0),
+			(Deuteranopia, Cliff) => Color::rgb(230, 159, 0),
+			(Deuteranopia, TileSolid) => Color::WHITE,
+			(Deuteranopia, TileIce) => Color::rgb(86, 180, 233),
+			(Deuteranopia, TileSpike) => Color::rgb(230, 159, 0),
+			(Deuteranopia, TileSign) => Color::rgb(204, 121, 167),
+			(Deuteranopia, TilePlate) => Color::rgb(150, 150, 150),
+			(Deuteranopia, TileSpring) => Color::rgb(0, 158, 115),
+			(Deuteranopia, TileSand) => Color::rgb(240, 228, 66),
+			(Deuteranopia, TileWater) => Color::rgb(0, 114, 178),
+			(Deuteranopia, Player) => Color::rgb(0, 114, 178),
+			(Deuteranopia, Enemy) => Color::rgb(213, 94, 0),
+			(Deuteranopia, Coin) => Color::rgb(240, 228, 66),
+			(Deuteranopia, GravityOrb) => Color::rgb(0, 158, 115),
+			(Deuteranopia, SpeedBoost) => Color::rgb(86, 180, 233),
+			(Deuteranopia, Energizer) => Color::rgb(240, 228, 66),
+			(Deuteranopia, Block) => Color::rgb(204, 121, 167),
+			(Deuteranopia, Health) => Color::rgb(230, 159, 0),
+			(Deuteranopia, Stamina) => Color::rgb(86, 180, 233),
+			(Deuteranopia, Highlight) => Color::rgb(240, 228, 66),
+			(Deuteranopia, MapFog) => Color::rgb(40, 40, 48),
+			(Deuteranopia, Accepted) => Color::rgb(86, 180, 233),
+			(Deuteranopia, Rejected) => Color::rgb(213, 94, 0),
+			(Deuteranopia, Cheat) => Color::rgb(204, 121, 167),
+			(Deuteranopia, UiText) => Color::WHITE,
+			(Deuteranopia, UiWarning) => Color::rgb(240, 228, 66),
+			(Deuteranopia, UiError) => Color::rgb(213, 94, 0),
+		}
+	}
+
+	// High-contrast mode thickens the outlines that matter for play, those
+	// of tiles, the player and highlighted tiles, and leaves grids,
+	// backgrounds and the HUD thin. There's no line width API on either
+	// backend, so this is done by drawing lines a second time with a one
+	// pixel offset.
+	pub fn thickens(&self, role: Role) -> bool {
+		use Role::*;
+
+		if self.kind != PaletteKind::HighContrast {
+			return false;
+		}
+		match role {
+			TileSolid | TileIce | TileSpike | TileSign | TilePlate
+			| TileSpring | TileSand | TileWater | Player | Highlight
+			| Accepted | Rejected => true,
+			_ => false,
+		}
+	}
+}
@@ -1,27 +1,17 @@
+use super::globe::Globe;
 use crate::geometry;
 
-use geometry::{Matrix4x4, Scalar, Vector3};
+use geometry::{Matrix4x4, Ray, Scalar, Vector3};
 
 #[derive(Copy, Clone)]
 pub struct Camera {
 	pub position: Vector3,
 	pub rotation: Vector3,
+	// Full vertical field of view.
 	pub fov_degrees: Scalar,
 }
 
-// cameraPos: vec(0, -5, 0),
-// 			cameraRot: vec(0, 0, 0),
-// 			fovDegrees: 40,
-
 impl Camera {
-	pub fn default() -> Self {
-		Self {
-			position: Vector3::new(0.0, 0.0, 2000.0),
-			rotation: Vector3::new(0.0, 0.0, 0.0),
-			fov_degrees: 40.0,
-		}
-	}
-
 	pub fn new(
 		position: Vector3,
 		rotation: Vector3,
@@ -34,10 +24,14 @@ impl Camera {
 		}
 	}
 
+	// `scale` is how much cube-space coordinates are magnified into camera
+	// world space. The projector's methods all take and return cube-space
+	// coordinates.
 	pub fn projector(
 		&self,
 		viewport_width: Scalar,
 		viewport_height: Scalar,
+		scale: Scalar,
 	) -> CameraProjector {
 		let pmv_matrix = create_pmv_matrix(
 			self.fov_degrees,
@@ -46,14 +40,24 @@ impl Camera {
 			viewport_width,
 			viewport_height,
 		);
-		CameraProjector::new(pmv_matrix, viewport_width, viewport_height, *self)
+		CameraProjector::new(
+			pmv_matrix,
+			viewport_width,
+			viewport_height,
+			scale,
+			*self,
+		)
 	}
 }
 
+#[derive(Copy, Clone)]
 pub struct CameraProjector {
 	pmv_matrix: Matrix4x4,
 	viewport_width: Scalar,
 	viewport_height: Scalar,
+	scale: Scalar,
+	// Applied to every point before it is projected.
+	globe: Globe,
 	pub camera: Camera,
 }
 
@@ -62,18 +66,33 @@ impl CameraProjector {
 		pmv_matrix: Matrix4x4,
 		viewport_width: Scalar,
 		viewport_height: Scalar,
+		scale: Scalar,
 		camera: Camera,
 	) -> Self {
 		Self {
 			pmv_matrix,
 			viewport_width,
 			viewport_height,
+			scale,
+			globe: Globe::flat(),
 			camera,
 		}
 	}
 
+	pub(super) fn with_globe(self, globe: Globe) -> Self {
+		Self { globe, ..self }
+	}
+
+	pub(super) fn globe(&self) -> &Globe {
+		&self.globe
+	}
+
+	// Normalized device coordinates are mapped to the viewport without
+	// flipping y, so +y in cube space points down the screen, the same as
+	// tile coordinates within a frame.
 	pub fn project_point(&self, point: Vector3) -> (Scalar, Scalar, Scalar) {
-		let projected_position = point * self.pmv_matrix;
+		let point = self.globe.inflate(point);
+		let projected_position = point * self.scale * self.pmv_matrix;
 
 		let (px, py) = (projected_position.x, projected_position.y);
 		let hw = self.viewport_width / 2.0;
@@ -82,8 +101,32 @@ impl CameraProjector {
 		(px * hw + hw, py * hh + hh, projected_position.z)
 	}
 
-	pub fn apply_projection_matrix(&self, point: Vector3) -> Vector3 {
-		point * self.pmv_matrix
+	// The ray of cube-space points that project onto the given screen
+	// position, starting at the near plane. `None` when nothing projects
+	// anywhere, such as into the empty viewport of a minimized window.
+	pub fn unproject(&self, screen_x: Scalar, screen_y: Scalar) -> Option<Ray> {
+		let hw = self.viewport_width / 2.0;
+		let hh = self.viewport_height / 2.0;
+		if hw <= 0.0 || hh <= 0.0 {
+			return None;
+		}
+		let ndc_x = (screen_x - hw) / hw;
+		let ndc_y = (screen_y - hh) / hh;
+
+		let inverse = self.pmv_matrix.inverse()?;
+		let near = Vector3::new(ndc_x, ndc_y, -1.0) * inverse / self.scale;
+		let far = Vector3::new(ndc_x, ndc_y, 1.0) * inverse / self.scale;
+		let finite =
+			|v: Vector3| v.x.is_finite() && v.y.is_finite() && v.z.is_finite();
+		if finite(near) == false || finite(far) == false {
+			return None;
+		}
+
+		Some(Ray::new(near, far - near))
+	}
+
+	pub fn camera_position(&self) -> Vector3 {
+		self.camera.position / self.scale
 	}
 }
 
@@ -99,7 +142,7 @@ fn create_pmv_matrix(
 	let near = 0.1;
 	let far = 50_000.0;
 
-	let height = 2.0 * near * fov_degrees.to_radians().tan();
+	let height = 2.0 * near * (fov_degrees.to_radians() / 2.0).tan();
 	let width = aspect_ratio * height;
 
 	#[rustfmt::skip]
@@ -135,3 +178,88 @@ fn create_pmv_matrix(
 
 	pmv_matrix
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use geometry::{approx_eq, vec3, PI};
+
+	// 5 along +z looking down -z, seeing 90 degrees from top to bottom of a
+	// 200 by 100 viewport, so the plane z = 0 shows from -5 to 5 down the
+	// screen and -10 to 10 across it.
+	fn projector(rotation: Vector3) -> CameraProjector {
+		let camera = Camera::new(vec3(0.0, 0.0, 5.0), rotation, 90.0);
+		camera.projector(200.0, 100.0, 1.0)
+	}
+
+	fn assert_projects_to(
+		projector: &CameraProjector,
+		point: Vector3,
+		expected: (Scalar, Scalar),
+	) {
+		let (x, y, _) = projector.project_point(point);
+		assert!(
+			approx_eq(x, expected.0, 1e-3) && approx_eq(y, expected.1, 1e-3),
+			"{} went to ({}, {}), not {:?}",
+			point,
+			x,
+			y,
+			expected
+		);
+	}
+
+	#[test]
+	fn project_point_pins_known_points() {
+		let p = projector(Vector3::zero());
+		assert_projects_to(&p, vec3(0.0, 0.0, 0.0), (100.0, 50.0));
+		assert_projects_to(&p, vec3(10.0, 0.0, 0.0), (200.0, 50.0));
+		assert_projects_to(&p, vec3(-5.0, 0.0, 0.0), (50.0, 50.0));
+		// +y is down the screen.
+		assert_projects_to(&p, vec3(0.0, 5.0, 0.0), (100.0, 100.0));
+		assert_projects_to(&p, vec3(0.0, -2.5, 0.0), (100.0, 25.0));
+		// Twice as far away, half as far from the middle.
+		assert_projects_to(&p, vec3(10.0, 5.0, -5.0), (150.0, 75.0));
+	}
+
+	#[test]
+	fn project_point_depth_grows_away_from_the_camera() {
+		let p = projector(Vector3::zero());
+		let (_, _, near) = p.project_point(vec3(0.0, 0.0, 4.0));
+		let (_, _, far) = p.project_point(vec3(0.0, 0.0, -100.0));
+		assert!(-1.0 < near && near < far && far < 1.0);
+	}
+
+	#[test]
+	fn camera_rotation_turns_the_view() {
+		// The world is turned a quarter about z before it is looked at, so
+		// what is along +x shows along +y.
+		let p = projector(vec3(0.0, 0.0, PI / 2.0));
+		assert_projects_to(&p, vec3(5.0, 0.0, 0.0), (100.0, 100.0));
+	}
+
+	#[test]
+	fn unproject_is_the_inverse_of_project_point() {
+		let p = projector(vec3(0.2, -0.1, 0.3));
+		let point = vec3(1.5, -2.0, 0.5);
+		let (x, y, _) = p.project_point(point);
+		let ray = p.unproject(x, y).unwrap();
+		// The point is on the ray through where it was drawn.
+		let along = (point - ray.origin).dot(ray.direction);
+		let nearest = ray.at(along);
+		assert!(
+			nearest.approx_eq(point, 1e-2),
+			"{} is not {}",
+			nearest,
+			point
+		);
+	}
+
+	#[test]
+	fn unproject_into_an_empty_viewport_is_none() {
+		let camera = Camera::new(vec3(0.0, 0.0, 5.0), Vector3::zero(), 90.0);
+		for &(width, height) in &[(200.0, 0.0), (0.0, 100.0), (0.0, 0.0)] {
+			let p = camera.projector(width, height, 1.0);
+			assert!(p.unproject(0.0, 0.0).is_none());
+		}
+	}
+}
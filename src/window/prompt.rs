@@ -0,0 +1,59 @@
+use super::{CameraProjector, Role, Window};
+use crate::geometry::{Matrix4x4, Scalar};
+use crate::world::{FrameId, World, TILE_SIZE};
+
+// Glyph height of the prompt, in frame units.
+const PROMPT_HEIGHT: Scalar = 0.06;
+
+impl Window {
+	// Once a tick, what the player has in reach to interact with.
+	pub(super) fn update_prompt(&mut self, world: &World) {
+		self.prompt = world.focus_entity.and_then(|id| world.interaction(id));
+	}
+
+	// The interact key and what it would do, just above the tile it would
+	// do it to, along with why not if it can't be done yet.
+	pub(super) fn draw_prompt(
+		&mut self,
+		projector: &CameraProjector,
+		surface_transforms: &[(FrameId, Matrix4x4)],
+		view_rotation: Matrix4x4,
+	) {
+		let interaction = match self.prompt {
+			Some(interaction) => interaction,
+			None => return,
+		};
+		let (frame_id, x, y) = interaction.target;
+		let transform = surface_transforms
+			.iter()
+			.find(|&&(transform_frame, _)| transform_frame == frame_id);
+		let m = match transform {
+			Some(&(_, m)) => m,
+			None => return,
+		};
+
+		let key = format!("{:?}", self.bindings.interact);
+		let (text, role) = match interaction.unavailable {
+			Some(reason) => (
+				format!("{}: {} - {}", key, interaction.verb, reason),
+				Role::UiWarning,
+			),
+			None => (format!("{}: {}", key, interaction.verb), Role::UiText),
+		};
+		let center = (
+			-1.0 + (x as Scalar + 0.5) * TILE_SIZE,
+			-1.0 + (y as Scalar - 0.5) * TILE_SIZE,
+		);
+		let color = self.palette.get(role);
+		let r = view_rotation;
+		self.draw_surface_text(
+			projector,
+			&text,
+			center,
+			PROMPT_HEIGHT,
+			m,
+			r,
+			color,
+		);
+	}
+}
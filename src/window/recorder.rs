@@ -0,0 +1,246 @@
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+
+use super::gif::{self, GifFrame};
+use super::{backend, Window};
+use crate::prelude::*;
+
+// Every this many rendered frames is captured.
+const CAPTURE_INTERVAL: usize = 3;
+// Recording stops by itself after this many seconds, to bound memory use.
+const MAX_RECORDING_SECONDS: f64 = 10.0;
+// Frames are scaled down to fit within this size.
+const MAX_GIF_WIDTH: usize = 450;
+const MAX_GIF_HEIGHT: usize = 350;
+// Captured frames waiting to be processed. Frames arriving while it is full
+// are dropped rather than stalling the game.
+const CAPTURE_QUEUE_LENGTH: usize = 4;
+// Levels per color channel; four of each makes 64 colors.
+const CHANNEL_LEVELS: usize = 4;
+
+// Pixels read back from the canvas, three bytes per pixel.
+struct CapturedFrame {
+	width: usize,
+	height: usize,
+	rgb: Vec<u8>,
+	// From `backend::time::now`.
+	time: f64,
+}
+
+enum Message {
+	Frame(CapturedFrame),
+	Finish,
+}
+
+// Captures rendered frames and writes them out as an animated GIF. Scaling,
+// quantizing and encoding happen on a background thread.
+pub struct Recorder {
+	session: Option<Session>,
+}
+
+struct Session {
+	sender: SyncSender<Message>,
+	start_time: f64,
+	frame_count: usize,
+	dropped: usize,
+}
+
+impl Recorder {
+	pub fn new() -> Self {
+		Self { session: None }
+	}
+
+	pub fn is_recording(&self) -> bool {
+		self.session.is_some()
+	}
+
+	pub fn start(&mut self, time: f64) {
+		if self.session.is_some() {
+			return;
+		}
+		let (sender, receiver) = mpsc::sync_channel(CAPTURE_QUEUE_LENGTH);
+		std::thread::spawn(move || encode_frames(receiver));
+		self.session = Some(Session {
+			sender,
+			start_time: time,
+			frame_count: 0,
+			dropped: 0,
+		});
+		log("Recording started");
+	}
+
+	// Whether the frame being rendered at `time` should be captured. Stops
+	// the recording once it reaches its maximum length.
+	pub fn wants_frame(&mut self, time: f64) -> bool {
+		let session = match self.session.as_mut() {
+			Some(session) => session,
+			None => return false,
+		};
+		if time - session.start_time >= MAX_RECORDING_SECONDS {
+			self.stop();
+			return false;
+		}
+		session.frame_count += 1;
+		(session.frame_count - 1) % CAPTURE_INTERVAL == 0
+	}
+
+	fn capture(&mut self, frame: CapturedFrame) {
+		let session = match self.session.as_mut() {
+			Some(session) => session,
+			None => return,
+		};
+		match session.sender.try_send(Message::Frame(frame)) {
+			Ok(()) => {}
+			Err(TrySendError::Full(_)) => session.dropped += 1,
+			Err(TrySendError::Disconnected(_)) => {
+				elog("Recording worker stopped unexpectedly");
+				self.session = None;
+			}
+		}
+	}
+
+	// Hands the recording over to be encoded and saved. Doesn't wait for
+	// that to finish.
+	pub fn stop(&mut self) {
+		let session = match self.session.take() {
+			Some(session) => session,
+			None => return,
+		};
+		// Blocks only until there is room in the queue.
+		if session.sender.send(Message::Finish).is_err() {
+			elog("Recording worker stopped unexpectedly");
+		}
+		if session.dropped > 0 {
+			log(format!(
+				"Recording stopped, {} frames dropped",
+				session.dropped
+			));
+		} else {
+			log("Recording stopped");
+		}
+	}
+}
+
+impl Window {
+	pub(super) fn toggle_recording(&mut self) {
+		if self.recorder.is_recording() {
+			self.recorder.stop();
+		} else if self.backend.supports_read_pixels() {
+			self.recorder.start(backend::time::now());
+		} else {
+			log("GIF recording is not supported here");
+		}
+	}
+
+	// Called after everything has been drawn and before it is presented.
+	pub(super) fn capture_recording_frame(&mut self) {
+		let time = backend::time::now();
+		if self.recorder.wants_frame(time) == false {
+			return;
+		}
+		match self.backend.read_pixels() {
+			Ok((width, height, rgb)) => self.recorder.capture(CapturedFrame {
+				width: width as usize,
+				height: height as usize,
+				rgb,
+				time,
+			}),
+			Err(error) => {
+				elog(format!("Could not capture frame: {}", error));
+				self.recorder.stop();
+			}
+		}
+	}
+}
+
+fn encode_frames(receiver: Receiver<Message>) {
+	let mut frames: Vec<GifFrame> = Vec::new();
+	let mut size = None;
+	let mut last_time = None;
+
+	while let Ok(Message::Frame(frame)) = receiver.recv() {
+		let (width, height, pixels) = downscale_and_index(&frame);
+		// Frames captured after a resize would have a different size, and
+		// a GIF has one size throughout.
+		if *size.get_or_insert((width, height)) != (width, height) {
+			continue;
+		}
+		if let (Some(last), Some(previous)) = (last_time, frames.last_mut()) {
+			previous.delay = centiseconds(frame.time - last);
+		}
+		last_time = Some(frame.time);
+		frames.push(GifFrame { pixels, delay: 0 });
+	}
+
+	let (width, height) = match size {
+		Some(size) => size,
+		None => return,
+	};
+	// The last frame is shown about as long as the others.
+	if frames.len() > 1 {
+		let delay = frames[frames.len() - 2].delay;
+		frames.last_mut().unwrap().delay = delay;
+	}
+
+	let data = gif::encode(width as u16, height as u16, &palette(), &frames);
+	let path = recording_path();
+	match std::fs::write(&path, data) {
+		Ok(()) => log(format!("Saved {} frames to {}", frames.len(), path)),
+		Err(error) => elog(format!("Could not save {}: {}", path, error)),
+	}
+}
+
+fn centiseconds(seconds: f64) -> u16 {
+	(seconds * 100.0).round().max(1.0).min(u16::MAX as f64) as u16
+}
+
+// The fixed palette every frame is quantized to: an evenly spaced cube of
+// `CHANNEL_LEVELS` per channel, indexed red major.
+fn palette() -> Vec<[u8; 3]> {
+	let level = |index: usize| (index * 255 / (CHANNEL_LEVELS - 1)) as u8;
+	let mut colors = Vec::new();
+	for r in 0..CHANNEL_LEVELS {
+		for g in 0..CHANNEL_LEVELS {
+			for b in 0..CHANNEL_LEVELS {
+				colors.push([level(r), level(g), level(b)]);
+			}
+		}
+	}
+	colors
+}
+
+fn palette_index(r: u8, g: u8, b: u8) -> u8 {
+	let level =
+		|channel: u8| (channel as usize * (CHANNEL_LEVELS - 1) + 127) / 255;
+	(level(r) * CHANNEL_LEVELS * CHANNEL_LEVELS
+		+ level(g) * CHANNEL_LEVELS
+		+ level(b)) as u8
+}
+
+// Nearest neighbor scaling to fit within the maximum GIF size, keeping the
+// aspect ratio. Frames that already fit are left at their size.
+fn downscale_and_index(frame: &CapturedFrame) -> (usize, usize, Vec<u8>) {
+	let scale = (MAX_GIF_WIDTH as f64 / frame.width as f64)
+		.min(MAX_GIF_HEIGHT as f64 / frame.height as f64)
+		.min(1.0);
+	let width = ((frame.width as f64 * scale) as usize).max(1);
+	let height = ((frame.height as f64 * scale) as usize).max(1);
+
+	let mut pixels = Vec::with_capacity(width * height);
+	for y in 0..height {
+		let source_y = y * frame.height / height;
+		for x in 0..width {
+			let source_x = x * frame.width / width;
+			let offset = (source_y * frame.width + source_x) * 3;
+			let rgb = &frame.rgb[offset..offset + 3];
+			pixels.push(palette_index(rgb[0], rgb[1], rgb[2]));
+		}
+	}
+	(width, height, pixels)
+}
+
+fn recording_path() -> String {
+	let seconds = std::time::SystemTime::now()
+		.duration_since(std::time::UNIX_EPOCH)
+		.map_or(0, |duration| duration.as_secs());
+	format!("recording-{}.gif", seconds)
+}
@@ -0,0 +1,97 @@
+use super::{CameraProjector, Color, Window};
+use crate::geometry::{vec3, Matrix4x4, Scalar, Vector3};
+use crate::world::{FrameId, World, WorldPosition};
+
+// Splits a polyline in frame coordinates wherever it crosses a frame edge,
+// so that no segment has ends on both sides of one.
+fn split_at_edges(points: &[(Scalar, Scalar)]) -> Vec<(Scalar, Scalar)> {
+	let mut split = Vec::with_capacity(points.len());
+	for pair in points.windows(2) {
+		let ((ax, ay), (bx, by)) = (pair[0], pair[1]);
+		split.push((ax, ay));
+
+		// Where along the segment each coordinate passes each edge line.
+		let mut crossings: Vec<Scalar> = Vec::new();
+		for &edge in &[-1.0, 1.0] {
+			for &(a, b) in &[(ax, bx), (ay, by)] {
+				if (a - edge) * (b - edge) < 0.0 {
+					crossings.push((edge - a) / (b - a));
+				}
+			}
+		}
+		crossings.sort_by(|a, b| a.partial_cmp(b).unwrap());
+		for t in crossings {
+			split.push((ax + (bx - ax) * t, ay + (by - ay) * t));
+		}
+	}
+	split.extend(points.last());
+	split
+}
+
+impl Window {
+	// Draws a polyline given in `frame_id`'s coordinates onto the cube
+	// surface. Parts past an edge are carried onto the neighboring face
+	// through its link, the same way positions are, so shapes straddling an
+	// edge fold over it instead of sticking out into space. Parts on faces
+	// that aren't among `surface_transforms` are left out. Thicker if
+	// `thick`.
+	pub(super) fn draw_surface_lines(
+		&mut self,
+		projector: &CameraProjector,
+		world: &World,
+		surface_transforms: &[(FrameId, Matrix4x4)],
+		frame_id: FrameId,
+		points: &[(Scalar, Scalar)],
+		view_rotation: Matrix4x4,
+		color: Color,
+		thick: bool,
+	) {
+		let r = view_rotation;
+		let transform_of = |frame_id: FrameId| {
+			surface_transforms
+				.iter()
+				.find(|&&(id, _)| id == frame_id)
+				.map(|&(_, transform)| transform)
+		};
+
+		let inside = points
+			.iter()
+			.all(|&(x, y)| x.abs() <= 1.0 && y.abs() <= 1.0);
+		if inside {
+			let m = match transform_of(frame_id) {
+				Some(m) => m,
+				None => return,
+			};
+			let points: Vec<Vector3> = points
+				.iter()
+				.map(|&(x, y)| vec3(x, y, 1.0) * m * r)
+				.collect();
+			self.draw_weighted_lines(projector, &points, color, thick);
+			return;
+		}
+
+		let cube_point = |(x, y): (Scalar, Scalar)| -> Option<Vector3> {
+			let position = WorldPosition { frame_id, x, y };
+			let position = position.normalize(world).ok()?;
+			let m = transform_of(position.frame_id)?;
+			Some(vec3(position.x, position.y, 1.0) * m * r)
+		};
+
+		// Drawn in runs, broken wherever a point can't be placed.
+		let mut run: Vec<Vector3> = Vec::new();
+		for point in split_at_edges(points) {
+			match cube_point(point) {
+				Some(point) => run.push(point),
+				None => {
+					if run.len() >= 2 {
+						self.draw_weighted_lines(projector, &run, color, thick);
+					}
+					run.clear();
+				}
+			}
+		}
+		if run.len() >= 2 {
+			self.draw_weighted_lines(projector, &run, color, thick);
+		}
+	}
+}
@@ -0,0 +1,77 @@
+use super::{CameraProjector, Role, Window};
+use crate::geometry::{Matrix4x4, Scalar, PI};
+use crate::world::{Entity, FrameId, World, TILE_SIZE};
+
+// Entities further than this many tiles above the ground cast no shadow.
+const SHADOW_MAX_TILES: usize = 6;
+// Half the width of a shadow right under an entity.
+const SHADOW_RADIUS: Scalar = 0.04;
+// How much shallower a shadow is than it is wide.
+const SHADOW_FLATNESS: Scalar = 0.3;
+// How far shadows are mixed towards the background, since there's no
+// alpha blending to draw them translucent.
+const SHADOW_FADE: Scalar = 0.7;
+
+impl Window {
+	// A flat oval on the ground under each entity in the air, smaller the
+	// higher up the entity is, so that it's clear where a jump will land.
+	pub(super) fn draw_shadows(
+		&mut self,
+		projector: &CameraProjector,
+		world: &World,
+		entities: &[&Entity],
+		surface_transforms: &[(FrameId, Matrix4x4)],
+		view_rotation: Matrix4x4,
+	) {
+		let color = self
+			.palette
+			.get(Role::TileSolid)
+			.mix(self.palette.get(Role::Background), SHADOW_FADE);
+		for entity in entities {
+			if entity.grounded
+				|| entity.kind.is_pickup()
+				|| entity.is_falling_off()
+			{
+				continue;
+			}
+			let position = match self.drawn_position(world, entity.id) {
+				Some(position) => position,
+				None => continue,
+			};
+			let (ground, distance, down) = match world.ground_below_turned(
+				position,
+				entity.gravity_dir,
+				SHADOW_MAX_TILES,
+			) {
+				Some(ground) => ground,
+				None => continue,
+			};
+
+			let width = SHADOW_RADIUS * TILE_SIZE / (TILE_SIZE + distance);
+			let depth = width * SHADOW_FLATNESS;
+			// Resting on the ground rather than sunk halfway into it.
+			let (dx, dy) = down.unit();
+			let (cx, cy) = (ground.x - dx * depth, ground.y - dy * depth);
+			let oval: Vec<(Scalar, Scalar)> = (0..=12)
+				.map(|i| {
+					let t = i as Scalar / 12.0 * PI * 2.0;
+					let (across, along) = (t.cos() * width, t.sin() * depth);
+					(
+						cx + dy * across + dx * along,
+						cy + dx * across + dy * along,
+					)
+				})
+				.collect();
+			self.draw_surface_lines(
+				projector,
+				world,
+				surface_transforms,
+				ground.frame_id,
+				&oval,
+				view_rotation,
+				color,
+				false,
+			);
+		}
+	}
+}
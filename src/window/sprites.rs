@@ -0,0 +1,108 @@
+use crate::geometry::Scalar;
+use crate::world::{AnimationKind, AnimationState};
+
+// A polyline in an entity's own units, which its kind's drawing code scales
+// and turns into frame coordinates.
+pub(super) type Shape = &'static [(Scalar, Scalar)];
+// How an entity looks on one frame of an animation.
+pub(super) type Pose = &'static [Shape];
+
+// The frames of each of an entity kind's animations, played in order and
+// looped. Any left empty show the idle frames instead, so a kind needs only
+// as many as it has reason to, and it only takes a new table here to give it
+// more.
+pub(super) struct Sprite {
+	pub idle: &'static [Pose],
+	pub walk: &'static [Pose],
+	pub airborne: &'static [Pose],
+	pub land: &'static [Pose],
+}
+
+impl Sprite {
+	pub fn pose(&self, animation: &AnimationState) -> Pose {
+		let poses = match animation.kind {
+			AnimationKind::Idle => self.idle,
+			AnimationKind::Walk => self.walk,
+			AnimationKind::Airborne => self.airborne,
+			AnimationKind::Land => self.land,
+		};
+		let poses = if poses.is_empty() { self.idle } else { poses };
+		poses[animation.frame % poses.len()]
+	}
+}
+
+// Feet down at y = 1, head up at y = -1.
+pub(super) const PLAYER: Sprite = Sprite {
+	idle: &[&[&[(0.0, -1.0), (0.0, 1.0), (-0.6, 1.0), (0.6, 1.0)]]],
+	walk: &[
+		&[
+			&[(0.0, -1.0), (0.0, 0.3)],
+			&[(-0.6, 1.0), (0.0, 0.3), (0.6, 1.0)],
+		],
+		&[&[(0.0, -1.0), (0.0, 1.0), (-0.6, 1.0), (0.6, 1.0)]],
+	],
+	airborne: &[&[
+		&[(0.0, -1.0), (0.0, 0.5)],
+		&[(-0.4, 0.9), (0.0, 0.5), (0.4, 0.9)],
+	]],
+	land: &[&[&[(0.0, -0.5), (0.0, 1.0), (-0.9, 1.0), (0.9, 1.0)]]],
+};
+
+// A diamond narrowing and widening again as it turns.
+pub(super) const COIN: Sprite = Sprite {
+	idle: &[
+		&[&[
+			(0.0, -1.0),
+			(1.0, 0.0),
+			(0.0, 1.0),
+			(-1.0, 0.0),
+			(0.0, -1.0),
+		]],
+		&[&[
+			(0.0, -1.0),
+			(0.6, 0.0),
+			(0.0, 1.0),
+			(-0.6, 0.0),
+			(0.0, -1.0),
+		]],
+		&[&[
+			(0.0, -1.0),
+			(0.15, 0.0),
+			(0.0, 1.0),
+			(-0.15, 0.0),
+			(0.0, -1.0),
+		]],
+		&[&[
+			(0.0, -1.0),
+			(0.6, 0.0),
+			(0.0, 1.0),
+			(-0.6, 0.0),
+			(0.0, -1.0),
+		]],
+	],
+	walk: &[],
+	airborne: &[],
+	land: &[],
+};
+
+// An arrowhead pointing the way it walks, along x, bobbing across it as it
+// goes.
+pub(super) const WALKER: Sprite = Sprite {
+	idle: &[&[&[(-1.0, 1.0), (1.0, 0.0), (-1.0, -1.0)]]],
+	walk: &[
+		&[&[(-1.0, 1.0), (1.0, 0.0), (-1.0, -1.0)]],
+		&[&[(-1.0, 0.75), (1.0, -0.25), (-1.0, -1.25)]],
+	],
+	airborne: &[],
+	land: &[],
+};
+
+// Each shape of `pose` with its points passed through `point`.
+pub(super) fn pose_shapes(
+	pose: Pose,
+	point: impl Fn(Scalar, Scalar) -> (Scalar, Scalar),
+) -> Vec<Vec<(Scalar, Scalar)>> {
+	pose.iter()
+		.map(|shape| shape.iter().map(|&(x, y)| point(x, y)).collect())
+		.collect()
+}
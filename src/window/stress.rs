@@ -0,0 +1,85 @@
+use super::{backend, font, Role, Window, TEXT_SCALE};
+use crate::geometry::Scalar;
+use crate::prelude::*;
+use crate::world::EdgeBehavior;
+use crate::GameState;
+
+// Seconds between tick timing reports.
+const REPORT_INTERVAL: f64 = 5.0;
+// Distance of the entity count from the bottom left corner.
+const ENTITY_COUNT_INSET: Scalar = 8.0;
+
+// How long world ticks take, reported as percentiles every few seconds.
+pub struct TickTimer {
+	// Seconds per tick since the last report.
+	samples: Vec<f64>,
+	// From `backend::time::now`.
+	last_report: f64,
+}
+
+impl TickTimer {
+	pub fn new() -> Self {
+		Self {
+			samples: Vec::new(),
+			last_report: backend::time::now(),
+		}
+	}
+
+	pub fn record(&mut self, seconds: f64) {
+		self.samples.push(seconds);
+
+		let now = backend::time::now();
+		if now - self.last_report < REPORT_INTERVAL {
+			return;
+		}
+		self.last_report = now;
+
+		self.samples.sort_by(|a, b| a.partial_cmp(b).unwrap());
+		let percentile = |p: f64| {
+			let index = ((self.samples.len() - 1) as f64 * p).round();
+			self.samples[index as usize] * 1000.0
+		};
+		log(format!(
+			"{} ticks: p50 {:.3} ms, p90 {:.3} ms, p99 {:.3} ms, max {:.3} ms",
+			self.samples.len(),
+			percentile(0.5),
+			percentile(0.9),
+			percentile(0.99),
+			percentile(1.0)
+		));
+		self.samples.clear();
+	}
+}
+
+impl Window {
+	// Adds walkers all over the world and starts reporting tick timings.
+	pub fn start_stress(
+		&mut self,
+		game_state: &mut GameState,
+		count: usize,
+		edge_behavior: EdgeBehavior,
+	) {
+		let world = &mut game_state.world;
+		let spawned = world.spawn_walkers(count, edge_behavior);
+		log(format!("Spawned {} walkers", spawned));
+		self.tick_timer.get_or_insert_with(TickTimer::new);
+	}
+
+	// While stress testing, how many entities there are and how many of them
+	// the last tick updated, in the bottom left corner.
+	pub(super) fn draw_entity_count(&mut self, game_state: &GameState) {
+		if self.tick_timer.is_none() {
+			return;
+		}
+		let world = &game_state.world;
+		let text = format!(
+			"{} entities, {} ticked",
+			world.entity_count(),
+			world.ticked_entity_count()
+		);
+		let color = self.palette.get(Role::UiText);
+		let line_height = (font::LINE_ADVANCE * TEXT_SCALE) as Scalar;
+		let y = self.ui_size().1 - ENTITY_COUNT_INSET - line_height;
+		self.draw_text(&text, ENTITY_COUNT_INSET, y, TEXT_SCALE, color);
+	}
+}
@@ -0,0 +1,258 @@
+use std::collections::HashMap;
+
+use super::{MouseButton, Role, Window};
+use crate::geometry::Scalar;
+use crate::world::{Frame, FrameId, Tile, World, FRAME_WIDTH};
+
+// UI pixels per tile.
+const THUMBNAIL_SCALE: Scalar = 3.0;
+const THUMBNAIL_SIZE: Scalar = FRAME_WIDTH as Scalar * THUMBNAIL_SCALE;
+const THUMBNAIL_GAP: Scalar = 8.0;
+// Gap between the strip and the bottom of the viewport.
+const STRIP_INSET: Scalar = 12.0;
+
+// The role a tile is shown with when the whole frame is drawn small, or
+// nothing for tiles left out.
+pub(super) fn tile_role(tile: Tile) -> Option<Role> {
+	match tile {
+		Tile::Solid
+		| Tile::ConveyorLeft
+		| Tile::ConveyorRight
+		| Tile::SlopeUpRight
+		| Tile::SlopeUpLeft => Some(Role::TileSolid),
+		Tile::Sign => Some(Role::TileSign),
+		Tile::PressurePlate => Some(Role::TilePlate),
+		Tile::Spring => Some(Role::TileSpring),
+		Tile::Sand => Some(Role::TileSand),
+		Tile::Water => Some(Role::TileWater),
+		Tile::Empty | Tile::Invalid => None,
+	}
+}
+
+// A frame's tiles one pixel each, as runs of the same role along each row
+// so that drawing takes a line per run rather than per tile. Drawn small in
+// the frame strip and large on the world map.
+pub(super) struct Thumbnail {
+	// The frame's revision when this was made.
+	revision: u64,
+	// Role, row, then the first and one past the last column.
+	pub runs: Vec<(Role, usize, usize, usize)>,
+}
+
+impl Thumbnail {
+	fn new(frame: &Frame) -> Self {
+		let mut runs = Vec::new();
+		for y in 0..FRAME_WIDTH {
+			let role_at = |x: usize| {
+				let tile = *frame.tile(x as isize, y as isize);
+				tile_role(tile).unwrap_or(Role::Background)
+			};
+			let mut start = 0;
+			for x in 1..=FRAME_WIDTH {
+				if x == FRAME_WIDTH || role_at(x) != role_at(start) {
+					runs.push((role_at(start), y, start, x));
+					start = x;
+				}
+			}
+		}
+		Self {
+			revision: frame.revision(),
+			runs,
+		}
+	}
+
+	// The frame's thumbnail in `thumbnails`, made again first if the frame
+	// has changed since.
+	pub fn updated<'a>(
+		thumbnails: &'a mut HashMap<FrameId, Thumbnail>,
+		frame: &Frame,
+	) -> &'a mut Thumbnail {
+		let thumbnail = thumbnails
+			.entry(frame.position)
+			.or_insert_with(|| Thumbnail::new(frame));
+		if thumbnail.revision != frame.revision() {
+			*thumbnail = Thumbnail::new(frame);
+		}
+		thumbnail
+	}
+}
+
+// A row of every frame along the bottom of the screen, for telling them
+// apart and turning the view to one without going there. Toggled with F.
+pub(super) struct FrameStrip {
+	pub visible: bool,
+	thumbnails: HashMap<FrameId, Thumbnail>,
+	// The frame picked from the strip, shown in place of the focus entity's
+	// until that entity moves onto another frame or the same thumbnail is
+	// picked again. Kept with the focus frame at the time it was picked.
+	view_frame: Option<(FrameId, FrameId)>,
+}
+
+impl FrameStrip {
+	pub fn new() -> Self {
+		Self {
+			visible: false,
+			thumbnails: HashMap::new(),
+			view_frame: None,
+		}
+	}
+
+	pub fn toggle(&mut self) {
+		self.visible = self.visible == false;
+		self.view_frame = None;
+	}
+
+	// The frame the view should be turned to, given the one the focus entity
+	// is on, and clearing the pick once the entity has left its frame.
+	pub fn view_frame(&mut self, world: &World, focus: FrameId) -> FrameId {
+		match self.view_frame {
+			Some((frame_id, picked_from))
+				if picked_from == focus
+					&& world.get_frame(frame_id).is_some() =>
+			{
+				frame_id
+			}
+			_ => {
+				self.view_frame = None;
+				focus
+			}
+		}
+	}
+}
+
+impl Window {
+	// Top left corners of each frame's thumbnail in UI coordinates, centred
+	// along the bottom of the viewport in frame id order.
+	fn thumbnail_positions(
+		&self,
+		world: &World,
+	) -> Vec<(FrameId, Scalar, Scalar)> {
+		if self.frame_strip.visible == false {
+			return Vec::new();
+		}
+		let frame_ids = world.frame_ids();
+		let count = frame_ids.len() as Scalar;
+		let width = count * THUMBNAIL_SIZE + (count - 1.0) * THUMBNAIL_GAP;
+		let (viewport_width, viewport_height) = self.ui_size();
+		let left = (viewport_width - width) / 2.0;
+		let top = viewport_height - STRIP_INSET - THUMBNAIL_SIZE;
+		frame_ids
+			.into_iter()
+			.enumerate()
+			.map(|(index, frame_id)| {
+				let x =
+					left + index as Scalar * (THUMBNAIL_SIZE + THUMBNAIL_GAP);
+				(frame_id, x, top)
+			})
+			.collect()
+	}
+
+	// How much of the bottom of the viewport the strip takes up, in UI
+	// coordinates, if any.
+	pub(super) fn frame_strip_height(&self) -> Scalar {
+		match self.frame_strip.visible {
+			true => STRIP_INSET + THUMBNAIL_SIZE,
+			false => 0.0,
+		}
+	}
+
+	// The frame whose thumbnail is under a position in viewport pixels.
+	pub(super) fn thumbnail_at(
+		&self,
+		world: &World,
+		position: (Scalar, Scalar),
+	) -> Option<FrameId> {
+		let (x, y) = self.ui().to_reference(position);
+		self.thumbnail_positions(world)
+			.into_iter()
+			.find(|&(_, left, top)| {
+				x >= left
+					&& x < left + THUMBNAIL_SIZE
+					&& y >= top && y < top + THUMBNAIL_SIZE
+			})
+			.map(|(frame_id, ..)| frame_id)
+	}
+
+	// Turns the view to the frame under a left click, or back if it was
+	// already turned there. Clicks of either button on the strip are used up
+	// so as not to also edit the tile behind it. Whether there was one.
+	pub(super) fn pick_thumbnail(&mut self, world: &World) -> bool {
+		let pressed = &self.input_state.mouse_buttons_pressed;
+		if pressed.is_empty() {
+			return false;
+		}
+		let frame_id = match self
+			.input_state
+			.mouse_position
+			.and_then(|position| self.thumbnail_at(world, position))
+		{
+			Some(frame_id) => frame_id,
+			None => return false,
+		};
+
+		if pressed.contains(&MouseButton::Left) {
+			let focus = world
+				.focus_entity
+				.and_then(|id| world.get_entity(id))
+				.map(|entity| entity.position.frame_id);
+			let strip = &mut self.frame_strip;
+			strip.view_frame = match (strip.view_frame, focus) {
+				(Some((viewed, _)), _) if viewed == frame_id => None,
+				(_, Some(focus)) => Some((frame_id, focus)),
+				(_, None) => None,
+			};
+		}
+		self.input_state.mouse_buttons_pressed.clear();
+		true
+	}
+
+	// Redraws only the thumbnails of frames changed since they were last
+	// drawn. `viewed` is outlined.
+	pub(super) fn draw_frame_strip(&mut self, world: &World, viewed: FrameId) {
+		let positions = self.thumbnail_positions(world);
+		if positions.is_empty() {
+			return;
+		}
+		self.frame_strip
+			.thumbnails
+			.retain(|&frame_id, _| world.get_frame(frame_id).is_some());
+
+		for (frame_id, left, top) in positions {
+			let frame = world.get_frame(frame_id).unwrap();
+			let thumbnail =
+				Thumbnail::updated(&mut self.frame_strip.thumbnails, frame);
+
+			// Moved out for the duration of the draw, which needs all of
+			// `self`, rather than copied.
+			let runs = std::mem::take(&mut thumbnail.runs);
+			for &(role, y, start, end) in &runs {
+				let color = self.palette.get(role);
+				let row_top = top + y as Scalar * THUMBNAIL_SCALE;
+				let row_bottom = row_top + THUMBNAIL_SCALE;
+				self.fill_ui_rect(
+					(left + start as Scalar * THUMBNAIL_SCALE, row_top),
+					(left + end as Scalar * THUMBNAIL_SCALE, row_bottom),
+					color,
+				);
+			}
+			self.frame_strip.thumbnails.get_mut(&frame_id).unwrap().runs = runs;
+
+			let role = if frame_id == viewed {
+				Role::Highlight
+			} else {
+				Role::FrameBorder
+			};
+			let (right, bottom) = (left + THUMBNAIL_SIZE, top + THUMBNAIL_SIZE);
+			self.draw_ui_lines(
+				&[
+					(left - 1.0, top - 1.0),
+					(right, top - 1.0),
+					(right, bottom),
+					(left - 1.0, bottom),
+					(left - 1.0, top - 1.0),
+				],
+				self.palette.get(role),
+			);
+		}
+	}
+}
@@ -0,0 +1,157 @@
+use super::backend;
+use crate::prelude::*;
+use crate::world::{World, TICK_RATE};
+
+// Frames in a row that must each fall more than `OVERLOAD_STEPS` steps behind
+// before the world drops to a lower tick rate.
+const OVERLOAD_FRAMES: usize = 30;
+const OVERLOAD_STEPS: usize = 4;
+// Seconds of keeping up, at most one step per frame, before trying the full
+// rate again. Doubled each time the full rate turns out to be too much.
+const RECOVERY_SECONDS: f64 = 5.0;
+const MAX_RECOVERY_SECONDS: f64 = 80.0;
+// Ticks each world step covers at the lower rate.
+const REDUCED_TICKS_PER_STEP: u32 = 2;
+
+// Halves the world's tick rate when ticks take so long that frames fall
+// steadily behind, and restores it once they have kept up for a while.
+pub struct TickRate {
+	ticks_per_step: u32,
+	overloaded_frames: usize,
+	// When steps started keeping up at the lower rate, from
+	// `backend::time::now`.
+	calm_since: Option<f64>,
+	recovery_seconds: f64,
+	// Set when the full rate was restored, until it has lasted a recovery
+	// period. Dropping again before then doubles the next recovery period.
+	restored_at: Option<f64>,
+}
+
+impl TickRate {
+	pub fn new() -> Self {
+		Self {
+			ticks_per_step: 1,
+			overloaded_frames: 0,
+			calm_since: None,
+			recovery_seconds: RECOVERY_SECONDS,
+			restored_at: None,
+		}
+	}
+
+	// Seconds of real time each world step covers.
+	pub fn step_duration(&self) -> f64 {
+		self.ticks_per_step as f64 / TICK_RATE
+	}
+
+	// Ticks per second the world actually runs at.
+	pub fn rate(&self) -> f64 {
+		TICK_RATE / self.ticks_per_step as f64
+	}
+
+	// Makes the world use the current rate, which a newly loaded world
+	// won't have.
+	pub fn apply(&self, world: &mut World) {
+		if world.ticks_per_step() != self.ticks_per_step {
+			world.set_ticks_per_step(self.ticks_per_step);
+		}
+	}
+
+	// Called each frame with how many steps were due when it started.
+	pub fn record_frame(&mut self, steps_due: usize) {
+		let now = backend::time::now();
+
+		if steps_due > OVERLOAD_STEPS {
+			self.overloaded_frames += 1;
+		} else {
+			self.overloaded_frames = 0;
+		}
+
+		if self.ticks_per_step == 1 {
+			if let Some(restored_at) = self.restored_at {
+				if now - restored_at >= self.recovery_seconds {
+					self.restored_at = None;
+					self.recovery_seconds = RECOVERY_SECONDS;
+				}
+			}
+			if self.overloaded_frames >= OVERLOAD_FRAMES {
+				if self.restored_at.take().is_some() {
+					self.recovery_seconds =
+						(self.recovery_seconds * 2.0).min(MAX_RECOVERY_SECONDS);
+				}
+				self.set_ticks_per_step(REDUCED_TICKS_PER_STEP);
+			}
+			return;
+		}
+
+		if steps_due > 1 {
+			self.calm_since = None;
+			return;
+		}
+		let calm_since = *self.calm_since.get_or_insert(now);
+		if now - calm_since >= self.recovery_seconds {
+			self.set_ticks_per_step(1);
+			self.restored_at = Some(now);
+		}
+	}
+
+	fn set_ticks_per_step(&mut self, ticks: u32) {
+		self.ticks_per_step = ticks;
+		self.overloaded_frames = 0;
+		self.calm_since = None;
+		log(format!("Tick rate: {:.0} Hz", self.rate()));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn overload(tick_rate: &mut TickRate, frames: usize) {
+		for _ in 0..frames {
+			tick_rate.record_frame(OVERLOAD_STEPS + 1);
+		}
+	}
+
+	#[test]
+	fn falling_behind_for_long_enough_halves_the_rate() {
+		let mut tick_rate = TickRate::new();
+		overload(&mut tick_rate, OVERLOAD_FRAMES - 1);
+		assert_eq!(tick_rate.rate(), TICK_RATE);
+		overload(&mut tick_rate, 1);
+		assert_eq!(tick_rate.rate(), TICK_RATE / 2.0);
+		assert_eq!(tick_rate.step_duration(), 2.0 / TICK_RATE);
+
+		let mut world = World::new();
+		tick_rate.apply(&mut world);
+		assert_eq!(world.ticks_per_step(), REDUCED_TICKS_PER_STEP);
+	}
+
+	#[test]
+	fn a_frame_that_keeps_up_starts_the_count_again() {
+		let mut tick_rate = TickRate::new();
+		overload(&mut tick_rate, OVERLOAD_FRAMES - 1);
+		tick_rate.record_frame(OVERLOAD_STEPS);
+		overload(&mut tick_rate, OVERLOAD_FRAMES - 1);
+		assert_eq!(tick_rate.rate(), TICK_RATE);
+	}
+
+	// Keeping up for the recovery period restores the full rate, and having
+	// to drop again straight after makes the next period twice as long.
+	#[test]
+	fn keeping_up_for_a_while_restores_the_rate() {
+		let mut tick_rate = TickRate::new();
+		overload(&mut tick_rate, OVERLOAD_FRAMES);
+		tick_rate.record_frame(1);
+		assert_eq!(tick_rate.rate(), TICK_RATE / 2.0);
+		tick_rate.record_frame(2);
+		assert_eq!(tick_rate.calm_since, None);
+
+		tick_rate.calm_since = Some(backend::time::now() - RECOVERY_SECONDS);
+		tick_rate.record_frame(1);
+		assert_eq!(tick_rate.rate(), TICK_RATE);
+
+		overload(&mut tick_rate, OVERLOAD_FRAMES);
+		assert_eq!(tick_rate.rate(), TICK_RATE / 2.0);
+		assert_eq!(tick_rate.recovery_seconds, RECOVERY_SECONDS * 2.0);
+	}
+}
@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::Role;
+use crate::geometry::{vec3, Scalar, Vector3};
+use crate::world::{Frame, FrameId, Tile, FRAME_WIDTH};
+
+// Whether a tile is drawn flat as its outline, which looks the same every
+// frame.
+fn is_outlined(tile: Tile) -> bool {
+	match tile {
+		Tile::Solid | Tile::ConveyorLeft | Tile::ConveyorRight => true,
+		_ => false,
+	}
+}
+
+// Whether a tile is drawn on its own every frame, being animated, turned or
+// drawn differently for each kind.
+fn is_special(tile: Tile) -> bool {
+	match tile {
+		Tile::Sign
+		| Tile::PressurePlate
+		| Tile::Spring
+		| Tile::Sand
+		| Tile::Water
+		| Tile::SlopeUpRight
+		| Tile::SlopeUpLeft => true,
+		_ => false,
+	}
+}
+
+// A corner of the tile grid in the frame's own space, on its surface.
+fn grid_point(x: usize, y: usize) -> Vector3 {
+	let f = 2.0 / FRAME_WIDTH as Scalar;
+	vec3(x as Scalar * f - 1.0, y as Scalar * f - 1.0, 1.0)
+}
+
+// The parts of a frame's tiles that only change along with the tiles, made
+// once in the frame's own space so that drawing them each frame is just
+// transforming their points.
+pub(super) struct TileGeometry {
+	// The frame's revision when this was made.
+	revision: u64,
+	// What each side of a tile was split into, as the globe had it.
+	pieces: usize,
+	// Outline segments of the flat tiles, by the role they are drawn in. An
+	// edge two outlined tiles share is only in here once.
+	pub outlines: Vec<(Role, Vec<(Vector3, Vector3)>)>,
+	// Tiles drawn on their own each frame, a row at a time from the top.
+	pub special: Vec<(usize, usize)>,
+}
+
+impl TileGeometry {
+	fn new(frame: &Frame, pieces: usize) -> Self {
+		let outlined = |x: usize, y: usize| {
+			x < FRAME_WIDTH
+				&& y < FRAME_WIDTH
+				&& is_outlined(*frame.tile(x as isize, y as isize))
+		};
+
+		let mut segments = Vec::new();
+		let mut special = Vec::new();
+		for y in 0..FRAME_WIDTH {
+			for x in 0..FRAME_WIDTH {
+				if is_special(*frame.tile(x as isize, y as isize)) {
+					special.push((x, y));
+				}
+				if outlined(x, y) == false {
+					continue;
+				}
+				// The edges below and to the right belong to the tile
+				// beyond them when it is outlined too.
+				segments.push((grid_point(x, y), grid_point(x + 1, y)));
+				segments.push((grid_point(x, y), grid_point(x, y + 1)));
+				if outlined(x, y + 1) == false {
+					let start = grid_point(x, y + 1);
+					segments.push((start, grid_point(x + 1, y + 1)));
+				}
+				if outlined(x + 1, y) == false {
+					let start = grid_point(x + 1, y);
+					segments.push((start, grid_point(x + 1, y + 1)));
+				}
+			}
+		}
+
+		let segments: Vec<_> = segments
+			.into_iter()
+			.flat_map(|(start, end)| {
+				(0..pieces).map(move |piece| {
+					let along =
+						|piece: usize| piece as Scalar / pieces as Scalar;
+					(
+						start.mix(end, along(piece)),
+						start.mix(end, along(piece + 1)),
+					)
+				})
+			})
+			.collect();
+		let outlines = match segments.is_empty() {
+			true => Vec::new(),
+			false => vec![(Role::TileSolid, segments)],
+		};
+		Self {
+			revision: frame.revision(),
+			pieces,
+			outlines,
+			special,
+		}
+	}
+
+	// The frame's geometry in `cache`, with each side of a tile in `pieces`,
+	// made again first if the frame or the pieces have changed since.
+	pub fn updated(
+		cache: &mut HashMap<FrameId, Rc<TileGeometry>>,
+		frame: &Frame,
+		pieces: usize,
+	) -> Rc<TileGeometry> {
+		let geometry = cache
+			.entry(frame.position)
+			.or_insert_with(|| Rc::new(TileGeometry::new(frame, pieces)));
+		let stale = geometry.revision != frame.revision();
+		if stale || geometry.pieces != pieces {
+			*geometry = Rc::new(TileGeometry::new(frame, pieces));
+		}
+		Rc::clone(geometry)
+	}
+}
@@ -0,0 +1,103 @@
+use super::{font, sprites, CameraProjector, Role, Window, TEXT_SCALE};
+use crate::campaign::format_ticks;
+use crate::geometry::{Matrix4x4, Scalar};
+use crate::ghost::TimeTrial;
+use crate::world::{Direction, FrameId, World, TICK_RATE};
+use crate::GameState;
+
+// Distance of the timer from the top of the viewport.
+const TIMER_INSET: Scalar = 8.0;
+// How far the ghost is mixed towards the background, since there's no
+// alpha blending to draw it translucent.
+const GHOST_FADE: Scalar = 0.6;
+
+impl Window {
+	// The best run so far, drawn where it was on this tick as a faded
+	// player.
+	pub(super) fn draw_ghost(
+		&mut self,
+		projector: &CameraProjector,
+		world: &World,
+		time_trial: &TimeTrial,
+		surface_transforms: &[(FrameId, Matrix4x4)],
+		view_rotation: Matrix4x4,
+	) {
+		let ghost = time_trial.ghost.as_ref();
+		let k = match ghost.and_then(|ghost| ghost.at(world.tick_count)) {
+			Some(keyframe) => keyframe,
+			None => return,
+		};
+
+		// The same size and way up as the player.
+		let down = if k.gravity == Direction::Up {
+			-1.0
+		} else {
+			1.0
+		};
+		let d = 0.01;
+		let point = |x: Scalar, y: Scalar| (k.x + x * d, k.y + y * d * down);
+		let shapes = sprites::pose_shapes(sprites::PLAYER.idle[0], point);
+		let color = self
+			.palette
+			.get(Role::Player)
+			.mix(self.palette.get(Role::Background), GHOST_FADE);
+		for shape in shapes {
+			self.draw_surface_lines(
+				projector,
+				world,
+				surface_transforms,
+				k.frame,
+				&shape,
+				view_rotation,
+				color,
+				false,
+			);
+		}
+	}
+
+	// The time so far and the best time, centred along the top, with how far
+	// ahead of or behind the ghost the run is below them.
+	pub(super) fn draw_time_trial(&mut self, game_state: &GameState) {
+		let time_trial = match &game_state.time_trial {
+			Some(time_trial) if self.hud.visible => time_trial,
+			_ => return,
+		};
+		let ticks = game_state
+			.completion
+			.as_ref()
+			.map_or(game_state.world.tick_count, |completion| completion.ticks);
+		let best = game_state
+			.level_path
+			.as_ref()
+			.and_then(|path| self.settings.best_times.get(path));
+		let text = match best {
+			Some(&best) => {
+				format!("{}  best {}", format_ticks(ticks), format_ticks(best))
+			}
+			None => format_ticks(ticks),
+		};
+
+		let pixel = TEXT_SCALE as Scalar;
+		let line_height = (font::LINE_ADVANCE * TEXT_SCALE) as Scalar;
+		let center = self.ui_size().0 / 2.0;
+		let left_of = |text: &str| {
+			center - font::text_width(text) as Scalar * pixel / 2.0
+		};
+		let color = self.palette.get(Role::UiText);
+		self.draw_text(&text, left_of(&text), TIMER_INSET, TEXT_SCALE, color);
+
+		let delta = match time_trial.delta(ticks) {
+			Some(delta) => delta,
+			None => return,
+		};
+		let (sign, role) = if delta > 0 {
+			('+', Role::UiWarning)
+		} else {
+			('-', Role::UiText)
+		};
+		let text = format!("{}{:.2}s", sign, delta.abs() as f64 / TICK_RATE);
+		let y = TIMER_INSET + line_height;
+		let color = self.palette.get(role);
+		self.draw_text(&text, left_of(&text), y, TEXT_SCALE, color);
+	}
+}
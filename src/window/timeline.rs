@@ -0,0 +1,175 @@
+use super::notifications::{Severity, NOTIFY_TICKS};
+use super::{Keycode, MouseButton, Role, Window, TEXT_SCALE};
+use crate::geometry::Scalar;
+use crate::GameState;
+
+// The bar along the bottom of the screen with a tick for every edit made
+// in the editor. Dragging along it, or PgUp and PgDn, goes back and forth
+// through them, and a new edit from back there drops the ones after.
+const TIMELINE_WIDTH: Scalar = 360.0;
+const TIMELINE_HEIGHT: Scalar = 10.0;
+// Gap between the bar and the bottom of the viewport, or the frame strip
+// when that is showing.
+const TIMELINE_INSET: Scalar = 12.0;
+// Edits PgUp and PgDn step over with Shift held, rather than one.
+const TIMELINE_PAGE: usize = 10;
+
+// How many edits in effect the bar has at `x` in UI coordinates, when its
+// left end is at `left`, from none there to all of them at the right end.
+fn cursor_at(left: Scalar, x: Scalar, len: usize) -> usize {
+	let along = ((x - left) / TIMELINE_WIDTH).clamp(0.0, 1.0);
+	(along * len as Scalar).round() as usize
+}
+
+impl Window {
+	// Left and top of the bar in UI coordinates, if there is anything to
+	// show on it.
+	fn timeline_position(
+		&self,
+		game_state: &GameState,
+	) -> Option<(Scalar, Scalar)> {
+		if game_state.edit_history.is_empty() {
+			return None;
+		}
+		let (viewport_width, viewport_height) = self.ui_size();
+		let bottom = viewport_height - self.frame_strip_height();
+		let top = bottom - TIMELINE_INSET - TIMELINE_HEIGHT;
+		Some(((viewport_width - TIMELINE_WIDTH) / 2.0, top))
+	}
+
+	// Drags along the bar from a left click on it, and uses up any click on
+	// it so as not to also edit the tile behind. Whether the pointer is the
+	// bar's.
+	pub(super) fn scrub_timeline(
+		&mut self,
+		game_state: &mut GameState,
+	) -> bool {
+		let (left, top) = match self.timeline_position(game_state) {
+			Some(position) => position,
+			None => {
+				self.timeline_drag = false;
+				return false;
+			}
+		};
+		let pointer = match self.input_state.mouse_position {
+			Some(position) => self.ui().to_reference(position),
+			None => return self.timeline_drag,
+		};
+		let on_bar = pointer.0 >= left
+			&& pointer.0 < left + TIMELINE_WIDTH
+			&& pointer.1 >= top
+			&& pointer.1 < top + TIMELINE_HEIGHT;
+		let pressed = &self.input_state.mouse_buttons_pressed;
+		if self.stroke.is_none() && on_bar && pressed.is_empty() == false {
+			self.timeline_drag = pressed.contains(&MouseButton::Left);
+			self.input_state.mouse_buttons_pressed.clear();
+			if self.timeline_drag == false {
+				return true;
+			}
+		}
+		if self.timeline_drag == false {
+			return false;
+		}
+
+		let len = game_state.edit_history.len();
+		let cursor = cursor_at(left, pointer.0, len);
+		self.seek_timeline(game_state, cursor);
+		let held = &self.input_state.mouse_buttons_held;
+		self.timeline_drag = held.contains(&MouseButton::Left);
+		true
+	}
+
+	// One edit back or forward, or a page of them with Shift held.
+	pub(super) fn step_timeline(
+		&mut self,
+		game_state: &mut GameState,
+		back: bool,
+	) {
+		// A drag still being painted is one edit, not yet over.
+		if self.stroke.is_some() {
+			return;
+		}
+		let held = &self.input_state.keys_held;
+		let step = match held.contains(&Keycode::Shift) {
+			true => TIMELINE_PAGE,
+			false => 1,
+		};
+		let cursor = game_state.edit_history.cursor();
+		let cursor = match back {
+			true => cursor.saturating_sub(step),
+			false => cursor + step,
+		};
+		self.seek_timeline(game_state, cursor);
+	}
+
+	fn seek_timeline(&mut self, game_state: &mut GameState, cursor: usize) {
+		let history = &mut game_state.edit_history;
+		if cursor == history.cursor() && history.is_editing() == false {
+			return;
+		}
+		let dropped = history.seek(&mut game_state.world, cursor);
+		self.notify_dropped_edits(dropped);
+	}
+
+	// Ends the edit painted since the last, telling of any that were
+	// dropped for it.
+	pub(super) fn finish_edit(&mut self, game_state: &mut GameState) {
+		let dropped = game_state.edit_history.finish();
+		self.notify_dropped_edits(dropped);
+	}
+
+	fn notify_dropped_edits(&mut self, dropped: usize) {
+		if dropped == 0 {
+			return;
+		}
+		let plural = if dropped == 1 { "" } else { "s" };
+		self.notifications.notify(
+			format!(
+				"Dropped {} undone edit{} to go on from here",
+				dropped, plural
+			),
+			NOTIFY_TICKS,
+			Severity::Info,
+		);
+	}
+
+	pub(super) fn draw_timeline(&mut self, game_state: &GameState) {
+		let (left, top) = match self.timeline_position(game_state) {
+			Some(position) => position,
+			None => return,
+		};
+		let history = &game_state.edit_history;
+		let (len, cursor) = (history.len(), history.cursor());
+		let (right, bottom) = (left + TIMELINE_WIDTH, top + TIMELINE_HEIGHT);
+		let middle = (top + bottom) / 2.0;
+		let border = self.palette.get(Role::FrameBorder);
+		self.draw_ui_lines(&[(left, middle), (right, middle)], border);
+
+		// Edits undone are dimmer than those in effect.
+		let background = self.palette.get(Role::Background);
+		let x_at = |edits: usize| {
+			left + TIMELINE_WIDTH * edits as Scalar / len as Scalar
+		};
+		for edit in 1..=len {
+			let color = match edit <= cursor {
+				true => border,
+				false => border.mix(background, 0.6),
+			};
+			let x = x_at(edit);
+			self.draw_ui_lines(&[(x, top + 2.0), (x, bottom - 2.0)], color);
+		}
+		let x = x_at(cursor);
+		let highlight = self.palette.get(Role::Highlight);
+		self.fill_ui_rect((x - 1.0, top), (x + 2.0, bottom), highlight);
+
+		let text = format!("{}/{}", cursor, len);
+		let color = self.palette.get(Role::UiText);
+		self.draw_text(
+			&text,
+			right + TIMELINE_INSET / 2.0,
+			top,
+			TEXT_SCALE,
+			color,
+		);
+	}
+}
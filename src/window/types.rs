@@ -1,19 +1,99 @@
+use std::path::PathBuf;
+
+use crate::geometry::Scalar;
+
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
 pub enum Keycode {
 	A,
+	C,
 	D,
 	E,
+	F,
+	G,
+	H,
+	M,
+	P,
 	Q,
+	R,
 	S,
+	T,
 	W,
 	Escape,
+	F3,
+	F9,
+	Backquote,
+	Backspace,
+	Tab,
+	Delete,
+	Return,
+	Space,
+	Up,
+	Down,
+	Left,
+	Right,
+	PageUp,
+	PageDown,
+	// Either shift key.
+	Shift,
+
+	Unknown,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+pub enum MouseButton {
+	Left,
+	Middle,
+	Right,
 
 	Unknown,
 }
 
+// Buttons of a gamepad with the usual layout, named for what they do in
+// menus rather than what is printed on them.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+pub enum GamepadButton {
+	Up,
+	Down,
+	Left,
+	Right,
+	// The bottom face button, A on most pads.
+	Confirm,
+	// The right face button.
+	Cancel,
+	Start,
+
+	Unknown,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+pub enum GamepadAxis {
+	LeftX,
+	LeftY,
+}
+
 pub enum WindowEvent {
 	KeyDown(Keycode),
 	KeyUp(Keycode),
+	// Every key the platform says is held right now, for putting right any
+	// presses or releases that never arrived as their own events.
+	KeysHeld(Vec<Keycode>),
+	// Pointer position in viewport pixels.
+	MouseMove(f32, f32),
+	MouseDown(MouseButton),
+	MouseUp(MouseButton),
+	// Notches scrolled, with positive values away from the user.
+	MouseWheel(f32),
+	GamepadDown(GamepadButton),
+	GamepadUp(GamepadButton),
+	// From -1 to 1, with positive values right and down.
+	GamepadAxisMotion(GamepadAxis, f32),
+	// Text typed by the user, after keyboard layout and modifiers have been
+	// applied.
+	TextInput(String),
+	// A file dropped onto the window. Browsers give the contents rather than
+	// a path.
+	FileDropped(PathBuf),
+	FileContentsDropped(Vec<u8>),
 	Quit,
 }
 
@@ -24,22 +104,164 @@ pub struct Color {
 	pub b: u8,
 }
 
-macro_rules! const_color {
-	($name:ident, $r:expr, $g:expr, $b:expr) => {
-		pub fn $name() -> Self {
-			Self::rgb($r, $g, $b)
-		}
-	};
-}
-
 impl Color {
 	pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
 		Self { r, g, b }
 	}
 
+	pub const RED: Self = Self::rgb(255, 0, 0);
+	pub const GREEN: Self = Self::rgb(0, 255, 0);
 	pub const BLUE: Self = Self::rgb(0, 0, 255);
+	pub const YELLOW: Self = Self::rgb(255, 255, 0);
 	pub const CYAN: Self = Self::rgb(0, 255, 255);
 	pub const GRAY: Self = Self::rgb(128, 128, 128);
 	pub const BLACK: Self = Self::rgb(0, 0, 0);
 	pub const WHITE: Self = Self::rgb(255, 255, 255);
+
+	// Linear blend from `self` at 0 to `other` at 1.
+	pub fn mix(&self, other: Color, amount: Scalar) -> Self {
+		let amount = amount.clamp(0.0, 1.0);
+		let channel = |a: u8, b: u8| {
+			(a as Scalar + (b as Scalar - a as Scalar) * amount).round() as u8
+		};
+		Self::rgb(
+			channel(self.r, other.r),
+			channel(self.g, other.g),
+			channel(self.b, other.b),
+		)
+	}
+}
+
+// What the backend window is created with. Headless and web backends have
+// no window of their own to give a title or vsync.
+#[derive(Clone, Debug)]
+#[cfg_attr(any(feature = "headless", target_arch = "wasm32"), allow(dead_code))]
+pub struct WindowConfig {
+	pub title: String,
+	pub width: u32,
+	pub height: u32,
+	// Wait for the display's vertical blank when presenting. Ignored on the
+	// web, where frames are always paced by the browser.
+	pub vsync: bool,
+}
+
+impl Default for WindowConfig {
+	fn default() -> Self {
+		Self {
+			title: String::from("cube"),
+			width: 900,
+			height: 700,
+			vsync: true,
+		}
+	}
+}
+
+// Why the backend couldn't start, with what the platform had to say about
+// it.
+#[derive(Clone, Debug)]
+pub enum BackendError {
+	// The platform layer itself, such as SDL, wouldn't start.
+	Init(String),
+	// There is no display to show anything on, or no driver for it.
+	Video(String),
+	Window(String),
+	// The window opened but nothing could be drawn to it.
+	Renderer(String),
+}
+
+impl BackendError {
+	// What the player might do about it.
+	pub fn hint(&self) -> &'static str {
+		match self {
+			BackendError::Init(_) | BackendError::Video(_) => {
+				"Check that there is a display to open a window on. Machines \
+				 without one can set SDL_VIDEODRIVER=dummy, or run the \
+				 simulation alone with --headless-ticks N."
+			}
+			BackendError::Window(_) => {
+				"The display refused a window. Check that its driver is \
+				 installed and working."
+			}
+			BackendError::Renderer(_) => {
+				"No renderer could be made for the graphics driver. Setting \
+				 SDL_RENDER_DRIVER=software draws without it."
+			}
+		}
+	}
+
+	// What the process exits with, different for each so that scripts can
+	// tell them apart, and none of them the 1 a failed check exits with.
+	pub fn exit_code(&self) -> i32 {
+		match self {
+			BackendError::Init(_) => 2,
+			BackendError::Video(_) => 3,
+			BackendError::Window(_) => 4,
+			BackendError::Renderer(_) => 5,
+		}
+	}
+}
+
+impl std::fmt::Display for BackendError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			BackendError::Init(message) => {
+				write!(f, "could not start: {}", message)
+			}
+			BackendError::Video(message) => {
+				write!(f, "could not start video: {}", message)
+			}
+			BackendError::Window(message) => {
+				write!(f, "could not open a window: {}", message)
+			}
+			BackendError::Renderer(message) => {
+				write!(f, "could not start drawing: {}", message)
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn every_error() -> Vec<BackendError> {
+		let message = || String::from("no driver");
+		vec![
+			BackendError::Init(message()),
+			BackendError::Video(message()),
+			BackendError::Window(message()),
+			BackendError::Renderer(message()),
+		]
+	}
+
+	#[test]
+	fn each_startup_error_reads_as_what_the_game_could_not_do() {
+		let read: Vec<_> = every_error()
+			.iter()
+			.map(|error| format!("The game {}", error))
+			.collect();
+		assert_eq!(
+			read,
+			vec![
+				"The game could not start: no driver",
+				"The game could not start video: no driver",
+				"The game could not open a window: no driver",
+				"The game could not start drawing: no driver",
+			]
+		);
+	}
+
+	#[test]
+	fn each_startup_error_exits_with_its_own_failing_code() {
+		let codes: Vec<_> =
+			every_error().iter().map(BackendError::exit_code).collect();
+		assert_eq!(codes, vec![2, 3, 4, 5]);
+	}
+
+	#[test]
+	fn without_a_display_the_hint_offers_running_headless() {
+		for error in every_error().iter().take(2) {
+			assert!(error.hint().contains("--headless-ticks"), "{:?}", error);
+		}
+	}
 }
@@ -0,0 +1,88 @@
+use super::{Color, Window};
+use crate::geometry::Scalar;
+use crate::settings::{MAX_UI_SCALE, MIN_UI_SCALE};
+
+// Everything drawn in screen space is laid out for a viewport this tall,
+// whatever the real one is, then scaled to fit it.
+const REFERENCE_HEIGHT: Scalar = 700.0;
+
+// Maps UI coordinates in the reference space to viewport pixels. Both axes
+// are scaled the same, so squares and circles keep their shape whatever
+// the viewport's aspect ratio.
+#[derive(Copy, Clone, Debug)]
+pub(super) struct UiTransform {
+	pub scale: Scalar,
+}
+
+impl UiTransform {
+	// From the viewport height, unless the settings give a scale of their
+	// own.
+	pub fn new(viewport_height: Scalar, scale: Option<Scalar>) -> Self {
+		let scale = scale.unwrap_or(viewport_height / REFERENCE_HEIGHT);
+		Self {
+			scale: scale.clamp(MIN_UI_SCALE, MAX_UI_SCALE),
+		}
+	}
+
+	pub fn to_screen(self, (x, y): (Scalar, Scalar)) -> (Scalar, Scalar) {
+		(x * self.scale, y * self.scale)
+	}
+
+	// For hit testing the pointer against what was drawn.
+	pub fn to_reference(self, (x, y): (Scalar, Scalar)) -> (Scalar, Scalar) {
+		(x / self.scale, y / self.scale)
+	}
+}
+
+impl Window {
+	// Worked out afresh from the viewport every time, so that resizing the
+	// window rescales the UI in the same frame.
+	pub(super) fn ui(&self) -> UiTransform {
+		let height = self.backend.viewport_height() as Scalar;
+		UiTransform::new(height, self.settings.ui_scale)
+	}
+
+	// The viewport's size in reference coordinates, for laying the UI out
+	// against its edges.
+	pub(super) fn ui_size(&self) -> (Scalar, Scalar) {
+		let width = self.backend.viewport_width() as Scalar;
+		let height = self.backend.viewport_height() as Scalar;
+		self.ui().to_reference((width, height))
+	}
+
+	// A polyline in reference coordinates.
+	pub(super) fn draw_ui_lines(
+		&mut self,
+		points: &[(Scalar, Scalar)],
+		color: Color,
+	) {
+		let ui = self.ui();
+		let points: Vec<(Scalar, Scalar)> =
+			points.iter().map(|&point| ui.to_screen(point)).collect();
+		self.draw_screen_lines(&points, color, false);
+	}
+
+	// Fills the box from `left`, `top` up to but not including `right`,
+	// `bottom`, in reference coordinates, with one line per pixel row since
+	// nothing can be filled. Edges are rounded to whole pixels so that
+	// neighbouring boxes meet without gaps or overlaps.
+	pub(super) fn fill_ui_rect(
+		&mut self,
+		(left, top): (Scalar, Scalar),
+		(right, bottom): (Scalar, Scalar),
+		color: Color,
+	) {
+		let ui = self.ui();
+		let (left, top) = ui.to_screen((left, top));
+		let (right, bottom) = ui.to_screen((right, bottom));
+		let (left, right) = (left.round(), right.round() - 1.0);
+		if right < left {
+			return;
+		}
+		let mut y = top.round();
+		while y < bottom.round() {
+			self.draw_screen_lines(&[(left, y), (right, y)], color, false);
+			y += 1.0;
+		}
+	}
+}
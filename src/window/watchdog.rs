@@ -0,0 +1,296 @@
+use std::collections::VecDeque;
+
+use super::backend;
+use crate::prelude::*;
+use crate::world::World;
+
+// Frames taking longer than this many seconds are logged.
+pub const SLOW_FRAME_SECONDS: f64 = 0.05;
+// Seconds after logging a slow frame before another is logged, so that a
+// run of them doesn't flood the log.
+const LOG_INTERVAL: f64 = 1.0;
+// Kinds of the latest world events, shown with a slow frame for a hint of
+// what the world was doing.
+const RECENT_EVENTS: usize = 8;
+// Slow frames kept for `stats`.
+#[cfg(feature = "profiling")]
+const WORST_FRAMES: usize = 10;
+
+// A part of a frame timed on its own.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Phase {
+	// Input, and anything picked or edited with the pointer.
+	Events,
+	// World ticks, along with everything run after each.
+	Ticks,
+	Autosave,
+	Render,
+}
+
+impl Phase {
+	fn name(&self) -> &'static str {
+		match self {
+			Phase::Events => "events",
+			Phase::Ticks => "ticks",
+			Phase::Autosave => "autosave",
+			Phase::Render => "render",
+		}
+	}
+}
+
+// Where the time went in one frame.
+#[derive(Clone, Debug)]
+pub struct FrameRecord {
+	// The world's tick count at the end of the frame.
+	pub tick: u64,
+	pub seconds: f64,
+	// Seconds spent in each phase, in the order they ran.
+	pub phases: Vec<(Phase, f64)>,
+	pub entities: usize,
+	// World ticks run during the frame.
+	pub ticks: usize,
+	// Kinds of the latest world events, oldest first.
+	pub events: Vec<&'static str>,
+}
+
+impl FrameRecord {
+	pub fn summary(&self) -> String {
+		let phases: Vec<String> = self
+			.phases
+			.iter()
+			.map(|(phase, seconds)| {
+				format!("{} {:.1} ms", phase.name(), seconds * 1000.0)
+			})
+			.collect();
+		let events = match self.events.is_empty() {
+			true => "none".to_string(),
+			false => self.events.join(", "),
+		};
+		format!(
+			"{:.1} ms at tick {} ({}), {} world ticks, {} entities, \
+			 recent events: {}",
+			self.seconds * 1000.0,
+			self.tick,
+			phases.join(", "),
+			self.ticks,
+			self.entities,
+			events
+		)
+	}
+}
+
+// Times each frame a phase at a time, and logs what it was doing when one
+// takes longer than the threshold.
+pub struct Watchdog {
+	threshold: f64,
+	// From `backend::time::now`, while a frame is being measured.
+	frame_start: Option<f64>,
+	phase_start: f64,
+	phases: Vec<(Phase, f64)>,
+	ticks: usize,
+	recent_events: VecDeque<&'static str>,
+	last_logged: Option<f64>,
+	// Slow frames since the last one logged.
+	unlogged: usize,
+	#[cfg(feature = "profiling")]
+	worst: Vec<FrameRecord>,
+}
+
+impl Watchdog {
+	pub fn new(threshold: f64) -> Self {
+		Self {
+			threshold,
+			frame_start: None,
+			phase_start: 0.0,
+			phases: Vec::new(),
+			ticks: 0,
+			recent_events: VecDeque::new(),
+			last_logged: None,
+			unlogged: 0,
+			#[cfg(feature = "profiling")]
+			worst: Vec::new(),
+		}
+	}
+
+	pub fn begin_frame(&mut self) {
+		let now = backend::time::now();
+		self.frame_start = Some(now);
+		self.phase_start = now;
+		self.phases.clear();
+		self.ticks = 0;
+	}
+
+	// Puts the time since the last phase ended towards `phase`.
+	pub fn end_phase(&mut self, phase: Phase) {
+		if self.frame_start.is_none() {
+			return;
+		}
+		let now = backend::time::now();
+		let seconds = now - self.phase_start;
+		self.phase_start = now;
+		match self.phases.iter_mut().find(|(other, _)| *other == phase) {
+			Some((_, total)) => *total += seconds,
+			None => self.phases.push((phase, seconds)),
+		}
+	}
+
+	// Called after each world tick.
+	pub fn record_tick(&mut self, world: &World) {
+		self.ticks += 1;
+		for event in world.events() {
+			self.recent_events.push_back(event.kind().name());
+		}
+		while self.recent_events.len() > RECENT_EVENTS {
+			self.recent_events.pop_front();
+		}
+	}
+
+	// Finishes measuring the frame, returning what it did if it was slow.
+	pub fn end_frame(&mut self, world: &World) -> Option<FrameRecord> {
+		let start = self.frame_start.take()?;
+		let now = backend::time::now();
+		let seconds = now - start;
+		if seconds <= self.threshold {
+			return None;
+		}
+
+		let record = FrameRecord {
+			tick: world.tick_count,
+			seconds,
+			phases: self.phases.clone(),
+			entities: world.entity_count(),
+			ticks: self.ticks,
+			events: self.recent_events.iter().copied().collect(),
+		};
+		let due = match self.last_logged {
+			Some(last) => now - last >= LOG_INTERVAL,
+			None => true,
+		};
+		if due {
+			let suppressed = match self.unlogged {
+				0 => String::new(),
+				count => format!(" ({} more since the last)", count),
+			};
+			log(format!("Slow frame{}: {}", suppressed, record.summary()));
+			self.last_logged = Some(now);
+			self.unlogged = 0;
+		} else {
+			self.unlogged += 1;
+		}
+		#[cfg(feature = "profiling")]
+		self.keep_if_worst(&record);
+		Some(record)
+	}
+
+	#[cfg(feature = "profiling")]
+	fn keep_if_worst(&mut self, record: &FrameRecord) {
+		self.worst.push(record.clone());
+		self.worst
+			.sort_by(|a, b| b.seconds.partial_cmp(&a.seconds).unwrap());
+		self.worst.truncate(WORST_FRAMES);
+	}
+
+	// The slowest frames of the session, slowest first.
+	#[cfg(feature = "profiling")]
+	pub fn worst_frames(&self) -> &[FrameRecord] {
+		&self.worst
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::world::Actions;
+
+	// A frame that ran its ticks for `seconds`, as if it had been stuck in
+	// them that long.
+	fn frame(
+		watchdog: &mut Watchdog,
+		world: &World,
+		seconds: f64,
+	) -> Option<FrameRecord> {
+		watchdog.begin_frame();
+		watchdog.end_phase(Phase::Events);
+		let backdated = watchdog.frame_start.unwrap() - seconds;
+		watchdog.frame_start = Some(backdated);
+		watchdog.phase_start -= seconds;
+		watchdog.record_tick(world);
+		watchdog.record_tick(world);
+		watchdog.end_phase(Phase::Ticks);
+		watchdog.end_phase(Phase::Render);
+		watchdog.end_frame(world)
+	}
+
+	#[test]
+	fn only_frames_over_the_threshold_are_reported() {
+		let world = World::new();
+		let mut watchdog = Watchdog::new(SLOW_FRAME_SECONDS);
+		assert!(frame(&mut watchdog, &world, 0.0).is_none());
+		let record = frame(&mut watchdog, &world, 0.2).unwrap();
+		assert!(record.seconds >= 0.2);
+		assert_eq!(record.ticks, 2);
+		assert_eq!(record.entities, world.entity_count());
+		assert_eq!(record.tick, world.tick_count);
+		let phases: Vec<Phase> =
+			record.phases.iter().map(|&(phase, _)| phase).collect();
+		assert_eq!(phases, vec![Phase::Events, Phase::Ticks, Phase::Render]);
+		// The time went to the ticks, where it was stuck.
+		assert!(record.phases[1].1 >= 0.2, "{:?}", record.phases);
+		assert!(record.summary().contains("2 world ticks"));
+		// Not measuring a frame, there is nothing to report.
+		assert!(watchdog.end_frame(&world).is_none());
+	}
+
+	#[test]
+	fn slow_frames_straight_after_one_logged_are_only_counted() {
+		let world = World::new();
+		let mut watchdog = Watchdog::new(SLOW_FRAME_SECONDS);
+		frame(&mut watchdog, &world, 0.2).unwrap();
+		let logged = watchdog.last_logged.unwrap();
+		frame(&mut watchdog, &world, 0.2).unwrap();
+		frame(&mut watchdog, &world, 0.2).unwrap();
+		assert_eq!(watchdog.unlogged, 2);
+		assert_eq!(watchdog.last_logged, Some(logged));
+
+		watchdog.last_logged = Some(logged - LOG_INTERVAL);
+		frame(&mut watchdog, &world, 0.2).unwrap();
+		assert_eq!(watchdog.unlogged, 0);
+		assert!(watchdog.last_logged.unwrap() > logged);
+	}
+
+	#[test]
+	fn only_the_latest_events_are_kept() {
+		let mut world = World::new();
+		let mut watchdog = Watchdog::new(SLOW_FRAME_SECONDS);
+		watchdog.begin_frame();
+		// Walking about, going into tile after tile.
+		let walking = Actions {
+			move_x: 1.0,
+			move_y: 1.0,
+			..Actions::none()
+		};
+		for _ in 0..1000 {
+			world.tick(&walking);
+			watchdog.record_tick(&world);
+		}
+		assert_eq!(watchdog.recent_events.len(), RECENT_EVENTS);
+		let record = frame(&mut watchdog, &world, 0.2).unwrap();
+		assert_eq!(record.events.len(), RECENT_EVENTS);
+	}
+
+	#[cfg(feature = "profiling")]
+	#[test]
+	fn the_worst_frames_are_kept_slowest_first() {
+		let world = World::new();
+		let mut watchdog = Watchdog::new(SLOW_FRAME_SECONDS);
+		for i in 0..WORST_FRAMES * 2 {
+			frame(&mut watchdog, &world, 0.1 + (i % 7) as f64 * 0.01);
+		}
+		let worst = watchdog.worst_frames();
+		assert_eq!(worst.len(), WORST_FRAMES);
+		assert!(worst
+			.windows(2)
+			.all(|pair| pair[0].seconds >= pair[1].seconds));
+		assert!(worst[0].seconds >= 0.16);
+	}
+}
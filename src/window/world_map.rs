@@ -0,0 +1,508 @@
+use std::collections::HashMap;
+
+use super::thumbnails::Thumbnail;
+use super::{
+	font, Color, GamepadButton, Keycode, MouseButton, Role, Window,
+	WindowEvent, TEXT_SCALE,
+};
+use crate::geometry::Scalar;
+use crate::world::{
+	Direction, EntityKind, FrameId, NetFace, World, FRAME_WIDTH, TILE_SIZE,
+};
+use crate::GameState;
+
+// Gap between the net and the viewport edges before zooming in.
+const MAP_MARGIN: Scalar = 48.0;
+// How fast held arrow keys pan the map, in UI units per second.
+const MAP_PAN_SPEED: Scalar = 400.0;
+// How much each notch of the mouse wheel zooms by, and how far it can go.
+const MAP_ZOOM_STEP: Scalar = 1.25;
+const MIN_MAP_ZOOM: Scalar = 0.5;
+const MAX_MAP_ZOOM: Scalar = 8.0;
+// Half the size of icons, in UI units whatever the zoom.
+const MAP_ICON_SIZE: Scalar = 4.0;
+// How far links leading somewhere other than the frame beside them are
+// marked in from the edge they leave by, in frame units.
+const MAP_PORTAL_INSET: Scalar = 0.15;
+// Gap between the pointer and the top left corner of the tooltip, and
+// between the tooltip's outline and its text.
+const TOOLTIP_OFFSET: Scalar = 14.0;
+const TOOLTIP_PADDING: Scalar = 4.0;
+
+// A full screen view of the whole world unfolded flat, without the frames
+// the player hasn't been on yet. Toggled with M, and the world is paused
+// while it is open.
+pub(super) struct WorldMap {
+	pub open: bool,
+	// How far the net has been moved from the middle of the viewport, in
+	// UI units.
+	pan: (Scalar, Scalar),
+	// Times the size the net is when it just fits the viewport.
+	zoom: Scalar,
+	// Where the pointer was while dragging the map, in UI units.
+	drag: Option<(Scalar, Scalar)>,
+	thumbnails: HashMap<FrameId, Thumbnail>,
+}
+
+impl WorldMap {
+	pub fn new() -> Self {
+		Self {
+			open: false,
+			pan: (0.0, 0.0),
+			zoom: 1.0,
+			drag: None,
+			thumbnails: HashMap::new(),
+		}
+	}
+}
+
+// Where the net lies on screen for the current pan and zoom.
+#[derive(Copy, Clone)]
+struct MapLayout {
+	// UI units per net unit.
+	scale: Scalar,
+	// Where the net's origin is, in UI units.
+	origin: (Scalar, Scalar),
+}
+
+impl MapLayout {
+	fn to_ui(self, (x, y): (Scalar, Scalar)) -> (Scalar, Scalar) {
+		(
+			self.origin.0 + x * self.scale,
+			self.origin.1 + y * self.scale,
+		)
+	}
+
+	fn to_net(self, (x, y): (Scalar, Scalar)) -> (Scalar, Scalar) {
+		(
+			(x - self.origin.0) / self.scale,
+			(y - self.origin.1) / self.scale,
+		)
+	}
+}
+
+impl Window {
+	// Only opens while the world is being played, rather than over a menu
+	// or a cube being generated. Opens with the whole net in view again.
+	pub(super) fn toggle_world_map(&mut self, game_state: &GameState) {
+		let map = &mut self.world_map;
+		if map.open {
+			map.open = false;
+		} else if game_state.menu.is_none()
+			&& game_state.completion.is_none()
+			&& game_state.generating.is_none()
+		{
+			map.open = true;
+			map.pan = (0.0, 0.0);
+			map.zoom = 1.0;
+		}
+		map.drag = None;
+		self.input_state.release_keys();
+	}
+
+	// Gives the event to the world map, if it is open. Pointer events are
+	// all used up by it, and keys other than those closing it are left for
+	// the arrows to be seen as held. Whether it was used up.
+	pub(super) fn world_map_event(
+		&mut self,
+		game_state: &GameState,
+		event: &WindowEvent,
+	) -> bool {
+		if self.world_map.open == false {
+			return false;
+		}
+		use WindowEvent::*;
+		match *event {
+			KeyDown(Keycode::M)
+			| KeyDown(Keycode::Escape)
+			| GamepadDown(GamepadButton::Cancel) => self.toggle_world_map(game_state),
+			MouseMove(x, y) => {
+				let position = (x as Scalar, y as Scalar);
+				self.input_state.mouse_position = Some(position);
+				let (x, y) = self.ui().to_reference(position);
+				let map = &mut self.world_map;
+				if let Some((last_x, last_y)) = map.drag {
+					map.pan.0 += x - last_x;
+					map.pan.1 += y - last_y;
+					map.drag = Some((x, y));
+				}
+			}
+			MouseDown(MouseButton::Left) => {
+				self.world_map.drag = self
+					.input_state
+					.mouse_position
+					.map(|position| self.ui().to_reference(position));
+			}
+			MouseUp(MouseButton::Left) => self.world_map.drag = None,
+			MouseDown(_) | MouseUp(_) => {}
+			MouseWheel(notches) => self.zoom_world_map(notches as Scalar),
+			_ => return false,
+		}
+		true
+	}
+
+	// Zooms about the pointer, so that whatever is under it stays there.
+	fn zoom_world_map(&mut self, notches: Scalar) {
+		let (viewport_width, viewport_height) = self.ui_size();
+		let (center_x, center_y) =
+			(viewport_width / 2.0, viewport_height / 2.0);
+		let (x, y) = match self.input_state.mouse_position {
+			Some(position) => self.ui().to_reference(position),
+			None => (center_x, center_y),
+		};
+		let map = &mut self.world_map;
+		let zoom = (map.zoom * MAP_ZOOM_STEP.powf(notches))
+			.clamp(MIN_MAP_ZOOM, MAX_MAP_ZOOM);
+		let ratio = zoom / map.zoom;
+		map.pan.0 = x - center_x - (x - center_x - map.pan.0) * ratio;
+		map.pan.1 = y - center_y - (y - center_y - map.pan.1) * ratio;
+		map.zoom = zoom;
+	}
+
+	// Pans with the held arrow keys, over `elapsed` seconds.
+	pub(super) fn pan_world_map(&mut self, elapsed: f64) {
+		let held = &self.input_state.keys_held;
+		let axis = |negative, positive| match (
+			held.contains(&negative),
+			held.contains(&positive),
+		) {
+			(true, false) => 1.0,
+			(false, true) => -1.0,
+			_ => 0.0,
+		};
+		let step = MAP_PAN_SPEED * elapsed as Scalar;
+		let (x, y) = (
+			axis(Keycode::Left, Keycode::Right),
+			axis(Keycode::Up, Keycode::Down),
+		);
+		self.world_map.pan.0 += x * step;
+		self.world_map.pan.1 += y * step;
+	}
+
+	// The net fitted into the viewport, then zoomed about and panned from
+	// its middle.
+	fn map_layout(&self, faces: &[NetFace]) -> MapLayout {
+		let min_x = faces.iter().map(|face| face.cell.0).min().unwrap_or(0);
+		let max_x = faces.iter().map(|face| face.cell.0).max().unwrap_or(0);
+		let min_y = faces.iter().map(|face| face.cell.1).min().unwrap_or(0);
+		let max_y = faces.iter().map(|face| face.cell.1).max().unwrap_or(0);
+		let left = min_x as Scalar * 2.0 - 1.0;
+		let top = min_y as Scalar * 2.0 - 1.0;
+		let net_width = (max_x - min_x + 1) as Scalar * 2.0;
+		let net_height = (max_y - min_y + 1) as Scalar * 2.0;
+
+		let (viewport_width, viewport_height) = self.ui_size();
+		let fit = ((viewport_width - MAP_MARGIN * 2.0) / net_width)
+			.min((viewport_height - MAP_MARGIN * 2.0) / net_height)
+			.max(0.0);
+		let map = &self.world_map;
+		let scale = fit * map.zoom;
+		let (center_x, center_y) =
+			(left + net_width / 2.0, top + net_height / 2.0);
+		MapLayout {
+			scale,
+			origin: (
+				viewport_width / 2.0 + map.pan.0 - center_x * scale,
+				viewport_height / 2.0 + map.pan.1 - center_y * scale,
+			),
+		}
+	}
+
+	// Fills the box between two corners given either way round, cut down
+	// to the viewport so that zooming in doesn't fill far off screen.
+	fn fill_map_rect(
+		&mut self,
+		(ax, ay): (Scalar, Scalar),
+		(bx, by): (Scalar, Scalar),
+		color: Color,
+	) {
+		let (viewport_width, viewport_height) = self.ui_size();
+		let (left, right) =
+			(ax.min(bx).max(0.0), ax.max(bx).min(viewport_width));
+		let (top, bottom) =
+			(ay.min(by).max(0.0), ay.max(by).min(viewport_height));
+		if left < right && top < bottom {
+			self.fill_ui_rect((left, top), (right, bottom), color);
+		}
+	}
+
+	// Every frame where the net has it, with explored frames' tiles, coins,
+	// the checkpoint and links that lead somewhere other than the frame
+	// beside them, and the rest fogged over. The player is shown wherever
+	// they are, and the tile under the pointer is named in a tooltip.
+	pub(super) fn draw_world_map(&mut self, world: &World) {
+		// Rooted at the lowest frame id, as in the net window.
+		let root = match world.frame_ids().first() {
+			Some(&root) => root,
+			None => return,
+		};
+		let faces = world.net_layout(root);
+		let layout = self.map_layout(&faces);
+		let point = |face: &NetFace, x: Scalar, y: Scalar| {
+			layout.to_ui(face.to_net(x, y))
+		};
+		let focus = world.focus_entity.and_then(|id| world.get_entity(id));
+
+		for face in &faces {
+			let frame = match world.get_frame(face.frame_id) {
+				Some(frame) => frame,
+				None => continue,
+			};
+			if frame.visited {
+				let thumbnail =
+					Thumbnail::updated(&mut self.world_map.thumbnails, frame);
+				// Moved out for the duration of the draw, as in the strip.
+				let runs = std::mem::take(&mut thumbnail.runs);
+				for &(role, y, start, end) in &runs {
+					if role == Role::Background {
+						continue;
+					}
+					let top = -1.0 + y as Scalar * TILE_SIZE;
+					let left = -1.0 + start as Scalar * TILE_SIZE;
+					let right = -1.0 + end as Scalar * TILE_SIZE;
+					self.fill_map_rect(
+						point(face, left, top),
+						point(face, right, top + TILE_SIZE),
+						self.palette.get(role),
+					);
+				}
+				self.world_map
+					.thumbnails
+					.get_mut(&face.frame_id)
+					.unwrap()
+					.runs = runs;
+			} else {
+				let fog = self.palette.get(Role::MapFog);
+				self.fill_map_rect(
+					point(face, -1.0, -1.0),
+					point(face, 1.0, 1.0),
+					fog,
+				);
+			}
+
+			let role = match focus {
+				Some(entity) if entity.position.frame_id == face.frame_id => {
+					Role::Highlight
+				}
+				_ => Role::FrameBorder,
+			};
+			self.draw_ui_lines(
+				&[
+					point(face, -1.0, -1.0),
+					point(face, 1.0, -1.0),
+					point(face, 1.0, 1.0),
+					point(face, -1.0, 1.0),
+					point(face, -1.0, -1.0),
+				],
+				self.palette.get(role),
+			);
+		}
+
+		for face in &faces {
+			match world.get_frame(face.frame_id) {
+				Some(frame) if frame.visited => {}
+				_ => continue,
+			}
+			self.draw_map_portals(world, &faces, face, layout);
+			// The level's goal is to collect every coin.
+			for &id in world.entities_in_frame(face.frame_id) {
+				let entity = match world.get_entity(id) {
+					Some(entity) if entity.kind == EntityKind::Coin => entity,
+					_ => continue,
+				};
+				let (x, y) = point(face, entity.position.x, entity.position.y);
+				let s = MAP_ICON_SIZE;
+				self.draw_ui_lines(
+					&[
+						(x, y - s),
+						(x + s, y),
+						(x, y + s),
+						(x - s, y),
+						(x, y - s),
+					],
+					self.palette.get(Role::Coin),
+				);
+			}
+		}
+
+		let checkpoint = world.checkpoint_position().or(world.spawn);
+		if let Some(position) = checkpoint {
+			let face = faces.iter().find(|f| f.frame_id == position.frame_id);
+			if let Some(face) = face {
+				let (x, y) = point(face, position.x, position.y);
+				let s = MAP_ICON_SIZE;
+				self.draw_ui_lines(
+					&[
+						(x, y + s),
+						(x, y - s * 2.0),
+						(x + s * 1.5, y - s * 1.5),
+						(x, y - s),
+					],
+					self.palette.get(Role::Highlight),
+				);
+			}
+		}
+
+		if let Some(entity) = focus {
+			let position = entity.position;
+			let face = faces.iter().find(|f| f.frame_id == position.frame_id);
+			if let Some(face) = face {
+				let (x, y) = point(face, position.x, position.y);
+				let s = MAP_ICON_SIZE;
+				self.fill_map_rect(
+					(x - s, y - s),
+					(x + s, y + s),
+					self.palette.get(Role::Player),
+				);
+			}
+		}
+
+		let color = self.palette.get(Role::UiText);
+		let line_height = (font::LINE_ADVANCE * TEXT_SCALE) as Scalar;
+		self.draw_text("World map", 12.0, 12.0, TEXT_SCALE, color);
+		self.draw_text(
+			"Arrows or drag to pan, wheel to zoom, M to close.",
+			12.0,
+			12.0 + line_height,
+			TEXT_SCALE,
+			color,
+		);
+		self.draw_map_tooltip(world, &faces, layout);
+	}
+
+	// Marks each link out of `face` that the net doesn't show as a shared
+	// edge with the frame it leads to, labelled with that frame's id.
+	fn draw_map_portals(
+		&mut self,
+		world: &World,
+		faces: &[NetFace],
+		face: &NetFace,
+		layout: MapLayout,
+	) {
+		let frame = world.get_frame(face.frame_id).unwrap();
+		let midpoint = |edge: Direction| match edge {
+			Direction::Up => (0.0, -1.0),
+			Direction::Down => (0.0, 1.0),
+			Direction::Left => (-1.0, 0.0),
+			Direction::Right => (1.0, 0.0),
+			Direction::Neutral => (0.0, 0.0),
+		};
+		for &edge in Direction::iter() {
+			if edge == Direction::Neutral {
+				continue;
+			}
+			let link = match frame.borders.at_direction(edge) {
+				Some(link) => link,
+				None => continue,
+			};
+			let (x, y) = midpoint(edge);
+			let exit = face.to_net(x, y);
+			let target = faces.iter().find(|f| f.frame_id == link.frame);
+			let joined = target.is_some_and(|target| {
+				let (x, y) = midpoint(link.entry_edge);
+				let entry = target.to_net(x, y);
+				(entry.0 - exit.0).hypot(entry.1 - exit.1) < 1e-3
+			});
+			if joined {
+				continue;
+			}
+
+			let inset = 1.0 - MAP_PORTAL_INSET;
+			let (x, y) = layout.to_ui(face.to_net(x * inset, y * inset));
+			let s = MAP_ICON_SIZE;
+			let color = self.palette.get(Role::UiText);
+			self.draw_ui_lines(
+				&[
+					(x - s, y - s),
+					(x + s, y - s),
+					(x + s, y + s),
+					(x - s, y + s),
+					(x - s, y - s),
+				],
+				color,
+			);
+			let label = link.frame.0.to_string();
+			self.draw_text(&label, x + s + 2.0, y - s, 1, color);
+		}
+	}
+
+	// The frame and tile under the pointer, or that it is unexplored.
+	fn draw_map_tooltip(
+		&mut self,
+		world: &World,
+		faces: &[NetFace],
+		layout: MapLayout,
+	) {
+		let pointer = match self.input_state.mouse_position {
+			Some(position) => self.ui().to_reference(position),
+			None => return,
+		};
+		if self.world_map.drag.is_some() {
+			return;
+		}
+		let (net_x, net_y) = layout.to_net(pointer);
+		let inside = |&(x, y): &(Scalar, Scalar)| {
+			(-1.0..1.0).contains(&x) && (-1.0..1.0).contains(&y)
+		};
+		let under = faces
+			.iter()
+			.map(|face| (face.frame_id, face.from_net(net_x, net_y)))
+			.find(|(_, position)| inside(position));
+		let (frame_id, (x, y)) = match under {
+			Some(under) => under,
+			None => return,
+		};
+		let frame = match world.get_frame(frame_id) {
+			Some(frame) => frame,
+			None => return,
+		};
+		let text = if frame.visited {
+			let last = FRAME_WIDTH as isize - 1;
+			let tile_x = (((x + 1.0) / TILE_SIZE) as isize).min(last);
+			let tile_y = (((y + 1.0) / TILE_SIZE) as isize).min(last);
+			let tile = frame.tile(tile_x, tile_y);
+			format!(
+				"Frame {}, tile {}, {}: {}",
+				frame_id.0,
+				tile_x,
+				tile_y,
+				tile.name()
+			)
+		} else {
+			format!("Frame {}: unexplored", frame_id.0)
+		};
+
+		// Kept on screen, flipping to the other side of the pointer if it
+		// would go off the right or bottom.
+		let (viewport_width, viewport_height) = self.ui_size();
+		let width = (font::text_width(&text) * TEXT_SCALE) as Scalar
+			+ TOOLTIP_PADDING * 2.0;
+		let height =
+			(font::GLYPH_HEIGHT * TEXT_SCALE) as Scalar + TOOLTIP_PADDING * 2.0;
+		let mut left = pointer.0 + TOOLTIP_OFFSET;
+		if left + width > viewport_width {
+			left = (pointer.0 - TOOLTIP_OFFSET - width).max(0.0);
+		}
+		let mut top = pointer.1 + TOOLTIP_OFFSET;
+		if top + height > viewport_height {
+			top = (pointer.1 - TOOLTIP_OFFSET - height).max(0.0);
+		}
+		let (right, bottom) = (left + width, top + height);
+
+		let background = self.palette.get(Role::Background);
+		self.fill_ui_rect((left, top), (right, bottom), background);
+		let color = self.palette.get(Role::UiText);
+		self.draw_ui_lines(
+			&[
+				(left, top),
+				(right, top),
+				(right, bottom),
+				(left, bottom),
+				(left, top),
+			],
+			color,
+		);
+		let (x, y) = (left + TOOLTIP_PADDING, top + TOOLTIP_PADDING);
+		self.draw_text(&text, x, y, TEXT_SCALE, color);
+	}
+}
@@ -1,80 +1,212 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 
-use crate::backend::random;
 use crate::prelude::*;
 
-use super::geometry::{self, vec3, Vector3};
-use super::window::InputState;
-use super::window::{Color, Keycode};
+use super::geometry::{length_2d, vec3, Scalar, Vector3};
 
 mod types;
 pub use types::*;
+mod activity;
+pub use activity::FrameActivity;
+mod alignment;
+pub use alignment::{AlignmentRules, SeamIssue};
+mod animation;
+pub use animation::{AnimationKind, AnimationState};
+mod block;
+use block::Slide;
+mod bounds;
+mod cheats;
+pub use cheats::Cheats;
+mod collision;
+pub use collision::{
+	CollisionChannels, CHANNEL_ALL, CHANNEL_BLOCK, CHANNEL_ENEMY,
+	CHANNEL_PICKUP, CHANNEL_PLAYER,
+};
+mod checkpoint;
+use checkpoint::Checkpoint;
+pub use checkpoint::{ForeverChange, Persistence};
+mod dump;
+pub use dump::{dump_difference, DumpDifference, DUMP_VERSION};
+mod editing;
+pub use editing::EditError;
+mod edges;
+pub use edges::{
+	compose_rotation, rotate_point, transform_position_across,
+	transform_tile_across, TileIndex,
+};
+mod events;
+pub use events::{WorldEvent, WorldEventKind};
+mod falling;
+pub use falling::{FallOff, FALL_OFF_TICKS};
+mod fluid;
 mod frame;
 pub use frame::{Frame, FrameLink};
-
+mod generator;
+pub use generator::WorldGenerator;
+mod ground;
+mod history;
+pub use history::EditHistory;
+mod hooks;
+pub use hooks::{Behavior, Hooks, Trigger};
+mod input;
+pub use input::Actions;
+mod interaction;
+pub use interaction::Interaction;
+mod invariants;
+pub use invariants::InvariantViolation;
+mod level;
+pub use level::{EntitySpawn, LevelError, LevelInfo};
+mod neighborhood;
+pub use neighborhood::FrameSource;
+use neighborhood::{
+	oriented_tile, point_contacts, slope_at, spring_ahead, FrameNeighborhood,
+};
+mod net;
+pub use net::NetFace;
+mod physics;
+pub use physics::PhysicsConfig;
+mod probe;
+use probe::StableHasher;
+pub use probe::{determinism_probe, PROBE_INTERVAL, PROBE_SEED, PROBE_TICKS};
+mod random;
+pub use random::{Rng, DEFAULT_SEED};
+mod replay;
+pub use replay::{Playback, Replay};
+mod serde;
+pub use serde::{from_hex, to_hex, SaveError, SaveHeader, SAVE_VERSION};
+pub(crate) use serde::{Reader, Writer};
+mod sound;
+pub use sound::{Sound, SoundPlacement, SOUND_RADIUS};
+mod stats;
+pub use stats::WorldStats;
+mod spring;
+pub use spring::SPRING_SQUASH_TICKS;
+mod stamina;
+mod status;
+pub use status::{StatusEffect, StatusKind};
+mod surface;
+mod sync;
+pub use sync::{SyncClient, SyncHost, SyncMessage, TileEdit};
+mod tags;
+pub use tags::TagId;
+mod topology;
+pub use topology::{OpenEdges, TopologyIssue};
+mod validation;
+pub use validation::{LevelIssue, TileLocation};
+mod walker;
+pub use walker::EdgeBehavior;
+mod worm;
+pub use worm::{TrailPoint, WORM_SEGMENTS};
+
+// World ticks per second. Speeds and durations in the world are per tick at
+// this rate, however many ticks each call to `World::tick` covers.
+pub const TICK_RATE: f64 = 60.0;
 pub const FRAME_WIDTH: usize = 16;
-pub const TILE_SIZE: f32 = 2.0 / FRAME_WIDTH as f32;
+pub const TILE_SIZE: Scalar = 2.0 / FRAME_WIDTH as Scalar;
 const FRAME_TILE_COUNT: usize = FRAME_WIDTH * FRAME_WIDTH;
+// How close the player has to get to a pickup to collect it.
+const PICKUP_RADIUS: Scalar = TILE_SIZE * 0.75;
+// How far from an entity the tile it is standing on is looked for, and how
+// close to a slope's surface it must be to count as standing on it.
+const CONTACT_PROBE: Scalar = TILE_SIZE * 0.01;
+const WALKER_SPAWN_ATTEMPTS: usize = 16;
+pub const MAX_HEALTH: Scalar = 10.0;
+// Walking acceleration is multiplied by this in sand.
+const SAND_SLOW: Scalar = 0.4;
+// The most ticks `World::tick` can be asked to cover at once.
+pub const MAX_TICKS_PER_STEP: u32 = 4;
+// Wind is multiplied by this for anything standing on the ground.
+const WIND_GROUNDED: Scalar = 0.25;
+// What collecting a speed boost pickup does.
+const SPEED_BOOST_TICKS: u32 = 300;
+const SPEED_BOOST_MULTIPLIER: Scalar = 1.5;
+// How long an energizer makes stamina come back faster for, and how much.
+const ENERGIZER_TICKS: u32 = 600;
+const ENERGIZER_MULTIPLIER: Scalar = 3.0;
 
 pub struct World {
 	frames: HashMap<FrameId, Frame>,
 	entities: HashMap<EntityId, Entity>,
+	// Which entities are on each frame, kept up to date as they move.
+	frame_entities: HashMap<FrameId, Vec<EntityId>>,
+	// Frames ticked in full this tick, or `None` for all of them.
+	active_frames: Option<HashSet<FrameId>>,
+	ticked_entities: usize,
+	events: Vec<WorldEvent>,
 	pub focus_entity: Option<EntityId>,
+	pub physics: PhysicsConfig,
+	pub hooks: Hooks,
+	// Group names, indexed by `TagId`.
+	tags: Vec<String>,
+	pub level: LevelInfo,
+	// Where players are put back after leaving the world.
+	pub spawn: Option<WorldPosition>,
+	// Coins the focus entity has picked up, which completes the level once
+	// there are none left.
+	pub coins_collected: u32,
+	stats: WorldStats,
+	// Blocks part of the way through moving a tile.
+	slides: HashMap<EntityId, Slide>,
+	// Whether the focus entity's jump is held this tick.
+	jump_held: bool,
+	// What the player comes back to after dying.
+	checkpoint: Option<Checkpoint>,
+	// Changes meant to outlast the level, not yet taken by whatever keeps
+	// them.
+	forever_changes: Vec<ForeverChange>,
+	// Ticks run since the world was created.
+	pub tick_count: u64,
+	// How many ticks each call to `tick` covers, for running at a lower
+	// rate on machines that can't keep up. Accelerations, friction and
+	// durations are scaled to match, so the world moves at about the same
+	// speed either way.
+	ticks_per_step: u32,
+	// Bumped by every change a saved level would capture, so savers can
+	// tell whether there is anything new to write.
+	change_count: u64,
 	iota: usize,
+	// Everything random in the world comes from here, so that it follows
+	// from the seed and the ticks run.
+	rng: Rng,
 }
 
 impl World {
 	pub fn new() -> Self {
-		let mut world = Self {
+		Self::with_seed(DEFAULT_SEED)
+	}
+
+	// A generated cube, laid out by `seed`.
+	pub fn with_seed(seed: u64) -> Self {
+		WorldGenerator::new(seed).finish()
+	}
+
+	// A world with no frames or entities.
+	fn empty() -> Self {
+		Self {
 			frames: HashMap::new(),
 			entities: HashMap::new(),
+			frame_entities: HashMap::new(),
+			active_frames: None,
+			ticked_entities: 0,
+			slides: HashMap::new(),
+			jump_held: false,
+			checkpoint: None,
+			forever_changes: Vec::new(),
+			events: Vec::new(),
 			focus_entity: None,
+			physics: PhysicsConfig::default(),
+			hooks: Hooks::new(),
+			tags: Vec::new(),
+			level: LevelInfo::default(),
+			spawn: None,
+			coins_collected: 0,
+			stats: WorldStats::default(),
+			tick_count: 0,
+			ticks_per_step: 1,
+			change_count: 0,
 			iota: 0,
-		};
-
-		let front = Frame::new_populated(FrameId::new(0));
-		let front_id = world.insert_frame(front);
-
-		let left = Frame::new_populated(FrameId::new(1));
-		let left_id = world.insert_frame(left);
-
-		let right = Frame::new_populated(FrameId::new(2));
-		let right_id = world.insert_frame(right);
-
-		let up = Frame::new_populated(FrameId::new(3));
-		let up_id = world.insert_frame(up);
-
-		let down = Frame::new_populated(FrameId::new(4));
-		let down_id = world.insert_frame(down);
-
-		let back = Frame::new_populated(FrameId::new(5));
-		let back_id = world.insert_frame(back);
-
-		use Direction::*;
-
-		world.connect_frames(front_id, Up, up_id, Down);
-		world.connect_frames(front_id, Left, left_id, Right);
-		world.connect_frames(front_id, Right, right_id, Left);
-		world.connect_frames(front_id, Down, down_id, Up);
-
-		world.connect_frames(back_id, Up, up_id, Up);
-		world.connect_frames(back_id, Right, left_id, Left);
-		world.connect_frames(back_id, Left, right_id, Right);
-		world.connect_frames(back_id, Down, down_id, Down);
-
-		world.connect_frames(left_id, Up, up_id, Left);
-		world.connect_frames(left_id, Down, down_id, Right);
-
-		world.connect_frames(right_id, Up, up_id, Right);
-		world.connect_frames(right_id, Down, down_id, Left);
-
-		let player = Entity::new_player(&mut world, front_id);
-		let player_id = player.id;
-		world.entities.insert(player.id, player);
-
-		world.focus_entity = Some(player_id);
-
-		world
+			rng: Rng::new(DEFAULT_SEED),
+		}
 	}
 
 	fn insert_frame(&mut self, frame: Frame) -> FrameId {
@@ -83,104 +215,537 @@ impl World {
 		id
 	}
 
-	pub fn tick(&mut self, input_state: &InputState) {
-		let player_id = self.focus_entity.unwrap();
+	pub fn tick(&mut self, actions: &Actions) {
+		self.events.clear();
+		let step = self.ticks_per_step;
+		self.tick_count += step as u64;
 
-		let speed = 0.002;
+		for id in self.entity_ids() {
+			if self.entity_in_bounds(id) == false {
+				self.recover_out_of_bounds(id, "not on any frame");
+			}
+		}
+		self.update_activity();
+
+		// Without a focus entity, as after the player is gone, the world
+		// carries on with nobody acting in it.
+		let player_id = self
+			.focus_entity
+			.filter(|&id| self.get_entity(id).is_some());
+		let sprinting = match player_id {
+			Some(id) => self.act(id, actions),
+			None => false,
+		};
 
-		for &keycode in input_state.keys_held.iter() {
-			use Keycode::*;
-			match keycode {
-				A => {
-					self.impulse_entity(player_id, vec3(-speed, 0.0, 0.0));
-				}
-				D => {
-					self.impulse_entity(player_id, vec3(speed, 0.0, 0.0));
-				}
-				W => {
-					self.impulse_entity(player_id, vec3(0.0, -speed, 0.0));
+		self.ticked_entities = 0;
+		for id in self.entity_ids() {
+			if self.entity_is_active(id) == false {
+				continue;
+			}
+			self.ticked_entities += 1;
+			if self.update_fall_off(id) {
+				continue;
+			}
+			match self.get_entity(id).unwrap().kind {
+				EntityKind::Block => {
+					self.update_block(id);
+					continue;
 				}
-				S => {
-					self.impulse_entity(player_id, vec3(0.0, speed, 0.0));
+				// Moved along after every head has, by `update_worms`.
+				EntityKind::WormSegment => {
+					self.update_status_effects(id);
+					continue;
 				}
 				_ => {}
 			}
+			let before = self.tile_location_at_entity(id);
+			if self.tile_at_entity(id) == Tile::Sand {
+				// Lasts into the next step, when walking speed is worked out.
+				let ticks = step + 1;
+				let slow =
+					StatusEffect::new(StatusKind::Slow, ticks, SAND_SLOW);
+				self.add_status_effect(id, slow);
+			}
+			self.update_status_effects(id);
+			self.steer_walker(id);
+			self.apply_conveyor(id);
+			self.apply_wind(id);
+			self.move_entity(id);
+			if self.get_entity(id).is_none() {
+				continue;
+			}
+			self.update_animation(id, step);
+			let after = self.tile_location_at_entity(id);
+			if after != before {
+				let (frame, x, y) = after;
+				let tile = *self.get_frame(frame).unwrap().tile(x, y);
+				self.events.push(WorldEvent::TileEntered {
+					entity: id,
+					frame,
+					x: x as usize,
+					y: y as usize,
+					tile,
+				});
+			}
+		}
+
+		if let Some(player_id) = player_id {
+			self.regenerate_stamina(player_id, sprinting);
+		}
+		self.update_fluids();
+		self.update_springs();
+		if let Some(player_id) = player_id {
+			self.collect_pickups(player_id);
+		}
+		self.update_worms(player_id);
+		self.update_deaths();
+		self.run_hooks();
+		self.visit_focus_frame();
+	}
+
+	// Does what the player's input says for this step, and whether they are
+	// trying to sprint, which keeps stamina from coming back even once there
+	// is none left to sprint on.
+	fn act(&mut self, player_id: EntityId, actions: &Actions) -> bool {
+		let step = self.ticks_per_step;
+		// The player has no say in anything while falling off the world.
+		let none = Actions::none();
+		let falling = self.get_entity(player_id).unwrap().is_falling_off();
+		let actions = if falling { &none } else { actions };
+
+		let (move_x, move_y) = (actions.move_x, actions.move_y);
+		let moving = move_x != 0.0 || move_y != 0.0;
+		let trying_to_sprint = actions.sprint && moving;
+		let sprinting = trying_to_sprint && self.sprint_entity(player_id);
+		let sprint = if sprinting {
+			self.physics.sprint_multiplier
+		} else {
+			1.0
+		};
+		let speed = self.physics.walk_acceleration
+			* self.speed_multiplier(player_id)
+			* sprint * step as Scalar;
+		if moving {
+			let impulse = vec3(move_x * speed, move_y * speed, 0.0);
+			self.impulse_entity(player_id, impulse);
+		}
+		self.jump_held = actions.jump_held;
+		let player = self.get_entity_mut(player_id).unwrap();
+		player.aim = actions.aim;
+		if move_x != 0.0 {
+			let direction = if move_x > 0.0 {
+				Direction::Right
+			} else {
+				Direction::Left
+			};
+			player.orientation = direction;
+			self.push_block(player_id, direction);
+		}
+
+		if actions.jump && self.jump_entity(player_id) {
+			self.record_jump(player_id);
+		}
+		if actions.dash {
+			self.dash_entity(player_id);
+		}
+		if actions.interact {
+			self.interact(player_id);
+		}
+		if actions.remove {
+			// Refusals are told as events, for the window to show.
+			let _ = self.try_remove_tile(player_id);
 		}
+		if actions.rotate {
+			let _ = self.try_rotate_tile(player_id);
+		}
+		trying_to_sprint
+	}
+
+	// The frame and index of the tile an entity is in.
+	fn tile_location_at_entity(&self, id: EntityId) -> (FrameId, isize, isize) {
+		let position = self.get_entity(id).unwrap().position;
+		let (x, y) = self.tile_index_at_position(position);
+		(position.frame_id, x, y)
+	}
+
+	fn collect_pickups(&mut self, collector: EntityId) {
+		let (position, channels) = match self.get_entity(collector) {
+			Some(entity) => (entity.position, entity.channels),
+			None => return,
+		};
 
-		for &keycode in input_state.keys_pressed.iter() {
-			use Keycode::*;
-			match keycode {
-				W => {
-					self.jump_entity(player_id);
+		let collected: Vec<EntityId> = self
+			.entities_in_frame(position.frame_id)
+			.iter()
+			.copied()
+			.filter(|&id| {
+				let pickup = self.get_entity(id).unwrap();
+				pickup.kind.is_pickup()
+					&& channels.touches(&pickup.channels)
+					&& self.surface_distance(position, pickup.position)
+						< PICKUP_RADIUS
+			})
+			.collect();
+
+		for pickup in collected {
+			let kind = self.remove_entity(pickup).unwrap().kind;
+			match kind {
+				EntityKind::Coin => {
+					self.coins_collected += 1;
+					self.events.push(WorldEvent::CoinCollected {
+						collector,
+						coin: pickup,
+					});
+					if self.coins_remaining() == 0 {
+						self.events.push(WorldEvent::LevelCompleted {
+							entity: collector,
+							ticks: self.tick_count,
+						});
+					}
 				}
-				E => {
-					let entity = self.get_entity(player_id).unwrap();
-					let mut position = entity.position;
-					let (ex, ey) = self.tile_index_at_position(position);
-					let (tile_frame_position, tx, ty) = self
-						.normalize_tile_index(position.frame_id, ex + 1, ey);
-					let frame =
-						self.get_frame_mut(tile_frame_position).unwrap();
-					*frame.tile_mut(tx, ty) = Tile::Solid;
+				EntityKind::GravityOrb => self.flip_gravity(collector),
+				EntityKind::SpeedBoost => {
+					let boost = StatusEffect::new(
+						StatusKind::SpeedBoost,
+						SPEED_BOOST_TICKS,
+						SPEED_BOOST_MULTIPLIER,
+					);
+					self.add_status_effect(collector, boost);
 				}
-				Q => {
-					let entity = self.get_entity(player_id).unwrap();
-					let mut position = entity.position;
-					let (ex, ey) = self.tile_index_at_position(position);
-					let (tile_frame_position, tx, ty) = self
-						.normalize_tile_index(position.frame_id, ex + 1, ey);
-					let frame =
-						self.get_frame_mut(tile_frame_position).unwrap();
-					*frame.tile_mut(tx, ty) = Tile::Empty;
+				EntityKind::Energizer => {
+					let energized = StatusEffect::new(
+						StatusKind::Energized,
+						ENERGIZER_TICKS,
+						ENERGIZER_MULTIPLIER,
+					);
+					self.add_status_effect(collector, energized);
 				}
-				_ => {}
+				EntityKind::Player
+				| EntityKind::Walker
+				| EntityKind::Block
+				| EntityKind::Worm
+				| EntityKind::WormSegment => {}
 			}
 		}
-		for id in self.entity_ids() {
-			self.move_entity(id);
+	}
+
+	// Pushes an entity resting on a conveyor along the belt. The belt has to
+	// be right against the entity, so the push stops the tick it jumps or
+	// walks off.
+	fn apply_conveyor(&mut self, id: EntityId) {
+		let entity = self.get_entity(id).unwrap();
+		let (gx, gy) = entity.gravity_dir.unit();
+		let mut below = entity.position;
+		below.x += gx * CONTACT_PROBE;
+		below.y += gy * CONTACT_PROBE;
+		let (tx, ty) = self.tile_index_at_position(below);
+		let (tile, rotation) = match oriented_tile(self, below.frame_id, tx, ty)
+		{
+			Some(oriented) => oriented,
+			None => return,
+		};
+
+		let (dx, dy) = match tile.conveyor_direction(rotation) {
+			Some(direction) => direction.unit(),
+			None => return,
+		};
+		// A belt turned to run towards or away from the entity has nothing
+		// to carry it along.
+		if dx * gx + dy * gy != 0.0 {
+			return;
 		}
+		let push =
+			self.physics.conveyor_acceleration * self.ticks_per_step as Scalar;
+		let velocity = &mut self.get_entity_mut(id).unwrap().velocity;
+		velocity.x += dx * push;
+		velocity.y += dy * push;
+	}
+
+	// Pushes an entity along its frame's wind, if it has any. Pickups stay
+	// where they were put, and the ground holds on to anything standing on
+	// it.
+	fn apply_wind(&mut self, id: EntityId) {
+		let entity = self.get_entity(id).unwrap();
+		if entity.kind.is_pickup() {
+			return;
+		}
+		let wind = match self.get_frame(entity.position.frame_id) {
+			Some(frame) => match frame.wind {
+				Some(wind) => wind,
+				None => return,
+			},
+			None => return,
+		};
+		let grip = if entity.grounded { WIND_GROUNDED } else { 1.0 };
+		let push = grip * self.ticks_per_step as Scalar;
+		let entity = self.get_entity_mut(id).unwrap();
+		entity.velocity.x += wind.x * push;
+		entity.velocity.y += wind.y * push;
+	}
+
+	// Whether the frame exists.
+	pub fn set_wind(
+		&mut self,
+		frame_id: FrameId,
+		wind: Option<Vector3>,
+	) -> bool {
+		match self.get_frame_mut(frame_id) {
+			Some(frame) => frame.wind = wind,
+			None => return false,
+		}
+		self.change_count += 1;
+		true
+	}
+
+	// Places `count` walkers on empty tiles chosen at random across every
+	// frame. Gives up on a walker after a few tries at finding room for it.
+	pub fn spawn_walkers(
+		&mut self,
+		count: usize,
+		edge_behavior: EdgeBehavior,
+	) -> usize {
+		let frame_ids = self.frame_ids();
+		if frame_ids.is_empty() {
+			return 0;
+		}
+		let w = FRAME_WIDTH as isize;
+		let mut spawned = 0;
+		for _ in 0..count {
+			for _ in 0..WALKER_SPAWN_ATTEMPTS {
+				let frame_id = *self.rng.pick(&frame_ids).unwrap();
+				let (x, y) = (self.rng.range_i(0, w), self.rng.range_i(0, w));
+				let frame = self.get_frame(frame_id).unwrap();
+				if *frame.tile(x, y) != Tile::Empty {
+					continue;
+				}
+				let position = WorldPosition {
+					frame_id,
+					x: -1.0 + (x as Scalar + 0.5) * TILE_SIZE,
+					y: -1.0 + (y as Scalar + 0.5) * TILE_SIZE,
+				};
+				let walker = Entity::new_walker(self, position, edge_behavior);
+				self.insert_entity(walker);
+				spawned += 1;
+				break;
+			}
+		}
+		spawned
+	}
+
+	// Reverses which way the entity falls and jumps.
+	pub fn flip_gravity(&mut self, id: EntityId) {
+		let entity = match self.get_entity_mut(id) {
+			Some(entity) => entity,
+			None => return,
+		};
+		entity.gravity_dir = entity.gravity_dir.reverse();
+		let gravity_dir = entity.gravity_dir;
+		self.events.push(WorldEvent::GravityFlipped {
+			entity: id,
+			gravity_dir,
+		});
 	}
 
 	// Change current position by current velocity and resolve collisions.
 	fn move_entity(&mut self, id: EntityId) {
+		// Collisions zero the velocity into whatever was hit, so how hard
+		// the entity lands has to be taken from before them.
+		let entity = self.get_entity(id).unwrap();
+		let was_grounded = entity.grounded;
+		let (velocity_before, gravity_before) =
+			(entity.velocity, entity.gravity_dir);
+		let movement = match self.resolve_movement(id) {
+			Ok(movement) => movement,
+			Err(NormalizeError::UnlinkedEdge { best_effort, edge })
+				if self.is_open_edge(best_effort.frame_id, edge) =>
+			{
+				self.start_falling_off(id, best_effort, edge);
+				return;
+			}
+			Err(error) => {
+				self.recover_out_of_bounds(id, &error.to_string());
+				return;
+			}
+		};
+
+		let crossed = movement.crossing.is_some();
+		self.record_movement(id, movement.distance, crossed);
+
+		let mut gravity_dir = self.get_entity(id).unwrap().gravity_dir;
+		if let Some(crossing) = movement.crossing {
+			// Gravity keeps pointing the same way on the surface, which is a
+			// different way in a rotated frame.
+			gravity_dir = gravity_dir.rotated(crossing.angle);
+			let entity = self.get_entity_mut(id).unwrap();
+			entity.orientation = entity.orientation.rotated(crossing.angle);
+			entity.aim = entity.aim.rotated(crossing.angle);
+			self.reindex_entity(id, crossing.from, crossing.to);
+			self.events.push(WorldEvent::EntityCrossedFrame {
+				entity: id,
+				from: crossing.from,
+				to: crossing.to,
+				exit_edge: crossing.exit_edge,
+				entry_edge: crossing.entry_edge,
+			});
+		}
+		// Multiplied out rather than with `powi`, which targets are free to
+		// round differently.
+		let retention = (0..self.ticks_per_step).fold(1.0, |retention, _| {
+			retention * self.physics.velocity_retention
+		});
 		let entity = self.get_entity_mut(id).unwrap();
+		entity.position = movement.position;
+		entity.velocity = movement.velocity;
+		entity.gravity_dir = gravity_dir;
+		entity.last_movement_direction_x = movement.set_direction_x;
+		entity.last_movement_direction_y = movement.set_direction_y;
+
+		// If the entity moved along both x and y this frame, y gets
+		// priority.
+		entity.last_movement_direction =
+			match (movement.direction_x, movement.direction_y) {
+				(Direction::Neutral, Direction::Neutral) => {
+					entity.last_movement_direction
+				}
+				(x, Direction::Neutral) => x,
+				(_, y) => y,
+			};
+
+		entity.grounded = movement.grounded;
+
+		// Air friction and gravity.
+		entity.velocity.x *= retention;
+		entity.velocity.y *= retention;
+
+		if entity.velocity.x.abs() < 0.00001 {
+			entity.velocity.x = 0.0;
+		}
+		if entity.velocity.y.abs() < 0.00001 {
+			entity.velocity.y = 0.0;
+		}
+		// Gravity is disabled for now. It would pull along `gravity_dir`:
+		//let (gx, gy) = entity.gravity_dir.unit();
+		//entity.velocity += vec3(gx, gy, 0.0) * 0.0004;
+
+		if let Some(spring) = movement.bounce {
+			self.bounce_entity(id, spring);
+		}
+		if movement.grounded && was_grounded == false {
+			// Both from before any frame crossing, so in the same frame.
+			let (gx, gy) = gravity_before.unit();
+			let speed = velocity_before.x * gx + velocity_before.y * gy;
+			let speed = speed.max(0.0);
+			self.land_entity(id, speed);
+		}
+	}
+
+	fn land_entity(&mut self, id: EntityId, speed: Scalar) {
+		self.events
+			.push(WorldEvent::EntityLanded { entity: id, speed });
+		let physics = self.physics;
+		if physics.fall_damage && speed > physics.fall_damage_speed {
+			let excess = speed - physics.fall_damage_speed;
+			self.hit_entity(id, excess * physics.fall_damage_per_speed);
+		}
+	}
+
+	// Health stops at zero. Entities that run out die at the end of the
+	// tick.
+	pub fn damage_entity(&mut self, id: EntityId, amount: Scalar) {
+		if self.has_status(id, StatusKind::Invulnerable) || self.is_shielded(id)
+		{
+			return;
+		}
+		let entity = match self.get_entity_mut(id) {
+			Some(entity) if entity.cheats.god_mode == false => entity,
+			_ => return,
+		};
+		let health = (entity.health - amount).max(0.0);
+		let lost = entity.health - health;
+		entity.health = health;
+		self.record_damage(id, lost);
+		self.events
+			.push(WorldEvent::EntityDamaged { entity: id, amount });
+	}
+
+	// Where the entity's velocity takes it this tick. Only reads the world,
+	// leaving `move_entity` to write the result back. Fails if that is off
+	// an unlinked edge, or the velocity isn't a number.
+	fn resolve_movement(
+		&self,
+		id: EntityId,
+	) -> Result<Movement, NormalizeError> {
+		let entity = self.get_entity(id).unwrap();
+		if entity.cheats.noclip {
+			return Ok(self.resolve_noclip_movement(id));
+		}
+		let frames = FrameNeighborhood::around(self, entity.position.frame_id);
 
 		// Move in smaller steps if the magnitude of the velocity is greater
 		// than the size of one tile. This does not fully eliminate clipping
 		// but should reduce it.
-		let iterations = (entity.velocity.len() / TILE_SIZE).max(1.0).ceil();
-		let step_vector = entity.velocity / iterations;
+		let displacement = entity.velocity * self.ticks_per_step as Scalar;
+		let iterations = (displacement.len() / TILE_SIZE).max(1.0).ceil();
+		let step_vector = displacement / iterations;
+		// An infinite velocity comes out as NaN here too.
+		if step_vector.x.is_nan() || step_vector.y.is_nan() {
+			return Err(NormalizeError::NaN);
+		}
 		let last_direction_x = entity.last_movement_direction_x;
 		let last_direction_y = entity.last_movement_direction_y;
 		let last_direction = entity.last_movement_direction;
+		let gravity_dir = entity.gravity_dir;
 
 		let direction_x = match step_vector.x {
-			dx if dx == 0.0 => Direction::Neutral,
 			dx if dx > 0.0 => Direction::Right,
 			dx if dx < 0.0 => Direction::Left,
-			_ => panic!("NaN velocity vector component, {:?}", step_vector),
+			_ => Direction::Neutral,
 		};
 		let mut set_direction_x = direction_x;
 
 		let direction_y = match step_vector.y {
-			dy if dy == 0.0 => Direction::Neutral,
 			dy if dy > 0.0 => Direction::Down,
 			dy if dy < 0.0 => Direction::Up,
-			_ => panic!("NaN velocity vector component, {:?}", step_vector),
+			_ => Direction::Neutral,
 		};
 		let mut set_direction_y = direction_y;
 
-		let f = FRAME_WIDTH as f32 / 2.0;
+		let f = FRAME_WIDTH as Scalar / 2.0;
 		let mut position = entity.position;
 		let mut velocity = entity.velocity;
-		// `entity` is dropped here, allowing more references to `self`.
 		let mut grounded = false;
+		let mut bounce = None;
+		// Springs throw just as hard however fast they are hit, and harder
+		// for a player holding jump.
+		let jump_held = self.jump_held && self.focus_entity == Some(id);
+		let spring_speed = self.physics.spring_speed
+			+ if jump_held {
+				self.physics.spring_jump_bonus
+			} else {
+				0.0
+			};
 		for _ in 0..iterations as usize {
 			use Direction::*;
 
-			let start_contacts = self.point_contacts(position);
+			// Ramps only hold up entities falling down onto them. One
+			// being stood on is followed as the entity walks, up to and
+			// past either end, so that it never sinks into the tile next to
+			// the top of the ramp and catches on it as if it were a wall.
+			let standing_slope = match gravity_dir {
+				Down => slope_at(&frames, position),
+				_ => None,
+			}
+			.filter(|slope| {
+				(slope.surface_at(position.x) - position.y).abs()
+					< CONTACT_PROBE
+			});
+
+			let start_contacts = point_contacts(&frames, position);
 			position.x += step_vector.x;
-			let end_contacts = self.point_contacts(position);
+			if let Some(slope) = standing_slope {
+				position.y = slope.surface_at(position.x);
+			}
+			let end_contacts = point_contacts(&frames, position);
 
 			let collision_x = match (
 				direction_x,
@@ -209,20 +774,47 @@ impl World {
 				_ => false,
 			};
 
+			// Snapped from the frame's corner, as contacts are found, so that
+			// rounding can't put the two in different tiles. A point a hair
+			// short of the far edge otherwise touches the tile beyond it but
+			// is snapped back a whole tile.
 			if collision_x {
+				let tile_x = (position.x + 1.0) * f;
 				match direction_x {
-					Right => position.x = (position.x * f).floor() / f,
-					Left => position.x = (position.x * f).ceil() / f,
+					Right => position.x = tile_x.floor() / f - 1.0,
+					Left => position.x = tile_x.ceil() / f - 1.0,
 					_ => panic!(),
 				}
-				velocity.x = 0.0;
+				// A spring throws the entity back off it rather than holding
+				// it, so it doesn't land either.
+				match spring_ahead(&frames, position, direction_x) {
+					Some((spring, throw)) => {
+						velocity.x = throw.unit().0 * spring_speed;
+						bounce = Some(spring);
+					}
+					None => {
+						if direction_x == gravity_dir {
+							grounded = true;
+						}
+						velocity.x = 0.0;
+					}
+				}
 			} else {
 				set_direction_x = last_direction_x;
 			}
 
-			let start_contacts = self.point_contacts(position);
+			let start_contacts = point_contacts(&frames, position);
 			position.y += step_vector.y;
-			let end_contacts = self.point_contacts(position);
+			let end_contacts = point_contacts(&frames, position);
+
+			// Still on the ramp unless this step took it off the surface,
+			// as stepping down off a ramp's lip with nothing beneath does.
+			if let Some(slope) = standing_slope {
+				let height = slope.surface_at(position.x) - position.y;
+				if height.abs() < CONTACT_PROBE {
+					grounded = true;
+				}
+			}
 
 			let collision_y = match (
 				direction_y,
@@ -252,25 +844,56 @@ impl World {
 			};
 
 			if collision_y {
+				let tile_y = (position.y + 1.0) * f;
 				match direction_y {
-					Down => {
-						position.y = (position.y * f).floor() / f;
-						grounded = true;
-					}
-					Up => position.y = (position.y * f).ceil() / f,
+					Down => position.y = tile_y.floor() / f - 1.0,
+					Up => position.y = tile_y.ceil() / f - 1.0,
 					_ => panic!(),
 				}
-				velocity.y = 0.0;
+				match spring_ahead(&frames, position, direction_y) {
+					Some((spring, throw)) => {
+						velocity.y = throw.unit().1 * spring_speed;
+						bounce = Some(spring);
+					}
+					// Landing against whichever side gravity points towards.
+					None => {
+						if direction_y == gravity_dir {
+							grounded = true;
+						}
+						velocity.y = 0.0;
+					}
+				}
 			} else {
 				set_direction_y = last_direction_y;
 			}
 
+			// Falling onto a ramp, or walking onto one from its low end,
+			// lifts the entity onto its surface. Anything deeper than this
+			// step could have gone came in through the ramp's vertical
+			// side, which is a wall.
+			let slope = match gravity_dir {
+				Down => slope_at(&frames, position),
+				_ => None,
+			};
+			if let Some(slope) = slope {
+				let depth = position.y - slope.surface_at(position.x);
+				let reach = step_vector.x.abs() + step_vector.y.max(0.0);
+				if depth > reach + CONTACT_PROBE {
+					position.x = slope.wall_x();
+					velocity.x = 0.0;
+				} else if depth > -CONTACT_PROBE {
+					position.y = slope.surface_at(position.x);
+					grounded = true;
+					velocity.y = velocity.y.min(0.0);
+				}
+			}
+
 			// 		position.x = (position.x * f).floor() / f;
 			// 		velocity.x = 0.0;
 			// 	}
 			// 	dx if dx < 0.0 => {
 			// 		position.x += dx;
-			// 		match self.point_contacts(position).left {
+			// 		match point_contacts(&frames, position).left {
 			// 			Tile::Empty => {}
 			// 			_ => {
 			// 				position.x = (position.x * f).ceil() / f;
@@ -285,7 +908,7 @@ impl World {
 			// 	dy if dy == 0.0 => {}
 			// 	dy if dy > 0.0 => {
 			// 		position.y += dy;
-			// 		match self.point_contacts(position).below {
+			// 		match point_contacts(&frames, position).below {
 			// 			Tile::Empty => {}
 			// 			_ => {
 			// 				position.y = (position.y * f).floor() / f;
@@ -295,7 +918,7 @@ impl World {
 			// 	}
 			// 	dy if dy < 0.0 => {
 			// 		position.y += dy;
-			// 		match self.point_contacts(position).above {
+			// 		match point_contacts(&frames, position).above {
 			// 			Tile::Empty => {}
 			// 			_ => {
 			// 				position.y = (position.y * f).ceil() / f;
@@ -331,38 +954,26 @@ impl World {
 			// }
 		}
 
-		let normalized_position = position.normalize(self);
-		let entity = self.get_entity_mut(id).unwrap();
-		entity.position = normalized_position;
-		entity.velocity = velocity;
-		entity.last_movement_direction_x = set_direction_x;
-		entity.last_movement_direction_y = set_direction_y;
-
-		//println!("{:?}", normalized_position);
-
-		// If the entity moved along both x and y this frame, y gets
-		// priority.
-		entity.last_movement_direction = match (direction_x, direction_y) {
-			(Direction::Neutral, Direction::Neutral) => {
-				entity.last_movement_direction
-			}
-			(x, Direction::Neutral) => x,
-			(_, y) => y,
-		};
-
-		entity.grounded = grounded;
-
-		// Air friction and gravity.
-		entity.velocity.x *= 0.8;
-		entity.velocity.y *= 0.8;
+		// Taken while the position is still in the starting frame, since
+		// normalizing it across an edge moves it to the far side of the next.
+		let distance = length_2d(
+			position.x - entity.position.x,
+			position.y - entity.position.y,
+		);
+		let (position, crossing) = position.normalize_traced(&frames)?;
 
-		if entity.velocity.x.abs() < 0.00001 {
-			entity.velocity.x = 0.0;
-		}
-		if entity.velocity.y.abs() < 0.00001 {
-			entity.velocity.y = 0.0;
-		}
-		//entity.velocity.y += 0.0004;
+		Ok(Movement {
+			position,
+			crossing,
+			distance,
+			velocity,
+			grounded,
+			bounce,
+			direction_x,
+			direction_y,
+			set_direction_x,
+			set_direction_y,
+		})
 	}
 
 	pub fn tile_at_entity(&self, id: EntityId) -> Tile {
@@ -377,6 +988,152 @@ impl World {
 		*frame.tile(tx, ty)
 	}
 
+	pub fn set_tile(
+		&mut self,
+		frame_id: FrameId,
+		x: isize,
+		y: isize,
+		tile: Tile,
+	) -> Result<(), SetTileError> {
+		let w = FRAME_WIDTH as isize;
+		if x < 0 || y < 0 || x >= w || y >= w {
+			return Err(SetTileError::OutOfBounds);
+		}
+		if self.get_frame(frame_id).is_none() {
+			return Err(SetTileError::MissingFrame(frame_id));
+		}
+		if tile.is_solid() {
+			self.clear_tile_for_placement(frame_id, x, y)?;
+		}
+
+		let frame = self
+			.get_frame_mut(frame_id)
+			.ok_or(SetTileError::MissingFrame(frame_id))?;
+		if *frame.tile(x, y) != tile {
+			frame.set_tile_data(x, y, None);
+			frame.set_rotation(x, y, Angle::Clockwise0);
+		}
+		*frame.tile_mut(x, y) = tile;
+		frame.mark_changed();
+		self.change_count += 1;
+		self.activate_fluids_around(frame_id, x, y);
+		Ok(())
+	}
+
+	// Sets every tile from one corner to the other, inclusive, carrying on
+	// past any that can't be set. Those that couldn't, and why.
+	pub fn fill_rect(
+		&mut self,
+		frame_id: FrameId,
+		from: (isize, isize),
+		to: (isize, isize),
+		tile: Tile,
+	) -> Vec<((isize, isize), SetTileError)> {
+		let mut refused = Vec::new();
+		for y in from.1.min(to.1)..=from.1.max(to.1) {
+			for x in from.0.min(to.0)..=from.0.max(to.0) {
+				if let Err(error) = self.set_tile(frame_id, x, y, tile) {
+					refused.push(((x, y), error));
+				}
+			}
+		}
+		refused
+	}
+
+	// Turns the tile at `x`, `y` to face `rotation` from the way its kind
+	// does, refused like `set_tile` if that would make it solid around an
+	// entity.
+	pub fn set_tile_rotation(
+		&mut self,
+		frame_id: FrameId,
+		x: isize,
+		y: isize,
+		rotation: Angle,
+	) -> Result<(), SetTileError> {
+		let w = FRAME_WIDTH as isize;
+		if x < 0 || y < 0 || x >= w || y >= w {
+			return Err(SetTileError::OutOfBounds);
+		}
+		let tile = match self.get_frame(frame_id) {
+			Some(frame) => *frame.tile(x, y),
+			None => return Err(SetTileError::MissingFrame(frame_id)),
+		};
+		if tile.is_solid_turned(rotation) {
+			self.clear_tile_for_placement(frame_id, x, y)?;
+		}
+
+		let frame = self.get_frame_mut(frame_id).unwrap();
+		frame.set_rotation(x, y, rotation);
+		frame.mark_changed();
+		self.change_count += 1;
+		Ok(())
+	}
+
+	// Entities strictly inside a tile would be embedded in it, while those
+	// on its boundary just end up standing on or against it. Normalized
+	// positions lie within their own frame, so only entities on the tile's
+	// frame need checking.
+	fn clear_tile_for_placement(
+		&mut self,
+		frame_id: FrameId,
+		x: isize,
+		y: isize,
+	) -> Result<(), SetTileError> {
+		let left = x as Scalar * TILE_SIZE - 1.0;
+		let top = y as Scalar * TILE_SIZE - 1.0;
+		let occupants: Vec<EntityId> = self
+			.entities_in_frame(frame_id)
+			.iter()
+			.copied()
+			.filter(|&id| {
+				let p = self.get_entity(id).unwrap().position;
+				p.x > left
+					&& p.x < left + TILE_SIZE
+					&& p.y > top && p.y < top + TILE_SIZE
+			})
+			.collect();
+
+		for id in occupants {
+			// Blocks fill the tile themselves, so are never moved aside.
+			let is_block =
+				self.get_entity(id).unwrap().kind == EntityKind::Block;
+			if self.physics.push_out_of_placed_tiles == false || is_block {
+				return Err(SetTileError::Occupied(id));
+			}
+
+			// Out through whichever side is closest.
+			let p = self.get_entity(id).unwrap().position;
+			let candidates = [
+				(left, p.y),
+				(left + TILE_SIZE, p.y),
+				(p.x, top),
+				(p.x, top + TILE_SIZE),
+			];
+			let distance = |&(cx, cy): &(Scalar, Scalar)| {
+				(cx - p.x).abs() + (cy - p.y).abs()
+			};
+			let &(new_x, new_y) = candidates
+				.iter()
+				.min_by(|a, b| distance(a).partial_cmp(&distance(b)).unwrap())
+				.unwrap();
+
+			// The far side of a tile in the last row or column is on the
+			// next frame.
+			let moved = WorldPosition {
+				x: new_x,
+				y: new_y,
+				..p
+			}
+			.normalize(self)
+			.map_err(|_| SetTileError::Occupied(id))?;
+			if moved.frame_id != p.frame_id {
+				self.reindex_entity(id, p.frame_id, moved.frame_id);
+			}
+			self.get_entity_mut(id).unwrap().position = moved;
+		}
+		Ok(())
+	}
+
 	pub fn tile_index_at_entity(&self, id: EntityId) -> (isize, isize) {
 		let entity = self.get_entity(id).unwrap();
 		self.tile_index_at_position(entity.position)
@@ -387,9 +1144,9 @@ impl World {
 		position: WorldPosition,
 	) -> (isize, isize) {
 		let tx =
-			((position.x + 1.0) * FRAME_WIDTH as f32 / 2.0).floor() as isize;
+			((position.x + 1.0) * FRAME_WIDTH as Scalar / 2.0).floor() as isize;
 		let ty =
-			((position.y + 1.0) * FRAME_WIDTH as f32 / 2.0).floor() as isize;
+			((position.y + 1.0) * FRAME_WIDTH as Scalar / 2.0).floor() as isize;
 
 		(tx, ty)
 	}
@@ -399,52 +1156,23 @@ impl World {
 		origin_frame_position: FrameId,
 		x: isize,
 		y: isize,
-	) -> (FrameId, isize, isize) {
-		let origin_frame = self.get_frame(origin_frame_position).unwrap();
-		let borders = origin_frame.borders;
-
-		let w = FRAME_WIDTH as isize;
-
-		if (x >= w || x < 0) && (y >= w || y < 0)
-			|| (x >= w * 2 || x < -w * 2 || y >= w * 2 || y < -w * 2)
-		{
-			panic!(
-				"Tile index exists outside its own frame \
-				and orthgonally neighboring frames"
-			);
-		}
-
-		use Direction::*;
-		let (direction, real_x, real_y) = match (x, y) {
-			(x, y) if (x >= w) => (Right, x - w, y),
-			(x, y) if (x < 0) => (Left, x + w, y),
-			(x, y) if (y >= w) => (Down, x, y - w),
-			(x, y) if (y < 0) => (Up, x, y + w),
-			(x, y) => (Neutral, x, y),
-		};
-
-		let real_frame_position = match borders.at_direction(direction) {
-			Some(p) => p,
-			None => {
-				elog("Could not access tile index's real frame:");
-				elog(format!(
-					"{}/({},{}) -> {:?}",
-					origin_frame_position, x, y, direction
-				));
-				elog(format!("selecting from {}", borders));
-				panic!("Tile index access error");
-			}
-		}
-		.frame;
-
-		(real_frame_position, real_x, real_y)
+	) -> Option<(FrameId, isize, isize)> {
+		neighborhood::normalize_tile_index(self, origin_frame_position, x, y)
 	}
 
 	fn jump_entity(&mut self, id: EntityId) -> bool {
-		let jump_speed = 0.018;
-
-		if self.entity_grounded(id) {
-			self.get_entity_mut(id).unwrap().velocity.y = -jump_speed;
+		let jump_speed = self.physics.jump_speed;
+
+		let infinite_jumps = self.get_entity(id).unwrap().cheats.infinite_jumps;
+		if self.entity_grounded(id) || infinite_jumps {
+			let entity = self.get_entity_mut(id).unwrap();
+			// Away from whatever the entity is standing on.
+			let (x, y) = entity.gravity_dir.reverse().unit();
+			if x != 0.0 {
+				entity.velocity.x = x * jump_speed;
+			} else {
+				entity.velocity.y = y * jump_speed;
+			}
 			true
 		} else {
 			false
@@ -455,45 +1183,75 @@ impl World {
 		self.get_entity_mut(id).unwrap().velocity += vector;
 	}
 
-	fn point_contacts(&mut self, position: WorldPosition) -> Contacts {
-		let frame = self.get_frame(position.frame_id).unwrap();
-		let position = position.normalize(self);
-
-		let f = FRAME_WIDTH as f32 / 2.0;
-		let tile_x_left = (((position.x + 1.0) * f).ceil() - 1.0) as isize;
-		let tile_x_right = (((position.x + 1.0) * f).floor()) as isize;
-		let tile_y_up = (((position.y + 1.0) * f).ceil() - 1.0) as isize;
-		let tile_y_down = ((position.y + 1.0) * f).floor() as isize;
+	fn entity_grounded(&mut self, id: EntityId) -> bool {
+		let entity = self.get_entity(id).unwrap();
+		entity.grounded
+	}
 
-		let is_solid = |x, y| {
-			let (tile_frame_pos, wrapped_x, wrapped_y) =
-				self.normalize_tile_index(position.frame_id, x, y);
-			let tile_frame = self.get_frame(tile_frame_pos).unwrap();
-			let tile = tile_frame.tile(wrapped_x, wrapped_y);
-			tile.is_solid()
-		};
+	// A digest of the tiles and entities, for checking that two runs of the
+	// same world stayed in step, on the same target or different ones.
+	pub fn state_hash(&self) -> u64 {
+		use std::hash::{Hash, Hasher};
+
+		let mut hasher = StableHasher::new();
+		self.tick_count.hash(&mut hasher);
+		self.rng.state().hash(&mut hasher);
+		for frame_id in self.frame_ids() {
+			frame_id.hash(&mut hasher);
+			let frame = &self.frames[&frame_id];
+			let w = FRAME_WIDTH as isize;
+			for y in 0..w {
+				for x in 0..w {
+					frame.tile(x, y).hash(&mut hasher);
+					frame.rotation(x, y).hash(&mut hasher);
+				}
+			}
+		}
+		for id in self.entity_ids() {
+			let entity = &self.entities[&id];
+			id.hash(&mut hasher);
+			entity.kind.hash(&mut hasher);
+			entity.position.frame_id.hash(&mut hasher);
+			let v = entity.velocity;
+			let values = [entity.position.x, entity.position.y, v.x, v.y, v.z];
+			for value in values.iter() {
+				value.to_bits().hash(&mut hasher);
+			}
+			entity.health.to_bits().hash(&mut hasher);
+			entity.stamina.to_bits().hash(&mut hasher);
+			entity.grounded.hash(&mut hasher);
+			entity.gravity_dir.hash(&mut hasher);
+			entity.orientation.hash(&mut hasher);
+			entity.aim.hash(&mut hasher);
+			entity.edge_behavior.hash(&mut hasher);
+			entity.tag.map(|tag| self.tag_name(tag)).hash(&mut hasher);
+			entity.parent.hash(&mut hasher);
+			entity.trail.len().hash(&mut hasher);
+			entity.falling_off.map(|fall| fall.ticks).hash(&mut hasher);
+			for effect in entity.effects.iter() {
+				effect.kind.hash(&mut hasher);
+				effect.remaining_ticks.hash(&mut hasher);
+				effect.magnitude.to_bits().hash(&mut hasher);
+			}
+		}
+		hasher.finish()
+	}
 
-		let up_left_solid = is_solid(tile_x_left, tile_y_up);
-		let up_right_solid = is_solid(tile_x_right, tile_y_up);
-		let down_left_solid = is_solid(tile_x_left, tile_y_down);
-		let down_right_solid = is_solid(tile_x_right, tile_y_down);
+	pub fn ticks_per_step(&self) -> u32 {
+		self.ticks_per_step
+	}
 
-		// let up_left_solid = frame.tile(tile_x_left, tile_y_up).is_solid();
-		// let up_right_solid = frame.tile(tile_x_right, tile_y_up).is_solid();
-		// let down_left_solid = frame.tile(tile_x_left, tile_y_down).is_solid();
-		// let down_right_solid = frame.tile(tile_x_right, tile_y_down).is_solid();
+	pub fn set_ticks_per_step(&mut self, ticks: u32) {
+		self.ticks_per_step = ticks.clamp(1, MAX_TICKS_PER_STEP);
+	}
 
-		Contacts {
-			top_left: up_left_solid,
-			top_right: up_right_solid,
-			bottom_left: down_left_solid,
-			bottom_right: down_right_solid,
-		}
+	pub fn change_count(&self) -> u64 {
+		self.change_count
 	}
 
-	fn entity_grounded(&mut self, id: EntityId) -> bool {
-		let entity = self.get_entity(id).unwrap();
-		entity.grounded
+	// Events emitted during the most recent tick.
+	pub fn events(&self) -> &[WorldEvent] {
+		&self.events
 	}
 
 	pub fn generate_id(&mut self) -> usize {
@@ -502,12 +1260,133 @@ impl World {
 		current
 	}
 
+	pub fn insert_entity(&mut self, entity: Entity) -> EntityId {
+		let id = entity.id;
+		self.frame_entities
+			.entry(entity.position.frame_id)
+			.or_default()
+			.push(id);
+		let kind = entity.kind;
+		self.entities.insert(id, entity);
+		if kind == EntityKind::Block {
+			self.claim_tile(id);
+		}
+		self.change_count += 1;
+		id
+	}
+
+	pub fn remove_entity(&mut self, entity_id: EntityId) -> Option<Entity> {
+		if self.get_entity(entity_id)?.kind == EntityKind::Block {
+			self.release_tile(entity_id);
+			self.slides.remove(&entity_id);
+		}
+		let entity = self.entities.remove(&entity_id)?;
+		if let Some(ids) =
+			self.frame_entities.get_mut(&entity.position.frame_id)
+		{
+			ids.retain(|&id| id != entity_id);
+		}
+		if self.focus_entity == Some(entity_id) {
+			self.focus_entity = None;
+		}
+		if let Some(tag) = entity.tag {
+			self.check_group_cleared(tag);
+		}
+		if entity.persistence == Persistence::PersistForever {
+			self.forever_changes.push(ForeverChange::Removed(entity_id));
+		}
+		self.change_count += 1;
+		Some(entity)
+	}
+
+	fn reindex_entity(
+		&mut self,
+		entity_id: EntityId,
+		from: FrameId,
+		to: FrameId,
+	) {
+		if let Some(ids) = self.frame_entities.get_mut(&from) {
+			ids.retain(|&id| id != entity_id);
+		}
+		self.frame_entities.entry(to).or_default().push(entity_id);
+	}
+
+	pub fn entities_in_frame(&self, frame_id: FrameId) -> &[EntityId] {
+		match self.frame_entities.get(&frame_id) {
+			Some(ids) => ids.as_slice(),
+			None => &[],
+		}
+	}
+
+	// The entity of `kind` closest to `from` along the surface. Frames are
+	// searched outwards by link hops, stopping one hop past the first frame
+	// with a match since a closer entity can still sit just across an edge.
+	pub fn nearest_entity_of_kind(
+		&self,
+		from: WorldPosition,
+		kind: EntityKind,
+	) -> Option<EntityId> {
+		let mut nearest: Option<(Scalar, EntityId)> = None;
+		let mut found_at_depth: Option<usize> = None;
+		let mut visited = vec![from.frame_id];
+		let mut queue = VecDeque::new();
+		queue.push_back((from.frame_id, 0));
+
+		while let Some((frame_id, depth)) = queue.pop_front() {
+			if found_at_depth.is_some_and(|found| depth > found + 1) {
+				break;
+			}
+
+			for &id in self.entities_in_frame(frame_id) {
+				let entity = self.get_entity(id).unwrap();
+				if entity.kind != kind {
+					continue;
+				}
+				let distance = self.surface_distance(from, entity.position);
+				if nearest.is_none_or(|(nearest, _)| distance < nearest) {
+					nearest = Some((distance, id));
+					found_at_depth.get_or_insert(depth);
+				}
+			}
+
+			let frame = match self.get_frame(frame_id) {
+				Some(frame) => frame,
+				None => continue,
+			};
+			for &edge in Direction::iter() {
+				if let Some(link) = frame.borders.at_direction(edge) {
+					if visited.contains(&link.frame) == false {
+						visited.push(link.frame);
+						queue.push_back((link.frame, depth + 1));
+					}
+				}
+			}
+		}
+
+		nearest.map(|(_, id)| id)
+	}
+
+	pub fn coins_remaining(&self) -> usize {
+		self.entities
+			.values()
+			.filter(|entity| entity.kind == EntityKind::Coin)
+			.count()
+	}
+
 	pub fn get_entity(&self, entity_id: EntityId) -> Option<&Entity> {
 		self.entities.get(&entity_id)
 	}
 
+	pub fn entity_count(&self) -> usize {
+		self.entities.len()
+	}
+
+	// Ordered by id, so that entities update in the same order every run.
 	pub fn entity_ids(&self) -> Vec<EntityId> {
-		self.entities.iter().map(|(_, ent)| ent.id).collect()
+		let mut entity_ids: Vec<EntityId> =
+			self.entities.keys().copied().collect();
+		entity_ids.sort_by_key(|id| id.0);
+		entity_ids
 	}
 
 	pub fn get_entity_mut(
@@ -517,6 +1396,13 @@ impl World {
 		self.entities.get_mut(&entity_id)
 	}
 
+	// Ordered by id.
+	pub fn frame_ids(&self) -> Vec<FrameId> {
+		let mut frame_ids: Vec<FrameId> = self.frames.keys().copied().collect();
+		frame_ids.sort_by_key(|id| id.0);
+		frame_ids
+	}
+
 	pub fn get_frame(&self, frame_position: FrameId) -> Option<&Frame> {
 		self.frames.get(&frame_position)
 	}
@@ -568,6 +1454,52 @@ impl World {
 	}
 }
 
+// A ramp tile, which way it is turned, and the position of its top left
+// corner in frame coordinates.
+#[derive(Copy, Clone, Debug)]
+struct Slope {
+	tile: Tile,
+	rotation: Angle,
+	left: Scalar,
+	top: Scalar,
+}
+
+impl Slope {
+	// Height of the surface at `x`, continuing level past either end.
+	fn surface_at(&self, x: Scalar) -> Scalar {
+		let u = ((x - self.left) / TILE_SIZE).clamp(0.0, 1.0);
+		let height = self.tile.slope_height(self.rotation, u).unwrap_or(1.0);
+		self.top + (1.0 - height) * TILE_SIZE
+	}
+
+	// The x coordinate of the ramp's vertical side.
+	fn wall_x(&self) -> Scalar {
+		match self.tile.slope_height(self.rotation, 1.0) {
+			Some(height) if height > 0.5 => self.left + TILE_SIZE,
+			_ => self.left,
+		}
+	}
+}
+
+// The outcome of resolving one entity's movement for a tick.
+struct Movement {
+	position: WorldPosition,
+	crossing: Option<FrameCrossing>,
+	// How far along the surface the entity moved.
+	distance: Scalar,
+	velocity: Vector3,
+	grounded: bool,
+	// The spring the entity was thrown off, if it hit one.
+	bounce: Option<TileLocation>,
+	// Which way the entity was heading along each axis this tick.
+	direction_x: Direction,
+	direction_y: Direction,
+	// What to store as the entity's last movement direction along each
+	// axis.
+	set_direction_x: Direction,
+	set_direction_y: Direction,
+}
+
 pub struct Entity {
 	pub position: WorldPosition,
 	pub velocity: Vector3,
@@ -575,13 +1507,68 @@ pub struct Entity {
 	pub last_movement_direction_x: Direction,
 	pub last_movement_direction_y: Direction,
 	pub kind: EntityKind,
+	// Which way the entity faces, kept while it stands still. For the
+	// player this follows the last horizontal input rather than velocity,
+	// so being knocked back doesn't turn them around.
 	pub orientation: Direction,
+	// Which way the entity is deliberately pointing, or `Neutral` when it
+	// isn't, in which case it points where it faces.
+	pub aim: Direction,
+	// What a walker does at the end of what it walks on. Unused by other
+	// kinds.
+	pub edge_behavior: EdgeBehavior,
 	pub id: EntityId,
 	pub grounded: bool,
+	// Which way the entity falls, in its current frame's coordinates. Its
+	// feet point this way.
+	pub gravity_dir: Direction,
+	pub health: Scalar,
+	// Used up by dashing and sprinting, up to `PhysicsConfig::max_stamina`.
+	// Only the player has any use for it.
+	pub stamina: Scalar,
+	// Temporary modifiers, counted down every tick.
+	pub effects: Vec<StatusEffect>,
+	// A color to draw the entity in for the given ticks instead of its own,
+	// counted down with its effects. Only for show, so never saved.
+	pub render_tint: Option<([u8; 3], u32)>,
+	// A layer to draw the entity on instead of its kind's, such as to put
+	// it behind everything else. Only for show, so never saved.
+	pub render_layer: Option<i8>,
+	pub animation: AnimationState,
+	pub cheats: Cheats,
+	// The group the entity belongs to, for levels to refer to it by.
+	pub tag: Option<TagId>,
+	// What becomes of the entity's changes when the player dies or the level
+	// is loaded again.
+	pub persistence: Persistence,
+	// Which other entities it touches.
+	pub channels: CollisionChannels,
+	// The part of a worm that a segment follows. Unused by other kinds.
+	pub parent: Option<EntityId>,
+	// Where a worm part has been, oldest first, for the segment behind it
+	// to go through. Only as long as that segment needs.
+	pub trail: VecDeque<TrailPoint>,
+	// Set once the entity has gone over an open edge, until it is gone.
+	pub falling_off: Option<FallOff>,
 	//pub contacts: Contacts,
 }
 
 impl Entity {
+	// Where the entity's actions are directed: its aim if it has one, and
+	// otherwise the way it faces.
+	pub fn pointing(&self) -> Direction {
+		match self.aim {
+			Direction::Neutral => self.orientation,
+			aim => aim,
+		}
+	}
+
+	// Entities on higher layers are drawn over those on lower ones.
+	pub fn drawn_layer(&self) -> i8 {
+		self.render_layer
+			.unwrap_or_else(|| self.kind.render_layer())
+	}
+
 	pub fn new_player(world: &mut World, frame_id: FrameId) -> Self {
 		let position = WorldPosition {
 			frame_id,
@@ -598,14 +1585,241 @@ impl Entity {
 			last_movement_direction_x: Direction::Neutral,
 			last_movement_direction_y: Direction::Neutral,
 			kind: EntityKind::Player,
-			orientation: Direction::Up,
+			orientation: Direction::Right,
+			aim: Direction::Neutral,
+			edge_behavior: EdgeBehavior::default(),
 			id,
 			grounded: false,
+			gravity_dir: Direction::Down,
+			health: MAX_HEALTH,
+			stamina: world.physics.max_stamina,
+			effects: Vec::new(),
+			render_tint: None,
+			render_layer: None,
+			animation: AnimationState::new(),
+			cheats: Cheats::default(),
+			tag: None,
+			persistence: Persistence::default_for(EntityKind::Player),
+			channels: CollisionChannels::default_for(EntityKind::Player),
+			parent: None,
+			trail: VecDeque::new(),
+			falling_off: None,
 			//contacts,
 		}
 	}
+
+	pub fn new_coin(world: &mut World, position: WorldPosition) -> Self {
+		Self::new_pickup(world, EntityKind::Coin, position)
+	}
+
+	// Walks left and right by itself.
+	pub fn new_walker(
+		world: &mut World,
+		position: WorldPosition,
+		edge_behavior: EdgeBehavior,
+	) -> Self {
+		let id = EntityId(world.generate_id());
+
+		Self {
+			position,
+			velocity: Vector3::zero(),
+			last_movement_direction: Direction::Neutral,
+			last_movement_direction_x: Direction::Neutral,
+			last_movement_direction_y: Direction::Neutral,
+			kind: EntityKind::Walker,
+			orientation: Direction::Right,
+			aim: Direction::Neutral,
+			edge_behavior,
+			id,
+			grounded: false,
+			gravity_dir: Direction::Down,
+			health: MAX_HEALTH,
+			stamina: world.physics.max_stamina,
+			effects: Vec::new(),
+			render_tint: None,
+			render_layer: None,
+			animation: AnimationState::new(),
+			cheats: Cheats::default(),
+			tag: None,
+			persistence: Persistence::default_for(EntityKind::Walker),
+			channels: CollisionChannels::default_for(EntityKind::Walker),
+			parent: None,
+			trail: VecDeque::new(),
+			falling_off: None,
+		}
+	}
+
+	// Fills the tile at `x`, `y`.
+	pub fn new_block(
+		world: &mut World,
+		frame_id: FrameId,
+		x: isize,
+		y: isize,
+	) -> Self {
+		let id = EntityId(world.generate_id());
+		let position = WorldPosition {
+			frame_id,
+			x: -1.0 + (x as Scalar + 0.5) * TILE_SIZE,
+			y: -1.0 + (y as Scalar + 0.5) * TILE_SIZE,
+		};
+
+		Self {
+			position,
+			velocity: Vector3::zero(),
+			last_movement_direction: Direction::Neutral,
+			last_movement_direction_x: Direction::Neutral,
+			last_movement_direction_y: Direction::Neutral,
+			kind: EntityKind::Block,
+			orientation: Direction::Up,
+			aim: Direction::Neutral,
+			edge_behavior: EdgeBehavior::default(),
+			id,
+			grounded: false,
+			gravity_dir: Direction::Down,
+			health: MAX_HEALTH,
+			stamina: world.physics.max_stamina,
+			effects: Vec::new(),
+			render_tint: None,
+			render_layer: None,
+			animation: AnimationState::new(),
+			cheats: Cheats::default(),
+			tag: None,
+			persistence: Persistence::default_for(EntityKind::Block),
+			channels: CollisionChannels::default_for(EntityKind::Block),
+			parent: None,
+			trail: VecDeque::new(),
+			falling_off: None,
+		}
+	}
+
+	// A stationary entity the player collects by touching it.
+	pub fn new_pickup(
+		world: &mut World,
+		kind: EntityKind,
+		position: WorldPosition,
+	) -> Self {
+		let id = EntityId(world.generate_id());
+
+		Self {
+			position,
+			velocity: Vector3::zero(),
+			last_movement_direction: Direction::Neutral,
+			last_movement_direction_x: Direction::Neutral,
+			last_movement_direction_y: Direction::Neutral,
+			kind,
+			orientation: Direction::Up,
+			aim: Direction::Neutral,
+			edge_behavior: EdgeBehavior::default(),
+			id,
+			grounded: false,
+			gravity_dir: Direction::Down,
+			health: MAX_HEALTH,
+			stamina: world.physics.max_stamina,
+			effects: Vec::new(),
+			render_tint: None,
+			render_layer: None,
+			animation: AnimationState::new(),
+			cheats: Cheats::default(),
+			tag: None,
+			persistence: Persistence::default_for(kind),
+			channels: CollisionChannels::default_for(kind),
+			parent: None,
+			trail: VecDeque::new(),
+			falling_off: None,
+		}
+	}
 }
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum EntityKind {
 	Player,
+	Coin,
+	// Flips the gravity of whoever collects it.
+	GravityOrb,
+	// Paces back and forth, for stress testing.
+	Walker,
+	// Speeds up whoever collects it for a while.
+	SpeedBoost,
+	// Makes stamina come back faster for whoever collects it for a while.
+	Energizer,
+	// Fills a tile, which it makes solid, and can be pushed a tile at a time.
+	Block,
+	// The head of a worm, which crawls over every surface with its segments
+	// behind it, biting the player. It can only be hurt at its tail.
+	Worm,
+	// Follows the part of a worm in front of it. Only ever made along with
+	// its worm.
+	WormSegment,
+}
+
+impl EntityKind {
+	// As levels name them.
+	pub fn name(&self) -> &'static str {
+		match self {
+			EntityKind::Player => "player",
+			EntityKind::Coin => "coin",
+			EntityKind::GravityOrb => "gravity_orb",
+			EntityKind::Walker => "walker",
+			EntityKind::SpeedBoost => "speed_boost",
+			EntityKind::Energizer => "energizer",
+			EntityKind::Block => "block",
+			EntityKind::Worm => "worm",
+			EntityKind::WormSegment => "worm_segment",
+		}
+	}
+
+	// The layer entities of this kind are drawn on, so that what matters
+	// most is never hidden by what is lying around.
+	pub fn render_layer(&self) -> i8 {
+		match self {
+			EntityKind::Block => 0,
+			EntityKind::Coin
+			| EntityKind::GravityOrb
+			| EntityKind::SpeedBoost
+			| EntityKind::Energizer => 1,
+			EntityKind::Walker | EntityKind::Worm | EntityKind::WormSegment => {
+				2
+			}
+			EntityKind::Player => 3,
+		}
+	}
+
+	// Every kind that levels and the editor can place. The player only
+	// comes from a level's spawn.
+	pub fn placeable() -> &'static [Self] {
+		use EntityKind::*;
+		&[Coin, GravityOrb, Walker, SpeedBoost, Energizer, Block, Worm]
+	}
+
+	pub fn from_name(name: &str) -> Option<Self> {
+		use EntityKind::*;
+		[
+			Player,
+			Coin,
+			GravityOrb,
+			Walker,
+			SpeedBoost,
+			Energizer,
+			Block,
+			Worm,
+			WormSegment,
+		]
+		.iter()
+		.find(|kind| kind.name() == name)
+		.copied()
+	}
+
+	pub fn is_pickup(&self) -> bool {
+		match self {
+			EntityKind::Player
+			| EntityKind::Walker
+			| EntityKind::Block
+			| EntityKind::Worm
+			| EntityKind::WormSegment => false,
+			EntityKind::Coin
+			| EntityKind::GravityOrb
+			| EntityKind::SpeedBoost
+			| EntityKind::Energizer => true,
+		}
+	}
 }
@@ -0,0 +1,71 @@
+use std::collections::HashSet;
+
+use super::types::*;
+use super::World;
+
+// How much of a frame is simulated each tick. Only frames near the focus
+// entity are worth the cost, so the rest wait until it comes back near them.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FrameActivity {
+	// The focus entity's frame and the frames linked to it, ticked in full.
+	Active,
+	// Everything further away. Entities stay frozen where they are and
+	// fluids keep their pending moves, all carrying on once the frame is
+	// active again. Nothing is removed for being far off, as everything
+	// there is something the level placed and means to stay.
+	Dormant,
+}
+
+impl FrameActivity {
+	pub fn name(&self) -> &'static str {
+		match self {
+			FrameActivity::Active => "active",
+			FrameActivity::Dormant => "dormant",
+		}
+	}
+}
+
+impl World {
+	// Worked out again at the start of every tick from where the focus
+	// entity is, so it only ever depends on the world itself and replays
+	// stay in step.
+	pub(super) fn update_activity(&mut self) {
+		let center = self
+			.focus_entity
+			.and_then(|id| self.get_entity(id))
+			.map(|entity| entity.position.frame_id);
+		let active = center.map(|center| {
+			let mut active = HashSet::new();
+			active.insert(center);
+			if let Some(frame) = self.get_frame(center) {
+				for &edge in Direction::iter() {
+					if let Some(link) = frame.borders.at_direction(edge) {
+						active.insert(link.frame);
+					}
+				}
+			}
+			active
+		});
+		self.active_frames = active;
+	}
+
+	// Every frame is active when there is no focus entity to be near.
+	pub fn frame_activity(&self, frame_id: FrameId) -> FrameActivity {
+		match &self.active_frames {
+			Some(active) if active.contains(&frame_id) == false => {
+				FrameActivity::Dormant
+			}
+			_ => FrameActivity::Active,
+		}
+	}
+
+	pub(super) fn entity_is_active(&self, id: EntityId) -> bool {
+		let frame_id = self.get_entity(id).unwrap().position.frame_id;
+		self.frame_activity(frame_id) == FrameActivity::Active
+	}
+
+	// How many entities the last tick updated, leaving out frozen ones.
+	pub fn ticked_entity_count(&self) -> usize {
+		self.ticked_entities
+	}
+}
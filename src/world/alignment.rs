@@ -0,0 +1,282 @@
+use std::collections::HashSet;
+
+use super::types::*;
+use super::{TileLocation, World};
+
+// How the tiles either side of a frame edge are made to line up, so that
+// crossing it doesn't walk the player into a pit or a wall that the other
+// side gave no sign of.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AlignmentRules {
+	// The chance that the tile across from a solid border tile is made
+	// solid too, carrying floors and walls over the edge.
+	pub solid_match_chance: f32,
+	// Places along each linked edge where the tiles on both sides are open,
+	// so that every frame can be entered from each of its neighbors.
+	pub min_corridors: usize,
+}
+
+impl Default for AlignmentRules {
+	fn default() -> Self {
+		Self {
+			solid_match_chance: 0.6,
+			min_corridors: 2,
+		}
+	}
+}
+
+// A frame edge that doesn't meet the alignment rules.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SeamIssue {
+	// Fewer places to cross the edge than the rules ask for.
+	TooFewCorridors {
+		frame: FrameId,
+		edge: Direction,
+		corridors: usize,
+	},
+	// An open tile on an edge that is walled in on every side, across the
+	// edge included, so can never be got into.
+	SealedPocket(TileLocation),
+}
+
+impl std::fmt::Display for SeamIssue {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match *self {
+			SeamIssue::TooFewCorridors {
+				frame,
+				edge,
+				corridors,
+			} => write!(
+				f,
+				"{} {} has only {} open tile{} to cross by",
+				frame,
+				edge.name(),
+				corridors,
+				if corridors == 1 { "" } else { "s" }
+			),
+			SeamIssue::SealedPocket((frame, x, y)) => write!(
+				f,
+				"open tile at {}/({},{}) is walled in along the edge",
+				frame, x, y
+			),
+		}
+	}
+}
+
+impl World {
+	// Makes the tiles along every linked edge line up by `rules`, with the
+	// world's own random numbers so the result follows from its seed.
+	// Meant for generated worlds, whose frames are made without regard for
+	// their neighbors. A tile is never made solid where that would wall in
+	// an open tile next to it.
+	pub fn align_edges(&mut self, rules: &AlignmentRules) {
+		for (frame_id, edge) in self.seams() {
+			let pairs = self.seam_pairs(frame_id, edge);
+			for &(a, b) in pairs.iter() {
+				for &(solid, across) in &[(a, b), (b, a)] {
+					if self.tile_open(solid) || self.tile_open(across) == false
+					{
+						continue;
+					}
+					if self.rng.chance(rules.solid_match_chance) {
+						self.fill_unless_sealing(across);
+					}
+				}
+			}
+
+			let mut closed: Vec<(TileLocation, TileLocation)> = pairs
+				.iter()
+				.copied()
+				.filter(|&(a, b)| {
+					self.tile_open(a) == false || self.tile_open(b) == false
+				})
+				.collect();
+			let corridors = pairs.len() - closed.len();
+			let missing = rules.min_corridors.saturating_sub(corridors);
+			self.rng.shuffle(&mut closed);
+			for &(a, b) in closed.iter().take(missing) {
+				for &(frame_id, x, y) in &[a, b] {
+					let _ = self.set_tile(frame_id, x, y, Tile::Empty);
+				}
+			}
+		}
+	}
+
+	// Every linked edge with fewer corridors than `rules` asks for, then
+	// every open tile along a linked edge that is walled in, each in frame
+	// order. Chance plays no part.
+	pub fn seam_issues(&self, rules: &AlignmentRules) -> Vec<SeamIssue> {
+		let mut issues = Vec::new();
+		let mut pockets = Vec::new();
+		for (frame, edge) in self.seams() {
+			let pairs = self.seam_pairs(frame, edge);
+			let corridors = pairs
+				.iter()
+				.filter(|&&(a, b)| self.tile_open(a) && self.tile_open(b))
+				.count();
+			if corridors < rules.min_corridors {
+				issues.push(SeamIssue::TooFewCorridors {
+					frame,
+					edge,
+					corridors,
+				});
+			}
+			for &(a, b) in pairs.iter() {
+				for &tile in &[a, b] {
+					if self.is_sealed(tile) && pockets.contains(&tile) == false
+					{
+						pockets.push(tile);
+					}
+				}
+			}
+		}
+		issues.extend(pockets.into_iter().map(SeamIssue::SealedPocket));
+		issues
+	}
+
+	// Each linked edge once, from whichever of its frames has the lower id.
+	fn seams(&self) -> Vec<(FrameId, Direction)> {
+		use Direction::*;
+		let mut seen = HashSet::new();
+		let mut seams = Vec::new();
+		for frame_id in self.frame_ids() {
+			let borders = &self.frames[&frame_id].borders;
+			for &edge in &[Up, Down, Left, Right] {
+				let link = match borders.at_direction(edge) {
+					Some(link) => link,
+					None => continue,
+				};
+				if seen.contains(&(frame_id, edge))
+					|| self.get_frame(link.frame).is_none()
+				{
+					continue;
+				}
+				seen.insert((link.frame, link.entry_edge));
+				seams.push((frame_id, edge));
+			}
+		}
+		seams
+	}
+
+	// The tiles along the edge paired with those they touch across it.
+	fn seam_pairs(
+		&self,
+		frame_id: FrameId,
+		edge: Direction,
+	) -> Vec<(TileLocation, TileLocation)> {
+		let tiles = match self.edge_tiles(frame_id, edge) {
+			Some(tiles) => tiles,
+			None => return Vec::new(),
+		};
+		tiles
+			.map(|((x, y), across, (ax, ay))| {
+				let own = (frame_id, x as isize, y as isize);
+				(own, (across, ax as isize, ay as isize))
+			})
+			.collect()
+	}
+
+	fn tile_open(&self, (frame_id, x, y): TileLocation) -> bool {
+		self.get_frame(frame_id).is_some_and(|frame| {
+			frame.tile(x, y).is_solid_turned(frame.rotation(x, y)) == false
+		})
+	}
+
+	// Whether the tile is open with nothing open on any side of it, looking
+	// across frame edges.
+	fn is_sealed(&self, tile: TileLocation) -> bool {
+		self.tile_open(tile)
+			&& Direction::iter()
+				.filter(|&&direction| direction != Direction::Neutral)
+				.all(|&direction| match self.tile_beyond(tile, direction) {
+					Some(next) => self.tile_open(next) == false,
+					None => true,
+				})
+	}
+
+	// Makes the tile solid, putting it back if that leaves a tile beside it
+	// walled in.
+	fn fill_unless_sealing(&mut self, (frame_id, x, y): TileLocation) {
+		let before = match self.get_frame(frame_id) {
+			Some(frame) => *frame.tile(x, y),
+			None => return,
+		};
+		if self.set_tile(frame_id, x, y, Tile::Solid).is_err() {
+			return;
+		}
+		let sealing = Direction::iter()
+			.filter(|&&direction| direction != Direction::Neutral)
+			.filter_map(|&direction| {
+				self.tile_beyond((frame_id, x, y), direction)
+			})
+			.any(|next| self.is_sealed(next));
+		if sealing {
+			let _ = self.set_tile(frame_id, x, y, before);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const SEEDS: [u64; 4] = [0, 1, 7, 12345];
+
+	fn tiles(world: &World) -> Vec<Vec<Tile>> {
+		let frames = world.frame_ids().into_iter();
+		frames
+			.map(|id| world.get_frame(id).unwrap().tiles().to_vec())
+			.collect()
+	}
+
+	fn too_few_corridors(world: &World, rules: &AlignmentRules) -> usize {
+		let issues = world.seam_issues(rules).into_iter();
+		issues
+			.filter(|issue| match issue {
+				SeamIssue::TooFewCorridors { .. } => true,
+				_ => false,
+			})
+			.count()
+	}
+
+	#[test]
+	fn every_edge_of_a_generated_cube_has_its_corridors() {
+		let rules = AlignmentRules::default();
+		for &seed in SEEDS.iter() {
+			let world = World::with_seed(seed);
+			assert_eq!(world.seams().len(), 12);
+			assert_eq!(too_few_corridors(&world, &rules), 0, "seed {}", seed);
+		}
+	}
+
+	// With nothing left to chance, a second pass finds nothing to change.
+	#[test]
+	fn aligning_twice_is_aligning_once() {
+		let rules = AlignmentRules {
+			solid_match_chance: 1.0,
+			min_corridors: 3,
+		};
+		for &seed in SEEDS.iter() {
+			let mut world = World::with_seed(seed);
+			world.align_edges(&rules);
+			let once = tiles(&world);
+			world.align_edges(&rules);
+			assert!(tiles(&world) == once, "seed {}", seed);
+			assert_eq!(too_few_corridors(&world, &rules), 0, "seed {}", seed);
+		}
+	}
+
+	// Chance only ever fills tiles, never takes away a corridor.
+	#[test]
+	fn aligning_again_keeps_the_corridors() {
+		let rules = AlignmentRules::default();
+		for &seed in SEEDS.iter() {
+			let mut world = World::with_seed(seed);
+			for _ in 0..3 {
+				world.align_edges(&rules);
+				let missing = too_few_corridors(&world, &rules);
+				assert_eq!(missing, 0, "seed {}", seed);
+			}
+		}
+	}
+}
@@ -0,0 +1,222 @@
+use super::events::WorldEvent;
+use super::types::*;
+use super::World;
+use crate::geometry::Scalar;
+
+// Speed across its gravity past which a grounded entity counts as walking.
+const WALK_ANIMATION_SPEED: Scalar = 0.0005;
+
+// What an entity is doing, as far as drawing it goes. Which frames each kind
+// has, and how they look, is up to whatever draws it; here they are only
+// counted.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AnimationKind {
+	Idle,
+	Walk,
+	Airborne,
+	// Squashed for a moment after touching down, then back to whatever the
+	// entity was doing.
+	Land,
+}
+
+impl AnimationKind {
+	// World ticks each frame of the animation is shown for.
+	pub fn frame_ticks(&self) -> u32 {
+		match self {
+			AnimationKind::Idle => 12,
+			AnimationKind::Walk => 8,
+			AnimationKind::Airborne => 8,
+			AnimationKind::Land => 6,
+		}
+	}
+
+	// Whether the animation plays its first frame once and then ends,
+	// rather than looping until the entity does something else.
+	pub fn is_transient(&self) -> bool {
+		*self == AnimationKind::Land
+	}
+}
+
+// Only for show, so never saved. `frame` counts up without wrapping, for
+// the drawing code to take modulo however many frames it has.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct AnimationState {
+	pub kind: AnimationKind,
+	pub frame: usize,
+	pub ticks_in_frame: u32,
+}
+
+impl AnimationState {
+	pub fn new() -> Self {
+		Self {
+			kind: AnimationKind::Idle,
+			frame: 0,
+			ticks_in_frame: 0,
+		}
+	}
+
+	// Moves on by `ticks`, given what the entity's movement looks like now
+	// and whether it touched down this tick. Changing animation starts the
+	// new one from its first frame.
+	pub fn advance(
+		&mut self,
+		movement: AnimationKind,
+		landed: bool,
+		ticks: u32,
+	) {
+		let kind = if landed {
+			AnimationKind::Land
+		} else if self.kind.is_transient()
+			&& movement != AnimationKind::Airborne
+		{
+			self.kind
+		} else {
+			movement
+		};
+		if kind != self.kind || landed {
+			*self = Self {
+				kind,
+				..Self::new()
+			};
+		}
+
+		self.ticks_in_frame += ticks;
+		let frame_ticks = self.kind.frame_ticks();
+		while self.ticks_in_frame >= frame_ticks {
+			self.ticks_in_frame -= frame_ticks;
+			self.frame += 1;
+		}
+		if self.kind.is_transient() && self.frame > 0 {
+			*self = Self {
+				kind: movement,
+				..Self::new()
+			};
+		}
+	}
+}
+
+impl Default for AnimationState {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl World {
+	// Called once the entity has moved this tick.
+	pub(super) fn update_animation(&mut self, id: EntityId, ticks: u32) {
+		let landed = self.events.iter().any(|event| match *event {
+			WorldEvent::EntityLanded { entity, .. } => entity == id,
+			_ => false,
+		});
+		let entity = match self.get_entity_mut(id) {
+			Some(entity) => entity,
+			None => return,
+		};
+
+		let (gx, gy) = entity.gravity_dir.unit();
+		let across = entity.velocity.x * gy - entity.velocity.y * gx;
+		let movement = if entity.grounded == false {
+			AnimationKind::Airborne
+		} else if across.abs() > WALK_ANIMATION_SPEED {
+			AnimationKind::Walk
+		} else {
+			AnimationKind::Idle
+		};
+		entity.animation.advance(movement, landed, ticks);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use AnimationKind::*;
+
+	fn advance_for(
+		state: &mut AnimationState,
+		movement: AnimationKind,
+		ticks: u32,
+	) {
+		for _ in 0..ticks {
+			state.advance(movement, false, 1);
+		}
+	}
+
+	#[test]
+	fn frames_count_up_at_the_animations_own_rate() {
+		let mut state = AnimationState::new();
+		advance_for(&mut state, Idle, 25);
+		assert_eq!(
+			(state.kind, state.frame, state.ticks_in_frame),
+			(Idle, 2, 1)
+		);
+
+		let mut state = AnimationState::new();
+		state.advance(Walk, false, 20);
+		assert_eq!(
+			(state.kind, state.frame, state.ticks_in_frame),
+			(Walk, 2, 4)
+		);
+	}
+
+	#[test]
+	fn changing_what_the_entity_does_starts_from_the_first_frame() {
+		let mut state = AnimationState::new();
+		advance_for(&mut state, Idle, 30);
+		state.advance(Walk, false, 1);
+		assert_eq!(
+			(state.kind, state.frame, state.ticks_in_frame),
+			(Walk, 0, 1)
+		);
+	}
+
+	#[test]
+	fn idle_then_walking_then_in_the_air_then_landing_then_idle() {
+		let mut state = AnimationState::new();
+		assert_eq!(state.kind, Idle);
+		advance_for(&mut state, Walk, 10);
+		assert_eq!(state.kind, Walk);
+		advance_for(&mut state, Airborne, 10);
+		assert_eq!(state.kind, Airborne);
+
+		// Back on the ground, squashed for a frame's worth of ticks first.
+		state.advance(Idle, true, 1);
+		assert_eq!((state.kind, state.frame), (Land, 0));
+		advance_for(&mut state, Idle, Land.frame_ticks() - 2);
+		assert_eq!(state.kind, Land);
+		advance_for(&mut state, Idle, 1);
+		assert_eq!(state, AnimationState::new());
+	}
+
+	#[test]
+	fn landing_gives_way_to_walking_once_over() {
+		let mut state = AnimationState::new();
+		state.advance(Walk, true, 1);
+		advance_for(&mut state, Walk, Land.frame_ticks() - 2);
+		assert_eq!(state.kind, Land);
+		advance_for(&mut state, Walk, 1);
+		assert_eq!((state.kind, state.frame), (Walk, 0));
+	}
+
+	#[test]
+	fn leaving_the_ground_cuts_a_landing_short() {
+		let mut state = AnimationState::new();
+		state.advance(Idle, true, 1);
+		state.advance(Airborne, false, 1);
+		assert_eq!(
+			(state.kind, state.frame, state.ticks_in_frame),
+			(Airborne, 0, 1)
+		);
+	}
+
+	#[test]
+	fn landing_again_restarts_the_squash() {
+		let mut state = AnimationState::new();
+		state.advance(Idle, true, 1);
+		advance_for(&mut state, Idle, 3);
+		state.advance(Idle, true, 1);
+		assert_eq!(
+			(state.kind, state.frame, state.ticks_in_frame),
+			(Land, 0, 1)
+		);
+	}
+}
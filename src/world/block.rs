@@ -0,0 +1,217 @@
+use super::events::WorldEvent;
+use super::types::*;
+use super::{EntityKind, World, CONTACT_PROBE, TILE_SIZE};
+use crate::geometry::Scalar;
+
+// Ticks a block takes to move one tile, whether pushed or falling.
+const BLOCK_SLIDE_TICKS: u32 = 8;
+
+// A block that has just moved a tile, drawn easing in from where it was.
+#[derive(Copy, Clone, Debug)]
+pub(super) struct Slide {
+	// From the block's position to where it came from, in its frame's
+	// coordinates.
+	pub(super) offset_x: Scalar,
+	pub(super) offset_y: Scalar,
+	pub(super) remaining_ticks: u32,
+}
+
+impl World {
+	// Makes the tile a block is in solid.
+	pub(super) fn claim_tile(&mut self, id: EntityId) {
+		let (frame, x, y) = self.tile_location_at_entity(id);
+		if let Some(frame) = self.get_frame_mut(frame) {
+			frame.set_owner(x, y, Some(id));
+		}
+	}
+
+	pub(super) fn release_tile(&mut self, id: EntityId) {
+		let (frame, x, y) = self.tile_location_at_entity(id);
+		if let Some(frame) = self.get_frame_mut(frame) {
+			if frame.owner(x, y) == Some(id) {
+				frame.set_owner(x, y, None);
+			}
+		}
+	}
+
+	// Whether something solid is `distance` from a position towards
+	// `direction`, which may be across a frame edge.
+	fn solid_towards(
+		&self,
+		position: WorldPosition,
+		direction: Direction,
+		distance: Scalar,
+	) -> bool {
+		let (dx, dy) = direction.unit();
+		let probe = WorldPosition {
+			x: position.x + dx * distance,
+			y: position.y + dy * distance,
+			..position
+		};
+		let probe = match probe.normalize(self) {
+			Ok(probe) => probe,
+			Err(_) => return true,
+		};
+		let (x, y) = self.tile_index_at_position(probe);
+		self.get_frame(probe.frame_id).unwrap().is_solid(x, y)
+	}
+
+	// Gravity is disabled, so entities at rest aren't grounded. Pushing
+	// only needs something underfoot.
+	fn entity_supported(&self, id: EntityId) -> bool {
+		let entity = self.get_entity(id).unwrap();
+		let distance = match entity.kind {
+			EntityKind::Block => TILE_SIZE / 2.0 + CONTACT_PROBE,
+			_ => CONTACT_PROBE,
+		};
+		self.solid_towards(entity.position, entity.gravity_dir, distance)
+	}
+
+	// Pushes the block right against an entity along `direction`, in the
+	// entity's frame coordinates, a tile further.
+	pub(super) fn push_block(
+		&mut self,
+		pusher: EntityId,
+		direction: Direction,
+	) {
+		if self.entity_supported(pusher) == false {
+			return;
+		}
+
+		// Just ahead of the entity, and just up from the floor it stands on.
+		let entity = self.get_entity(pusher).unwrap();
+		let p = entity.position;
+		let (dx, dy) = direction.unit();
+		let (gx, gy) = entity.gravity_dir.unit();
+		let probe = WorldPosition {
+			x: p.x + (dx - gx) * CONTACT_PROBE,
+			y: p.y + (dy - gy) * CONTACT_PROBE,
+			..p
+		};
+		let (probe, crossing) = match probe.normalize_traced(self) {
+			Ok(result) => result,
+			Err(_) => return,
+		};
+		let (x, y) = self.tile_index_at_position(probe);
+		let block = match self.get_frame(probe.frame_id).unwrap().owner(x, y) {
+			Some(block) => block,
+			None => return,
+		};
+		if self.slides.contains_key(&block) {
+			return;
+		}
+
+		let direction = match crossing {
+			Some(crossing) => direction.rotated(crossing.angle),
+			None => direction,
+		};
+		self.slide_block(block, direction);
+	}
+
+	// Moves a block one tile along `direction`, in its frame's coordinates,
+	// if nothing is in the way. Carried across frame edges like any other
+	// entity, so its gravity turns with the frame.
+	fn slide_block(&mut self, id: EntityId, direction: Direction) -> bool {
+		let entity = self.get_entity(id).unwrap();
+		let position = entity.position;
+		let (dx, dy) = direction.unit();
+		let target = WorldPosition {
+			x: position.x + dx * TILE_SIZE,
+			y: position.y + dy * TILE_SIZE,
+			..position
+		};
+		let (target, crossing) = match target.normalize_traced(self) {
+			Ok(result) => result,
+			Err(_) => return false,
+		};
+
+		let (x, y) = self.tile_index_at_position(target);
+		if self.get_frame(target.frame_id).unwrap().is_solid(x, y) {
+			return false;
+		}
+		// Only what the block would collide with stops it. Anything else,
+		// such as a pickup, ends up inside it.
+		let channels = entity.channels;
+		let blocked =
+			self.entities_in_frame(target.frame_id)
+				.iter()
+				.any(|&other| {
+					let other_entity = self.get_entity(other).unwrap();
+					let p = other_entity.position;
+					other != id
+						&& channels.blocks(&other_entity.channels)
+						&& self.tile_index_at_position(p) == (x, y)
+				});
+		if blocked {
+			return false;
+		}
+
+		self.release_tile(id);
+		self.get_entity_mut(id).unwrap().position = target;
+		let mut direction = direction;
+		if let Some(crossing) = crossing {
+			let entity = self.get_entity_mut(id).unwrap();
+			entity.gravity_dir = entity.gravity_dir.rotated(crossing.angle);
+			direction = direction.rotated(crossing.angle);
+			self.reindex_entity(id, crossing.from, crossing.to);
+			self.events.push(WorldEvent::EntityCrossedFrame {
+				entity: id,
+				from: crossing.from,
+				to: crossing.to,
+				exit_edge: crossing.exit_edge,
+				entry_edge: crossing.entry_edge,
+			});
+		}
+		self.claim_tile(id);
+		self.change_count += 1;
+
+		let (dx, dy) = direction.unit();
+		self.slides.insert(
+			id,
+			Slide {
+				offset_x: -dx * TILE_SIZE,
+				offset_y: -dy * TILE_SIZE,
+				remaining_ticks: BLOCK_SLIDE_TICKS,
+			},
+		);
+
+		let tile = *self.get_frame(target.frame_id).unwrap().tile(x, y);
+		self.events.push(WorldEvent::TileEntered {
+			entity: id,
+			frame: target.frame_id,
+			x: x as usize,
+			y: y as usize,
+			tile,
+		});
+		true
+	}
+
+	// Blocks move a tile at a time instead of by velocity, falling whenever
+	// they aren't sliding and nothing holds them up.
+	pub(super) fn update_block(&mut self, id: EntityId) {
+		if let Some(slide) = self.slides.get_mut(&id) {
+			slide.remaining_ticks =
+				slide.remaining_ticks.saturating_sub(self.ticks_per_step);
+			if slide.remaining_ticks == 0 {
+				self.slides.remove(&id);
+			}
+			return;
+		}
+		if self.entity_supported(id) == false {
+			let gravity_dir = self.get_entity(id).unwrap().gravity_dir;
+			self.slide_block(id, gravity_dir);
+		}
+	}
+
+	// How far from its position a block should be drawn while it slides.
+	pub fn slide_offset(&self, id: EntityId) -> (Scalar, Scalar) {
+		match self.slides.get(&id) {
+			Some(slide) => {
+				let t = slide.remaining_ticks as Scalar
+					/ BLOCK_SLIDE_TICKS as Scalar;
+				(slide.offset_x * t, slide.offset_y * t)
+			}
+			None => (0.0, 0.0),
+		}
+	}
+}
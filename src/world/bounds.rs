@@ -0,0 +1,70 @@
+use super::types::*;
+use super::{EntityKind, World, WorldEvent};
+use crate::geometry::{Scalar, Vector3};
+use crate::prelude::*;
+
+// Taken by a player put back at the spawn point after leaving the world.
+const VOID_DAMAGE: Scalar = 2.0;
+
+impl World {
+	// Whether the entity is on a frame that exists, at a real position.
+	pub(super) fn entity_in_bounds(&self, id: EntityId) -> bool {
+		let position = self.get_entity(id).unwrap().position;
+		position.x.is_finite()
+			&& position.y.is_finite()
+			&& self.get_frame(position.frame_id).is_some()
+	}
+
+	// Where players leaving the world are put back: the spawn point if its
+	// frame still exists, or else the same default spot levels use.
+	pub(super) fn respawn_position(&self) -> Option<WorldPosition> {
+		let spawn = self
+			.spawn
+			.filter(|spawn| self.get_frame(spawn.frame_id).is_some());
+		spawn.or_else(|| {
+			let frame_id = self.frames.keys().min_by_key(|id| id.0).copied()?;
+			Some(WorldPosition {
+				frame_id,
+				x: 0.3,
+				y: 0.1,
+			})
+		})
+	}
+
+	// For an entity somewhere it can't be, such as off an unlinked edge or
+	// on a frame that was removed. Players are put back at the spawn point
+	// and hurt; anything else is removed.
+	pub(super) fn recover_out_of_bounds(&mut self, id: EntityId, reason: &str) {
+		let entity = self.get_entity(id).unwrap();
+		let position = entity.position;
+		let is_player =
+			entity.kind == EntityKind::Player || self.focus_entity == Some(id);
+		elog(format!(
+			"Entity {} out of bounds at {:?}: {}",
+			id.0, position, reason
+		));
+		self.events.push(WorldEvent::EntityOutOfBounds {
+			entity: id,
+			position,
+		});
+
+		if is_player == false {
+			self.remove_entity(id);
+			return;
+		}
+		let spawn = match self.respawn_position() {
+			Some(spawn) => spawn,
+			None => {
+				elog("No frame to respawn on".to_string());
+				return;
+			}
+		};
+		self.reindex_entity(id, position.frame_id, spawn.frame_id);
+		let entity = self.get_entity_mut(id).unwrap();
+		entity.position = spawn;
+		entity.velocity = Vector3::zero();
+		entity.gravity_dir = Direction::Down;
+		entity.grounded = false;
+		self.hit_entity(id, VOID_DAMAGE);
+	}
+}
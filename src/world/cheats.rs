@@ -0,0 +1,94 @@
+use super::types::*;
+use super::{Movement, World};
+use crate::geometry::{length_2d, Scalar, Vector3};
+
+// Assists for building and debugging levels. Never written to levels or
+// saves, so loading one always turns them all off.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Cheats {
+	// Damage is ignored.
+	pub god_mode: bool,
+	// Tiles are passed through and the entity moves freely in its frame,
+	// still crossing edges to the next one.
+	pub noclip: bool,
+	// Jumping works in midair.
+	pub infinite_jumps: bool,
+}
+
+impl Cheats {
+	pub fn any(&self) -> bool {
+		self.god_mode || self.noclip || self.infinite_jumps
+	}
+
+	pub fn names() -> &'static [&'static str] {
+		&["god", "noclip", "infinite_jumps"]
+	}
+
+	pub fn get(&self, name: &str) -> Option<bool> {
+		match name {
+			"god" => Some(self.god_mode),
+			"noclip" => Some(self.noclip),
+			"infinite_jumps" => Some(self.infinite_jumps),
+			_ => None,
+		}
+	}
+
+	// Whether `name` is a cheat.
+	pub fn set(&mut self, name: &str, on: bool) -> bool {
+		match name {
+			"god" => self.god_mode = on,
+			"noclip" => self.noclip = on,
+			"infinite_jumps" => self.infinite_jumps = on,
+			_ => return false,
+		}
+		true
+	}
+}
+
+impl World {
+	// `resolve_movement` for an entity with noclip on: the whole velocity
+	// is applied with no contacts, slopes or snapping. An edge with no
+	// link stops the entity where it was instead.
+	pub(super) fn resolve_noclip_movement(&self, id: EntityId) -> Movement {
+		let entity = self.get_entity(id).unwrap();
+		let velocity = entity.velocity;
+		let displacement = velocity * self.ticks_per_step as Scalar;
+		let direction_x = match velocity.x {
+			dx if dx > 0.0 => Direction::Right,
+			dx if dx < 0.0 => Direction::Left,
+			_ => Direction::Neutral,
+		};
+		let direction_y = match velocity.y {
+			dy if dy > 0.0 => Direction::Down,
+			dy if dy < 0.0 => Direction::Up,
+			_ => Direction::Neutral,
+		};
+
+		let moved = WorldPosition {
+			x: entity.position.x + displacement.x,
+			y: entity.position.y + displacement.y,
+			..entity.position
+		};
+		let distance = length_2d(displacement.x, displacement.y);
+		let (position, crossing, velocity, distance) =
+			match moved.normalize_traced(self) {
+				Ok((position, crossing)) => {
+					(position, crossing, velocity, distance)
+				}
+				Err(_) => (entity.position, None, Vector3::zero(), 0.0),
+			};
+
+		Movement {
+			position,
+			crossing,
+			distance,
+			velocity,
+			grounded: false,
+			bounce: None,
+			direction_x,
+			direction_y,
+			set_direction_x: direction_x,
+			set_direction_y: direction_y,
+		}
+	}
+}
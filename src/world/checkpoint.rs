@@ -0,0 +1,344 @@
+use std::collections::HashMap;
+
+use super::events::WorldEvent;
+use super::tags::TagId;
+use super::types::*;
+use super::{
+	CollisionChannels, EdgeBehavior, Entity, EntityKind, TileLocation, World,
+	MAX_HEALTH,
+};
+use crate::geometry::{Scalar, Vector3};
+
+// How long something in the world stays the way the player left it.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Persistence {
+	// Put back the way it was at the last checkpoint when the player dies,
+	// like enemies and the player's own tile edits.
+	ResetOnDeath,
+	// Kept through deaths until the level is loaded again, like collected
+	// coins. As the player's tile edits always were before anything could
+	// reset them.
+	#[default]
+	PersistForLevel,
+	// Kept even through loading the level or a save again, like a powerup
+	// that is never given twice.
+	PersistForever,
+}
+
+impl Persistence {
+	pub fn default_for(kind: EntityKind) -> Self {
+		match kind {
+			EntityKind::Walker | EntityKind::Block => Persistence::ResetOnDeath,
+			EntityKind::Player
+			| EntityKind::Coin
+			| EntityKind::GravityOrb
+			| EntityKind::SpeedBoost
+			| EntityKind::Energizer
+			| EntityKind::Worm
+			| EntityKind::WormSegment => Persistence::PersistForLevel,
+		}
+	}
+
+	pub fn name(&self) -> &'static str {
+		match self {
+			Persistence::ResetOnDeath => "death",
+			Persistence::PersistForLevel => "level",
+			Persistence::PersistForever => "forever",
+		}
+	}
+
+	pub fn from_name(name: &str) -> Option<Self> {
+		[
+			Persistence::ResetOnDeath,
+			Persistence::PersistForLevel,
+			Persistence::PersistForever,
+		]
+		.iter()
+		.find(|persistence| persistence.name() == name)
+		.copied()
+	}
+}
+
+// A change for whatever loads the level again to make as well, told by
+// `take_forever_changes`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ForeverChange {
+	Removed(EntityId),
+	Tile(TileLocation, Tile),
+	Rotation(TileLocation, Angle),
+}
+
+// What is kept of an entity to put it back as it was.
+#[derive(Clone, Debug)]
+struct EntitySnapshot {
+	id: EntityId,
+	kind: EntityKind,
+	position: WorldPosition,
+	velocity: Vector3,
+	orientation: Direction,
+	edge_behavior: EdgeBehavior,
+	gravity_dir: Direction,
+	health: Scalar,
+	tag: Option<TagId>,
+	channels: CollisionChannels,
+}
+
+// Where the player comes back after dying, and everything that resets
+// with them. Only what resets on death is kept, rather than the whole
+// world, so anything else stays as it is.
+#[derive(Clone, Debug)]
+pub(super) struct Checkpoint {
+	position: WorldPosition,
+	gravity_dir: Direction,
+	entities: Vec<EntitySnapshot>,
+	// What each tile edited since has to go back to, and which way it
+	// faced.
+	tiles: HashMap<TileLocation, (Tile, Angle)>,
+}
+
+impl World {
+	// Makes where the entity is now the place it comes back to, told as an
+	// event.
+	pub(super) fn reach_checkpoint(&mut self, id: EntityId) {
+		if self.take_checkpoint(id) {
+			self.events
+				.push(WorldEvent::CheckpointReached { entity: id });
+		}
+	}
+
+	// Whether there was such an entity to take it from.
+	pub(super) fn take_checkpoint(&mut self, id: EntityId) -> bool {
+		let (position, gravity_dir) = match self.get_entity(id) {
+			Some(entity) => (entity.position, entity.gravity_dir),
+			None => return false,
+		};
+		let mut entities: Vec<EntitySnapshot> = self
+			.entities
+			.values()
+			.filter(|e| e.persistence == Persistence::ResetOnDeath)
+			.map(|e| EntitySnapshot {
+				id: e.id,
+				kind: e.kind,
+				position: e.position,
+				velocity: e.velocity,
+				orientation: e.orientation,
+				edge_behavior: e.edge_behavior,
+				gravity_dir: e.gravity_dir,
+				health: e.health,
+				tag: e.tag,
+				channels: e.channels,
+			})
+			.collect();
+		entities.sort_by_key(|snapshot| snapshot.id.0);
+		self.checkpoint = Some(Checkpoint {
+			position,
+			gravity_dir,
+			entities,
+			tiles: HashMap::new(),
+		});
+		true
+	}
+
+	// Where the player would come back to if they died now, if not the
+	// spawn point.
+	pub fn checkpoint_position(&self) -> Option<WorldPosition> {
+		self.checkpoint
+			.as_ref()
+			.map(|checkpoint| checkpoint.position)
+	}
+
+	// Keeps an entity's edit of a tile from `before` to what it is now for
+	// as long as the level says edits last. Only the first change since the
+	// checkpoint is kept to put back, as that is what the tile was at it.
+	pub(super) fn record_tile_edit(
+		&mut self,
+		location: TileLocation,
+		before: (Tile, Angle),
+	) {
+		let (frame, x, y) = location;
+		let (tile, rotation) = match self.get_frame(frame) {
+			Some(frame) => (*frame.tile(x, y), frame.rotation(x, y)),
+			None => return,
+		};
+		match self.level.edit_persistence {
+			Persistence::ResetOnDeath => {
+				if let Some(checkpoint) = self.checkpoint.as_mut() {
+					checkpoint.tiles.entry(location).or_insert(before);
+				}
+			}
+			Persistence::PersistForLevel => {}
+			Persistence::PersistForever => {
+				if tile != before.0 {
+					self.forever_changes
+						.push(ForeverChange::Tile(location, tile));
+				}
+				if rotation != before.1 {
+					self.forever_changes
+						.push(ForeverChange::Rotation(location, rotation));
+				}
+			}
+		}
+	}
+
+	// Changes made since last called that should still be there the next
+	// time the level is loaded, in the order they were made.
+	pub fn take_forever_changes(&mut self) -> Vec<ForeverChange> {
+		std::mem::take(&mut self.forever_changes)
+	}
+
+	// Makes changes kept from an earlier time the level was played. Entities
+	// are only removed if they are still meant to stay removed, in case the
+	// level has changed since.
+	pub fn apply_forever_changes(&mut self, changes: &[ForeverChange]) {
+		for &change in changes {
+			match change {
+				ForeverChange::Removed(id) => {
+					let persistence =
+						self.get_entity(id).map(|e| e.persistence);
+					if persistence == Some(Persistence::PersistForever) {
+						self.remove_entity(id);
+					}
+				}
+				ForeverChange::Tile((frame, x, y), tile) => {
+					let _ = self.set_tile(frame, x, y, tile);
+				}
+				ForeverChange::Rotation((frame, x, y), rotation) => {
+					let _ = self.set_tile_rotation(frame, x, y, rotation);
+				}
+			}
+		}
+		// Already kept.
+		self.forever_changes.clear();
+	}
+
+	// Players that run out of health come back at the last checkpoint, and
+	// anything else is removed.
+	pub(super) fn update_deaths(&mut self) {
+		for id in self.entity_ids() {
+			let entity = self.get_entity(id).unwrap();
+			if entity.health > 0.0 {
+				continue;
+			}
+			let is_player = entity.kind == EntityKind::Player
+				|| self.focus_entity == Some(id);
+			self.events.push(WorldEvent::EntityDied { entity: id });
+			if is_player {
+				self.respawn(id);
+			} else {
+				self.remove_entity(id);
+			}
+		}
+	}
+
+	fn respawn(&mut self, id: EntityId) {
+		// Falling back to the spawn point if the checkpoint's frame is gone.
+		let checkpoint = self.checkpoint.take().filter(|checkpoint| {
+			self.get_frame(checkpoint.position.frame_id).is_some()
+		});
+		let checkpoint = match checkpoint {
+			Some(checkpoint) => checkpoint,
+			None => match self.respawn_position() {
+				Some(position) => Checkpoint {
+					position,
+					gravity_dir: Direction::Down,
+					entities: Vec::new(),
+					tiles: HashMap::new(),
+				},
+				None => return,
+			},
+		};
+
+		// Before the tiles, so that any put back where the player died
+		// aren't refused for being in the way.
+		self.move_entity_to(id, checkpoint.position);
+		let max_stamina = self.physics.max_stamina;
+		let player = self.get_entity_mut(id).unwrap();
+		player.velocity = Vector3::zero();
+		player.gravity_dir = checkpoint.gravity_dir;
+		player.grounded = false;
+		player.health = MAX_HEALTH;
+		player.stamina = max_stamina;
+		player.effects.clear();
+		player.falling_off = None;
+
+		for snapshot in &checkpoint.entities {
+			self.restore_entity(snapshot);
+		}
+		let mut tiles: Vec<_> = checkpoint.tiles.iter().collect();
+		tiles.sort_by_key(|&(&(frame, x, y), _)| (frame.0, y, x));
+		for (&(frame, x, y), &(tile, rotation)) in tiles {
+			// Left as it is if something has since moved into its way.
+			if self.set_tile(frame, x, y, tile).is_ok() {
+				let _ = self.set_tile_rotation(frame, x, y, rotation);
+			}
+		}
+
+		self.checkpoint = Some(Checkpoint {
+			tiles: HashMap::new(),
+			..checkpoint
+		});
+	}
+
+	// Brought back if it was removed, with the same id so that anything
+	// referring to it still does.
+	fn restore_entity(&mut self, snapshot: &EntitySnapshot) {
+		if self.get_frame(snapshot.position.frame_id).is_none() {
+			return;
+		}
+		if self.get_entity(snapshot.id).is_none() {
+			let p = snapshot.position;
+			let entity = match snapshot.kind {
+				// A segment that is gone has nothing left to follow.
+				EntityKind::Player | EntityKind::WormSegment => return,
+				EntityKind::Worm => Entity::new_worm(self, p),
+				EntityKind::Walker => {
+					Entity::new_walker(self, p, snapshot.edge_behavior)
+				}
+				EntityKind::Block => {
+					let (x, y) = self.tile_index_at_position(p);
+					Entity::new_block(self, p.frame_id, x, y)
+				}
+				kind => Entity::new_pickup(self, kind, p),
+			};
+			self.insert_entity(Entity {
+				id: snapshot.id,
+				persistence: Persistence::ResetOnDeath,
+				tag: snapshot.tag,
+				channels: snapshot.channels,
+				..entity
+			});
+		}
+
+		self.move_entity_to(snapshot.id, snapshot.position);
+		let entity = self.get_entity_mut(snapshot.id).unwrap();
+		entity.velocity = snapshot.velocity;
+		entity.orientation = snapshot.orientation;
+		entity.gravity_dir = snapshot.gravity_dir;
+		entity.grounded = false;
+		entity.health = snapshot.health;
+		entity.effects.clear();
+	}
+
+	// Puts the entity straight somewhere else, keeping the frame index and
+	// any tile a block fills up to date.
+	pub(super) fn move_entity_to(
+		&mut self,
+		id: EntityId,
+		position: WorldPosition,
+	) {
+		let entity = self.get_entity(id).unwrap();
+		let (from, is_block) =
+			(entity.position.frame_id, entity.kind == EntityKind::Block);
+		if is_block {
+			self.release_tile(id);
+			self.slides.remove(&id);
+		}
+		self.get_entity_mut(id).unwrap().position = position;
+		if from != position.frame_id {
+			self.reindex_entity(id, from, position.frame_id);
+		}
+		if is_block {
+			self.claim_tile(id);
+		}
+	}
+}
@@ -0,0 +1,163 @@
+use super::EntityKind;
+
+// Groups of entities, as bits, for deciding which of them touch.
+pub const CHANNEL_PLAYER: u8 = 1 << 0;
+pub const CHANNEL_ENEMY: u8 = 1 << 1;
+pub const CHANNEL_PICKUP: u8 = 1 << 2;
+pub const CHANNEL_BLOCK: u8 = 1 << 3;
+pub const CHANNEL_ALL: u8 =
+	CHANNEL_PLAYER | CHANNEL_ENEMY | CHANNEL_PICKUP | CHANNEL_BLOCK;
+
+// As levels name them.
+const CHANNEL_NAMES: &[(&str, u8)] = &[
+	("player", CHANNEL_PLAYER),
+	("enemy", CHANNEL_ENEMY),
+	("pickup", CHANNEL_PICKUP),
+	("block", CHANNEL_BLOCK),
+];
+
+// Which entities an entity touches. Two entities only touch when each is
+// in a channel the other collides with.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CollisionChannels {
+	// The channels the entity is in.
+	pub category: u8,
+	pub collides_with: u8,
+	// Whether touching the entity only counts as overlapping it, as with
+	// collecting a pickup, without it ever getting in the way.
+	pub sensor: bool,
+}
+
+impl CollisionChannels {
+	// Enemies pass through each other, so that one never pushes another off
+	// a ledge, and pickups only ever touch the player.
+	pub fn default_for(kind: EntityKind) -> Self {
+		let (category, collides_with, sensor) = match kind {
+			EntityKind::Player => (CHANNEL_PLAYER, CHANNEL_ALL, false),
+			EntityKind::Walker | EntityKind::Worm | EntityKind::WormSegment => {
+				(CHANNEL_ENEMY, CHANNEL_ALL & !CHANNEL_ENEMY, false)
+			}
+			EntityKind::Block => (CHANNEL_BLOCK, CHANNEL_ALL, false),
+			EntityKind::Coin
+			| EntityKind::GravityOrb
+			| EntityKind::SpeedBoost
+			| EntityKind::Energizer => (CHANNEL_PICKUP, CHANNEL_PLAYER, true),
+		};
+		Self {
+			category,
+			collides_with,
+			sensor,
+		}
+	}
+
+	pub fn touches(&self, other: &Self) -> bool {
+		self.collides_with & other.category != 0
+			&& other.collides_with & self.category != 0
+	}
+
+	// Whether the two touch and one stands in the other's way, rather than
+	// only overlapping it.
+	pub fn blocks(&self, other: &Self) -> bool {
+		self.touches(other) && self.sensor == false && other.sensor == false
+	}
+}
+
+// Channels as levels write them: names joined by `+`, or `all` or `none`.
+pub fn channels_from_names(names: &str) -> Option<u8> {
+	match names {
+		"all" => return Some(CHANNEL_ALL),
+		"none" => return Some(0),
+		_ => {}
+	}
+	let mut bits = 0;
+	for name in names.split('+') {
+		let (_, bit) =
+			CHANNEL_NAMES.iter().find(|(other, _)| *other == name)?;
+		bits |= bit;
+	}
+	Some(bits)
+}
+
+pub fn channel_names(bits: u8) -> String {
+	match bits {
+		CHANNEL_ALL => return "all".to_string(),
+		0 => return "none".to_string(),
+		_ => {}
+	}
+	let names: Vec<&str> = CHANNEL_NAMES
+		.iter()
+		.filter(|(_, bit)| bits & bit != 0)
+		.map(|(name, _)| *name)
+		.collect();
+	names.join("+")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn of(kind: EntityKind) -> CollisionChannels {
+		CollisionChannels::default_for(kind)
+	}
+
+	#[test]
+	fn touching_goes_both_ways_or_not_at_all() {
+		let one_way = CollisionChannels {
+			category: CHANNEL_ENEMY,
+			collides_with: CHANNEL_PLAYER,
+			sensor: false,
+		};
+		let deaf = CollisionChannels {
+			collides_with: 0,
+			..of(EntityKind::Player)
+		};
+		assert!(one_way.touches(&of(EntityKind::Player)));
+		assert!(of(EntityKind::Player).touches(&one_way));
+		assert!(one_way.touches(&of(EntityKind::Block)) == false);
+		assert!(of(EntityKind::Block).touches(&one_way) == false);
+		assert!(deaf.touches(&of(EntityKind::Walker)) == false);
+		assert!(of(EntityKind::Walker).touches(&deaf) == false);
+	}
+
+	#[test]
+	fn enemies_pass_through_each_other_but_not_the_player() {
+		let walker = of(EntityKind::Walker);
+		assert!(walker.touches(&of(EntityKind::Worm)) == false);
+		assert!(walker.touches(&walker) == false);
+		assert!(walker.blocks(&of(EntityKind::Player)));
+		assert!(walker.blocks(&of(EntityKind::Block)));
+	}
+
+	#[test]
+	fn pickups_touch_only_the_player_and_never_block() {
+		let player = of(EntityKind::Player);
+		for &kind in &[
+			EntityKind::Coin,
+			EntityKind::GravityOrb,
+			EntityKind::SpeedBoost,
+			EntityKind::Energizer,
+		] {
+			let pickup = of(kind);
+			assert!(pickup.touches(&player), "{:?}", kind);
+			assert!(pickup.blocks(&player) == false, "{:?}", kind);
+			assert!(player.blocks(&pickup) == false, "{:?}", kind);
+			assert!(pickup.touches(&of(EntityKind::Walker)) == false);
+			assert!(pickup.touches(&of(EntityKind::Block)) == false);
+		}
+	}
+
+	#[test]
+	fn channel_names_read_back_as_the_same_bits() {
+		for bits in 0..=CHANNEL_ALL {
+			let names = channel_names(bits);
+			assert_eq!(channels_from_names(&names), Some(bits), "{}", names);
+		}
+		assert_eq!(channel_names(CHANNEL_ALL), "all");
+		assert_eq!(channel_names(0), "none");
+		let bits = CHANNEL_PLAYER | CHANNEL_BLOCK;
+		assert_eq!(channel_names(bits), "player+block");
+		assert_eq!(channels_from_names("block+player"), Some(bits));
+		assert_eq!(channels_from_names("player+ghost"), None);
+		assert_eq!(channels_from_names(""), None);
+	}
+}
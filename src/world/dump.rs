@@ -0,0 +1,222 @@
+use std::fmt::Write;
+
+use super::types::*;
+use super::{Direction, World};
+
+// Bumped whenever a line changes meaning, so that dumps from different
+// builds are never read as the same state.
+pub const DUMP_VERSION: u32 = 3;
+
+// A snapshot of the world as plain text, for reading out, streaming or
+// diffing rather than loading. Each line is a keyword followed by fields
+// separated by spaces:
+//
+//   dump <version>
+//   tick <count>
+//   rng <state>
+//   level <name>
+//   frame <id> <orientation> <visited>
+//       Followed by FRAME_WIDTH rows of tiles, as `Frame::to_ascii` gives
+//       them, each after `row `.
+//   link <frame> <edge> <frame> <edge>
+//   rotation <frame> <x> <y> <quarter turns>
+//   sign <frame> <x> <y> <text>
+//   squash <frame> <x> <y> <remaining ticks>
+//   fluid_pending <frame> <x> <y>
+//   entity <id> <kind> <frame> <x> <y> <vx> <vy> <health>
+//       <stamina>
+//   entity_state <id> <gravity> <facing> <grounded> <tag>
+//   effect <entity> <status> <remaining ticks> <magnitude>
+//   slide <entity> <offset x> <offset y> <remaining ticks>
+//   checkpoint <frame> <x> <y>
+//   stat <name> <value>
+//
+// Everything is written in the same order for the same state, and scalars
+// exactly, so two dumps differ only where their worlds do. Values missing
+// are written as `-`.
+impl World {
+	pub fn dump_state(&self) -> String {
+		let mut out = String::new();
+		writeln!(out, "dump {}", DUMP_VERSION).unwrap();
+		writeln!(out, "tick {}", self.tick_count).unwrap();
+		writeln!(out, "rng {:016x}", self.rng.state()).unwrap();
+		let name = self.level.name.as_deref().unwrap_or("-");
+		writeln!(out, "level {}", name).unwrap();
+
+		for frame_id in self.frame_ids() {
+			let frame = &self.frames[&frame_id];
+			writeln!(
+				out,
+				"frame {} {} {}",
+				frame_id.0,
+				frame.orientation.name(),
+				frame.visited
+			)
+			.unwrap();
+			for row in frame.to_ascii().lines() {
+				writeln!(out, "row {}", row).unwrap();
+			}
+			for &edge in Direction::iter() {
+				if edge == Direction::Neutral {
+					continue;
+				}
+				if let Some(link) = frame.borders.at_direction(edge) {
+					writeln!(
+						out,
+						"link {} {} {} {}",
+						frame_id.0,
+						edge.name(),
+						link.frame.0,
+						link.entry_edge.name()
+					)
+					.unwrap();
+				}
+			}
+
+			let mut rotations: Vec<_> = frame.rotation_entries().collect();
+			rotations.sort_by_key(|&((x, y), _)| (y, x));
+			for ((x, y), rotation) in rotations {
+				let turns = rotation.quarter_turns();
+				writeln!(out, "rotation {} {} {} {}", frame_id.0, x, y, turns)
+					.unwrap();
+			}
+			let mut entries: Vec<_> = frame.tile_data_entries().collect();
+			entries.sort_by_key(|&((x, y), _)| (y, x));
+			for ((x, y), data) in entries {
+				match data {
+					TileData::Text(text) => {
+						writeln!(
+							out,
+							"sign {} {} {} {:?}",
+							frame_id.0, x, y, text
+						)
+						.unwrap();
+					}
+					TileData::Squash(ticks) => {
+						writeln!(
+							out,
+							"squash {} {} {} {}",
+							frame_id.0, x, y, ticks
+						)
+						.unwrap();
+					}
+				}
+			}
+			for &(y, x) in frame.active_tiles() {
+				writeln!(out, "fluid_pending {} {} {}", frame_id.0, x, y)
+					.unwrap();
+			}
+		}
+
+		for id in self.entity_ids() {
+			let e = &self.entities[&id];
+			let p = e.position;
+			writeln!(
+				out,
+				"entity {} {} {} {:?} {:?} {:?} {:?} {:?} {:?}",
+				id.0,
+				e.kind.name(),
+				p.frame_id.0,
+				p.x,
+				p.y,
+				e.velocity.x,
+				e.velocity.y,
+				e.health,
+				e.stamina
+			)
+			.unwrap();
+			let tag = e.tag.map_or("-", |tag| self.tag_name(tag));
+			writeln!(
+				out,
+				"entity_state {} {} {} {} {}",
+				id.0,
+				e.gravity_dir.name(),
+				e.orientation.name(),
+				e.grounded,
+				tag
+			)
+			.unwrap();
+			for effect in &e.effects {
+				writeln!(
+					out,
+					"effect {} {} {} {:?}",
+					id.0,
+					effect.kind.name(),
+					effect.remaining_ticks,
+					effect.magnitude
+				)
+				.unwrap();
+			}
+		}
+
+		let mut slides: Vec<_> = self.slides.iter().collect();
+		slides.sort_by_key(|&(id, _)| id.0);
+		for (id, slide) in slides {
+			writeln!(
+				out,
+				"slide {} {:?} {:?} {}",
+				id.0, slide.offset_x, slide.offset_y, slide.remaining_ticks
+			)
+			.unwrap();
+		}
+		if let Some(p) = self.checkpoint_position() {
+			writeln!(out, "checkpoint {} {:?} {:?}", p.frame_id.0, p.x, p.y)
+				.unwrap();
+		}
+
+		let stats = self.stats();
+		writeln!(out, "stat ticks {}", stats.ticks).unwrap();
+		writeln!(out, "stat distance {:?}", stats.distance).unwrap();
+		writeln!(out, "stat tiles_placed {}", stats.tiles_placed).unwrap();
+		writeln!(out, "stat tiles_removed {}", stats.tiles_removed).unwrap();
+		writeln!(out, "stat jumps {}", stats.jumps).unwrap();
+		writeln!(out, "stat frame_crossings {}", stats.frame_crossings)
+			.unwrap();
+		writeln!(out, "stat damage_taken {:?}", stats.damage_taken).unwrap();
+		writeln!(out, "stat coins {}", stats.coins_collected).unwrap();
+		out
+	}
+}
+
+// Where two dumps first disagree, numbered from one. A line is `None` where
+// that dump has already ended.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DumpDifference {
+	pub line: usize,
+	pub left: Option<String>,
+	pub right: Option<String>,
+}
+
+impl std::fmt::Display for DumpDifference {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		let show = |line: &Option<String>| {
+			line.clone().unwrap_or_else(|| "<end of dump>".to_string())
+		};
+		write!(
+			f,
+			"dumps differ at line {}:\n  {}\n  {}",
+			self.line,
+			show(&self.left),
+			show(&self.right)
+		)
+	}
+}
+
+// `None` if the dumps are the same.
+pub fn dump_difference(left: &str, right: &str) -> Option<DumpDifference> {
+	let (mut a, mut b) = (left.lines(), right.lines());
+	let mut line = 1;
+	loop {
+		match (a.next(), b.next()) {
+			(None, None) => return None,
+			(l, r) if l != r => {
+				return Some(DumpDifference {
+					line,
+					left: l.map(str::to_string),
+					right: r.map(str::to_string),
+				});
+			}
+			_ => line += 1,
+		}
+	}
+}
@@ -0,0 +1,302 @@
+use super::frame::FrameLink;
+use super::types::*;
+use super::{World, FRAME_WIDTH};
+use crate::geometry::Scalar;
+
+// How things carried across frame edges are turned. Coordinates, tile
+// indices and directions all leave a frame through `exit_edge` and come out
+// of the linked frame's `link.entry_edge`, facing back into it.
+
+// The rotation that takes things in the exited frame's coordinates into
+// the entered frame's, for a crossing through `exit_edge` along `link`. Its
+// `negative` takes them back.
+pub fn compose_rotation(exit_edge: Direction, link: FrameLink) -> Angle {
+	exit_edge.angle_to(link.entry_edge.reverse())
+}
+
+// Turns a point about the frame's center.
+pub fn rotate_point(x: Scalar, y: Scalar, angle: Angle) -> (Scalar, Scalar) {
+	match angle {
+		Angle::Clockwise0 => (x, y),
+		Angle::Clockwise90 => (-y, x),
+		Angle::Clockwise180 => (-x, -y),
+		Angle::Clockwise270 => (y, -x),
+	}
+}
+
+// A position past `exit_edge` in the exited frame's coordinates, in the
+// entered frame's. It is only moved across the one edge, so may still be
+// outside the entered frame if it was more than a frame past the edge.
+pub fn transform_position_across(
+	exit_edge: Direction,
+	link: FrameLink,
+	x: Scalar,
+	y: Scalar,
+) -> (Scalar, Scalar) {
+	let (dx, dy) = exit_edge.unit();
+	let (x, y) = (x - dx * 2.0, y - dy * 2.0);
+	rotate_point(x, y, compose_rotation(exit_edge, link))
+}
+
+// `transform_position_across` for tile indices, which are turned about the
+// center of the frame's tile grid rather than its corner.
+pub fn transform_tile_across(
+	exit_edge: Direction,
+	link: FrameLink,
+	x: isize,
+	y: isize,
+) -> (isize, isize) {
+	let w = FRAME_WIDTH as isize;
+	let (dx, dy) = exit_edge.unit();
+	let (x, y) = (x - dx as isize * w, y - dy as isize * w);
+	// Doubled and centered, so that every tile's center is a whole number
+	// and rotating about the grid's center stays exact.
+	let (cx, cy) = (2 * x - (w - 1), 2 * y - (w - 1));
+	let (cx, cy) = match compose_rotation(exit_edge, link) {
+		Angle::Clockwise0 => (cx, cy),
+		Angle::Clockwise90 => (-cy, cx),
+		Angle::Clockwise180 => (-cx, -cy),
+		Angle::Clockwise270 => (cy, -cx),
+	};
+	((cx + w - 1) / 2, (cy + w - 1) / 2)
+}
+
+// A tile's x and y within its frame.
+pub type TileIndex = (usize, usize);
+
+impl World {
+	// Each tile along the frame's `edge`, in order of increasing x or y,
+	// with the tile it touches across the edge. `None` if the edge isn't
+	// linked to a frame that exists, or is `Neutral`.
+	pub fn edge_tiles(
+		&self,
+		frame: FrameId,
+		edge: Direction,
+	) -> Option<impl Iterator<Item = (TileIndex, FrameId, TileIndex)>> {
+		if edge == Direction::Neutral {
+			return None;
+		}
+		let link = self.get_frame(frame)?.borders.at_direction(edge)?;
+		self.get_frame(link.frame)?;
+		let w = FRAME_WIDTH as isize;
+		let (dx, dy) = edge.unit();
+		let (dx, dy) = (dx as isize, dy as isize);
+		Some((0..w).map(move |i| {
+			// Along the edge, then just past it.
+			let (x, y) = match edge {
+				Direction::Up => (i, 0),
+				Direction::Down => (i, w - 1),
+				Direction::Left => (0, i),
+				_ => (w - 1, i),
+			};
+			let (nx, ny) = transform_tile_across(edge, link, x + dx, y + dy);
+			let own = (x as usize, y as usize);
+			(own, link.frame, (nx as usize, ny as usize))
+		}))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const EDGES: [Direction; 4] = [
+		Direction::Up,
+		Direction::Down,
+		Direction::Left,
+		Direction::Right,
+	];
+
+	fn link(entry_edge: Direction) -> FrameLink {
+		FrameLink {
+			frame: FrameId::new(1),
+			entry_edge,
+		}
+	}
+
+	// Every exit edge with every entry edge, and the link back the other way.
+	fn pairs() -> impl Iterator<Item = (Direction, Direction)> {
+		EDGES
+			.iter()
+			.flat_map(|&exit| EDGES.iter().map(move |&entry| (exit, entry)))
+	}
+
+	fn close(a: (Scalar, Scalar), b: (Scalar, Scalar)) -> bool {
+		(a.0 - b.0).abs() < 1e-5 && (a.1 - b.1).abs() < 1e-5
+	}
+
+	// Leaving through the exit edge is going in through the entry edge, and
+	// coming back undoes the turn.
+	#[test]
+	fn the_rotation_across_turns_the_exit_into_the_entry() {
+		for (exit, entry) in pairs() {
+			let turn = compose_rotation(exit, link(entry));
+			assert_eq!(exit.rotated(turn), entry.reverse(), "{:?}", exit);
+			let back = compose_rotation(entry, link(exit));
+			assert_eq!(turn.compose(back), Angle::Clockwise0);
+			assert_eq!(back, turn.negative());
+		}
+	}
+
+	#[test]
+	fn composing_rotations_across_edges_is_associative() {
+		let turns: Vec<_> = pairs()
+			.map(|(exit, entry)| compose_rotation(exit, link(entry)))
+			.collect();
+		for &a in turns.iter() {
+			for &b in turns.iter() {
+				for &c in turns.iter() {
+					assert_eq!(
+						a.compose(b).compose(c),
+						a.compose(b.compose(c))
+					);
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn rotating_a_point_turns_it_as_the_directions_turn() {
+		for (exit, entry) in pairs() {
+			let turn = compose_rotation(exit, link(entry));
+			let (x, y) = exit.unit();
+			assert!(close(rotate_point(x, y, turn), entry.reverse().unit()));
+			// Rotating by each of two angles in turn is rotating by both.
+			for &other in Angle::iter() {
+				let (x, y) = (0.3, -0.8);
+				let (tx, ty) = rotate_point(x, y, turn);
+				assert!(close(
+					rotate_point(tx, ty, other),
+					rotate_point(x, y, turn.compose(other))
+				));
+			}
+		}
+	}
+
+	// A point just past the exit edge comes in just past the entry edge,
+	// and anything taken across and back is where it started.
+	#[test]
+	fn positions_land_past_the_entry_edge_and_come_back() {
+		for (exit, entry) in pairs() {
+			let (ex, ey) = exit.unit();
+			let (nx, ny) = entry.unit();
+			let turn = compose_rotation(exit, link(entry));
+			for &along in [-0.9, 0.0, 0.4].iter() {
+				let (ax, ay) = exit.rotated(Angle::Clockwise90).unit();
+				let (x, y) = (ex * 1.1 + ax * along, ey * 1.1 + ay * along);
+				let (to_x, to_y) =
+					transform_position_across(exit, link(entry), x, y);
+				let (bx, by) =
+					exit.rotated(Angle::Clockwise90).rotated(turn).unit();
+				let expected = (nx * 0.9 + bx * along, ny * 0.9 + by * along);
+				assert!(
+					close((to_x, to_y), expected),
+					"{:?} into {:?}: ({}, {}) went to ({}, {})",
+					exit,
+					entry,
+					x,
+					y,
+					to_x,
+					to_y
+				);
+
+				let (back_x, back_y) =
+					transform_position_across(entry, link(exit), to_x, to_y);
+				assert!(close((back_x, back_y), (x, y)));
+			}
+		}
+	}
+
+	// Each tile just past the exit edge is one of those along the entry
+	// edge, every one a different one, and taken back it is where it was.
+	#[test]
+	fn tiles_land_along_the_entry_edge_and_come_back() {
+		let w = FRAME_WIDTH as isize;
+		for (exit, entry) in pairs() {
+			let (dx, dy) = exit.unit();
+			let (dx, dy) = (dx as isize, dy as isize);
+			let mut landed = Vec::new();
+			for i in 0..w {
+				let (x, y) = match exit {
+					Direction::Up => (i, 0),
+					Direction::Down => (i, w - 1),
+					Direction::Left => (0, i),
+					_ => (w - 1, i),
+				};
+				let (px, py) = (x + dx, y + dy);
+				let (nx, ny) = transform_tile_across(exit, link(entry), px, py);
+				let on_entry_edge = match entry {
+					Direction::Up => ny == 0,
+					Direction::Down => ny == w - 1,
+					Direction::Left => nx == 0,
+					_ => nx == w - 1,
+				};
+				assert!(
+					on_entry_edge
+						&& (0..w).contains(&nx)
+						&& (0..w).contains(&ny),
+					"{:?} into {:?}: ({}, {}) went to ({}, {})",
+					exit,
+					entry,
+					px,
+					py,
+					nx,
+					ny
+				);
+				landed.push((nx, ny));
+
+				let back = transform_tile_across(entry, link(exit), nx, ny);
+				assert_eq!(back, (px, py), "{:?} into {:?}", exit, entry);
+			}
+			landed.sort();
+			landed.dedup();
+			assert_eq!(landed.len(), FRAME_WIDTH);
+		}
+	}
+
+	// On a whole cube, each of the six frames' four edges goes to the tiles
+	// along the edge it is linked to, and that edge goes back to the same
+	// tiles again.
+	#[test]
+	fn every_edge_of_a_cube_matches_the_edge_it_is_linked_to() {
+		let world = World::new();
+		let mut edges = 0;
+		for frame in world.frame_ids() {
+			for &edge in EDGES.iter() {
+				let link = world
+					.get_frame(frame)
+					.unwrap()
+					.borders
+					.at_direction(edge)
+					.unwrap();
+				let tiles: Vec<_> =
+					world.edge_tiles(frame, edge).unwrap().collect();
+				let back: Vec<_> = world
+					.edge_tiles(link.frame, link.entry_edge)
+					.unwrap()
+					.map(|(own, to, other)| (other, to, own))
+					.collect();
+				assert_eq!(tiles.len(), FRAME_WIDTH);
+				for (i, &(own, to, other)) in tiles.iter().enumerate() {
+					let along = match edge {
+						Direction::Up | Direction::Down => own.0,
+						_ => own.1,
+					};
+					assert_eq!(along, i);
+					assert_eq!(to, link.frame);
+					assert!(
+						back.contains(&(own, frame, other)),
+						"{:?} {:?}: {:?} went to {:?} in {:?}",
+						frame,
+						edge,
+						own,
+						other,
+						to
+					);
+				}
+				edges += 1;
+			}
+		}
+		assert_eq!(edges, 24);
+	}
+}
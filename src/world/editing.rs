@@ -0,0 +1,140 @@
+use super::events::WorldEvent;
+use super::types::*;
+use super::{TileLocation, World};
+
+// Why an entity's edit to the tile it points at didn't happen.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum EditError {
+	// Out of range: past a frame edge with no link, or on a frame that
+	// doesn't exist.
+	Unreachable,
+	// A solid tile would have trapped this entity inside it.
+	Occupied(EntityId),
+	// The tile is already what the edit would make it.
+	AlreadyThatTile,
+	// Turning the tile wouldn't change what it does.
+	NotDirectional,
+}
+
+// What an entity's edit does to the tile it points at.
+#[derive(Copy, Clone, Debug)]
+enum TileEdit {
+	Set(Tile),
+	// A quarter turn clockwise.
+	Rotate,
+}
+
+impl EditError {
+	pub fn describe(&self) -> &'static str {
+		match self {
+			EditError::Unreachable => "nothing there to edit",
+			EditError::Occupied(_) => "something is in the way",
+			EditError::AlreadyThatTile => "nothing to change",
+			EditError::NotDirectional => "that doesn't turn",
+		}
+	}
+}
+
+impl World {
+	// Fills the tile the entity points at, which is the way it faces unless
+	// it is aiming somewhere else.
+	pub fn try_place_tile(&mut self, id: EntityId) -> Result<(), EditError> {
+		self.try_edit_beside(id, TileEdit::Set(Tile::Solid))
+	}
+
+	pub fn try_remove_tile(&mut self, id: EntityId) -> Result<(), EditError> {
+		self.try_edit_beside(id, TileEdit::Set(Tile::Empty))
+	}
+
+	pub fn try_rotate_tile(&mut self, id: EntityId) -> Result<(), EditError> {
+		self.try_edit_beside(id, TileEdit::Rotate)
+	}
+
+	// The tile an entity's edits go to, across a frame edge if it is
+	// pointing over one.
+	pub fn edit_target(&self, id: EntityId) -> Option<TileLocation> {
+		let entity = self.get_entity(id)?;
+		self.get_frame(entity.position.frame_id)?;
+		let (x, y) = self.tile_index_at_position(entity.position);
+		self.tile_beyond((entity.position.frame_id, x, y), entity.pointing())
+	}
+
+	// Either way the outcome is told as an event, for the window to show.
+	fn try_edit_beside(
+		&mut self,
+		id: EntityId,
+		edit: TileEdit,
+	) -> Result<(), EditError> {
+		let (frame, x, y) = match self.edit_target(id) {
+			Some(target) => target,
+			None => {
+				self.events.push(WorldEvent::TileEditFailed {
+					entity: id,
+					target: None,
+					error: EditError::Unreachable,
+				});
+				return Err(EditError::Unreachable);
+			}
+		};
+
+		let result = self.edit_tile(id, (frame, x, y), edit);
+		let (x, y) = (x as usize, y as usize);
+		self.events.push(match result {
+			Ok((tile, rotation)) => WorldEvent::TileEdited {
+				entity: id,
+				frame,
+				x,
+				y,
+				tile,
+				rotation,
+			},
+			Err(error) => WorldEvent::TileEditFailed {
+				entity: id,
+				target: Some((frame, x, y)),
+				error,
+			},
+		});
+		result.map(|_| ())
+	}
+
+	// What the tile is and which way it faces after the edit.
+	fn edit_tile(
+		&mut self,
+		id: EntityId,
+		(frame_id, x, y): TileLocation,
+		edit: TileEdit,
+	) -> Result<(Tile, Angle), EditError> {
+		let before = match self.get_frame(frame_id) {
+			Some(frame) => (*frame.tile(x, y), frame.rotation(x, y)),
+			None => return Err(EditError::Unreachable),
+		};
+		let result = match edit {
+			TileEdit::Set(tile) if tile == before.0 => {
+				return Err(EditError::AlreadyThatTile)
+			}
+			TileEdit::Set(tile) => self.set_tile(frame_id, x, y, tile),
+			TileEdit::Rotate if before.0.is_directional() == false => {
+				return Err(EditError::NotDirectional)
+			}
+			TileEdit::Rotate => {
+				let rotation = before.1.compose(Angle::Clockwise90);
+				self.set_tile_rotation(frame_id, x, y, rotation)
+			}
+		};
+		match result {
+			Ok(()) => {
+				if let TileEdit::Set(tile) = edit {
+					self.record_tile_change(id, tile);
+				}
+				self.record_tile_edit((frame_id, x, y), before);
+				let frame = self.get_frame(frame_id).unwrap();
+				Ok((*frame.tile(x, y), frame.rotation(x, y)))
+			}
+			Err(SetTileError::Occupied(occupant)) => {
+				Err(EditError::Occupied(occupant))
+			}
+			Err(SetTileError::MissingFrame(_))
+			| Err(SetTileError::OutOfBounds) => Err(EditError::Unreachable),
+		}
+	}
+}
@@ -0,0 +1,241 @@
+use super::editing::EditError;
+use super::tags::TagId;
+use super::types::*;
+use crate::geometry::Scalar;
+
+// Things that happened in the world during the most recent tick, for other
+// systems (rendering, sound, tests) to react to. Cleared at the start of
+// every tick.
+#[derive(Copy, Clone, Debug)]
+pub enum WorldEvent {
+	EntityCrossedFrame {
+		entity: EntityId,
+		from: FrameId,
+		to: FrameId,
+		exit_edge: Direction,
+		entry_edge: Direction,
+	},
+	// An entity interacted with a sign tile within reach.
+	SignActivated {
+		entity: EntityId,
+		frame: FrameId,
+		x: usize,
+		y: usize,
+	},
+	CoinCollected {
+		collector: EntityId,
+		coin: EntityId,
+	},
+	GravityFlipped {
+		entity: EntityId,
+		gravity_dir: Direction,
+	},
+	// An entity moved into a different tile.
+	TileEntered {
+		entity: EntityId,
+		frame: FrameId,
+		x: usize,
+		y: usize,
+		tile: Tile,
+	},
+	// An entity touched down on whatever gravity pulls it towards, moving
+	// towards it at `speed` just before.
+	EntityLanded {
+		entity: EntityId,
+		speed: Scalar,
+	},
+	EntityDamaged {
+		entity: EntityId,
+		amount: Scalar,
+	},
+	// An entity ran into the face of the spring at these coordinates and
+	// was thrown off it, instead of landing.
+	EntityBounced {
+		entity: EntityId,
+		frame: FrameId,
+		x: usize,
+		y: usize,
+	},
+	// The last coin was collected, `ticks` into the level.
+	LevelCompleted {
+		entity: EntityId,
+		ticks: u64,
+	},
+	// An entity was found somewhere it can't be, at `position`, and was
+	// put back at the spawn point or removed.
+	EntityOutOfBounds {
+		entity: EntityId,
+		position: WorldPosition,
+	},
+	// An entity set or turned the tile it points at, which is now `tile`
+	// turned by `rotation`.
+	TileEdited {
+		entity: EntityId,
+		frame: FrameId,
+		x: usize,
+		y: usize,
+		tile: Tile,
+		rotation: Angle,
+	},
+	// An entity tried to set the tile it points at and couldn't, with the
+	// tile it was pointing at if there was one.
+	TileEditFailed {
+		entity: EntityId,
+		target: Option<(FrameId, usize, usize)>,
+		error: EditError,
+	},
+	// The last entity in a group was removed, such as by being collected.
+	GroupCleared {
+		tag: TagId,
+	},
+	// An entity ran out of health. Players come back at their last
+	// checkpoint, and anything else is removed.
+	EntityDied {
+		entity: EntityId,
+	},
+	// A player reached a checkpoint, which is where they come back now.
+	CheckpointReached {
+		entity: EntityId,
+	},
+	// An entity dashed the way it points, using up stamina.
+	EntityDashed {
+		entity: EntityId,
+	},
+	// An entity tried to dash without the stamina for it.
+	DashRefused {
+		entity: EntityId,
+	},
+	// A hook changed a tile and asked for it to be shown to the player.
+	TargetShown {
+		frame: FrameId,
+		x: usize,
+		y: usize,
+	},
+}
+
+// Events without their data, for matching against.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WorldEventKind {
+	EntityCrossedFrame,
+	SignActivated,
+	CoinCollected,
+	GravityFlipped,
+	TileEntered,
+	EntityLanded,
+	EntityDamaged,
+	EntityBounced,
+	LevelCompleted,
+	EntityOutOfBounds,
+	TileEdited,
+	TileEditFailed,
+	GroupCleared,
+	EntityDied,
+	CheckpointReached,
+	EntityDashed,
+	DashRefused,
+	TargetShown,
+}
+
+impl WorldEvent {
+	pub fn kind(&self) -> WorldEventKind {
+		use WorldEvent::*;
+		match self {
+			EntityCrossedFrame { .. } => WorldEventKind::EntityCrossedFrame,
+			SignActivated { .. } => WorldEventKind::SignActivated,
+			CoinCollected { .. } => WorldEventKind::CoinCollected,
+			GravityFlipped { .. } => WorldEventKind::GravityFlipped,
+			TileEntered { .. } => WorldEventKind::TileEntered,
+			EntityLanded { .. } => WorldEventKind::EntityLanded,
+			EntityDamaged { .. } => WorldEventKind::EntityDamaged,
+			EntityBounced { .. } => WorldEventKind::EntityBounced,
+			LevelCompleted { .. } => WorldEventKind::LevelCompleted,
+			EntityOutOfBounds { .. } => WorldEventKind::EntityOutOfBounds,
+			TileEdited { .. } => WorldEventKind::TileEdited,
+			TileEditFailed { .. } => WorldEventKind::TileEditFailed,
+			GroupCleared { .. } => WorldEventKind::GroupCleared,
+			EntityDied { .. } => WorldEventKind::EntityDied,
+			CheckpointReached { .. } => WorldEventKind::CheckpointReached,
+			EntityDashed { .. } => WorldEventKind::EntityDashed,
+			DashRefused { .. } => WorldEventKind::DashRefused,
+			TargetShown { .. } => WorldEventKind::TargetShown,
+		}
+	}
+
+	// The entity the event happened to.
+	pub fn subject(&self) -> Option<EntityId> {
+		use WorldEvent::*;
+		match *self {
+			EntityCrossedFrame { entity, .. } => Some(entity),
+			SignActivated { entity, .. } => Some(entity),
+			CoinCollected { collector, .. } => Some(collector),
+			GravityFlipped { entity, .. } => Some(entity),
+			TileEntered { entity, .. } => Some(entity),
+			EntityLanded { entity, .. } => Some(entity),
+			EntityDamaged { entity, .. } => Some(entity),
+			EntityBounced { entity, .. } => Some(entity),
+			LevelCompleted { entity, .. } => Some(entity),
+			EntityOutOfBounds { entity, .. } => Some(entity),
+			TileEdited { entity, .. } => Some(entity),
+			TileEditFailed { entity, .. } => Some(entity),
+			GroupCleared { .. } => None,
+			EntityDied { entity } => Some(entity),
+			CheckpointReached { entity } => Some(entity),
+			EntityDashed { entity } => Some(entity),
+			DashRefused { entity } => Some(entity),
+			TargetShown { .. } => None,
+		}
+	}
+}
+
+impl WorldEventKind {
+	pub fn name(&self) -> &'static str {
+		use WorldEventKind::*;
+		match self {
+			EntityCrossedFrame => "entity_crossed_frame",
+			SignActivated => "sign_activated",
+			CoinCollected => "coin_collected",
+			GravityFlipped => "gravity_flipped",
+			TileEntered => "tile_entered",
+			EntityLanded => "entity_landed",
+			EntityDamaged => "entity_damaged",
+			EntityBounced => "entity_bounced",
+			LevelCompleted => "level_completed",
+			EntityOutOfBounds => "entity_out_of_bounds",
+			TileEdited => "tile_edited",
+			TileEditFailed => "tile_edit_failed",
+			GroupCleared => "group_cleared",
+			EntityDied => "entity_died",
+			CheckpointReached => "checkpoint_reached",
+			EntityDashed => "entity_dashed",
+			DashRefused => "dash_refused",
+			TargetShown => "target_shown",
+		}
+	}
+
+	pub fn from_name(name: &str) -> Option<Self> {
+		use WorldEventKind::*;
+		[
+			EntityCrossedFrame,
+			SignActivated,
+			CoinCollected,
+			GravityFlipped,
+			TileEntered,
+			EntityLanded,
+			EntityDamaged,
+			EntityBounced,
+			LevelCompleted,
+			EntityOutOfBounds,
+			TileEdited,
+			TileEditFailed,
+			GroupCleared,
+			EntityDied,
+			CheckpointReached,
+			EntityDashed,
+			DashRefused,
+			TargetShown,
+		]
+		.iter()
+		.find(|kind| kind.name() == name)
+		.copied()
+	}
+}
@@ -0,0 +1,75 @@
+use super::types::*;
+use super::{Entity, World};
+use crate::geometry::{Scalar, Vector3};
+
+// How long something takes to fall off the world before it is gone.
+pub const FALL_OFF_TICKS: u32 = 45;
+// How far inside its frame an entity falling off is held, so that the tile
+// it is in is still one of the frame's.
+const EDGE_INSET: Scalar = 1e-3;
+
+// An entity that went over an open edge, on its way out of the world.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FallOff {
+	// The edge of the entity's frame it went over.
+	pub edge: Direction,
+	pub ticks: u32,
+}
+
+impl FallOff {
+	// From nothing as it goes over the edge to one once it is gone.
+	pub fn progress(&self) -> Scalar {
+		(self.ticks as Scalar / FALL_OFF_TICKS as Scalar).min(1.0)
+	}
+}
+
+impl Entity {
+	pub fn is_falling_off(&self) -> bool {
+		self.falling_off.is_some()
+	}
+}
+
+impl World {
+	pub fn is_open_edge(&self, frame_id: FrameId, edge: Direction) -> bool {
+		self.get_frame(frame_id)
+			.is_some_and(|frame| frame.is_open(edge))
+	}
+
+	// Holds the entity at the edge it went over, where it is drawn drifting
+	// off until the fall is over.
+	pub(super) fn start_falling_off(
+		&mut self,
+		id: EntityId,
+		at: WorldPosition,
+		edge: Direction,
+	) {
+		let limit = 1.0 - EDGE_INSET;
+		let position = WorldPosition {
+			x: at.x.max(-limit).min(limit),
+			y: at.y.max(-limit).min(limit),
+			..at
+		};
+		self.move_entity_to(id, position);
+		let entity = self.get_entity_mut(id).unwrap();
+		entity.velocity = Vector3::zero();
+		entity.grounded = false;
+		entity.falling_off = Some(FallOff { edge, ticks: 0 });
+	}
+
+	// Moves a fall along, and once it is over leaves the entity with no
+	// health, to die as anything else would. Whether the entity is falling,
+	// in which case it does nothing else.
+	pub(super) fn update_fall_off(&mut self, id: EntityId) -> bool {
+		let step = self.ticks_per_step;
+		let entity = self.get_entity_mut(id).unwrap();
+		let fall = match entity.falling_off.as_mut() {
+			Some(fall) => fall,
+			None => return false,
+		};
+		fall.ticks += step;
+		if fall.ticks >= FALL_OFF_TICKS {
+			entity.health = 0.0;
+		}
+		true
+	}
+}
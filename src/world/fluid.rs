@@ -0,0 +1,132 @@
+use std::collections::BTreeSet;
+
+use super::types::*;
+use super::{FrameActivity, World, FRAME_WIDTH};
+
+// Fluid tiles move at most once every this many ticks.
+pub const FLUID_INTERVAL: u64 = 4;
+
+// Offsets from a tile to those whose movement it can unblock: the three
+// above it and the two beside it.
+const UNBLOCKED_OFFSETS: [(isize, isize); 5] =
+	[(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0)];
+
+impl World {
+	// Moves sand and water a tile each, for every frame that has tiles that
+	// might move. Frames go in id order and tiles from the bottom row up,
+	// so that a pass always does the same thing and a column of sand
+	// falls together.
+	pub(super) fn update_fluids(&mut self) {
+		// Once each time the tick count passes a multiple of the interval,
+		// however many ticks a step covers.
+		let pass = self.tick_count / FLUID_INTERVAL;
+		let step = self.ticks_per_step as u64;
+		if pass == (self.tick_count - step) / FLUID_INTERVAL {
+			return;
+		}
+
+		for frame_id in self.frame_ids() {
+			// Left pending until the frame is active again.
+			if self.frame_activity(frame_id) == FrameActivity::Dormant {
+				continue;
+			}
+			let active = self.frames.get_mut(&frame_id).unwrap().take_active();
+			for &(y, x) in active.iter().rev() {
+				let (x, y) = (x as isize, y as isize);
+				let tile = *self.frames[&frame_id].tile(x, y);
+				// Sideways first from the left or the right, alternating
+				// between passes and columns so that water doesn't drift.
+				let side = if (pass as isize + x) % 2 == 0 { -1 } else { 1 };
+				let moves: &[(isize, isize)] = match tile {
+					Tile::Sand => &[(0, 1), (side, 1), (-side, 1)],
+					Tile::Water => {
+						&[(0, 1), (side, 1), (-side, 1), (side, 0), (-side, 0)]
+					}
+					_ => continue,
+				};
+				for &(dx, dy) in moves {
+					if self.move_fluid(frame_id, x, y, x + dx, y + dy) {
+						break;
+					}
+				}
+			}
+		}
+	}
+
+	// Swaps a fluid tile into an empty tile, or sand down into water.
+	// `x`, `y` must be within the frame, while the destination can be one
+	// tile past an edge.
+	fn move_fluid(
+		&mut self,
+		frame_id: FrameId,
+		x: isize,
+		y: isize,
+		to_x: isize,
+		to_y: isize,
+	) -> bool {
+		let (to_frame, to_x, to_y) =
+			match self.normalize_tile_index(frame_id, to_x, to_y) {
+				Some(index) => index,
+				None => return false,
+			};
+		let tile = *self.frames[&frame_id].tile(x, y);
+		let target_frame = &self.frames[&to_frame];
+		if target_frame.owner(to_x, to_y).is_some() {
+			return false;
+		}
+		let target = *target_frame.tile(to_x, to_y);
+		let sinks = tile == Tile::Sand && target == Tile::Water && to_y > y;
+		if target != Tile::Empty && sinks == false {
+			return false;
+		}
+
+		let frame = self.frames.get_mut(&frame_id).unwrap();
+		*frame.tile_mut(x, y) = target;
+		frame.mark_changed();
+		let frame = self.frames.get_mut(&to_frame).unwrap();
+		*frame.tile_mut(to_x, to_y) = tile;
+		frame.mark_changed();
+		self.activate_fluids_around(frame_id, x, y);
+		self.activate_fluids_around(to_frame, to_x, to_y);
+		self.change_count += 1;
+		true
+	}
+
+	// Every fluid tile, for a world whose tiles were set directly.
+	pub(super) fn activate_all_fluids(&mut self) {
+		let w = FRAME_WIDTH as isize;
+		for frame in self.frames.values_mut() {
+			for y in 0..w {
+				for x in 0..w {
+					if frame.tile(x, y).is_fluid() {
+						frame.activate(x, y);
+					}
+				}
+			}
+		}
+	}
+
+	// Marks a tile and those that could move once it changes to be looked
+	// at in the next pass.
+	pub(super) fn activate_fluids_around(
+		&mut self,
+		frame_id: FrameId,
+		x: isize,
+		y: isize,
+	) {
+		let around =
+			std::iter::once((0, 0)).chain(UNBLOCKED_OFFSETS.iter().copied());
+		for (dx, dy) in around {
+			let index = self.normalize_tile_index(frame_id, x + dx, y + dy);
+			if let Some((frame_id, x, y)) = index {
+				let frame = self.frames.get_mut(&frame_id).unwrap();
+				if frame.tile(x, y).is_fluid() {
+					frame.activate(x, y);
+				}
+			}
+		}
+	}
+}
+
+// Tiles that may be able to move, as (y, x) so that they sort by row.
+pub type ActiveTiles = BTreeSet<(usize, usize)>;
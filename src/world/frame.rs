@@ -1,6 +1,11 @@
-use crate::backend::random;
-use super::{FRAME_WIDTH, TILE_SIZE, FRAME_TILE_COUNT};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use super::fluid::ActiveTiles;
 use super::types::*;
+use super::Rng;
+use super::{FRAME_TILE_COUNT, FRAME_WIDTH, SPRING_SQUASH_TICKS};
+use crate::geometry::{Scalar, Vector3};
 
 #[derive(Copy, Clone, Debug)]
 pub struct FrameLinks {
@@ -70,13 +75,43 @@ impl FrameLinks {
 	}
 }
 
+// Shared by every frame of every world, so no two revisions are ever the
+// same.
+static NEXT_REVISION: AtomicU64 = AtomicU64::new(0);
+
+fn next_revision() -> u64 {
+	NEXT_REVISION.fetch_add(1, Ordering::Relaxed)
+}
 
 pub struct Frame {
 	tiles: [Tile; FRAME_TILE_COUNT],
+	// Changed whenever the tiles are, for anything drawn from them to know
+	// when to redraw. Never the same between two frames, even in different
+	// worlds, so a loaded world can't be mistaken for the one before it.
+	revision: u64,
+	tile_data: HashMap<(usize, usize), TileData>,
+	// How far each directional tile is turned from the way its kind faces,
+	// with tiles that aren't turned left out.
+	rotations: HashMap<(usize, usize), Angle>,
+	// Entities filling a whole tile, which is then solid whatever the tile
+	// itself is.
+	owners: HashMap<(usize, usize), EntityId>,
+	// Fluid tiles to try moving in the next fluid pass.
+	active: ActiveTiles,
 	invalid_tile: Tile,
+	pub background: BackgroundStyle,
+	// A constant push on everything that moves while in the frame, per tick
+	// and in the frame's own axes, so it turns with the frame.
+	pub wind: Option<Vector3>,
 	pub borders: FrameLinks,
+	// Edges left unlinked on purpose, which anything that goes over falls
+	// off the world from.
+	pub open_edges: Vec<Direction>,
 	pub position: FrameId,
 	pub orientation: Direction,
+	// Whether the focus entity has been on the frame, for the world map to
+	// show only what has been explored. Never set by levels.
+	pub visited: bool,
 }
 
 impl Frame {
@@ -94,10 +129,19 @@ impl Frame {
 
 		Self {
 			tiles: [Tile::Empty; FRAME_TILE_COUNT],
+			revision: next_revision(),
+			tile_data: HashMap::new(),
+			rotations: HashMap::new(),
+			owners: HashMap::new(),
+			active: ActiveTiles::new(),
 			invalid_tile: Tile::Invalid,
+			background: BackgroundStyle::default_for(position),
+			wind: None,
 			borders,
+			open_edges: Vec::new(),
 			position,
 			orientation: Direction::Neutral,
+			visited: false,
 		}
 	}
 
@@ -112,6 +156,22 @@ impl Frame {
 		&self.tiles[y as usize * FRAME_WIDTH + x as usize]
 	}
 
+	// Every tile, a row at a time from the top.
+	pub fn tiles(&self) -> &[Tile] {
+		&self.tiles
+	}
+
+	// One line of `Tile::symbol`s per row, top to bottom, each ending in a
+	// newline.
+	pub fn to_ascii(&self) -> String {
+		let mut out = String::with_capacity((FRAME_WIDTH + 1) * FRAME_WIDTH);
+		for row in self.tiles.chunks(FRAME_WIDTH) {
+			out.extend(row.iter().map(Tile::symbol));
+			out.push('\n');
+		}
+		out
+	}
+
 	pub fn tile_mut(&mut self, x: isize, y: isize) -> &mut Tile {
 		if x < 0
 			|| y < 0 || x >= FRAME_WIDTH as isize
@@ -123,13 +183,144 @@ impl Frame {
 		&mut self.tiles[y as usize * FRAME_WIDTH + x as usize]
 	}
 
-	pub fn new_populated(position: FrameId) -> Self {
+	pub fn revision(&self) -> u64 {
+		self.revision
+	}
+
+	pub(super) fn mark_changed(&mut self) {
+		self.revision = next_revision();
+	}
+
+	// Whether the tile or an entity filling it blocks movement.
+	pub fn is_solid(&self, x: isize, y: isize) -> bool {
+		self.tile(x, y).is_solid_turned(self.rotation(x, y))
+			|| self.owner(x, y).is_some()
+	}
+
+	pub fn rotation(&self, x: isize, y: isize) -> Angle {
+		if x < 0 || y < 0 {
+			return Angle::Clockwise0;
+		}
+		self.rotations
+			.get(&(x as usize, y as usize))
+			.copied()
+			.unwrap_or(Angle::Clockwise0)
+	}
+
+	pub fn set_rotation(&mut self, x: isize, y: isize, rotation: Angle) {
+		let w = FRAME_WIDTH as isize;
+		if x < 0 || y < 0 || x >= w || y >= w {
+			return;
+		}
+		let index = (x as usize, y as usize);
+		match rotation {
+			Angle::Clockwise0 => self.rotations.remove(&index),
+			rotation => self.rotations.insert(index, rotation),
+		};
+	}
+
+	// Every turned tile, in no particular order.
+	pub fn rotation_entries(
+		&self,
+	) -> impl Iterator<Item = ((usize, usize), Angle)> + '_ {
+		self.rotations
+			.iter()
+			.map(|(&index, &rotation)| (index, rotation))
+	}
+
+	pub fn owner(&self, x: isize, y: isize) -> Option<EntityId> {
+		if x < 0 || y < 0 {
+			return None;
+		}
+		self.owners.get(&(x as usize, y as usize)).copied()
+	}
+
+	pub fn set_owner(&mut self, x: isize, y: isize, owner: Option<EntityId>) {
+		let w = FRAME_WIDTH as isize;
+		if x < 0 || y < 0 || x >= w || y >= w {
+			return;
+		}
+		let index = (x as usize, y as usize);
+		match owner {
+			Some(owner) => self.owners.insert(index, owner),
+			None => self.owners.remove(&index),
+		};
+	}
+
+	pub fn activate(&mut self, x: isize, y: isize) {
+		let w = FRAME_WIDTH as isize;
+		if x >= 0 && y >= 0 && x < w && y < w {
+			self.active.insert((y as usize, x as usize));
+		}
+	}
+
+	pub fn take_active(&mut self) -> ActiveTiles {
+		std::mem::take(&mut self.active)
+	}
+
+	// Fluid tiles waiting for the next fluid pass, as (y, x).
+	pub fn active_tiles(&self) -> &ActiveTiles {
+		&self.active
+	}
+
+	pub fn tile_data(&self, x: isize, y: isize) -> Option<&TileData> {
+		if x < 0 || y < 0 {
+			return None;
+		}
+		self.tile_data.get(&(x as usize, y as usize))
+	}
+
+	// How squashed the spring at `x`, `y` is, from one just after it
+	// throws something down to zero once it has sprung back.
+	pub fn spring_squash(&self, x: isize, y: isize) -> Scalar {
+		match self.tile_data(x, y) {
+			Some(&TileData::Squash(ticks)) => {
+				ticks as Scalar / SPRING_SQUASH_TICKS as Scalar
+			}
+			_ => 0.0,
+		}
+	}
+
+	// Whether the edge is left unlinked on purpose, for whatever goes over
+	// it to fall off the world.
+	pub fn is_open(&self, edge: Direction) -> bool {
+		self.open_edges.contains(&edge)
+			&& self.borders.at_direction(edge).is_none()
+	}
+
+	// Every tile with data attached, in no particular order.
+	pub fn tile_data_entries(
+		&self,
+	) -> impl Iterator<Item = ((usize, usize), &TileData)> {
+		self.tile_data.iter().map(|(&index, data)| (index, data))
+	}
+
+	pub fn set_tile_data(
+		&mut self,
+		x: isize,
+		y: isize,
+		data: Option<TileData>,
+	) {
+		let w = FRAME_WIDTH as isize;
+		if x < 0 || y < 0 || x >= w || y >= w {
+			return;
+		}
+		let index = (x as usize, y as usize);
+		match data {
+			Some(data) => self.tile_data.insert(index, data),
+			None => self.tile_data.remove(&index),
+		};
+	}
+
+	pub fn new_populated(position: FrameId, rng: &mut Rng) -> Self {
 		let mut frame = Self::new(position);
 
 		for x in 0..FRAME_WIDTH {
 			for y in 0..FRAME_WIDTH {
-				let tile = match random::rangei(1, 100) {
-					1..=17 => Tile::Solid,
+				let tile = match rng.range_i(1, 100) {
+					1..=15 => Tile::Solid,
+					16 => Tile::SlopeUpRight,
+					17 => Tile::SlopeUpLeft,
 					18..=100 => Tile::Empty,
 					_ => panic!(),
 				};
@@ -0,0 +1,91 @@
+use super::types::*;
+use super::{AlignmentRules, Entity, Frame, Rng, World};
+
+// The faces of a generated cube, by frame id, as they are linked once all
+// of them are generated.
+const CUBE_FRAMES: usize = 6;
+const CUBE_LINKS: &[(usize, Direction, usize, Direction)] = {
+	use Direction::*;
+	&[
+		(0, Up, 3, Down),
+		(0, Left, 1, Right),
+		(0, Right, 2, Left),
+		(0, Down, 4, Up),
+		(5, Up, 3, Up),
+		(5, Right, 1, Left),
+		(5, Left, 2, Right),
+		(5, Down, 4, Down),
+		(1, Up, 3, Left),
+		(1, Down, 4, Left),
+		(2, Up, 3, Right),
+		(2, Down, 4, Right),
+	]
+};
+
+// Makes a generated cube a frame at a time, so that whatever is waiting on
+// it can carry on in between. Frames are made in the same order from the
+// same random numbers however the work is split up, so the world only
+// depends on the seed.
+pub struct WorldGenerator {
+	world: World,
+	generated: usize,
+}
+
+impl WorldGenerator {
+	pub fn new(seed: u64) -> Self {
+		let mut world = World::empty();
+		world.rng = Rng::new(seed);
+		Self {
+			world,
+			generated: 0,
+		}
+	}
+
+	// Frames made so far, out of `total`.
+	pub fn generated(&self) -> usize {
+		self.generated
+	}
+
+	pub fn total(&self) -> usize {
+		CUBE_FRAMES
+	}
+
+	pub fn is_done(&self) -> bool {
+		self.generated == CUBE_FRAMES
+	}
+
+	// Makes the next frame, and after the last links them into a cube with
+	// the player on the front. Does nothing once done.
+	pub fn step(&mut self) {
+		if self.is_done() {
+			return;
+		}
+		let id = FrameId::new(self.generated);
+		let frame = Frame::new_populated(id, &mut self.world.rng);
+		self.world.insert_frame(frame);
+		self.generated += 1;
+		if self.is_done() == false {
+			return;
+		}
+
+		let world = &mut self.world;
+		for &(from, edge, to, entry_edge) in CUBE_LINKS {
+			let (from, to) = (FrameId::new(from), FrameId::new(to));
+			world.connect_frames(from, edge, to, entry_edge);
+		}
+		world.align_edges(&AlignmentRules::default());
+		let player = Entity::new_player(world, FrameId::new(0));
+		world.spawn = Some(player.position);
+		let player_id = world.insert_entity(player);
+		world.focus_entity = Some(player_id);
+		world.take_checkpoint(player_id);
+	}
+
+	// The generated world, making whatever is left of it first.
+	pub fn finish(mut self) -> World {
+		while self.is_done() == false {
+			self.step();
+		}
+		self.world
+	}
+}
@@ -0,0 +1,89 @@
+use super::edges::{
+	compose_rotation, transform_position_across, transform_tile_across,
+};
+use super::types::*;
+use super::{World, FRAME_WIDTH, TILE_SIZE};
+use crate::geometry::Scalar;
+
+impl World {
+	// The nearest solid tile at most `max_tiles` on from `position` towards
+	// `down`, not counting the tile `position` is in. Gives the point on
+	// that tile's near face straight on from `position`, and how far away
+	// the face is. Followed across frame edges, with `down` turned to match
+	// each one, so that it always points the way things would fall.
+	pub fn ground_below(
+		&self,
+		position: WorldPosition,
+		down: Direction,
+		max_tiles: usize,
+	) -> Option<(WorldPosition, Scalar)> {
+		self.ground_below_turned(position, down, max_tiles)
+			.map(|(point, distance, _)| (point, distance))
+	}
+
+	// `ground_below`, along with `down` as turned onto the ground's frame.
+	pub fn ground_below_turned(
+		&self,
+		position: WorldPosition,
+		down: Direction,
+		max_tiles: usize,
+	) -> Option<(WorldPosition, Scalar, Direction)> {
+		if down == Direction::Neutral {
+			return None;
+		}
+		let (mut point, crossing) = position.normalize_traced(self).ok()?;
+		let mut down = match crossing {
+			Some(crossing) => down.rotated(crossing.angle),
+			None => down,
+		};
+		let (mut x, mut y) = self.tile_index_at_position(point);
+		let mut distance = 0.0;
+		// The coordinate of a tile's face on the side `step` points to.
+		let face = |index: isize, step: Scalar| {
+			let far = if step > 0.0 { index + 1 } else { index };
+			-1.0 + far as Scalar * TILE_SIZE
+		};
+
+		for _ in 0..max_tiles {
+			// Onto the face between this tile and the next.
+			let (dx, dy) = down.unit();
+			if dx != 0.0 {
+				let edge = face(x, dx);
+				distance += (edge - point.x).abs();
+				point.x = edge;
+			} else {
+				let edge = face(y, dy);
+				distance += (edge - point.y).abs();
+				point.y = edge;
+			}
+
+			let w = FRAME_WIDTH as isize;
+			let (nx, ny) = (x + dx as isize, y + dy as isize);
+			if nx >= 0 && ny >= 0 && nx < w && ny < w {
+				x = nx;
+				y = ny;
+			} else {
+				let frame = self.get_frame(point.frame_id)?;
+				let link = frame.borders.at_direction(down)?;
+				self.get_frame(link.frame)?;
+				let (px, py) =
+					transform_position_across(down, link, point.x, point.y);
+				let (tx, ty) = transform_tile_across(down, link, nx, ny);
+				point = WorldPosition {
+					frame_id: link.frame,
+					x: px,
+					y: py,
+				};
+				x = tx;
+				y = ty;
+				down = down.rotated(compose_rotation(down, link));
+			}
+
+			let frame = self.get_frame(point.frame_id)?;
+			if frame.is_solid(x, y) {
+				return Some((point, distance, down));
+			}
+		}
+		None
+	}
+}
@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+
+use super::types::*;
+use super::{TileLocation, World};
+
+// All there is to a tile, for putting it back exactly as it was.
+#[derive(Clone, Debug, PartialEq)]
+struct TileState {
+	tile: Tile,
+	rotation: Angle,
+	data: Option<TileData>,
+}
+
+impl TileState {
+	fn at(world: &World, (frame_id, x, y): TileLocation) -> Option<Self> {
+		let frame = world.get_frame(frame_id)?;
+		Some(Self {
+			tile: *frame.tile(x, y),
+			rotation: frame.rotation(x, y),
+			data: frame.tile_data(x, y).cloned(),
+		})
+	}
+}
+
+#[derive(Clone, Debug)]
+struct TileChange {
+	at: TileLocation,
+	before: TileState,
+	after: TileState,
+}
+
+// The edits made to the world in the editor, oldest first, to go back and
+// forth through. One edit is everything a drag or a filled rectangle
+// changed. Those from `cursor` on have been undone, and are dropped once a
+// new edit is made from there.
+pub struct EditHistory {
+	edits: Vec<Vec<TileChange>>,
+	cursor: usize,
+	// The changes of an edit still being made, such as by a drag that is
+	// still held.
+	open: Vec<TileChange>,
+}
+
+impl Default for EditHistory {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl EditHistory {
+	pub fn new() -> Self {
+		Self {
+			edits: Vec::new(),
+			cursor: 0,
+			open: Vec::new(),
+		}
+	}
+
+	pub fn len(&self) -> usize {
+		self.edits.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.edits.is_empty()
+	}
+
+	// How many edits are in effect, from the oldest.
+	pub fn cursor(&self) -> usize {
+		self.cursor
+	}
+
+	// Whether an edit has been started and not yet finished.
+	pub fn is_editing(&self) -> bool {
+		self.open.is_empty() == false
+	}
+
+	// `World::set_tile`, as part of the edit being made.
+	pub fn set_tile(
+		&mut self,
+		world: &mut World,
+		frame_id: FrameId,
+		x: isize,
+		y: isize,
+		tile: Tile,
+	) -> Result<(), SetTileError> {
+		let at = (frame_id, x, y);
+		let before = TileState::at(world, at);
+		world.set_tile(frame_id, x, y, tile)?;
+		if let Some(before) = before {
+			self.record(world, at, before);
+		}
+		Ok(())
+	}
+
+	// `World::fill_rect`, as part of the edit being made.
+	pub fn fill_rect(
+		&mut self,
+		world: &mut World,
+		frame_id: FrameId,
+		from: (isize, isize),
+		to: (isize, isize),
+		tile: Tile,
+	) -> Vec<((isize, isize), SetTileError)> {
+		let mut before = Vec::new();
+		for y in from.1.min(to.1)..=from.1.max(to.1) {
+			for x in from.0.min(to.0)..=from.0.max(to.0) {
+				if let Some(state) = TileState::at(world, (frame_id, x, y)) {
+					before.push(((frame_id, x, y), state));
+				}
+			}
+		}
+		let refused = world.fill_rect(frame_id, from, to, tile);
+		for (at, state) in before {
+			self.record(world, at, state);
+		}
+		refused
+	}
+
+	fn record(&mut self, world: &World, at: TileLocation, before: TileState) {
+		let after = match TileState::at(world, at) {
+			Some(after) if after != before => after,
+			_ => return,
+		};
+		self.open.push(TileChange { at, before, after });
+	}
+
+	// Ends the edit being made, if it changed anything, dropping any edits
+	// that had been undone. How many were.
+	pub fn finish(&mut self) -> usize {
+		if self.open.is_empty() {
+			return 0;
+		}
+		let dropped = self.edits.len() - self.cursor;
+		self.edits.truncate(self.cursor);
+		self.edits.push(std::mem::take(&mut self.open));
+		self.cursor = self.edits.len();
+		dropped
+	}
+
+	// Undoes or redoes edits until `cursor` of them are in effect, setting
+	// each tile only once however many of the edits changed it. Tiles are
+	// put back as they were even with an entity in them now. An edit being
+	// made is finished first.
+	pub fn seek(&mut self, world: &mut World, cursor: usize) -> usize {
+		let dropped = self.finish();
+		let cursor = cursor.min(self.edits.len());
+		let mut tiles = HashMap::new();
+		if cursor < self.cursor {
+			// The oldest edit undone has the tile as it was before all of
+			// them.
+			for change in self.edits[cursor..self.cursor].iter().flatten() {
+				tiles.entry(change.at).or_insert(&change.before);
+			}
+		} else {
+			let edits = &self.edits[self.cursor..cursor];
+			for change in edits.iter().flatten().rev() {
+				tiles.entry(change.at).or_insert(&change.after);
+			}
+		}
+
+		// In order, so that the world ends up the same every time.
+		let mut tiles: Vec<_> = tiles.into_iter().collect();
+		tiles.sort_by_key(|&((frame_id, x, y), _)| (frame_id.0, y, x));
+		for (at, state) in tiles {
+			world.put_tile(at, state);
+		}
+		self.cursor = cursor;
+		dropped
+	}
+}
+
+impl World {
+	fn put_tile(&mut self, (frame_id, x, y): TileLocation, state: &TileState) {
+		let frame = match self.get_frame_mut(frame_id) {
+			Some(frame) => frame,
+			None => return,
+		};
+		*frame.tile_mut(x, y) = state.tile;
+		frame.set_rotation(x, y, state.rotation);
+		frame.set_tile_data(x, y, state.data.clone());
+		frame.mark_changed();
+		self.change_count += 1;
+		self.activate_fluids_around(frame_id, x, y);
+	}
+}
@@ -0,0 +1,160 @@
+use super::events::{WorldEvent, WorldEventKind};
+use super::tags::TagId;
+use super::types::*;
+use super::World;
+use crate::prelude::*;
+
+// What sets a hook off.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Trigger {
+	// An entity moving into the tile at these coordinates.
+	TileAt { frame: FrameId, x: usize, y: usize },
+	// An entity moving into any tile of this kind.
+	TileKind(Tile),
+	// Any event of this kind.
+	Event(WorldEventKind),
+	// The last entity in this group being removed.
+	GroupCleared(TagId),
+}
+
+impl Trigger {
+	pub fn matches(&self, event: &WorldEvent) -> bool {
+		match (*self, *event) {
+			(
+				Trigger::TileAt { frame, x, y },
+				WorldEvent::TileEntered {
+					frame: entered_frame,
+					x: entered_x,
+					y: entered_y,
+					..
+				},
+			) => (frame, x, y) == (entered_frame, entered_x, entered_y),
+			(Trigger::TileKind(kind), WorldEvent::TileEntered { tile, .. }) => {
+				kind == tile
+			}
+			(Trigger::Event(kind), event) => event.kind() == kind,
+			(
+				Trigger::GroupCleared(tag),
+				WorldEvent::GroupCleared { tag: cleared },
+			) => tag == cleared,
+			_ => false,
+		}
+	}
+}
+
+// A built-in response, so that levels can compose behaviors without any
+// code of their own.
+#[derive(Copy, Clone, Debug)]
+pub enum Behavior {
+	// Moves the entity that set the hook off, keeping its velocity.
+	Teleport {
+		destination: WorldPosition,
+	},
+	// Changes a tile, and if `show` is set has the camera show it being
+	// changed, as for a switch opening a door somewhere out of sight.
+	SetTile {
+		frame: FrameId,
+		x: usize,
+		y: usize,
+		tile: Tile,
+		show: bool,
+	},
+	// Makes where the player who set the hook off is now the place they
+	// come back to after dying. Ignored for anything else.
+	Checkpoint,
+}
+
+// Behaviors attached to triggers, run in the order they were added.
+pub struct Hooks {
+	hooks: Vec<(Trigger, Behavior)>,
+}
+
+impl Hooks {
+	pub fn new() -> Self {
+		Self { hooks: Vec::new() }
+	}
+
+	pub fn add(&mut self, trigger: Trigger, behavior: Behavior) {
+		self.hooks.push((trigger, behavior));
+	}
+
+	pub fn on_tile_enter(&mut self, tile: Tile, behavior: Behavior) {
+		self.add(Trigger::TileKind(tile), behavior);
+	}
+
+	pub fn on_event(&mut self, kind: WorldEventKind, behavior: Behavior) {
+		self.add(Trigger::Event(kind), behavior);
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = &(Trigger, Behavior)> {
+		self.hooks.iter()
+	}
+}
+
+impl Default for Hooks {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl World {
+	// Runs the behaviors for every event so far this tick. Events the
+	// behaviors cause themselves don't set off further hooks until the next
+	// tick, so hooks can't trigger each other endlessly.
+	pub(super) fn run_hooks(&mut self) {
+		let mut fired = Vec::new();
+		for event in &self.events {
+			for &(trigger, behavior) in self.hooks.iter() {
+				if trigger.matches(event) {
+					fired.push((behavior, event.subject()));
+				}
+			}
+		}
+
+		for (behavior, subject) in fired {
+			self.perform(behavior, subject);
+		}
+	}
+
+	fn perform(&mut self, behavior: Behavior, subject: Option<EntityId>) {
+		match behavior {
+			Behavior::Teleport { destination } => {
+				let id = match subject {
+					Some(id) => id,
+					None => return,
+				};
+				if self.get_entity(id).is_none() {
+					return;
+				}
+				if self.get_frame(destination.frame_id).is_none() {
+					elog(format!(
+						"Teleport to missing frame {}",
+						destination.frame_id
+					));
+					return;
+				}
+				self.move_entity_to(id, destination);
+			}
+			Behavior::SetTile {
+				frame,
+				x,
+				y,
+				tile,
+				show,
+			} => {
+				let result = self.set_tile(frame, x as isize, y as isize, tile);
+				if let Err(error) = result {
+					elog(format!("Hook could not set tile: {:?}", error));
+				} else if show {
+					self.events.push(WorldEvent::TargetShown { frame, x, y });
+				}
+			}
+			Behavior::Checkpoint => match subject {
+				Some(id) if self.focus_entity == Some(id) => {
+					self.reach_checkpoint(id)
+				}
+				_ => {}
+			},
+		}
+	}
+}
@@ -0,0 +1,120 @@
+use super::types::Direction;
+use crate::geometry::Scalar;
+
+// What the player is trying to do during one tick, however it was input.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Actions {
+	// Movement along each axis of the player's frame, from -1 to 1, with
+	// positive y downwards.
+	pub move_x: Scalar,
+	pub move_y: Scalar,
+	pub jump: bool,
+	// Whether jump is held down at all, rather than just pressed this tick,
+	// for springs to throw the player further.
+	pub jump_held: bool,
+	// Reads a sign the player is in front of, and otherwise places a tile
+	// beside them.
+	pub interact: bool,
+	// Clears the tile beside the player.
+	pub remove: bool,
+	// Turns the directional tile beside the player a quarter turn.
+	pub rotate: bool,
+	// Walks faster while held, for as long as there is stamina for it.
+	pub sprint: bool,
+	// Throws the player the way they point, if they have the stamina.
+	pub dash: bool,
+	// Which way the player is pointing, if they are aiming rather than
+	// just facing somewhere. Placing and clearing tiles use this first.
+	pub aim: Direction,
+}
+
+impl Actions {
+	// Nothing at all, for ticks where the player can't act.
+	pub fn none() -> Self {
+		Self::default()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::super::World;
+	use super::*;
+
+	// The player just above a floor along the bottom three rows of a
+	// single frame, in the middle of it.
+	fn standing() -> World {
+		let mut level = String::from("frame 0\n");
+		for y in 0..16 {
+			level += if y < 13 {
+				"................\n"
+			} else {
+				"################\n"
+			};
+		}
+		level += "spawn 0 0.0 0.6\n";
+		World::from_level_str(&level).unwrap()
+	}
+
+	fn player_x(world: &World) -> Scalar {
+		world
+			.get_entity(world.focus_entity.unwrap())
+			.unwrap()
+			.position
+			.x
+	}
+
+	fn tick_for(world: &mut World, actions: Actions, ticks: usize) {
+		for _ in 0..ticks {
+			world.tick(&actions);
+		}
+	}
+
+	#[test]
+	fn moving_walks_the_player_along_the_floor() {
+		let mut world = standing();
+		tick_for(&mut world, Actions::none(), 10);
+		let start = player_x(&world);
+
+		let right = Actions {
+			move_x: 1.0,
+			..Actions::none()
+		};
+		tick_for(&mut world, right, 30);
+		let after_right = player_x(&world);
+		assert!(after_right > start + 0.1, "only got to {}", after_right);
+
+		let left = Actions {
+			move_x: -1.0,
+			..Actions::none()
+		};
+		tick_for(&mut world, left, 60);
+		assert!(player_x(&world) < start - 0.1);
+	}
+
+	#[test]
+	fn doing_nothing_leaves_the_player_where_they_are() {
+		let mut world = standing();
+		tick_for(&mut world, Actions::none(), 10);
+		let start = player_x(&world);
+		tick_for(&mut world, Actions::none(), 60);
+		assert_eq!(player_x(&world), start);
+	}
+
+	#[test]
+	fn jumping_lifts_the_player_off_the_floor() {
+		let mut world = standing();
+		let down = Actions {
+			move_y: 1.0,
+			..Actions::none()
+		};
+		tick_for(&mut world, down, 30);
+		let id = world.focus_entity.unwrap();
+		assert!(world.get_entity(id).unwrap().grounded);
+
+		let jump = Actions { jump: true, ..down };
+		world.tick(&jump);
+		tick_for(&mut world, down, 2);
+		let player = world.get_entity(id).unwrap();
+		assert!(player.position.y < 0.6, "still at {}", player.position.y);
+	}
+}
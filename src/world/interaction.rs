@@ -0,0 +1,114 @@
+use super::events::WorldEvent;
+use super::types::*;
+use super::{TileLocation, World};
+
+// What can be interacted with, and what interacting does. The first rule
+// that applies to a tile is the one it gets. There are only tiles: pickups
+// are collected and blocks pushed just by touching them, and checkpoints
+// are hooks set off by entering a tile, so no entity has anything to do
+// when interacted with.
+struct InteractionRule {
+	applies: fn(&World, TileLocation) -> bool,
+	// What interacting does, for the prompt, such as "read".
+	verb: &'static str,
+	// Why interacting would do nothing yet, if it wouldn't.
+	unavailable: fn(&World, EntityId, TileLocation) -> Option<&'static str>,
+	act: fn(&mut World, EntityId, TileLocation),
+}
+
+const INTERACTIONS: &[InteractionRule] = &[InteractionRule {
+	applies: |world, at| world.tile_at_location(at) == Some(Tile::Sign),
+	verb: "read",
+	unavailable: |world, _, (frame_id, x, y)| {
+		let data = world
+			.get_frame(frame_id)
+			.and_then(|frame| frame.tile_data(x, y));
+		match data {
+			Some(TileData::Text(_)) => None,
+			_ => Some("nothing written"),
+		}
+	},
+	act: |world, entity, (frame, x, y)| {
+		world.events.push(WorldEvent::SignActivated {
+			entity,
+			frame,
+			x: x as usize,
+			y: y as usize,
+		})
+	},
+}];
+
+// Something an entity has in reach to interact with.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Interaction {
+	pub target: TileLocation,
+	pub verb: &'static str,
+	pub unavailable: Option<&'static str>,
+	// Index into `INTERACTIONS`.
+	rule: usize,
+}
+
+impl World {
+	fn tile_at_location(&self, (frame_id, x, y): TileLocation) -> Option<Tile> {
+		self.get_frame(frame_id).map(|frame| *frame.tile(x, y))
+	}
+
+	// The tiles within one of the entity's, the one it is in first, then the
+	// one it points at, then the rest beside it.
+	fn tiles_in_reach(&self, id: EntityId) -> Vec<TileLocation> {
+		let pointing = match self.get_entity(id) {
+			Some(entity) => entity.pointing(),
+			None => return Vec::new(),
+		};
+		let here = self.tile_location_at_entity(id);
+		let others = Direction::iter().filter(|&&direction| {
+			direction != pointing && direction != Direction::Neutral
+		});
+		let mut tiles = vec![here];
+		for &direction in std::iter::once(&pointing).chain(others) {
+			tiles.extend(self.tile_beyond(here, direction));
+		}
+		tiles
+	}
+
+	// What the entity would interact with now, the nearest thing in reach
+	// that can be, or failing that the nearest that can't be yet, to say
+	// why.
+	pub fn interaction(&self, id: EntityId) -> Option<Interaction> {
+		let mut unavailable = None;
+		for target in self.tiles_in_reach(id) {
+			let found = INTERACTIONS
+				.iter()
+				.position(|rule| (rule.applies)(self, target));
+			let rule = match found {
+				Some(rule) => rule,
+				None => continue,
+			};
+			let interaction = Interaction {
+				target,
+				verb: INTERACTIONS[rule].verb,
+				unavailable: (INTERACTIONS[rule].unavailable)(self, id, target),
+				rule,
+			};
+			if interaction.unavailable.is_none() {
+				return Some(interaction);
+			}
+			unavailable = unavailable.or(Some(interaction));
+		}
+		unavailable
+	}
+
+	// Interacts with whatever `interaction` gives, or with nothing in reach
+	// that can be interacted with places a tile instead.
+	pub(super) fn interact(&mut self, id: EntityId) {
+		match self.interaction(id) {
+			Some(interaction) if interaction.unavailable.is_none() => {
+				let act = INTERACTIONS[interaction.rule].act;
+				act(self, id, interaction.target);
+			}
+			_ => {
+				let _ = self.try_place_tile(id);
+			}
+		}
+	}
+}
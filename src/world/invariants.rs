@@ -0,0 +1,149 @@
+use super::neighborhood::{point_contacts, slope_at};
+use super::types::*;
+use super::{World, TILE_SIZE};
+use crate::geometry::Scalar;
+
+// Something that should never be true of a world between ticks, whatever
+// the player did. Unlike `LevelIssue`s these are bugs in the simulation,
+// not in a level.
+#[derive(Copy, Clone, Debug)]
+pub enum InvariantViolation {
+	MissingFocusEntity,
+	// The entity is on a frame the world doesn't have.
+	MissingFrame(EntityId, FrameId),
+	// The entity's position is outside its frame, so ought to have been
+	// normalized onto another.
+	Unnormalized(EntityId, WorldPosition),
+	NonFiniteVelocity(EntityId),
+	// The entity is grounded with nothing solid under it.
+	Unsupported(EntityId, WorldPosition),
+}
+
+impl std::fmt::Display for InvariantViolation {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		use InvariantViolation::*;
+		match self {
+			MissingFocusEntity => write!(f, "the focus entity is missing"),
+			MissingFrame(id, frame) => {
+				write!(f, "entity {} is on missing frame {}", id.0, frame)
+			}
+			Unnormalized(id, p) => write!(
+				f,
+				"entity {} is off its frame at {} ({}, {})",
+				id.0, p.frame_id, p.x, p.y
+			),
+			NonFiniteVelocity(id) => {
+				write!(f, "entity {} has a non-finite velocity", id.0)
+			}
+			Unsupported(id, p) => write!(
+				f,
+				"entity {} is grounded on nothing at {} ({}, {})",
+				id.0, p.frame_id, p.x, p.y
+			),
+		}
+	}
+}
+
+impl World {
+	// Everything wrong with the world's state, in entity order. Meant to be
+	// checked between ticks by anything driving the world in bulk, such as
+	// the fuzz test, since a violation usually only shows up as a panic
+	// or a stuck entity some time later.
+	pub fn invariant_violations(&self) -> Vec<InvariantViolation> {
+		use InvariantViolation::*;
+
+		let mut violations = Vec::new();
+		if self
+			.focus_entity
+			.and_then(|id| self.get_entity(id))
+			.is_none()
+		{
+			violations.push(MissingFocusEntity);
+		}
+
+		for id in self.entity_ids() {
+			let entity = self.get_entity(id).unwrap();
+			let p = entity.position;
+			if self.get_frame(p.frame_id).is_none() {
+				violations.push(MissingFrame(id, p.frame_id));
+				continue;
+			}
+			let inside = |v: Scalar| (-1.0..=1.0).contains(&v);
+			if inside(p.x) == false || inside(p.y) == false {
+				violations.push(Unnormalized(id, p));
+			}
+			let v = entity.velocity;
+			if v.x.is_finite() == false
+				|| v.y.is_finite() == false
+				|| v.z.is_finite() == false
+			{
+				violations.push(NonFiniteVelocity(id));
+			}
+			if entity.grounded
+				&& self.is_supported(p, entity.gravity_dir) == false
+			{
+				violations.push(Unsupported(id, p));
+			}
+		}
+
+		violations
+	}
+
+	// Whether there is a solid tile, or an entity filling one, or a ramp,
+	// within one tile of `position` the way gravity pulls. Sideways as well
+	// as straight down, since a point resting on a tile's corner is held up
+	// by it, and one walking off the end of a ramp follows its slope for the
+	// rest of that step.
+	fn is_supported(
+		&self,
+		position: WorldPosition,
+		gravity: Direction,
+	) -> bool {
+		// Where three frames meet at a corner of the cube, which way a tile
+		// lies depends on which frame it is seen from, so any touching the
+		// corner counts. Positions kept inside a frame can only come within
+		// an epsilon of its far edges, so are also tried moved out onto the
+		// corner itself.
+		let on_edge = |v: Scalar| 1.0 - v.abs() <= Scalar::EPSILON;
+		if on_edge(position.x) && on_edge(position.y) {
+			let corner = WorldPosition {
+				x: position.x.signum(),
+				y: position.y.signum(),
+				..position
+			};
+			let touching = |p: WorldPosition| {
+				let (a, b, c, d) = point_contacts(self, p).as_tuple();
+				a || b || c || d
+			};
+			if touching(position) || corner.normalize(self).is_ok_and(touching)
+			{
+				return true;
+			}
+		}
+
+		let (gx, gy) = gravity.unit();
+		let (nx, ny) = (-gy, gx);
+		let half = TILE_SIZE / 2.0;
+		for &across in &[-half, 0.0, half] {
+			for &down in &[0.0, half] {
+				let probe = WorldPosition {
+					x: position.x + gx * down + nx * across,
+					y: position.y + gy * down + ny * across,
+					..position
+				};
+				let probe = match probe.normalize(self) {
+					Ok(probe) => probe,
+					Err(_) => continue,
+				};
+				if slope_at(self, probe).is_some() {
+					return true;
+				}
+				let (a, b, c, d) = point_contacts(self, probe).as_tuple();
+				if down > 0.0 && (a || b || c || d) {
+					return true;
+				}
+			}
+		}
+		false
+	}
+}
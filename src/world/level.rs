@@ -0,0 +1,1269 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::str::FromStr;
+
+use super::collision::{channel_names, channels_from_names};
+use super::frame::Frame;
+use super::types::*;
+use super::{
+	Behavior, CollisionChannels, EdgeBehavior, Entity, EntityKind, Persistence,
+	StatusEffect, StatusKind, Trigger, World, WorldEventKind, FRAME_WIDTH,
+	WORM_SEGMENTS,
+};
+use super::{TagId, TILE_SIZE};
+use crate::geometry::{vec3, Scalar};
+use crate::prelude::*;
+
+// Levels are plain text made of one statement per line. Blank lines and
+// lines starting with `;` are ignored.
+//
+//   name <text>
+//   author <text>
+//       Shown when the level is completed.
+//   par <ticks>
+//       The time to beat, in world ticks.
+//   next_level <path>
+//       The level to play after this one. Paths are tried against the
+//       levels built into the game before the file system.
+//   frame <id>
+//       Followed by FRAME_WIDTH rows of FRAME_WIDTH tile symbols each, as
+//       given by `Tile::symbol`.
+//   link <frame> <edge> <frame> <edge>
+//       Connects two frame edges, named up, down, left or right.
+//   open <frame> <edge>
+//       Leaves the edge unlinked on purpose, for anything that goes over it
+//       to fall off the world. Other unlinked edges are taken for mistakes
+//       by `validate_level`.
+//   background <frame> <pattern> <rrggbb>
+//       The pattern behind the frame's tiles, one of none, dots, diagonals
+//       or checker, and its tint in hex. Each frame id has a default.
+//   wind <frame> <x> <y>
+//       A constant push on everything that moves in the frame, as an
+//       acceleration per tick along the frame's axes.
+//   rotate <frame> <x> <y> <quarter turns>
+//       Turns the ramp, conveyor or spring at x, y clockwise from the way
+//       its symbol faces, by 0 to 3 quarter turns.
+//   spawn <frame> <x> <y>
+//       Where the player starts, in frame coordinates. Defaults to a point
+//       on the lowest numbered frame.
+//   sign <frame> <x> <y> <string>
+//       Attaches the text of a strings entry to the sign tile at x, y.
+//   coin <frame> <x> <y>
+//       Places a coin, in frame coordinates.
+//   gravity_orb <frame> <x> <y>
+//       Places a pickup that flips the player's gravity.
+//   speed_boost <frame> <x> <y>
+//       Places a pickup that speeds the player up for a while.
+//   energizer <frame> <x> <y>
+//       Places a pickup that makes the player's stamina come back faster
+//       for a while.
+//   block <frame> <x> <y>
+//       Places a pushable block filling the tile at x, y.
+//   walker <frame> <x> <y> [edge behavior]
+//       Places a walker, in frame coordinates. What it does at the end of
+//       its floor is one of turn_at_ledge, walk_off or follow_surface, and
+//       walk_off by default.
+//
+//       Pickups, blocks and walkers can end with `tag <name>`, which puts
+//       them in a group for hooks to refer to, and with
+//       `persist <death|level|forever>`, in either order. That is whether
+//       the entity is put back as it was at the last checkpoint when the
+//       player dies, as walkers and blocks are by default, stays the way the
+//       player left it until the level is loaded again, as pickups do, or
+//       stays that way even then.
+//   entity <kind> <frame> <x> <y> [<property>=<value> ...]
+//       Places an entity of any kind above but the player, or a worm, in
+//       frame coordinates, with blocks filling the tile the point is in.
+//       Properties are `tag`, `persist` and `edge`, for a walker's edge
+//       behavior, taking the same values as above, `segments`, for how
+//       many segments follow a worm's head, four by default, and `channel`,
+//       `collides` and `sensor`. The first two are collision channels, any
+//       of player, enemy, pickup or block joined by `+`, or all or none:
+//       the ones the entity is in and the ones it touches. Entities only
+//       touch when each is in a channel the other touches. A sensor, set
+//       with `sensor=on`, is only overlapped, as pickups are by default,
+//       and never in the way. Unknown kinds and properties are skipped with
+//       a warning rather than failing, so that levels using newer ones
+//       still load.
+//   effect <status name> <ticks> <magnitude>
+//       Starts the player with a status effect.
+//   hook <trigger> <behavior>
+//       Runs a behavior each time the trigger happens. Triggers are
+//       `tile <frame> <x> <y>`, `tile_kind <tile name>`,
+//       `event <event name>` and `group_cleared <tag>`, when the last
+//       entity in a group is removed. Behaviors are
+//       `teleport <frame> <x> <y>`, in frame coordinates,
+//       `set_tile <frame> <x> <y> <tile name>`, followed by `show` to
+//       have the camera go and show the tile changing, and `checkpoint`,
+//       which makes wherever the player is the place they come back to
+//       after dying.
+//   fall_damage <on|off>
+//       Whether landing too fast hurts. Off by default.
+//   edits <death|level|forever>
+//       How long the player's own tile edits last, as with entities' persist.
+//       Until the level is loaded again by default.
+//   strings
+//       Must come last. Every following line is `<index> <text>`, where
+//       `\n` in the text starts a new paragraph.
+
+// What a level says about itself rather than its world.
+#[derive(Clone, Debug, Default)]
+pub struct LevelInfo {
+	pub name: Option<String>,
+	pub author: Option<String>,
+	pub par_ticks: Option<u64>,
+	pub next_level: Option<String>,
+	// How long the player's own tile edits last.
+	pub edit_persistence: Persistence,
+}
+
+#[derive(Clone, Debug)]
+pub struct LevelError {
+	// The line the error was found on, counting from one, if it can be
+	// attributed to one.
+	pub line: Option<usize>,
+	pub message: String,
+}
+
+impl std::fmt::Display for LevelError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self.line {
+			Some(line) => write!(f, "line {}: {}", line, self.message),
+			None => write!(f, "{}", self.message),
+		}
+	}
+}
+
+struct Link {
+	line: usize,
+	parent: FrameId,
+	parent_edge: Direction,
+	child: FrameId,
+	child_edge: Direction,
+}
+
+struct Hook {
+	line: usize,
+	trigger: Trigger,
+	behavior: Behavior,
+}
+
+// An entity a level places, made once the level's frames are linked.
+#[derive(Clone, Debug)]
+pub struct EntitySpawn {
+	pub kind: EntityKind,
+	// In frame coordinates. A block fills the tile this is in.
+	pub position: WorldPosition,
+	// Only used by walkers.
+	pub edge_behavior: EdgeBehavior,
+	// How many segments follow a worm's head. Only used by worms.
+	pub segments: usize,
+	pub tag: Option<TagId>,
+	// The kind's own if not given.
+	pub persistence: Option<Persistence>,
+	// The kind's own if not given.
+	pub channels: Option<CollisionChannels>,
+}
+
+impl EntitySpawn {
+	pub fn new(kind: EntityKind, position: WorldPosition) -> Self {
+		Self {
+			kind,
+			position,
+			edge_behavior: EdgeBehavior::default(),
+			segments: WORM_SEGMENTS,
+			tag: None,
+			persistence: None,
+			channels: None,
+		}
+	}
+}
+
+struct Sign {
+	line: usize,
+	frame: FrameId,
+	x: isize,
+	y: isize,
+	string: usize,
+}
+
+impl World {
+	pub fn from_level_str(source: &str) -> Result<Self, LevelError> {
+		let mut world = World::empty();
+		let mut links = Vec::new();
+		let mut signs = Vec::new();
+		let mut strings: HashMap<usize, String> = HashMap::new();
+		let mut spawn = None;
+		let mut spawns = Vec::new();
+		let mut hooks = Vec::new();
+		let mut effects = Vec::new();
+		let mut backgrounds = Vec::new();
+		let mut winds = Vec::new();
+		let mut opens = Vec::new();
+		let mut rotations = Vec::new();
+
+		let mut lines =
+			source.lines().enumerate().map(|(i, line)| (i + 1, line));
+		while let Some((line_number, line)) = lines.next() {
+			let line = line.trim();
+			if is_ignored(line) {
+				continue;
+			}
+			let error = |message: String| LevelError {
+				line: Some(line_number),
+				message,
+			};
+
+			let mut words = line.split_whitespace();
+			let keyword = words.next().unwrap();
+			let mut args: Vec<&str> = words.collect();
+			let (tag, persistence) = match keyword {
+				"coin" | "gravity_orb" | "speed_boost" | "energizer"
+				| "block" | "walker" => {
+					let mut persistence = split_persistence(&mut args);
+					let tag = split_tag(&mut args);
+					if persistence.is_none() {
+						persistence = split_persistence(&mut args);
+					}
+					(tag, persistence)
+				}
+				_ => (None, None),
+			};
+			let tag = tag.map(|name| world.tag_id(name));
+			let persistence = persistence
+				.map(|name| parse_persistence(name, line_number))
+				.transpose()?;
+			match keyword {
+				"name" | "author" if args.is_empty() == false => {
+					let text = Some(args.join(" "));
+					match keyword {
+						"name" => world.level.name = text,
+						_ => world.level.author = text,
+					}
+				}
+				"par" => {
+					expect_args(&args, 1, line_number)?;
+					world.level.par_ticks = Some(parse(args[0], line_number)?);
+				}
+				"next_level" => {
+					expect_args(&args, 1, line_number)?;
+					world.level.next_level = Some(args[0].to_string());
+				}
+				"frame" => {
+					expect_args(&args, 1, line_number)?;
+					let id = FrameId::new(parse(args[0], line_number)?);
+					if world.get_frame(id).is_some() {
+						return Err(error(format!("duplicate frame {}", id)));
+					}
+
+					let mut frame = Frame::new(id);
+					for y in 0..FRAME_WIDTH {
+						let (row_number, row) =
+							lines.next().ok_or_else(|| {
+								error(format!("frame {} is missing rows", id))
+							})?;
+						let row: Vec<char> = row.trim().chars().collect();
+						if row.len() != FRAME_WIDTH {
+							return Err(LevelError {
+								line: Some(row_number),
+								message: format!(
+									"expected {} tiles, found {}",
+									FRAME_WIDTH,
+									row.len()
+								),
+							});
+						}
+						for (x, &symbol) in row.iter().enumerate() {
+							let tile =
+								Tile::from_symbol(symbol).ok_or_else(|| {
+									LevelError {
+										line: Some(row_number),
+										message: format!(
+											"unknown tile '{}'",
+											symbol
+										),
+									}
+								})?;
+							*frame.tile_mut(x as isize, y as isize) = tile;
+						}
+					}
+					world.insert_frame(frame);
+				}
+				"link" => {
+					expect_args(&args, 4, line_number)?;
+					links.push(Link {
+						line: line_number,
+						parent: FrameId::new(parse(args[0], line_number)?),
+						parent_edge: parse_edge(args[1], line_number)?,
+						child: FrameId::new(parse(args[2], line_number)?),
+						child_edge: parse_edge(args[3], line_number)?,
+					});
+				}
+				"open" => {
+					expect_args(&args, 2, line_number)?;
+					opens.push((
+						line_number,
+						FrameId::new(parse(args[0], line_number)?),
+						parse_edge(args[1], line_number)?,
+					));
+				}
+				"background" => {
+					expect_args(&args, 3, line_number)?;
+					let pattern = BackgroundPattern::from_name(args[1])
+						.ok_or_else(|| {
+							error(format!("unknown pattern '{}'", args[1]))
+						})?;
+					let tint = BackgroundStyle::parse_tint(args[2])
+						.ok_or_else(|| {
+							error(format!("invalid tint '{}'", args[2]))
+						})?;
+					backgrounds.push((
+						line_number,
+						FrameId::new(parse(args[0], line_number)?),
+						BackgroundStyle { pattern, tint },
+					));
+				}
+				"wind" => {
+					expect_args(&args, 3, line_number)?;
+					winds.push((
+						line_number,
+						FrameId::new(parse(args[0], line_number)?),
+						vec3(
+							parse(args[1], line_number)?,
+							parse(args[2], line_number)?,
+							0.0,
+						),
+					));
+				}
+				"rotate" => {
+					expect_args(&args, 4, line_number)?;
+					let turns: u8 = parse(args[3], line_number)?;
+					if turns > 3 {
+						return Err(error(format!(
+							"expected 0 to 3 quarter turns, found {}",
+							turns
+						)));
+					}
+					rotations.push((
+						line_number,
+						FrameId::new(parse(args[0], line_number)?),
+						parse(args[1], line_number)?,
+						parse(args[2], line_number)?,
+						Angle::from_quarter_turns(turns),
+					));
+				}
+				"spawn" => {
+					expect_args(&args, 3, line_number)?;
+					spawn = Some((
+						line_number,
+						WorldPosition {
+							frame_id: FrameId::new(parse(
+								args[0],
+								line_number,
+							)?),
+							x: parse(args[1], line_number)?,
+							y: parse(args[2], line_number)?,
+						},
+					));
+				}
+				"sign" => {
+					expect_args(&args, 4, line_number)?;
+					signs.push(Sign {
+						line: line_number,
+						frame: FrameId::new(parse(args[0], line_number)?),
+						x: parse(args[1], line_number)?,
+						y: parse(args[2], line_number)?,
+						string: parse(args[3], line_number)?,
+					});
+				}
+				"coin" | "gravity_orb" | "speed_boost" | "energizer" => {
+					expect_args(&args, 3, line_number)?;
+					let kind = match keyword {
+						"coin" => EntityKind::Coin,
+						"gravity_orb" => EntityKind::GravityOrb,
+						"speed_boost" => EntityKind::SpeedBoost,
+						_ => EntityKind::Energizer,
+					};
+					let position = WorldPosition {
+						frame_id: FrameId::new(parse(args[0], line_number)?),
+						x: parse(args[1], line_number)?,
+						y: parse(args[2], line_number)?,
+					};
+					spawns.push((
+						line_number,
+						EntitySpawn {
+							tag,
+							persistence,
+							..EntitySpawn::new(kind, position)
+						},
+					));
+				}
+				"entity" => {
+					if let Some(spawn) =
+						parse_entity(&mut world, &args, line_number)?
+					{
+						spawns.push((line_number, spawn));
+					}
+				}
+				"hook" => {
+					let (trigger, rest) =
+						parse_trigger(&mut world, &args, line_number)?;
+					let behavior = parse_behavior(rest, line_number)?;
+					hooks.push(Hook {
+						line: line_number,
+						trigger,
+						behavior,
+					});
+				}
+				"walker" => {
+					if args.len() != 4 {
+						expect_args(&args, 3, line_number)?;
+					}
+					let edge_behavior = match args.get(3) {
+						Some(&name) => EdgeBehavior::from_name(name)
+							.ok_or_else(|| {
+								error(format!(
+									"unknown edge behavior '{}'",
+									name
+								))
+							})?,
+						None => EdgeBehavior::default(),
+					};
+					let position = WorldPosition {
+						frame_id: FrameId::new(parse(args[0], line_number)?),
+						x: parse(args[1], line_number)?,
+						y: parse(args[2], line_number)?,
+					};
+					spawns.push((
+						line_number,
+						EntitySpawn {
+							edge_behavior,
+							tag,
+							persistence,
+							..EntitySpawn::new(EntityKind::Walker, position)
+						},
+					));
+				}
+				"block" => {
+					expect_args(&args, 3, line_number)?;
+					let frame_id = FrameId::new(parse(args[0], line_number)?);
+					let x: isize = parse(args[1], line_number)?;
+					let y: isize = parse(args[2], line_number)?;
+					let w = FRAME_WIDTH as isize;
+					if x < 0 || y < 0 || x >= w || y >= w {
+						return Err(error(format!("no tile at {}, {}", x, y)));
+					}
+					let position = WorldPosition {
+						frame_id,
+						x: -1.0 + (x as Scalar + 0.5) * TILE_SIZE,
+						y: -1.0 + (y as Scalar + 0.5) * TILE_SIZE,
+					};
+					spawns.push((
+						line_number,
+						EntitySpawn {
+							tag,
+							persistence,
+							..EntitySpawn::new(EntityKind::Block, position)
+						},
+					));
+				}
+				"effect" => {
+					expect_args(&args, 3, line_number)?;
+					let kind =
+						StatusKind::from_name(args[0]).ok_or_else(|| {
+							error(format!("unknown status '{}'", args[0]))
+						})?;
+					effects.push(StatusEffect::new(
+						kind,
+						parse(args[1], line_number)?,
+						parse(args[2], line_number)?,
+					));
+				}
+				"fall_damage" => {
+					expect_args(&args, 1, line_number)?;
+					world.physics.fall_damage = match args[0] {
+						"on" => true,
+						"off" => false,
+						other => {
+							return Err(error(format!(
+								"expected on or off, found '{}'",
+								other
+							)))
+						}
+					};
+				}
+				"edits" => {
+					expect_args(&args, 1, line_number)?;
+					world.level.edit_persistence =
+						parse_persistence(args[0], line_number)?;
+				}
+				"strings" => {
+					expect_args(&args, 0, line_number)?;
+					for (line_number, line) in lines.by_ref() {
+						let line = line.trim();
+						if is_ignored(line) {
+							continue;
+						}
+						let mut parts = line.splitn(2, char::is_whitespace);
+						let index = parse(parts.next().unwrap(), line_number)?;
+						let text = parts.next().unwrap_or("").trim();
+						strings.insert(index, text.replace("\\n", "\n"));
+					}
+				}
+				_ => {
+					return Err(error(format!(
+						"unknown statement '{}'",
+						keyword
+					)))
+				}
+			}
+		}
+
+		for link in links {
+			let error = |message: String| LevelError {
+				line: Some(link.line),
+				message,
+			};
+			for &(frame_id, edge) in &[
+				(link.parent, link.parent_edge),
+				(link.child, link.child_edge),
+			] {
+				let frame = world.get_frame(frame_id).ok_or_else(|| {
+					error(format!("unknown frame {}", frame_id))
+				})?;
+				if frame.borders.at_direction(edge).is_some() {
+					return Err(error(format!(
+						"edge {} of frame {} is already linked",
+						edge.name(),
+						frame_id
+					)));
+				}
+			}
+			if (link.parent, link.parent_edge) == (link.child, link.child_edge)
+			{
+				return Err(error("cannot link an edge to itself".to_string()));
+			}
+			world.connect_frames(
+				link.parent,
+				link.parent_edge,
+				link.child,
+				link.child_edge,
+			);
+		}
+
+		for (line, frame_id, edge) in opens {
+			let frame =
+				world.get_frame_mut(frame_id).ok_or_else(|| LevelError {
+					line: Some(line),
+					message: format!("unknown frame {}", frame_id),
+				})?;
+			if frame.borders.at_direction(edge).is_some() {
+				return Err(LevelError {
+					line: Some(line),
+					message: format!(
+						"edge {} of frame {} is linked, so can't be open",
+						edge.name(),
+						frame_id
+					),
+				});
+			}
+			if frame.open_edges.contains(&edge) == false {
+				frame.open_edges.push(edge);
+			}
+		}
+
+		for (line, frame_id, style) in backgrounds {
+			let frame =
+				world.get_frame_mut(frame_id).ok_or_else(|| LevelError {
+					line: Some(line),
+					message: format!("unknown frame {}", frame_id),
+				})?;
+			frame.background = style;
+		}
+
+		for (line, frame_id, wind) in winds {
+			let frame =
+				world.get_frame_mut(frame_id).ok_or_else(|| LevelError {
+					line: Some(line),
+					message: format!("unknown frame {}", frame_id),
+				})?;
+			frame.wind = Some(wind);
+		}
+
+		for (line, frame_id, x, y, rotation) in rotations {
+			let error = |message: String| LevelError {
+				line: Some(line),
+				message,
+			};
+			let frame = world
+				.get_frame_mut(frame_id)
+				.ok_or_else(|| error(format!("unknown frame {}", frame_id)))?;
+			if frame.tile(x, y).is_directional() == false {
+				return Err(error(format!(
+					"no ramp, conveyor or spring at {}, {}",
+					x, y
+				)));
+			}
+			frame.set_rotation(x, y, rotation);
+		}
+
+		for sign in signs {
+			let error = |message: String| LevelError {
+				line: Some(sign.line),
+				message,
+			};
+			let text = strings.get(&sign.string).ok_or_else(|| {
+				error(format!("unknown string {}", sign.string))
+			})?;
+			let frame = world.get_frame_mut(sign.frame).ok_or_else(|| {
+				error(format!("unknown frame {}", sign.frame))
+			})?;
+			if *frame.tile(sign.x, sign.y) != Tile::Sign {
+				return Err(error(format!(
+					"no sign tile at {}, {}",
+					sign.x, sign.y
+				)));
+			}
+			frame.set_tile_data(
+				sign.x,
+				sign.y,
+				Some(TileData::Text(text.clone())),
+			);
+		}
+
+		for hook in hooks {
+			check_hook(&world, &hook)?;
+			world.hooks.add(hook.trigger, hook.behavior);
+		}
+
+		for (line, spawn) in spawns {
+			world.spawn_entity(&spawn).map_err(|message| LevelError {
+				line: Some(line),
+				message,
+			})?;
+		}
+
+		let spawn = match spawn {
+			Some((line, position)) => {
+				check_position(&world, position, line)?;
+				position
+			}
+			None => {
+				let frame_id = world
+					.frames
+					.keys()
+					.min_by_key(|id| id.0)
+					.copied()
+					.ok_or_else(|| LevelError {
+						line: None,
+						message: "level has no frames".to_string(),
+					})?;
+				WorldPosition {
+					frame_id,
+					x: 0.3,
+					y: 0.1,
+				}
+			}
+		};
+
+		world.activate_all_fluids();
+
+		let mut player = Entity::new_player(&mut world, spawn.frame_id);
+		player.position = spawn;
+		world.spawn = Some(spawn);
+		player.effects = effects;
+		let player_id = world.insert_entity(player);
+		world.focus_entity = Some(player_id);
+		world.take_checkpoint(player_id);
+
+		Ok(world)
+	}
+
+	// Makes the entity a level or the editor describes. Fails without
+	// changing anything if it has nowhere to go.
+	pub fn spawn_entity(
+		&mut self,
+		spawn: &EntitySpawn,
+	) -> Result<EntityId, String> {
+		let position = spawn.position;
+		let frame = self
+			.get_frame(position.frame_id)
+			.ok_or_else(|| format!("unknown frame {}", position.frame_id))?;
+		if position.is_in_bounds() == false {
+			return Err("position is outside its frame".to_string());
+		}
+		let entity = match spawn.kind {
+			EntityKind::Player => {
+				return Err("the player is placed by spawn".to_string())
+			}
+			EntityKind::WormSegment => {
+				return Err("worm segments come with their worm".to_string())
+			}
+			EntityKind::Worm => Entity::new_worm(self, position),
+			EntityKind::Walker => {
+				Entity::new_walker(self, position, spawn.edge_behavior)
+			}
+			EntityKind::Block => {
+				let (x, y) = self.tile_index_at_position(position);
+				if frame.is_solid(x, y) {
+					return Err(format!("tile {}, {} is solid", x, y));
+				}
+				Entity::new_block(self, position.frame_id, x, y)
+			}
+			kind => Entity::new_pickup(self, kind, position),
+		};
+		let id = self.insert_entity(Entity {
+			tag: spawn.tag,
+			persistence: spawn.persistence.unwrap_or(entity.persistence),
+			channels: spawn.channels.unwrap_or(entity.channels),
+			..entity
+		});
+		if spawn.kind == EntityKind::Worm {
+			self.grow_worm(id, spawn.segments);
+		}
+		Ok(id)
+	}
+
+	// The world as a level that `from_level_str` loads back. Only what the
+	// format can describe is kept: level info, tiles and their rotations,
+	// links and open edges, backgrounds, wind, sign text, pickups, blocks,
+	// walkers, tags, persistence, collision channels, and the focus entity's
+	// position and status effects as the spawn point.
+	// Velocities, gravity directions, health and checkpoints are lost.
+	pub fn to_level_string(&self) -> String {
+		let mut out = String::new();
+		let mut strings = Vec::new();
+		let mut signs = Vec::new();
+
+		let level = &self.level;
+		if let Some(name) = &level.name {
+			writeln!(out, "name {}", name).unwrap();
+		}
+		if let Some(author) = &level.author {
+			writeln!(out, "author {}", author).unwrap();
+		}
+		if let Some(par_ticks) = level.par_ticks {
+			writeln!(out, "par {}", par_ticks).unwrap();
+		}
+		if let Some(next_level) = &level.next_level {
+			writeln!(out, "next_level {}", next_level).unwrap();
+		}
+		if level.edit_persistence != Persistence::default() {
+			writeln!(out, "edits {}", level.edit_persistence.name()).unwrap();
+		}
+
+		for frame_id in self.frame_ids() {
+			let frame = &self.frames[&frame_id];
+			writeln!(out, "frame {}", frame_id.0).unwrap();
+			out.push_str(&frame.to_ascii());
+
+			let mut entries: Vec<_> = frame.tile_data_entries().collect();
+			entries.sort_by_key(|&((x, y), _)| (y, x));
+			for ((x, y), data) in entries {
+				match data {
+					TileData::Text(text) => {
+						signs.push((frame_id, x, y, strings.len()));
+						strings.push(text);
+					}
+					TileData::Squash(_) => {}
+				}
+			}
+		}
+
+		// Each link is stored on both of its frames but written once.
+		let mut written = HashSet::new();
+		for frame_id in self.frame_ids() {
+			let frame = &self.frames[&frame_id];
+			for &edge in Direction::iter() {
+				if edge == Direction::Neutral {
+					continue;
+				}
+				let link = match frame.borders.at_direction(edge) {
+					Some(link) => link,
+					None => continue,
+				};
+				if written.contains(&(frame_id.0, edge.name())) {
+					continue;
+				}
+				written.insert((link.frame.0, link.entry_edge.name()));
+				writeln!(
+					out,
+					"link {} {} {} {}",
+					frame_id.0,
+					edge.name(),
+					link.frame.0,
+					link.entry_edge.name()
+				)
+				.unwrap();
+			}
+		}
+		for frame_id in self.frame_ids() {
+			for &edge in self.frames[&frame_id].open_edges.iter() {
+				writeln!(out, "open {} {}", frame_id.0, edge.name()).unwrap();
+			}
+		}
+
+		for frame_id in self.frame_ids() {
+			let style = self.frames[&frame_id].background;
+			if style != BackgroundStyle::default_for(frame_id) {
+				let (pattern, tint) = (style.pattern.name(), style.tint_hex());
+				writeln!(out, "background {} {} {}", frame_id.0, pattern, tint)
+					.unwrap();
+			}
+			if let Some(wind) = self.frames[&frame_id].wind {
+				writeln!(out, "wind {} {} {}", frame_id.0, wind.x, wind.y)
+					.unwrap();
+			}
+			let mut rotations: Vec<_> =
+				self.frames[&frame_id].rotation_entries().collect();
+			rotations.sort_by_key(|&((x, y), _)| (y, x));
+			for ((x, y), rotation) in rotations {
+				let turns = rotation.quarter_turns();
+				writeln!(out, "rotate {} {} {} {}", frame_id.0, x, y, turns)
+					.unwrap();
+			}
+		}
+
+		if let Some(player) =
+			self.focus_entity.and_then(|id| self.get_entity(id))
+		{
+			let p = player.position;
+			writeln!(out, "spawn {} {} {}", p.frame_id.0, p.x, p.y).unwrap();
+			for effect in player.effects.iter() {
+				let (kind, ticks) =
+					(effect.kind.name(), effect.remaining_ticks);
+				writeln!(out, "effect {} {} {}", kind, ticks, effect.magnitude)
+					.unwrap();
+			}
+		}
+
+		for (frame_id, x, y, string) in signs {
+			writeln!(out, "sign {} {} {} {}", frame_id.0, x, y, string)
+				.unwrap();
+		}
+
+		let mut entities: Vec<&Entity> = self.entities.values().collect();
+		entities.sort_by_key(|entity| entity.id.0);
+		for entity in entities {
+			let p = entity.position;
+			let statement = match entity.kind {
+				EntityKind::Coin => {
+					format!("coin {} {} {}", p.frame_id.0, p.x, p.y)
+				}
+				EntityKind::GravityOrb => {
+					format!("gravity_orb {} {} {}", p.frame_id.0, p.x, p.y)
+				}
+				EntityKind::SpeedBoost => {
+					format!("speed_boost {} {} {}", p.frame_id.0, p.x, p.y)
+				}
+				EntityKind::Energizer => {
+					format!("energizer {} {} {}", p.frame_id.0, p.x, p.y)
+				}
+				EntityKind::Block => {
+					let (x, y) = self.tile_index_at_position(p);
+					format!("block {} {} {}", p.frame_id.0, x, y)
+				}
+				EntityKind::Walker => {
+					let behavior = entity.edge_behavior.name();
+					format!(
+						"walker {} {} {} {}",
+						p.frame_id.0, p.x, p.y, behavior
+					)
+				}
+				// Written as one with its worm.
+				EntityKind::Player | EntityKind::WormSegment => continue,
+				// Always an entity statement, below.
+				EntityKind::Worm => String::new(),
+			};
+			// Only entity statements can give collision channels, or a
+			// worm at all.
+			let channels = entity.channels;
+			if channels != CollisionChannels::default_for(entity.kind)
+				|| entity.kind == EntityKind::Worm
+			{
+				let (kind, frame) = (entity.kind.name(), p.frame_id.0);
+				write!(out, "entity {} {} {} {}", kind, frame, p.x, p.y)
+					.unwrap();
+				if entity.kind == EntityKind::Walker {
+					write!(out, " edge={}", entity.edge_behavior.name())
+						.unwrap();
+				}
+				if entity.kind == EntityKind::Worm {
+					let mut segments = 0;
+					let mut last = entity.id;
+					while let Some(follower) = self.worm_follower(last) {
+						segments += 1;
+						last = follower;
+					}
+					write!(out, " segments={}", segments).unwrap();
+				}
+				if let Some(tag) = entity.tag {
+					write!(out, " tag={}", self.tag_name(tag)).unwrap();
+				}
+				if entity.persistence != Persistence::default_for(entity.kind) {
+					write!(out, " persist={}", entity.persistence.name())
+						.unwrap();
+				}
+				if channels != CollisionChannels::default_for(entity.kind) {
+					let sensor = if channels.sensor { "on" } else { "off" };
+					write!(
+						out,
+						" channel={} collides={} sensor={}",
+						channel_names(channels.category),
+						channel_names(channels.collides_with),
+						sensor
+					)
+					.unwrap();
+				}
+				out.push('\n');
+				continue;
+			}
+			out.push_str(&statement);
+			if let Some(tag) = entity.tag {
+				write!(out, " tag {}", self.tag_name(tag)).unwrap();
+			}
+			if entity.persistence != Persistence::default_for(entity.kind) {
+				write!(out, " persist {}", entity.persistence.name()).unwrap();
+			}
+			out.push('\n');
+		}
+
+		if self.physics.fall_damage {
+			writeln!(out, "fall_damage on").unwrap();
+		}
+
+		for &(trigger, behavior) in self.hooks.iter() {
+			let trigger = match trigger {
+				Trigger::TileAt { frame, x, y } => {
+					format!("tile {} {} {}", frame.0, x, y)
+				}
+				Trigger::TileKind(tile) => format!("tile_kind {}", tile.name()),
+				Trigger::Event(kind) => format!("event {}", kind.name()),
+				Trigger::GroupCleared(tag) => {
+					format!("group_cleared {}", self.tag_name(tag))
+				}
+			};
+			let behavior = match behavior {
+				Behavior::Teleport { destination: p } => {
+					format!("teleport {} {} {}", p.frame_id.0, p.x, p.y)
+				}
+				Behavior::SetTile {
+					frame,
+					x,
+					y,
+					tile,
+					show,
+				} => {
+					let show = if show { " show" } else { "" };
+					let (frame, tile) = (frame.0, tile.name());
+					format!("set_tile {} {} {} {}{}", frame, x, y, tile, show)
+				}
+				Behavior::Checkpoint => "checkpoint".to_string(),
+			};
+			writeln!(out, "hook {} {}", trigger, behavior).unwrap();
+		}
+
+		if strings.is_empty() == false {
+			writeln!(out, "strings").unwrap();
+			for (index, text) in strings.iter().enumerate() {
+				writeln!(out, "{} {}", index, text.replace('\n', "\\n"))
+					.unwrap();
+			}
+		}
+
+		out
+	}
+}
+
+// An `entity` statement, or nothing if it is of a kind this build doesn't
+// know.
+fn parse_entity(
+	world: &mut World,
+	args: &[&str],
+	line: usize,
+) -> Result<Option<EntitySpawn>, LevelError> {
+	if args.len() < 4 {
+		return Err(LevelError {
+			line: Some(line),
+			message: format!(
+				"expected at least 4 arguments, found {}",
+				args.len()
+			),
+		});
+	}
+	let kind = match EntityKind::from_name(args[0]) {
+		Some(EntityKind::Player) | Some(EntityKind::WormSegment) | None => {
+			log(format!(
+				"Level line {}: skipping unknown entity kind '{}'",
+				line, args[0]
+			));
+			return Ok(None);
+		}
+		Some(kind) => kind,
+	};
+	let position = WorldPosition {
+		frame_id: FrameId::new(parse(args[1], line)?),
+		x: parse(args[2], line)?,
+		y: parse(args[3], line)?,
+	};
+	let mut spawn = EntitySpawn::new(kind, position);
+	for property in &args[4..] {
+		let mut parts = property.splitn(2, '=');
+		let (key, value) = (parts.next().unwrap(), parts.next());
+		match (key, value) {
+			("tag", Some(name)) => spawn.tag = Some(world.tag_id(name)),
+			("persist", Some(name)) => {
+				spawn.persistence = Some(parse_persistence(name, line)?)
+			}
+			("segments", Some(count)) => spawn.segments = parse(count, line)?,
+			("edge", Some(name)) => {
+				spawn.edge_behavior =
+					EdgeBehavior::from_name(name).ok_or_else(|| LevelError {
+						line: Some(line),
+						message: format!("unknown edge behavior '{}'", name),
+					})?
+			}
+			("channel", Some(names)) | ("collides", Some(names)) => {
+				let bits =
+					channels_from_names(names).ok_or_else(|| LevelError {
+						line: Some(line),
+						message: format!("unknown channels '{}'", names),
+					})?;
+				let channels = spawn
+					.channels
+					.get_or_insert(CollisionChannels::default_for(kind));
+				match key {
+					"channel" => channels.category = bits,
+					_ => channels.collides_with = bits,
+				}
+			}
+			("sensor", Some(value)) => {
+				let sensor = match value {
+					"on" => true,
+					"off" => false,
+					_ => {
+						return Err(LevelError {
+							line: Some(line),
+							message: format!(
+								"expected on or off, found '{}'",
+								value
+							),
+						})
+					}
+				};
+				spawn
+					.channels
+					.get_or_insert(CollisionChannels::default_for(kind))
+					.sensor = sensor;
+			}
+			_ => log(format!(
+				"Level line {}: skipping unknown entity property '{}'",
+				line, property
+			)),
+		}
+	}
+	Ok(Some(spawn))
+}
+
+fn check_position(
+	world: &World,
+	position: WorldPosition,
+	line: usize,
+) -> Result<(), LevelError> {
+	let error = |message: String| LevelError {
+		line: Some(line),
+		message,
+	};
+	if world.get_frame(position.frame_id).is_none() {
+		return Err(error(format!("unknown frame {}", position.frame_id)));
+	}
+	if position.is_in_bounds() == false {
+		return Err(error("position is outside its frame".to_string()));
+	}
+	Ok(())
+}
+
+fn check_hook(world: &World, hook: &Hook) -> Result<(), LevelError> {
+	let error = |message: String| LevelError {
+		line: Some(hook.line),
+		message,
+	};
+	let check_tile = |frame: FrameId, x: usize, y: usize| {
+		if world.get_frame(frame).is_none() {
+			return Err(error(format!("unknown frame {}", frame)));
+		}
+		if x >= FRAME_WIDTH || y >= FRAME_WIDTH {
+			return Err(error(format!("no tile at {}, {}", x, y)));
+		}
+		Ok(())
+	};
+
+	if let Trigger::TileAt { frame, x, y } = hook.trigger {
+		check_tile(frame, x, y)?;
+	}
+	match hook.behavior {
+		Behavior::Teleport { destination } => {
+			check_position(world, destination, hook.line)
+		}
+		Behavior::SetTile { frame, x, y, .. } => check_tile(frame, x, y),
+		Behavior::Checkpoint => Ok(()),
+	}
+}
+
+// The trigger at the start of a hook's arguments, and the arguments after
+// it.
+fn parse_trigger<'a, 'b>(
+	world: &mut World,
+	args: &'a [&'b str],
+	line: usize,
+) -> Result<(Trigger, &'a [&'b str]), LevelError> {
+	let kind = args.first().copied().unwrap_or("");
+	let rest = args.get(1..).unwrap_or(&[]);
+	match kind {
+		"tile" if rest.len() >= 3 => {
+			let trigger = Trigger::TileAt {
+				frame: FrameId::new(parse(rest[0], line)?),
+				x: parse(rest[1], line)?,
+				y: parse(rest[2], line)?,
+			};
+			Ok((trigger, &rest[3..]))
+		}
+		"tile_kind" if rest.is_empty() == false => {
+			let tile = parse_tile_name(rest[0], line)?;
+			Ok((Trigger::TileKind(tile), &rest[1..]))
+		}
+		"event" if rest.is_empty() == false => {
+			let kind = WorldEventKind::from_name(rest[0]).ok_or_else(|| {
+				LevelError {
+					line: Some(line),
+					message: format!("unknown event '{}'", rest[0]),
+				}
+			})?;
+			Ok((Trigger::Event(kind), &rest[1..]))
+		}
+		"group_cleared" if rest.is_empty() == false => {
+			let tag = world.tag_id(rest[0]);
+			Ok((Trigger::GroupCleared(tag), &rest[1..]))
+		}
+		_ => Err(LevelError {
+			line: Some(line),
+			message: format!("invalid trigger '{}'", args.join(" ")),
+		}),
+	}
+}
+
+fn parse_behavior(args: &[&str], line: usize) -> Result<Behavior, LevelError> {
+	let kind = args.first().copied().unwrap_or("");
+	let rest = args.get(1..).unwrap_or(&[]);
+	match kind {
+		"teleport" => {
+			expect_args(rest, 3, line)?;
+			Ok(Behavior::Teleport {
+				destination: WorldPosition {
+					frame_id: FrameId::new(parse(rest[0], line)?),
+					x: parse(rest[1], line)?,
+					y: parse(rest[2], line)?,
+				},
+			})
+		}
+		"set_tile" => {
+			let show = rest.len() == 5 && rest[4] == "show";
+			let rest = if show { &rest[..4] } else { rest };
+			expect_args(rest, 4, line)?;
+			Ok(Behavior::SetTile {
+				frame: FrameId::new(parse(rest[0], line)?),
+				x: parse(rest[1], line)?,
+				y: parse(rest[2], line)?,
+				tile: parse_tile_name(rest[3], line)?,
+				show,
+			})
+		}
+		"checkpoint" => {
+			expect_args(rest, 0, line)?;
+			Ok(Behavior::Checkpoint)
+		}
+		_ => Err(LevelError {
+			line: Some(line),
+			message: format!("invalid behavior '{}'", args.join(" ")),
+		}),
+	}
+}
+
+fn parse_tile_name(word: &str, line: usize) -> Result<Tile, LevelError> {
+	Tile::from_name(word).ok_or_else(|| LevelError {
+		line: Some(line),
+		message: format!("unknown tile '{}'", word),
+	})
+}
+
+// Takes a trailing `tag <name>` off an entity statement's arguments.
+fn split_tag<'a>(args: &mut Vec<&'a str>) -> Option<&'a str> {
+	let count = args.len();
+	if count >= 2 && args[count - 2] == "tag" {
+		let name = args.pop();
+		args.pop();
+		return name;
+	}
+	None
+}
+
+// Takes a trailing `persist <name>` off an entity statement's arguments.
+fn split_persistence<'a>(args: &mut Vec<&'a str>) -> Option<&'a str> {
+	let count = args.len();
+	if count >= 2 && args[count - 2] == "persist" {
+		let name = args.pop();
+		args.pop();
+		return name;
+	}
+	None
+}
+
+fn parse_persistence(
+	word: &str,
+	line: usize,
+) -> Result<Persistence, LevelError> {
+	Persistence::from_name(word).ok_or_else(|| LevelError {
+		line: Some(line),
+		message: format!("unknown persistence '{}'", word),
+	})
+}
+
+fn is_ignored(line: &str) -> bool {
+	line.is_empty() || line.starts_with(';')
+}
+
+fn expect_args(
+	args: &[&str],
+	count: usize,
+	line: usize,
+) -> Result<(), LevelError> {
+	if args.len() == count {
+		Ok(())
+	} else {
+		Err(LevelError {
+			line: Some(line),
+			message: format!(
+				"expected {} arguments, found {}",
+				count,
+				args.len()
+			),
+		})
+	}
+}
+
+fn parse<T: FromStr>(word: &str, line: usize) -> Result<T, LevelError> {
+	word.parse().map_err(|_| LevelError {
+		line: Some(line),
+		message: format!("invalid value '{}'", word),
+	})
+}
+
+fn parse_edge(word: &str, line: usize) -> Result<Direction, LevelError> {
+	match Direction::from_name(word) {
+		Some(Direction::Neutral) | None => Err(LevelError {
+			line: Some(line),
+			message: format!("invalid edge '{}'", word),
+		}),
+		Some(edge) => Ok(edge),
+	}
+}
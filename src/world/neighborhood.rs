@@ -0,0 +1,271 @@
+use super::edges::{compose_rotation, transform_tile_across};
+use super::frame::Frame;
+use super::types::*;
+use super::{Slope, TileLocation, World, CONTACT_PROBE, FRAME_WIDTH};
+use crate::geometry::Scalar;
+
+// Looks frames up by id, so that position and tile helpers can work from
+// either the whole world or a few frames fetched ahead of time.
+pub trait FrameSource {
+	fn frame(&self, id: FrameId) -> Option<&Frame>;
+}
+
+impl FrameSource for World {
+	fn frame(&self, id: FrameId) -> Option<&Frame> {
+		self.frames.get(&id)
+	}
+}
+
+// A frame and the frames linked to its edges, fetched once so that resolving
+// an entity's movement doesn't search the world's frame map for every
+// contact sample. Frames further away are still found through the world.
+pub struct FrameNeighborhood<'a> {
+	world: &'a World,
+	frames: [Option<(FrameId, &'a Frame)>; 5],
+}
+
+impl<'a> FrameNeighborhood<'a> {
+	pub fn around(world: &'a World, center: FrameId) -> Self {
+		let mut frames = [None; 5];
+		if let Some(frame) = world.frame(center) {
+			frames[0] = Some((center, frame));
+			let edges = [
+				Direction::Up,
+				Direction::Down,
+				Direction::Left,
+				Direction::Right,
+			];
+			for (slot, &edge) in frames[1..].iter_mut().zip(edges.iter()) {
+				*slot = frame.borders.at_direction(edge).and_then(|link| {
+					world.frame(link.frame).map(|frame| (link.frame, frame))
+				});
+			}
+		}
+		Self { world, frames }
+	}
+}
+
+impl FrameSource for FrameNeighborhood<'_> {
+	fn frame(&self, id: FrameId) -> Option<&Frame> {
+		self.frames
+			.iter()
+			.flatten()
+			.find(|&&(frame_id, _)| frame_id == id)
+			.map(|&(_, frame)| frame)
+			.or_else(|| self.world.frame(id))
+	}
+}
+
+// Wraps a tile index that is past an edge of its frame onto the frame
+// beyond that edge, turned to match the link. Indices may be at most one
+// frame away. There is no tile past an unlinked edge, nor diagonally past
+// a corner, since only three frames meet at a corner of the cube.
+pub fn normalize_tile_index(
+	frames: &impl FrameSource,
+	origin_frame_position: FrameId,
+	x: isize,
+	y: isize,
+) -> Option<(FrameId, isize, isize)> {
+	normalize_tile_index_turned(frames, origin_frame_position, x, y)
+		.map(|(location, _)| location)
+}
+
+// A tile and which way it is turned, as seen from the origin frame. One
+// past an edge is turned in its own frame's axes, which the link may turn
+// again on the way back to the origin frame's.
+pub fn oriented_tile(
+	frames: &impl FrameSource,
+	origin_frame_position: FrameId,
+	x: isize,
+	y: isize,
+) -> Option<(Tile, Angle)> {
+	let ((frame_id, x, y), crossing) =
+		normalize_tile_index_turned(frames, origin_frame_position, x, y)?;
+	let frame = frames.frame(frame_id)?;
+	let rotation = frame.rotation(x, y).compose(crossing.negative());
+	Some((*frame.tile(x, y), rotation))
+}
+
+// `normalize_tile_index`, along with the rotation that takes the origin
+// frame's axes into those of the frame the tile is on.
+fn normalize_tile_index_turned(
+	frames: &impl FrameSource,
+	origin_frame_position: FrameId,
+	x: isize,
+	y: isize,
+) -> Option<((FrameId, isize, isize), Angle)> {
+	let origin_frame = frames.frame(origin_frame_position)?;
+	let borders = origin_frame.borders;
+
+	let w = FRAME_WIDTH as isize;
+
+	if x >= w * 2 || x < -w * 2 || y >= w * 2 || y < -w * 2 {
+		panic!(
+			"Tile index exists outside its own frame \
+			and orthgonally neighboring frames"
+		);
+	}
+	if (x >= w || x < 0) && (y >= w || y < 0) {
+		return None;
+	}
+
+	use Direction::*;
+	let direction = match (x, y) {
+		(x, _) if (x >= w) => Right,
+		(x, _) if (x < 0) => Left,
+		(_, y) if (y >= w) => Down,
+		(_, y) if (y < 0) => Up,
+		_ => return Some(((origin_frame_position, x, y), Angle::Clockwise0)),
+	};
+
+	let link = borders.at_direction(direction)?;
+	let (real_x, real_y) = transform_tile_across(direction, link, x, y);
+	let crossing = compose_rotation(direction, link);
+	Some(((link.frame, real_x, real_y), crossing))
+}
+
+// Which of the four tiles meeting at a point are solid.
+pub fn point_contacts(
+	frames: &impl FrameSource,
+	position: WorldPosition,
+) -> Contacts {
+	// Past an unlinked edge, or anywhere else a position can't be, there is
+	// nothing to run into, and whatever got there is told so once its move
+	// is normalized.
+	let none = Contacts {
+		top_left: false,
+		top_right: false,
+		bottom_left: false,
+		bottom_right: false,
+	};
+	let position = match position.normalize(frames) {
+		Ok(position) => position,
+		Err(_) => return none,
+	};
+
+	let f = FRAME_WIDTH as Scalar / 2.0;
+	let tile_x_left = (((position.x + 1.0) * f).ceil() - 1.0) as isize;
+	let tile_x_right = (((position.x + 1.0) * f).floor()) as isize;
+	let tile_y_up = (((position.y + 1.0) * f).ceil() - 1.0) as isize;
+	let tile_y_down = ((position.y + 1.0) * f).floor() as isize;
+
+	// Most contacts are within the position's own frame, which can then be
+	// read directly rather than looked up again for every tile.
+	let frame = frames.frame(position.frame_id).unwrap();
+	let w = FRAME_WIDTH as isize;
+	let is_solid = |x, y| {
+		let inside = |i| i >= 0 && i < w;
+		if inside(x) && inside(y) {
+			return frame.is_solid(x, y);
+		}
+		// Nothing diagonally past a corner of the frame, which a point
+		// exactly on the corner reaches, or across an unlinked edge.
+		let ((tile_frame_pos, wrapped_x, wrapped_y), crossing) =
+			match normalize_tile_index_turned(frames, position.frame_id, x, y) {
+				Some(turned) => turned,
+				None => return false,
+			};
+		// Whether a ramp is solid depends on which way up it is, which is
+		// taken in this frame's axes rather than its own.
+		let tile_frame = match frames.frame(tile_frame_pos) {
+			Some(tile_frame) => tile_frame,
+			None => return false,
+		};
+		let rotation = tile_frame
+			.rotation(wrapped_x, wrapped_y)
+			.compose(crossing.negative());
+		tile_frame
+			.tile(wrapped_x, wrapped_y)
+			.is_solid_turned(rotation)
+			|| tile_frame.owner(wrapped_x, wrapped_y).is_some()
+	};
+
+	Contacts {
+		top_left: is_solid(tile_x_left, tile_y_up),
+		top_right: is_solid(tile_x_right, tile_y_up),
+		bottom_left: is_solid(tile_x_left, tile_y_down),
+		bottom_right: is_solid(tile_x_right, tile_y_down),
+	}
+}
+
+// The spring a point on a tile boundary runs into moving `direction`, if
+// the spring's face is turned towards it: where the spring is, and which
+// way it throws, in the axes of the point's frame. Either tile can be hit
+// by a point on the corner between two.
+pub(super) fn spring_ahead(
+	frames: &impl FrameSource,
+	position: WorldPosition,
+	direction: Direction,
+) -> Option<(TileLocation, Direction)> {
+	let f = FRAME_WIDTH as Scalar / 2.0;
+	let (x, y) = ((position.x + 1.0) * f, (position.y + 1.0) * f);
+	let before = |i: Scalar| i.ceil() as isize - 1;
+	let after = |i: Scalar| i.floor() as isize;
+	use Direction::*;
+	let tiles = match direction {
+		Up => [(before(x), before(y)), (after(x), before(y))],
+		Down => [(before(x), after(y)), (after(x), after(y))],
+		Left => [(before(x), before(y)), (before(x), after(y))],
+		Right => [(after(x), before(y)), (after(x), after(y))],
+		Neutral => return None,
+	};
+
+	for &(x, y) in &tiles {
+		let (location, crossing) = match normalize_tile_index_turned(
+			frames,
+			position.frame_id,
+			x,
+			y,
+		) {
+			Some(turned) => turned,
+			None => continue,
+		};
+		let (frame_id, x, y) = location;
+		let tile_frame = match frames.frame(frame_id) {
+			Some(tile_frame) => tile_frame,
+			None => continue,
+		};
+		let rotation = tile_frame.rotation(x, y).compose(crossing.negative());
+		let throw = tile_frame.tile(x, y).spring_direction(rotation);
+		if throw == Some(direction.reverse()) {
+			return Some((location, direction.reverse()));
+		}
+	}
+	None
+}
+
+// The ramp tile a point lies in or is standing on top of, looking across
+// linked edges so that one at a frame edge is followed from either side.
+// A point on the boundary between two tiles is on both, the one after it
+// first, so a ramp is still stood on from the very end of its top.
+pub(super) fn slope_at(
+	frames: &impl FrameSource,
+	position: WorldPosition,
+) -> Option<Slope> {
+	let f = FRAME_WIDTH as Scalar / 2.0;
+	let x = (position.x + 1.0) * f;
+	let columns = [x.floor(), x.ceil() - 1.0];
+
+	// Above first: at the bottom corner of a ramp, the point is on the tile
+	// boundary and the tile below it is the floor.
+	for &probe in &[-CONTACT_PROBE, CONTACT_PROBE] {
+		let tile_y = ((position.y + probe + 1.0) * f).floor();
+		for &tile_x in &columns {
+			let (x, y) = (tile_x as isize, tile_y as isize);
+			let (tile, rotation) =
+				match oriented_tile(frames, position.frame_id, x, y) {
+					Some(oriented) => oriented,
+					None => continue,
+				};
+			if tile.slope_height(rotation, 0.0).is_some() {
+				return Some(Slope {
+					tile,
+					rotation,
+					left: tile_x / f - 1.0,
+					top: tile_y / f - 1.0,
+				});
+			}
+		}
+	}
+	None
+}
@@ -0,0 +1,95 @@
+use std::collections::VecDeque;
+
+use super::surface::Unfolding;
+use super::types::*;
+use super::World;
+use crate::geometry::Scalar;
+
+// A frame's place in the world's net: the whole surface unfolded flat, with
+// each frame one grid cell of width two.
+#[derive(Copy, Clone, Debug)]
+pub struct NetFace {
+	pub frame_id: FrameId,
+	// Grid cell the frame's center lies on, relative to the root frame.
+	pub cell: (isize, isize),
+	unfolding: Unfolding,
+}
+
+impl NetFace {
+	// Frame coordinates to net coordinates, in which the root frame covers
+	// [-1, 1] on both axes.
+	pub fn to_net(&self, x: Scalar, y: Scalar) -> (Scalar, Scalar) {
+		let point = self.unfolding.apply(x, y);
+		(point.x, point.y)
+	}
+
+	// Net coordinates back to the frame's, which may be outside it.
+	pub fn from_net(&self, x: Scalar, y: Scalar) -> (Scalar, Scalar) {
+		self.unfolding.unapply(x, y)
+	}
+}
+
+impl World {
+	// Marks wherever the focus entity has ended up this tick as visited.
+	pub(super) fn visit_focus_frame(&mut self) {
+		let frame_id =
+			match self.focus_entity.and_then(|id| self.get_entity(id)) {
+				Some(entity) => entity.position.frame_id,
+				None => return,
+			};
+		if let Some(frame) = self.frames.get_mut(&frame_id) {
+			frame.visited = true;
+		}
+	}
+
+	// Lays out every frame reachable from `root` flat around it, breadth
+	// first. A frame whose cell is already taken is placed through another of
+	// its links if one leads somewhere free, and left out otherwise.
+	pub fn net_layout(&self, root: FrameId) -> Vec<NetFace> {
+		let mut faces = Vec::new();
+		if self.get_frame(root).is_none() {
+			return faces;
+		}
+
+		let mut queue = VecDeque::new();
+		queue.push_back((root, Unfolding::identity()));
+
+		while let Some((frame_id, unfolding)) = queue.pop_front() {
+			let center = unfolding.apply(0.0, 0.0);
+			let cell = (
+				(center.x / 2.0).round() as isize,
+				(center.y / 2.0).round() as isize,
+			);
+			let placed = faces.iter().any(|face: &NetFace| {
+				face.frame_id == frame_id || face.cell == cell
+			});
+			if placed {
+				continue;
+			}
+			faces.push(NetFace {
+				frame_id,
+				cell,
+				unfolding,
+			});
+
+			let frame = match self.get_frame(frame_id) {
+				Some(frame) => frame,
+				None => continue,
+			};
+			for &exit_edge in Direction::iter() {
+				if exit_edge == Direction::Neutral {
+					continue;
+				}
+				if let Some(link) = frame.borders.at_direction(exit_edge) {
+					if self.get_frame(link.frame).is_none() {
+						continue;
+					}
+					let next = unfolding.across(exit_edge, link);
+					queue.push_back((link.frame, next));
+				}
+			}
+		}
+
+		faces
+	}
+}
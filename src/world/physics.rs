@@ -0,0 +1,78 @@
+use crate::geometry::Scalar;
+
+// Tunable movement constants, all per tick.
+#[derive(Copy, Clone, Debug)]
+pub struct PhysicsConfig {
+	// Added to the player's velocity each tick a movement key is held.
+	pub walk_acceleration: Scalar,
+	pub jump_speed: Scalar,
+	// Fraction of velocity kept from one tick to the next.
+	pub velocity_retention: Scalar,
+	// Added to the velocity of anything standing on a conveyor each tick,
+	// in the direction the belt runs.
+	pub conveyor_acceleration: Scalar,
+	// Whether placing a solid tile on an entity pushes it out of the way.
+	// Otherwise the placement is refused.
+	pub push_out_of_placed_tiles: bool,
+	// Whether landing faster than `fall_damage_speed` hurts. Levels turn
+	// this on with the `fall_damage` statement.
+	pub fall_damage: bool,
+	pub fall_damage_speed: Scalar,
+	// Health lost per unit of landing speed over `fall_damage_speed`.
+	pub fall_damage_per_speed: Scalar,
+	// The speed a spring throws things off at, however fast they hit it.
+	pub spring_speed: Scalar,
+	// Added to the throw for a player holding jump as they hit the spring.
+	pub spring_jump_bonus: Scalar,
+	// The most stamina the player can have, which dashing and sprinting
+	// use up.
+	pub max_stamina: Scalar,
+	// Regained each tick on the ground while not sprinting.
+	pub stamina_regen: Scalar,
+	// Walking acceleration is multiplied by this while sprinting, which
+	// costs `sprint_stamina_cost` each tick.
+	pub sprint_multiplier: Scalar,
+	pub sprint_stamina_cost: Scalar,
+	// Added to the player's velocity the way they point by a dash, which
+	// is refused with less than `dash_stamina_cost` left.
+	pub dash_speed: Scalar,
+	pub dash_stamina_cost: Scalar,
+}
+
+impl Default for PhysicsConfig {
+	fn default() -> Self {
+		Self {
+			walk_acceleration: 0.002,
+			jump_speed: 0.018,
+			velocity_retention: 0.8,
+			conveyor_acceleration: 0.0005,
+			push_out_of_placed_tiles: false,
+			fall_damage: false,
+			fall_damage_speed: 0.012,
+			fall_damage_per_speed: 500.0,
+			spring_speed: 0.03,
+			spring_jump_bonus: 0.008,
+			max_stamina: 1.0,
+			stamina_regen: 0.004,
+			sprint_multiplier: 1.6,
+			sprint_stamina_cost: 0.006,
+			dash_speed: 0.035,
+			dash_stamina_cost: 0.4,
+		}
+	}
+}
+
+impl PhysicsConfig {
+	// The speed something left standing on a conveyor settles at, where
+	// the belt's push is balanced by friction.
+	pub fn conveyor_speed(&self) -> Scalar {
+		self.terminal_speed(self.conveyor_acceleration)
+	}
+
+	// The speed a constant acceleration settles at, where what it adds each
+	// tick is what friction takes away.
+	pub fn terminal_speed(&self, acceleration: Scalar) -> Scalar {
+		let retention = self.velocity_retention;
+		acceleration * retention / (1.0 - retention)
+	}
+}
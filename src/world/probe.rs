@@ -0,0 +1,82 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hasher;
+
+use super::{Actions, Direction, World};
+
+// A fixed run of the simulation, for checking that every target steps the
+// world exactly alike. Its hashes are compared against another build's,
+// and its last against the one tests/determinism.rs checks in for each
+// precision.
+pub const PROBE_SEED: u64 = 0x5eed_cafe;
+pub const PROBE_TICKS: u64 = 5000;
+// Ticks between the hashes the probe reports.
+pub const PROBE_INTERVAL: u64 = 500;
+
+// Input for each tick of the probe, as a cycle of held moves with presses
+// now and then. Made up from the tick alone, so nothing but the simulation
+// itself can differ between runs.
+fn probe_actions(tick: u64) -> Actions {
+	let phase = tick % 600;
+	let (move_x, aim) = match phase / 150 {
+		0 => (1.0, Direction::Neutral),
+		1 => (-1.0, Direction::Down),
+		2 => (0.0, Direction::Left),
+		_ => (1.0, Direction::Up),
+	};
+	Actions {
+		move_x,
+		move_y: 0.0,
+		jump: tick.is_multiple_of(47),
+		jump_held: phase % 90 < 30,
+		interact: tick.is_multiple_of(211),
+		remove: tick.is_multiple_of(307),
+		rotate: tick.is_multiple_of(401),
+		sprint: phase < 75,
+		dash: tick.is_multiple_of(263),
+		aim,
+	}
+}
+
+// Runs the probe, giving the tick count and state hash every
+// `PROBE_INTERVAL` ticks, ending with those after the last tick.
+pub fn determinism_probe() -> Vec<(u64, u64)> {
+	let mut world = World::with_seed(PROBE_SEED);
+
+	let mut hashes = Vec::new();
+	for tick in 1..=PROBE_TICKS {
+		world.tick(&probe_actions(tick));
+		if tick.is_multiple_of(PROBE_INTERVAL) {
+			hashes.push((world.tick_count, world.state_hash()));
+		}
+	}
+	hashes
+}
+
+// Hashes `usize` and `isize` values, enum discriminants among them, at 64
+// bits whatever their size on the target, so that wasm's four byte ones
+// hash the same as native eight byte ones.
+pub(super) struct StableHasher(DefaultHasher);
+
+impl StableHasher {
+	pub(super) fn new() -> Self {
+		Self(DefaultHasher::new())
+	}
+}
+
+impl Hasher for StableHasher {
+	fn finish(&self) -> u64 {
+		self.0.finish()
+	}
+
+	fn write(&mut self, bytes: &[u8]) {
+		self.0.write(bytes);
+	}
+
+	fn write_usize(&mut self, value: usize) {
+		self.0.write_u64(value as u64);
+	}
+
+	fn write_isize(&mut self, value: isize) {
+		self.0.write_i64(value as i64);
+	}
+}
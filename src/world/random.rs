@@ -0,0 +1,192 @@
+// Seed for worlds that aren't given one, so that they come out the same
+// every run.
+pub const DEFAULT_SEED: u64 = 0x5eed_c0be;
+
+// A small seeded generator (SplitMix64), so that everything random in a
+// world follows from its seed and the same seed gives the same world on
+// every platform. Not for anything that needs to be unpredictable.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Rng {
+	state: u64,
+}
+
+impl Rng {
+	pub fn new(seed: u64) -> Self {
+		Self { state: seed }
+	}
+
+	// Where the sequence has got to, which `new` carries on from.
+	pub fn state(&self) -> u64 {
+		self.state
+	}
+
+	pub fn next_u64(&mut self) -> u64 {
+		self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+		let mut z = self.state;
+		z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+		z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+		z ^ (z >> 31)
+	}
+
+	// From `start` up to but not including `end`. `start` if the range is
+	// empty.
+	pub fn range_i(&mut self, start: isize, end: isize) -> isize {
+		if end <= start {
+			return start;
+		}
+		let span = (end as i128 - start as i128) as u128;
+		// The high bits of a widening multiply, which unlike a remainder
+		// favors no part of the range by more than one in 2^64.
+		let offset = (self.next_u64() as u128 * span) >> 64;
+		(start as i128 + offset as i128) as isize
+	}
+
+	// From `start` up to but not including `end`.
+	pub fn range_f32(&mut self, start: f32, end: f32) -> f32 {
+		// The top 24 bits, as many as an f32 holds exactly.
+		let unit = (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32;
+		start + unit * (end - start)
+	}
+
+	// True with probability `p`.
+	pub fn chance(&mut self, p: f32) -> bool {
+		self.range_f32(0.0, 1.0) < p
+	}
+
+	pub fn pick<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+		if items.is_empty() {
+			return None;
+		}
+		let index = self.range_i(0, items.len() as isize);
+		items.get(index as usize)
+	}
+
+	// Fisher-Yates, so every order is equally likely.
+	pub fn shuffle<T>(&mut self, items: &mut [T]) {
+		for i in (1..items.len()).rev() {
+			let j = self.range_i(0, i as isize + 1) as usize;
+			items.swap(i, j);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const ROLLS: usize = 60_000;
+
+	// How many of `ROLLS` rolls from 0 to `buckets` land in each bucket,
+	// checked against what an even spread gives with a chi-squared
+	// statistic well past what chance would ever get to.
+	fn is_even(counts: &[usize]) -> bool {
+		let expected = ROLLS as f64 / counts.len() as f64;
+		let chi_squared: f64 = counts
+			.iter()
+			.map(|&count| (count as f64 - expected).powi(2) / expected)
+			.sum();
+		// Far beyond the 99.9th percentile for the bucket counts here.
+		chi_squared < 3.0 * counts.len() as f64 + 30.0
+	}
+
+	#[test]
+	fn a_seed_gives_the_same_numbers_on_every_platform() {
+		// The SplitMix64 reference outputs for seed 0.
+		let mut rng = Rng::new(0);
+		let first: Vec<_> = (0..3).map(|_| rng.next_u64()).collect();
+		assert_eq!(
+			first,
+			vec![
+				0xe220_a839_7b1d_cdaf,
+				0x6e78_9e6a_a1b9_65f4,
+				0x06c4_5d18_8009_454f
+			]
+		);
+
+		let mut rng = Rng::new(DEFAULT_SEED);
+		let rolls: Vec<_> = (0..8).map(|_| rng.range_i(-3, 5)).collect();
+		let mut again = Rng::new(DEFAULT_SEED);
+		let repeated: Vec<_> = (0..8).map(|_| again.range_i(-3, 5)).collect();
+		assert_eq!(rolls, repeated);
+		assert_eq!(rng.state(), again.state());
+	}
+
+	#[test]
+	fn carrying_on_from_a_state_gives_the_rest_of_the_sequence() {
+		let mut rng = Rng::new(42);
+		rng.next_u64();
+		let mut resumed = Rng::new(rng.state());
+		assert_eq!(rng.next_u64(), resumed.next_u64());
+	}
+
+	#[test]
+	fn integer_ranges_are_spread_evenly_and_stay_in_bounds() {
+		let mut rng = Rng::new(7);
+		let mut counts = [0; 10];
+		for _ in 0..ROLLS {
+			let roll = rng.range_i(-5, 5);
+			assert!((-5..5).contains(&roll), "{}", roll);
+			counts[(roll + 5) as usize] += 1;
+		}
+		assert!(is_even(&counts), "{:?}", counts);
+		assert_eq!(rng.range_i(3, 3), 3);
+		assert_eq!(rng.range_i(3, -2), 3);
+	}
+
+	#[test]
+	fn float_ranges_are_spread_evenly_and_stay_in_bounds() {
+		let mut rng = Rng::new(8);
+		let mut counts = [0; 16];
+		for _ in 0..ROLLS {
+			let roll = rng.range_f32(-2.0, 2.0);
+			assert!((-2.0..2.0).contains(&roll), "{}", roll);
+			counts[((roll + 2.0) * 4.0) as usize] += 1;
+		}
+		assert!(is_even(&counts), "{:?}", counts);
+	}
+
+	#[test]
+	fn chances_come_up_about_as_often_as_they_should() {
+		let mut rng = Rng::new(9);
+		let hits = (0..ROLLS).filter(|_| rng.chance(0.25)).count();
+		let expected = ROLLS / 4;
+		assert!(hits.abs_diff(expected) < expected / 20, "{}", hits);
+		assert!((0..100).all(|_| rng.chance(0.0) == false));
+		assert!((0..100).all(|_| rng.chance(1.0)));
+	}
+
+	#[test]
+	fn picks_come_from_the_items_and_none_from_nothing() {
+		let mut rng = Rng::new(10);
+		let items = ['a', 'b', 'c', 'd'];
+		let mut counts = [0; 4];
+		for _ in 0..ROLLS {
+			let &item = rng.pick(&items).unwrap();
+			counts[item as usize - 'a' as usize] += 1;
+		}
+		assert!(is_even(&counts), "{:?}", counts);
+		assert_eq!(rng.pick::<char>(&[]), None);
+	}
+
+	#[test]
+	fn shuffles_keep_every_item_and_favor_no_order() {
+		let mut rng = Rng::new(11);
+		// Each of the six orders of three items.
+		let orders = [
+			[0, 1, 2],
+			[0, 2, 1],
+			[1, 0, 2],
+			[1, 2, 0],
+			[2, 0, 1],
+			[2, 1, 0],
+		];
+		let mut counts = [0; 6];
+		for _ in 0..ROLLS {
+			let mut items = [0, 1, 2];
+			rng.shuffle(&mut items);
+			let order = orders.iter().position(|&order| order == items);
+			counts[order.unwrap()] += 1;
+		}
+		assert!(is_even(&counts), "{:?}", counts);
+	}
+}
@@ -0,0 +1,74 @@
+use super::Actions;
+
+// The input of every tick of a run through a level, to play the run back
+// exactly, from the level at `level`. Kept as `to_bytes` writes it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Replay {
+	pub level: String,
+	// The state hash the world should end up with, which is only good for
+	// the build that wrote it down.
+	pub hash: Option<u64>,
+	// Ticks in a row with the same actions.
+	pub runs: Vec<(u64, Actions)>,
+}
+
+impl Replay {
+	pub fn new(level: &str) -> Self {
+		Self {
+			level: level.to_string(),
+			hash: None,
+			runs: Vec::new(),
+		}
+	}
+
+	// Adds a tick to the end, as part of the last run if nothing changed.
+	pub fn push(&mut self, actions: &Actions) {
+		match self.runs.last_mut() {
+			Some((ticks, last)) if last == actions => *ticks += 1,
+			_ => self.runs.push((1, *actions)),
+		}
+	}
+
+	pub fn tick_count(&self) -> u64 {
+		self.runs.iter().map(|&(ticks, _)| ticks).sum()
+	}
+}
+
+// Hands out a replay's actions a tick at a time, from the first.
+#[derive(Clone, Debug)]
+pub struct Playback {
+	pub replay: Replay,
+	run: usize,
+	// Into the current run.
+	tick: u64,
+	played: u64,
+}
+
+impl Playback {
+	pub fn new(replay: Replay) -> Self {
+		Self {
+			replay,
+			run: 0,
+			tick: 0,
+			played: 0,
+		}
+	}
+
+	// The actions for the next tick, or none once the replay is over.
+	pub fn next_actions(&mut self) -> Option<Actions> {
+		while let Some(&(ticks, actions)) = self.replay.runs.get(self.run) {
+			if self.tick < ticks {
+				self.tick += 1;
+				self.played += 1;
+				return Some(actions);
+			}
+			self.run += 1;
+			self.tick = 0;
+		}
+		None
+	}
+
+	pub fn is_finished(&self) -> bool {
+		self.played >= self.replay.tick_count()
+	}
+}
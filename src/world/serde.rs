@@ -0,0 +1,1439 @@
+mod migration;
+
+use std::collections::{HashMap, VecDeque};
+
+use super::block::Slide;
+use super::events::WorldEventKind;
+use super::frame::{Frame, FrameLink};
+use super::hooks::{Behavior, Trigger};
+use super::status::{StatusEffect, StatusKind};
+use super::types::*;
+use super::{Actions, AnimationState, CollisionChannels, EdgeBehavior, Entity};
+use super::{EntityKind, Hooks};
+use super::{FallOff, Replay, SyncMessage, TileEdit, TrailPoint};
+use super::{LevelInfo, Persistence, PhysicsConfig, Rng, World, WorldStats};
+use super::{FRAME_TILE_COUNT, FRAME_WIDTH};
+use crate::geometry::{vec3, Scalar};
+
+// The binary save format, which unlike level files keeps everything needed
+// to carry on exactly where the world left off. Layout, all little-endian:
+//
+//   magic          4 bytes, "CUBE"
+//   version        u32, `SAVE_VERSION` when written
+//   saved_at       f64, Unix time the save was made
+//   body_length    u64
+//   metadata       `METADATA_LENGTH` bytes, from version 7 on:
+//     level_name   `LEVEL_NAME_LENGTH` bytes of UTF-8, padded with zeroes,
+//                  empty for a world with no level name
+//     play_ticks   u64, the world's tick count
+//   body           `body_length` bytes, laid out as `write_body` had it for
+//                  the version in the header
+//
+// The metadata repeats what the body holds, at a fixed place, so that saves
+// can be listed without reading whole bodies. Saves from before it had one
+// have it read from the start of the body instead.
+//
+// Scalars are always written as f64 so that saves move between builds with
+// and without the `f64` feature. Enums are written as fixed codes, or as
+// the names levels use for them, never as their position in the enum.
+//
+// Changing the body means bumping `SAVE_VERSION`, changing `read_body` to
+// read the new layout, and adding a step to `migration` that brings bodies
+// of the version before up to it, so older saves keep loading. Versions:
+//
+//   1  First version.
+//   2  Adds each entity's edge behavior after its gravity direction.
+//   3  Adds the focus entity's statistics after the hooks.
+//   4  Adds each entity's tag after its status effects, and group cleared
+//      hook triggers.
+//   5  Adds each frame's wind after its background.
+//   6  Adds the random state after the tick count.
+//   7  Adds the metadata block to the header.
+//   8  Adds the level's edit persistence after its next level, each
+//      entity's persistence after its tag, and checkpoint hook behaviors.
+//   9  Adds each frame's tile rotations after its tile data.
+//  10  Adds the spring speeds after the fall damage settings, and spring
+//      squash tile data.
+//  11  Adds the stamina settings after the spring speeds, and each
+//      entity's stamina after its persistence.
+//  12  Adds whether each frame has been visited after its tile rotations.
+//  13  Writes each frame's tiles as runs, from `encode_tiles`, after their
+//      length in bytes.
+//  14  Adds each entity's collision channels after its stamina.
+//  15  Adds each entity's parent and trail after its collision channels.
+//  16  Adds each frame's open edges after whether it has been visited, and
+//      whether each entity is falling off the world after its trail.
+pub const SAVE_VERSION: u32 = 16;
+const MAGIC: &[u8; 4] = b"CUBE";
+const HEADER_LENGTH: usize = 4 + 4 + 8 + 8;
+// Longer level names are cut short, at a character boundary.
+const LEVEL_NAME_LENGTH: usize = 48;
+const METADATA_LENGTH: usize = LEVEL_NAME_LENGTH + 8;
+// Replays are read only by builds that write the same version, since they
+// are only good for the build that recorded them anyway.
+const REPLAY_MAGIC: &[u8; 4] = b"CUBR";
+const REPLAY_VERSION: u32 = 1;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SaveError {
+	NotASave,
+	// Written by a newer build than this one.
+	UnsupportedVersion(u32),
+	// The save ends before everything it says it holds, as when a write
+	// was cut short.
+	Truncated,
+	Invalid(String),
+}
+
+impl std::fmt::Display for SaveError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		use SaveError::*;
+		match self {
+			NotASave => write!(f, "not a save"),
+			UnsupportedVersion(version) => {
+				write!(f, "unsupported save version {}", version)
+			}
+			Truncated => write!(f, "save is cut short"),
+			Invalid(message) => write!(f, "invalid save: {}", message),
+		}
+	}
+}
+
+fn invalid(message: &str) -> SaveError {
+	SaveError::Invalid(message.to_string())
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SaveHeader {
+	pub version: u32,
+	pub saved_at: f64,
+	pub body_length: u64,
+	pub level_name: Option<String>,
+	pub play_ticks: u64,
+}
+
+impl SaveHeader {
+	// Reads just the header, for telling saves apart without loading them.
+	// Fails on a save whose body is shorter than the header says, or that is
+	// newer than this build can read.
+	pub fn read(bytes: &[u8]) -> Result<Self, SaveError> {
+		let mut reader = Reader::new(bytes);
+		let magic =
+			reader.take(MAGIC.len()).map_err(|_| SaveError::NotASave)?;
+		if magic != MAGIC {
+			return Err(SaveError::NotASave);
+		}
+		let version = reader.u32()?;
+		if version == 0 || version > SAVE_VERSION {
+			return Err(SaveError::UnsupportedVersion(version));
+		}
+		let saved_at = reader.f64()?;
+		// Saves are sorted by when they were made.
+		if saved_at.is_finite() == false {
+			return Err(invalid("save time is not a real number"));
+		}
+		let body_length = reader.u64()?;
+
+		let (level_name, play_ticks) = if version >= 7 {
+			let name = reader.take(LEVEL_NAME_LENGTH)?;
+			let end = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+			let name = std::str::from_utf8(&name[..end])
+				.map_err(|_| invalid("level name is not UTF-8"))?;
+			let name = match name {
+				"" => None,
+				name => Some(name.to_string()),
+			};
+			(name, reader.u64()?)
+		} else {
+			// Only the fields ahead of the tick count are read.
+			let body = &bytes[reader.offset..];
+			let mut body = Reader::new(body);
+			let name = body.option(Reader::string)?;
+			body.option(Reader::string)?;
+			body.option(Reader::u64)?;
+			body.option(Reader::string)?;
+			body.take(6 * 8 + 2)?;
+			(name, body.u64()?)
+		};
+
+		if (reader.remaining() as u64) < body_length {
+			return Err(SaveError::Truncated);
+		}
+		Ok(Self {
+			version,
+			saved_at,
+			body_length,
+			level_name,
+			play_ticks,
+		})
+	}
+
+	// Where the body starts, which depends on the version.
+	fn body_start(&self) -> usize {
+		match self.version {
+			version if version >= 7 => HEADER_LENGTH + METADATA_LENGTH,
+			_ => HEADER_LENGTH,
+		}
+	}
+}
+
+// Writes values for `Reader` to read back. Anything else the game keeps in
+// binary, such as ghosts, is written with these too.
+pub(crate) struct Writer {
+	pub(crate) bytes: Vec<u8>,
+}
+
+impl Writer {
+	pub(crate) fn new() -> Self {
+		Self { bytes: Vec::new() }
+	}
+
+	fn u8(&mut self, value: u8) {
+		self.bytes.push(value);
+	}
+
+	pub(crate) fn u32(&mut self, value: u32) {
+		self.bytes.extend_from_slice(&value.to_le_bytes());
+	}
+
+	pub(crate) fn u64(&mut self, value: u64) {
+		self.bytes.extend_from_slice(&value.to_le_bytes());
+	}
+
+	fn f64(&mut self, value: f64) {
+		self.bytes.extend_from_slice(&value.to_le_bytes());
+	}
+
+	pub(crate) fn scalar(&mut self, value: Scalar) {
+		self.f64(value as f64);
+	}
+
+	fn bool(&mut self, value: bool) {
+		self.u8(value as u8);
+	}
+
+	fn str(&mut self, value: &str) {
+		self.u32(value.len() as u32);
+		self.bytes.extend_from_slice(value.as_bytes());
+	}
+
+	// A presence byte, then the value if there is one.
+	fn option<T>(&mut self, value: Option<T>, write: impl Fn(&mut Self, T)) {
+		self.bool(value.is_some());
+		if let Some(value) = value {
+			write(self, value);
+		}
+	}
+
+	pub(crate) fn id(&mut self, value: usize) {
+		self.u64(value as u64);
+	}
+
+	pub(crate) fn direction(&mut self, direction: Direction) {
+		use Direction::*;
+		self.u8(match direction {
+			Up => 0,
+			Down => 1,
+			Left => 2,
+			Right => 3,
+			Neutral => 4,
+		});
+	}
+
+	fn tile(&mut self, tile: Tile) {
+		self.u8(tile.symbol() as u8);
+	}
+
+	fn tile_data(&mut self, data: &TileData) {
+		match data {
+			TileData::Text(text) => {
+				self.u8(0);
+				self.str(text);
+			}
+			TileData::Squash(ticks) => {
+				self.u8(1);
+				self.u32(*ticks);
+			}
+		}
+	}
+
+	fn position(&mut self, position: WorldPosition) {
+		self.id(position.frame_id.0);
+		self.scalar(position.x);
+		self.scalar(position.y);
+	}
+
+	// How many edits each frame has had, for `SyncMessage`.
+	fn sequences(&mut self, sequences: &[(FrameId, u64)]) {
+		self.u32(sequences.len() as u32);
+		for &(frame_id, sequence) in sequences {
+			self.id(frame_id.0);
+			self.u64(sequence);
+		}
+	}
+}
+
+// Reads values back in the order they were written. Every read checks there
+// are enough bytes left first, and counts are checked against what the rest
+// of the buffer could hold before anything is allocated for them.
+pub(crate) struct Reader<'a> {
+	bytes: &'a [u8],
+	offset: usize,
+}
+
+impl<'a> Reader<'a> {
+	pub(crate) fn new(bytes: &'a [u8]) -> Self {
+		Self { bytes, offset: 0 }
+	}
+
+	pub(crate) fn remaining(&self) -> usize {
+		self.bytes.len() - self.offset
+	}
+
+	pub(crate) fn take(
+		&mut self,
+		length: usize,
+	) -> Result<&'a [u8], SaveError> {
+		if length > self.remaining() {
+			return Err(SaveError::Truncated);
+		}
+		let taken = &self.bytes[self.offset..self.offset + length];
+		self.offset += length;
+		Ok(taken)
+	}
+
+	fn array<const N: usize>(&mut self) -> Result<[u8; N], SaveError> {
+		let mut array = [0; N];
+		array.copy_from_slice(self.take(N)?);
+		Ok(array)
+	}
+
+	fn u8(&mut self) -> Result<u8, SaveError> {
+		Ok(self.take(1)?[0])
+	}
+
+	pub(crate) fn u32(&mut self) -> Result<u32, SaveError> {
+		Ok(u32::from_le_bytes(self.array()?))
+	}
+
+	pub(crate) fn u64(&mut self) -> Result<u64, SaveError> {
+		Ok(u64::from_le_bytes(self.array()?))
+	}
+
+	fn f64(&mut self) -> Result<f64, SaveError> {
+		Ok(f64::from_le_bytes(self.array()?))
+	}
+
+	fn scalar(&mut self) -> Result<Scalar, SaveError> {
+		Ok(self.f64()? as Scalar)
+	}
+
+	// Positions and velocities have to be real numbers for movement to
+	// work at all.
+	pub(crate) fn finite_scalar(&mut self) -> Result<Scalar, SaveError> {
+		let value = self.scalar()?;
+		if value.is_finite() == false {
+			return Err(invalid("non-finite number"));
+		}
+		Ok(value)
+	}
+
+	fn bool(&mut self) -> Result<bool, SaveError> {
+		match self.u8()? {
+			0 => Ok(false),
+			1 => Ok(true),
+			_ => Err(invalid("bad flag")),
+		}
+	}
+
+	fn string(&mut self) -> Result<String, SaveError> {
+		let length = self.u32()? as usize;
+		let bytes = self.take(length)?;
+		String::from_utf8(bytes.to_vec())
+			.map_err(|_| invalid("text is not UTF-8"))
+	}
+
+	fn option<T>(
+		&mut self,
+		read: impl Fn(&mut Self) -> Result<T, SaveError>,
+	) -> Result<Option<T>, SaveError> {
+		match self.bool()? {
+			true => Ok(Some(read(self)?)),
+			false => Ok(None),
+		}
+	}
+
+	// A count of items each at least `min_size` bytes long.
+	pub(crate) fn count(
+		&mut self,
+		min_size: usize,
+	) -> Result<usize, SaveError> {
+		let count = self.u32()? as usize;
+		if count.saturating_mul(min_size) > self.remaining() {
+			return Err(SaveError::Truncated);
+		}
+		Ok(count)
+	}
+
+	pub(crate) fn id(&mut self) -> Result<usize, SaveError> {
+		let id = self.u64()?;
+		if id > usize::MAX as u64 {
+			return Err(invalid("id out of range"));
+		}
+		Ok(id as usize)
+	}
+
+	pub(crate) fn direction(&mut self) -> Result<Direction, SaveError> {
+		use Direction::*;
+		match self.u8()? {
+			0 => Ok(Up),
+			1 => Ok(Down),
+			2 => Ok(Left),
+			3 => Ok(Right),
+			4 => Ok(Neutral),
+			_ => Err(invalid("bad direction")),
+		}
+	}
+
+	fn tile(&mut self) -> Result<Tile, SaveError> {
+		Tile::from_symbol(self.u8()? as char).ok_or_else(|| invalid("bad tile"))
+	}
+
+	fn tile_data(&mut self) -> Result<TileData, SaveError> {
+		match self.u8()? {
+			0 => Ok(TileData::Text(self.string()?)),
+			1 => Ok(TileData::Squash(self.u32()?)),
+			_ => Err(invalid("bad tile data")),
+		}
+	}
+
+	fn position(&mut self) -> Result<WorldPosition, SaveError> {
+		Ok(WorldPosition {
+			frame_id: FrameId(self.id()?),
+			x: self.finite_scalar()?,
+			y: self.finite_scalar()?,
+		})
+	}
+
+	fn tile_index(&mut self) -> Result<(usize, usize), SaveError> {
+		let (x, y) = (self.u8()? as usize, self.u8()? as usize);
+		if x >= FRAME_WIDTH || y >= FRAME_WIDTH {
+			return Err(invalid("tile index out of range"));
+		}
+		Ok((x, y))
+	}
+
+	fn sequences(&mut self) -> Result<Vec<(FrameId, u64)>, SaveError> {
+		let mut sequences = Vec::new();
+		for _ in 0..self.count(8 + 8)? {
+			sequences.push((FrameId(self.id()?), self.u64()?));
+		}
+		Ok(sequences)
+	}
+}
+
+// The longest run `encode_tiles` writes, so that its length fits a byte.
+const MAX_TILE_RUN: usize = u8::MAX as usize;
+
+// Tiles as runs of the same kind, each a tile's symbol byte then how many
+// are in the run, from 1 to `MAX_TILE_RUN`. Rotations and tile data are
+// kept apart from the tiles, so a run never has to be broken up for them.
+pub fn encode_tiles(tiles: &[Tile]) -> Vec<u8> {
+	let mut out = Vec::new();
+	let mut rest = tiles;
+	while let Some(&tile) = rest.first() {
+		let run = rest
+			.iter()
+			.take(MAX_TILE_RUN)
+			.take_while(|&&other| other == tile)
+			.count();
+		out.push(tile.symbol() as u8);
+		out.push(run as u8);
+		rest = &rest[run..];
+	}
+	out
+}
+
+// Reads back exactly `count` tiles written by `encode_tiles`, failing on
+// empty runs, runs that go past `count`, or bytes left over.
+pub fn decode_tiles(
+	bytes: &[u8],
+	count: usize,
+) -> Result<Vec<Tile>, SaveError> {
+	let mut tiles = Vec::with_capacity(count);
+	for pair in bytes.chunks(2) {
+		let (symbol, run) = match *pair {
+			[symbol, run] => (symbol, run as usize),
+			_ => return Err(SaveError::Truncated),
+		};
+		let tile = Tile::from_symbol(symbol as char)
+			.ok_or_else(|| invalid("bad tile"))?;
+		if run == 0 {
+			return Err(invalid("empty tile run"));
+		}
+		if tiles.len() + run > count {
+			return Err(invalid("tile runs overrun the frame"));
+		}
+		tiles.resize(tiles.len() + run, tile);
+	}
+	if tiles.len() < count {
+		return Err(SaveError::Truncated);
+	}
+	Ok(tiles)
+}
+
+fn kind_code(kind: EntityKind) -> u8 {
+	use EntityKind::*;
+	match kind {
+		Player => 0,
+		Coin => 1,
+		GravityOrb => 2,
+		Walker => 3,
+		SpeedBoost => 4,
+		Block => 5,
+		Energizer => 6,
+		Worm => 7,
+		WormSegment => 8,
+	}
+}
+
+fn kind_from_code(code: u8) -> Result<EntityKind, SaveError> {
+	use EntityKind::*;
+	match code {
+		0 => Ok(Player),
+		1 => Ok(Coin),
+		2 => Ok(GravityOrb),
+		3 => Ok(Walker),
+		4 => Ok(SpeedBoost),
+		5 => Ok(Block),
+		6 => Ok(Energizer),
+		7 => Ok(Worm),
+		8 => Ok(WormSegment),
+		_ => Err(invalid("bad entity kind")),
+	}
+}
+
+fn persistence_code(persistence: Persistence) -> u8 {
+	use Persistence::*;
+	match persistence {
+		ResetOnDeath => 0,
+		PersistForLevel => 1,
+		PersistForever => 2,
+	}
+}
+
+fn persistence_from_code(code: u8) -> Result<Persistence, SaveError> {
+	use Persistence::*;
+	match code {
+		0 => Ok(ResetOnDeath),
+		1 => Ok(PersistForLevel),
+		2 => Ok(PersistForever),
+		_ => Err(invalid("bad persistence")),
+	}
+}
+
+fn edge_behavior_code(behavior: EdgeBehavior) -> u8 {
+	use EdgeBehavior::*;
+	match behavior {
+		TurnAtLedge => 0,
+		WalkOff => 1,
+		FollowSurface => 2,
+	}
+}
+
+fn edge_behavior_from_code(code: u8) -> Result<EdgeBehavior, SaveError> {
+	use EdgeBehavior::*;
+	match code {
+		0 => Ok(TurnAtLedge),
+		1 => Ok(WalkOff),
+		2 => Ok(FollowSurface),
+		_ => Err(invalid("bad edge behavior")),
+	}
+}
+
+impl World {
+	// Everything about the world except cheats, which are never saved, and
+	// the tick rate, which belongs to the machine rather than the world.
+	pub fn to_save_bytes(&self, saved_at: f64) -> Vec<u8> {
+		let body = self.write_body();
+		let mut out = Writer::new();
+		out.bytes.extend_from_slice(MAGIC);
+		out.u32(SAVE_VERSION);
+		out.f64(saved_at);
+		out.u64(body.len() as u64);
+
+		let name = self.level.name.as_deref().unwrap_or("");
+		let mut end = name.len().min(LEVEL_NAME_LENGTH);
+		while name.is_char_boundary(end) == false {
+			end -= 1;
+		}
+		let name = &name[..end];
+		out.bytes.extend_from_slice(name.as_bytes());
+		let padding = LEVEL_NAME_LENGTH - name.len();
+		out.bytes.extend(std::iter::repeat_n(0, padding));
+		out.u64(self.tick_count);
+
+		out.bytes.extend_from_slice(&body);
+		out.bytes
+	}
+
+	pub fn from_save_bytes(bytes: &[u8]) -> Result<Self, SaveError> {
+		let header = SaveHeader::read(bytes)?;
+		let start = header.body_start();
+		let body = &bytes[start..start + header.body_length as usize];
+		Self::read_body(&migration::migrate(body, header.version)?)
+	}
+
+	fn write_body(&self) -> Vec<u8> {
+		let mut out = Writer::new();
+
+		let level = &self.level;
+		out.option(level.name.as_ref(), |out, name| out.str(name));
+		out.option(level.author.as_ref(), |out, author| out.str(author));
+		out.option(level.par_ticks, Writer::u64);
+		out.option(level.next_level.as_ref(), |out, next| out.str(next));
+		out.u8(persistence_code(level.edit_persistence));
+
+		let physics = &self.physics;
+		out.scalar(physics.walk_acceleration);
+		out.scalar(physics.jump_speed);
+		out.scalar(physics.velocity_retention);
+		out.scalar(physics.conveyor_acceleration);
+		out.bool(physics.push_out_of_placed_tiles);
+		out.bool(physics.fall_damage);
+		out.scalar(physics.fall_damage_speed);
+		out.scalar(physics.fall_damage_per_speed);
+		out.scalar(physics.spring_speed);
+		out.scalar(physics.spring_jump_bonus);
+		out.scalar(physics.max_stamina);
+		out.scalar(physics.stamina_regen);
+		out.scalar(physics.sprint_multiplier);
+		out.scalar(physics.sprint_stamina_cost);
+		out.scalar(physics.dash_speed);
+		out.scalar(physics.dash_stamina_cost);
+
+		out.u64(self.tick_count);
+		out.u64(self.rng.state());
+		out.u32(self.coins_collected);
+		out.id(self.iota);
+		out.option(self.focus_entity, |out, id| out.id(id.0));
+		out.option(self.spawn, Writer::position);
+
+		let frame_ids = self.frame_ids();
+		out.u32(frame_ids.len() as u32);
+		for frame_id in frame_ids {
+			let frame = &self.frames[&frame_id];
+			out.id(frame_id.0);
+			out.direction(frame.orientation);
+			for &edge in Direction::iter() {
+				out.option(frame.borders.at_direction(edge), |out, link| {
+					out.id(link.frame.0);
+					out.direction(link.entry_edge);
+				});
+			}
+			out.str(frame.background.pattern.name());
+			out.bytes.extend_from_slice(&frame.background.tint);
+			out.option(frame.wind, |out, wind| {
+				out.scalar(wind.x);
+				out.scalar(wind.y);
+			});
+			let tiles = encode_tiles(frame.tiles());
+			out.u32(tiles.len() as u32);
+			out.bytes.extend_from_slice(&tiles);
+
+			let mut entries: Vec<_> = frame.tile_data_entries().collect();
+			entries.sort_by_key(|&((x, y), _)| (y, x));
+			out.u32(entries.len() as u32);
+			for ((x, y), data) in entries {
+				out.u8(x as u8);
+				out.u8(y as u8);
+				out.tile_data(data);
+			}
+
+			let mut rotations: Vec<_> = frame.rotation_entries().collect();
+			rotations.sort_by_key(|&((x, y), _)| (y, x));
+			out.u32(rotations.len() as u32);
+			for ((x, y), rotation) in rotations {
+				out.u8(x as u8);
+				out.u8(y as u8);
+				out.u8(rotation.quarter_turns());
+			}
+			out.bool(frame.visited);
+			out.u32(frame.open_edges.len() as u32);
+			for &edge in frame.open_edges.iter() {
+				out.direction(edge);
+			}
+		}
+
+		let mut entities: Vec<&Entity> = self.entities.values().collect();
+		entities.sort_by_key(|entity| entity.id.0);
+		out.u32(entities.len() as u32);
+		for entity in entities {
+			self.write_entity(&mut out, entity);
+		}
+
+		let mut slides: Vec<_> = self.slides.iter().collect();
+		slides.sort_by_key(|&(id, _)| id.0);
+		out.u32(slides.len() as u32);
+		for (id, slide) in slides {
+			out.id(id.0);
+			out.scalar(slide.offset_x);
+			out.scalar(slide.offset_y);
+			out.u32(slide.remaining_ticks);
+		}
+
+		let hooks: Vec<_> = self.hooks.iter().collect();
+		out.u32(hooks.len() as u32);
+		for &(trigger, behavior) in hooks {
+			match trigger {
+				Trigger::TileAt { frame, x, y } => {
+					out.u8(0);
+					out.id(frame.0);
+					out.u8(x as u8);
+					out.u8(y as u8);
+				}
+				Trigger::TileKind(tile) => {
+					out.u8(1);
+					out.tile(tile);
+				}
+				Trigger::Event(kind) => {
+					out.u8(2);
+					out.str(kind.name());
+				}
+				Trigger::GroupCleared(tag) => {
+					out.u8(3);
+					out.str(self.tag_name(tag));
+				}
+			}
+			match behavior {
+				Behavior::Teleport { destination } => {
+					out.u8(0);
+					out.position(destination);
+				}
+				Behavior::SetTile {
+					frame,
+					x,
+					y,
+					tile,
+					show,
+				} => {
+					out.u8(if show { 3 } else { 1 });
+					out.id(frame.0);
+					out.u8(x as u8);
+					out.u8(y as u8);
+					out.tile(tile);
+				}
+				Behavior::Checkpoint => out.u8(2),
+			}
+		}
+
+		let stats = &self.stats;
+		out.scalar(stats.distance);
+		out.u32(stats.tiles_placed);
+		out.u32(stats.tiles_removed);
+		out.u32(stats.jumps);
+		out.u32(stats.frame_crossings);
+		out.scalar(stats.damage_taken);
+
+		out.bytes
+	}
+
+	fn read_body(body: &[u8]) -> Result<Self, SaveError> {
+		let mut input = Reader::new(body);
+		let mut world = World::empty();
+
+		world.level = LevelInfo {
+			name: input.option(Reader::string)?,
+			author: input.option(Reader::string)?,
+			par_ticks: input.option(Reader::u64)?,
+			next_level: input.option(Reader::string)?,
+			edit_persistence: persistence_from_code(input.u8()?)?,
+		};
+
+		world.physics = PhysicsConfig {
+			walk_acceleration: input.scalar()?,
+			jump_speed: input.scalar()?,
+			velocity_retention: input.scalar()?,
+			conveyor_acceleration: input.scalar()?,
+			push_out_of_placed_tiles: input.bool()?,
+			fall_damage: input.bool()?,
+			fall_damage_speed: input.scalar()?,
+			fall_damage_per_speed: input.scalar()?,
+			spring_speed: input.scalar()?,
+			spring_jump_bonus: input.scalar()?,
+			max_stamina: input.scalar()?,
+			stamina_regen: input.scalar()?,
+			sprint_multiplier: input.scalar()?,
+			sprint_stamina_cost: input.scalar()?,
+			dash_speed: input.scalar()?,
+			dash_stamina_cost: input.scalar()?,
+		};
+
+		world.tick_count = input.u64()?;
+		world.rng = Rng::new(input.u64()?);
+		world.coins_collected = input.u32()?;
+		let iota = input.id()?;
+		let focus_entity = input.option(|input| Ok(EntityId(input.id()?)))?;
+		world.spawn = input.option(Reader::position)?;
+
+		// An id, an orientation, five absent links, a pattern name, a tint,
+		// no wind, a length and one run of tiles, and the counts of tile
+		// data, rotations and open edges either side of whether it has been
+		// visited.
+		let min_frame_size = 8 + 1 + 5 + 4 + 3 + 1 + 4 + 2 + 4 + 4 + 1 + 4;
+		for _ in 0..input.count(min_frame_size)? {
+			let frame_id = FrameId(input.id()?);
+			if world.frames.contains_key(&frame_id) {
+				return Err(invalid("duplicate frame"));
+			}
+			let mut frame = Frame::new(frame_id);
+			frame.orientation = input.direction()?;
+			for &edge in Direction::iter() {
+				*frame.borders.at_direction_mut(edge) =
+					input.option(|input| {
+						Ok(FrameLink {
+							frame: FrameId(input.id()?),
+							entry_edge: input.direction()?,
+						})
+					})?;
+			}
+			let pattern = input.string()?;
+			frame.background.pattern =
+				BackgroundPattern::from_name(&pattern)
+					.ok_or_else(|| invalid("bad background pattern"))?;
+			frame.background.tint = input.array()?;
+			frame.wind = input.option(|input| {
+				let x = input.finite_scalar()?;
+				Ok(vec3(x, input.finite_scalar()?, 0.0))
+			})?;
+			let length = input.u32()? as usize;
+			let tiles = decode_tiles(input.take(length)?, FRAME_TILE_COUNT)?;
+			for (i, tile) in tiles.into_iter().enumerate() {
+				let (x, y) = (i % FRAME_WIDTH, i / FRAME_WIDTH);
+				*frame.tile_mut(x as isize, y as isize) = tile;
+			}
+
+			for _ in 0..input.count(2 + 1 + 4)? {
+				let (x, y) = input.tile_index()?;
+				let data = input.tile_data()?;
+				frame.set_tile_data(x as isize, y as isize, Some(data));
+			}
+			for _ in 0..input.count(2 + 1)? {
+				let (x, y) = input.tile_index()?;
+				let rotation = match input.u8()? {
+					turns if turns < 4 => Angle::from_quarter_turns(turns),
+					_ => return Err(invalid("bad tile rotation")),
+				};
+				frame.set_rotation(x as isize, y as isize, rotation);
+			}
+			frame.visited = input.bool()?;
+			for _ in 0..input.count(1)? {
+				frame.open_edges.push(input.direction()?);
+			}
+			world.insert_frame(frame);
+		}
+
+		// Everything up to the effect count, no tag, a persistence, stamina,
+		// collision channels, no parent, a trail count and not falling off.
+		let min_entity_size =
+			8 + 1 + 24 + 24 + 5 + 1 + 1 + 1 + 8 + 4 + 1 + 1 + 8 + 3 + 1 + 4 + 1;
+		for _ in 0..input.count(min_entity_size)? {
+			let entity = world.read_entity(&mut input)?;
+			if world.entities.contains_key(&entity.id) {
+				return Err(invalid("duplicate entity"));
+			}
+			world.insert_entity(entity);
+		}
+
+		for _ in 0..input.count(8 + 8 + 8 + 4)? {
+			let id = EntityId(input.id()?);
+			let slide = Slide {
+				offset_x: input.finite_scalar()?,
+				offset_y: input.finite_scalar()?,
+				remaining_ticks: input.u32()?,
+			};
+			if world.entities.contains_key(&id) == false {
+				return Err(invalid("slide for a missing entity"));
+			}
+			world.slides.insert(id, slide);
+		}
+
+		let mut hooks = Hooks::new();
+		for _ in 0..input.count(2)? {
+			let trigger = match input.u8()? {
+				0 => {
+					let frame = FrameId(input.id()?);
+					let (x, y) = input.tile_index()?;
+					Trigger::TileAt { frame, x, y }
+				}
+				1 => Trigger::TileKind(input.tile()?),
+				2 => {
+					let name = input.string()?;
+					let kind = WorldEventKind::from_name(&name)
+						.ok_or_else(|| invalid("bad event kind"))?;
+					Trigger::Event(kind)
+				}
+				3 => Trigger::GroupCleared(world.tag_id(&input.string()?)),
+				_ => return Err(invalid("bad hook trigger")),
+			};
+			let behavior = match input.u8()? {
+				0 => Behavior::Teleport {
+					destination: input.position()?,
+				},
+				code @ 1 | code @ 3 => {
+					let frame = FrameId(input.id()?);
+					let (x, y) = input.tile_index()?;
+					let tile = input.tile()?;
+					let show = code == 3;
+					Behavior::SetTile {
+						frame,
+						x,
+						y,
+						tile,
+						show,
+					}
+				}
+				2 => Behavior::Checkpoint,
+				_ => return Err(invalid("bad hook behavior")),
+			};
+			hooks.add(trigger, behavior);
+		}
+		world.hooks = hooks;
+
+		world.stats = WorldStats {
+			distance: input.finite_scalar()?,
+			tiles_placed: input.u32()?,
+			tiles_removed: input.u32()?,
+			jumps: input.u32()?,
+			frame_crossings: input.u32()?,
+			damage_taken: input.finite_scalar()?,
+			..WorldStats::default()
+		};
+
+		if input.remaining() != 0 {
+			return Err(invalid("unexpected data after the end"));
+		}
+		match focus_entity {
+			Some(id) if world.entities.contains_key(&id) == false => {
+				return Err(invalid("missing focus entity"));
+			}
+			None => return Err(invalid("no focus entity")),
+			_ => {}
+		}
+		world.focus_entity = focus_entity;
+		world.iota = iota;
+		world.activate_all_fluids();
+		// Checkpoints aren't saved, so a loaded world carries on from where
+		// it was saved after dying as well.
+		world.take_checkpoint(focus_entity.unwrap());
+		Ok(world)
+	}
+
+	// An entity, as the body of a save holds each one.
+	fn write_entity(&self, out: &mut Writer, entity: &Entity) {
+		out.id(entity.id.0);
+		out.u8(kind_code(entity.kind));
+		out.position(entity.position);
+		out.scalar(entity.velocity.x);
+		out.scalar(entity.velocity.y);
+		out.scalar(entity.velocity.z);
+		out.direction(entity.last_movement_direction);
+		out.direction(entity.last_movement_direction_x);
+		out.direction(entity.last_movement_direction_y);
+		out.direction(entity.orientation);
+		out.direction(entity.aim);
+		out.bool(entity.grounded);
+		out.direction(entity.gravity_dir);
+		out.u8(edge_behavior_code(entity.edge_behavior));
+		out.scalar(entity.health);
+		out.u32(entity.effects.len() as u32);
+		for effect in entity.effects.iter() {
+			out.str(effect.kind.name());
+			out.u32(effect.remaining_ticks);
+			out.scalar(effect.magnitude);
+		}
+		let tag = entity.tag.map(|tag| self.tag_name(tag));
+		out.option(tag, |out, name| out.str(name));
+		out.u8(persistence_code(entity.persistence));
+		out.scalar(entity.stamina);
+		out.u8(entity.channels.category);
+		out.u8(entity.channels.collides_with);
+		out.bool(entity.channels.sensor);
+		out.option(entity.parent, |out, parent| out.id(parent.0));
+		out.u32(entity.trail.len() as u32);
+		for point in entity.trail.iter() {
+			out.position(point.position);
+			out.direction(point.gravity_dir);
+			out.bool(point.grounded);
+			out.scalar(point.distance);
+		}
+		out.option(entity.falling_off, |out, fall| {
+			out.direction(fall.edge);
+			out.u32(fall.ticks);
+		});
+	}
+
+	// Reads back an entity written by `write_entity`, failing if it is on a
+	// frame the world doesn't have.
+	fn read_entity(&mut self, input: &mut Reader) -> Result<Entity, SaveError> {
+		let id = EntityId(input.id()?);
+		let kind = kind_from_code(input.u8()?)?;
+		let position = input.position()?;
+		if self.frames.contains_key(&position.frame_id) == false {
+			return Err(invalid("entity on a missing frame"));
+		}
+		let velocity = vec3(
+			input.finite_scalar()?,
+			input.finite_scalar()?,
+			input.finite_scalar()?,
+		);
+		let last_movement_direction = input.direction()?;
+		let last_movement_direction_x = input.direction()?;
+		let last_movement_direction_y = input.direction()?;
+		let orientation = input.direction()?;
+		let aim = input.direction()?;
+		let grounded = input.bool()?;
+		let gravity_dir = input.direction()?;
+		let edge_behavior = edge_behavior_from_code(input.u8()?)?;
+		let health = input.scalar()?;
+
+		let mut effects = Vec::new();
+		for _ in 0..input.count(4 + 4 + 8)? {
+			let name = input.string()?;
+			let kind = StatusKind::from_name(&name)
+				.ok_or_else(|| invalid("bad status effect"))?;
+			let ticks = input.u32()?;
+			let magnitude = input.scalar()?;
+			effects.push(StatusEffect::new(kind, ticks, magnitude));
+		}
+		let tag = input.option(Reader::string)?;
+		let tag = tag.map(|name| self.tag_id(&name));
+		let persistence = persistence_from_code(input.u8()?)?;
+		let stamina = input.scalar()?;
+		let channels = CollisionChannels {
+			category: input.u8()?,
+			collides_with: input.u8()?,
+			sensor: input.bool()?,
+		};
+		let parent = input.option(Reader::id)?.map(EntityId);
+		let mut trail = VecDeque::new();
+		for _ in 0..input.count(3 * 8 + 1 + 1 + 8)? {
+			trail.push_back(TrailPoint {
+				position: input.position()?,
+				gravity_dir: input.direction()?,
+				grounded: input.bool()?,
+				distance: input.finite_scalar()?,
+			});
+		}
+		let falling_off = input.option(|input| {
+			Ok(FallOff {
+				edge: input.direction()?,
+				ticks: input.u32()?,
+			})
+		})?;
+
+		Ok(Entity {
+			position,
+			velocity,
+			last_movement_direction,
+			last_movement_direction_x,
+			last_movement_direction_y,
+			kind,
+			orientation,
+			aim,
+			edge_behavior,
+			id,
+			grounded,
+			gravity_dir,
+			health,
+			stamina,
+			effects,
+			render_tint: None,
+			render_layer: None,
+			animation: AnimationState::new(),
+			cheats: Default::default(),
+			tag,
+			persistence,
+			channels,
+			parent,
+			trail,
+			falling_off,
+		})
+	}
+
+	// Everything that moves from one tick to the next when nobody is
+	// editing, which is every entity and little else, for another copy of
+	// the world to be kept up with without sending its frames again. Only
+	// read by the same build.
+	pub fn to_entity_bytes(&self) -> Vec<u8> {
+		let mut out = Writer::new();
+		out.u32(SAVE_VERSION);
+		out.u64(self.tick_count);
+		out.u64(self.rng.state());
+		out.u32(self.coins_collected);
+		out.id(self.iota);
+		out.option(self.focus_entity, |out, id| out.id(id.0));
+
+		let mut entities: Vec<&Entity> = self.entities.values().collect();
+		entities.sort_by_key(|entity| entity.id.0);
+		out.u32(entities.len() as u32);
+		for entity in entities {
+			self.write_entity(&mut out, entity);
+		}
+		let mut slides: Vec<_> = self.slides.iter().collect();
+		slides.sort_by_key(|&(id, _)| id.0);
+		out.u32(slides.len() as u32);
+		for (id, slide) in slides {
+			out.id(id.0);
+			out.scalar(slide.offset_x);
+			out.scalar(slide.offset_y);
+			out.u32(slide.remaining_ticks);
+		}
+		out.bytes
+	}
+
+	// Replaces every entity with those written by `to_entity_bytes`. How
+	// entities were being drawn is kept for those that are still there.
+	// The entities are left as they were if the bytes can't be read.
+	pub fn apply_entity_bytes(
+		&mut self,
+		bytes: &[u8],
+	) -> Result<(), SaveError> {
+		let mut input = Reader::new(bytes);
+		let version = input.u32()?;
+		if version != SAVE_VERSION {
+			return Err(SaveError::UnsupportedVersion(version));
+		}
+		let tick_count = input.u64()?;
+		let rng = Rng::new(input.u64()?);
+		let coins_collected = input.u32()?;
+		let iota = input.id()?;
+		let focus_entity = input.option(|input| Ok(EntityId(input.id()?)))?;
+
+		let mut entities = HashMap::new();
+		let min_entity_size = 8 + 1 + 24 + 24 + 5 + 1 + 1 + 8 + 4;
+		for _ in 0..input.count(min_entity_size)? {
+			let entity = self.read_entity(&mut input)?;
+			if entities.contains_key(&entity.id) {
+				return Err(invalid("duplicate entity"));
+			}
+			entities.insert(entity.id, entity);
+		}
+		let mut slides = HashMap::new();
+		for _ in 0..input.count(8 + 8 + 8 + 4)? {
+			let id = EntityId(input.id()?);
+			let slide = Slide {
+				offset_x: input.finite_scalar()?,
+				offset_y: input.finite_scalar()?,
+				remaining_ticks: input.u32()?,
+			};
+			if entities.contains_key(&id) == false {
+				return Err(invalid("slide for a missing entity"));
+			}
+			slides.insert(id, slide);
+		}
+
+		for id in self.entity_ids() {
+			if self.entities[&id].kind == EntityKind::Block {
+				self.release_tile(id);
+			}
+			let old = &self.entities[&id];
+			if let Some(entity) = entities.get_mut(&id) {
+				entity.render_tint = old.render_tint;
+				entity.render_layer = old.render_layer;
+				entity.animation = old.animation;
+			}
+		}
+		self.entities.clear();
+		self.frame_entities.clear();
+		let mut ids: Vec<EntityId> = entities.keys().copied().collect();
+		ids.sort_by_key(|id| id.0);
+		for id in ids {
+			self.insert_entity(entities.remove(&id).unwrap());
+		}
+		self.slides = slides;
+		self.tick_count = tick_count;
+		self.rng = rng;
+		self.coins_collected = coins_collected;
+		self.iota = iota;
+		self.focus_entity = focus_entity;
+		Ok(())
+	}
+}
+
+// A kind byte, then whatever that kind of message holds.
+impl SyncMessage {
+	pub fn to_bytes(&self) -> Vec<u8> {
+		let mut out = Writer::new();
+		match self {
+			SyncMessage::Join => out.u8(0),
+			SyncMessage::Resync(frame_id) => {
+				out.u8(1);
+				out.id(frame_id.0);
+			}
+			SyncMessage::Snapshot { save, sequences } => {
+				out.u8(2);
+				out.sequences(sequences);
+				out.bytes.extend_from_slice(save);
+			}
+			SyncMessage::Edit(edit) => {
+				out.u8(3);
+				out.id(edit.frame_id.0);
+				out.u8(edit.x as u8);
+				out.u8(edit.y as u8);
+				out.tile(edit.tile);
+				out.u8(edit.rotation.quarter_turns());
+				out.option(edit.data.as_ref(), Writer::tile_data);
+				out.u64(edit.sequence);
+			}
+			SyncMessage::Frame {
+				frame_id,
+				sequence,
+				tiles,
+				rotations,
+				data,
+			} => {
+				out.u8(4);
+				out.id(frame_id.0);
+				out.u64(*sequence);
+				out.u32(rotations.len() as u32);
+				for &((x, y), rotation) in rotations {
+					out.u8(x as u8);
+					out.u8(y as u8);
+					out.u8(rotation.quarter_turns());
+				}
+				out.u32(data.len() as u32);
+				for ((x, y), data) in data {
+					out.u8(*x as u8);
+					out.u8(*y as u8);
+					out.tile_data(data);
+				}
+				out.bytes.extend_from_slice(tiles);
+			}
+			SyncMessage::Tick {
+				sequences,
+				entities,
+			} => {
+				out.u8(5);
+				out.sequences(sequences);
+				out.bytes.extend_from_slice(entities);
+			}
+		}
+		out.bytes
+	}
+
+	pub fn from_bytes(bytes: &[u8]) -> Result<Self, SaveError> {
+		let mut input = Reader::new(bytes);
+		let rotation = |input: &mut Reader| match input.u8()? {
+			turns if turns < 4 => Ok(Angle::from_quarter_turns(turns)),
+			_ => Err(invalid("bad tile rotation")),
+		};
+		let message = match input.u8()? {
+			0 => SyncMessage::Join,
+			1 => SyncMessage::Resync(FrameId(input.id()?)),
+			2 => {
+				let sequences = input.sequences()?;
+				let rest = input.remaining();
+				SyncMessage::Snapshot {
+					save: input.take(rest)?.to_vec(),
+					sequences,
+				}
+			}
+			3 => {
+				let frame_id = FrameId(input.id()?);
+				let (x, y) = input.tile_index()?;
+				SyncMessage::Edit(TileEdit {
+					frame_id,
+					x,
+					y,
+					tile: input.tile()?,
+					rotation: rotation(&mut input)?,
+					data: input.option(Reader::tile_data)?,
+					sequence: input.u64()?,
+				})
+			}
+			4 => {
+				let frame_id = FrameId(input.id()?);
+				let sequence = input.u64()?;
+				let mut rotations = Vec::new();
+				for _ in 0..input.count(2 + 1)? {
+					let index = input.tile_index()?;
+					rotations.push((index, rotation(&mut input)?));
+				}
+				let mut data = Vec::new();
+				for _ in 0..input.count(2 + 1 + 4)? {
+					let index = input.tile_index()?;
+					data.push((index, input.tile_data()?));
+				}
+				let rest = input.remaining();
+				SyncMessage::Frame {
+					frame_id,
+					sequence,
+					tiles: input.take(rest)?.to_vec(),
+					rotations,
+					data,
+				}
+			}
+			5 => {
+				let sequences = input.sequences()?;
+				let rest = input.remaining();
+				SyncMessage::Tick {
+					sequences,
+					entities: input.take(rest)?.to_vec(),
+				}
+			}
+			_ => return Err(invalid("bad sync message")),
+		};
+		if input.remaining() > 0 {
+			return Err(invalid("bytes left over after sync message"));
+		}
+		Ok(message)
+	}
+}
+
+impl Replay {
+	// `REPLAY_MAGIC` and `REPLAY_VERSION`, the level's path, the state hash
+	// if there is one, then each run as its number of ticks and its
+	// actions.
+	pub fn to_bytes(&self) -> Vec<u8> {
+		let mut out = Writer::new();
+		out.bytes.extend_from_slice(REPLAY_MAGIC);
+		out.u32(REPLAY_VERSION);
+		out.str(&self.level);
+		out.option(self.hash, Writer::u64);
+		out.u32(self.runs.len() as u32);
+		for &(ticks, actions) in &self.runs {
+			out.u64(ticks);
+			out.scalar(actions.move_x);
+			out.scalar(actions.move_y);
+			out.bool(actions.jump);
+			out.bool(actions.jump_held);
+			out.bool(actions.interact);
+			out.bool(actions.remove);
+			out.bool(actions.rotate);
+			out.bool(actions.sprint);
+			out.bool(actions.dash);
+			out.direction(actions.aim);
+		}
+		out.bytes
+	}
+
+	pub fn from_bytes(bytes: &[u8]) -> Result<Self, SaveError> {
+		let mut input = Reader::new(bytes);
+		match input.take(REPLAY_MAGIC.len()) {
+			Ok(magic) if magic == REPLAY_MAGIC => {}
+			_ => return Err(SaveError::NotASave),
+		}
+		let version = input.u32()?;
+		if version != REPLAY_VERSION {
+			return Err(SaveError::UnsupportedVersion(version));
+		}
+		let level = input.string()?;
+		let hash = input.option(Reader::u64)?;
+		let mut runs = Vec::new();
+		for _ in 0..input.count(8 + 8 + 8 + 7 + 1)? {
+			let ticks = input.u64()?;
+			let actions = Actions {
+				move_x: input.finite_scalar()?,
+				move_y: input.finite_scalar()?,
+				jump: input.bool()?,
+				jump_held: input.bool()?,
+				interact: input.bool()?,
+				remove: input.bool()?,
+				rotate: input.bool()?,
+				sprint: input.bool()?,
+				dash: input.bool()?,
+				aim: input.direction()?,
+			};
+			runs.push((ticks, actions));
+		}
+		if input.remaining() > 0 {
+			return Err(invalid("bytes left over after replay"));
+		}
+		Ok(Self { level, hash, runs })
+	}
+}
+
+// Bytes as text, two hexadecimal digits each, for keeping them where only
+// text can be kept, such as local storage on the web.
+pub fn to_hex(bytes: &[u8]) -> String {
+	bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// Reads back `to_hex`, ignoring whitespace either side.
+pub fn from_hex(text: &str) -> Option<Vec<u8>> {
+	let text = text.trim().as_bytes();
+	if text.len().is_multiple_of(2) == false {
+		return None;
+	}
+	text.chunks(2)
+		.map(|pair| {
+			let pair = std::str::from_utf8(pair).ok()?;
+			u8::from_str_radix(pair, 16).ok()
+		})
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// Every placeable tile in runs of different lengths, one of them too
+	// long for a single run.
+	fn mixed() -> Vec<Tile> {
+		let mut tiles = Vec::new();
+		for (i, &tile) in Tile::placeable().iter().enumerate() {
+			tiles.extend(std::iter::repeat_n(tile, i + 1));
+		}
+		tiles.extend(std::iter::repeat_n(Tile::Solid, MAX_TILE_RUN * 2 + 3));
+		tiles.push(Tile::Empty);
+		tiles
+	}
+
+	#[test]
+	fn tiles_come_back_as_they_were_encoded() {
+		let tiles = mixed();
+		let bytes = encode_tiles(&tiles);
+		assert_eq!(decode_tiles(&bytes, tiles.len()), Ok(tiles));
+		assert_eq!(decode_tiles(&[], 0), Ok(Vec::new()));
+	}
+
+	#[test]
+	fn a_long_run_is_split_into_full_runs() {
+		let tiles = vec![Tile::Water; MAX_TILE_RUN * 2 + 1];
+		let bytes = encode_tiles(&tiles);
+		let run = MAX_TILE_RUN as u8;
+		assert_eq!(bytes, vec![b'~', run, b'~', run, b'~', 1]);
+	}
+
+	#[test]
+	fn cut_short_tiles_are_truncated() {
+		let tiles = mixed();
+		let bytes = encode_tiles(&tiles);
+		for cut in [1, 2, 3].iter() {
+			let short = &bytes[..bytes.len() - cut];
+			let decoded = decode_tiles(short, tiles.len());
+			assert_eq!(decoded, Err(SaveError::Truncated), "cut {}", cut);
+		}
+	}
+
+	#[test]
+	fn runs_past_the_count_are_refused() {
+		let tiles = mixed();
+		let bytes = encode_tiles(&tiles);
+		let decoded = decode_tiles(&bytes, tiles.len() - 1);
+		assert_eq!(decoded, Err(invalid("tile runs overrun the frame")));
+		let mut longer = bytes.clone();
+		longer.extend_from_slice(&[b'#', 1]);
+		let decoded = decode_tiles(&longer, tiles.len());
+		assert_eq!(decoded, Err(invalid("tile runs overrun the frame")));
+	}
+
+	#[test]
+	fn empty_runs_and_unknown_tiles_are_refused() {
+		let decoded = decode_tiles(&[b'#', 0, b'#', 2], 2);
+		assert_eq!(decoded, Err(invalid("empty tile run")));
+		let decoded = decode_tiles(&[b'Z', 2], 2);
+		assert_eq!(decoded, Err(invalid("bad tile")));
+	}
+}
@@ -0,0 +1,459 @@
+use super::{edge_behavior_code, encode_tiles, invalid, kind_from_code};
+use super::{persistence_code, Reader, SaveError, Writer, SAVE_VERSION};
+use super::{CollisionChannels, EdgeBehavior, EntityKind, Persistence};
+use super::{PhysicsConfig, Rng, WorldStats, FRAME_TILE_COUNT};
+use crate::world::DEFAULT_SEED;
+
+// Bodies of older saves are brought up to date a version at a time. Each
+// step copies the body through as the older version laid it out, writing
+// in what the next version adds as the older one behaved without it, so
+// that `read_body` only ever reads the newest layout.
+
+type Migration = fn(&[u8]) -> Result<Vec<u8>, SaveError>;
+
+// From each version to the next, starting from version 1. Changing the
+// body means adding one to the end.
+const MIGRATIONS: [Migration; SAVE_VERSION as usize - 1] = [
+	migrate_v1_to_v2,
+	migrate_v2_to_v3,
+	migrate_v3_to_v4,
+	migrate_v4_to_v5,
+	migrate_v5_to_v6,
+	migrate_v6_to_v7,
+	migrate_v7_to_v8,
+	migrate_v8_to_v9,
+	migrate_v9_to_v10,
+	migrate_v10_to_v11,
+	migrate_v11_to_v12,
+	migrate_v12_to_v13,
+	migrate_v13_to_v14,
+	migrate_v14_to_v15,
+	migrate_v15_to_v16,
+];
+
+// A body of `version`, which the header has already checked is one this
+// build can read, as the newest version would have written it.
+pub(super) fn migrate(body: &[u8], version: u32) -> Result<Vec<u8>, SaveError> {
+	let mut body = body.to_vec();
+	for migration in &MIGRATIONS[version as usize - 1..] {
+		body = migration(&body)?;
+	}
+	Ok(body)
+}
+
+const POSITION: usize = 8 + 8 + 8;
+// Four scalars, two flags and two more scalars, up to the fall damage
+// settings.
+const PHYSICS_V1: usize = 4 * 8 + 2 + 2 * 8;
+// Everything between the level's names and the focus entity: the edit
+// persistence, physics settings, tick count, random state, coins collected
+// and iota, as far as each version has them.
+const SETTINGS_V1: usize = PHYSICS_V1 + 8 + 4 + 8;
+const SETTINGS_V6: usize = SETTINGS_V1 + 8;
+const SETTINGS_V8: usize = 1 + SETTINGS_V6;
+const SETTINGS_V10: usize = SETTINGS_V8 + 2 * 8;
+const SETTINGS_V11: usize = SETTINGS_V10 + 6 * 8;
+// An entity's id, kind, position, velocity, five directions, whether it is
+// grounded and its gravity, which it has started with since version 1.
+const ENTITY_HEAD: usize = 8 + 1 + POSITION + 3 * 8 + 5 + 1 + 1;
+
+// Reads the older body while writing the newer one.
+struct Copier<'a> {
+	input: Reader<'a>,
+	out: Writer,
+}
+
+impl<'a> Copier<'a> {
+	fn new(body: &'a [u8]) -> Self {
+		Self {
+			input: Reader::new(body),
+			out: Writer::new(),
+		}
+	}
+
+	fn copy(&mut self, length: usize) -> Result<(), SaveError> {
+		let bytes = self.input.take(length)?;
+		self.out.bytes.extend_from_slice(bytes);
+		Ok(())
+	}
+
+	fn byte(&mut self) -> Result<u8, SaveError> {
+		let byte = self.input.u8()?;
+		self.out.u8(byte);
+		Ok(byte)
+	}
+
+	fn string(&mut self) -> Result<(), SaveError> {
+		let length = self.input.u32()?;
+		self.out.u32(length);
+		self.copy(length as usize)
+	}
+
+	// A presence flag, then whatever `copy` copies if there is a value.
+	fn option(
+		&mut self,
+		copy: impl Fn(&mut Self) -> Result<(), SaveError>,
+	) -> Result<(), SaveError> {
+		let present = self.input.bool()?;
+		self.out.bool(present);
+		if present {
+			copy(self)?;
+		}
+		Ok(())
+	}
+
+	fn fixed_option(&mut self, length: usize) -> Result<(), SaveError> {
+		self.option(|copier| copier.copy(length))
+	}
+
+	// A count, then as many of whatever `copy` copies. Every item is at
+	// least a byte, so a count too large for the body fails on the first
+	// item past its end.
+	fn each(
+		&mut self,
+		copy: impl Fn(&mut Self) -> Result<(), SaveError>,
+	) -> Result<(), SaveError> {
+		let count = self.input.u32()?;
+		self.out.u32(count);
+		for _ in 0..count {
+			copy(self)?;
+		}
+		Ok(())
+	}
+
+	// The rest of the body as it is, once nothing more needs writing in.
+	fn finish(mut self) -> Result<Vec<u8>, SaveError> {
+		let rest = self.input.remaining();
+		self.copy(rest)?;
+		Ok(self.out.bytes)
+	}
+
+	fn level_names(&mut self) -> Result<(), SaveError> {
+		self.option(Self::string)?;
+		self.option(Self::string)?;
+		self.fixed_option(8)?;
+		self.option(Self::string)
+	}
+
+	// Up to the frames, given the length of everything in `SETTINGS_V1`
+	// and after.
+	fn head(&mut self, settings: usize) -> Result<(), SaveError> {
+		self.level_names()?;
+		self.copy(settings)?;
+		self.fixed_option(8)?;
+		self.fixed_option(POSITION)
+	}
+
+	// A frame's id, orientation, links, background pattern and tint.
+	fn frame_head(&mut self) -> Result<(), SaveError> {
+		self.copy(8 + 1)?;
+		for _ in 0..5 {
+			self.fixed_option(8 + 1)?;
+		}
+		self.string()?;
+		self.copy(3)
+	}
+
+	fn tile_data(&mut self) -> Result<(), SaveError> {
+		self.each(|copier| {
+			copier.copy(2)?;
+			match copier.byte()? {
+				0 => copier.string(),
+				1 => copier.copy(4),
+				_ => Err(invalid("bad tile data")),
+			}
+		})
+	}
+
+	fn frame_v1(&mut self) -> Result<(), SaveError> {
+		self.frame_head()?;
+		self.copy(FRAME_TILE_COUNT)?;
+		self.tile_data()
+	}
+
+	fn frame_v5(&mut self) -> Result<(), SaveError> {
+		self.frame_head()?;
+		self.fixed_option(2 * 8)?;
+		self.copy(FRAME_TILE_COUNT)?;
+		self.tile_data()
+	}
+
+	fn frame_v9(&mut self) -> Result<(), SaveError> {
+		self.frame_v5()?;
+		self.each(|copier| copier.copy(3))
+	}
+
+	fn frame_v13(&mut self) -> Result<(), SaveError> {
+		self.frame_head()?;
+		self.fixed_option(2 * 8)?;
+		self.string()?;
+		self.tile_data()?;
+		self.each(|copier| copier.copy(3))?;
+		self.copy(1)
+	}
+
+	fn entity_head(&mut self) -> Result<EntityKind, SaveError> {
+		self.copy(8)?;
+		let kind = kind_from_code(self.byte()?)?;
+		self.copy(ENTITY_HEAD - 8 - 1)?;
+		Ok(kind)
+	}
+
+	fn effects(&mut self) -> Result<(), SaveError> {
+		self.each(|copier| {
+			copier.string()?;
+			copier.copy(4 + 8)
+		})
+	}
+
+	fn entity_v2(&mut self) -> Result<EntityKind, SaveError> {
+		let kind = self.entity_head()?;
+		self.copy(1 + 8)?;
+		self.effects()?;
+		Ok(kind)
+	}
+
+	fn entity_v4(&mut self) -> Result<EntityKind, SaveError> {
+		let kind = self.entity_v2()?;
+		self.option(Self::string)?;
+		Ok(kind)
+	}
+
+	fn entity_v11(&mut self) -> Result<EntityKind, SaveError> {
+		let kind = self.entity_v4()?;
+		self.copy(1 + 8)?;
+		Ok(kind)
+	}
+
+	fn entity_v15(&mut self) -> Result<EntityKind, SaveError> {
+		let kind = self.entity_v11()?;
+		self.copy(3)?;
+		self.fixed_option(8)?;
+		self.each(|copier| copier.copy(POSITION + 1 + 1 + 8))?;
+		Ok(kind)
+	}
+}
+
+// Each entity's edge behavior, after its gravity direction.
+fn migrate_v1_to_v2(body: &[u8]) -> Result<Vec<u8>, SaveError> {
+	let mut copier = Copier::new(body);
+	copier.head(SETTINGS_V1)?;
+	copier.each(Copier::frame_v1)?;
+	copier.each(|copier| {
+		copier.entity_head()?;
+		copier.out.u8(edge_behavior_code(EdgeBehavior::default()));
+		copier.copy(8)?;
+		copier.effects()
+	})?;
+	copier.finish()
+}
+
+// The focus entity's statistics, after the hooks at the end.
+fn migrate_v2_to_v3(body: &[u8]) -> Result<Vec<u8>, SaveError> {
+	let mut out = Writer {
+		bytes: body.to_vec(),
+	};
+	let stats = WorldStats::default();
+	out.scalar(stats.distance);
+	out.u32(stats.tiles_placed);
+	out.u32(stats.tiles_removed);
+	out.u32(stats.jumps);
+	out.u32(stats.frame_crossings);
+	out.scalar(stats.damage_taken);
+	Ok(out.bytes)
+}
+
+// Each entity's tag, after its status effects. Group cleared hook
+// triggers are a new code, which older saves never have.
+fn migrate_v3_to_v4(body: &[u8]) -> Result<Vec<u8>, SaveError> {
+	let mut copier = Copier::new(body);
+	copier.head(SETTINGS_V1)?;
+	copier.each(Copier::frame_v1)?;
+	copier.each(|copier| {
+		copier.entity_v2()?;
+		copier.out.bool(false);
+		Ok(())
+	})?;
+	copier.finish()
+}
+
+// Each frame's wind, after its background.
+fn migrate_v4_to_v5(body: &[u8]) -> Result<Vec<u8>, SaveError> {
+	let mut copier = Copier::new(body);
+	copier.head(SETTINGS_V1)?;
+	copier.each(|copier| {
+		copier.frame_head()?;
+		copier.out.bool(false);
+		copier.copy(FRAME_TILE_COUNT)?;
+		copier.tile_data()
+	})?;
+	copier.finish()
+}
+
+// The random state, after the tick count. Older worlds were loaded with
+// the default seed.
+fn migrate_v5_to_v6(body: &[u8]) -> Result<Vec<u8>, SaveError> {
+	let mut copier = Copier::new(body);
+	copier.level_names()?;
+	copier.copy(PHYSICS_V1 + 8)?;
+	copier.out.u64(Rng::new(DEFAULT_SEED).state());
+	copier.finish()
+}
+
+// Only the header changed, gaining its metadata block.
+fn migrate_v6_to_v7(body: &[u8]) -> Result<Vec<u8>, SaveError> {
+	Ok(body.to_vec())
+}
+
+// The level's edit persistence, after its next level, and each entity's
+// persistence, after its tag. Checkpoint hook behaviors are a new code.
+fn migrate_v7_to_v8(body: &[u8]) -> Result<Vec<u8>, SaveError> {
+	let mut copier = Copier::new(body);
+	copier.level_names()?;
+	copier.out.u8(persistence_code(Persistence::default()));
+	copier.copy(SETTINGS_V6)?;
+	copier.fixed_option(8)?;
+	copier.fixed_option(POSITION)?;
+	copier.each(Copier::frame_v5)?;
+	copier.each(|copier| {
+		let kind = copier.entity_v4()?;
+		copier
+			.out
+			.u8(persistence_code(Persistence::default_for(kind)));
+		Ok(())
+	})?;
+	copier.finish()
+}
+
+// Each frame's tile rotations, after its tile data.
+fn migrate_v8_to_v9(body: &[u8]) -> Result<Vec<u8>, SaveError> {
+	let mut copier = Copier::new(body);
+	copier.head(SETTINGS_V8)?;
+	copier.each(|copier| {
+		copier.frame_v5()?;
+		copier.out.u32(0);
+		Ok(())
+	})?;
+	copier.finish()
+}
+
+// The spring speeds, after the fall damage settings. Spring squash tile
+// data is a new code.
+fn migrate_v9_to_v10(body: &[u8]) -> Result<Vec<u8>, SaveError> {
+	let mut copier = Copier::new(body);
+	let physics = PhysicsConfig::default();
+	copier.level_names()?;
+	copier.copy(1 + PHYSICS_V1)?;
+	copier.out.scalar(physics.spring_speed);
+	copier.out.scalar(physics.spring_jump_bonus);
+	copier.finish()
+}
+
+// The stamina settings, after the spring speeds, and each entity's
+// stamina, after its persistence. Everything starts with full stamina.
+fn migrate_v10_to_v11(body: &[u8]) -> Result<Vec<u8>, SaveError> {
+	let mut copier = Copier::new(body);
+	let physics = PhysicsConfig::default();
+	copier.level_names()?;
+	copier.copy(1 + PHYSICS_V1 + 2 * 8)?;
+	copier.out.scalar(physics.max_stamina);
+	copier.out.scalar(physics.stamina_regen);
+	copier.out.scalar(physics.sprint_multiplier);
+	copier.out.scalar(physics.sprint_stamina_cost);
+	copier.out.scalar(physics.dash_speed);
+	copier.out.scalar(physics.dash_stamina_cost);
+	copier.copy(SETTINGS_V6 - PHYSICS_V1)?;
+	copier.fixed_option(8)?;
+	copier.fixed_option(POSITION)?;
+	copier.each(Copier::frame_v9)?;
+	copier.each(|copier| {
+		copier.entity_v4()?;
+		copier.copy(1)?;
+		copier.out.scalar(physics.max_stamina);
+		Ok(())
+	})?;
+	copier.finish()
+}
+
+// Whether each frame has been visited, after its tile rotations. Nothing
+// that could have been seen is hidden in older saves.
+fn migrate_v11_to_v12(body: &[u8]) -> Result<Vec<u8>, SaveError> {
+	let mut copier = Copier::new(body);
+	copier.head(SETTINGS_V11)?;
+	copier.each(|copier| {
+		copier.frame_v9()?;
+		copier.out.bool(true);
+		Ok(())
+	})?;
+	copier.finish()
+}
+
+// Each frame's tiles as runs, from `encode_tiles`, after their length in
+// bytes.
+fn migrate_v12_to_v13(body: &[u8]) -> Result<Vec<u8>, SaveError> {
+	let mut copier = Copier::new(body);
+	copier.head(SETTINGS_V11)?;
+	copier.each(|copier| {
+		copier.frame_head()?;
+		copier.fixed_option(2 * 8)?;
+		let tiles = (0..FRAME_TILE_COUNT)
+			.map(|_| copier.input.tile())
+			.collect::<Result<Vec<_>, _>>()?;
+		let runs = encode_tiles(&tiles);
+		copier.out.u32(runs.len() as u32);
+		copier.out.bytes.extend_from_slice(&runs);
+		copier.tile_data()?;
+		copier.each(|copier| copier.copy(3))?;
+		copier.copy(1)
+	})?;
+	copier.finish()
+}
+
+// Each entity's collision channels, after its stamina.
+fn migrate_v13_to_v14(body: &[u8]) -> Result<Vec<u8>, SaveError> {
+	let mut copier = Copier::new(body);
+	copier.head(SETTINGS_V11)?;
+	copier.each(Copier::frame_v13)?;
+	copier.each(|copier| {
+		let kind = copier.entity_v11()?;
+		let channels = CollisionChannels::default_for(kind);
+		copier.out.u8(channels.category);
+		copier.out.u8(channels.collides_with);
+		copier.out.bool(channels.sensor);
+		Ok(())
+	})?;
+	copier.finish()
+}
+
+// Each entity's parent and trail, after its collision channels.
+fn migrate_v14_to_v15(body: &[u8]) -> Result<Vec<u8>, SaveError> {
+	let mut copier = Copier::new(body);
+	copier.head(SETTINGS_V11)?;
+	copier.each(Copier::frame_v13)?;
+	copier.each(|copier| {
+		copier.entity_v11()?;
+		copier.copy(3)?;
+		copier.out.bool(false);
+		copier.out.u32(0);
+		Ok(())
+	})?;
+	copier.finish()
+}
+
+// Each frame's open edges, after whether it has been visited, and whether
+// each entity is falling off the world, after its trail.
+fn migrate_v15_to_v16(body: &[u8]) -> Result<Vec<u8>, SaveError> {
+	let mut copier = Copier::new(body);
+	copier.head(SETTINGS_V11)?;
+	copier.each(|copier| {
+		copier.frame_v13()?;
+		copier.out.u32(0);
+		Ok(())
+	})?;
+	copier.each(|copier| {
+		copier.entity_v15()?;
+		copier.out.bool(false);
+		Ok(())
+	})?;
+	copier.finish()
+}
@@ -0,0 +1,134 @@
+use super::types::*;
+use super::{World, WorldEvent, TILE_SIZE};
+use crate::geometry::Scalar;
+
+// Sounds further than this along the surface from the listener are silent.
+// Two frame widths.
+pub const SOUND_RADIUS: Scalar = 4.0;
+// Extra attenuation for sounds on a frame that isn't the listener's or one
+// linked to it.
+const DISTANT_FRAME_GAIN: Scalar = 0.15;
+
+// How loud a sound should play and where in the stereo field.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SoundPlacement {
+	// From silent at zero to full volume at one.
+	pub gain: Scalar,
+	// From fully left at -1 to fully right at 1.
+	pub pan: Scalar,
+}
+
+impl SoundPlacement {
+	// Full volume and centered, for sounds that don't come from anywhere in
+	// the world, such as the interface's.
+	pub fn centered() -> Self {
+		Self {
+			gain: 1.0,
+			pan: 0.0,
+		}
+	}
+}
+
+// What the world makes a sound for, by the number the backend plays each
+// as.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Sound {
+	Coin,
+	Landing,
+	Bounce,
+	Hurt,
+	TileEdited,
+	EditRefused,
+	Dash,
+	Checkpoint,
+	LevelCompleted,
+}
+
+impl Sound {
+	pub fn id(self) -> u32 {
+		self as u32
+	}
+}
+
+impl World {
+	// The sounds made in the last tick, each placed as heard from the focus
+	// entity. Sounds from entities that are already gone aren't heard.
+	pub fn tick_sounds(&self) -> Vec<(Sound, SoundPlacement)> {
+		let at_entity = |entity| self.get_entity(entity).map(|e| e.position);
+		let at_tile = |frame_id, x: usize, y: usize| {
+			Some(WorldPosition {
+				frame_id,
+				x: -1.0 + (x as Scalar + 0.5) * TILE_SIZE,
+				y: -1.0 + (y as Scalar + 0.5) * TILE_SIZE,
+			})
+		};
+		let mut sounds = Vec::new();
+		for event in self.events() {
+			let (sound, source) = match *event {
+				WorldEvent::CoinCollected { collector, .. } => {
+					(Sound::Coin, at_entity(collector))
+				}
+				WorldEvent::EntityLanded { entity, .. } => {
+					(Sound::Landing, at_entity(entity))
+				}
+				WorldEvent::EntityBounced { frame, x, y, .. } => {
+					(Sound::Bounce, at_tile(frame, x, y))
+				}
+				WorldEvent::EntityDamaged { entity, .. } => {
+					(Sound::Hurt, at_entity(entity))
+				}
+				WorldEvent::TileEdited { frame, x, y, .. } => {
+					(Sound::TileEdited, at_tile(frame, x, y))
+				}
+				WorldEvent::TileEditFailed { entity, .. } => {
+					(Sound::EditRefused, at_entity(entity))
+				}
+				WorldEvent::EntityDashed { entity } => {
+					(Sound::Dash, at_entity(entity))
+				}
+				WorldEvent::CheckpointReached { entity } => {
+					(Sound::Checkpoint, at_entity(entity))
+				}
+				// Heard the same wherever it happened, like the interface.
+				WorldEvent::LevelCompleted { .. } => {
+					sounds.push((
+						Sound::LevelCompleted,
+						SoundPlacement::centered(),
+					));
+					continue;
+				}
+				_ => continue,
+			};
+			if let Some(source) = source {
+				sounds.push((sound, self.sound_placement(source)));
+			}
+		}
+		sounds
+	}
+
+	// How a sound made at `source` is heard from the focus entity. Falls off
+	// linearly with surface distance, and pans towards whichever side of the
+	// listener's frame the sound is reached along.
+	pub fn sound_placement(&self, source: WorldPosition) -> SoundPlacement {
+		let listener =
+			match self.focus_entity.and_then(|id| self.get_entity(id)) {
+				Some(entity) => entity.position,
+				None => return SoundPlacement::centered(),
+			};
+
+		let distance = self.surface_distance(listener, source);
+		let mut gain = (1.0 - distance / SOUND_RADIUS).max(0.0);
+		let near_frame = listener.frame_id == source.frame_id
+			|| Direction::iter().any(|&edge| {
+				let frame = self.get_frame(listener.frame_id).unwrap();
+				frame.borders.at_direction(edge).map(|link| link.frame)
+					== Some(source.frame_id)
+			});
+		if near_frame == false {
+			gain *= DISTANT_FRAME_GAIN;
+		}
+
+		let pan = self.surface_direction(listener, source).x;
+		SoundPlacement { gain, pan }
+	}
+}
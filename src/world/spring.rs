@@ -0,0 +1,45 @@
+use super::events::WorldEvent;
+use super::types::*;
+use super::{TileLocation, World};
+
+// Ticks a spring is drawn squashed for after throwing something.
+pub const SPRING_SQUASH_TICKS: u32 = 8;
+
+impl World {
+	// Squashes the spring an entity was just thrown off, told as an event.
+	pub(super) fn bounce_entity(&mut self, id: EntityId, spring: TileLocation) {
+		let (frame_id, x, y) = spring;
+		let frame = match self.get_frame_mut(frame_id) {
+			Some(frame) => frame,
+			None => return,
+		};
+		frame.set_tile_data(x, y, Some(TileData::Squash(SPRING_SQUASH_TICKS)));
+		self.events.push(WorldEvent::EntityBounced {
+			entity: id,
+			frame: frame_id,
+			x: x as usize,
+			y: y as usize,
+		});
+	}
+
+	// Counts down every squashed spring, leaving them as they were once
+	// done.
+	pub(super) fn update_springs(&mut self) {
+		let step = self.ticks_per_step;
+		for frame_id in self.frame_ids() {
+			let frame = self.get_frame_mut(frame_id).unwrap();
+			let squashed: Vec<((usize, usize), u32)> = frame
+				.tile_data_entries()
+				.filter_map(|(index, data)| match *data {
+					TileData::Squash(ticks) => Some((index, ticks)),
+					_ => None,
+				})
+				.collect();
+			for ((x, y), ticks) in squashed {
+				let ticks = ticks.saturating_sub(step);
+				let data = Some(TileData::Squash(ticks)).filter(|_| ticks > 0);
+				frame.set_tile_data(x as isize, y as isize, data);
+			}
+		}
+	}
+}
@@ -0,0 +1,56 @@
+use super::events::WorldEvent;
+use super::status::StatusKind;
+use super::types::*;
+use super::World;
+use crate::geometry::{vec3, Scalar};
+
+impl World {
+	// Whether the entity sprints this step, using up what it costs if so.
+	// Any stamina left at all is enough to sprint on until it runs out.
+	pub(super) fn sprint_entity(&mut self, id: EntityId) -> bool {
+		let step = self.ticks_per_step as Scalar;
+		let cost = self.physics.sprint_stamina_cost * step;
+		let entity = self.get_entity_mut(id).unwrap();
+		if entity.stamina <= 0.0 {
+			return false;
+		}
+		entity.stamina = (entity.stamina - cost).max(0.0);
+		true
+	}
+
+	// Throws the entity the way it points if it has the stamina for it,
+	// told as an event either way.
+	pub(super) fn dash_entity(&mut self, id: EntityId) -> bool {
+		let (speed, cost) =
+			(self.physics.dash_speed, self.physics.dash_stamina_cost);
+		let entity = self.get_entity_mut(id).unwrap();
+		if entity.stamina < cost {
+			self.events.push(WorldEvent::DashRefused { entity: id });
+			return false;
+		}
+		entity.stamina -= cost;
+		let (x, y) = entity.pointing().unit();
+		entity.velocity += vec3(x * speed, y * speed, 0.0);
+		self.events.push(WorldEvent::EntityDashed { entity: id });
+		true
+	}
+
+	// Stamina comes back while standing on something and not sprinting, up
+	// to the most the entity can have.
+	pub(super) fn regenerate_stamina(&mut self, id: EntityId, sprinting: bool) {
+		let step = self.ticks_per_step as Scalar;
+		let (regen, max) =
+			(self.physics.stamina_regen, self.physics.max_stamina);
+		let entity = match self.get_entity_mut(id) {
+			Some(entity) if entity.grounded && sprinting == false => entity,
+			_ => return,
+		};
+		let multiplier: Scalar = entity
+			.effects
+			.iter()
+			.filter(|effect| effect.kind == StatusKind::Energized)
+			.map(|effect| effect.magnitude)
+			.product();
+		entity.stamina = (entity.stamina + regen * multiplier * step).min(max);
+	}
+}
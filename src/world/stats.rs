@@ -0,0 +1,90 @@
+use super::types::*;
+use super::{World, TICK_RATE, TILE_SIZE};
+use crate::geometry::Scalar;
+
+// What the focus entity has been up to since the level began.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct WorldStats {
+	pub ticks: u64,
+	// Along the surface, in frame coordinates.
+	pub distance: Scalar,
+	pub tiles_placed: u32,
+	pub tiles_removed: u32,
+	pub jumps: u32,
+	pub frame_crossings: u32,
+	// Health lost, not counting hits that god mode or invulnerability
+	// turned away.
+	pub damage_taken: Scalar,
+	pub coins_collected: u32,
+}
+
+impl WorldStats {
+	// One `name: value` line per statistic.
+	pub fn lines(&self) -> Vec<String> {
+		vec![
+			format!(
+				"time: {:.2}s ({} ticks)",
+				self.ticks as f64 / TICK_RATE,
+				self.ticks
+			),
+			format!("distance: {:.1} tiles", self.distance / TILE_SIZE),
+			format!("tiles placed: {}", self.tiles_placed),
+			format!("tiles removed: {}", self.tiles_removed),
+			format!("jumps: {}", self.jumps),
+			format!("frame crossings: {}", self.frame_crossings),
+			format!("damage taken: {:.1}", self.damage_taken),
+			format!("coins: {}", self.coins_collected),
+		]
+	}
+}
+
+impl World {
+	// Ticks and coins are the world's own counts. Everything else is
+	// tallied as the focus entity does it.
+	pub fn stats(&self) -> WorldStats {
+		WorldStats {
+			ticks: self.tick_count,
+			coins_collected: self.coins_collected,
+			..self.stats
+		}
+	}
+
+	// Whether `id` is the entity statistics are kept for.
+	fn tracks_stats(&self, id: EntityId) -> bool {
+		self.focus_entity == Some(id)
+	}
+
+	pub(super) fn record_movement(
+		&mut self,
+		id: EntityId,
+		distance: Scalar,
+		crossed: bool,
+	) {
+		if self.tracks_stats(id) {
+			self.stats.distance += distance;
+			self.stats.frame_crossings += crossed as u32;
+		}
+	}
+
+	pub(super) fn record_jump(&mut self, id: EntityId) {
+		if self.tracks_stats(id) {
+			self.stats.jumps += 1;
+		}
+	}
+
+	pub(super) fn record_tile_change(&mut self, id: EntityId, tile: Tile) {
+		if self.tracks_stats(id) == false {
+			return;
+		}
+		match tile {
+			Tile::Empty => self.stats.tiles_removed += 1,
+			_ => self.stats.tiles_placed += 1,
+		}
+	}
+
+	pub(super) fn record_damage(&mut self, id: EntityId, amount: Scalar) {
+		if self.tracks_stats(id) {
+			self.stats.damage_taken += amount;
+		}
+	}
+}
@@ -0,0 +1,170 @@
+use super::types::*;
+use super::World;
+use crate::geometry::Scalar;
+
+// Ticks an entity can't be hurt for after being hit.
+const HIT_INVULNERABILITY_TICKS: u32 = 60;
+// How an entity flashes when a hit hurts it.
+const HIT_FLASH_TINT: [u8; 3] = [255, 255, 255];
+const HIT_FLASH_TICKS: u32 = 6;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum StatusKind {
+	// Walking acceleration is multiplied by the magnitude, which is below
+	// one for slows and above it for boosts.
+	Slow,
+	SpeedBoost,
+	// Damage is ignored. The magnitude is unused.
+	Invulnerable,
+	// Deals the magnitude in damage every tick.
+	Poison,
+	// Stamina comes back the magnitude times as fast.
+	Energized,
+}
+
+impl StatusKind {
+	pub fn name(&self) -> &'static str {
+		use StatusKind::*;
+		match self {
+			Slow => "slow",
+			SpeedBoost => "speed_boost",
+			Invulnerable => "invulnerable",
+			Poison => "poison",
+			Energized => "energized",
+		}
+	}
+
+	pub fn from_name(name: &str) -> Option<Self> {
+		use StatusKind::*;
+		[Slow, SpeedBoost, Invulnerable, Poison, Energized]
+			.iter()
+			.find(|kind| kind.name() == name)
+			.copied()
+	}
+
+	// Whether a new effect of this kind runs alongside any already on the
+	// entity. Otherwise it refreshes the existing one, which keeps the
+	// longer duration and the stronger magnitude of the two.
+	pub fn stacks(&self) -> bool {
+		match self {
+			StatusKind::Poison => true,
+			_ => false,
+		}
+	}
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct StatusEffect {
+	pub kind: StatusKind,
+	pub remaining_ticks: u32,
+	pub magnitude: Scalar,
+}
+
+impl StatusEffect {
+	pub fn new(kind: StatusKind, ticks: u32, magnitude: Scalar) -> Self {
+		Self {
+			kind,
+			remaining_ticks: ticks,
+			magnitude,
+		}
+	}
+
+	// Whichever of `self` and `other` is further from having no effect.
+	fn stronger(&self, other: &StatusEffect) -> Scalar {
+		match self.kind {
+			StatusKind::Slow => self.magnitude.min(other.magnitude),
+			_ => self.magnitude.max(other.magnitude),
+		}
+	}
+}
+
+impl World {
+	pub fn add_status_effect(&mut self, id: EntityId, effect: StatusEffect) {
+		let entity = match self.get_entity_mut(id) {
+			Some(entity) => entity,
+			None => return,
+		};
+		let existing = entity
+			.effects
+			.iter_mut()
+			.find(|existing| existing.kind == effect.kind);
+		match existing {
+			Some(existing) if effect.kind.stacks() == false => {
+				existing.magnitude = existing.stronger(&effect);
+				existing.remaining_ticks =
+					existing.remaining_ticks.max(effect.remaining_ticks);
+			}
+			_ => entity.effects.push(effect),
+		}
+	}
+
+	pub fn has_status(&self, id: EntityId, kind: StatusKind) -> bool {
+		self.get_entity(id).is_some_and(|entity| {
+			entity.effects.iter().any(|effect| effect.kind == kind)
+		})
+	}
+
+	// What an entity's walking acceleration is multiplied by.
+	pub(super) fn speed_multiplier(&self, id: EntityId) -> Scalar {
+		let entity = self.get_entity(id).unwrap();
+		entity
+			.effects
+			.iter()
+			.filter(|effect| match effect.kind {
+				StatusKind::Slow | StatusKind::SpeedBoost => true,
+				_ => false,
+			})
+			.map(|effect| effect.magnitude)
+			.product()
+	}
+
+	// Deals damage over time, then counts every effect down by the ticks in
+	// a step and drops those that have run out. An effect added with N ticks
+	// therefore acts on N ticks.
+	pub(super) fn update_status_effects(&mut self, id: EntityId) {
+		let step = self.ticks_per_step;
+		let poison: Scalar = self
+			.get_entity(id)
+			.unwrap()
+			.effects
+			.iter()
+			.filter(|effect| effect.kind == StatusKind::Poison)
+			.map(|effect| effect.magnitude)
+			.sum();
+		if poison > 0.0 {
+			self.damage_entity(id, poison * step as Scalar);
+		}
+
+		let entity = self.get_entity_mut(id).unwrap();
+		for effect in entity.effects.iter_mut() {
+			effect.remaining_ticks =
+				effect.remaining_ticks.saturating_sub(step);
+		}
+		entity.effects.retain(|effect| effect.remaining_ticks > 0);
+		entity.render_tint = entity
+			.render_tint
+			.map(|(tint, ticks)| (tint, ticks.saturating_sub(step)))
+			.filter(|&(_, ticks)| ticks > 0);
+	}
+
+	// Damage from a single blow, which flashes the entity if it hurt and
+	// leaves it briefly unable to be hurt again.
+	pub fn hit_entity(&mut self, id: EntityId, amount: Scalar) {
+		if self.has_status(id, StatusKind::Invulnerable) {
+			return;
+		}
+		let health = |world: &Self| world.get_entity(id).map(|e| e.health);
+		let before = health(self);
+		self.damage_entity(id, amount);
+		if health(self) != before {
+			let entity = self.get_entity_mut(id).unwrap();
+			entity.render_tint = Some((HIT_FLASH_TINT, HIT_FLASH_TICKS));
+		}
+		let invulnerable = StatusEffect::new(
+			StatusKind::Invulnerable,
+			HIT_INVULNERABILITY_TICKS,
+			0.0,
+		);
+		self.add_status_effect(id, invulnerable);
+	}
+}
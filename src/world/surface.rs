@@ -0,0 +1,513 @@
+use std::collections::VecDeque;
+
+use crate::geometry::{vec3, Matrix4x4, Scalar, Vector3, PI};
+
+use super::edges::{compose_rotation, rotate_point};
+use super::frame::FrameLink;
+use super::types::*;
+use super::World;
+
+// How many link hops `surface_transforms` follows from the focus frame. Two
+// is enough to reach every face of a cube.
+const SURFACE_SEARCH_DEPTH: usize = 2;
+// How many link hops `surface_distance` unfolds along every possible path
+// before falling back to a single shortest path.
+const UNFOLD_SEARCH_DEPTH: usize = 2;
+
+// Each frame occupies the square x, y ∈ [-1, 1] of the plane z = 1 in its
+// own local space, with +y pointing "down" the frame as tile coordinates do.
+// A surface transform maps that local space into cube space, where the focus
+// frame sits at z = 1 unrotated.
+impl World {
+	// Transforms for every frame reachable from `focus` within
+	// `SURFACE_SEARCH_DEPTH` hops, in breadth-first order starting with the
+	// focus frame itself.
+	pub fn surface_transforms(
+		&self,
+		focus: FrameId,
+	) -> Vec<(FrameId, Matrix4x4)> {
+		let mut found: Vec<(FrameId, Matrix4x4)> = Vec::new();
+		if self.get_frame(focus).is_none() {
+			return found;
+		}
+
+		let mut queue = VecDeque::new();
+		queue.push_back((focus, Matrix4x4::identity(), 0));
+		found.push((focus, Matrix4x4::identity()));
+
+		while let Some((frame_id, transform, depth)) = queue.pop_front() {
+			if depth >= SURFACE_SEARCH_DEPTH {
+				continue;
+			}
+			let frame = match self.get_frame(frame_id) {
+				Some(frame) => frame,
+				None => continue,
+			};
+
+			for &exit_edge in Direction::iter() {
+				if exit_edge == Direction::Neutral {
+					continue;
+				}
+				let link = match frame.borders.at_direction(exit_edge) {
+					Some(link) => link,
+					None => continue,
+				};
+				if found.iter().any(|&(id, _)| id == link.frame) {
+					continue;
+				}
+
+				let neighbor_transform = transform
+					* fold_matrix(exit_edge)
+					* in_plane_rotation(
+						compose_rotation(exit_edge, link).negative(),
+					);
+				found.push((link.frame, neighbor_transform));
+				queue.push_back((link.frame, neighbor_transform, depth + 1));
+			}
+		}
+
+		found
+	}
+
+	pub fn surface_transform(
+		&self,
+		focus: FrameId,
+		target: FrameId,
+	) -> Option<Matrix4x4> {
+		self.surface_transforms(focus)
+			.into_iter()
+			.find(|&(id, _)| id == target)
+			.map(|(_, transform)| transform)
+	}
+
+	// The point in cube space of a position on the surface, as seen with
+	// `focus` as the front face.
+	pub fn surface_point(
+		&self,
+		focus: FrameId,
+		position: WorldPosition,
+	) -> Option<Vector3> {
+		let transform = self.surface_transform(focus, position.frame_id)?;
+		Some(vec3(position.x, position.y, 1.0) * transform)
+	}
+
+	// Distance between two positions travelling along the surface, or
+	// infinity if there's no path of links between their frames.
+	//
+	// `to`'s frame is laid flat next to `from`'s by unfolding every path of
+	// up to `UNFOLD_SEARCH_DEPTH` links and taking the shortest straight line.
+	// Farther frames are unfolded along one shortest path of links only, which
+	// can overestimate when the straight line leaves that strip of frames.
+	pub fn surface_distance(
+		&self,
+		from: WorldPosition,
+		to: WorldPosition,
+	) -> Scalar {
+		match self.unfolded_position(from, to) {
+			Some(unfolded) => (unfolded - vec3(from.x, from.y, 0.0)).len(),
+			None => Scalar::INFINITY,
+		}
+	}
+
+	// The unit vector in `from`'s frame coordinates to head along to reach
+	// `to` by the path `surface_distance` measures. Zero if the positions are
+	// the same or unconnected.
+	pub fn surface_direction(
+		&self,
+		from: WorldPosition,
+		to: WorldPosition,
+	) -> Vector3 {
+		match self.unfolded_position(from, to) {
+			Some(unfolded) => {
+				(unfolded - vec3(from.x, from.y, 0.0)).normalized()
+			}
+			None => Vector3::zero(),
+		}
+	}
+
+	// `to` in the coordinates of `from`'s frame extended out into the plane,
+	// with z = 0.
+	fn unfolded_position(
+		&self,
+		from: WorldPosition,
+		to: WorldPosition,
+	) -> Option<Vector3> {
+		let start = vec3(from.x, from.y, 0.0);
+		let mut nearest: Option<Vector3> = None;
+		let mut visit = |unfolded: Vector3| {
+			let closer = nearest.is_none_or(|nearest| {
+				(unfolded - start).len() < (nearest - start).len()
+			});
+			if closer {
+				nearest = Some(unfolded);
+			}
+		};
+
+		let mut path = vec![from.frame_id];
+		self.unfold_paths(
+			&mut path,
+			Unfolding::identity(),
+			to,
+			UNFOLD_SEARCH_DEPTH,
+			&mut visit,
+		);
+		if nearest.is_some() {
+			return nearest;
+		}
+
+		let unfolding =
+			self.shortest_path_unfolding(from.frame_id, to.frame_id)?;
+		Some(unfolding.apply(to.x, to.y))
+	}
+
+	// Calls `visit` with `target` unfolded along every path of links that
+	// starts with `path` and doesn't revisit a frame.
+	fn unfold_paths(
+		&self,
+		path: &mut Vec<FrameId>,
+		unfolding: Unfolding,
+		target: WorldPosition,
+		depth: usize,
+		visit: &mut impl FnMut(Vector3),
+	) {
+		let frame_id = *path.last().unwrap();
+		if frame_id == target.frame_id {
+			visit(unfolding.apply(target.x, target.y));
+		}
+		if depth == 0 {
+			return;
+		}
+		let frame = match self.get_frame(frame_id) {
+			Some(frame) => frame,
+			None => return,
+		};
+
+		for &exit_edge in Direction::iter() {
+			if exit_edge == Direction::Neutral {
+				continue;
+			}
+			let link = match frame.borders.at_direction(exit_edge) {
+				Some(link) => link,
+				None => continue,
+			};
+			if path.contains(&link.frame) {
+				continue;
+			}
+
+			path.push(link.frame);
+			let next = unfolding.across(exit_edge, link);
+			self.unfold_paths(path, next, target, depth - 1, visit);
+			path.pop();
+		}
+	}
+
+	fn shortest_path_unfolding(
+		&self,
+		from: FrameId,
+		to: FrameId,
+	) -> Option<Unfolding> {
+		let mut visited = vec![from];
+		let mut queue = VecDeque::new();
+		queue.push_back((from, Unfolding::identity()));
+
+		while let Some((frame_id, unfolding)) = queue.pop_front() {
+			if frame_id == to {
+				return Some(unfolding);
+			}
+			let frame = match self.get_frame(frame_id) {
+				Some(frame) => frame,
+				None => continue,
+			};
+			for &exit_edge in Direction::iter() {
+				if exit_edge == Direction::Neutral {
+					continue;
+				}
+				if let Some(link) = frame.borders.at_direction(exit_edge) {
+					if visited.contains(&link.frame) {
+						continue;
+					}
+					visited.push(link.frame);
+					let next = unfolding.across(exit_edge, link);
+					queue.push_back((link.frame, next));
+				}
+			}
+		}
+
+		None
+	}
+}
+
+// Maps a frame's coordinates into the plane of another frame they have been
+// unfolded into: rotate by `angle`, then translate by `offset`.
+#[derive(Copy, Clone, Debug)]
+pub(super) struct Unfolding {
+	angle: Angle,
+	offset: Vector3,
+}
+
+impl Unfolding {
+	pub(super) fn identity() -> Self {
+		Self {
+			angle: Angle::Clockwise0,
+			offset: Vector3::zero(),
+		}
+	}
+
+	pub(super) fn apply(&self, x: Scalar, y: Scalar) -> Vector3 {
+		let (x, y) = rotate_point(x, y, self.angle);
+		self.offset + vec3(x, y, 0.0)
+	}
+
+	// The inverse of `apply`.
+	pub(super) fn unapply(&self, x: Scalar, y: Scalar) -> (Scalar, Scalar) {
+		let (x, y) = (x - self.offset.x, y - self.offset.y);
+		rotate_point(x, y, self.angle.negative())
+	}
+
+	// The unfolding of the frame entered along `link` after leaving this one
+	// through `exit_edge`. The neighbor's center lies one frame width beyond
+	// the exit edge, and it is rotated so that its entry edge faces back the
+	// way it was entered, as in `surface_transforms`.
+	pub(super) fn across(&self, exit_edge: Direction, link: FrameLink) -> Self {
+		let (edge_x, edge_y) = match exit_edge {
+			Direction::Up => (0.0, -2.0),
+			Direction::Down => (0.0, 2.0),
+			Direction::Left => (-2.0, 0.0),
+			Direction::Right => (2.0, 0.0),
+			Direction::Neutral => (0.0, 0.0),
+		};
+		let rotation = compose_rotation(exit_edge, link).negative();
+
+		Self {
+			angle: self.angle.compose(rotation),
+			offset: self.apply(edge_x, edge_y),
+		}
+	}
+}
+
+// Folds a frame lying next to the z = 1 face in the direction of `edge` down
+// onto the neighboring side of the cube, about their shared edge.
+pub fn fold_matrix(edge: Direction) -> Matrix4x4 {
+	let (rotate_pitch, rotate_roll) = match edge {
+		Direction::Neutral => (0.0, 0.0),
+		Direction::Up => (PI / 2.0, 0.0),
+		Direction::Down => (-PI / 2.0, 0.0),
+		Direction::Right => (0.0, PI / 2.0),
+		Direction::Left => (0.0, -PI / 2.0),
+	};
+
+	Matrix4x4::rotation(rotate_pitch, rotate_roll, 0.0)
+}
+
+// The rotation within the frame plane matching `edges::rotate_point`.
+pub fn in_plane_rotation(angle: Angle) -> Matrix4x4 {
+	let turns = angle.quarter_turns() as Scalar;
+	Matrix4x4::rotation(0.0, 0.0, turns * (PI / 2.0))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::super::edges::transform_position_across;
+	use super::super::Rng;
+	use super::*;
+
+	fn position(frame_id: FrameId, (x, y): (Scalar, Scalar)) -> WorldPosition {
+		WorldPosition { frame_id, x, y }
+	}
+
+	// A quarter of a frame in from each side of every linked edge of the
+	// cube, at a few places along it, is half a frame away across it, and
+	// the way there is straight out through the edge.
+	#[test]
+	fn the_distance_across_one_edge_is_straight_through_it() {
+		let world = World::new();
+		for frame_id in world.frame_ids() {
+			let frame = world.get_frame(frame_id).unwrap();
+			for &exit in Direction::iter() {
+				let link = match frame.borders.at_direction(exit) {
+					Some(link) if exit != Direction::Neutral => link,
+					_ => continue,
+				};
+				let (ex, ey) = exit.unit();
+				let (ax, ay) = exit.rotated(Angle::Clockwise90).unit();
+				for &along in [-0.6, 0.0, 0.8].iter() {
+					let at = |depth: Scalar| {
+						(ex * depth + ax * along, ey * depth + ay * along)
+					};
+					let from = position(frame_id, at(0.75));
+					let (x, y) = at(1.25);
+					let to = transform_position_across(exit, link, x, y);
+					let to = position(link.frame, to);
+					let distance = world.surface_distance(from, to);
+					assert!(
+						(distance - 0.5).abs() < 1e-4,
+						"{:?} to {:?} is {}",
+						from,
+						to,
+						distance
+					);
+					let direction = world.surface_direction(from, to);
+					assert!(
+						direction.approx_eq(vec3(ex, ey, 0.0), 1e-4),
+						"{:?} to {:?} heads {:?}",
+						from,
+						to,
+						direction
+					);
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn the_distance_within_a_frame_is_a_straight_line() {
+		let world = World::new();
+		let frame_id = world.frame_ids()[0];
+		let (from, to) = (
+			position(frame_id, (-0.5, 0.2)),
+			position(frame_id, (0.1, -0.6)),
+		);
+		assert!((world.surface_distance(from, to) - 1.0).abs() < 1e-4);
+		assert_eq!(world.surface_distance(from, from), 0.0);
+	}
+
+	#[test]
+	fn the_distance_is_the_same_either_way() {
+		let world = World::new();
+		let frame_ids = world.frame_ids();
+		let mut rng = Rng::new(3);
+		let mut random_position = || {
+			let frame_id = *rng.pick(&frame_ids).unwrap();
+			let x = rng.range_f32(-1.0, 1.0) as Scalar;
+			let y = rng.range_f32(-1.0, 1.0) as Scalar;
+			position(frame_id, (x, y))
+		};
+		for _ in 0..500 {
+			let (a, b) = (random_position(), random_position());
+			let (there, back) =
+				(world.surface_distance(a, b), world.surface_distance(b, a));
+			assert!(there.is_finite(), "{:?} to {:?}", a, b);
+			assert!(
+				(there - back).abs() < 1e-4,
+				"{:?} to {:?} is {} there and {} back",
+				a,
+				b,
+				there,
+				back
+			);
+		}
+	}
+
+	// The same points of every frame, seen from `focus`.
+	fn on_every_frame(
+		world: &World,
+		focus: FrameId,
+		points: &[(Scalar, Scalar)],
+	) -> Vec<Vector3> {
+		let mut found = Vec::new();
+		for frame_id in world.frame_ids() {
+			for &point in points {
+				let point =
+					world.surface_point(focus, position(frame_id, point));
+				found.push(point.expect("a face out of reach"));
+			}
+		}
+		found
+	}
+
+	fn distinct(points: &[Vector3]) -> usize {
+		let mut kept: Vec<Vector3> = Vec::new();
+		for &point in points {
+			if kept.iter().all(|kept| kept.approx_eq(point, 1e-4) == false) {
+				kept.push(point);
+			}
+		}
+		kept.len()
+	}
+
+	#[test]
+	fn the_focus_frame_is_the_front_face() {
+		let world = World::new();
+		for focus in world.frame_ids() {
+			for &(x, y) in [(0.0, 0.0), (-0.5, 0.25), (1.0, -1.0)].iter() {
+				let point = world.surface_point(focus, position(focus, (x, y)));
+				assert!(point.unwrap().approx_eq(vec3(x, y, 1.0), 1e-6));
+			}
+		}
+	}
+
+	// The middle of each frame is the middle of its own face, a unit along
+	// one axis, and the six faces are all different.
+	#[test]
+	fn each_frame_is_its_own_face_of_the_cube() {
+		let world = World::new();
+		for focus in world.frame_ids() {
+			let centers = on_every_frame(&world, focus, &[(0.0, 0.0)]);
+			for center in &centers {
+				let axes = [center.x, center.y, center.z];
+				let ones =
+					axes.iter().filter(|&&a| (a.abs() - 1.0).abs() < 1e-4);
+				let zeros = axes.iter().filter(|&&a| a.abs() < 1e-4);
+				assert_eq!(
+					(ones.count(), zeros.count()),
+					(1, 2),
+					"{:?}",
+					center
+				);
+			}
+			assert_eq!(distinct(&centers), 6);
+		}
+	}
+
+	// An edge's midpoint is shared by the two faces either side of it, and
+	// a corner by three, so that the whole surface folds up closed.
+	#[test]
+	fn edges_and_corners_are_shared_by_the_faces_that_meet_there() {
+		let world = World::new();
+		let midpoints = [(1.0, 0.0), (-1.0, 0.0), (0.0, 1.0), (0.0, -1.0)];
+		let corners = [(1.0, 1.0), (-1.0, 1.0), (1.0, -1.0), (-1.0, -1.0)];
+		for focus in world.frame_ids() {
+			let midpoints = on_every_frame(&world, focus, &midpoints);
+			assert_eq!(distinct(&midpoints), 12);
+			let corners = on_every_frame(&world, focus, &corners);
+			for corner in &corners {
+				let on_vertex = [corner.x, corner.y, corner.z]
+					.iter()
+					.all(|a| (a.abs() - 1.0).abs() < 1e-4);
+				assert!(on_vertex, "{:?}", corner);
+			}
+			assert_eq!(distinct(&corners), 8);
+		}
+	}
+
+	// Just either side of every linked edge is just as near in cube space
+	// as it is along the surface.
+	#[test]
+	fn positions_either_side_of_an_edge_are_neighbors_in_cube_space() {
+		let world = World::new();
+		for frame_id in world.frame_ids() {
+			let frame = world.get_frame(frame_id).unwrap();
+			for &exit in Direction::iter() {
+				let link = match frame.borders.at_direction(exit) {
+					Some(link) if exit != Direction::Neutral => link,
+					_ => continue,
+				};
+				let (ex, ey) = exit.unit();
+				let (ax, ay) = exit.rotated(Angle::Clockwise90).unit();
+				for &along in [-0.9, 0.0, 0.4].iter() {
+					let at = |depth: Scalar| {
+						(ex * depth + ax * along, ey * depth + ay * along)
+					};
+					let here = position(frame_id, at(0.99));
+					let (x, y) = at(1.01);
+					let there = transform_position_across(exit, link, x, y);
+					let there = position(link.frame, there);
+					let a = world.surface_point(frame_id, here).unwrap();
+					let b = world.surface_point(frame_id, there).unwrap();
+					let gap = (a - b).len();
+					assert!(gap < 0.03, "{:?} to {:?} is {}", here, there, gap);
+				}
+			}
+		}
+	}
+}
@@ -0,0 +1,331 @@
+use std::collections::{HashMap, HashSet};
+
+use super::serde::{decode_tiles, encode_tiles};
+use super::types::*;
+use super::{Frame, SaveError, World, FRAME_TILE_COUNT, FRAME_WIDTH};
+
+// A tile as the host changed it, for a client to change the same way.
+// `sequence` is how many edits the frame has had once this one is made,
+// for a client to tell that it missed one.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TileEdit {
+	pub frame_id: FrameId,
+	pub x: usize,
+	pub y: usize,
+	pub tile: Tile,
+	pub rotation: Angle,
+	pub data: Option<TileData>,
+	pub sequence: u64,
+}
+
+// What a host and its clients send each other, each as the bytes of one
+// `to_bytes`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SyncMessage {
+	// From a client wanting the whole world, as it does on joining.
+	Join,
+	// From a client that missed an edit to the frame, wanting all of it.
+	Resync(FrameId),
+	// The whole world as a save, and how many edits each frame has had.
+	Snapshot {
+		save: Vec<u8>,
+		sequences: Vec<(FrameId, u64)>,
+	},
+	Edit(TileEdit),
+	// A frame's tiles as `encode_tiles` writes them, its turned tiles and
+	// what its tiles hold, as of its `sequence`th edit.
+	Frame {
+		frame_id: FrameId,
+		sequence: u64,
+		tiles: Vec<u8>,
+		rotations: Vec<((usize, usize), Angle)>,
+		data: Vec<((usize, usize), TileData)>,
+	},
+	// Once a tick, after its edits: the entities as
+	// `World::to_entity_bytes` writes them, and how many edits each frame
+	// has had, so that a missed edit is noticed even if it was the last.
+	Tick {
+		sequences: Vec<(FrameId, u64)>,
+		entities: Vec<u8>,
+	},
+}
+
+// The frames as the host last sent them.
+struct SentFrame {
+	sequence: u64,
+	tiles: Vec<SentTile>,
+}
+
+type SentTile = (Tile, Angle, Option<TileData>);
+
+impl SentFrame {
+	fn new(frame: &Frame) -> Self {
+		Self {
+			sequence: 0,
+			tiles: frame_tiles(frame),
+		}
+	}
+}
+
+fn frame_tiles(frame: &Frame) -> Vec<SentTile> {
+	let w = FRAME_WIDTH as isize;
+	let mut tiles = Vec::with_capacity(FRAME_TILE_COUNT);
+	for y in 0..w {
+		for x in 0..w {
+			let data = frame.tile_data(x, y).cloned();
+			tiles.push((*frame.tile(x, y), frame.rotation(x, y), data));
+		}
+	}
+	tiles
+}
+
+// Keeps clients in step with a world that only the host ticks. Clients
+// are sent the whole world once, then each tile as it changes and every
+// entity every tick. However the tiles were changed, they are found by
+// comparing the frames with what was sent before.
+pub struct SyncHost {
+	sent: HashMap<FrameId, SentFrame>,
+}
+
+impl SyncHost {
+	pub fn new(world: &World) -> Self {
+		let mut host = Self {
+			sent: HashMap::new(),
+		};
+		host.start_over(world);
+		host
+	}
+
+	fn start_over(&mut self, world: &World) {
+		self.sent = world
+			.frames
+			.iter()
+			.map(|(&frame_id, frame)| (frame_id, SentFrame::new(frame)))
+			.collect();
+	}
+
+	fn sequences(&self) -> Vec<(FrameId, u64)> {
+		let mut sequences: Vec<_> = self
+			.sent
+			.iter()
+			.map(|(&frame_id, sent)| (frame_id, sent.sequence))
+			.collect();
+		sequences.sort_by_key(|&(frame_id, _)| frame_id.0);
+		sequences
+	}
+
+	// The whole world, for a client that is joining.
+	pub fn snapshot(&self, world: &World) -> SyncMessage {
+		SyncMessage::Snapshot {
+			save: world.to_save_bytes(0.0),
+			sequences: self.sequences(),
+		}
+	}
+
+	// All of a frame, for a client that asked for it again.
+	pub fn frame(
+		&self,
+		world: &World,
+		frame_id: FrameId,
+	) -> Option<SyncMessage> {
+		let frame = world.get_frame(frame_id)?;
+		let sent = self.sent.get(&frame_id)?;
+		let mut rotations: Vec<_> = frame.rotation_entries().collect();
+		rotations.sort_by_key(|&((x, y), _)| (y, x));
+		let mut data: Vec<_> = frame
+			.tile_data_entries()
+			.map(|(index, data)| (index, data.clone()))
+			.collect();
+		data.sort_by_key(|&((x, y), _)| (y, x));
+		Some(SyncMessage::Frame {
+			frame_id,
+			sequence: sent.sequence,
+			tiles: encode_tiles(frame.tiles()),
+			rotations,
+			data,
+		})
+	}
+
+	// What every client needs to be sent after a tick: the tiles changed
+	// since the last one and then the entities. A world whose frames are
+	// no longer the same ones is sent whole instead.
+	pub fn update(&mut self, world: &World) -> Vec<SyncMessage> {
+		let same_frames = self.sent.len() == world.frames.len()
+			&& world.frames.keys().all(|id| self.sent.contains_key(id));
+		if same_frames == false {
+			self.start_over(world);
+			return vec![self.snapshot(world)];
+		}
+
+		let mut messages = Vec::new();
+		for frame_id in world.frame_ids() {
+			let tiles = frame_tiles(&world.frames[&frame_id]);
+			let sent = self.sent.get_mut(&frame_id).unwrap();
+			for (i, tile) in tiles.into_iter().enumerate() {
+				if sent.tiles[i] == tile {
+					continue;
+				}
+				sent.tiles[i] = tile.clone();
+				sent.sequence += 1;
+				let (tile, rotation, data) = tile;
+				messages.push(SyncMessage::Edit(TileEdit {
+					frame_id,
+					x: i % FRAME_WIDTH,
+					y: i / FRAME_WIDTH,
+					tile,
+					rotation,
+					data,
+					sequence: sent.sequence,
+				}));
+			}
+		}
+		messages.push(SyncMessage::Tick {
+			sequences: self.sequences(),
+			entities: world.to_entity_bytes(),
+		});
+		messages
+	}
+}
+
+// A copy of the host's world, kept up to date from what the host sends.
+// An edit is only made if it is the very next one to its frame; after a
+// gap the frame is asked for again, and its edits ignored until it comes.
+pub struct SyncClient {
+	// How many edits each frame has had, once the world has come.
+	sequences: Option<HashMap<FrameId, u64>>,
+	resyncing: HashSet<FrameId>,
+}
+
+impl Default for SyncClient {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl SyncClient {
+	pub fn new() -> Self {
+		Self {
+			sequences: None,
+			resyncing: HashSet::new(),
+		}
+	}
+
+	// Whether the world has come and no frame is waiting to come again.
+	pub fn is_synced(&self) -> bool {
+		self.sequences.is_some() && self.resyncing.is_empty()
+	}
+
+	// Applies a message from the host to `world`, which a snapshot replaces
+	// outright. Gives what to send the host back.
+	pub fn receive(
+		&mut self,
+		world: &mut World,
+		message: SyncMessage,
+	) -> Result<Vec<SyncMessage>, SaveError> {
+		let (save, sequences) = match message {
+			SyncMessage::Snapshot { save, sequences } => (save, sequences),
+			message => return self.receive_update(world, message),
+		};
+		*world = World::from_save_bytes(&save)?;
+		self.sequences = Some(sequences.into_iter().collect());
+		self.resyncing.clear();
+		Ok(Vec::new())
+	}
+
+	// Anything after the world has come, which is ignored until it has.
+	fn receive_update(
+		&mut self,
+		world: &mut World,
+		message: SyncMessage,
+	) -> Result<Vec<SyncMessage>, SaveError> {
+		let sequences = match self.sequences.as_mut() {
+			Some(sequences) => sequences,
+			None => return Ok(Vec::new()),
+		};
+		let mut replies = Vec::new();
+		match message {
+			SyncMessage::Edit(edit) => {
+				let frame_id = edit.frame_id;
+				if self.resyncing.contains(&frame_id) {
+					return Ok(replies);
+				}
+				let last = match sequences.get_mut(&frame_id) {
+					Some(last) => last,
+					None => return Ok(replies),
+				};
+				if edit.sequence <= *last {
+					return Ok(replies);
+				}
+				if edit.sequence > *last + 1 {
+					self.resyncing.insert(frame_id);
+					replies.push(SyncMessage::Resync(frame_id));
+					return Ok(replies);
+				}
+				*last = edit.sequence;
+				if let Some(frame) = world.get_frame_mut(frame_id) {
+					let (x, y) = (edit.x, edit.y);
+					set_tile(frame, x, y, edit.tile, edit.rotation, edit.data);
+					frame.mark_changed();
+				}
+			}
+			SyncMessage::Frame {
+				frame_id,
+				sequence,
+				tiles,
+				rotations,
+				data,
+			} => {
+				let tiles = decode_tiles(&tiles, FRAME_TILE_COUNT)?;
+				let frame = match world.get_frame_mut(frame_id) {
+					Some(frame) => frame,
+					None => return Ok(replies),
+				};
+				for (i, tile) in tiles.into_iter().enumerate() {
+					let (x, y) = (i % FRAME_WIDTH, i / FRAME_WIDTH);
+					set_tile(frame, x, y, tile, Angle::Clockwise0, None);
+				}
+				for ((x, y), rotation) in rotations {
+					frame.set_rotation(x as isize, y as isize, rotation);
+				}
+				for ((x, y), data) in data {
+					frame.set_tile_data(x as isize, y as isize, Some(data));
+				}
+				frame.mark_changed();
+				sequences.insert(frame_id, sequence);
+				self.resyncing.remove(&frame_id);
+			}
+			SyncMessage::Tick {
+				sequences: latest,
+				entities,
+			} => {
+				world.apply_entity_bytes(&entities)?;
+				for (frame_id, sequence) in latest {
+					let behind = match sequences.get(&frame_id) {
+						Some(&last) => last < sequence,
+						None => false,
+					};
+					if behind && self.resyncing.insert(frame_id) {
+						replies.push(SyncMessage::Resync(frame_id));
+					}
+				}
+			}
+			_ => {}
+		}
+		Ok(replies)
+	}
+}
+
+// As the host has it, holding exactly what the host's tile holds.
+fn set_tile(
+	frame: &mut Frame,
+	x: usize,
+	y: usize,
+	tile: Tile,
+	turn: Angle,
+	data: Option<TileData>,
+) {
+	let (x, y) = (x as isize, y as isize);
+	*frame.tile_mut(x, y) = tile;
+	frame.set_rotation(x, y, turn);
+	frame.set_tile_data(x, y, data);
+}
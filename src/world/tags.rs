@@ -0,0 +1,61 @@
+use super::events::WorldEvent;
+use super::types::*;
+use super::World;
+
+// A group name, interned by the world so that entities, triggers and events
+// can carry it by copy. Only meaningful in the world that made it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TagId(pub usize);
+
+impl World {
+	// The id for `name`, making one if no entity or trigger has used it
+	// yet.
+	pub fn tag_id(&mut self, name: &str) -> TagId {
+		match self.find_tag(name) {
+			Some(tag) => tag,
+			None => {
+				self.tags.push(name.to_string());
+				TagId(self.tags.len() - 1)
+			}
+		}
+	}
+
+	pub fn find_tag(&self, name: &str) -> Option<TagId> {
+		self.tags.iter().position(|tag| tag == name).map(TagId)
+	}
+
+	pub fn tag_name(&self, tag: TagId) -> &str {
+		&self.tags[tag.0]
+	}
+
+	// Every entity in the group, in id order.
+	pub fn entities_with_tag(&self, name: &str) -> Vec<EntityId> {
+		let tag = match self.find_tag(name) {
+			Some(tag) => tag,
+			None => return Vec::new(),
+		};
+		self.entity_ids()
+			.into_iter()
+			.filter(|&id| self.entities[&id].tag == Some(tag))
+			.collect()
+	}
+
+	// Moves the entity into the group named `name`, or out of any with
+	// `None`. Leaving a group this way doesn't count as clearing it.
+	pub fn set_entity_tag(&mut self, id: EntityId, name: Option<&str>) {
+		let tag = name.map(|name| self.tag_id(name));
+		if let Some(entity) = self.get_entity_mut(id) {
+			entity.tag = tag;
+			self.change_count += 1;
+		}
+	}
+
+	// Called once an entity in the group has been removed.
+	pub(super) fn check_group_cleared(&mut self, tag: TagId) {
+		let remaining =
+			self.entities.values().any(|entity| entity.tag == Some(tag));
+		if remaining == false {
+			self.events.push(WorldEvent::GroupCleared { tag });
+		}
+	}
+}
@@ -0,0 +1,149 @@
+use super::types::*;
+use super::World;
+
+// Which edges `validate_topology` lets be left unlinked.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum OpenEdges {
+	// Any of them, as generated and edited worlds often leave some.
+	Any,
+	// Only those the level says are open on purpose, with any other taken
+	// for a link that was forgotten.
+	Declared,
+}
+
+// A frame edge that can't be followed consistently in both directions, or
+// that leads nowhere without meaning to.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TopologyIssue {
+	// The link points at a frame that doesn't exist.
+	MissingFrame {
+		frame: FrameId,
+		edge: Direction,
+		target: FrameId,
+	},
+	// The edge the link enters `target` through doesn't link back to this
+	// edge.
+	NotReciprocal {
+		frame: FrameId,
+		edge: Direction,
+		target: FrameId,
+		entry_edge: Direction,
+	},
+	// The edge has no link and isn't open.
+	Unlinked {
+		frame: FrameId,
+		edge: Direction,
+	},
+}
+
+impl TopologyIssue {
+	// The frame whose link is at fault.
+	pub fn frame(&self) -> FrameId {
+		match *self {
+			TopologyIssue::MissingFrame { frame, .. } => frame,
+			TopologyIssue::NotReciprocal { frame, .. } => frame,
+			TopologyIssue::Unlinked { frame, .. } => frame,
+		}
+	}
+
+	pub fn edge(&self) -> Direction {
+		match *self {
+			TopologyIssue::MissingFrame { edge, .. } => edge,
+			TopologyIssue::NotReciprocal { edge, .. } => edge,
+			TopologyIssue::Unlinked { edge, .. } => edge,
+		}
+	}
+}
+
+impl std::fmt::Display for TopologyIssue {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match *self {
+			TopologyIssue::MissingFrame {
+				frame,
+				edge,
+				target,
+			} => write!(
+				f,
+				"{} {} links to missing frame {}",
+				frame,
+				edge.name(),
+				target
+			),
+			TopologyIssue::NotReciprocal {
+				frame,
+				edge,
+				target,
+				entry_edge,
+			} => write!(
+				f,
+				"{} {} links to {} {}, which doesn't link back",
+				frame,
+				edge.name(),
+				target,
+				entry_edge.name()
+			),
+			TopologyIssue::Unlinked { frame, edge } => write!(
+				f,
+				"{} {} is neither linked nor open",
+				frame,
+				edge.name()
+			),
+		}
+	}
+}
+
+impl World {
+	// Every link that doesn't have a matching link back, along with every
+	// unlinked edge that `open` doesn't allow, ordered by frame.
+	pub fn validate_topology(&self, open: OpenEdges) -> Vec<TopologyIssue> {
+		let mut issues = Vec::new();
+		for frame_id in self.frame_ids() {
+			let frame = &self.frames[&frame_id];
+			for &edge in Direction::iter() {
+				if edge == Direction::Neutral {
+					continue;
+				}
+				let link = match frame.borders.at_direction(edge) {
+					Some(link) => link,
+					None => {
+						let declared = frame.open_edges.contains(&edge);
+						if open == OpenEdges::Declared && declared == false {
+							issues.push(TopologyIssue::Unlinked {
+								frame: frame_id,
+								edge,
+							});
+						}
+						continue;
+					}
+				};
+
+				let target = match self.get_frame(link.frame) {
+					Some(target) => target,
+					None => {
+						issues.push(TopologyIssue::MissingFrame {
+							frame: frame_id,
+							edge,
+							target: link.frame,
+						});
+						continue;
+					}
+				};
+
+				let reciprocal = target.borders.at_direction(link.entry_edge);
+				let consistent = reciprocal.is_some_and(|back| {
+					back.frame == frame_id && back.entry_edge == edge
+				});
+				if consistent == false {
+					issues.push(TopologyIssue::NotReciprocal {
+						frame: frame_id,
+						edge,
+						target: link.frame,
+						entry_edge: link.entry_edge,
+					});
+				}
+			}
+		}
+
+		issues
+	}
+}
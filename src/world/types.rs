@@ -1,8 +1,9 @@
-use crate::prelude::*;
+use crate::geometry::Scalar;
 
-use super::World;
+use super::edges::{compose_rotation, transform_position_across};
+use super::FrameSource;
 
-#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct EntityId(pub usize);
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
@@ -28,98 +29,258 @@ impl FrameId {
 #[derive(Copy, Clone, Debug)]
 pub struct WorldPosition {
 	pub frame_id: FrameId,
-	pub x: f32,
-	pub y: f32,
+	pub x: Scalar,
+	pub y: Scalar,
 }
 
 impl WorldPosition {
-	pub fn normalize(&self, world: &World) -> Self {
+	pub fn normalize(
+		&self,
+		world: &impl FrameSource,
+	) -> Result<WorldPosition, NormalizeError> {
+		self.as_raw().normalize(world)
+	}
+
+	pub fn normalize_traced(
+		&self,
+		world: &impl FrameSource,
+	) -> Result<(WorldPosition, Option<FrameCrossing>), NormalizeError> {
+		self.as_raw().normalize_traced(world)
+	}
+
+	fn as_raw(&self) -> RawWorldPosition {
 		RawWorldPosition {
 			root_frame_id: self.frame_id,
 			x: self.x,
 			y: self.y,
 		}
-		.normalize(world)
+	}
+
+	pub fn is_in_bounds(&self) -> bool {
+		in_frame_bounds(self.x) && in_frame_bounds(self.y)
+	}
+}
+
+// Positions further out than this many frame hops are rejected rather than
+// followed, so a bad link or a huge velocity can't send `normalize` around
+// the world indefinitely.
+pub const MAX_NORMALIZE_HOPS: usize = 8;
+
+// Largest coordinate that is still inside a frame.
+const FRAME_BOUND_BELOW_ONE: Scalar = 1.0 - Scalar::EPSILON;
+
+pub(super) fn in_frame_bounds(value: Scalar) -> bool {
+	(-1.0..1.0).contains(&value)
+}
+
+// How far past the frame's bounds a coordinate is, or zero if it isn't.
+fn overshoot(value: Scalar) -> Scalar {
+	if value >= 1.0 {
+		value - 1.0
+	} else if value < -1.0 {
+		-1.0 - value
+	} else {
+		0.0
+	}
+}
+
+// The edge an out of bounds position is taken across first. Past a corner,
+// that is the edge it is furthest past, so that approaches from either side
+// of the corner's diagonal meet at the same places on the far face. Exact
+// ties go across the horizontal edge.
+fn exit_edge(x: Scalar, y: Scalar) -> Direction {
+	use Direction::*;
+	let (over_x, over_y) = (overshoot(x), overshoot(y));
+	let horizontal = if x >= 1.0 { Right } else { Left };
+	let vertical = if y >= 1.0 { Down } else { Up };
+	match (in_frame_bounds(x), in_frame_bounds(y)) {
+		(true, true) => Neutral,
+		(false, true) => horizontal,
+		(true, false) => vertical,
+		(false, false) if over_y > over_x => vertical,
+		(false, false) => horizontal,
 	}
 }
 
+#[derive(Copy, Clone, Debug)]
+pub enum NormalizeError {
+	NaN,
+	MissingFrame(FrameId),
+	// The position left its frame through an edge with no link. Contains
+	// the last position reached, which is still out of bounds.
+	UnlinkedEdge {
+		best_effort: WorldPosition,
+		edge: Direction,
+	},
+	TooManyHops {
+		best_effort: WorldPosition,
+	},
+}
+
+impl NormalizeError {
+	pub fn best_effort(&self) -> Option<WorldPosition> {
+		use NormalizeError::*;
+		match *self {
+			NaN | MissingFrame(_) => None,
+			UnlinkedEdge { best_effort, .. } => Some(best_effort),
+			TooManyHops { best_effort } => Some(best_effort),
+		}
+	}
+}
+
+impl std::fmt::Display for NormalizeError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		use NormalizeError::*;
+		match self {
+			NaN => write!(f, "NaN position"),
+			MissingFrame(frame_id) => write!(f, "missing frame {}", frame_id),
+			UnlinkedEdge { best_effort, edge } => write!(
+				f,
+				"unlinked edge {:?} of frame {} at ({}, {})",
+				edge, best_effort.frame_id, best_effort.x, best_effort.y
+			),
+			TooManyHops { best_effort } => write!(
+				f,
+				"more than {} frame hops, stopped at {} ({}, {})",
+				MAX_NORMALIZE_HOPS,
+				best_effort.frame_id,
+				best_effort.x,
+				best_effort.y
+			),
+		}
+	}
+}
+
+// Describes how a position moved between frames while being normalized. If
+// several edges were crossed, `exit_edge` is the first edge left and
+// `entry_edge` the last edge entered, with `angle` their combined rotation.
+#[derive(Copy, Clone, Debug)]
+pub struct FrameCrossing {
+	pub from: FrameId,
+	pub to: FrameId,
+	pub exit_edge: Direction,
+	pub entry_edge: Direction,
+	pub angle: Angle,
+}
+
 #[derive(Copy, Clone, Debug)]
 pub struct RawWorldPosition {
 	pub root_frame_id: FrameId,
-	pub x: f32,
-	pub y: f32,
+	pub x: Scalar,
+	pub y: Scalar,
 }
 
 impl RawWorldPosition {
-	pub fn normalize(&self, world: &World) -> WorldPosition {
-		let (x, y) = (self.x, self.y);
-		let root_frame = world
-			.get_frame(self.root_frame_id)
-			.expect("Frame neighbor access error");
+	// Moves the position across frame edges until it lies within the bounds
+	// of a single frame.
+	pub fn normalize(
+		&self,
+		world: &impl FrameSource,
+	) -> Result<WorldPosition, NormalizeError> {
+		self.normalize_traced(world).map(|(position, _)| position)
+	}
 
-		if x.is_nan() || y.is_nan() {
-			panic!("NaN RawWorldPosition");
+	pub fn normalize_traced(
+		&self,
+		world: &impl FrameSource,
+	) -> Result<(WorldPosition, Option<FrameCrossing>), NormalizeError> {
+		if self.x.is_nan() || self.y.is_nan() {
+			return Err(NormalizeError::NaN);
 		}
 
-		if x >= -1.0 && x < 1.0 && y >= -1.0 && y < 1.0 {
-			return WorldPosition {
-				frame_id: self.root_frame_id,
-				x,
-				y,
-			};
-		}
+		let mut current = *self;
+		let mut crossing: Option<FrameCrossing> = None;
 
-		let borders = root_frame.borders;
+		for _ in 0..MAX_NORMALIZE_HOPS {
+			let (x, y) = (current.x, current.y);
+			let root_frame = world
+				.frame(current.root_frame_id)
+				.ok_or(NormalizeError::MissingFrame(current.root_frame_id))?;
 
-		use Direction::*;
-		let (exit_edge, real_x, real_y) = match (x, y) {
-			(x, y) if (x >= 1.0) => (Right, x - 2.0, y),
-			(x, y) if (x < -1.0) => (Left, x + 2.0, y),
-			(x, y) if (y >= 1.0) => (Down, x, y - 2.0),
-			(x, y) if (y < -1.0) => (Up, x, y + 2.0),
-			(x, y) => (Neutral, x, y),
-		};
-
-		let neighbor = match borders.at_direction(exit_edge) {
-			Some(p) => p,
-			None => {
-				elog("Could not access position's real frame:");
-				elog(format!("{:?} @ {:?}", self, exit_edge));
-				elog(format!("selecting from {:?}", borders));
-				panic!("Frame neighbor access error");
+			if in_frame_bounds(x) && in_frame_bounds(y) {
+				return Ok((current.unchecked_position(), crossing));
 			}
-		};
 
-		let entry_edge = neighbor.entry_edge;
-		let entry_frame_id = neighbor.frame;
-		let angle_change = exit_edge.angle_to(entry_edge.reverse());
+			let borders = root_frame.borders;
+
+			use Direction::*;
+			let exit_edge = exit_edge(x, y);
+
+			let neighbor = match borders.at_direction(exit_edge) {
+				Some(p) => p,
+				None => {
+					return Err(NormalizeError::UnlinkedEdge {
+						best_effort: current.unchecked_position(),
+						edge: exit_edge,
+					});
+				}
+			};
+
+			let entry_edge = neighbor.entry_edge;
+			let entry_frame_id = neighbor.frame;
+			let angle_change = compose_rotation(exit_edge, neighbor);
 
-		// other.rotated(self.as_angle().reverse()).as_angle()
-		//println!("{:?}, {:?}", exit_edge, entry_edge.rotated(Angle::Clockwise180));
-		//println!("! {:?}->{:?} '{:?}", exit_edge, entry_edge, angle_change);
+			let (next_x, next_y) =
+				transform_position_across(exit_edge, neighbor, x, y);
+			let mut next = RawWorldPosition {
+				root_frame_id: entry_frame_id,
+				x: next_x,
+				y: next_y,
+			};
+
+			// Rotation can map the coordinate along the crossed edge from the
+			// inclusive -1.0 bound onto the exclusive 1.0 bound, which would
+			// otherwise cause a spurious second hop at frame corners.
+			let horizontal_exit = exit_edge == Left || exit_edge == Right;
+			let (along_edge, across_edge) =
+				if horizontal_exit { (y, x) } else { (x, y) };
+			let swapped = angle_change == Angle::Clockwise90
+				|| angle_change == Angle::Clockwise270;
+			let (rotated_along_edge, rotated_across_edge) =
+				if horizontal_exit != swapped {
+					(&mut next.y, &mut next.x)
+				} else {
+					(&mut next.x, &mut next.y)
+				};
+			if in_frame_bounds(along_edge) && *rotated_along_edge >= 1.0 {
+				*rotated_along_edge = FRAME_BOUND_BELOW_ONE;
+			}
+			// Likewise a position exactly on the exclusive 1.0 bound it
+			// leaves by lands exactly on the edge it enters by, which is
+			// the exclusive bound again if that is the far edge, and would
+			// hop back and forth between the two frames forever.
+			if overshoot(across_edge) == 0.0 && *rotated_across_edge >= 1.0 {
+				*rotated_across_edge = FRAME_BOUND_BELOW_ONE;
+			}
 
-		let real_world_position = RawWorldPosition {
-			root_frame_id: entry_frame_id,
-			x: real_x,
-			y: real_y,
+			crossing = Some(match crossing {
+				None => FrameCrossing {
+					from: current.root_frame_id,
+					to: entry_frame_id,
+					exit_edge,
+					entry_edge,
+					angle: angle_change,
+				},
+				Some(previous) => FrameCrossing {
+					to: entry_frame_id,
+					entry_edge,
+					angle: previous.angle.compose(angle_change),
+					..previous
+				},
+			});
+			current = next;
 		}
-		.rotated(angle_change);
 
-		// Call recursively until position is brought within bounds.
-		real_world_position.normalize(world)
+		Err(NormalizeError::TooManyHops {
+			best_effort: current.unchecked_position(),
+		})
 	}
 
-	pub fn rotated(&self, angle: Angle) -> Self {
-		let (rotated_x, rotated_y) = match angle {
-			Angle::Clockwise0 => (self.x, self.y),
-			Angle::Clockwise90 => (-self.y, self.x),
-			Angle::Clockwise180 => (-self.x, -self.y),
-			Angle::Clockwise270 => (self.y, -self.x),
-		};
-		Self {
-			root_frame_id: self.root_frame_id,
-			x: rotated_x,
-			y: rotated_y,
+	fn unchecked_position(&self) -> WorldPosition {
+		WorldPosition {
+			frame_id: self.root_frame_id,
+			x: self.x,
+			y: self.y,
 		}
 	}
 }
@@ -143,10 +304,30 @@ impl Contacts {
 	}
 }
 
-#[derive(Copy, Clone, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Tile {
 	Empty,
 	Solid,
+	// Shows the `TileData::Text` attached to it when the player interacts
+	// in or beside it.
+	Sign,
+	// 45 degree ramps, rising towards the right or left. Entities stand on
+	// the diagonal rather than the tile top.
+	SlopeUpRight,
+	SlopeUpLeft,
+	// Solid, and carries anything standing on it along at a steady speed.
+	ConveyorLeft,
+	ConveyorRight,
+	// Not solid, and does nothing by itself. Levels hook behaviors to
+	// entities moving onto it.
+	PressurePlate,
+	// Solid, and throws anything that runs into its face back off it, up
+	// unless it is turned.
+	Spring,
+	// Fall, and pile up or spread sideways. Neither is solid; sand slows
+	// whatever is in it.
+	Sand,
+	Water,
 	Invalid,
 }
 
@@ -156,12 +337,276 @@ impl Tile {
 		match *self {
 			Empty => false,
 			Solid => true,
+			Sign => false,
+			// Handled separately by `slope_at` in `World::resolve_movement`.
+			SlopeUpRight => false,
+			SlopeUpLeft => false,
+			ConveyorLeft => true,
+			ConveyorRight => true,
+			PressurePlate => false,
+			Spring => true,
+			Sand => false,
+			Water => false,
 			Invalid => true,
 		}
 	}
+
+	pub fn is_fluid(&self) -> bool {
+		match *self {
+			Tile::Sand | Tile::Water => true,
+			_ => false,
+		}
+	}
+
+	// Whether the tile blocks movement once turned by `rotation`. Ramps
+	// turned to hang from the top of their tile are solid, since entities
+	// only stand on ramps facing up.
+	pub fn is_solid_turned(&self, rotation: Angle) -> bool {
+		match self.ramp_sides(rotation) {
+			Some(_) => self.slope_height(rotation, 0.0).is_none(),
+			None => self.is_solid(),
+		}
+	}
+
+	// Whether turning the tile changes what it does.
+	pub fn is_directional(&self) -> bool {
+		self.ramp_sides(Angle::Clockwise0).is_some()
+			|| self.conveyor_direction(Angle::Clockwise0).is_some()
+			|| self.spring_direction(Angle::Clockwise0).is_some()
+	}
+
+	// The two sides of a ramp that are solid all the way along, the one its
+	// surface rises towards and the one it stands on, once turned by
+	// `rotation`. Turned a quarter turn, a ramp stands on what was its high
+	// side and rises the other way.
+	pub fn ramp_sides(
+		&self,
+		rotation: Angle,
+	) -> Option<(Direction, Direction)> {
+		let high = match *self {
+			Tile::SlopeUpRight => Direction::Right,
+			Tile::SlopeUpLeft => Direction::Left,
+			_ => return None,
+		};
+		Some((high.rotated(rotation), Direction::Down.rotated(rotation)))
+	}
+
+	// The corners of a ramp turned by `rotation`, from -1 to 1 across the
+	// tile with +y down: the ends of its diagonal, low end first for a ramp
+	// standing up, then the corner between its solid sides.
+	pub fn ramp_corners(
+		&self,
+		rotation: Angle,
+	) -> Option<[(Scalar, Scalar); 3]> {
+		let (high, base) = self.ramp_sides(rotation)?;
+		let ((hx, hy), (bx, by)) = (high.unit(), base.unit());
+		Some([(bx - hx, by - hy), (hx - bx, hy - by), (hx + bx, hy + by)])
+	}
+
+	// Height of the slope surface above the bottom of the tile, as a
+	// fraction of the tile size, at `u` from its left edge to its right,
+	// once turned by `rotation`. `None` for tiles that aren't slopes, and
+	// for hanging ramps.
+	pub fn slope_height(&self, rotation: Angle, u: Scalar) -> Option<Scalar> {
+		let rises = match self.ramp_sides(rotation)? {
+			(Direction::Down, side) | (side, Direction::Down) => side,
+			_ => return None,
+		};
+		match rises {
+			Direction::Right => Some(u),
+			_ => Some(1.0 - u),
+		}
+	}
+
+	// Which way a conveyor carries things once turned by `rotation`, in the
+	// axes of its frame.
+	pub fn conveyor_direction(&self, rotation: Angle) -> Option<Direction> {
+		let direction = match *self {
+			Tile::ConveyorLeft => Direction::Left,
+			Tile::ConveyorRight => Direction::Right,
+			_ => return None,
+		};
+		Some(direction.rotated(rotation))
+	}
+
+	// Which way a spring's face points, and so which way it throws, once
+	// turned by `rotation`.
+	pub fn spring_direction(&self, rotation: Angle) -> Option<Direction> {
+		match *self {
+			Tile::Spring => Some(Direction::Up.rotated(rotation)),
+			_ => None,
+		}
+	}
+
+	pub fn name(&self) -> &'static str {
+		use Tile::*;
+		match *self {
+			Empty => "empty",
+			Solid => "solid",
+			Sign => "sign",
+			SlopeUpRight => "slope_up_right",
+			SlopeUpLeft => "slope_up_left",
+			ConveyorLeft => "conveyor_left",
+			ConveyorRight => "conveyor_right",
+			PressurePlate => "pressure_plate",
+			Spring => "spring",
+			Sand => "sand",
+			Water => "water",
+			Invalid => "invalid",
+		}
+	}
+
+	// Every tile that can be placed in a level.
+	pub fn placeable() -> &'static [Self] {
+		use Tile::*;
+		&[
+			Empty,
+			Solid,
+			Sign,
+			SlopeUpRight,
+			SlopeUpLeft,
+			ConveyorLeft,
+			ConveyorRight,
+			PressurePlate,
+			Spring,
+			Sand,
+			Water,
+		]
+	}
+
+	// Any placeable tile, by name.
+	pub fn from_name(name: &str) -> Option<Self> {
+		Self::placeable()
+			.iter()
+			.find(|tile| tile.name() == name)
+			.copied()
+	}
+
+	// Character used for the tile in level files.
+	pub fn symbol(&self) -> char {
+		use Tile::*;
+		match *self {
+			Empty => '.',
+			Solid => '#',
+			Sign => '?',
+			SlopeUpRight => '/',
+			SlopeUpLeft => '\\',
+			ConveyorLeft => '<',
+			ConveyorRight => '>',
+			PressurePlate => '_',
+			Spring => '^',
+			Sand => ':',
+			Water => '~',
+			Invalid => '!',
+		}
+	}
+
+	pub fn from_symbol(symbol: char) -> Option<Self> {
+		use Tile::*;
+		match symbol {
+			'.' => Some(Empty),
+			'#' => Some(Solid),
+			'?' => Some(Sign),
+			'/' => Some(SlopeUpRight),
+			'\\' => Some(SlopeUpLeft),
+			'<' => Some(ConveyorLeft),
+			'>' => Some(ConveyorRight),
+			'_' => Some(PressurePlate),
+			'^' => Some(Spring),
+			':' => Some(Sand),
+			'~' => Some(Water),
+			_ => None,
+		}
+	}
+}
+
+// A faint pattern drawn across a frame behind its tiles, so that faces can
+// be told apart at a glance.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum BackgroundPattern {
+	None,
+	Dots,
+	Diagonals,
+	Checker,
+}
+
+impl BackgroundPattern {
+	pub fn name(&self) -> &'static str {
+		use BackgroundPattern::*;
+		match self {
+			None => "none",
+			Dots => "dots",
+			Diagonals => "diagonals",
+			Checker => "checker",
+		}
+	}
+
+	pub fn from_name(name: &str) -> Option<Self> {
+		use BackgroundPattern::*;
+		[None, Dots, Diagonals, Checker]
+			.iter()
+			.find(|pattern| pattern.name() == name)
+			.copied()
+	}
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BackgroundStyle {
+	pub pattern: BackgroundPattern,
+	// Red, green and blue. Renderers dim it well below the tiles.
+	pub tint: [u8; 3],
+}
+
+impl BackgroundStyle {
+	// Six distinct styles, given to frames by id until a level picks its
+	// own.
+	pub fn default_for(frame_id: FrameId) -> Self {
+		use BackgroundPattern::*;
+		let styles = [
+			(Dots, [255, 90, 90]),
+			(Diagonals, [90, 220, 90]),
+			(Checker, [90, 140, 255]),
+			(Diagonals, [255, 220, 60]),
+			(Dots, [220, 90, 255]),
+			(Checker, [60, 220, 220]),
+		];
+		let (pattern, tint) = styles[frame_id.0 % styles.len()];
+		Self { pattern, tint }
+	}
+
+	// The tint as six hex digits, as in level files.
+	pub fn tint_hex(&self) -> String {
+		let [r, g, b] = self.tint;
+		format!("{:02x}{:02x}{:02x}", r, g, b)
+	}
+
+	pub fn parse_tint(hex: &str) -> Option<[u8; 3]> {
+		if hex.len() != 6 || hex.is_ascii() == false {
+			return None;
+		}
+		let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+		Some([channel(0)?, channel(2)?, channel(4)?])
+	}
+}
+
+// Extra per-tile state that doesn't fit in `Tile` itself, stored sparsely
+// on the frame.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TileData {
+	Text(String),
+	// Ticks left of a spring's squash after throwing something.
+	Squash(u32),
 }
 
 #[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SetTileError {
+	MissingFrame(FrameId),
+	OutOfBounds,
+	// A solid tile would have trapped this entity inside it.
+	Occupied(EntityId),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Angle {
 	Clockwise0,
 	Clockwise90,
@@ -189,14 +634,46 @@ impl Angle {
 			Clockwise270 => Clockwise90,
 		}
 	}
+
+	// The angle equivalent to rotating by `self` and then by `other`, e.g.
+	// the total rotation accumulated over several frame hops.
+	pub fn compose(self, other: Angle) -> Self {
+		Self::from_quarter_turns(self.quarter_turns() + other.quarter_turns())
+	}
+
+	pub fn quarter_turns(&self) -> u8 {
+		use Angle::*;
+		match *self {
+			Clockwise0 => 0,
+			Clockwise90 => 1,
+			Clockwise180 => 2,
+			Clockwise270 => 3,
+		}
+	}
+
+	pub fn from_quarter_turns(turns: u8) -> Self {
+		use Angle::*;
+		match turns % 4 {
+			0 => Clockwise0,
+			1 => Clockwise90,
+			2 => Clockwise180,
+			_ => Clockwise270,
+		}
+	}
+
+	pub fn iter<'a>() -> impl std::iter::Iterator<Item = &'a Self> {
+		use Angle::*;
+		[Clockwise0, Clockwise90, Clockwise180, Clockwise270].iter()
+	}
 }
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub enum Direction {
 	Up,
 	Down,
 	Left,
 	Right,
+	#[default]
 	Neutral,
 }
 
@@ -224,6 +701,9 @@ impl Direction {
 		}
 	}
 
+	// The angle that rotates `self` onto `other`, so that
+	// `self.rotated(self.angle_to(other)) == other`. `Neutral` has no angle
+	// and is treated as `Up` here; it is unchanged by any rotation.
 	pub fn angle_to(&self, other: Direction) -> Angle {
 		other.rotated(self.as_angle().negative()).as_angle()
 	}
@@ -233,6 +713,35 @@ impl Direction {
 		[Up, Down, Left, Right, Neutral].iter()
 	}
 
+	pub fn name(&self) -> &'static str {
+		use Direction::*;
+		match self {
+			Up => "up",
+			Down => "down",
+			Left => "left",
+			Right => "right",
+			Neutral => "neutral",
+		}
+	}
+
+	pub fn from_name(name: &str) -> Option<Self> {
+		Self::iter()
+			.find(|direction| direction.name() == name)
+			.copied()
+	}
+
+	// A unit step this way in frame coordinates, where +y points down.
+	pub fn unit(&self) -> (Scalar, Scalar) {
+		use Direction::*;
+		match self {
+			Up => (0.0, -1.0),
+			Down => (0.0, 1.0),
+			Left => (-1.0, 0.0),
+			Right => (1.0, 0.0),
+			Neutral => (0.0, 0.0),
+		}
+	}
+
 	pub fn rotated(&self, angle: Angle) -> Self {
 		use Angle::*;
 		use Direction::*;
@@ -254,3 +763,299 @@ impl Direction {
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use std::collections::HashMap;
+
+	use super::super::{Frame, FrameLink, Rng, World};
+	use super::*;
+
+	const EDGES: [Direction; 4] = [
+		Direction::Up,
+		Direction::Down,
+		Direction::Left,
+		Direction::Right,
+	];
+
+	struct Frames(HashMap<FrameId, Frame>);
+
+	impl FrameSource for Frames {
+		fn frame(&self, id: FrameId) -> Option<&Frame> {
+			self.0.get(&id)
+		}
+	}
+
+	// Frame 0 with `exit` linked into frame 1 through `entry`, and nothing
+	// else linked.
+	fn pair(exit: Direction, entry: Direction) -> Frames {
+		let (a, b) = (FrameId::new(0), FrameId::new(1));
+		let mut frame = Frame::new(a);
+		*frame.borders.at_direction_mut(exit) = Some(FrameLink {
+			frame: b,
+			entry_edge: entry,
+		});
+		let mut frames = HashMap::new();
+		frames.insert(a, frame);
+		frames.insert(b, Frame::new(b));
+		Frames(frames)
+	}
+
+	fn position(frame_id: usize, x: Scalar, y: Scalar) -> WorldPosition {
+		WorldPosition {
+			frame_id: FrameId::new(frame_id),
+			x,
+			y,
+		}
+	}
+
+	fn scaled((x, y): (Scalar, Scalar), by: Scalar) -> (Scalar, Scalar) {
+		(x * by, y * by)
+	}
+
+	fn added(a: (Scalar, Scalar), b: (Scalar, Scalar)) -> (Scalar, Scalar) {
+		(a.0 + b.0, a.1 + b.1)
+	}
+
+	// Every way two frames can be linked, with points just past the exit
+	// edge, far past it and exactly on it. Each should come out as far in
+	// past the entry edge and at the same place along it, with the frame
+	// turned so that going out through one is going in through the other.
+	#[test]
+	fn crossing_every_edge_pair_lands_where_it_should() {
+		let (alongs, depths) =
+			([-1.0, -0.7, 0.0, 0.3, 0.95], [0.0, 0.01, 0.25, 0.9]);
+		for &exit in EDGES.iter() {
+			for &entry in EDGES.iter() {
+				let frames = pair(exit, entry);
+				let turn = *Angle::iter()
+					.find(|&&angle| exit.rotated(angle) == entry.reverse())
+					.unwrap();
+				// The way along the edge, counted clockwise, on each
+				// side.
+				let along_exit = exit.rotated(Angle::Clockwise90).unit();
+				let along_entry =
+					entry.reverse().rotated(Angle::Clockwise90).unit();
+				for &along in alongs.iter() {
+					for &depth in depths.iter() {
+						let (x, y) = added(
+							scaled(exit.unit(), 1.0 + depth),
+							scaled(along_exit, along),
+						);
+						let (expected_x, expected_y) = added(
+							scaled(entry.unit(), 1.0 - depth),
+							scaled(along_entry, along),
+						);
+						let from = position(0, x, y);
+						// Exactly on the top or left edge is still inside,
+						// and exactly on the right or bottom edge along
+						// another is past both.
+						let past_both = in_frame_bounds(x) == false
+							&& in_frame_bounds(y) == false;
+						if exit_edge(x, y) != exit || past_both {
+							continue;
+						}
+						let (to, crossing) = from
+							.normalize_traced(&frames)
+							.unwrap_or_else(|error| {
+								panic!("{:?} into {:?}: {}", exit, entry, error)
+							});
+						let crossing = crossing.unwrap();
+						let context = format!(
+							"{:?} into {:?}, ({}, {}) went to ({}, {}), \
+							expected ({}, {})",
+							exit,
+							entry,
+							x,
+							y,
+							to.x,
+							to.y,
+							expected_x,
+							expected_y
+						);
+						assert_eq!(to.frame_id, FrameId::new(1), "{}", context);
+						assert!(to.is_in_bounds(), "{}", context);
+						assert!(
+							(to.x - expected_x).abs() < 1e-5
+								&& (to.y - expected_y).abs() < 1e-5,
+							"{}",
+							context
+						);
+						assert_eq!(crossing.exit_edge, exit);
+						assert_eq!(crossing.entry_edge, entry);
+						assert_eq!(crossing.angle, turn, "{}", context);
+					}
+				}
+			}
+		}
+	}
+
+	// Exactly on the right or bottom edge is past it by nothing, and comes
+	// out exactly on the edge entered by. Linked back the same way, that
+	// used to be past the far edge again and go back and forth until it
+	// ran out of hops.
+	#[test]
+	fn exactly_on_an_edge_linked_both_ways_crosses_once() {
+		for &exit in [Direction::Right, Direction::Down].iter() {
+			for &entry in [Direction::Right, Direction::Down].iter() {
+				let mut frames = pair(exit, entry);
+				let b = frames.0.get_mut(&FrameId::new(1)).unwrap();
+				*b.borders.at_direction_mut(entry) = Some(FrameLink {
+					frame: FrameId::new(0),
+					entry_edge: exit,
+				});
+				let along_exit = exit.rotated(Angle::Clockwise90).unit();
+				for &along in [-0.7, 0.0, 0.3].iter() {
+					let (x, y) = added(exit.unit(), scaled(along_exit, along));
+					let (to, crossing) = position(0, x, y)
+						.normalize_traced(&frames)
+						.unwrap_or_else(|error| {
+							panic!("{:?} into {:?}: {}", exit, entry, error)
+						});
+					let context = format!(
+						"{:?} into {:?}, ({}, {}) went to {:?}",
+						exit, entry, x, y, to
+					);
+					assert_eq!(to.frame_id, FrameId::new(1), "{}", context);
+					assert!(to.is_in_bounds(), "{}", context);
+					assert_eq!(crossing.unwrap().from, FrameId::new(0));
+					let again = to.normalize(&frames).unwrap();
+					assert_eq!(again.frame_id, to.frame_id, "{}", context);
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn unlinked_edges_and_nan_are_errors() {
+		for &exit in EDGES.iter() {
+			let frames = pair(exit, Direction::Up);
+			for &edge in EDGES.iter().filter(|&&edge| edge != exit) {
+				let (x, y) = scaled(edge.unit(), 1.5);
+				match position(0, x, y).normalize(&frames) {
+					Err(NormalizeError::UnlinkedEdge {
+						edge: unlinked,
+						..
+					}) => assert_eq!(unlinked, edge),
+					other => panic!("{:?} past {:?}", other, edge),
+				}
+			}
+		}
+		let frames = pair(Direction::Up, Direction::Up);
+		let nan = Scalar::NAN;
+		match position(0, nan, 0.0).normalize(&frames) {
+			Err(NormalizeError::NaN) => {}
+			other => panic!("{:?} for NaN", other),
+		}
+		match position(7, 0.0, 0.0).normalize(&frames) {
+			Err(NormalizeError::MissingFrame(_)) => {}
+			other => panic!("{:?} on a missing frame", other),
+		}
+	}
+
+	// A frame linked to itself straight across goes round forever, which
+	// is cut off with the furthest position reached.
+	#[test]
+	fn runaway_positions_stop_after_too_many_hops() {
+		let id = FrameId::new(0);
+		let mut frame = Frame::new(id);
+		frame.borders.right = Some(FrameLink {
+			frame: id,
+			entry_edge: Direction::Left,
+		});
+		let mut frames = HashMap::new();
+		frames.insert(id, frame);
+		let frames = Frames(frames);
+		let far = (MAX_NORMALIZE_HOPS * 2 + 3) as Scalar * 2.0;
+		match position(0, far, 0.0).normalize(&frames) {
+			Err(NormalizeError::TooManyHops { best_effort }) => {
+				assert!(best_effort.x.is_finite());
+			}
+			other => panic!("{:?} far past a frame", other),
+		}
+	}
+
+	// Anywhere up to two frames past the edges of any face of the cube
+	// comes out somewhere on the cube, and is already where it should be
+	// once it has.
+	#[test]
+	fn normalizing_the_cube_is_in_bounds_and_idempotent() {
+		let world = World::new();
+		let frame_ids = world.frame_ids();
+		let mut rng = Rng::new(394);
+		for _ in 0..5000 {
+			let frame_id = *rng.pick(&frame_ids).unwrap();
+			let x = rng.range_f32(-3.0, 3.0) as Scalar;
+			let y = rng.range_f32(-3.0, 3.0) as Scalar;
+			let from = WorldPosition { frame_id, x, y };
+			let once = from.normalize(&world).unwrap_or_else(|error| {
+				panic!("{:?} didn't normalize: {}", from, error)
+			});
+			assert!(once.x.is_nan() == false && once.y.is_nan() == false);
+			assert!(once.is_in_bounds(), "{:?} went to {:?}", from, once);
+			let twice = once.normalize(&world).unwrap();
+			assert!(
+				twice.frame_id == once.frame_id
+					&& twice.x == once.x
+					&& twice.y == once.y,
+				"{:?} went to {:?}, then {:?}",
+				from,
+				once,
+				twice
+			);
+		}
+	}
+
+	#[test]
+	fn turning_a_direction_four_times_is_no_turn() {
+		for &direction in Direction::iter() {
+			for &angle in Angle::iter() {
+				let mut turned = direction;
+				for _ in 0..4 {
+					turned = turned.rotated(angle);
+				}
+				assert_eq!(turned, direction, "{:?} by {:?}", direction, angle);
+			}
+			let by_quarters = (0..4).fold(direction, |turned, _| {
+				turned.rotated(Angle::Clockwise90)
+			});
+			assert_eq!(by_quarters, direction);
+		}
+	}
+
+	#[test]
+	fn the_opposite_of_the_opposite_is_the_same() {
+		for &direction in Direction::iter() {
+			assert_eq!(direction.reverse().reverse(), direction);
+			assert_eq!(
+				direction.rotated(Angle::Clockwise180),
+				direction.reverse()
+			);
+		}
+		for &angle in Angle::iter() {
+			assert_eq!(angle.reverse().reverse(), angle);
+			assert_eq!(angle.negative().negative(), angle);
+		}
+	}
+
+	// Adding an angle and then taking it away again, either way round, is
+	// where it started, as is turning by one and then the other.
+	#[test]
+	fn adding_and_taking_away_an_angle_round_trips() {
+		for &a in Angle::iter() {
+			assert_eq!(a.compose(Angle::Clockwise0), a);
+			for &b in Angle::iter() {
+				assert_eq!(a.compose(b).compose(b.negative()), a);
+				assert_eq!(a.compose(b.negative()).compose(b), a);
+				assert_eq!(a.compose(b), b.compose(a));
+				for &direction in Direction::iter() {
+					assert_eq!(
+						direction.rotated(a).rotated(b),
+						direction.rotated(a.compose(b))
+					);
+				}
+			}
+			assert_eq!(Angle::from_quarter_turns(a.quarter_turns()), a);
+		}
+	}
+}
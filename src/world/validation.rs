@@ -0,0 +1,195 @@
+use std::collections::{HashSet, VecDeque};
+
+use super::edges::transform_tile_across;
+use super::types::*;
+use super::{AlignmentRules, Behavior, EntityKind, SeamIssue};
+use super::{OpenEdges, TopologyIssue};
+use super::{World, FRAME_WIDTH};
+
+// A tile anywhere in the world.
+pub type TileLocation = (FrameId, isize, isize);
+
+// Something wrong with a level that loads, but plays badly or not at all.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LevelIssue {
+	Topology(TopologyIssue),
+	Seam(SeamIssue),
+	// There is no player to start from.
+	NoSpawn,
+	// The player starts inside a solid tile.
+	SpawnBlocked(TileLocation),
+	// A coin the player can't get to from where they start, so the level
+	// can't be completed.
+	UnreachableCoin(EntityId, TileLocation),
+	// Text attached to a tile that isn't a sign, which nothing reads.
+	UnusedTileData(TileLocation),
+}
+
+impl std::fmt::Display for LevelIssue {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			LevelIssue::Topology(issue) => write!(f, "{}", issue),
+			LevelIssue::Seam(issue) => write!(f, "{}", issue),
+			LevelIssue::NoSpawn => write!(f, "no player to spawn"),
+			LevelIssue::SpawnBlocked((frame, x, y)) => {
+				write!(
+					f,
+					"player spawns inside a tile at {}/({},{})",
+					frame, x, y
+				)
+			}
+			LevelIssue::UnreachableCoin(id, (frame, x, y)) => write!(
+				f,
+				"coin {} at {}/({},{}) can't be reached from the spawn",
+				id.0, frame, x, y
+			),
+			LevelIssue::UnusedTileData((frame, x, y)) => write!(
+				f,
+				"text at {}/({},{}) isn't on a sign, so is never shown",
+				frame, x, y
+			),
+		}
+	}
+}
+
+impl World {
+	// Everything that would stop a level being played through, ordered by
+	// kind and then by frame. Empty for a good level.
+	pub fn validate_level(&self) -> Vec<LevelIssue> {
+		let mut issues: Vec<LevelIssue> = self
+			.validate_topology(OpenEdges::Declared)
+			.into_iter()
+			.map(LevelIssue::Topology)
+			.collect();
+		// Hand-made levels close off edges on purpose, such as with a floor
+		// along one, so only walled in tiles are counted against them.
+		let rules = AlignmentRules {
+			min_corridors: 0,
+			..AlignmentRules::default()
+		};
+		issues
+			.extend(self.seam_issues(&rules).into_iter().map(LevelIssue::Seam));
+
+		match self.focus_entity.and_then(|id| self.get_entity(id)) {
+			None => issues.push(LevelIssue::NoSpawn),
+			Some(player) => {
+				let spawn = self.entity_tile(player.position);
+				if self.tile_passable(spawn) == false {
+					issues.push(LevelIssue::SpawnBlocked(spawn));
+				} else {
+					let reachable = self.reachable_tiles(spawn);
+					for id in self.entity_ids() {
+						let entity = self.get_entity(id).unwrap();
+						if entity.kind != EntityKind::Coin {
+							continue;
+						}
+						let tile = self.entity_tile(entity.position);
+						if reachable.contains(&tile) == false {
+							issues.push(LevelIssue::UnreachableCoin(id, tile));
+						}
+					}
+				}
+			}
+		}
+
+		for frame_id in self.frame_ids() {
+			let frame = &self.frames[&frame_id];
+			let mut unused: Vec<(usize, usize)> = frame
+				.tile_data_entries()
+				.filter(|&((x, y), data)| match data {
+					TileData::Text(_) => {
+						*frame.tile(x as isize, y as isize) != Tile::Sign
+					}
+					TileData::Squash(_) => {
+						*frame.tile(x as isize, y as isize) != Tile::Spring
+					}
+				})
+				.map(|(index, _)| index)
+				.collect();
+			unused.sort_by_key(|&(x, y)| (y, x));
+			issues.extend(unused.into_iter().map(|(x, y)| {
+				LevelIssue::UnusedTileData((frame_id, x as isize, y as isize))
+			}));
+		}
+
+		issues
+	}
+
+	// Every tile that can be got to from `start` without passing through a
+	// solid one, following frame links. Gravity is disabled, so the player
+	// moves freely in all four directions and anything open is in reach by
+	// some path; once it pulls again this will need to account for how high
+	// and how far a jump carries. Pushable blocks are treated as open,
+	// since they can be moved out of the way, as are tiles a hook can clear.
+	pub fn reachable_tiles(
+		&self,
+		start: TileLocation,
+	) -> HashSet<TileLocation> {
+		let mut reached = HashSet::new();
+		let mut open = VecDeque::new();
+		reached.insert(start);
+		open.push_back(start);
+
+		while let Some(tile) = open.pop_front() {
+			for &direction in Direction::iter() {
+				if direction == Direction::Neutral {
+					continue;
+				}
+				let next = match self.tile_beyond(tile, direction) {
+					Some(next) => next,
+					None => continue,
+				};
+				if self.tile_passable(next) && reached.insert(next) {
+					open.push_back(next);
+				}
+			}
+		}
+
+		reached
+	}
+
+	// The tile next to `tile` in `direction`, across a frame edge if need
+	// be. None past an edge with no link, or a link to a missing frame.
+	pub(super) fn tile_beyond(
+		&self,
+		(frame_id, x, y): TileLocation,
+		direction: Direction,
+	) -> Option<TileLocation> {
+		let w = FRAME_WIDTH as isize;
+		let (dx, dy) = direction.unit();
+		let (x, y) = (x + dx as isize, y + dy as isize);
+		if x >= 0 && y >= 0 && x < w && y < w {
+			return Some((frame_id, x, y));
+		}
+		let link = self.get_frame(frame_id)?.borders.at_direction(direction)?;
+		self.get_frame(link.frame)?;
+		let (x, y) = transform_tile_across(direction, link, x, y);
+		Some((link.frame, x, y))
+	}
+
+	fn tile_passable(&self, (frame_id, x, y): TileLocation) -> bool {
+		let open = self.get_frame(frame_id).is_some_and(|frame| {
+			frame.tile(x, y).is_solid_turned(frame.rotation(x, y)) == false
+		});
+		open || self.hooks.iter().any(|&(_, behavior)| match behavior {
+			Behavior::SetTile {
+				frame,
+				x: tx,
+				y: ty,
+				tile,
+				..
+			} => {
+				(frame, tx as isize, ty as isize) == (frame_id, x, y)
+					&& tile.is_solid() == false
+			}
+			_ => false,
+		})
+	}
+
+	// Kept inside the frame for positions exactly on its far edges.
+	fn entity_tile(&self, position: WorldPosition) -> TileLocation {
+		let max = FRAME_WIDTH as isize - 1;
+		let (x, y) = self.tile_index_at_position(position);
+		(position.frame_id, x.max(0).min(max), y.max(0).min(max))
+	}
+}
@@ -0,0 +1,179 @@
+use super::types::*;
+use super::{EntityKind, World, CONTACT_PROBE, FRAME_WIDTH, TILE_SIZE};
+use crate::geometry::{Scalar, Vector3};
+
+// Walkers accelerate at this fraction of the player's walking rate.
+const WALKER_SPEED: Scalar = 0.5;
+
+// What a walker does on reaching the end of whatever it walks along.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum EdgeBehavior {
+	// Turns around at walls and before stepping off the end of its floor.
+	TurnAtLedge,
+	// Turns around only at walls, and carries on past the end of its floor.
+	#[default]
+	WalkOff,
+	// Keeps hugging the floor round every corner, going down the far side
+	// of a ledge and up any wall it meets, so it circles whatever it walks
+	// on. Across frame edges it carries on onto the next face like
+	// anything else.
+	FollowSurface,
+}
+
+impl EdgeBehavior {
+	pub fn name(&self) -> &'static str {
+		use EdgeBehavior::*;
+		match self {
+			TurnAtLedge => "turn_at_ledge",
+			WalkOff => "walk_off",
+			FollowSurface => "follow_surface",
+		}
+	}
+
+	pub fn from_name(name: &str) -> Option<Self> {
+		Self::iter()
+			.find(|behavior| behavior.name() == name)
+			.copied()
+	}
+
+	pub fn iter<'a>() -> impl std::iter::Iterator<Item = &'a Self> {
+		use EdgeBehavior::*;
+		[TurnAtLedge, WalkOff, FollowSurface].iter()
+	}
+}
+
+// Which tiles around a walker are solid, relative to the way it walks and
+// the way its floor is.
+struct Surroundings {
+	// Beneath it, ahead of it, and beneath the tiles ahead and behind.
+	floor: bool,
+	ahead: bool,
+	floor_ahead: bool,
+	floor_behind: bool,
+	// How far across its tile the walker is in the way it walks, from zero
+	// at the back edge to one at the front.
+	progress: Scalar,
+	// Where the tile's back edge lies along the walking axis.
+	back_edge: Scalar,
+}
+
+impl World {
+	pub(super) fn steer_walker(&mut self, id: EntityId) {
+		let entity = self.get_entity(id).unwrap();
+		match entity.kind {
+			EntityKind::Walker | EntityKind::Worm => {}
+			_ => return,
+		}
+		let behavior = entity.edge_behavior;
+		let walking = entity.orientation;
+		let (wx, wy) = walking.unit();
+		let blocked = entity.velocity.x * wx + entity.velocity.y * wy == 0.0;
+		let around = self.walker_surroundings(id);
+
+		let entity = self.get_entity_mut(id).unwrap();
+		match behavior {
+			EdgeBehavior::WalkOff => {
+				if blocked {
+					entity.orientation = walking.reverse();
+				}
+			}
+			EdgeBehavior::TurnAtLedge => {
+				let ledge = around.floor
+					&& around.floor_ahead == false
+					&& around.progress > 0.5;
+				if blocked || ledge {
+					entity.orientation = walking.reverse();
+				}
+			}
+			EdgeBehavior::FollowSurface => {
+				let floor_dir = entity.gravity_dir;
+				if blocked && around.ahead {
+					// The wall ahead becomes the floor, and it climbs.
+					entity.gravity_dir = walking;
+					entity.orientation = floor_dir.reverse();
+					entity.velocity = Vector3::zero();
+				} else if around.floor == false && around.floor_behind {
+					// Just past a ledge, so it goes down the far side,
+					// flush against it.
+					entity.gravity_dir = walking.reverse();
+					entity.orientation = floor_dir;
+					entity.velocity = Vector3::zero();
+					let edge = around.back_edge + CONTACT_PROBE;
+					if wx != 0.0 {
+						entity.position.x = edge * wx;
+					} else {
+						entity.position.y = edge * wy;
+					}
+				}
+			}
+		}
+
+		let speed = self.physics.walk_acceleration
+			* WALKER_SPEED
+			* self.speed_multiplier(id)
+			* self.ticks_per_step as Scalar;
+		let entity = self.get_entity_mut(id).unwrap();
+		let (dx, dy) = entity.orientation.unit();
+		entity.velocity.x += dx * speed;
+		entity.velocity.y += dy * speed;
+	}
+
+	fn walker_surroundings(&self, id: EntityId) -> Surroundings {
+		let entity = self.get_entity(id).unwrap();
+		let (wx, wy) = entity.orientation.unit();
+		let (gx, gy) = entity.gravity_dir.unit();
+
+		// Backed off from tile edges it may be touching, such as the wall
+		// it just stopped at or the floor it stands on.
+		let mut probe = entity.position;
+		probe.x -= (wx + gx) * CONTACT_PROBE;
+		probe.y -= (wy + gy) * CONTACT_PROBE;
+		let (x, y) = self.tile_index_at_position(probe);
+		let frame_id = probe.frame_id;
+		// Nothing is there past an unlinked edge.
+		let solid = |dx: Scalar, dy: Scalar| {
+			let index = self.normalize_tile_index(
+				frame_id,
+				x + dx as isize,
+				y + dy as isize,
+			);
+			match index {
+				Some((frame, tx, ty)) => self
+					.get_frame(frame)
+					.is_some_and(|frame| frame.is_solid(tx, ty)),
+				None => false,
+			}
+		};
+
+		// Tile coordinates along the walking axis, flipped so that they
+		// increase in the walking direction.
+		let (along, index) = if wx != 0.0 {
+			(probe.x * wx, x)
+		} else {
+			(probe.y * wy, y)
+		};
+		let tiles = FRAME_WIDTH as Scalar / 2.0;
+		let step = if wx + wy > 0.0 { 0 } else { 1 };
+		let back_index = index + step;
+		let back_edge = (-1.0 + back_index as Scalar / tiles) * (wx + wy);
+		let progress = ((along - back_edge) / TILE_SIZE).clamp(0.0, 1.0);
+
+		// Whether there is floor under the tile `side` tiles along the
+		// walking axis, which may be past an edge that turns gravity.
+		let floor_at = |side: Scalar| {
+			let mut beside = probe;
+			beside.x += wx * side * TILE_SIZE;
+			beside.y += wy * side * TILE_SIZE;
+			self.ground_below(beside, entity.gravity_dir, 1).is_some()
+		};
+
+		Surroundings {
+			floor: floor_at(0.0),
+			ahead: solid(wx, wy),
+			floor_ahead: floor_at(1.0),
+			floor_behind: floor_at(-1.0),
+			progress,
+			back_edge,
+		}
+	}
+}
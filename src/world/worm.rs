@@ -0,0 +1,226 @@
+use super::types::*;
+use super::{
+	CollisionChannels, EdgeBehavior, Entity, EntityKind, Persistence, World,
+	TILE_SIZE,
+};
+use crate::geometry::Scalar;
+
+// Parts that follow a worm's head unless a level says otherwise.
+pub const WORM_SEGMENTS: usize = 4;
+// How far along the head's path each part keeps behind the one before it.
+const WORM_SPACING: Scalar = TILE_SIZE * 0.6;
+// How close the player has to be to a part to touch it.
+const WORM_CONTACT_RADIUS: Scalar = TILE_SIZE * 0.6;
+// Dealt to the player by the head, and to the worm by the player at its
+// tail.
+const WORM_BITE_DAMAGE: Scalar = 2.0;
+const WORM_TAIL_DAMAGE: Scalar = MAX_WORM_HEALTH / 2.0;
+const MAX_WORM_HEALTH: Scalar = 4.0;
+
+// Somewhere a worm part has been, for the part behind it to go through in
+// turn.
+#[derive(Copy, Clone, Debug)]
+pub struct TrailPoint {
+	pub position: WorldPosition,
+	pub gravity_dir: Direction,
+	pub grounded: bool,
+	// Along the surface from the point before.
+	pub distance: Scalar,
+}
+
+impl Entity {
+	// Crawls along whatever it is on like a walker that follows the
+	// surface, with its segments trailing behind through every place it
+	// goes.
+	pub fn new_worm(world: &mut World, position: WorldPosition) -> Self {
+		let walker =
+			Entity::new_walker(world, position, EdgeBehavior::FollowSurface);
+		Self {
+			kind: EntityKind::Worm,
+			health: MAX_WORM_HEALTH,
+			persistence: Persistence::default_for(EntityKind::Worm),
+			channels: CollisionChannels::default_for(EntityKind::Worm),
+			..walker
+		}
+	}
+
+	// Follows `parent`, a worm's head or another of its segments.
+	pub fn new_worm_segment(
+		world: &mut World,
+		parent: EntityId,
+		position: WorldPosition,
+	) -> Self {
+		let walker =
+			Entity::new_walker(world, position, EdgeBehavior::default());
+		Self {
+			kind: EntityKind::WormSegment,
+			health: MAX_WORM_HEALTH,
+			persistence: Persistence::default_for(EntityKind::WormSegment),
+			channels: CollisionChannels::default_for(EntityKind::WormSegment),
+			parent: Some(parent),
+			..walker
+		}
+	}
+
+	fn is_worm_part(&self) -> bool {
+		match self.kind {
+			EntityKind::Worm | EntityKind::WormSegment => true,
+			_ => false,
+		}
+	}
+}
+
+fn same_place(a: WorldPosition, b: WorldPosition) -> bool {
+	a.frame_id == b.frame_id && a.x == b.x && a.y == b.y
+}
+
+impl World {
+	// Adds `count` segments behind a worm's last part, all where that part
+	// is, to spread out once it moves. Each takes the part's tag and
+	// persistence.
+	pub fn grow_worm(&mut self, id: EntityId, count: usize) {
+		let mut last = id;
+		while let Some(follower) = self.worm_follower(last) {
+			last = follower;
+		}
+		let part = match self.get_entity(last) {
+			Some(part) if part.is_worm_part() => part,
+			_ => return,
+		};
+		let (position, tag, persistence) =
+			(part.position, part.tag, part.persistence);
+		for _ in 0..count {
+			let segment = Entity::new_worm_segment(self, last, position);
+			last = self.insert_entity(Entity {
+				tag,
+				persistence,
+				..segment
+			});
+		}
+	}
+
+	// The segment that follows the entity, if any.
+	pub fn worm_follower(&self, id: EntityId) -> Option<EntityId> {
+		self.entity_ids().into_iter().find(|&other| {
+			self.get_entity(other)
+				.is_some_and(|entity| entity.parent == Some(id))
+		})
+	}
+
+	// A worm is only ever hurt at its tail, which is its head once it has
+	// lost every segment.
+	pub(super) fn is_shielded(&self, id: EntityId) -> bool {
+		match self.get_entity(id) {
+			Some(entity) if entity.is_worm_part() => {
+				self.worm_follower(id).is_some()
+			}
+			_ => false,
+		}
+	}
+
+	// Moves every segment up behind the part before it, head first, once
+	// the heads have moved for the tick. Segments left with nothing to
+	// follow are removed. Then the player, if there is one, is bitten by
+	// any head and bites any tail it is touching.
+	pub(super) fn update_worms(&mut self, player_id: Option<EntityId>) {
+		for id in self.entity_ids() {
+			let entity = match self.get_entity(id) {
+				Some(entity) => entity,
+				None => continue,
+			};
+			let orphaned = match entity.parent {
+				Some(parent) => self.get_entity(parent).is_none(),
+				None => false,
+			};
+			if entity.kind == EntityKind::WormSegment && orphaned {
+				self.remove_entity(id);
+				continue;
+			}
+			if entity.kind != EntityKind::Worm {
+				continue;
+			}
+			let mut leader = id;
+			while let Some(follower) = self.worm_follower(leader) {
+				self.follow_leader(follower, leader);
+				leader = follower;
+			}
+		}
+		if let Some(player_id) = player_id {
+			self.worm_contacts(player_id);
+		}
+	}
+
+	// Adds where the leader is now to its trail, and puts the follower on
+	// the trail as far back as the spacing, once the trail is that long.
+	// Whatever is further back than that is forgotten.
+	fn follow_leader(&mut self, follower: EntityId, leader: EntityId) {
+		let part = self.get_entity(leader).unwrap();
+		let (position, gravity_dir, grounded) =
+			(part.position, part.gravity_dir, part.grounded);
+		let last = part.trail.back().map(|point| point.position);
+		let moved = last.is_none_or(|last| same_place(last, position) == false);
+		if moved {
+			let distance = match last {
+				Some(last) => self.surface_distance(last, position),
+				None => 0.0,
+			};
+			let point = TrailPoint {
+				position,
+				gravity_dir,
+				grounded,
+				distance,
+			};
+			self.get_entity_mut(leader).unwrap().trail.push_back(point);
+		}
+
+		let trail = &mut self.get_entity_mut(leader).unwrap().trail;
+		let mut behind = 0.0;
+		let mut target = None;
+		for i in (0..trail.len()).rev() {
+			if behind >= WORM_SPACING {
+				target = Some(i);
+				break;
+			}
+			behind += trail[i].distance;
+		}
+		let point = match target {
+			Some(i) => {
+				trail.drain(..i);
+				trail[0]
+			}
+			None => return,
+		};
+		self.move_entity_to(follower, point.position);
+		let segment = self.get_entity_mut(follower).unwrap();
+		segment.gravity_dir = point.gravity_dir;
+		segment.grounded = point.grounded;
+	}
+
+	fn worm_contacts(&mut self, player_id: EntityId) {
+		let player = match self.get_entity(player_id) {
+			Some(player) => player,
+			None => return,
+		};
+		let (position, channels) = (player.position, player.channels);
+		let touching: Vec<EntityId> = self
+			.entities_in_frame(position.frame_id)
+			.iter()
+			.copied()
+			.filter(|&id| {
+				let part = self.get_entity(id).unwrap();
+				part.is_worm_part()
+					&& channels.touches(&part.channels)
+					&& self.surface_distance(position, part.position)
+						< WORM_CONTACT_RADIUS
+			})
+			.collect();
+		for id in touching {
+			if self.get_entity(id).unwrap().kind == EntityKind::Worm {
+				self.hit_entity(player_id, WORM_BITE_DAMAGE);
+			}
+			if self.is_shielded(id) == false {
+				self.hit_entity(id, WORM_TAIL_DAMAGE);
+			}
+		}
+	}
+}
@@ -0,0 +1,87 @@
+// Frames far from the player waiting, with everything on them as it was,
+// until the player comes near again.
+
+mod common;
+
+use common::{frame, load, moving, player};
+use sdl2_1::geometry::Scalar;
+use sdl2_1::world::{Actions, EntityKind, FrameActivity, FrameId, Tile, World};
+
+// A cube with the player at the middle of the top face, 3, and the bottom
+// face, 4, as far from it as can be, with a walker on a floor there and
+// sand above it to fall.
+fn far_away() -> World {
+	let floor = "################";
+	let mut level = String::new();
+	for id in 0..6 {
+		level += &match id {
+			4 => frame(id, &[(5, "...:"), (13, floor), (14, floor)]),
+			_ => frame(id, &[]),
+		};
+	}
+	level += "link 0 up 3 down\nlink 0 left 1 right\nlink 0 right 2 left\n";
+	level += "link 0 down 4 up\nlink 5 up 3 up\nlink 5 right 1 left\n";
+	level += "link 5 left 2 right\nlink 5 down 4 down\nlink 1 up 3 left\n";
+	level += "link 1 down 4 left\nlink 2 up 3 right\nlink 2 down 4 right\n";
+	level += "spawn 3 0.0 0.0\n";
+	level += "walker 4 0.0 0.6 turn_at_ledge\n";
+	load(&level)
+}
+
+fn walker(world: &World) -> (FrameId, Scalar, Scalar) {
+	let id = world
+		.entity_ids()
+		.into_iter()
+		.find(|&id| world.get_entity(id).unwrap().kind == EntityKind::Walker)
+		.expect("the walker went missing");
+	let position = world.get_entity(id).unwrap().position;
+	(position.frame_id, position.x, position.y)
+}
+
+fn sand_row(world: &World) -> Option<isize> {
+	let frame = world.get_frame(FrameId(4)).unwrap();
+	(0..16).find(|&y| *frame.tile(3, y) == Tile::Sand)
+}
+
+#[test]
+fn only_the_players_frame_and_those_beside_it_are_active() {
+	let mut world = far_away();
+	world.tick(&Actions::none());
+	for id in 0..6 {
+		let expected = match id {
+			4 => FrameActivity::Dormant,
+			_ => FrameActivity::Active,
+		};
+		assert_eq!(world.frame_activity(FrameId(id)), expected, "{}", id);
+	}
+	assert_eq!(world.ticked_entity_count(), 1);
+}
+
+// Nothing is taken away for being far off, only left as it is.
+#[test]
+fn a_dormant_frame_waits_and_then_carries_on() {
+	let mut world = far_away();
+	let (walker_at, sand_at) = (walker(&world), sand_row(&world));
+	let entities = world.entity_count();
+	common::tick_for(&mut world, &Actions::none(), 120);
+	assert_eq!(walker(&world), walker_at);
+	assert_eq!(sand_row(&world), sand_at);
+	assert_eq!(world.entity_count(), entities);
+
+	// Down off the top face onto the one beside the bottom.
+	let mut ticks = 0;
+	while player(&world).position.frame_id != FrameId(0) {
+		assert!(ticks < 600, "never got off the top");
+		world.tick(&moving(0.0, 1.0));
+		ticks += 1;
+	}
+	// Worked out at the start of the next tick.
+	world.tick(&Actions::none());
+	assert_eq!(world.frame_activity(FrameId(4)), FrameActivity::Active);
+	common::tick_for(&mut world, &Actions::none(), 120);
+	let (frame, x, _) = walker(&world);
+	assert_eq!(frame, FrameId(4));
+	assert!(x != walker_at.1, "the walker never moved");
+	assert!(sand_row(&world) > sand_at, "the sand never fell");
+	assert_eq!(world.entity_count(), entities);
+}
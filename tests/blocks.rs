@@ -0,0 +1,75 @@
+// Pushing blocks around, ticked without a window.
+
+mod common;
+
+use sdl2_1::world::{EntityKind, World};
+
+// The frame and tile each block is in, in the order they were placed.
+fn blocks(world: &World) -> Vec<(usize, isize, isize)> {
+	let mut ids = world.entity_ids();
+	ids.sort_by_key(|id| id.0);
+	ids.iter()
+		.map(|&id| world.get_entity(id).unwrap())
+		.filter(|entity| entity.kind == EntityKind::Block)
+		.map(|entity| {
+			let (x, y) = world.tile_index_at_position(entity.position);
+			(entity.position.frame_id.0, x, y)
+		})
+		.collect()
+}
+
+// Walks the player right along the floor for long enough to push
+// whatever is ahead of them a few tiles.
+fn push_right(world: &mut World) {
+	common::tick_for(world, &common::moving(1.0, 1.0), 240);
+}
+
+// Frame 0 as in `common::floor_frame`, with `row` drawn in just above the
+// floor, where `block_at` gives the blocks.
+fn floor_with(row: &str, block_at: &[isize]) -> String {
+	let floor = "################";
+	let rows = [(12, row), (13, floor), (14, floor), (15, floor)];
+	let mut level = common::frame(0, &rows);
+	level += "spawn 0 -0.9 0.6\n";
+	for x in block_at {
+		level += &format!("block 0 {} 12\n", x);
+	}
+	level
+}
+
+#[test]
+fn a_block_is_pushed_along_the_floor() {
+	let mut world = common::load(&floor_with("", &[4]));
+	push_right(&mut world);
+	let pushed = blocks(&world);
+	assert!(pushed[0].1 > 6, "only pushed to {:?}", pushed);
+	assert_eq!(pushed[0].2, 12);
+}
+
+#[test]
+fn a_block_isnt_pushed_into_a_wall() {
+	let mut world = common::load(&floor_with(".....#", &[4]));
+	push_right(&mut world);
+	assert_eq!(blocks(&world), vec![(0, 4, 12)]);
+}
+
+#[test]
+fn a_block_isnt_pushed_into_another() {
+	let mut world = common::load(&floor_with("", &[4, 5]));
+	push_right(&mut world);
+	assert_eq!(blocks(&world), vec![(0, 4, 12), (0, 5, 12)]);
+}
+
+// Off the end of one frame and onto the start of the next.
+#[test]
+fn a_block_is_pushed_across_a_frame_edge() {
+	let mut level = floor_with("", &[14]);
+	level += &common::floor_frame(1);
+	level += "link 0 right 1 left\nlink 1 right 0 left\n";
+	level = level.replace("spawn 0 -0.9 0.6", "spawn 0 0.6 0.6");
+	let mut world = common::load(&level);
+	push_right(&mut world);
+	let pushed = blocks(&world);
+	assert_eq!(pushed[0].0, 1, "still on frame 0 at {:?}", pushed);
+	assert_eq!(pushed[0].2, 12);
+}
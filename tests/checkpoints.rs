@@ -0,0 +1,119 @@
+// Dying and coming back at the last checkpoint, with enemies put back and
+// collected coins left collected.
+
+mod common;
+
+use common::{floor_frame, load, moving, player};
+use sdl2_1::world::{
+	Direction, EntityKind, Tile, TileLocation, World, WorldPosition,
+};
+
+// The player walking right from column 4 takes a checkpoint at column 5,
+// then a coin at column 7, with a walker off at the far end and every
+// edit the player makes undone by dying.
+fn course() -> World {
+	let mut level = floor_frame(0);
+	level += "spawn 0 -0.4375 0.6\n";
+	level += "hook tile 0 5 13 checkpoint\n";
+	level += "coin 0 -0.0625 0.6\n";
+	level += "walker 0 0.8125 0.6 turn_at_ledge\n";
+	level += "edits death\n";
+	load(&level)
+}
+
+fn of_kind(world: &World, kind: EntityKind) -> usize {
+	let ids = world.entity_ids();
+	ids.iter()
+		.filter(|&&id| world.get_entity(id).unwrap().kind == kind)
+		.count()
+}
+
+fn walk_to(world: &mut World, column: isize) {
+	let mut ticks = 0;
+	while ((player(world).position.x + 1.0) * 8.0).floor() as isize != column {
+		assert!(ticks < 600, "never got to column {}", column);
+		world.tick(&moving(1.0, 1.0));
+		ticks += 1;
+	}
+}
+
+fn die(world: &mut World) {
+	let id = world.focus_entity.unwrap();
+	world.get_entity_mut(id).unwrap().health = 0.0;
+	world.tick(&moving(0.0, 1.0));
+}
+
+#[test]
+fn the_enemy_comes_back_and_the_coin_does_not() {
+	let mut world = course();
+	walk_to(&mut world, 6);
+	let checkpoint = world.checkpoint_position().expect("no checkpoint");
+	walk_to(&mut world, 8);
+	assert_eq!(of_kind(&world, EntityKind::Coin), 0);
+
+	let walker = world
+		.entity_ids()
+		.into_iter()
+		.find(|&id| world.get_entity(id).unwrap().kind == EntityKind::Walker);
+	world.get_entity_mut(walker.unwrap()).unwrap().health = 0.0;
+	world.tick(&moving(0.0, 1.0));
+	assert_eq!(of_kind(&world, EntityKind::Walker), 0);
+
+	die(&mut world);
+	let position = player(&world).position;
+	assert_eq!(position.frame_id, checkpoint.frame_id);
+	assert!((position.x - checkpoint.x).abs() < 0.01, "{:?}", position);
+	assert_eq!(of_kind(&world, EntityKind::Coin), 0);
+	assert_eq!(of_kind(&world, EntityKind::Walker), 1);
+	// With the same id, for anything that was keeping track of it.
+	assert!(world.get_entity(walker.unwrap()).is_some());
+}
+
+// Places the tile the player points at, or clears it if it is already
+// there, giving where it was and what it was before.
+fn toggle(world: &mut World) -> (TileLocation, Tile) {
+	let id = world.focus_entity.unwrap();
+	let target = world.edit_target(id).unwrap();
+	let was = tile(world, target);
+	let edited = match was {
+		Tile::Empty => world.try_place_tile(id),
+		_ => world.try_remove_tile(id),
+	};
+	assert_eq!(edited, Ok(()));
+	assert!(tile(world, target) != was);
+	(target, was)
+}
+
+fn tile(world: &World, (frame, x, y): TileLocation) -> Tile {
+	*world.get_frame(frame).unwrap().tile(x, y)
+}
+
+#[test]
+fn dying_undoes_edits_since_the_checkpoint_only() {
+	let mut world = course();
+	let id = world.focus_entity.unwrap();
+	world.get_entity_mut(id).unwrap().aim = Direction::Left;
+	let (before, _) = toggle(&mut world);
+	world.get_entity_mut(id).unwrap().aim = Direction::Neutral;
+	walk_to(&mut world, 7);
+	let (after, was) = toggle(&mut world);
+	let edited_before = tile(&world, before);
+
+	die(&mut world);
+	assert_eq!(tile(&world, before), edited_before);
+	assert_eq!(tile(&world, after), was);
+}
+
+#[test]
+fn dying_twice_comes_back_to_the_same_checkpoint() {
+	let mut world = course();
+	walk_to(&mut world, 8);
+	let checkpoint = world.checkpoint_position().unwrap();
+	die(&mut world);
+	walk_to(&mut world, 8);
+	die(&mut world);
+	let position = world.checkpoint_position().unwrap();
+	let at = |position: WorldPosition| (position.frame_id, position.x);
+	assert_eq!(at(position), at(checkpoint));
+	assert_eq!(of_kind(&world, EntityKind::Coin), 0);
+}
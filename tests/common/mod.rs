@@ -0,0 +1,58 @@
+// Helpers for building small levels to tick in tests.
+
+#![allow(dead_code)]
+
+use sdl2_1::geometry::Scalar;
+use sdl2_1::world::{Actions, Entity, World, FRAME_WIDTH};
+
+// A `frame` statement with the given rows drawn in, by their index from
+// the top, and every other tile empty. Rows shorter than the frame are
+// filled out with empty tiles.
+pub fn frame(id: usize, rows: &[(usize, &str)]) -> String {
+	let mut level = format!("frame {}\n", id);
+	for y in 0..FRAME_WIDTH {
+		let row = rows
+			.iter()
+			.find(|&&(row_y, _)| row_y == y)
+			.map_or("", |&(_, row)| row);
+		let row: String = row
+			.chars()
+			.chain(std::iter::repeat('.'))
+			.take(FRAME_WIDTH)
+			.collect();
+		level += &row;
+		level += "\n";
+	}
+	level
+}
+
+// A frame with a floor along its bottom three rows.
+pub fn floor_frame(id: usize) -> String {
+	let floor = "################";
+	frame(id, &[(13, floor), (14, floor), (15, floor)])
+}
+
+pub fn load(source: &str) -> World {
+	World::from_level_str(source)
+		.unwrap_or_else(|error| panic!("bad test level: {}\n{}", error, source))
+}
+
+pub fn player(world: &World) -> &Entity {
+	world.get_entity(world.focus_entity.unwrap()).unwrap()
+}
+
+// Holds the movement keys the given way. There is no gravity, so holding
+// down is what keeps the player on the floor.
+pub fn moving(move_x: Scalar, move_y: Scalar) -> Actions {
+	Actions {
+		move_x,
+		move_y,
+		..Actions::none()
+	}
+}
+
+pub fn tick_for(world: &mut World, actions: &Actions, ticks: usize) {
+	for _ in 0..ticks {
+		world.tick(actions);
+	}
+}
@@ -0,0 +1,158 @@
+use sdl2_1::geometry::{vec3, Scalar, Vector3};
+use sdl2_1::world::{Direction, FrameId, World, WorldPosition};
+
+// Positions crossing every edge and corner of each cube, checked against
+// where they are in cube space. Crossing an edge should carry on the same
+// way around the cube, whichever face it starts from, and past a corner
+// the axis overshot the most should win the same way on every face.
+
+const EDGES: [Direction; 4] = [
+	Direction::Up,
+	Direction::Down,
+	Direction::Left,
+	Direction::Right,
+];
+const HOW_FAR_PAST: Scalar = 0.01;
+// Of approaches into a corner, from along one edge towards the diagonal.
+const CORNER_ANGLES: [Scalar; 4] = [5.0, 20.0, 35.0, 44.0];
+const CORNER_DISTANCE: Scalar = 0.05;
+const TOLERANCE: Scalar = 1e-4;
+
+fn cubes() -> Vec<(&'static str, World)> {
+	let mut cubes = vec![("generated", World::new())];
+	for path in [
+		"levels/tutorial.lvl",
+		"levels/campaign/1_first_steps.lvl",
+		"levels/campaign/2_walls.lvl",
+		"levels/campaign/3_upside_down.lvl",
+	] {
+		let source = std::fs::read_to_string(path).unwrap();
+		cubes.push((path, World::from_level_str(&source).unwrap()));
+	}
+	cubes
+}
+
+fn at(frame_id: FrameId, x: Scalar, y: Scalar) -> WorldPosition {
+	WorldPosition { frame_id, x, y }
+}
+
+// In cube space, with `focus` as the front face.
+fn point(world: &World, focus: FrameId, position: WorldPosition) -> Vector3 {
+	world.surface_point(focus, position).unwrap_or_else(|| {
+		panic!("frame {:?} isn't near {:?}", position.frame_id, focus)
+	})
+}
+
+#[test]
+fn crossing_any_edge_carries_on_around_the_cube() {
+	for (name, world) in cubes() {
+		// The same face in front throughout, so that the faces either side
+		// of a link are placed by way of other links as well.
+		let focus = world.frame_ids()[0];
+		for frame_id in world.frame_ids() {
+			for &edge in EDGES.iter() {
+				let (out_x, out_y) = edge.unit();
+				let (along_x, along_y) = (out_y.abs(), out_x.abs());
+				for i in 0..9 {
+					let along = -0.8 + 0.2 * i as Scalar;
+					let on_edge = at(
+						frame_id,
+						out_x + along_x * along,
+						out_y + along_y * along,
+					);
+					let past = at(
+						frame_id,
+						on_edge.x + out_x * HOW_FAR_PAST,
+						on_edge.y + out_y * HOW_FAR_PAST,
+					);
+					let crossed = past.normalize(&world).unwrap();
+					let context = format!(
+						"{}: {:?} {:?} at {}, crossed into {:?} ({}, {})",
+						name,
+						frame_id,
+						edge,
+						along,
+						crossed.frame_id,
+						crossed.x,
+						crossed.y
+					);
+					assert!(crossed.frame_id != frame_id, "{}", context);
+					let from = point(&world, focus, on_edge);
+					let to = point(&world, focus, crossed);
+					let distance = (to - from).len();
+					assert!(
+						(distance - HOW_FAR_PAST).abs() < TOLERANCE,
+						"{}: {} from the edge",
+						context,
+						distance
+					);
+				}
+			}
+		}
+	}
+}
+
+// Every corner of every face, so each of the eight cube corners from all
+// three faces around it, at angles either side of the diagonal. Swapping
+// the overshoots mirrors the approach about the plane through the corner,
+// the diagonal and the middle of the cube, which should mirror where it
+// ends up too.
+#[test]
+fn crossing_near_a_corner_is_the_same_either_side_of_the_diagonal() {
+	for (name, world) in cubes() {
+		for frame_id in world.frame_ids() {
+			for &(sx, sy) in
+				[(-1.0, -1.0), (-1.0, 1.0), (1.0, -1.0), (1.0, 1.0)].iter()
+			{
+				let (sx, sy): (Scalar, Scalar) = (sx, sy);
+				let corner = |x: Scalar, y: Scalar| {
+					at(frame_id, sx + sx * x, sy + sy * y)
+				};
+				let mirrored =
+					|p: Vector3| vec3(sx * sy * p.y, sx * sy * p.x, p.z);
+				for &angle in CORNER_ANGLES.iter() {
+					let radians = angle.to_radians();
+					let (a, b) = (
+						CORNER_DISTANCE * radians.cos(),
+						CORNER_DISTANCE * radians.sin(),
+					);
+					let one = corner(a, b).normalize(&world).unwrap();
+					let other = corner(b, a).normalize(&world).unwrap();
+					let context = format!(
+						"{}: {:?} corner ({}, {}) at {} degrees, \
+						ended on {:?} ({}, {}) and {:?} ({}, {})",
+						name,
+						frame_id,
+						sx,
+						sy,
+						angle,
+						one.frame_id,
+						one.x,
+						one.y,
+						other.frame_id,
+						other.x,
+						other.y
+					);
+					assert!(one.frame_id != frame_id, "{}", context);
+					assert!(other.frame_id != one.frame_id, "{}", context);
+					let one = point(&world, frame_id, one);
+					let other = point(&world, frame_id, other);
+					let corner = vec3(sx, sy, 1.0);
+					assert!(
+						(one - corner).len() <= 2.0 * CORNER_DISTANCE,
+						"{}: ended {} from the corner",
+						context,
+						(one - corner).len()
+					);
+					assert!(
+						(mirrored(one) - other).len() < TOLERANCE,
+						"{}: {:?} isn't the mirror of {:?}",
+						context,
+						one,
+						other
+					);
+				}
+			}
+		}
+	}
+}
@@ -0,0 +1,29 @@
+// Plays the menu's built-in demo with no window, as the game would, and
+// checks the world ends up with the state hash written down in the demo.
+// The demo is a replay, so this is a check that replays still play back
+// exactly. A new compiler can change the hash on its own, as the hasher
+// isn't fixed across versions; the hash it ended on is then the one to
+// write down as `DEMO_HASH` in src/campaign.rs instead.
+
+use sdl2_1::world::Actions;
+use sdl2_1::GameState;
+
+#[test]
+fn the_demo_ends_on_its_hash() {
+	let mut game_state = GameState::demo().unwrap();
+	let expected = game_state
+		.demo
+		.as_ref()
+		.and_then(|playback| playback.replay.hash)
+		.expect("the demo has no hash to check against");
+	while game_state.demo_finished() == false {
+		game_state.tick(&Actions::none());
+	}
+
+	let hash = game_state.world.state_hash();
+	assert_eq!(
+		hash, expected,
+		"tick {}: ended on {:016x}, not {:016x}",
+		game_state.world.tick_count, hash, expected
+	);
+}
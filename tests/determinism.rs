@@ -0,0 +1,60 @@
+// The simulation steps the same way on every build, given the same input.
+
+mod common;
+
+use sdl2_1::world::{determinism_probe, World, PROBE_TICKS};
+
+// The probe's final state hash, for each precision the world can be built
+// in. A change to how the world steps changes these on purpose, and they
+// are checked in again from
+//
+//   cargo run --example determinism_probe
+//
+// with and without `--features f64`. Any other change to them is the
+// simulation behaving differently from one build to the next.
+#[cfg(not(feature = "f64"))]
+const PROBE_HASH: u64 = 0x33b2_9716_2b4f_6a66;
+#[cfg(feature = "f64")]
+const PROBE_HASH: u64 = 0xee16_f416_9371_bb5a;
+
+#[test]
+fn the_probe_ends_on_the_checked_in_hash() {
+	let hashes = determinism_probe();
+	let &(tick, hash) = hashes.last().unwrap();
+	assert_eq!(tick, PROBE_TICKS);
+	assert_eq!(hash, PROBE_HASH, "ended on {:016x}", hash);
+}
+
+// Five walkers pacing a walled floor, into each other and the player, who
+// walks back and forth among them.
+fn crowded_floor() -> World {
+	let wall = "#..............#";
+	let mut rows = vec![(12, wall), (11, wall), (10, wall)];
+	let floor = "################";
+	rows.extend(&[(13, floor), (14, floor), (15, floor)]);
+	let mut level = common::frame(0, &rows);
+	level += "spawn 0 0.0 0.6\n";
+	for &x in &[-0.7, -0.4, -0.1, 0.3, 0.6] {
+		level += &format!("walker 0 {} 0.6\n", x);
+	}
+	common::load(&level)
+}
+
+fn run_crowded_floor() -> u64 {
+	let mut world = crowded_floor();
+	for tick in 0..1000 {
+		let move_x = if tick / 150 % 2 == 0 { 1.0 } else { -1.0 };
+		world.tick(&common::moving(move_x, 1.0));
+	}
+	world.state_hash()
+}
+
+#[test]
+fn walkers_step_the_same_way_every_run() {
+	assert_eq!(crowded_floor().entity_count(), 6);
+	let first = run_crowded_floor();
+	assert_ne!(first, crowded_floor().state_hash(), "nothing moved");
+	for _ in 0..4 {
+		assert_eq!(run_crowded_floor(), first);
+	}
+}
@@ -0,0 +1,37 @@
+// Runs the determinism probe in the wasm build under Node, printing the
+// same hash lines as `cargo run --example determinism_probe` so that the
+// two outputs can be diffed:
+//
+//   node tests/determinism_probe.js target/wasm32-unknown-unknown/release/sdl2_1.wasm
+//
+// Nothing is drawn, so every import but the log is a stub.
+
+const fs = require('fs');
+
+async function main() {
+	const path = process.argv[2];
+	if (path === undefined) {
+		console.error('usage: node determinism_probe.js <sdl2_1.wasm>');
+		process.exit(2);
+	}
+
+	let memory = null;
+	const env = new Proxy({}, {
+		get: (_, name) => {
+			if (name === 'console_log') {
+				return (ptr, len) => {
+					const bytes = new Uint8Array(memory.buffer, ptr, len);
+					console.log(new TextDecoder('utf-8').decode(bytes));
+				};
+			}
+			return () => 0;
+		},
+	});
+	const { instance } =
+		await WebAssembly.instantiate(fs.readFileSync(path), { env });
+	memory = instance.exports.memory;
+
+	instance.exports.run_probe();
+}
+
+main();
@@ -0,0 +1,267 @@
+// Editing tiles in a world ticked without a window.
+
+mod common;
+
+use sdl2_1::geometry::Scalar;
+use sdl2_1::world::{
+	Actions, Angle, Direction, EditError, EditHistory, FrameId, SetTileError,
+	Tile, TileData, World, WorldEvent, FRAME_WIDTH, TILE_SIZE,
+};
+
+// The player floating in the middle of the frame, well inside the tile at
+// column 4, row 10, nearer its top than its bottom.
+fn floating_player() -> World {
+	let mut level = common::floor_frame(0);
+	level += "spawn 0 -0.4375 0.3\n";
+	common::load(&level)
+}
+
+fn player_at(world: &World) -> (FrameId, Scalar, Scalar) {
+	let position = common::player(world).position;
+	(position.frame_id, position.x, position.y)
+}
+
+#[test]
+fn a_tile_is_placed_beside_and_under_the_player() {
+	let mut world = floating_player();
+	let before = player_at(&world);
+	for &(x, y) in &[(5, 10), (3, 10), (4, 11), (4, 9)] {
+		assert_eq!(world.set_tile(FrameId(0), x, y, Tile::Solid), Ok(()));
+	}
+	assert_eq!(player_at(&world), before);
+}
+
+#[test]
+fn a_tile_isnt_placed_on_the_player() {
+	let mut world = floating_player();
+	let id = world.focus_entity.unwrap();
+	let before = player_at(&world);
+	assert_eq!(
+		world.set_tile(FrameId(0), 4, 10, Tile::Solid),
+		Err(SetTileError::Occupied(id))
+	);
+	let frame = world.get_frame(FrameId(0)).unwrap();
+	assert_eq!(*frame.tile(4, 10), Tile::Empty);
+	assert_eq!(player_at(&world), before);
+
+	// Nothing solid, nothing in the way.
+	assert_eq!(world.set_tile(FrameId(0), 4, 10, Tile::Sign), Ok(()));
+}
+
+// Pushed out through the nearest side, the top, to stand on the tile.
+#[test]
+fn a_tile_placed_on_the_player_can_push_them_out() {
+	let mut world = floating_player();
+	world.physics.push_out_of_placed_tiles = true;
+	assert_eq!(world.set_tile(FrameId(0), 4, 10, Tile::Solid), Ok(()));
+
+	let frame = world.get_frame(FrameId(0)).unwrap();
+	assert_eq!(*frame.tile(4, 10), Tile::Solid);
+	let position = common::player(&world).position;
+	assert_eq!(position.frame_id, FrameId(0));
+	assert_eq!(position.x, -0.4375);
+	assert!((position.y - (-1.0 + 10.0 * TILE_SIZE)).abs() < 1e-6);
+}
+
+// Every tile of every frame, all there is to it.
+fn tiles(world: &World) -> Vec<(Tile, Angle, Option<TileData>)> {
+	let w = FRAME_WIDTH as isize;
+	let mut tiles = Vec::new();
+	for frame_id in world.frame_ids() {
+		let frame = world.get_frame(frame_id).unwrap();
+		for y in 0..w {
+			for x in 0..w {
+				let data = frame.tile_data(x, y).cloned();
+				tiles.push((*frame.tile(x, y), frame.rotation(x, y), data));
+			}
+		}
+	}
+	tiles
+}
+
+// Edits over a written sign and a turned ramp, some of them to the same
+// tiles more than once, one a drag of several tiles, and the last still
+// being made when the scrubbing starts.
+#[test]
+fn scrubbing_to_the_start_and_back_puts_every_tile_back() {
+	let mut level =
+		common::frame(0, &[(11, "..?..../........"), (13, "################")]);
+	level += &common::floor_frame(1);
+	level += "link 0 right 1 left\nspawn 0 0.8 0.6\nsign 0 2 11 0\n";
+	level += "rotate 0 7 11 1\nstrings\n0 Read me.\n";
+	let mut world = common::load(&level);
+	let start = tiles(&world);
+	let (a, b) = (FrameId(0), FrameId(1));
+	let frame = world.get_frame(a).unwrap();
+	assert!(frame.tile_data(2, 11).is_some());
+	assert!(frame.rotation(7, 11) != Angle::Clockwise0);
+
+	let mut history = EditHistory::new();
+	let edits: [&[(FrameId, isize, isize, Tile)]; 4] = [
+		&[(a, 2, 11, Tile::Solid)],
+		&[
+			(a, 3, 5, Tile::Sand),
+			(a, 4, 5, Tile::Sand),
+			(a, 5, 5, Tile::Sand),
+		],
+		&[(a, 7, 11, Tile::Empty), (b, 0, 13, Tile::Empty)],
+		&[(a, 2, 11, Tile::Empty), (a, 4, 5, Tile::Solid)],
+	];
+	for (i, edit) in edits.iter().enumerate() {
+		for &(frame_id, x, y, tile) in edit.iter() {
+			history.set_tile(&mut world, frame_id, x, y, tile).unwrap();
+		}
+		if i < edits.len() - 1 {
+			history.finish();
+		}
+	}
+	history.fill_rect(&mut world, b, (3, 2), (6, 4), Tile::Solid);
+	assert!(history.is_editing());
+	let end = tiles(&world);
+	assert!(end != start);
+
+	assert_eq!(history.seek(&mut world, 0), 0);
+	assert_eq!(history.len(), edits.len());
+	assert_eq!(history.cursor(), 0);
+	assert!(tiles(&world) == start, "not as it was at the start");
+	history.seek(&mut world, history.len());
+	assert!(tiles(&world) == end, "not as it was at the end");
+
+	// A step at a time, back and forth, ends up the same.
+	for cursor in (0..history.len()).rev().chain(1..=history.len()) {
+		history.seek(&mut world, cursor);
+	}
+	assert!(tiles(&world) == end, "not as it was after stepping");
+}
+
+fn tile(world: &World, frame: usize, x: isize, y: isize) -> Tile {
+	*world.get_frame(FrameId(frame)).unwrap().tile(x, y)
+}
+
+fn placing(aim: Direction) -> Actions {
+	Actions {
+		interact: true,
+		aim,
+		..Actions::none()
+	}
+}
+
+// The tile a refused edit was aimed at, if there was one to aim at.
+type Target = Option<(FrameId, usize, usize)>;
+
+fn refusal(world: &World) -> Option<(Target, EditError)> {
+	world.events().iter().find_map(|event| match *event {
+		WorldEvent::TileEditFailed { target, error, .. } => {
+			Some((target, error))
+		}
+		_ => None,
+	})
+}
+
+#[test]
+fn edits_go_the_way_the_player_faces() {
+	let mut world = floating_player();
+	world.tick(&placing(Direction::Neutral));
+	assert_eq!(tile(&world, 0, 5, 10), Tile::Solid);
+
+	world.tick(&common::moving(-0.1, 0.0));
+	assert_eq!(common::player(&world).orientation, Direction::Left);
+	world.tick(&placing(Direction::Neutral));
+	assert_eq!(tile(&world, 0, 3, 10), Tile::Solid);
+
+	let removing = Actions {
+		remove: true,
+		..Actions::none()
+	};
+	world.tick(&removing);
+	assert_eq!(tile(&world, 0, 3, 10), Tile::Empty);
+	assert_eq!(tile(&world, 0, 5, 10), Tile::Solid);
+}
+
+#[test]
+fn aiming_outweighs_facing() {
+	let mut world = floating_player();
+	world.tick(&placing(Direction::Up));
+	assert_eq!(tile(&world, 0, 4, 9), Tile::Solid);
+	assert_eq!(tile(&world, 0, 5, 10), Tile::Empty);
+	world.tick(&placing(Direction::Down));
+	assert_eq!(tile(&world, 0, 4, 11), Tile::Solid);
+}
+
+#[test]
+fn an_edit_that_changes_nothing_is_refused_and_says_why() {
+	let mut world = floating_player();
+	let id = world.focus_entity.unwrap();
+	world.set_tile(FrameId(0), 5, 10, Tile::Solid).unwrap();
+	world.tick(&placing(Direction::Neutral));
+	let (target, error) = refusal(&world).unwrap();
+	assert_eq!(target, Some((FrameId(0), 5, 10)));
+	assert_eq!(error, EditError::AlreadyThatTile);
+	assert_eq!(error.describe(), "nothing to change");
+
+	assert_eq!(world.try_remove_tile(id), Ok(()));
+	assert_eq!(world.try_remove_tile(id), Err(EditError::AlreadyThatTile));
+
+	world.set_tile(FrameId(0), 5, 10, Tile::Solid).unwrap();
+	assert_eq!(world.try_rotate_tile(id), Err(EditError::NotDirectional));
+	assert_eq!(EditError::NotDirectional.describe(), "that doesn't turn");
+}
+
+#[test]
+fn a_tile_isnt_placed_on_someone_else() {
+	let mut level = common::floor_frame(0);
+	level += "spawn 0 -0.4375 0.3\nwalker 0 -0.3125 0.3\n";
+	let mut world = common::load(&level);
+	let id = world.focus_entity.unwrap();
+	let error = world.try_place_tile(id).unwrap_err();
+	let walker = match error {
+		EditError::Occupied(walker) => walker,
+		_ => panic!("placed on the walker: {:?}", error),
+	};
+	assert!(walker != id);
+	assert_eq!(error.describe(), "something is in the way");
+	assert_eq!(tile(&world, 0, 5, 10), Tile::Empty);
+}
+
+#[test]
+fn nothing_past_an_unlinked_edge_is_edited() {
+	let mut level = common::floor_frame(0);
+	level += "spawn 0 0.9375 0.3\n";
+	let mut world = common::load(&level);
+	world.tick(&placing(Direction::Right));
+	let (target, error) = refusal(&world).unwrap();
+	assert_eq!((target, error), (None, EditError::Unreachable));
+	assert_eq!(error.describe(), "nothing there to edit");
+}
+
+// The right edge of frame 0 leads into the top of frame 1, turned a
+// quarter, so the tile past it is whichever one going over lands in.
+#[test]
+fn an_edit_over_a_turned_edge_lands_where_walking_over_would() {
+	let turned = || {
+		let mut level = common::floor_frame(0) + &common::frame(1, &[]);
+		level += "link 0 right 1 up\nspawn 0 0.9375 0.3\n";
+		common::load(&level)
+	};
+
+	let mut world = turned();
+	let mut ticks = 0;
+	while common::player(&world).position.frame_id == FrameId(0) {
+		assert!(ticks < 200, "never went over the edge");
+		world.tick(&common::moving(1.0, 0.0));
+		ticks += 1;
+	}
+	let position = common::player(&world).position;
+	let arrived = (((position.x + 1.0) / TILE_SIZE).floor() as isize, 0);
+
+	let mut world = turned();
+	let id = world.focus_entity.unwrap();
+	assert_eq!(world.try_place_tile(id), Ok(()));
+	let w = FRAME_WIDTH as isize;
+	let placed: Vec<_> = (0..w)
+		.flat_map(|y| (0..w).map(move |x| (x, y)))
+		.filter(|&(x, y)| tile(&world, 1, x, y) == Tile::Solid)
+		.collect();
+	assert_eq!(placed, vec![arrived]);
+	assert_eq!(tile(&world, 0, 15, 10), Tile::Empty);
+}
@@ -0,0 +1,112 @@
+// Drives generated worlds with random input for a long time, checking the
+// world's invariants after every tick, then replays the same input on a
+// fresh copy of each world to check it comes out the same.
+
+use sdl2_1::world::{dump_difference, Actions, Direction, Rng, World};
+
+const SEEDS: [u64; 3] = [1, 2, 3];
+const TICKS: usize = 10_000;
+// Input is held for up to this many ticks before changing, as a player's
+// would be, so that movement has time to build up speed and hit things.
+const MAX_HOLD_TICKS: isize = 40;
+const AIMS: [Direction; 5] = [
+	Direction::Neutral,
+	Direction::Up,
+	Direction::Down,
+	Direction::Left,
+	Direction::Right,
+];
+
+// Stands in for the window's input handling: hands the world one set of
+// actions a tick, made up from its own seed.
+struct MockInput {
+	rng: Rng,
+	held: Actions,
+	ticks_left: isize,
+}
+
+impl MockInput {
+	fn new(seed: u64) -> Self {
+		Self {
+			rng: Rng::new(seed),
+			held: Actions::none(),
+			ticks_left: 0,
+		}
+	}
+
+	fn actions(&mut self) -> Actions {
+		if self.ticks_left <= 0 {
+			self.ticks_left = self.rng.range_i(1, MAX_HOLD_TICKS + 1);
+			let axis = [-1.0, 0.0, 1.0];
+			self.held = Actions {
+				move_x: *self.rng.pick(&axis).unwrap(),
+				move_y: *self.rng.pick(&axis).unwrap(),
+				jump: false,
+				jump_held: self.rng.chance(0.5),
+				interact: false,
+				remove: false,
+				rotate: false,
+				sprint: self.rng.chance(0.3),
+				dash: false,
+				aim: *self.rng.pick(&AIMS).unwrap(),
+			};
+		}
+		self.ticks_left -= 1;
+
+		// Presses are only ever for one tick.
+		let mut actions = self.held;
+		actions.jump = self.rng.chance(0.05);
+		actions.interact = self.rng.chance(0.02);
+		actions.remove = self.rng.chance(0.02);
+		actions.rotate = self.rng.chance(0.02);
+		actions.dash = self.rng.chance(0.03);
+		actions
+	}
+}
+
+// The world after playing each action in turn, or the tick and what was
+// wrong at the first tick that broke an invariant.
+fn play(seed: u64, actions: &[Actions]) -> Result<World, String> {
+	let mut world = World::with_seed(seed);
+	for (tick, actions) in actions.iter().enumerate() {
+		world.tick(actions);
+		let violations = world.invariant_violations();
+		if violations.is_empty() == false {
+			let described: Vec<String> =
+				violations.iter().map(|v| v.to_string()).collect();
+			return Err(format!("tick {}: {}", tick, described.join("; ")));
+		}
+	}
+	Ok(world)
+}
+
+// Plays the actions twice over, since the same seed and input should always
+// give the same world. Where they differ is told from their state dumps,
+// which the hash alone can't say.
+fn replayed(seed: u64, actions: &[Actions]) -> Result<u64, String> {
+	let world = play(seed, actions)?;
+	let replayed = play(seed, actions)?;
+	let (hash, replayed_hash) = (world.state_hash(), replayed.state_hash());
+	if replayed_hash != hash {
+		let difference =
+			dump_difference(&world.dump_state(), &replayed.dump_state())
+				.map_or("dumps are the same".to_string(), |d| d.to_string());
+		return Err(format!(
+			"replay hashed {:016x}, not {:016x}; {}",
+			replayed_hash, hash, difference
+		));
+	}
+	Ok(hash)
+}
+
+#[test]
+fn random_input_keeps_the_world_whole_and_replays_the_same() {
+	for &seed in SEEDS.iter() {
+		let mut input = MockInput::new(seed);
+		let actions: Vec<Actions> =
+			(0..TICKS).map(|_| input.actions()).collect();
+		if let Err(error) = replayed(seed, &actions) {
+			panic!("seed {}: {}", seed, error);
+		}
+	}
+}
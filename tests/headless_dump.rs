@@ -0,0 +1,61 @@
+// The game run from the command line without a window, as
+// `--headless-ticks` and `--dump-state` do.
+
+use std::process::Command;
+
+use sdl2_1::world::{DUMP_VERSION, FRAME_WIDTH};
+
+fn run(args: &[&str]) -> (bool, String) {
+	let output = Command::new(env!("CARGO_BIN_EXE_sdl2_1"))
+		.args(args)
+		.output()
+		.unwrap_or_else(|error| panic!("could not run the game: {}", error));
+	let stdout = String::from_utf8(output.stdout).unwrap();
+	(output.status.success(), stdout)
+}
+
+#[test]
+fn the_state_is_dumped_after_the_ticks() {
+	let (ok, dump) = run(&["--headless-ticks", "30", "--dump-state"]);
+	assert!(ok, "{}", dump);
+	let lines: Vec<&str> = dump.lines().collect();
+	assert_eq!(lines[0], format!("dump {}", DUMP_VERSION));
+	assert_eq!(lines[1], "tick 30");
+
+	// Every frame is followed by its rows of tiles, all of them whole.
+	let frames: Vec<usize> = (0..lines.len())
+		.filter(|&i| lines[i].starts_with("frame "))
+		.collect();
+	assert_eq!(frames.len(), 6);
+	for &i in frames.iter() {
+		let rows = &lines[i + 1..i + 1 + FRAME_WIDTH];
+		for row in rows {
+			let tiles = row.strip_prefix("row ").unwrap_or_else(|| {
+				panic!("{} is not a row of {}", row, lines[i])
+			});
+			assert_eq!(tiles.chars().count(), FRAME_WIDTH, "{}", row);
+		}
+	}
+	assert!(lines.iter().any(|line| line.starts_with("entity ")));
+	assert!(lines.iter().any(|line| line.starts_with("stat ")));
+
+	let (_, again) = run(&["--dump-state", "--headless-ticks", "30"]);
+	assert_eq!(dump, again);
+}
+
+#[test]
+fn without_a_dump_the_tick_and_hash_are_printed() {
+	let (ok, output) = run(&["--headless-ticks", "5"]);
+	assert!(ok, "{}", output);
+	let fields: Vec<&str> = output.split_whitespace().collect();
+	assert_eq!(fields.len(), 4, "{}", output);
+	assert_eq!(&fields[..3], &["tick", "5", "hash"]);
+	assert!(u64::from_str_radix(fields[3], 16).is_ok(), "{}", output);
+}
+
+#[test]
+fn a_count_that_isnt_one_fails() {
+	let (ok, output) = run(&["--headless-ticks", "soon"]);
+	assert!(ok == false);
+	assert!(output.contains("expects a number"), "{}", output);
+}
@@ -0,0 +1,101 @@
+// Hooks set off by each kind of trigger a level can give them, each
+// opening a door up in the air out of the player's way.
+
+mod common;
+
+use common::{frame, load, moving};
+use sdl2_1::world::{FrameId, Tile, World, WorldEvent};
+
+const DOOR: (isize, isize) = (10, 5);
+const FLOOR: &str = "################";
+
+// The player at column 4 of the given floor, which is along row 13, walking
+// right on it as long as it takes to get to column 12. Entities enter the
+// tile they stand on.
+fn walk(floor: &str, rest: &str) -> Vec<(Vec<WorldEvent>, bool)> {
+	let mut level = frame(0, &[(13, floor), (14, FLOOR)]);
+	level += "spawn 0 -0.4375 0.6\n";
+	level += rest;
+	let mut world = load(&level);
+	let mut ticks = Vec::new();
+	while column(&world) < 12 {
+		assert!(ticks.len() < 600, "the player never got there");
+		world.tick(&moving(1.0, 1.0));
+		ticks.push((world.events().to_vec(), door_open(&world)));
+	}
+	ticks
+}
+
+fn column(world: &World) -> isize {
+	let x = common::player(world).position.x;
+	((x + 1.0) * 8.0).floor() as isize
+}
+
+fn door_open(world: &World) -> bool {
+	let frame = world.get_frame(FrameId(0)).unwrap();
+	*frame.tile(DOOR.0, DOOR.1) == Tile::Solid
+}
+
+// The tick the door opened on, checking that it stayed open from then on.
+fn opened(ticks: &[(Vec<WorldEvent>, bool)]) -> Option<usize> {
+	let first = ticks.iter().position(|&(_, open)| open)?;
+	assert!(ticks[first..].iter().all(|&(_, open)| open));
+	Some(first)
+}
+
+fn first_event(
+	ticks: &[(Vec<WorldEvent>, bool)],
+	found: impl Fn(&WorldEvent) -> bool,
+) -> Option<usize> {
+	ticks
+		.iter()
+		.position(|(events, _)| events.iter().any(&found))
+}
+
+fn entered(ticks: &[(Vec<WorldEvent>, bool)], at: usize) -> Option<usize> {
+	first_event(ticks, |event| match *event {
+		WorldEvent::TileEntered { x, y, .. } => (x, y) == (at, 13),
+		_ => false,
+	})
+}
+
+#[test]
+fn a_hook_on_a_tile_goes_off_when_that_tile_is_entered() {
+	let hook = "hook tile 0 7 13 set_tile 0 10 5 solid\n";
+	let ticks = walk(FLOOR, hook);
+	assert!(opened(&ticks).is_some());
+	assert_eq!(opened(&ticks), entered(&ticks, 7));
+}
+
+#[test]
+fn a_hook_on_a_tile_behind_the_player_never_goes_off() {
+	let ticks = walk(FLOOR, "hook tile 0 2 13 set_tile 0 10 5 solid\n");
+	assert_eq!(opened(&ticks), None);
+}
+
+#[test]
+fn a_hook_on_a_tile_kind_goes_off_at_the_first_tile_of_that_kind() {
+	let hook = "hook tile_kind conveyor_right set_tile 0 10 5 solid\n";
+	let ticks = walk("#######>#>######", hook);
+	assert!(opened(&ticks).is_some());
+	assert_eq!(opened(&ticks), entered(&ticks, 7));
+}
+
+#[test]
+fn a_hook_on_an_event_goes_off_on_the_tick_it_happens() {
+	let coin = "coin 0 -0.0625 0.6\n";
+	let hook = "hook event coin_collected set_tile 0 10 5 solid\n";
+	let ticks = walk(FLOOR, &(coin.to_string() + hook));
+	let collected = first_event(&ticks, |event| match *event {
+		WorldEvent::CoinCollected { .. } => true,
+		_ => false,
+	});
+	assert!(collected.is_some());
+	assert_eq!(opened(&ticks), collected);
+}
+
+#[test]
+fn a_hook_on_an_event_that_never_happens_never_goes_off() {
+	let hook = "hook event coin_collected set_tile 0 10 5 solid\n";
+	assert_eq!(opened(&walk(FLOOR, hook)), None);
+}
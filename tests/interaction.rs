@@ -0,0 +1,73 @@
+// What the interact key does with things in reach of the player.
+
+mod common;
+
+use common::{frame, load, moving};
+use sdl2_1::world::{Actions, Direction, FrameId, Tile, World, WorldEvent};
+
+// The player standing in the tile at column 4, row 12, with the given row
+// drawn in beside them and the signs written on that are.
+fn beside(row: &str, written: &[isize]) -> World {
+	let floor = "################";
+	let mut level = frame(0, &[(12, row), (13, floor), (14, floor)]);
+	level += "spawn 0 -0.4375 0.6\n";
+	for (i, x) in written.iter().enumerate() {
+		level += &format!("sign 0 {} 12 {}\n", x, i);
+	}
+	level += "strings\n";
+	for i in 0..written.len() {
+		level += &format!("{} Sign {}.\n", i, i);
+	}
+	load(&level)
+}
+
+fn interact(world: &mut World, aim: Direction) {
+	let actions = Actions {
+		interact: true,
+		aim,
+		..moving(0.0, 1.0)
+	};
+	world.tick(&actions);
+}
+
+fn read(world: &World) -> Option<(FrameId, usize, usize)> {
+	world.events().iter().find_map(|event| match *event {
+		WorldEvent::SignActivated { frame, x, y, .. } => Some((frame, x, y)),
+		_ => None,
+	})
+}
+
+fn tile(world: &World, x: isize, y: isize) -> Tile {
+	*world.get_frame(FrameId(0)).unwrap().tile(x, y)
+}
+
+#[test]
+fn a_written_sign_in_reach_is_read() {
+	let mut world = beside(".....?", &[5]);
+	interact(&mut world, Direction::Up);
+	assert_eq!(read(&world), Some((FrameId(0), 5, 12)));
+	assert_eq!(tile(&world, 4, 11), Tile::Empty);
+}
+
+#[test]
+fn with_nothing_to_read_a_tile_is_placed() {
+	let mut world = beside(".....?", &[]);
+	let id = world.focus_entity.unwrap();
+	let interaction = world.interaction(id).unwrap();
+	assert_eq!(interaction.target, (FrameId(0), 5, 12));
+	assert_eq!(interaction.unavailable, Some("nothing written"));
+
+	interact(&mut world, Direction::Up);
+	assert_eq!(read(&world), None);
+	assert_eq!(tile(&world, 4, 11), Tile::Solid);
+}
+
+#[test]
+fn a_sign_that_can_be_read_comes_before_one_pointed_at_that_cant() {
+	let mut world = beside("...?.?", &[5]);
+	let id = world.focus_entity.unwrap();
+	interact(&mut world, Direction::Left);
+	assert_eq!(read(&world), Some((FrameId(0), 5, 12)));
+	let interaction = world.interaction(id).unwrap();
+	assert_eq!(interaction.unavailable, None);
+}
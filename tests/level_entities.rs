@@ -0,0 +1,130 @@
+// Entities placed by `entity` lines in a level, with their properties, and
+// written back out the same.
+
+mod common;
+
+use common::{floor_frame, load};
+use sdl2_1::world::{
+	CollisionChannels, EdgeBehavior, Entity, EntityKind, FrameId, Persistence,
+	World, CHANNEL_ENEMY, CHANNEL_PLAYER,
+};
+
+// Frames 0 and 1 with floors, the player on 0 and `entities` after.
+fn level(entities: &str) -> String {
+	floor_frame(0) + &floor_frame(1) + "spawn 0 -0.5 0.6\n" + entities
+}
+
+fn of_kind(world: &World, kind: EntityKind) -> Vec<&Entity> {
+	let mut found: Vec<&Entity> = world
+		.entity_ids()
+		.into_iter()
+		.map(|id| world.get_entity(id).unwrap())
+		.filter(|entity| entity.kind == kind)
+		.collect();
+	found.sort_by_key(|entity| entity.id.0);
+	found
+}
+
+#[test]
+fn entities_are_placed_with_their_properties() {
+	let mut entities = String::new();
+	entities += "entity walker 1 0.25 0.6 edge=follow_surface tag=arena\n";
+	entities += "entity coin 0 0.1 0.3 persist=forever\n";
+	entities += "entity worm 1 -0.5 0.6 segments=2\n";
+	entities += "entity speed_boost 0 0.2 0.3 channel=enemy collides=player\n";
+	let world = load(&level(&entities));
+
+	let walker = of_kind(&world, EntityKind::Walker)[0];
+	assert_eq!(walker.position.frame_id, FrameId(1));
+	assert_eq!((walker.position.x, walker.position.y), (0.25, 0.6));
+	assert_eq!(walker.edge_behavior, EdgeBehavior::FollowSurface);
+	assert_eq!(walker.tag, world.find_tag("arena"));
+	assert!(walker.tag.is_some());
+
+	let coin = of_kind(&world, EntityKind::Coin)[0];
+	assert_eq!(coin.persistence, Persistence::from_name("forever").unwrap());
+	assert_eq!(coin.channels, CollisionChannels::default_for(coin.kind));
+
+	assert_eq!(of_kind(&world, EntityKind::Worm).len(), 1);
+	assert_eq!(of_kind(&world, EntityKind::WormSegment).len(), 2);
+
+	let boost = of_kind(&world, EntityKind::SpeedBoost)[0];
+	assert_eq!(boost.channels.category, CHANNEL_ENEMY);
+	assert_eq!(boost.channels.collides_with, CHANNEL_PLAYER);
+	// Not said otherwise, so still the pickup's own.
+	assert!(boost.channels.sensor);
+}
+
+// Newer levels still load, without what this build doesn't know of.
+#[test]
+fn unknown_kinds_and_properties_are_skipped() {
+	let mut entities = String::new();
+	entities += "entity dragon 0 0.0 0.3 breath=fire\n";
+	entities += "entity player 0 0.0 0.3\n";
+	entities += "entity coin 0 0.1 0.3 glow=on tag=loot\n";
+	let world = load(&level(&entities));
+	assert_eq!(world.entity_count(), 2);
+	let coin = of_kind(&world, EntityKind::Coin)[0];
+	assert_eq!(coin.tag, world.find_tag("loot"));
+}
+
+// Line 1 and a line per row for each frame, then the spawn.
+fn entity_line() -> usize {
+	let frames = level("").lines().count();
+	frames + 1
+}
+
+#[test]
+fn bad_entity_lines_fail_on_their_line() {
+	let bad = [
+		(
+			"entity coin 0 0.1\n",
+			"expected at least 4 arguments, found 3",
+		),
+		(
+			"entity walker 0 0.1 0.6 edge=fly\n",
+			"unknown edge behavior 'fly'",
+		),
+		(
+			"entity coin 0 0.1 0.3 persist=ever\n",
+			"unknown persistence 'ever'",
+		),
+		(
+			"entity coin 0 0.1 0.3 channel=ghost\n",
+			"unknown channels 'ghost'",
+		),
+		(
+			"entity coin 0 0.1 0.3 sensor=yes\n",
+			"expected on or off, found 'yes'",
+		),
+		("entity coin 7 0.1 0.3\n", "unknown frame [7]"),
+		("entity coin 0 1.5 0.3\n", "position is outside its frame"),
+	];
+	for &(entity, message) in bad.iter() {
+		let error = match World::from_level_str(&level(entity)) {
+			Ok(_) => panic!("{} loaded", entity),
+			Err(error) => error,
+		};
+		assert_eq!(error.line, Some(entity_line()), "{}", entity);
+		assert_eq!(error.message, message, "{}", entity);
+	}
+}
+
+#[test]
+fn entities_are_written_back_the_same() {
+	let mut entities = String::new();
+	entities += "entity walker 1 0.25 0.6 edge=turn_at_ledge tag=arena\n";
+	entities += "entity coin 0 0.1 0.3 persist=death\n";
+	entities += "entity worm 1 -0.5 0.6 segments=3 tag=boss\n";
+	entities += "entity block 0 0.3 0.6\n";
+	entities += "entity energizer 0 0.2 0.3 collides=none sensor=off\n";
+	let world = load(&level(&entities));
+	let written = world.to_level_string();
+	let again = load(&written);
+	assert_eq!(again.state_hash(), world.state_hash(), "{}", written);
+	assert_eq!(again.to_level_string(), written);
+	for &kind in EntityKind::placeable() {
+		let count = of_kind(&world, kind).len();
+		assert_eq!(of_kind(&again, kind).len(), count, "{:?}", kind);
+	}
+}
@@ -0,0 +1,234 @@
+// How entities move through the world, ticked without a window.
+
+mod common;
+
+use sdl2_1::geometry::{vec3, Scalar};
+use sdl2_1::world::{
+	Actions, PhysicsConfig, World, WorldEvent, MAX_HEALTH, TILE_SIZE,
+};
+
+// Something that sent the player's velocity to NaN, or to infinity, puts
+// them back at the spawn point rather than taking the game down.
+#[test]
+fn a_velocity_that_isnt_a_number_respawns_the_player() {
+	for &speed in &[Scalar::NAN, Scalar::INFINITY] {
+		let mut world = World::new();
+		let id = world.focus_entity.unwrap();
+		world.get_entity_mut(id).unwrap().velocity = vec3(speed, 0.0, 0.0);
+		world.tick(&Actions::none());
+
+		let player = world.get_entity(id).unwrap();
+		assert!(player.position.x.is_finite() && player.position.y.is_finite());
+		assert!(player.velocity.x.is_finite());
+	}
+}
+
+// Two frames side by side in a loop, the first's right edge onto the
+// second's left and back round, with the rows given drawn into each over
+// a floor along the bottom.
+fn two_frames(first: &[(usize, &str)], second: &[(usize, &str)]) -> String {
+	let floor = "################";
+	let floor = [(13, floor), (14, floor), (15, floor)];
+	let first: Vec<_> = first.iter().chain(&floor).copied().collect();
+	let second: Vec<_> = second.iter().chain(&floor).copied().collect();
+	let mut level = common::frame(0, &first);
+	level += &common::frame(1, &second);
+	level += "link 0 right 1 left\nlink 1 right 0 left\n";
+	for &frame in &[0, 1] {
+		level += &format!("open {} up\nopen {} down\n", frame, frame);
+	}
+	level
+}
+
+// Top of the row of tiles, in frame coordinates.
+fn row_top(row: usize) -> Scalar {
+	-1.0 + row as Scalar * TILE_SIZE
+}
+
+// How far the player has walked round the loop of `two_frames`, as an x
+// coordinate that carries on from the first frame into the second.
+fn along_loop(world: &World) -> Scalar {
+	let position = common::player(world).position;
+	position.x + position.frame_id.0 as Scalar * 2.0
+}
+
+// Walks the player the way `move_x` says until they are past `until` along
+// the loop, failing if they stop short of it for a moment on the way, as
+// they would catching on a seam. Returns where along the loop they stood
+// on each tick.
+fn walk_until(world: &mut World, move_x: Scalar, until: Scalar) -> Vec<Scalar> {
+	let actions = common::moving(move_x, 1.0);
+	let mut trace = vec![along_loop(world)];
+	for _ in 0..600 {
+		world.tick(&actions);
+		let along = along_loop(world);
+		trace.push(along);
+		if (along - until) * move_x > 0.0 {
+			return trace;
+		}
+		let stalled = trace.len() > 20
+			&& (along - trace[trace.len() - 20]).abs() < TILE_SIZE * 0.1;
+		assert!(stalled == false, "caught at {}, after {:?}", along, trace);
+	}
+	panic!("never got past {}: {:?}", until, trace);
+}
+
+// Once the player has come to a stop, holding down onto the floor.
+fn assert_standing_on(world: &mut World, frame: usize, top: Scalar) {
+	common::tick_for(world, &common::moving(0.0, 1.0), 30);
+	let player = common::player(world);
+	assert_eq!(player.position.frame_id.0, frame);
+	assert!(
+		player.grounded,
+		"not on the ground at {:?}",
+		player.position
+	);
+	assert!(
+		(player.position.y - top).abs() < TILE_SIZE * 0.05,
+		"standing at {}, not {}",
+		player.position.y,
+		top
+	);
+}
+
+// Up a ramp onto the raised floor it leads to, and back down it, without
+// catching on the seam at either end.
+#[test]
+fn walking_over_a_ramp_doesnt_catch_on_its_ends() {
+	let ramp = [(12, "......./########")];
+	let mut level = two_frames(&ramp, &[(12, "################")]);
+	level += "spawn 0 -0.7 0.6\n";
+	let mut world = common::load(&level);
+	common::tick_for(&mut world, &common::moving(0.0, 1.0), 30);
+
+	walk_until(&mut world, 1.0, 0.5);
+	assert_standing_on(&mut world, 0, row_top(12));
+	walk_until(&mut world, -1.0, -0.3);
+	assert_standing_on(&mut world, 0, row_top(13));
+}
+
+// A ramp against the edge of its frame, walked up from the frame it is on
+// into the next, and down from the next.
+#[test]
+fn a_ramp_at_the_end_of_a_frame_leads_into_the_next() {
+	let ramp = [(12, ".............../")];
+	let mut level = two_frames(&ramp, &[(12, "################")]);
+	level += "spawn 0 0.2 0.6\n";
+	let mut world = common::load(&level);
+	common::tick_for(&mut world, &common::moving(0.0, 1.0), 30);
+
+	walk_until(&mut world, 1.0, 2.5);
+	assert_standing_on(&mut world, 1, row_top(12));
+	walk_until(&mut world, -1.0, 0.5);
+	assert_standing_on(&mut world, 0, row_top(13));
+}
+
+// A ramp against the edge of the next frame, walked up from the frame
+// before it, and down into that frame.
+#[test]
+fn a_ramp_at_the_start_of_a_frame_is_walked_onto_from_the_last() {
+	let mut level = two_frames(&[], &[(12, "/###############")]);
+	level += "spawn 0 0.2 0.6\n";
+	let mut world = common::load(&level);
+	common::tick_for(&mut world, &common::moving(0.0, 1.0), 30);
+
+	walk_until(&mut world, 1.0, 2.5);
+	assert_standing_on(&mut world, 1, row_top(12));
+	walk_until(&mut world, -1.0, 0.5);
+	assert_standing_on(&mut world, 0, row_top(13));
+}
+
+// Left standing on a belt, the player is carried along at the speed the
+// belt's push settles at, and only slows down once off its end.
+#[test]
+fn a_conveyor_carries_the_player_until_they_leave_it() {
+	let floor = "################";
+	let rows = [(13, ">>>>>>>>########"), (14, floor), (15, floor)];
+	let mut level = common::frame(0, &rows);
+	level += "spawn 0 -0.6 0.6\n";
+	let mut world = common::load(&level);
+	let physics = world.physics;
+	let drift = physics.conveyor_speed();
+
+	let actions = common::moving(0.0, 1.0);
+	common::tick_for(&mut world, &actions, 60);
+	let player = common::player(&world);
+	assert!(player.position.x < 0.0, "off the belt already");
+	assert!(
+		(player.velocity.x - drift).abs() < drift * 0.01,
+		"drifting at {}, not {}",
+		player.velocity.x,
+		drift
+	);
+
+	// The belt ends at the middle of the frame.
+	let mut off_belt = false;
+	for _ in 0..400 {
+		let before = common::player(&world).velocity.x;
+		world.tick(&actions);
+		let player = common::player(&world);
+		if off_belt {
+			let slowed = before * physics.velocity_retention;
+			assert!(
+				player.velocity.x <= slowed + 1e-9,
+				"still pushed at {:?}",
+				player.position
+			);
+		}
+		off_belt = player.position.x >= 0.0;
+	}
+	assert!(off_belt, "never carried off the belt");
+	assert!(common::player(&world).velocity.x.abs() < 1e-6);
+}
+
+// The player a hair above the floor of a level with fall damage on,
+// moving down onto it at `speed`. Their health once they have landed, and
+// how fast they were said to land.
+fn land_at(speed: Scalar) -> (Scalar, Scalar) {
+	let mut level = common::floor_frame(0);
+	level += "fall_damage on\nspawn 0 0.0 0.62\n";
+	let mut world = common::load(&level);
+	let id = world.focus_entity.unwrap();
+	world.get_entity_mut(id).unwrap().velocity = vec3(0.0, speed, 0.0);
+	world.tick(&Actions::none());
+
+	let landed = world.events().iter().find_map(|event| match *event {
+		WorldEvent::EntityLanded { entity, speed } if entity == id => {
+			Some(speed)
+		}
+		_ => None,
+	});
+	let landed = landed.expect("never landed");
+	(world.get_entity(id).unwrap().health, landed)
+}
+
+#[test]
+fn landing_just_slower_than_the_threshold_doesnt_hurt() {
+	let threshold = PhysicsConfig::default().fall_damage_speed;
+	let (health, landed) = land_at(threshold * 0.95);
+	assert!(
+		(landed - threshold * 0.95).abs() < 1e-6,
+		"landed at {}",
+		landed
+	);
+	assert_eq!(health, MAX_HEALTH);
+}
+
+#[test]
+fn landing_just_faster_than_the_threshold_hurts_by_how_much_faster() {
+	let physics = PhysicsConfig::default();
+	let threshold = physics.fall_damage_speed;
+	let (health, landed) = land_at(threshold * 1.2);
+	assert!(
+		(landed - threshold * 1.2).abs() < 1e-6,
+		"landed at {}",
+		landed
+	);
+	let damage = (landed - threshold) * physics.fall_damage_per_speed;
+	assert!(
+		(MAX_HEALTH - health - damage).abs() < 1e-3,
+		"lost {}, not {}",
+		MAX_HEALTH - health,
+		damage
+	);
+}
@@ -0,0 +1,46 @@
+mod common;
+
+use common::{floor_frame, load, moving, tick_for};
+use sdl2_1::world::{InvariantViolation, World};
+
+// A world whose player is gone, as just after dying, with a walker still
+// about to keep things moving.
+fn without_focus() -> World {
+	let mut source = floor_frame(0);
+	source += "spawn 0 0.0 0.6\nwalker 0 0.5 0.6\n";
+	let mut world = load(&source);
+	let id = world.focus_entity.unwrap();
+	world.remove_entity(id);
+	assert_eq!(world.focus_entity, None);
+	world
+}
+
+#[test]
+fn a_world_without_a_focus_entity_still_ticks() {
+	let mut world = without_focus();
+	let hash = world.state_hash();
+	tick_for(&mut world, &moving(1.0, 1.0), 60);
+	assert_eq!(world.tick_count, 60);
+	assert!(world.state_hash() != hash, "nothing moved");
+	let violations = world.invariant_violations();
+	let missing = |violation: &InvariantViolation| match violation {
+		InvariantViolation::MissingFocusEntity => true,
+		_ => false,
+	};
+	assert!(violations.iter().all(missing), "{:?}", violations);
+}
+
+#[cfg(feature = "headless")]
+#[test]
+fn a_world_without_a_focus_entity_is_still_drawn() {
+	use sdl2_1::window::Window;
+	use sdl2_1::GameState;
+
+	let mut window = Window::headless().unwrap();
+	let mut game_state = GameState::from_world(without_focus());
+	for _ in 0..3 {
+		window.tick(&mut game_state);
+		window.render(&mut game_state);
+		assert!(window.drawn_lines().is_empty() == false);
+	}
+}
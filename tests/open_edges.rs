@@ -0,0 +1,155 @@
+// What happens to the player at the edges of a level that don't lead
+// anywhere, and in worlds broken badly enough that they can't be anywhere.
+
+mod common;
+
+use sdl2_1::geometry::Scalar;
+use sdl2_1::world::{EntityKind, FrameId, WorldEvent};
+use sdl2_1::GameState;
+
+const SPAWN_X: Scalar = -0.5;
+// Ticks after going over an open edge before the player is back at the
+// spawn point, at most.
+const FALL_TICKS: usize = 120;
+
+// A lone frame with nothing linked to any of its edges and none of them
+// open, so that walking off one leaves the world.
+fn lone_frame() -> GameState {
+	let mut level = common::floor_frame(0);
+	level += &format!("spawn 0 {} 0.6\n", SPAWN_X);
+	GameState::from_level_source(&level).unwrap()
+}
+
+// A lone frame open on every edge, with a wall up its left side for
+// blocks and walkers to look past the edge from, and a walker about.
+fn open_frame() -> GameState {
+	let wall = "#";
+	let floor = "################";
+	let mut level = common::frame(
+		0,
+		&(0..13)
+			.map(|y| (y, wall))
+			.chain((13..16).map(|y| (y, floor)))
+			.collect::<Vec<_>>(),
+	);
+	level += &format!("spawn 0 {} 0.6\n", SPAWN_X);
+	level += "walker 0 0.5 0.6\n";
+	for edge in ["left", "right", "up", "down"].iter() {
+		level += &format!("open 0 {}\n", edge);
+	}
+	GameState::from_level_source(&level).unwrap()
+}
+
+fn player_died(game_state: &GameState) -> bool {
+	let world = &game_state.world;
+	world.events().iter().any(|event| match *event {
+		WorldEvent::EntityDied { entity } => world.focus_entity == Some(entity),
+		_ => false,
+	})
+}
+
+fn respawned(game_state: &GameState) -> bool {
+	game_state.world.events().iter().any(|event| match *event {
+		WorldEvent::EntityOutOfBounds { .. } => true,
+		_ => false,
+	})
+}
+
+#[test]
+fn walking_out_of_a_broken_world_respawns_the_player() {
+	let mut game_state = lone_frame();
+	let actions = common::moving(1.0, 1.0);
+	let mut ticks = 0;
+	while respawned(&game_state) == false {
+		assert!(ticks < 600, "never left the frame");
+		game_state.tick(&actions);
+		ticks += 1;
+	}
+	let player = common::player(&game_state.world);
+	assert_eq!(player.position.frame_id, FrameId(0));
+	assert!((player.position.x - SPAWN_X).abs() < 0.1);
+}
+
+#[test]
+fn a_player_on_a_frame_that_doesnt_exist_respawns() {
+	let mut game_state = lone_frame();
+	let world = &mut game_state.world;
+	let id = world.focus_entity.unwrap();
+	world.get_entity_mut(id).unwrap().position.frame_id = FrameId(7);
+	game_state.tick(&common::moving(0.0, 1.0));
+
+	assert!(respawned(&game_state));
+	let player = common::player(&game_state.world);
+	assert_eq!(player.position.frame_id, FrameId(0));
+}
+
+#[test]
+fn walking_off_an_open_edge_falls_off_and_respawns() {
+	let mut game_state = open_frame();
+	let actions = common::moving(1.0, 1.0);
+	let mut ticks = 0;
+	while common::player(&game_state.world).is_falling_off() == false {
+		assert!(ticks < 600, "never went over the edge");
+		game_state.tick(&actions);
+		ticks += 1;
+	}
+	let mut fell = 0;
+	while player_died(&game_state) == false {
+		assert!(fell < FALL_TICKS, "never finished falling");
+		game_state.tick(&actions);
+		fell += 1;
+	}
+	assert!(respawned(&game_state) == false);
+	let player = common::player(&game_state.world);
+	assert!(player.is_falling_off() == false);
+	assert_eq!(player.position.frame_id, FrameId(0));
+	assert!((player.position.x - SPAWN_X).abs() < 0.1);
+}
+
+#[test]
+fn a_walker_by_an_open_edge_walks_off_it() {
+	let mut game_state = open_frame();
+	let walkers = |game_state: &GameState| {
+		let world = &game_state.world;
+		let ids = world.entity_ids();
+		ids.iter()
+			.filter(|&&id| {
+				world.get_entity(id).unwrap().kind == EntityKind::Walker
+			})
+			.count()
+	};
+	assert_eq!(walkers(&game_state), 1);
+	let mut ticks = 0;
+	while walkers(&game_state) > 0 {
+		assert!(ticks < 1200, "the walker never left");
+		game_state.tick(&common::moving(0.0, 1.0));
+		ticks += 1;
+	}
+}
+
+// Tiles drawn as blocks look past the frame's edges to see which sides
+// are hidden, which across an open edge is nothing.
+#[cfg(feature = "headless")]
+#[test]
+fn an_open_frame_is_drawn_with_cliffs() {
+	use sdl2_1::window::{Palette, PaletteKind, Role, Window};
+
+	let cliff = Palette::new(PaletteKind::Default).get(Role::Cliff);
+	let cliffs = |window: &Window| {
+		let lines = window.drawn_lines();
+		lines.iter().filter(|line| line.color == cliff).count()
+	};
+	for &three_d_tiles in [false, true].iter() {
+		let mut window = Window::headless().unwrap();
+		window.set_three_d_tiles(three_d_tiles);
+		let mut game_state = open_frame();
+		for _ in 0..3 {
+			window.tick(&mut game_state);
+			window.render(&mut game_state);
+			assert!(cliffs(&window) > 0);
+		}
+		let mut game_state = lone_frame();
+		window.render(&mut game_state);
+		assert_eq!(cliffs(&window), 0);
+	}
+}
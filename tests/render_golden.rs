@@ -0,0 +1,14 @@
+// What the renderer draws, checked against the scenes in
+// `tests/render_golden`. When a change to what is drawn is meant, check
+// them in again with
+//
+//   cargo run --example render_golden --no-default-features --features headless -- --update
+
+use sdl2_1::window::golden::check_golden_scenes;
+
+#[test]
+fn the_golden_scenes_are_drawn_as_checked_in() {
+	if let Err(report) = check_golden_scenes() {
+		panic!("{}", report);
+	}
+}
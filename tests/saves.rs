@@ -0,0 +1,320 @@
+mod common;
+
+use common::{floor_frame, frame, load, moving, player, tick_for};
+use sdl2_1::geometry::Scalar;
+use sdl2_1::world::{
+	Actions, Angle, Direction, FrameId, Replay, Rng, SaveError, SaveHeader,
+	World, SAVE_VERSION,
+};
+
+// The tutorial after two seconds of walking down and to the right, as the
+// first version of the save format wrote it at Unix time 1.6e9.
+const SAVE_V1: &[u8] = include_bytes!("data/save_v1.bin");
+// Of the world `SAVE_V1` holds, once loaded, which is read into the
+// world's own scalars.
+#[cfg(not(feature = "f64"))]
+const SAVE_V1_HASH: u64 = 0x973b_f983_cddb_19af;
+#[cfg(feature = "f64")]
+const SAVE_V1_HASH: u64 = 0xc20d_800d_be68_acca;
+
+// Where each part of the header starts.
+const SAVED_AT_OFFSET: usize = 4 + 4;
+const BODY_LENGTH_OFFSET: usize = SAVED_AT_OFFSET + 8;
+const V1_BODY_START: usize = BODY_LENGTH_OFFSET + 8;
+
+fn walked_tutorial() -> World {
+	let source = std::fs::read_to_string("levels/tutorial.lvl").unwrap();
+	let mut world = World::from_level_str(&source).unwrap();
+	tick_for(&mut world, &moving(1.0, 1.0), 120);
+	world
+}
+
+// `save` with its body cut or padded to `length` bytes and the header
+// saying as much.
+fn with_body_length(save: &[u8], body_start: usize, length: usize) -> Vec<u8> {
+	let mut bytes = save[..body_start.min(save.len())].to_vec();
+	let body = &save[body_start..];
+	bytes.extend_from_slice(&body[..length.min(body.len())]);
+	bytes.resize(body_start + length, 0);
+	let at = BODY_LENGTH_OFFSET..BODY_LENGTH_OFFSET + 8;
+	bytes[at].copy_from_slice(&(length as u64).to_le_bytes());
+	bytes
+}
+
+// Something of everything a save keeps: level details, wind, tile data,
+// rotations, open edges, every kind of entity with tags, persistence and
+// channels, a worm's trail, status effects and every kind of hook, partway
+// through being played.
+fn everything() -> World {
+	let mut source = String::from(
+		"\
+name Everything
+author Someone
+par 600
+next_level levels/tutorial.lvl
+",
+	);
+	source += &frame(
+		0,
+		&[
+			(11, "..?.........>..."),
+			(12, "^............/.."),
+			(13, "################"),
+			(14, "##~~~~~~::::####"),
+			(15, "################"),
+		],
+	);
+	source += &floor_frame(1);
+	source += "\
+link 0 right 1 left
+link 1 right 0 left
+open 0 up
+open 1 down
+background 1 checker 336699
+wind 1 0.001 0
+rotate 0 12 11 2
+spawn 0 -0.4 0.6
+sign 0 2 11 0
+coin 0 0.2 0.6 tag loot
+gravity_orb 1 0.5 0.6 persist forever
+speed_boost 1 -0.5 0.6
+energizer 1 0.0 0.6
+block 0 9 12
+walker 0 0.6 0.6 turn_at_ledge tag enemies
+entity worm 1 0.3 0.6 segments=3 channel=enemy collides=player+block
+effect slow 200 0.5
+hook tile 0 9 12 set_tile 0 14 11 solid show
+hook tile_kind spring teleport 1 0.0 0.6
+hook event coin_collected checkpoint
+hook group_cleared loot set_tile 1 3 12 spring
+fall_damage on
+edits death
+strings
+0 Hello,\\nworld.
+";
+	let mut world = load(&source);
+	tick_for(&mut world, &moving(1.0, 1.0), 20);
+	let place = Actions {
+		interact: true,
+		aim: Direction::Up,
+		..moving(0.0, 1.0)
+	};
+	world.tick(&place);
+	tick_for(&mut world, &moving(-1.0, 1.0), 30);
+	world
+}
+
+#[test]
+fn a_saved_world_loads_as_it_was() {
+	for world in [walked_tutorial(), everything()] {
+		let bytes = world.to_save_bytes(0.0);
+		let loaded = World::from_save_bytes(&bytes).unwrap();
+		assert_eq!(loaded.state_hash(), world.state_hash());
+		assert_eq!(loaded.to_save_bytes(0.0), bytes);
+	}
+}
+
+#[test]
+fn a_loaded_world_carries_on_as_it_would_have() {
+	for mut world in [walked_tutorial(), everything()] {
+		let bytes = world.to_save_bytes(0.0);
+		let mut loaded = World::from_save_bytes(&bytes).unwrap();
+		tick_for(&mut world, &moving(-1.0, 1.0), 120);
+		tick_for(&mut loaded, &moving(-1.0, 1.0), 120);
+		assert_eq!(loaded.state_hash(), world.state_hash());
+	}
+}
+
+// Every directional tile turned each of the four ways, in row 10 for
+// conveyors and ramps and row 11 for springs.
+fn turned_tiles() -> World {
+	let mut source = frame(0, &[(10, "<<<<>>>>////\\\\\\\\"), (11, "^^^^")]);
+	for x in 0..16 {
+		source += &format!("rotate 0 {} 10 {}\n", x, x % 4);
+	}
+	for x in 0..4 {
+		source += &format!("rotate 0 {} 11 {}\n", x, x);
+	}
+	source += "spawn 0 0.0 0.6\n";
+	load(&source)
+}
+
+fn rotations(world: &World) -> Vec<Angle> {
+	let frame = world.get_frame(FrameId(0)).unwrap();
+	let row = |y| (0..16).map(move |x| frame.rotation(x, y));
+	row(10).chain(row(11).take(4)).collect()
+}
+
+#[test]
+fn every_turn_of_every_directional_tile_is_saved() {
+	let world = turned_tiles();
+	let turned = rotations(&world);
+	let angles = [
+		Angle::Clockwise0,
+		Angle::Clockwise90,
+		Angle::Clockwise180,
+		Angle::Clockwise270,
+	];
+	assert_eq!(turned, angles.repeat(5));
+
+	let bytes = world.to_save_bytes(0.0);
+	let saved = World::from_save_bytes(&bytes).unwrap();
+	assert_eq!(rotations(&saved), turned);
+	let written = World::from_level_str(&world.to_level_string()).unwrap();
+	assert_eq!(rotations(&written), turned);
+	assert_eq!(written.to_level_string(), world.to_level_string());
+}
+
+#[test]
+fn the_first_version_still_loads() {
+	let header = SaveHeader::read(SAVE_V1).unwrap();
+	assert_eq!(header.version, 1);
+	assert_eq!(header.saved_at, 1.6e9);
+	assert_eq!(header.play_ticks, 120);
+
+	let world = World::from_save_bytes(SAVE_V1).unwrap();
+	assert_eq!(world.level.name, None);
+	assert_eq!(world.tick_count, 120);
+	assert_eq!(world.frame_ids().len(), 6);
+	assert_eq!(world.entity_count(), 6);
+	let position = player(&world).position;
+	assert_eq!(position.frame_id, FrameId(0));
+	assert!(
+		(position.x - 0.4393 as Scalar).abs() < 1e-4,
+		"{}",
+		position.x
+	);
+	assert!(
+		(position.y - 0.625 as Scalar).abs() < 1e-4,
+		"{}",
+		position.y
+	);
+	let hash = world.state_hash();
+	assert_eq!(hash, SAVE_V1_HASH, "loaded as {:016x}", hash);
+}
+
+#[test]
+fn the_first_version_is_saved_again_as_the_newest() {
+	let world = World::from_save_bytes(SAVE_V1).unwrap();
+	let bytes = world.to_save_bytes(0.0);
+	assert_eq!(SaveHeader::read(&bytes).unwrap().version, SAVE_VERSION);
+	let loaded = World::from_save_bytes(&bytes).unwrap();
+	assert_eq!(loaded.state_hash(), world.state_hash());
+}
+
+#[test]
+fn a_cut_short_save_never_loads() {
+	let bytes = walked_tutorial().to_save_bytes(0.0);
+	for length in 0..bytes.len() {
+		let result = World::from_save_bytes(&bytes[..length]);
+		assert!(result.is_err(), "loaded from the first {} bytes", length);
+	}
+	for length in 0..SAVE_V1.len() {
+		let result = World::from_save_bytes(&SAVE_V1[..length]);
+		assert!(result.is_err(), "loaded from the first {} bytes", length);
+	}
+}
+
+// With the header changed to match, so that it is the body that is read
+// short rather than the header that turns it away.
+#[test]
+fn a_cut_short_body_never_loads() {
+	let body_start =
+		SAVE_V1.len() - SaveHeader::read(SAVE_V1).unwrap().body_length as usize;
+	assert_eq!(body_start, V1_BODY_START);
+	for length in 0..SAVE_V1.len() - body_start {
+		let bytes = with_body_length(SAVE_V1, body_start, length);
+		let result = World::from_save_bytes(&bytes);
+		assert!(result.is_err(), "loaded from {} bytes of body", length);
+	}
+
+	let save = walked_tutorial().to_save_bytes(0.0);
+	let body_start =
+		save.len() - SaveHeader::read(&save).unwrap().body_length as usize;
+	for length in 0..save.len() - body_start {
+		let bytes = with_body_length(&save, body_start, length);
+		let result = World::from_save_bytes(&bytes);
+		assert!(result.is_err(), "loaded from {} bytes of body", length);
+	}
+}
+
+#[test]
+fn a_body_length_past_the_end_is_cut_short() {
+	for save in [SAVE_V1.to_vec(), walked_tutorial().to_save_bytes(0.0)] {
+		let mut bytes = save.clone();
+		let at = BODY_LENGTH_OFFSET..BODY_LENGTH_OFFSET + 8;
+		let length = SaveHeader::read(&save).unwrap().body_length;
+		for &wrong in [length + 1, u64::MAX / 2, u64::MAX].iter() {
+			bytes[at.clone()].copy_from_slice(&wrong.to_le_bytes());
+			let result = World::from_save_bytes(&bytes);
+			assert_eq!(result.err(), Some(SaveError::Truncated));
+		}
+	}
+}
+
+// Saves are sorted by when they were made, which has to be a number to
+// sort by.
+#[test]
+fn a_save_made_at_no_real_time_isnt_read() {
+	for save in [SAVE_V1.to_vec(), walked_tutorial().to_save_bytes(0.0)] {
+		for &time in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY].iter() {
+			let mut bytes = save.clone();
+			let at = SAVED_AT_OFFSET..SAVED_AT_OFFSET + 8;
+			bytes[at].copy_from_slice(&time.to_le_bytes());
+			match SaveHeader::read(&bytes) {
+				Err(SaveError::Invalid(_)) => {}
+				other => panic!("{:?} made at {}", other, time),
+			}
+			assert!(World::from_save_bytes(&bytes).is_err());
+		}
+	}
+}
+
+// Whatever the bytes are, loading them fails or gives a world, and never
+// panics.
+#[test]
+fn a_corrupted_save_never_panics() {
+	let mut rng = Rng::new(0x5a7e);
+	for save in [SAVE_V1.to_vec(), walked_tutorial().to_save_bytes(0.0)] {
+		for _ in 0..2000 {
+			let mut bytes = save.clone();
+			for _ in 0..rng.range_i(1, 8) {
+				let at = rng.range_i(0, bytes.len() as isize) as usize;
+				bytes[at] = rng.next_u64() as u8;
+			}
+			let _ = World::from_save_bytes(&bytes);
+		}
+	}
+}
+
+fn replay() -> Replay {
+	let mut replay = Replay::new("levels/tutorial.lvl");
+	replay.hash = Some(0x0123_4567_89ab_cdef);
+	let aiming = Actions {
+		move_y: -0.5,
+		jump: true,
+		interact: true,
+		aim: Direction::Up,
+		..moving(1.0, 0.0)
+	};
+	for actions in [Actions::none(), aiming, aiming, moving(-1.0, 1.0)] {
+		replay.push(&actions);
+	}
+	replay
+}
+
+#[test]
+fn a_replay_is_read_back_as_it_was() {
+	let replay = replay();
+	assert_eq!(replay.runs.len(), 3);
+	assert_eq!(Replay::from_bytes(&replay.to_bytes()), Ok(replay));
+}
+
+#[test]
+fn a_cut_short_replay_is_never_read() {
+	let bytes = replay().to_bytes();
+	for length in 0..bytes.len() {
+		let result = Replay::from_bytes(&bytes[..length]);
+		assert!(result.is_err(), "read from the first {} bytes", length);
+	}
+}
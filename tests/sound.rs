@@ -0,0 +1,123 @@
+// How loud the world's sounds are and where they're heard from, as the
+// player hears them.
+
+mod common;
+
+use common::{floor_frame, load, player};
+use sdl2_1::geometry::Scalar;
+use sdl2_1::world::{
+	Actions, Direction, FrameId, Sound, SoundPlacement, World, WorldPosition,
+	SOUND_RADIUS,
+};
+
+// Three frames in a row, left to right, with the player a quarter of a
+// frame in from the right edge of the first.
+fn three_in_a_row() -> World {
+	let mut level = floor_frame(0) + &floor_frame(1) + &floor_frame(2);
+	level += "link 0 right 1 left\nlink 1 right 2 left\n";
+	level += "spawn 0 0.75 0.6\n";
+	load(&level)
+}
+
+fn beside_player(world: &World, frame_id: usize, x: Scalar) -> WorldPosition {
+	WorldPosition {
+		frame_id: FrameId(frame_id),
+		x,
+		y: player(world).position.y,
+	}
+}
+
+fn assert_placed(placement: SoundPlacement, gain: Scalar, pan: Scalar) {
+	assert!(
+		(placement.gain - gain).abs() < 1e-4
+			&& (placement.pan - pan).abs() < 1e-4,
+		"{:?}, not gain {} and pan {}",
+		placement,
+		gain,
+		pan
+	);
+}
+
+#[test]
+fn a_sound_across_an_edge_is_as_loud_as_one_as_far_away_on_the_frame() {
+	let world = three_in_a_row();
+	let x = player(&world).position.x;
+	let across = world.sound_placement(beside_player(&world, 1, -0.75));
+	let distance = (1.0 - x) + 0.25;
+	assert_placed(across, 1.0 - distance / SOUND_RADIUS, 1.0);
+
+	let same_frame = world.sound_placement(beside_player(&world, 0, x - 0.5));
+	assert_placed(same_frame, 1.0 - 0.5 / SOUND_RADIUS, -1.0);
+}
+
+// Two frames over is much quieter than it would be as far away on a frame
+// linked to the player's, and too far away is silent.
+#[test]
+fn a_sound_on_a_frame_further_off_is_quieter() {
+	let world = three_in_a_row();
+	let x = player(&world).position.x;
+	let distant = world.sound_placement(beside_player(&world, 2, -0.9));
+	let distance = (1.0 - x) + 2.0 + 0.1;
+	assert_placed(distant, (1.0 - distance / SOUND_RADIUS) * 0.15, 1.0);
+
+	let silent = world.sound_placement(beside_player(&world, 2, 0.9));
+	assert_placed(silent, 0.0, 1.0);
+}
+
+#[test]
+fn without_a_player_every_sound_is_centered() {
+	let mut world = three_in_a_row();
+	let id = world.focus_entity.unwrap();
+	let source = beside_player(&world, 2, 0.0);
+	world.remove_entity(id);
+	assert_eq!(world.sound_placement(source), SoundPlacement::centered());
+}
+
+// Placing a tile above the player is heard from right where it was put.
+#[test]
+fn a_tile_placed_is_heard_where_it_goes() {
+	let mut world = three_in_a_row();
+	world.tick(&Actions {
+		interact: true,
+		aim: Direction::Up,
+		..Actions::none()
+	});
+	let sounds = world.tick_sounds();
+	let placed: Vec<_> = sounds
+		.iter()
+		.filter(|(sound, _)| *sound == Sound::TileEdited)
+		.collect();
+	assert_eq!(placed.len(), 1, "{:?}", sounds);
+	let (_, placement) = placed[0];
+	assert!(placement.gain > 0.9, "{:?}", placement);
+	assert!(placement.pan.abs() < 0.5, "{:?}", placement);
+}
+
+// The player spawned on a coin picks it up, which the window plays as it
+// ticks.
+#[cfg(feature = "headless")]
+#[test]
+fn the_window_plays_what_the_world_sounds() {
+	use sdl2_1::window::Window;
+	use sdl2_1::GameState;
+
+	let mut level = floor_frame(0);
+	level += "spawn 0 0.0 0.6\ncoin 0 0.0 0.6\ncoin 0 -0.8 0.6\n";
+	let mut window = Window::headless().unwrap();
+	let mut game_state = GameState::from_world(load(&level));
+	let start = std::time::Instant::now();
+	while game_state.world.tick_count < 5 {
+		assert!(start.elapsed().as_secs() < 5, "the world never ticked");
+		window.tick(&mut game_state);
+	}
+	let coin = Sound::Coin.id();
+	let played = window.played_sounds();
+	let heard: Vec<_> =
+		played.iter().filter(|sound| sound.id == coin).collect();
+	assert_eq!(heard.len(), 1, "{:?}", played);
+	assert!(
+		heard[0].gain > 0.9 && heard[0].pan.abs() < 0.5,
+		"{:?}",
+		heard
+	);
+}
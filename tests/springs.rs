@@ -0,0 +1,102 @@
+// Springs throwing things off them, the way they face.
+
+mod common;
+
+use common::{frame, load, player};
+use sdl2_1::geometry::{vec3, Scalar};
+use sdl2_1::world::{Actions, PhysicsConfig, World, WorldEvent, MAX_HEALTH};
+
+const FLOOR: &str = "################";
+
+fn bounced(world: &World) -> bool {
+	world.events().iter().any(|event| match *event {
+		WorldEvent::EntityBounced { .. } => true,
+		_ => false,
+	})
+}
+
+fn landed(world: &World) -> bool {
+	world.events().iter().any(|event| match *event {
+		WorldEvent::EntityLanded { .. } => true,
+		_ => false,
+	})
+}
+
+// Ticks with the given input until the player is thrown, giving their
+// velocity straight after.
+fn until_bounced(world: &mut World, actions: &Actions) -> (Scalar, Scalar) {
+	let mut ticks = 0;
+	loop {
+		assert!(ticks < 600, "never bounced");
+		world.tick(actions);
+		ticks += 1;
+		if bounced(world) {
+			assert!(landed(world) == false, "landed on the spring");
+			let velocity = player(world).velocity;
+			return (velocity.x, velocity.y);
+		}
+	}
+}
+
+// The player just above a spring in the floor, falling onto it at
+// `speed`, with fall damage on for anything that counts as landing.
+fn dropped_onto_spring(speed: Scalar) -> World {
+	let mut level = frame(0, &[(13, "....^..........."), (14, FLOOR)]);
+	level += "spawn 0 -0.4375 0.55\nfall_damage on\n";
+	let mut world = load(&level);
+	let id = world.focus_entity.unwrap();
+	world.get_entity_mut(id).unwrap().velocity = vec3(0.0, speed, 0.0);
+	world
+}
+
+#[test]
+fn a_spring_throws_as_hard_however_fast_it_is_hit() {
+	let throws: Vec<_> = [0.02, 0.04, 0.08]
+		.iter()
+		.map(|&speed| {
+			let mut world = dropped_onto_spring(speed);
+			let throw = until_bounced(&mut world, &Actions::none());
+			assert_eq!(player(&world).health, MAX_HEALTH);
+			throw
+		})
+		.collect();
+	let (x, y) = throws[0];
+	assert_eq!(x, 0.0);
+	assert!(y < 0.0, "thrown downwards: {}", y);
+	assert!(
+		throws.iter().all(|&throw| throw == throws[0]),
+		"{:?}",
+		throws
+	);
+	// Higher than jumping would.
+	assert!(-y > PhysicsConfig::default().jump_speed, "{}", y);
+}
+
+#[test]
+fn holding_jump_throws_further() {
+	let mut world = dropped_onto_spring(0.02);
+	let (_, plain) = until_bounced(&mut world, &Actions::none());
+	let mut world = dropped_onto_spring(0.02);
+	let holding = Actions {
+		jump_held: true,
+		..Actions::none()
+	};
+	let (_, held) = until_bounced(&mut world, &holding);
+	assert!(-held > -plain, "{} against {}", held, plain);
+}
+
+// A spring on the side of a wall at column 10, turned to face left, back
+// towards a player walking right into it.
+#[test]
+fn a_spring_on_a_wall_throws_sideways() {
+	let wall = "..........^";
+	let mut level =
+		frame(0, &[(12, wall), (13, FLOOR), (14, FLOOR), (15, FLOOR)]);
+	level += "rotate 0 10 12 3\nspawn 0 -0.4375 0.6\n";
+	let mut world = load(&level);
+	let (x, _) = until_bounced(&mut world, &common::moving(1.0, 1.0));
+	assert!(x < 0.0, "not thrown back: {}", x);
+	let thrown_from = player(&world).position.x;
+	common::tick_for(&mut world, &Actions::none(), 10);
+	assert!(player(&world).position.x < thrown_from);
+}
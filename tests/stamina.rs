@@ -0,0 +1,131 @@
+// Stamina, used up by dashing and sprinting and coming back only while
+// standing on something.
+
+mod common;
+
+use common::{floor_frame, frame, load, moving, player, tick_for};
+use sdl2_1::geometry::Scalar;
+use sdl2_1::world::{Actions, PhysicsConfig, World, WorldEvent};
+
+// The player standing on a floor, with the stamina given.
+fn standing(stamina: Scalar) -> World {
+	let mut world = load(&(floor_frame(0) + "spawn 0 0.0 0.6\n"));
+	tick_for(&mut world, &moving(0.0, 1.0), 30);
+	assert!(player(&world).grounded);
+	set_stamina(&mut world, stamina);
+	world
+}
+
+fn set_stamina(world: &mut World, stamina: Scalar) {
+	let id = world.focus_entity.unwrap();
+	world.get_entity_mut(id).unwrap().stamina = stamina;
+}
+
+fn physics() -> PhysicsConfig {
+	PhysicsConfig::default()
+}
+
+fn dashing() -> Actions {
+	Actions {
+		dash: true,
+		move_y: 1.0,
+		..Actions::none()
+	}
+}
+
+fn sprinting() -> Actions {
+	Actions {
+		sprint: true,
+		..moving(1.0, 1.0)
+	}
+}
+
+fn happened(world: &World, found: impl Fn(&WorldEvent) -> bool) -> bool {
+	world.events().iter().any(found)
+}
+
+fn dashed(event: &WorldEvent) -> bool {
+	match *event {
+		WorldEvent::EntityDashed { .. } => true,
+		_ => false,
+	}
+}
+
+fn refused(event: &WorldEvent) -> bool {
+	match *event {
+		WorldEvent::DashRefused { .. } => true,
+		_ => false,
+	}
+}
+
+#[test]
+fn dashing_uses_up_its_cost() {
+	let mut world = standing(1.0);
+	world.tick(&dashing());
+	assert!(happened(&world, dashed));
+	assert!(happened(&world, refused) == false);
+	let left = 1.0 - physics().dash_stamina_cost;
+	let stamina = player(&world).stamina;
+	// Give or take what came back in the same tick.
+	let regen = physics().stamina_regen;
+	assert!((left..=left + regen).contains(&stamina), "{}", stamina);
+}
+
+#[test]
+fn a_dash_without_the_stamina_for_it_is_refused() {
+	let short = physics().dash_stamina_cost - 0.01;
+	let mut world = standing(short);
+	let mut still = standing(short);
+	world.tick(&dashing());
+	still.tick(&moving(0.0, 1.0));
+	assert!(happened(&world, refused));
+	assert!(happened(&world, dashed) == false);
+	let (dashed, still) = (player(&world), player(&still));
+	assert_eq!(dashed.velocity.x, still.velocity.x);
+	assert_eq!(dashed.velocity.y, still.velocity.y);
+	assert_eq!(dashed.stamina, still.stamina);
+}
+
+#[test]
+fn sprinting_is_faster_and_uses_stamina_each_tick() {
+	let mut sprinter = standing(1.0);
+	let mut walker = standing(1.0);
+	tick_for(&mut sprinter, &sprinting(), 10);
+	tick_for(&mut walker, &moving(1.0, 1.0), 10);
+	let (sprinter, walker) = (player(&sprinter), player(&walker));
+	assert!(sprinter.position.x > walker.position.x);
+	let used = 10.0 * physics().sprint_stamina_cost;
+	assert!((sprinter.stamina - (1.0 - used)).abs() < 1e-4);
+	assert_eq!(walker.stamina, 1.0);
+}
+
+#[test]
+fn without_stamina_sprinting_is_only_walking() {
+	let mut sprinter = standing(0.0);
+	let mut walker = standing(0.0);
+	tick_for(&mut sprinter, &sprinting(), 10);
+	tick_for(&mut walker, &moving(1.0, 1.0), 10);
+	assert_eq!(player(&sprinter).position.x, player(&walker).position.x);
+	// Nothing comes back while still trying to sprint.
+	assert_eq!(player(&sprinter).stamina, 0.0);
+	assert!(player(&walker).stamina > 0.0);
+}
+
+#[test]
+fn stamina_comes_back_while_standing_up_to_the_most() {
+	let mut world = standing(0.5);
+	tick_for(&mut world, &moving(0.0, 1.0), 10);
+	let expected = 0.5 + 10.0 * physics().stamina_regen;
+	assert!((player(&world).stamina - expected).abs() < 1e-4);
+	tick_for(&mut world, &moving(0.0, 1.0), 1000);
+	assert_eq!(player(&world).stamina, physics().max_stamina);
+}
+
+#[test]
+fn stamina_does_not_come_back_in_the_air() {
+	let mut world = load(&(frame(0, &[]) + "spawn 0 0.0 0.0\n"));
+	set_stamina(&mut world, 0.5);
+	tick_for(&mut world, &Actions::none(), 60);
+	assert!(player(&world).grounded == false);
+	assert_eq!(player(&world).stamina, 0.5);
+}
@@ -0,0 +1,146 @@
+// Hosts a world over localhost and has a client join it in the same
+// process, to check that the client's copy ends up the same as the host's
+// world. Tiles are changed every tick from the start, the client joins
+// part of the way through, and some of the edits it is sent are thrown
+// away for a while, as a lossy link would, so that it has to ask for those
+// frames again. The state hashes and what the tiles hold have to match
+// once the client is in step, which it has to get to.
+
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::{frame, load};
+use sdl2_1::multiplayer::{Client, Host};
+use sdl2_1::world::{
+	Actions, FrameId, Rng, SyncMessage, Tile, TileData, World, FRAME_WIDTH,
+};
+
+const JOIN_TICK: u64 = 500;
+// Every so many edits to the client is lost until `DROP_UNTIL_TICK`.
+const DROP_EVERY: usize = 7;
+const DROP_UNTIL_TICK: u64 = 600;
+const TICKS: u64 = 800;
+// Ticks the hashes have to match by once the client is in step.
+const SETTLE_TICKS: u64 = 3;
+// How long the client is waited on to catch up with a tick.
+const CATCH_UP_TIMEOUT: Duration = Duration::from_millis(500);
+
+fn edit(world: &mut World, rng: &mut Rng) {
+	let frame_ids = world.frame_ids();
+	let frame_id = *rng.pick(&frame_ids).unwrap();
+	let w = FRAME_WIDTH as isize;
+	let (x, y) = (rng.range_i(0, w), rng.range_i(0, w));
+	let tile = *rng.pick(&[Tile::Empty, Tile::Solid, Tile::Sand]).unwrap();
+	// Tiles an entity is in can't be filled, which is fine to leave.
+	world.set_tile(frame_id, x, y, tile).ok();
+}
+
+// Rewrites one of the signs along the top row, or knocks it down and puts
+// it back up blank.
+fn edit_sign(world: &mut World, rng: &mut Rng) {
+	let x = rng.range_i(0, FRAME_WIDTH as isize);
+	let frame = world.get_frame_mut(FrameId(0)).unwrap();
+	let text = format!("Written at {}.", rng.range_i(0, 1000));
+	match rng.range_i(0, 3) {
+		0 => *frame.tile_mut(x, 0) = Tile::Empty,
+		1 => *frame.tile_mut(x, 0) = Tile::Sign,
+		_ => frame.set_tile_data(x, 0, Some(TileData::Text(text))),
+	}
+}
+
+// What each frame's tiles hold, which the state hash leaves out.
+fn tile_data(world: &World) -> Vec<(FrameId, (usize, usize), TileData)> {
+	let mut data = Vec::new();
+	for frame_id in world.frame_ids() {
+		let frame = world.get_frame(frame_id).unwrap();
+		for (index, tile_data) in frame.tile_data_entries() {
+			data.push((frame_id, index, tile_data.clone()));
+		}
+	}
+	data.sort_by_key(|&(frame_id, (x, y), _)| (frame_id.0, y, x));
+	data
+}
+
+#[test]
+fn a_late_client_catches_up_with_the_host() {
+	catch_up(World::new(), edit);
+}
+
+#[test]
+fn a_late_client_reads_the_signs_the_host_does() {
+	let floor = "################";
+	let signs = "????????????????";
+	let mut source = frame(0, &[(0, signs), (13, floor), (14, floor)]);
+	source += "spawn 0 0.0 0.6\n";
+	source += "sign 0 3 0 0\nsign 0 9 0 1\n";
+	source += "strings\n0 The first sign.\n1 The second sign.\n";
+	catch_up(load(&source), edit_sign);
+}
+
+fn catch_up(mut world: World, edit: fn(&mut World, &mut Rng)) {
+	let mut rng = Rng::new(1);
+	let mut host = Host::bind("127.0.0.1:0", &world)
+		.unwrap_or_else(|error| panic!("could not host: {}", error));
+	let address = host.local_address().unwrap().to_string();
+
+	let mut client: Option<(Client, World)> = None;
+	let (mut edits, mut dropped) = (0, 0);
+	let mut synced_at = None;
+	for _ in 0..TICKS {
+		edit(&mut world, &mut rng);
+		world.tick(&Actions::none());
+		host.update(&world);
+		let tick = world.tick_count;
+		if tick == JOIN_TICK {
+			let joined = Client::connect(&address)
+				.unwrap_or_else(|error| panic!("no join: {}", error));
+			client = Some((joined, World::new()));
+		}
+		let (client, copy) = match client.as_mut() {
+			Some(client) => client,
+			None => continue,
+		};
+
+		// Until the host has had the client's requests, which it only
+		// answers on its next update, there is nothing to wait for.
+		let started = Instant::now();
+		while copy.tick_count != tick && started.elapsed() < CATCH_UP_TIMEOUT {
+			let messages = client.messages().unwrap_or_else(|error| {
+				panic!("client lost the host: {}", error)
+			});
+			for message in messages {
+				if let SyncMessage::Edit(_) = message {
+					edits += 1;
+					if tick < DROP_UNTIL_TICK && edits % DROP_EVERY == 0 {
+						dropped += 1;
+						continue;
+					}
+				}
+				client.apply(copy, message).unwrap_or_else(|error| {
+					panic!("client could not apply: {}", error)
+				});
+			}
+			std::thread::sleep(Duration::from_millis(1));
+		}
+		if client.is_synced() == false || copy.tick_count != tick {
+			synced_at = None;
+			continue;
+		}
+		let synced_at = *synced_at.get_or_insert(tick);
+		let (hash, copy_hash) = (world.state_hash(), copy.state_hash());
+		if tick >= synced_at + SETTLE_TICKS {
+			assert!(
+				hash == copy_hash,
+				"tick {}: host has {:016x}, client {:016x}",
+				tick,
+				hash,
+				copy_hash
+			);
+			assert_eq!(tile_data(&world), tile_data(copy), "tick {}", tick);
+		}
+	}
+
+	assert!(synced_at.is_some(), "the client never got in step");
+	assert!(dropped > 0, "none of {} edits were lost", edits);
+}
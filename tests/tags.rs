@@ -0,0 +1,85 @@
+// Entities put in groups by name, and finding them again by it.
+
+mod common;
+
+use common::{floor_frame, load};
+use sdl2_1::world::{World, WorldEvent};
+
+// Two walkers in the arena group, a coin in the loot group and a walker in
+// none.
+fn grouped() -> World {
+	let mut level = floor_frame(0);
+	level += "spawn 0 -0.5 0.6\n";
+	level += "walker 0 0.0 0.6 tag arena\n";
+	level += "coin 0 0.25 0.3 tag loot\n";
+	level += "walker 0 0.5 0.6 walk_off tag arena\n";
+	level += "walker 0 0.75 0.6\n";
+	load(&level)
+}
+
+fn cleared(world: &World) -> Vec<String> {
+	let events = world.events().iter();
+	events
+		.filter_map(|event| match *event {
+			WorldEvent::GroupCleared { tag } => Some(tag),
+			_ => None,
+		})
+		.map(|tag| world.tag_name(tag).to_string())
+		.collect()
+}
+
+#[test]
+fn entities_are_found_by_their_group_in_id_order() {
+	let world = grouped();
+	let arena = world.entities_with_tag("arena");
+	assert_eq!(arena.len(), 2);
+	assert!(arena[0].0 < arena[1].0);
+	for &id in &arena {
+		let tag = world.get_entity(id).unwrap().tag;
+		assert_eq!(tag, world.find_tag("arena"));
+	}
+	assert_eq!(world.entities_with_tag("loot").len(), 1);
+}
+
+#[test]
+fn a_group_nothing_is_in_has_no_entities_and_no_id() {
+	let world = grouped();
+	assert!(world.entities_with_tag("nobody").is_empty());
+	assert_eq!(world.find_tag("nobody"), None);
+}
+
+#[test]
+fn the_same_name_is_always_the_same_tag() {
+	let mut world = grouped();
+	let arena = world.find_tag("arena").unwrap();
+	assert_eq!(world.tag_id("arena"), arena);
+	let new = world.tag_id("new");
+	assert!(new != arena);
+	assert_eq!(world.tag_name(new), "new");
+	assert_eq!(world.tag_id("new"), new);
+}
+
+#[test]
+fn an_entity_can_be_moved_between_groups() {
+	let mut world = grouped();
+	let coin = world.entities_with_tag("loot")[0];
+	world.set_entity_tag(coin, Some("arena"));
+	assert!(world.entities_with_tag("loot").is_empty());
+	assert!(world.entities_with_tag("arena").contains(&coin));
+	world.set_entity_tag(coin, None);
+	assert_eq!(world.get_entity(coin).unwrap().tag, None);
+	assert_eq!(world.entities_with_tag("arena").len(), 2);
+	assert!(cleared(&world).is_empty());
+}
+
+#[test]
+fn removing_the_last_of_a_group_clears_it() {
+	let mut world = grouped();
+	let arena = world.entities_with_tag("arena");
+	world.remove_entity(arena[0]);
+	assert!(cleared(&world).is_empty());
+	assert_eq!(world.entities_with_tag("arena"), vec![arena[1]]);
+	world.remove_entity(arena[1]);
+	assert_eq!(cleared(&world), vec!["arena"]);
+	assert!(world.entities_with_tag("arena").is_empty());
+}
@@ -0,0 +1,96 @@
+mod common;
+
+use common::{floor_frame, frame, load};
+use sdl2_1::world::{FrameId, LevelIssue, TileLocation, World};
+
+const BUILT_IN_LEVELS: [&str; 5] = [
+	"levels/tutorial.lvl",
+	"levels/groups.lvl",
+	"levels/campaign/1_first_steps.lvl",
+	"levels/campaign/2_walls.lvl",
+	"levels/campaign/3_upside_down.lvl",
+];
+
+// A frame with a floor and a closed box on it, from (5, 5) to (8, 8),
+// with `rest` after it.
+fn walled_box(rest: &str) -> World {
+	let side = ".....####";
+	let inside = ".....#..#";
+	let mut source = frame(
+		0,
+		&[
+			(5, side),
+			(6, inside),
+			(7, inside),
+			(8, side),
+			(13, "################"),
+		],
+	);
+	source += rest;
+	load(&source)
+}
+
+fn tile(frame: usize, x: isize, y: isize) -> TileLocation {
+	(FrameId(frame), x, y)
+}
+
+#[test]
+fn every_built_in_level_can_be_played_through() {
+	for path in BUILT_IN_LEVELS.iter() {
+		let source = std::fs::read_to_string(path).unwrap();
+		let world = World::from_level_str(&source).unwrap();
+		let issues = world.validate_level();
+		assert!(issues.is_empty(), "{}: {:?}", path, issues);
+	}
+}
+
+#[test]
+fn nothing_walled_off_is_in_reach() {
+	let world = walled_box("");
+	let reachable = world.reachable_tiles(tile(0, 1, 1));
+	assert!(reachable.contains(&tile(0, 1, 1)));
+	assert!(reachable.contains(&tile(0, 15, 12)));
+	assert!(reachable.contains(&tile(0, 4, 6)));
+	for &inside in [tile(0, 6, 6), tile(0, 7, 7)].iter() {
+		assert!(reachable.contains(&inside) == false, "{:?}", inside);
+	}
+	for &solid in [tile(0, 5, 5), tile(0, 8, 6), tile(0, 3, 13)].iter() {
+		assert!(reachable.contains(&solid) == false, "{:?}", solid);
+	}
+	// Under the floor is open but shut off, with every edge unlinked.
+	assert!(reachable.contains(&tile(0, 3, 14)) == false);
+	assert!(reachable.iter().all(|&(frame, _, _)| frame == FrameId(0)));
+}
+
+#[test]
+fn a_tile_a_hook_clears_is_a_way_in() {
+	let world = walled_box("hook tile 0 1 12 set_tile 0 5 6 empty\n");
+	let reachable = world.reachable_tiles(tile(0, 1, 1));
+	assert!(reachable.contains(&tile(0, 5, 6)));
+	assert!(reachable.contains(&tile(0, 7, 7)));
+}
+
+#[test]
+fn reach_carries_on_across_links() {
+	let mut source = floor_frame(0) + &floor_frame(1);
+	source += "link 0 right 1 left\n";
+	let world = load(&source);
+	let reachable = world.reachable_tiles(tile(0, 1, 1));
+	assert!(reachable.contains(&tile(1, 0, 12)));
+	assert!(reachable.contains(&tile(1, 15, 0)));
+	assert!(reachable.contains(&tile(1, 3, 13)) == false);
+}
+
+#[test]
+fn a_coin_in_a_closed_box_is_an_issue() {
+	let world = walled_box("spawn 0 -0.9 -0.9\ncoin 0 -0.1 -0.1\n");
+	let unreachable: Vec<TileLocation> = world
+		.validate_level()
+		.into_iter()
+		.filter_map(|issue| match issue {
+			LevelIssue::UnreachableCoin(_, tile) => Some(tile),
+			_ => None,
+		})
+		.collect();
+	assert_eq!(unreachable, vec![tile(0, 7, 7)]);
+}
@@ -0,0 +1,59 @@
+// Walkers going the whole way around a cube, over every edge in turn.
+
+mod common;
+
+use common::floor_frame;
+use sdl2_1::world::{Actions, Direction, EntityId, EntityKind, FrameId, World};
+
+// A cube with a floor along the bottom of the four faces around its
+// equator, 0, 2, 5 and 1 going right, and the player up on top, where
+// every face of the equator is near enough to be ticked.
+fn equator() -> World {
+	let mut level = String::new();
+	for id in 0..6 {
+		level += &match id {
+			3 | 4 => common::frame(id, &[]),
+			_ => floor_frame(id),
+		};
+	}
+	level += "link 0 up 3 down\nlink 0 left 1 right\nlink 0 right 2 left\n";
+	level += "link 0 down 4 up\nlink 5 up 3 up\nlink 5 right 1 left\n";
+	level += "link 5 left 2 right\nlink 5 down 4 down\nlink 1 up 3 left\n";
+	level += "link 1 down 4 left\nlink 2 up 3 right\nlink 2 down 4 right\n";
+	level += "spawn 3 0.0 0.0\n";
+	level += "walker 0 0.5 0.6 follow_surface\n";
+	common::load(&level)
+}
+
+fn walker(world: &World) -> Option<EntityId> {
+	world
+		.entity_ids()
+		.into_iter()
+		.find(|&id| world.get_entity(id).unwrap().kind == EntityKind::Walker)
+}
+
+#[test]
+fn a_surface_following_walker_goes_around_the_equator() {
+	let mut world = equator();
+	let id = walker(&world).unwrap();
+	let mut visited = vec![FrameId(0)];
+	let mut ticks = 0;
+	while visited.len() < 5 {
+		assert!(ticks < 20_000, "only got through {:?}", visited);
+		world.tick(&Actions::none());
+		ticks += 1;
+		assert_eq!(walker(&world), Some(id), "the walker went missing");
+		let frame_id = world.get_entity(id).unwrap().position.frame_id;
+		if visited.last() != Some(&frame_id) {
+			visited.push(frame_id);
+		}
+	}
+	let around: Vec<_> =
+		[0, 2, 5, 1, 0].iter().map(|&id| FrameId(id)).collect();
+	assert_eq!(visited, around);
+
+	// Still on the floor it set off on, not gone over onto another face.
+	let walker = world.get_entity(id).unwrap();
+	assert_eq!(walker.gravity_dir, Direction::Down);
+	assert!(walker.position.y > 0.6 - 0.1, "{:?}", walker.position);
+}
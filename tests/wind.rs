@@ -0,0 +1,79 @@
+// Wind blowing across a frame, and only that frame.
+
+mod common;
+
+use common::{floor_frame, load, player};
+use sdl2_1::geometry::Scalar;
+use sdl2_1::world::{Actions, FrameId, World};
+
+// The player in the middle of frame 0, with frame 1 off to its right and
+// the given wind line.
+fn windy(wind: &str) -> World {
+	let mut level = floor_frame(0) + &floor_frame(1);
+	level += "link 0 right 1 left\nlink 1 right 0 left\n";
+	level += "spawn 0 0.0 0.0\n";
+	level += wind;
+	load(&level)
+}
+
+// Where the player is after standing still for a while, and how fast they
+// are going then.
+fn drift(world: &mut World, ticks: usize) -> (FrameId, Scalar, Scalar) {
+	for _ in 0..ticks {
+		world.tick(&Actions::none());
+	}
+	let player = player(world);
+	(
+		player.position.frame_id,
+		player.position.x,
+		player.velocity.x,
+	)
+}
+
+#[test]
+fn without_wind_the_player_stays_put() {
+	let mut world = windy("");
+	let (frame, x, speed) = drift(&mut world, 60);
+	assert_eq!(frame, FrameId(0));
+	assert_eq!((x, speed), (0.0, 0.0));
+}
+
+#[test]
+fn wind_pushes_the_player_along_it() {
+	let mut world = windy("wind 0 0.001 0\n");
+	let (frame, x, speed) = drift(&mut world, 30);
+	assert_eq!(frame, FrameId(0));
+	assert!(x > 0.0 && speed > 0.0, "{} {}", x, speed);
+
+	let mut world = windy("wind 0 -0.001 0\n");
+	let (_, x, speed) = drift(&mut world, 30);
+	assert!(x < 0.0 && speed < 0.0, "{} {}", x, speed);
+}
+
+#[test]
+fn wind_in_another_frame_does_nothing_here() {
+	let mut world = windy("wind 1 0.001 0\n");
+	let (frame, x, speed) = drift(&mut world, 60);
+	assert_eq!(frame, FrameId(0));
+	assert_eq!((x, speed), (0.0, 0.0));
+}
+
+#[test]
+fn blown_out_of_its_frame_the_wind_stops_pushing() {
+	let mut world = windy("wind 0 0.001 0\n");
+	let mut ticks = 0;
+	while player(&world).position.frame_id == FrameId(0) {
+		assert!(ticks < 2000, "the wind never blew the player out");
+		world.tick(&Actions::none());
+		ticks += 1;
+	}
+	let mut speed = player(&world).velocity.x;
+	assert!(speed > 0.0);
+	for _ in 0..60 {
+		world.tick(&Actions::none());
+		let player = player(&world);
+		assert_eq!(player.position.frame_id, FrameId(1));
+		assert!(player.velocity.x <= speed, "still speeding up");
+		speed = player.velocity.x;
+	}
+}
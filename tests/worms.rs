@@ -0,0 +1,79 @@
+// A worm's segments following its head over the edge of a frame.
+
+mod common;
+
+use common::{floor_frame, load};
+use sdl2_1::world::{
+	Actions, Direction, EntityId, EntityKind, FrameId, World, TILE_SIZE,
+};
+
+// A floor along the bottom of frames 0 and 2, with 2 off to the right of
+// 0, a worm of three segments crawling right along frame 0's and the
+// player out of the way up on frame 3.
+fn crawling() -> World {
+	let mut level = String::new();
+	for id in 0..6 {
+		level += &match id {
+			0 | 2 => floor_frame(id),
+			_ => common::frame(id, &[]),
+		};
+	}
+	level += "link 0 up 3 down\nlink 0 left 1 right\nlink 0 right 2 left\n";
+	level += "link 0 down 4 up\nlink 5 up 3 up\nlink 5 right 1 left\n";
+	level += "link 5 left 2 right\nlink 5 down 4 down\nlink 1 up 3 left\n";
+	level += "link 1 down 4 left\nlink 2 up 3 right\nlink 2 down 4 right\n";
+	level += "spawn 3 0.0 0.0\n";
+	level += "entity worm 0 0.5 0.6 segments=3\n";
+	load(&level)
+}
+
+// The head, then each segment in the order they follow it.
+fn parts(world: &World) -> Vec<EntityId> {
+	let head = world
+		.entity_ids()
+		.into_iter()
+		.find(|&id| world.get_entity(id).unwrap().kind == EntityKind::Worm);
+	let mut parts: Vec<_> = head.into_iter().collect();
+	while let Some(next) = world.worm_follower(*parts.last().unwrap()) {
+		parts.push(next);
+	}
+	parts
+}
+
+#[test]
+fn segments_follow_their_head_across_an_edge() {
+	let mut world = crawling();
+	let parts = parts(&world);
+	assert_eq!(parts.len(), 4);
+	let mut visited = vec![vec![FrameId(0)]; parts.len()];
+	let mut ticks = 0;
+	while visited.iter().any(|frames| frames.len() < 2) {
+		assert!(ticks < 2000, "only got through {:?}", visited);
+		world.tick(&Actions::none());
+		ticks += 1;
+		for (i, &id) in parts.iter().enumerate() {
+			let part = world.get_entity(id).expect("a part went missing");
+			assert_eq!(part.gravity_dir, Direction::Down, "part {}", i);
+			let frame_id = part.position.frame_id;
+			if visited[i].last() != Some(&frame_id) {
+				visited[i].push(frame_id);
+			}
+			// Never left behind on the other side, nor cut across to
+			// somewhere the head hasn't been.
+			if i > 0 {
+				let ahead = world.get_entity(parts[i - 1]).unwrap().position;
+				let apart = world.surface_distance(ahead, part.position);
+				assert!(apart < TILE_SIZE * 2.0, "part {}: {}", i, apart);
+				assert!(visited[i].len() <= visited[i - 1].len());
+			}
+		}
+	}
+	for frames in visited.iter() {
+		assert_eq!(frames, &[FrameId(0), FrameId(2)]);
+	}
+	// Still strung out along the floor.
+	for &id in parts.iter() {
+		let part = world.get_entity(id).unwrap();
+		assert!(part.position.y > 0.6 - 0.1, "{:?}", part.position);
+	}
+}